@@ -0,0 +1,470 @@
+use crate::error::{ApiError, RpcError};
+use crate::host::Host;
+use crate::limit::RateLimiter;
+use crate::rpc::{JsonRpcResponse, JSON_RPC_VERSION};
+use bytes::Bytes;
+use parking_lot::RwLock;
+use reqwest::Client;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+use serde_json::json;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Calls sent per JSON-RPC batch; longer batches go out as several.
+pub const MAX_BATCH_CALLS: usize = 50;
+/// Response header carrying the remaining request credits, when sent.
+const CREDITS_HEADER: &str = "x-ratelimit-remaining";
+/// Tokens are refreshed this long before Deribit expires them.
+const TOKEN_MARGIN: Duration = Duration::from_secs(30);
+
+/// An API key. `Debug` leaves the secret out.
+#[derive(Clone)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone)]
+struct AccessToken {
+    token: String,
+    expires_at: Instant,
+}
+
+/// Hooks for callers that account for their API usage: per-call latency
+/// and outcome, backoffs and the credit balance Deribit reports.
+pub trait CallObserver: Send + Sync {
+    /// One attempt at `method` finished; retries report each attempt.
+    fn on_response(&self, _method: &str, _elapsed: Duration, _result: Result<(), &ApiError>) {}
+    /// `method` failed with `err` and is resent after `wait`.
+    fn on_backoff(&self, _method: &str, _wait: Duration, _err: &ApiError) {}
+    fn on_credits(&self, _remaining: u64) {}
+}
+
+/// Backoffs before a transient failure is surfaced; each doubles the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+/// JSON-RPC over HTTP to one Deribit host. `private/...` methods
+/// authenticate with the client's credentials, caching the access token
+/// until shortly before it expires. Clones share the token, the rate
+/// limiter and the observer.
+#[derive(Clone)]
+pub struct ApiClient {
+    http: Client,
+    base_url: String,
+    credentials: Option<Credentials>,
+    token: Arc<RwLock<Option<AccessToken>>>,
+    limiter: Option<Arc<RateLimiter>>,
+    retry: RetryPolicy,
+    observer: Option<Arc<dyn CallObserver>>,
+}
+
+impl Debug for ApiClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiClient")
+            .field("base_url", &self.base_url)
+            .field("credentials", &self.credentials)
+            .field("limiter", &self.limiter)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ApiClient {
+    pub fn new(host: Host) -> Self {
+        Self {
+            http: Self::http_client(concat!("deribit_api/", env!("CARGO_PKG_VERSION"))),
+            base_url: host.base_url().to_string(),
+            credentials: None,
+            token: Arc::new(RwLock::new(None)),
+            limiter: None,
+            retry: RetryPolicy::default(),
+            observer: None,
+        }
+    }
+
+    fn http_client(user_agent: &str) -> Client {
+        Client::builder()
+            .user_agent(user_agent)
+            .build()
+            .expect("failed to build http client")
+    }
+
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.http = Self::http_client(user_agent);
+        self
+    }
+
+    /// Sends every call to `base_url` instead, e.g. a local mock server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// The same client pointed at `host`. The rate limiter stays shared, so
+    /// falling back to the history host does not double the request rate;
+    /// the access token does not carry over.
+    pub fn with_host(mut self, host: Host) -> Self {
+        self.base_url = host.base_url().to_string();
+        self.token = Arc::new(RwLock::new(None));
+        self
+    }
+
+    pub fn with_credentials(mut self, credentials: Option<Credentials>) -> Self {
+        self.credentials = credentials;
+        self.token = Arc::new(RwLock::new(None));
+        self
+    }
+
+    /// Spaces requests at most `per_second` apart, auth included.
+    pub fn with_rate_limit(mut self, per_second: u32) -> Self {
+        self.limiter = Some(Arc::new(RateLimiter::per_second(per_second)));
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_observer(mut self, observer: impl CallObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        self.credentials.is_some()
+    }
+
+    /// Sends one call and decodes its `result`, backing off and retrying
+    /// per [`ApiError::is_retryable`].
+    pub async fn call<P: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> Result<R, ApiError> {
+        let (result, _) = self
+            .with_retries(method, || self.send_call::<P, R>(method, params))
+            .await?;
+        Ok(result)
+    }
+
+    /// [`Self::call`], returning the whole response body for callers that
+    /// archive what the API sent. Errors are surfaced the same way.
+    pub async fn call_raw<P: Serialize + ?Sized>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> Result<Bytes, ApiError> {
+        let (_, body) = self
+            .with_retries(method, || self.send_call::<P, IgnoredAny>(method, params))
+            .await?;
+        Ok(body)
+    }
+
+    /// Sends `params` as JSON-RPC batches of up to [`MAX_BATCH_CALLS`] calls
+    /// to `method`, one round trip each, and returns one result per entry
+    /// in `params` order, so a call Deribit rejects does not fail the rest.
+    /// A batch is retried like [`Self::call`] when it fails as a whole.
+    pub async fn call_batch<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &[P],
+    ) -> Result<Vec<Result<R, ApiError>>, ApiError> {
+        let mut results = Vec::with_capacity(params.len());
+        for chunk in params.chunks(MAX_BATCH_CALLS) {
+            let responses = self
+                .with_retries(method, || self.send_batch(method, chunk))
+                .await?;
+            results.extend(responses.into_iter().map(|response| {
+                response.and_then(|value| {
+                    serde_json::from_value(value.clone()).map_err(|source| ApiError::Decode {
+                        method: method.to_string(),
+                        source,
+                        body: value.to_string(),
+                    })
+                })
+            }));
+        }
+        Ok(results)
+    }
+
+    /// Obtains (or reuses) an access token without making a private call.
+    pub async fn authenticate(&self) -> Result<(), ApiError> {
+        self.ensure_token("public/auth").await.map(|_| ())
+    }
+
+    async fn with_retries<R, F, Fut>(&self, method: &str, send: F) -> Result<R, ApiError>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<R, ApiError>>,
+    {
+        let private = is_private(method);
+        let mut attempt = 0;
+        loop {
+            let started = Instant::now();
+            let result = send().await;
+            if let Some(observer) = &self.observer {
+                observer.on_response(method, started.elapsed(), result.as_ref().map(|_| ()));
+            }
+            match result {
+                Err(err) if attempt < self.retry.retries && err.is_retryable(private) => {
+                    let wait = self.retry.backoff * 2u32.pow(attempt);
+                    warn!(target: "deribit_api", method, wait_ms = wait.as_millis() as u64, error = %err, "transient failure, backing off");
+                    if let Some(observer) = &self.observer {
+                        observer.on_backoff(method, wait, &err);
+                    }
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// A request object for `method`, carrying the access token when private.
+    fn request_body<P: Serialize + ?Sized>(
+        &self,
+        id: u64,
+        method: &str,
+        params: &P,
+        token: Option<&str>,
+    ) -> Result<serde_json::Value, ApiError> {
+        let mut params = serde_json::to_value(params).map_err(|source| ApiError::Decode {
+            method: method.to_string(),
+            source,
+            body: String::new(),
+        })?;
+        if let Some(token) = token {
+            if params.is_null() {
+                params = json!({});
+            }
+            if let Some(params) = params.as_object_mut() {
+                params.insert("access_token".to_string(), json!(token));
+            }
+        }
+        Ok(json!({
+            "jsonrpc": JSON_RPC_VERSION,
+            "id": id,
+            "method": method,
+            "params": params,
+        }))
+    }
+
+    /// Posts `body` and returns the response body of a successful status,
+    /// noting the remaining credits. Deribit answers failed calls with HTTP
+    /// 400 and a JSON-RPC error, which is surfaced as [`ApiError::Rpc`].
+    async fn post(&self, method: &str, body: &serde_json::Value) -> Result<Bytes, ApiError> {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire().await;
+        }
+        let transport = |source| ApiError::Transport {
+            method: method.to_string(),
+            source: reqwest::Error::without_url(source),
+        };
+        let res = self
+            .http
+            .post(&self.base_url)
+            .json(body)
+            .send()
+            .await
+            .map_err(transport)?;
+        let status = res.status();
+        if let Some(remaining) = res
+            .headers()
+            .get(CREDITS_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+        {
+            if let Some(observer) = &self.observer {
+                observer.on_credits(remaining);
+            }
+        }
+        let bytes = res.bytes().await.map_err(transport)?;
+        if !status.is_success() {
+            if let Ok(JsonRpcResponse::<IgnoredAny> {
+                error: Some(err), ..
+            }) = serde_json::from_slice(&bytes)
+            {
+                return Err(RpcError::new(method, err).into());
+            }
+            return Err(ApiError::Http {
+                method: method.to_string(),
+                status,
+                body: String::from_utf8_lossy(&bytes).into_owned(),
+            });
+        }
+        Ok(bytes)
+    }
+
+    async fn send_call<P: Serialize + ?Sized, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &P,
+    ) -> Result<(R, Bytes), ApiError> {
+        let token = match is_private(method) {
+            true => Some(self.ensure_token(method).await?),
+            false => None,
+        };
+        let body = self.request_body(rand::random::<u64>(), method, params, token.as_deref())?;
+        let bytes = self.post(method, &body).await?;
+        let result = decode_result(method, &bytes)?;
+        Ok((result, bytes))
+    }
+
+    /// One round trip for `params.len()` calls. Responses are matched to
+    /// calls by ID, since the batch may be answered in any order; an error
+    /// object instead of an array fails the whole batch.
+    async fn send_batch<P: Serialize>(
+        &self,
+        method: &str,
+        params: &[P],
+    ) -> Result<Vec<Result<serde_json::Value, ApiError>>, ApiError> {
+        let token = match is_private(method) {
+            true => Some(self.ensure_token(method).await?),
+            false => None,
+        };
+        let first_id = u64::from(rand::random::<u32>());
+        let batch = params
+            .iter()
+            .enumerate()
+            .map(|(offset, params)| {
+                self.request_body(first_id + offset as u64, method, params, token.as_deref())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes = self.post(method, &serde_json::Value::Array(batch)).await?;
+        let responses: Vec<JsonRpcResponse<serde_json::Value>> =
+            match serde_json::from_slice(&bytes) {
+                Ok(responses) => responses,
+                Err(source) => {
+                    return Err(
+                        match serde_json::from_slice::<JsonRpcResponse<IgnoredAny>>(&bytes) {
+                            Ok(JsonRpcResponse {
+                                error: Some(err), ..
+                            }) => RpcError::new(method, err).into(),
+                            _ => ApiError::Decode {
+                                method: method.to_string(),
+                                source,
+                                body: String::from_utf8_lossy(&bytes).into_owned(),
+                            },
+                        },
+                    )
+                }
+            };
+        let mut results: Vec<Option<Result<serde_json::Value, ApiError>>> =
+            (0..params.len()).map(|_| None).collect();
+        for response in responses {
+            let Some(slot) = response
+                .id
+                .checked_sub(first_id)
+                .and_then(|offset| results.get_mut(offset as usize))
+            else {
+                continue;
+            };
+            *slot = Some(match (response.error, response.result) {
+                (Some(err), _) => Err(RpcError::new(method, err).into()),
+                (None, Some(result)) => Ok(result),
+                (None, None) => Err(ApiError::MissingResult {
+                    method: method.to_string(),
+                }),
+            });
+        }
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(ApiError::MissingResult {
+                        method: method.to_string(),
+                    })
+                })
+            })
+            .collect())
+    }
+
+    async fn ensure_token(&self, method: &str) -> Result<String, ApiError> {
+        let Some(credentials) = &self.credentials else {
+            return Err(ApiError::NoCredentials {
+                method: method.to_string(),
+            });
+        };
+        if let Some(token) = &*self.token.read() {
+            if token.expires_at > Instant::now() {
+                return Ok(token.token.clone());
+            }
+        }
+        #[derive(serde::Deserialize)]
+        struct AuthResult {
+            access_token: String,
+            #[serde(default = "default_expires_in")]
+            expires_in: u64,
+        }
+        fn default_expires_in() -> u64 {
+            3000
+        }
+        let body = self.request_body(
+            rand::random::<u64>(),
+            "public/auth",
+            &json!({
+                "grant_type": "client_credentials",
+                "client_id": credentials.client_id,
+                "client_secret": credentials.client_secret,
+            }),
+            None,
+        )?;
+        let bytes = self.post("public/auth", &body).await?;
+        let auth: AuthResult = decode_result("public/auth", &bytes)?;
+        let lifetime = Duration::from_secs(auth.expires_in).saturating_sub(TOKEN_MARGIN);
+        *self.token.write() = Some(AccessToken {
+            token: auth.access_token.clone(),
+            expires_at: Instant::now() + lifetime,
+        });
+        Ok(auth.access_token)
+    }
+}
+
+fn is_private(method: &str) -> bool {
+    method.starts_with("private/")
+}
+
+/// Decodes the `result` of a JSON-RPC response body, surfacing its `error`.
+pub fn decode_result<R: DeserializeOwned>(method: &str, body: &[u8]) -> Result<R, ApiError> {
+    let rpc: JsonRpcResponse<R> =
+        serde_json::from_slice(body).map_err(|source| ApiError::Decode {
+            method: method.to_string(),
+            source,
+            body: String::from_utf8_lossy(body).into_owned(),
+        })?;
+    if let Some(err) = rpc.error {
+        return Err(RpcError::new(method, err).into());
+    }
+    rpc.result.ok_or_else(|| ApiError::MissingResult {
+        method: method.to_string(),
+    })
+}
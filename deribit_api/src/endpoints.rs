@@ -0,0 +1,173 @@
+//! Typed requests for the public methods more than one consumer reads.
+//! Anything else goes through [`crate::ApiClient::call`] with its own DTO.
+
+use crate::client::{decode_result, ApiClient};
+use crate::error::ApiError;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// A JSON-RPC method and the params it takes; the implementing struct is
+/// the params object.
+pub trait Endpoint: Serialize {
+    const METHOD: &'static str;
+    type Response: DeserializeOwned;
+
+    /// Decodes a body fetched with [`ApiClient::request_raw`].
+    fn decode(body: &[u8]) -> Result<Self::Response, ApiError> {
+        decode_result(Self::METHOD, body)
+    }
+}
+
+impl ApiClient {
+    pub async fn request<E: Endpoint>(&self, request: &E) -> Result<E::Response, ApiError> {
+        self.call(E::METHOD, request).await
+    }
+
+    /// The whole response body, for callers that store it as sent.
+    pub async fn request_raw<E: Endpoint>(&self, request: &E) -> Result<Bytes, ApiError> {
+        self.call_raw(E::METHOD, request).await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstrumentKind {
+    Future,
+    Option,
+    Spot,
+    FutureCombo,
+    OptionCombo,
+}
+
+/// `public/get_instruments`: listings of a currency, live or expired.
+/// Production serves expired listings from [`crate::Host::History`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GetInstruments {
+    pub currency: String,
+    pub kind: InstrumentKind,
+    pub expired: bool,
+}
+
+impl Endpoint for GetInstruments {
+    const METHOD: &'static str = "public/get_instruments";
+    type Response = Vec<InstrumentRecord>;
+}
+
+/// `public/get_instrument`: one listing by name; unknown names fail with a
+/// validation error.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GetInstrument {
+    pub instrument_name: String,
+}
+
+impl Endpoint for GetInstrument {
+    const METHOD: &'static str = "public/get_instrument";
+    type Response = InstrumentRecord;
+}
+
+/// Listing metadata as Deribit sends it. Fields some hosts or kinds omit
+/// are optional.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstrumentRecord {
+    pub instrument_name: String,
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub is_combo: Option<bool>,
+    /// Milliseconds since the epoch.
+    #[serde(default)]
+    pub creation_timestamp: Option<i64>,
+    #[serde(default)]
+    pub expiration_timestamp: Option<i64>,
+    #[serde(default)]
+    pub strike: Option<f64>,
+    /// `call` or `put`.
+    #[serde(default)]
+    pub option_type: Option<String>,
+    #[serde(default)]
+    pub settlement_period: Option<String>,
+    #[serde(default)]
+    pub settlement_currency: Option<String>,
+    #[serde(default)]
+    pub base_currency: Option<String>,
+    #[serde(default)]
+    pub quote_currency: Option<String>,
+    #[serde(default)]
+    pub underlying_index: Option<String>,
+    #[serde(default)]
+    pub tick_size: Option<f64>,
+    #[serde(default)]
+    pub min_trade_amount: Option<f64>,
+    #[serde(default)]
+    pub contract_size: Option<f64>,
+}
+
+/// `public/get_last_trades_by_instrument_and_time`: up to `count` trades
+/// from `start_timestamp` (milliseconds), oldest first with
+/// `include_oldest`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GetLastTradesByInstrumentAndTime {
+    pub instrument_name: String,
+    pub start_timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_timestamp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_oldest: Option<bool>,
+}
+
+impl Endpoint for GetLastTradesByInstrumentAndTime {
+    const METHOD: &'static str = "public/get_last_trades_by_instrument_and_time";
+    type Response = TradesPage;
+}
+
+/// `public/get_last_trades_by_instrument`: up to `count` trades numbered
+/// `start_seq..=end_seq` in the instrument's `trade_seq` order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GetLastTradesByInstrument {
+    pub instrument_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_seq: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_seq: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    /// `asc`, `desc` or `default`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sorting: Option<String>,
+}
+
+impl Endpoint for GetLastTradesByInstrument {
+    const METHOD: &'static str = "public/get_last_trades_by_instrument";
+    type Response = TradesPage;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradesPage {
+    pub trades: Vec<TradeRecord>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeRecord {
+    pub trade_id: String,
+    #[serde(default)]
+    pub instrument_name: Option<String>,
+    /// Milliseconds since the epoch.
+    pub timestamp: u64,
+    /// `buy` or `sell`, the taker's side.
+    pub direction: String,
+    pub price: f64,
+    pub amount: f64,
+    #[serde(default)]
+    pub trade_seq: Option<u64>,
+    #[serde(default)]
+    pub index_price: Option<f64>,
+    #[serde(default)]
+    pub mark_price: Option<f64>,
+    #[serde(default)]
+    pub iv: Option<f64>,
+}
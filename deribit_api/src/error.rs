@@ -0,0 +1,136 @@
+use crate::rpc::JsonRpcError;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+/// Broad cause of a Deribit JSON-RPC error, which decides whether a call is
+/// retried and whether it counts against a caller's circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcErrorClass {
+    /// Missing, expired or insufficient credentials.
+    Auth,
+    /// `too_many_requests`: the request credit budget is spent.
+    RateLimit,
+    /// The request itself was rejected: bad params, unknown instrument,
+    /// order constraints. Retrying the same request cannot succeed.
+    Validation,
+    /// The matching engine or gateway is busy, in maintenance or timed out.
+    Engine,
+    /// A code this taxonomy does not know.
+    Other,
+}
+
+impl RpcErrorClass {
+    /// Classifies a Deribit error code, per the API's error code table.
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            10000 | 13004 | 13009 | 13021 | 13668 => RpcErrorClass::Auth,
+            10028 => RpcErrorClass::RateLimit,
+            10040 | 10041 | 10047 | 11051 | 11094 | 11095 | 11096 | 13028 | 13888 | -32000 => {
+                RpcErrorClass::Engine
+            }
+            -32700..=-32600 | 10001..=11999 | 13000..=13999 => RpcErrorClass::Validation,
+            _ => RpcErrorClass::Other,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again after a wait.
+    pub fn is_transient(self) -> bool {
+        matches!(self, RpcErrorClass::RateLimit | RpcErrorClass::Engine)
+    }
+}
+
+impl Display for RpcErrorClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RpcErrorClass::Auth => "auth",
+            RpcErrorClass::RateLimit => "rate-limit",
+            RpcErrorClass::Validation => "validation",
+            RpcErrorClass::Engine => "engine",
+            RpcErrorClass::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An `error` object Deribit answered a call with.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("RPC error {method}: {message} ({code})")]
+pub struct RpcError {
+    pub method: String,
+    pub code: i32,
+    pub message: String,
+    pub class: RpcErrorClass,
+}
+
+impl RpcError {
+    pub fn new(method: &str, err: JsonRpcError) -> Self {
+        Self {
+            method: method.to_string(),
+            code: err.code,
+            class: RpcErrorClass::from_code(err.code),
+            message: err.message,
+        }
+    }
+}
+
+/// Why a call to the API failed.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    /// A non-success status without a JSON-RPC error body.
+    #[error("HTTP {status} for {method}: {body}")]
+    Http {
+        method: String,
+        status: StatusCode,
+        body: String,
+    },
+    #[error("failed to call {method}: {source}")]
+    Transport {
+        method: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("failed to parse response for {method}: {source}: {body}")]
+    Decode {
+        method: String,
+        #[source]
+        source: serde_json::Error,
+        body: String,
+    },
+    #[error("missing result for {method}")]
+    MissingResult { method: String },
+    #[error("API key/secret required for {method}")]
+    NoCredentials { method: String },
+}
+
+impl ApiError {
+    /// The JSON-RPC error Deribit answered with, if that is the failure.
+    pub fn rpc(&self) -> Option<&RpcError> {
+        match self {
+            ApiError::Rpc(rpc) => Some(rpc),
+            _ => None,
+        }
+    }
+
+    /// Deribit's request rate limit, either as the `too_many_requests` RPC
+    /// error or a plain HTTP 429.
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            ApiError::Rpc(rpc) => rpc.class == RpcErrorClass::RateLimit,
+            ApiError::Http { status, .. } => *status == StatusCode::TOO_MANY_REQUESTS,
+            _ => false,
+        }
+    }
+
+    /// Whether the failed call is worth resending after a backoff: rate
+    /// limits always, transient engine errors only for calls that cannot
+    /// trade, since an ambiguous failure there may have filled.
+    pub fn is_retryable(&self, private: bool) -> bool {
+        self.is_rate_limited()
+            || (!private && self.rpc().map(|rpc| rpc.class) == Some(RpcErrorClass::Engine))
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt::{Display, Formatter};
+
+/// A Deribit HTTP API endpoint. Live books trade on production or testnet;
+/// production moves expired listings and their trades to the history host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Host {
+    Production,
+    Testnet,
+    /// `history.deribit.com`: expired instruments, their trades and
+    /// settlements. Public methods only.
+    History,
+}
+
+impl Host {
+    /// JSON-RPC endpoint; `public/...` methods also answer GETs below it.
+    pub fn base_url(self) -> &'static str {
+        match self {
+            Host::Production => "https://www.deribit.com/api/v2",
+            Host::Testnet => "https://test.deribit.com/api/v2",
+            Host::History => "https://history.deribit.com/api/v2",
+        }
+    }
+
+    /// Where expired listings of this host's books are served. Testnet
+    /// keeps its own history.
+    pub fn history(self) -> Host {
+        match self {
+            Host::Production | Host::History => Host::History,
+            Host::Testnet => Host::Testnet,
+        }
+    }
+}
+
+impl Display for Host {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.base_url())
+    }
+}
@@ -0,0 +1,23 @@
+//! Deribit's HTTP API, shared by `deribit_arb`, `optstore` and
+//! `oldest_eth_options`: JSON-RPC transport with auth, retries and rate
+//! limiting ([`ApiClient`]), the production, testnet and history hosts
+//! ([`Host`]), and typed requests for the common public methods
+//! ([`endpoints`]).
+
+pub mod client;
+pub mod endpoints;
+pub mod error;
+pub mod host;
+pub mod limit;
+pub mod rpc;
+
+pub use client::{
+    decode_result, ApiClient, CallObserver, Credentials, RetryPolicy, MAX_BATCH_CALLS,
+};
+pub use endpoints::{
+    Endpoint, GetInstrument, GetInstruments, GetLastTradesByInstrument,
+    GetLastTradesByInstrumentAndTime, InstrumentKind, InstrumentRecord, TradeRecord, TradesPage,
+};
+pub use error::{ApiError, RpcError, RpcErrorClass};
+pub use host::Host;
+pub use limit::RateLimiter;
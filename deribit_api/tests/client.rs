@@ -0,0 +1,300 @@
+use deribit_api::{
+    ApiClient, ApiError, CallObserver, Credentials, Endpoint, GetInstrument, GetInstruments,
+    GetLastTradesByInstrument, GetLastTradesByInstrumentAndTime, Host, InstrumentKind, RateLimiter,
+    RetryPolicy, RpcErrorClass,
+};
+use parking_lot::Mutex;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn rpc(rpc_method: &str, status: u16, body: serde_json::Value) -> Mock {
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({ "method": rpc_method })))
+        .respond_with(ResponseTemplate::new(status).set_body_json(body))
+}
+
+fn client(server: &MockServer) -> ApiClient {
+    ApiClient::new(Host::Testnet)
+        .with_base_url(server.uri())
+        .with_retry(RetryPolicy {
+            retries: 2,
+            backoff: Duration::from_millis(1),
+        })
+}
+
+#[tokio::test]
+async fn typed_endpoints_send_params_and_decode_results() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "public/get_instruments",
+            "params": { "currency": "ETH", "kind": "option", "expired": true },
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": [{
+                "instrument_name": "ETH-29MAR19-100-C",
+                "creation_timestamp": 1_546_000_000_000i64,
+                "expiration_timestamp": 1_553_846_400_000i64,
+                "strike": 100.0,
+                "option_type": "call",
+                "tick_size": 0.0005,
+            }],
+        })))
+        .mount(&server)
+        .await;
+    rpc(
+        "public/get_last_trades_by_instrument_and_time",
+        200,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "result": {
+                "trades": [{
+                    "trade_id": "ETH-1",
+                    "timestamp": 1_546_300_000_000u64,
+                    "direction": "buy",
+                    "price": 0.05,
+                    "amount": 3.0,
+                }],
+                "has_more": true,
+            },
+        }),
+    )
+    .mount(&server)
+    .await;
+    let client = client(&server);
+
+    let instruments = client
+        .request(&GetInstruments {
+            currency: "ETH".to_string(),
+            kind: InstrumentKind::Option,
+            expired: true,
+        })
+        .await
+        .expect("instruments");
+    assert_eq!(instruments.len(), 1);
+    assert_eq!(instruments[0].strike, Some(100.0));
+    assert_eq!(instruments[0].contract_size, None);
+
+    let request = GetLastTradesByInstrumentAndTime {
+        instrument_name: "ETH-29MAR19-100-C".to_string(),
+        start_timestamp: 0,
+        end_timestamp: None,
+        count: Some(100),
+        include_oldest: Some(true),
+    };
+    let body = client.request_raw(&request).await.expect("raw trades");
+    let page = GetLastTradesByInstrumentAndTime::decode(&body).expect("decoded page");
+    assert!(page.has_more);
+    assert_eq!(page.trades[0].trade_id, "ETH-1");
+    assert_eq!(client.request(&request).await.expect("typed trades"), page);
+}
+
+#[tokio::test]
+async fn requests_trades_by_sequence_range() {
+    let server = MockServer::start().await;
+    let trades: Vec<_> = [41u64, 42]
+        .into_iter()
+        .map(|seq| {
+            json!({
+                "trade_id": format!("ETH-{seq}"),
+                "trade_seq": seq,
+                "timestamp": 1_546_300_000_000u64 + seq,
+                "direction": "sell",
+                "price": 0.05,
+                "amount": 1.0,
+            })
+        })
+        .collect();
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "public/get_last_trades_by_instrument",
+            "params": {
+                "instrument_name": "ETH-29MAR19-100-C",
+                "start_seq": 41,
+                "end_seq": 42,
+                "sorting": "asc",
+            },
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "trades": trades,
+                "has_more": false,
+            },
+        })))
+        .mount(&server)
+        .await;
+
+    let page = client(&server)
+        .request(&GetLastTradesByInstrument {
+            instrument_name: "ETH-29MAR19-100-C".to_string(),
+            start_seq: Some(41),
+            end_seq: Some(42),
+            count: Some(1000),
+            sorting: Some("asc".to_string()),
+        })
+        .await
+        .expect("trades by seq");
+    assert_eq!(
+        page.trades
+            .iter()
+            .map(|trade| trade.trade_seq)
+            .collect::<Vec<_>>(),
+        [Some(41), Some(42)]
+    );
+    assert!(!page.has_more);
+}
+
+#[tokio::test]
+async fn surfaces_rpc_errors_sent_with_http_400() {
+    let server = MockServer::start().await;
+    rpc(
+        "public/get_instrument",
+        400,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "error": { "code": 13020, "message": "not_found" },
+        }),
+    )
+    .mount(&server)
+    .await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(
+            json!({ "method": "public/get_index_price" }),
+        ))
+        .respond_with(ResponseTemplate::new(502).set_body_string("bad gateway"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let err = client
+        .request(&GetInstrument {
+            instrument_name: "ETH-NOPE".to_string(),
+        })
+        .await
+        .expect_err("unknown instrument");
+    let rpc = err.rpc().expect("typed rpc error");
+    assert_eq!((rpc.code, rpc.class), (13020, RpcErrorClass::Validation));
+    assert_eq!(
+        err.to_string(),
+        "RPC error public/get_instrument: not_found (13020)"
+    );
+
+    let err = client
+        .call::<_, serde_json::Value>("public/get_index_price", &json!({}))
+        .await
+        .expect_err("http error");
+    assert!(err.to_string().starts_with("HTTP 502"), "{err}");
+    assert!(err.rpc().is_none() && !err.is_rate_limited());
+}
+
+#[derive(Clone, Default)]
+struct Recorder {
+    calls: Arc<Mutex<Vec<(String, bool)>>>,
+    backoffs: Arc<Mutex<Vec<Duration>>>,
+}
+
+impl CallObserver for Recorder {
+    fn on_response(&self, method: &str, _elapsed: Duration, result: Result<(), &ApiError>) {
+        self.calls.lock().push((method.to_string(), result.is_ok()));
+    }
+
+    fn on_backoff(&self, _method: &str, wait: Duration, _err: &ApiError) {
+        self.backoffs.lock().push(wait);
+    }
+}
+
+#[tokio::test]
+async fn authenticates_private_calls_and_retries_rate_limits() {
+    let server = MockServer::start().await;
+    rpc(
+        "public/auth",
+        200,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": { "access_token": "tok", "expires_in": 900 },
+        }),
+    )
+    .expect(1)
+    .mount(&server)
+    .await;
+    rpc(
+        "private/get_positions",
+        429,
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "error": { "code": 10028, "message": "too_many_requests" },
+        }),
+    )
+    .up_to_n_times(1)
+    .with_priority(1)
+    .mount(&server)
+    .await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "private/get_positions",
+            "params": { "access_token": "tok" },
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "result": [],
+        })))
+        .mount(&server)
+        .await;
+    let recorder = Recorder::default();
+    let anonymous = client(&server);
+    let client = anonymous
+        .clone()
+        .with_credentials(Some(Credentials {
+            client_id: "id".to_string(),
+            client_secret: "secret".to_string(),
+        }))
+        .with_observer(recorder.clone());
+
+    let err = anonymous
+        .call::<_, Vec<serde_json::Value>>("private/get_positions", &json!({}))
+        .await
+        .expect_err("no credentials");
+    assert!(matches!(err, ApiError::NoCredentials { .. }));
+
+    for _ in 0..2 {
+        let positions: Vec<serde_json::Value> = client
+            .call("private/get_positions", &json!({ "currency": "ETH" }))
+            .await
+            .expect("positions after backoff");
+        assert!(positions.is_empty());
+    }
+    // The token is cached, the rate limit retried once.
+    assert_eq!(
+        *recorder.calls.lock(),
+        [
+            ("private/get_positions".to_string(), false),
+            ("private/get_positions".to_string(), true),
+            ("private/get_positions".to_string(), true),
+        ]
+    );
+    assert_eq!(*recorder.backoffs.lock(), [Duration::from_millis(1)]);
+    assert!(!format!("{client:?}").contains("secret"));
+}
+
+#[tokio::test]
+async fn rate_limiter_spaces_requests() {
+    let limiter = RateLimiter::per_second(20);
+    let started = tokio::time::Instant::now();
+    for _ in 0..3 {
+        limiter.acquire().await;
+    }
+    // The first slot is immediate, the next two 50ms apart.
+    assert!(started.elapsed() >= Duration::from_millis(100));
+}
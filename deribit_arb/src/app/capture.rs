@@ -0,0 +1,152 @@
+use super::stream::ticker_channels;
+use super::WS_RECONNECT_DELAY_SECS;
+use crate::capture::{self, CaptureHealth, ListingChange};
+use crate::client::{DeribitHttpClient, DeribitWsClient, WsEvent, WsRecorder};
+use crate::config::{AppConfig, CaptureArgs};
+use crate::metrics::metrics;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use std::collections::BTreeSet;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// How often `capture` checks for midnight and expired captures.
+const CAPTURE_HOUSEKEEPING_SECS: u64 = 60;
+
+/// The `capture` subcommand: streams the ticker frames of the configured
+/// listings into daily [`WsRecorder`] files until interrupted. Every
+/// `--relist-secs` the listings are read again and a changed set (a new
+/// expiry, or one that expired) replaces the subscription with a fresh
+/// connection; the old one closes once its receiver is dropped.
+pub(super) async fn run_capture(config: &AppConfig, args: &CaptureArgs) -> Result<()> {
+    let http_client = DeribitHttpClient::new(config.environment, None);
+    let mut listed = capture_listing(&http_client, config).await?;
+    if listed.is_empty() {
+        bail!("no listed instruments match the configured filters");
+    }
+    let recorder = WsRecorder::daily(&args.dir, Utc::now())?;
+    let health = CaptureHealth::new(&listed);
+    if let Some(addr) = args.health_addr {
+        capture::serve(addr, health.clone(), recorder.clone()).await?;
+    }
+    let ws = DeribitWsClient::new(config.environment).with_recorder(recorder.clone());
+    let mut rx = ws.subscribe(&ticker_channels(&listed)).await?;
+    info!(target: "capture", instruments = listed.len(), dir = %args.dir.display(), "capturing ticker stream");
+
+    let mut relist = tokio::time::interval(Duration::from_secs(args.relist_secs));
+    relist.tick().await;
+    let mut housekeeping = tokio::time::interval(Duration::from_secs(CAPTURE_HOUSEKEEPING_SECS));
+    let max_age = args
+        .max_age_days
+        .map(|days| std::time::Duration::from_secs(days * 86_400));
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Some(WsEvent::TickerUpdate { instrument_name, .. })
+                | Some(WsEvent::BookUpdate { instrument_name, .. }) => {
+                    health.tick(&instrument_name, Utc::now());
+                }
+                Some(WsEvent::Disconnected { reason }) => {
+                    warn!(target: "capture", %reason, "capture stream disconnected");
+                }
+                Some(_) => {}
+                None => {
+                    sleep(Duration::from_secs(WS_RECONNECT_DELAY_SECS)).await;
+                    match ws.subscribe(&ticker_channels(&listed)).await {
+                        Ok(next) => {
+                            rx = next;
+                            metrics().record_ws_reconnect();
+                        }
+                        Err(err) => warn!(target: "capture", error = %err, "reconnect failed"),
+                    }
+                }
+            },
+            _ = relist.tick() => {
+                let next = match capture_listing(&http_client, config).await {
+                    Ok(next) => next,
+                    Err(err) => {
+                        warn!(target: "capture", error = %err, "failed to reload listings");
+                        continue;
+                    }
+                };
+                let change = ListingChange::between(&listed, &next);
+                if change.is_empty() || next.is_empty() {
+                    continue;
+                }
+                info!(
+                    target: "capture",
+                    added = ?change.added,
+                    removed = ?change.removed,
+                    "listings changed, restarting subscription"
+                );
+                match ws.subscribe(&ticker_channels(&next)).await {
+                    Ok(next_rx) => {
+                        rx = next_rx;
+                        listed = next;
+                        health.resubscribe(&listed);
+                    }
+                    // Kept on the old set; the next check tries again.
+                    Err(err) => warn!(target: "capture", error = %err, "resubscribe failed"),
+                }
+            }
+            _ = housekeeping.tick() => {
+                recorder.rotate(Utc::now());
+                if let Some(max_age) = max_age {
+                    if let Err(err) = capture::prune(
+                        &args.dir,
+                        max_age,
+                        std::time::SystemTime::now(),
+                        &recorder.path(),
+                    ) {
+                        warn!(target: "capture", error = %err, "failed to remove expired captures");
+                    }
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!(target: "capture", "interrupted, stopping capture");
+                break;
+            }
+        }
+    }
+    recorder.finish()
+}
+
+/// Names of the listings `discover` would stream, without loading their
+/// tickers; the strike filter is skipped while the index is unavailable.
+async fn capture_listing(
+    http_client: &DeribitHttpClient,
+    config: &AppConfig,
+) -> Result<BTreeSet<String>> {
+    let filter = &config.instrument_filter;
+    let mut listed = BTreeSet::new();
+    for currency in &config.currencies {
+        let index = http_client.get_index_price(*currency).await.ok();
+        let now = Utc::now();
+        let mut instruments = Vec::new();
+        for book in currency.instrument_books(&config.settlements) {
+            instruments.extend(http_client.get_instruments(book).await?);
+        }
+        let nearest = filter.nearest_expiries(
+            instruments
+                .iter()
+                .filter(|inst| inst.currency == *currency)
+                .map(|inst| inst.expiry),
+            now,
+        );
+        listed.extend(
+            instruments
+                .into_iter()
+                .filter(|inst| {
+                    inst.currency == *currency
+                        && config.settlements.contains(&inst.settlement_currency)
+                        && filter.allows_expiry(inst.expiry, now)
+                        && nearest
+                            .as_ref()
+                            .is_none_or(|nearest| nearest.contains(&inst.expiry))
+                        && index.is_none_or(|index| filter.allows_strike(inst.strike, index))
+                })
+                .map(|inst| inst.instrument_name),
+        );
+    }
+    Ok(listed)
+}
@@ -0,0 +1,87 @@
+use crate::chain::OptionChain;
+use crate::client::DeribitHttpClient;
+use crate::config::AppConfig;
+use anyhow::Result;
+use chrono::Utc;
+use tracing::{error, info, warn};
+
+/// Loads instruments and an initial ticker for each, returning the names to
+/// stream. Instruments outside the configured settlements are kept in the
+/// chain for metadata but not quoted.
+pub(super) async fn discover(
+    http_client: &DeribitHttpClient,
+    config: &AppConfig,
+    chain: &OptionChain,
+) -> Result<Vec<String>> {
+    let mut subscribed = Vec::new();
+    for currency in &config.currencies {
+        info!(target: "discover", currency = %currency, "loading instruments");
+        let mut instruments = Vec::new();
+        for book in currency.instrument_books(&config.settlements) {
+            instruments.extend(http_client.get_instruments(book).await?);
+        }
+        let now = Utc::now();
+        // One index read keeps the moneyness filter consistent across the
+        // currency; per-ticker index prices are the fallback.
+        let mut reference_index = match http_client.get_index_price(*currency).await {
+            Ok(index) => Some(index),
+            Err(err) => {
+                warn!(target: "discover", %currency, error = %err, "failed to load index price");
+                None
+            }
+        };
+        let nearest = config.instrument_filter.nearest_expiries(
+            instruments
+                .iter()
+                .filter(|inst| inst.currency == *currency)
+                .map(|inst| inst.expiry),
+            now,
+        );
+        let listed: Vec<_> = instruments
+            .into_iter()
+            .filter(|inst| {
+                inst.currency == *currency
+                    && config.instrument_filter.allows_expiry(inst.expiry, now)
+                    && nearest
+                        .as_ref()
+                        .is_none_or(|nearest| nearest.contains(&inst.expiry))
+            })
+            .collect();
+        if reference_index.is_none() {
+            // Without the index, the first quoted listing's stands in.
+            if let Some(first) = listed
+                .iter()
+                .find(|inst| config.settlements.contains(&inst.settlement_currency))
+            {
+                let quote = http_client.get_ticker(&first.instrument_name).await?;
+                reference_index = Some(quote.index_price).filter(|index| !index.is_zero());
+            }
+        }
+        let mut quoted = Vec::new();
+        for instrument in listed {
+            if let Some(index) = reference_index {
+                if !config
+                    .instrument_filter
+                    .allows_strike(instrument.strike, index)
+                {
+                    continue;
+                }
+            }
+            if config.settlements.contains(&instrument.settlement_currency) {
+                quoted.push(instrument.instrument_name.clone());
+            }
+            chain.upsert_instrument(instrument);
+        }
+        // Batched tickers: one round trip per `MAX_BATCH_CALLS` listings.
+        let quotes = http_client.get_tickers(&quoted).await?;
+        for (name, quote) in quoted.into_iter().zip(quotes) {
+            let quote = quote.map_err(|e| {
+                error!(target: "ticker", instrument = %name, error = %e, "failed to load ticker");
+                e
+            })?;
+            chain.update_quote(&name, quote);
+            subscribed.push(name);
+        }
+    }
+    Ok(subscribed)
+}
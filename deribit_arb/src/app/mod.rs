@@ -0,0 +1,480 @@
+//! The scanner's entry point: everything the `deribit_arb` binary runs after
+//! parsing its arguments, shared with `deribit-data scan` and `backtest`.
+
+mod capture;
+mod discover;
+mod refresh;
+mod scan;
+mod status;
+mod stream;
+mod sweep;
+
+use crate::audit::AuditLog;
+use crate::backtest::{self, BacktestWindow, ResearchData, SnapshotRecorder};
+use crate::chain::{OptionChain, QuoteConflator};
+use crate::client::{DeribitCredentials, DeribitHttpClient, DeribitWsClient, WsRecorder};
+use crate::config::{
+    AppConfig, Cli, Command, Environment, ExecutionMode, OutputFormat, SelftestArgs,
+    DEFAULT_ACCOUNT,
+};
+use crate::control::{self, ControlHandle};
+use crate::detect::{DetectorSuite, IncrementalScanner};
+use crate::exec::{AdverseSelectionTracker, ComboCache, MakerQuoter};
+use crate::health::HealthMonitor;
+use crate::ledger::SimulatedLedger;
+use crate::metrics;
+use crate::model::Trade;
+use crate::notify::{Notifier, NotifyHandle};
+use crate::posttrade::PostTradeValidator;
+use crate::registry::{OpportunityDiff, OpportunityRegistry};
+use crate::render;
+use crate::risk::RiskManager;
+use crate::selftest;
+use crate::tui::Dashboard;
+use crate::webhook::WebhookSink;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+/// Loop interval when `--tui` or `--daemon-addr` run without `--loop-interval`.
+const DEFAULT_LIVE_INTERVAL_SECS: u64 = 5;
+const WS_RECONNECT_DELAY_SECS: u64 = 2;
+/// Deribit's minimum heartbeat interval.
+const SAFETY_HEARTBEAT_SECS: u64 = 10;
+
+/// Runs one invocation from parsed, profile-layered arguments (see
+/// [`Cli::parse_layered`]). Installs the global tracing subscriber.
+pub async fn run_cli(mut cli: Cli) -> Result<()> {
+    let command = cli.command.take();
+    let tui = cli.tui;
+    let level = if cli.quiet { "warn" } else { "info" };
+    // Keep stdout clean for machine-readable output.
+    let jsonl = OutputFormat::from_str(&cli.output).ok() == Some(OutputFormat::Jsonl);
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(level.parse()?))
+        // Log lines would tear the dashboard; use --audit-log for a record in TUI mode.
+        .with_writer(move || -> Box<dyn std::io::Write> {
+            if tui {
+                Box::new(std::io::sink())
+            } else if jsonl {
+                Box::new(std::io::stderr())
+            } else {
+                Box::new(std::io::stdout())
+            }
+        })
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    let config = AppConfig::from_cli(cli)?;
+    match command {
+        Some(Command::Backtest(args)) => {
+            let window = BacktestWindow::parse(&args.from, &args.to, args.step_secs)?;
+            let research = if args.research {
+                let client = DeribitHttpClient::new(config.environment, None)
+                    .with_host(config.environment.history_host());
+                Some(ResearchData::load(&client, &config).await?)
+            } else {
+                None
+            };
+            let report = backtest::run_with(&config, &args.data, window, research.as_ref())?;
+            return print_backtest(&config, &report);
+        }
+        Some(Command::Sweep(args)) => return sweep::sweep(&config, &args).await,
+        Some(Command::Selftest(args)) => return run_selftest(&config, &args).await,
+        Some(Command::Capture(args)) => return capture::run_capture(&config, &args).await,
+        None => {}
+    }
+    if let Some(path) = &config.replay {
+        // Scan at the live cadence, in recorded time.
+        let step = config
+            .loop_interval_secs
+            .unwrap_or(DEFAULT_LIVE_INTERVAL_SECS);
+        let report =
+            backtest::replay_capture(&config, path, chrono::Duration::seconds(step as i64))?;
+        return print_backtest(&config, &report);
+    }
+    let result = run(config).await;
+    if tui {
+        // Also covers early error returns while the dashboard owns the terminal.
+        ratatui::restore();
+    }
+    result
+}
+
+fn print_backtest(config: &AppConfig, report: &backtest::BacktestReport) -> Result<()> {
+    match config.output {
+        OutputFormat::Table => render::print_backtest(report),
+        OutputFormat::Jsonl => render::write_backtest_json(report, std::io::stdout().lock()),
+    }
+}
+
+/// One credential set and its client. The default account (`API_KEY`/
+/// `API_SECRET`) comes first and also serves discovery and maker quoting.
+struct TradingAccount {
+    label: String,
+    environment: Environment,
+    read_only: bool,
+    credentials: Option<DeribitCredentials>,
+    client: DeribitHttpClient,
+}
+
+impl TradingAccount {
+    fn new(
+        label: &str,
+        environment: Environment,
+        read_only: bool,
+        api_key: &Option<String>,
+        api_secret: &Option<String>,
+    ) -> Self {
+        let credentials = match (api_key.clone(), api_secret.clone()) {
+            (Some(id), Some(secret)) => Some(DeribitCredentials {
+                client_id: id,
+                client_secret: secret,
+            }),
+            _ => None,
+        };
+        Self {
+            label: label.to_string(),
+            environment,
+            read_only,
+            client: DeribitHttpClient::new(environment, credentials.clone()),
+            credentials,
+        }
+    }
+}
+
+async fn run(config: AppConfig) -> Result<()> {
+    let mut accounts = vec![TradingAccount::new(
+        DEFAULT_ACCOUNT,
+        config.environment,
+        false,
+        &config.api_key,
+        &config.api_secret,
+    )];
+    accounts.extend(config.accounts.iter().map(|account| {
+        TradingAccount::new(
+            &account.label,
+            account.environment,
+            account.read_only,
+            &account.api_key,
+            &account.api_secret,
+        )
+    }));
+
+    if !config.dry_run && config.cancel_on_disconnect {
+        for account in accounts.iter().filter(|account| !account.read_only) {
+            match &account.credentials {
+                Some(credentials) => {
+                    stream::hold_safety_session(
+                        &account.label,
+                        account.environment,
+                        credentials.clone(),
+                    )
+                    .await?
+                }
+                None => {
+                    warn!(target: "ws.session", account = %account.label, "no API credentials, cancel-on-disconnect not enabled")
+                }
+            }
+        }
+    }
+    let http_client = &accounts[0].client;
+    let warm_chain = match (&config.chain_snapshot, config.warm_start) {
+        (Some(path), true) if path.exists() => match OptionChain::load(path) {
+            Ok(chain) => Some(chain),
+            Err(err) => {
+                warn!(target: "discover", error = %err, "failed to load chain snapshot, discovering");
+                None
+            }
+        },
+        _ => None,
+    };
+    let warm = warm_chain.is_some();
+    let chain = warm_chain.unwrap_or_default();
+    if let Some(addr) = config.metrics_addr {
+        metrics::serve(addr).await?;
+    }
+    let dashboard = config.tui.then(|| Dashboard::new(chain.clone()));
+    if let Some(dashboard) = &dashboard {
+        dashboard.spawn()?;
+    }
+
+    status::spawn_stats(chain.clone(), accounts[0].client.telemetry());
+
+    let subscribed = if warm {
+        let names: Vec<String> = chain
+            .snapshot()
+            .instruments
+            .into_iter()
+            .filter(|inst| {
+                config.currencies.contains(&inst.instrument.currency)
+                    && config
+                        .settlements
+                        .contains(&inst.instrument.settlement_currency)
+            })
+            .map(|inst| inst.instrument.instrument_name)
+            .collect();
+        info!(target: "discover", instruments = names.len(), "warm start, skipping discovery");
+        names
+    } else {
+        discover::discover(http_client, &config, &chain).await?
+    };
+
+    let audit = match &config.audit_log {
+        Some(path) => AuditLog::open(path)?,
+        None => AuditLog::disabled(),
+    };
+    let detector = DetectorSuite::new(&config);
+    let risk = match &config.risk_state {
+        Some(path) => {
+            let mut risk = RiskManager::open(path)?;
+            if config.dry_run {
+                // Simulated fills and ledger settlements must not count
+                // against a later live run.
+                info!(target: "risk.state", path = %path.display(), "dry run, risk state is not written back");
+                risk = risk.in_memory();
+            }
+            reconcile_risk(&accounts, &config, &risk).await;
+            risk
+        }
+        None => RiskManager::new(),
+    };
+    let combos = match &config.combo_cache {
+        Some(path) => ComboCache::open(path)?,
+        None => ComboCache::new(),
+    };
+    let control = match config.daemon_addr {
+        Some(addr) => {
+            let handle = ControlHandle::new(chain.clone(), risk.clone());
+            control::serve(addr, handle.clone()).await?;
+            Some(handle)
+        }
+        None => None,
+    };
+    let mut scan = ScanLoop {
+        config: &config,
+        chain: &chain,
+        detector: &detector,
+        risk: &risk,
+        accounts: &accounts,
+        audit: &audit,
+        dashboard: dashboard.as_ref(),
+        webhook: config
+            .webhook_url
+            .clone()
+            .map(|url| WebhookSink::new(url, config.webhook_min_edge_usd))
+            .transpose()?,
+        registry: OpportunityRegistry::new(chrono::Duration::seconds(
+            config.dedup_cooldown_secs as i64,
+        )),
+        diff: config.diff_min_change_usd.map(OpportunityDiff::new),
+        maker: (config.execution_mode == ExecutionMode::Maker).then(|| {
+            MakerQuoter::new(http_client, &config)
+                .with_combos(combos.clone())
+                .with_risk(risk.clone())
+        }),
+        health: HealthMonitor::new(),
+        incremental: config.incremental_ms.map(|_| IncrementalScanner::new()),
+        recorder: config.record_dir.clone().map(|dir| {
+            let recorder = SnapshotRecorder::new(dir, config.record_layout);
+            match config.record_snapshot_interval {
+                Some(interval) => recorder.with_book_stream(interval),
+                None => recorder,
+            }
+        }),
+        vol_refreshed_at: None,
+        equity_refreshed_at: None,
+        carry_refreshed_at: None,
+        fx_refreshed_at: None,
+        adverse: AdverseSelectionTracker::new(chrono::Duration::seconds(
+            config.adverse_window_secs as i64,
+        )),
+        trades: mpsc::unbounded_channel(),
+        control,
+        ledger: config
+            .sim_capital
+            .filter(|_| config.dry_run)
+            .map(SimulatedLedger::new),
+        conflator: config.conflate_quotes.then(QuoteConflator::new),
+        combos,
+        notify: (!config.notifiers.is_empty())
+            .then(|| Notifier::from_config(&config.notifiers))
+            .transpose()?
+            .map(Notifier::spawn),
+        post_trade: config
+            .post_trade_delay_secs
+            .map(|secs| PostTradeValidator::new(chrono::Duration::seconds(secs as i64))),
+    };
+
+    // The dashboard and control API are only useful while live, so both always loop.
+    let interval_secs = config
+        .loop_interval_secs
+        .or((config.tui || config.daemon_addr.is_some()).then_some(DEFAULT_LIVE_INTERVAL_SECS));
+    let Some(interval_secs) = interval_secs else {
+        return scan.run_cycle().await;
+    };
+
+    let ws_recorder = config
+        .record
+        .as_deref()
+        .map(WsRecorder::create)
+        .transpose()?;
+    stream::stream_quotes(
+        &config,
+        &chain,
+        scan.conflator.clone(),
+        ws_recorder.clone(),
+        subscribed,
+    )
+    .await?;
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    let mut incremental = config
+        .incremental_ms
+        .map(|ms| tokio::time::interval(Duration::from_millis(ms)));
+    let dashboard_closed = async {
+        match &dashboard {
+            Some(dashboard) => dashboard.closed().await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(dashboard_closed);
+    let result = loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                if let Err(err) = scan.run_cycle().await {
+                    break Err(err);
+                }
+            }
+            _ = async {
+                match incremental.as_mut() {
+                    Some(ticker) => ticker.tick().await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                if let Err(err) = scan.run_incremental().await {
+                    break Err(err);
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!(target: "scan", "interrupted, stopping scan loop");
+                break Ok(());
+            }
+            _ = &mut dashboard_closed => break Ok(()),
+        }
+    };
+    scan.shutdown().await;
+    if let Some(recorder) = ws_recorder {
+        if let Err(err) = recorder.finish() {
+            warn!(target: "ws.capture", error = %err, "failed to close WS capture");
+        }
+    }
+    result
+}
+
+/// The `selftest` subcommand: runs every step against the default account
+/// and the first configured currency, failing the process if any step did.
+async fn run_selftest(config: &AppConfig, args: &SelftestArgs) -> Result<()> {
+    if args.orders && config.environment != Environment::Testnet {
+        bail!("selftest --orders only runs against testnet");
+    }
+    let account = TradingAccount::new(
+        DEFAULT_ACCOUNT,
+        config.environment,
+        false,
+        &config.api_key,
+        &config.api_secret,
+    );
+    let currency = *config
+        .currencies
+        .first()
+        .context("selftest needs a currency")?;
+    let ws = DeribitWsClient::new(config.environment);
+    let report = selftest::run(&account.client, &ws, currency, args.orders).await;
+    render::print_selftest(&report)?;
+    if !report.passed() {
+        bail!("selftest failed");
+    }
+    Ok(())
+}
+
+/// Drops restored combos whose legs are no longer held on any account.
+/// Without API credentials the saved state is trusted as-is.
+async fn reconcile_risk(accounts: &[TradingAccount], config: &AppConfig, risk: &RiskManager) {
+    let authenticated: Vec<&TradingAccount> = accounts
+        .iter()
+        .filter(|account| account.credentials.is_some())
+        .collect();
+    if authenticated.is_empty() {
+        info!(target: "risk.state", "no API credentials, skipping position reconciliation");
+        return;
+    }
+    // Linear options are booked under their stablecoin, not their underlying.
+    let mut currencies: Vec<&str> = config.currencies.iter().map(|c| c.as_str()).collect();
+    currencies.extend(
+        config
+            .settlements
+            .iter()
+            .filter_map(|settlement| settlement.stablecoin()),
+    );
+    let mut positions = Vec::new();
+    for account in authenticated {
+        for currency in &currencies {
+            match account.client.get_positions(currency).await {
+                Ok(held) => positions.extend(held),
+                Err(err) => {
+                    warn!(target: "risk.state", account = %account.label, %currency, error = %err, "failed to load positions, keeping saved risk state");
+                    return;
+                }
+            }
+        }
+    }
+    let dropped = risk.reconcile(&positions, Utc::now());
+    info!(target: "risk.state", positions = positions.len(), dropped, "risk state reconciled");
+}
+
+/// State of the scan loop: the cycle itself is in [`scan`], the periodic
+/// volatility, carry and equity reads in [`refresh`], and the scan and ledger
+/// summaries in [`status`].
+struct ScanLoop<'a> {
+    config: &'a AppConfig,
+    chain: &'a OptionChain,
+    detector: &'a DetectorSuite<'a>,
+    risk: &'a RiskManager,
+    accounts: &'a [TradingAccount],
+    audit: &'a AuditLog,
+    dashboard: Option<&'a Dashboard>,
+    webhook: Option<WebhookSink>,
+    registry: OpportunityRegistry,
+    /// Scan-to-scan changes, when `--diff-min-change-usd` is set.
+    diff: Option<OpportunityDiff>,
+    maker: Option<MakerQuoter<'a, DeribitHttpClient>>,
+    health: HealthMonitor,
+    incremental: Option<IncrementalScanner>,
+    recorder: Option<SnapshotRecorder>,
+    vol_refreshed_at: Option<std::time::Instant>,
+    equity_refreshed_at: Option<std::time::Instant>,
+    carry_refreshed_at: Option<std::time::Instant>,
+    fx_refreshed_at: Option<std::time::Instant>,
+    adverse: AdverseSelectionTracker,
+    /// Trade prints of recently executed legs, fed by [`stream::watch_trades`].
+    trades: (
+        mpsc::UnboundedSender<Vec<Trade>>,
+        mpsc::UnboundedReceiver<Vec<Trade>>,
+    ),
+    /// Pause flag and threshold overrides set through `--daemon-addr`.
+    control: Option<ControlHandle>,
+    /// Dry-run fills simulated against `--sim-capital`.
+    ledger: Option<SimulatedLedger>,
+    /// Streamed updates held back until the next scan, with `--conflate-quotes`.
+    conflator: Option<QuoteConflator>,
+    /// Combo IDs by leg signature, shared by every account's planner.
+    combos: ComboCache,
+    /// Alerts for the `[notify.*]` routes, delivered in the background.
+    notify: Option<NotifyHandle>,
+    /// Executed and missed combos waiting for `--post-trade-delay-secs`.
+    post_trade: Option<PostTradeValidator>,
+}
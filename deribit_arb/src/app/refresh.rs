@@ -0,0 +1,126 @@
+use super::ScanLoop;
+use crate::model::{Currency, SettlementCurrency, StrategyKind};
+use tracing::{debug, warn};
+
+/// How often `--high-vol-threshold` re-reads DVOL and realized volatility.
+const VOL_REFRESH_SECS: u64 = 60;
+/// How often `--jelly-carry` re-reads futures basis and funding.
+const CARRY_REFRESH_SECS: u64 = 60;
+/// How often `--max-margin-utilization` re-reads account equity.
+const EQUITY_REFRESH_SECS: u64 = 30;
+/// How often EURR scans re-read the `eur_usd` index.
+const FX_REFRESH_SECS: u64 = 60;
+
+impl ScanLoop<'_> {
+    /// Feeds the risk manager each currency's DVOL and hourly realized vol,
+    /// at most every [`VOL_REFRESH_SECS`]. Only BTC and ETH publish DVOL.
+    pub(super) async fn refresh_volatility(&mut self) {
+        let Some(threshold) = self.config.high_vol_threshold else {
+            return;
+        };
+        if self
+            .vol_refreshed_at
+            .is_some_and(|at| at.elapsed().as_secs() < VOL_REFRESH_SECS)
+        {
+            return;
+        }
+        self.vol_refreshed_at = Some(std::time::Instant::now());
+        let client = &self.accounts[0].client;
+        for currency in &self.config.currencies {
+            if *currency != Currency::BTC && *currency != Currency::ETH {
+                continue;
+            }
+            let dvol = client.get_dvol(*currency).await;
+            let realized = client.get_historical_volatility(*currency).await;
+            if let Err(err) = &dvol {
+                warn!(target: "risk.vol_regime", %currency, error = %err, "failed to fetch DVOL");
+            }
+            if let Err(err) = &realized {
+                warn!(target: "risk.vol_regime", %currency, error = %err, "failed to fetch historical volatility");
+            }
+            let realized = realized
+                .ok()
+                .and_then(|points| points.last().map(|(_, vol)| *vol));
+            let Some(vol) = dvol.ok().into_iter().chain(realized).reduce(f64::max) else {
+                continue;
+            };
+            debug!(target: "risk.vol_regime", %currency, vol, high = vol >= threshold, "volatility refreshed");
+            self.risk.set_volatility(*currency, vol);
+        }
+    }
+
+    /// Feeds the detectors each currency's futures basis and perpetual
+    /// funding for jelly roll valuation, at most every [`CARRY_REFRESH_SECS`].
+    pub(super) async fn refresh_carry(&mut self) {
+        if !self.config.jelly_carry
+            || !self.config.strategy_filter.allows(StrategyKind::JellyRoll)
+            || self
+                .carry_refreshed_at
+                .is_some_and(|at| at.elapsed().as_secs() < CARRY_REFRESH_SECS)
+        {
+            return;
+        }
+        self.carry_refreshed_at = Some(std::time::Instant::now());
+        let client = &self.accounts[0].client;
+        for currency in &self.config.currencies {
+            match client.get_carry_curve(*currency).await {
+                Ok(curve) => {
+                    debug!(
+                        target: "detect.carry",
+                        %currency,
+                        futures = curve.futures.len(),
+                        funding = ?curve.funding_rate,
+                        "carry curve refreshed"
+                    );
+                    self.detector.set_carry(*currency, curve);
+                }
+                Err(err) => {
+                    warn!(target: "detect.carry", %currency, error = %err, "failed to fetch carry curve")
+                }
+            }
+        }
+    }
+
+    /// Feeds the detectors the `eur_usd` index EURR books are converted
+    /// through, at most every [`FX_REFRESH_SECS`]. EURR listings are not
+    /// scanned until the first read succeeds.
+    pub(super) async fn refresh_fx(&mut self) {
+        if !self.config.settlements.contains(&SettlementCurrency::Eurr)
+            || self
+                .fx_refreshed_at
+                .is_some_and(|at| at.elapsed().as_secs() < FX_REFRESH_SECS)
+        {
+            return;
+        }
+        self.fx_refreshed_at = Some(std::time::Instant::now());
+        match self.accounts[0].client.get_eur_usd().await {
+            Ok(rate) => {
+                debug!(target: "detect.fx", %rate, "eur_usd index refreshed");
+                self.detector.set_eur_usd(rate);
+            }
+            Err(err) => warn!(target: "detect.fx", error = %err, "failed to fetch eur_usd index"),
+        }
+    }
+
+    /// Feeds the risk manager the default account's equity for the margin
+    /// utilization cap, at most every [`EQUITY_REFRESH_SECS`].
+    pub(super) async fn refresh_equity(&mut self) {
+        if self.config.max_margin_utilization.is_none()
+            || self
+                .equity_refreshed_at
+                .is_some_and(|at| at.elapsed().as_secs() < EQUITY_REFRESH_SECS)
+        {
+            return;
+        }
+        self.equity_refreshed_at = Some(std::time::Instant::now());
+        match self.accounts[0].client.get_total_equity_usd().await {
+            Ok(equity) => {
+                debug!(target: "risk.margin", equity = %equity, "account equity refreshed");
+                self.risk.set_equity(equity);
+            }
+            Err(err) => {
+                warn!(target: "risk.margin", error = %err, "failed to fetch account equity")
+            }
+        }
+    }
+}
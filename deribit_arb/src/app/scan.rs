@@ -0,0 +1,684 @@
+use super::stream::watch_trades;
+use super::{ScanLoop, TradingAccount};
+use crate::audit::AuditEvent;
+use crate::config::{FeeReportFormat, OutputFormat, ReportFormat, DEFAULT_ACCOUNT};
+use crate::control::ControlHandle;
+use crate::exec::{snap_to_ticks, CancelReason, ExecutionPlanner, MakerAction};
+use crate::greeks::combo_greeks;
+use crate::metrics::metrics;
+use crate::model::{ChainSnapshot, InstrumentSnapshot, StrategyKind, StrategyOpportunity};
+use crate::notify::Alert;
+use crate::posttrade::{self, PostTradeOutcome};
+use crate::render;
+use crate::tui::ExecutionEntry;
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashSet;
+use tokio::time::Duration;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+impl<'a> ScanLoop<'a> {
+    /// Full rescan of the chain; also resynchronises incremental detection.
+    pub(super) async fn run_cycle(&mut self) -> Result<()> {
+        if let Some(path) = &self.config.chain_snapshot {
+            if let Err(err) = self.chain.save(path) {
+                warn!(target: "chain", error = %err, "failed to save chain snapshot");
+            }
+        }
+        self.flush_quotes();
+        if self.incremental.is_some() {
+            self.chain.take_dirty();
+        }
+        self.refresh_volatility().await;
+        self.refresh_equity().await;
+        self.refresh_carry().await;
+        self.refresh_fx().await;
+        let snapshot = self.chain.snapshot();
+        if let Some(recorder) = self.recorder.as_mut() {
+            match recorder.record(&snapshot) {
+                Ok(ticks) => debug!(target: "record", ticks, "recorded scan snapshot"),
+                Err(err) => warn!(target: "record", error = %err, "failed to record scan snapshot"),
+            }
+        }
+        // Counts from incremental passes would overlap the full scan's.
+        self.detector.clear_rejections();
+        self.apply_thresholds();
+        let started = std::time::Instant::now();
+        let detected = self.detector.scan(&snapshot.instruments);
+        let summary = self.detector.take_scan_summary(
+            snapshot.instruments.len(),
+            &detected,
+            started.elapsed(),
+        );
+        self.log_summary(&summary)?;
+        self.log_ledger();
+        if let Some(incremental) = self.incremental.as_mut() {
+            incremental.reset(&detected);
+        }
+        self.process(snapshot, detected, true).await
+    }
+
+    /// Applies leg trade prints received since the last cycle and writes the
+    /// adverse-selection report once any fill's window has closed.
+    fn settle_fills(&mut self) {
+        while let Ok(trades) = self.trades.1.try_recv() {
+            self.adverse.on_trades(&trades);
+        }
+        if !self.adverse.settle(Utc::now()) {
+            return;
+        }
+        let report = self.adverse.report();
+        for row in &report {
+            info!(
+                target: "execution.adverse",
+                strategy = %row.strategy,
+                legs = row.stats.legs,
+                confirmed = row.stats.confirmed,
+                traded_through = row.stats.traded_through,
+                rate = row.traded_through_rate,
+                "adverse selection"
+            );
+        }
+        self.audit
+            .record(AuditEvent::AdverseSelection { report: &report });
+    }
+
+    /// Checks executed and missed combos whose post-trade window has closed
+    /// against where their legs actually traded.
+    async fn validate_post_trades(&self) {
+        let Some(validator) = &self.post_trade else {
+            return;
+        };
+        let due = validator.due(Utc::now());
+        if due.is_empty() {
+            return;
+        }
+        for check in &due {
+            match posttrade::validate(&self.accounts[0].client, check).await {
+                Ok(report) => {
+                    info!(
+                        target: "execution.posttrade",
+                        id = %report.opportunity_id,
+                        strategy = %report.strategy,
+                        outcome = %report.outcome,
+                        verdict = ?report.verdict,
+                        detected_edge_usd = %report.detected_edge_usd,
+                        edge_at_prints_usd = %report.edge_at_prints_usd,
+                        "post-trade check"
+                    );
+                    validator.record(&report);
+                    self.audit.record(AuditEvent::PostTrade { report: &report });
+                }
+                Err(err) => {
+                    warn!(target: "execution.posttrade", id = %check.opportunity.id, error = %err, "failed to fetch leg trades")
+                }
+            }
+        }
+        for row in validator.report() {
+            info!(
+                target: "execution.posttrade",
+                outcome = %row.outcome,
+                checks = row.stats.checks,
+                real = row.stats.real,
+                vanished = row.stats.vanished,
+                unverified = row.stats.unverified,
+                detected_edge_usd = %row.stats.detected_edge_usd,
+                edge_at_prints_usd = %row.stats.edge_at_prints_usd,
+                pending = validator.pending(),
+                "post-trade summary"
+            );
+        }
+    }
+
+    /// Hands thresholds changed through the control API to the detectors.
+    fn apply_thresholds(&self) {
+        if let Some(control) = &self.control {
+            self.detector.set_thresholds(control.thresholds());
+        }
+    }
+
+    /// Writes quotes conflated since the previous scan into the chain.
+    fn flush_quotes(&self) {
+        let Some(conflator) = &self.conflator else {
+            return;
+        };
+        let stats = conflator.flush(self.chain);
+        debug!(
+            target: "chain.conflate",
+            quotes = stats.quotes,
+            books = stats.books,
+            coalesced = stats.coalesced,
+            "flushed conflated updates"
+        );
+    }
+
+    /// Re-evaluates only the strike neighbourhoods of quotes updated since the
+    /// previous cycle; a no-op when nothing changed.
+    pub(super) async fn run_incremental(&mut self) -> Result<()> {
+        self.apply_thresholds();
+        self.flush_quotes();
+        let Some(incremental) = self.incremental.as_mut() else {
+            return Ok(());
+        };
+        let dirty = self.chain.take_dirty();
+        if dirty.is_empty() {
+            return Ok(());
+        }
+        let started = std::time::Instant::now();
+        let detected = incremental.update(self.detector, self.chain, &dirty, Utc::now());
+        debug!(
+            target: "scan.incremental",
+            updated = dirty.len(),
+            detected = detected.len(),
+            elapsed_us = started.elapsed().as_micros() as u64,
+            "incremental rescan"
+        );
+        let snapshot = self.chain.snapshot();
+        self.process(snapshot, detected, false).await
+    }
+
+    async fn process(
+        &mut self,
+        snapshot: ChainSnapshot,
+        detected: Vec<StrategyOpportunity>,
+        full_scan: bool,
+    ) -> Result<()> {
+        let (config, risk, audit) = (self.config, self.risk, self.audit);
+        self.settle_fills();
+        self.validate_post_trades().await;
+        metrics().record_staleness(&snapshot);
+        if let Some(transition) =
+            self.health
+                .check(config, &snapshot, metrics().api_call_totals(), Utc::now())
+        {
+            self.notify(vec![Alert::breaker(&transition)]);
+        }
+        let halted = self.health.is_tripped();
+        let paused = self.control.as_ref().is_some_and(ControlHandle::is_paused);
+        if let Some(dashboard) = self.dashboard {
+            dashboard.update_scan(detected.clone(), risk.snapshot());
+        }
+        if let Some(control) = &self.control {
+            control.publish(&detected);
+        }
+        // Resting orders are repriced against every detection, not just fresh ones.
+        let maker_candidates: Vec<StrategyOpportunity> = match self.maker {
+            Some(_) => detected.iter().take(config.execute_top).cloned().collect(),
+            None => Vec::new(),
+        };
+        // With diffs on, alerts go out for new and improved combos only.
+        let alerts = self.diff.as_mut().map(|diff| {
+            let events = diff.observe(&detected);
+            for change in &events {
+                info!(
+                    target: "diff",
+                    kind = ?change.kind,
+                    strategy = %change.strategy,
+                    id = %change.id,
+                    net_edge_usd = %change.net_edge_usd,
+                    "opportunity changed"
+                );
+                audit.record(AuditEvent::Diff { change });
+            }
+            let keys: HashSet<&str> = events
+                .iter()
+                .filter(|change| change.alerts())
+                .map(|change| change.key.as_str())
+                .collect();
+            detected
+                .iter()
+                .filter(|opp| keys.contains(opp.combo_key().as_str()))
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+        let update = self.registry.observe(detected, Utc::now());
+        for gone in &update.disappeared {
+            info!(
+                target: "dedup",
+                strategy = %gone.strategy,
+                persisted_ms = gone.persisted.num_milliseconds(),
+                "opportunity disappeared"
+            );
+            audit.record(AuditEvent::Disappeared {
+                strategy: gone.strategy,
+                key: &gone.key,
+                id: &gone.id,
+                first_seen: gone.first_seen,
+                persisted_ms: gone.persisted.num_milliseconds(),
+            });
+        }
+        if update.suppressed > 0 {
+            info!(target: "dedup", suppressed = update.suppressed, "repeat opportunities within cooldown");
+        }
+        let opportunities = update.fresh;
+        metrics().record_opportunities(&opportunities);
+        for opportunity in &opportunities {
+            audit.record(AuditEvent::Detected { opportunity });
+        }
+        if halted || paused {
+            if let Some(maker) = self.maker.as_mut() {
+                let reason = if halted {
+                    CancelReason::CircuitBreaker
+                } else {
+                    CancelReason::Paused
+                };
+                let actions = maker.cancel_all(reason).await;
+                for action in &actions {
+                    self.record_maker_action(action);
+                }
+            }
+        } else if self.maker.is_some() {
+            self.sync_maker(maker_candidates, &snapshot.instruments)
+                .await;
+        }
+
+        if let Some(webhook) = &self.webhook {
+            webhook
+                .publish(alerts.as_deref().unwrap_or(&opportunities))
+                .await;
+        }
+        if self.notify.is_some() {
+            let fresh = alerts.as_deref().unwrap_or(&opportunities);
+            self.notify(fresh.iter().map(Alert::opportunity).collect());
+        }
+
+        if opportunities.is_empty() {
+            // Incremental passes run many times a second; only full scans report quiet cycles.
+            if full_scan {
+                info!(target: "scan", "no actionable opportunities at this snapshot");
+            }
+            return Ok(());
+        }
+
+        let shown = config.view.apply(&opportunities, render::DEFAULT_TOP_ROWS);
+        match config.output {
+            OutputFormat::Jsonl => render::write_jsonl(
+                &config.view.apply(&opportunities, usize::MAX),
+                std::io::stdout().lock(),
+            )?,
+            OutputFormat::Table if self.dashboard.is_none() => {
+                render::print_table(&shown, shown.len(), config.view.group_by)?
+            }
+            OutputFormat::Table => {}
+        }
+        if let Some(dir) = &config.report_dir {
+            let path = dir.join(format!(
+                "scan-{}.{}",
+                Utc::now().format("%Y%m%dT%H%M%S"),
+                config.report_format.extension()
+            ));
+            let written = match config.report_format {
+                ReportFormat::Html => render::export_html(&shown, shown.len(), &path),
+                ReportFormat::Markdown => render::export_markdown(&shown, shown.len(), &path),
+                ReportFormat::Csv => render::export_csv(&shown, &path),
+            };
+            if let Err(err) = written {
+                warn!(target: "export", path = %path.display(), error = %err, "failed to write scan report");
+            }
+            if let Some(format) = config.fee_report {
+                let path = path.with_file_name(format!(
+                    "{}-fees.{}",
+                    path.file_stem().unwrap_or_default().to_string_lossy(),
+                    format.extension()
+                ));
+                let written = match format {
+                    FeeReportFormat::Csv => render::export_fee_csv(&shown, &path),
+                    #[cfg(feature = "export-polars")]
+                    FeeReportFormat::Parquet => render::export_fee_parquet(&shown, &path),
+                    #[cfg(not(feature = "export-polars"))]
+                    FeeReportFormat::Parquet => {
+                        Err(anyhow::anyhow!("built without parquet support"))
+                    }
+                };
+                if let Err(err) = written {
+                    warn!(target: "export", path = %path.display(), error = %err, "failed to write fee report");
+                }
+            }
+        }
+        if self.maker.is_some() {
+            return Ok(());
+        }
+        if let Some(reason) = self.health.trip_reason() {
+            info!(target: "health", %reason, "circuit breaker tripped, skipping execution");
+            return Ok(());
+        }
+        if paused {
+            info!(target: "control", "execution paused, skipping execution");
+            return Ok(());
+        }
+
+        for opportunity in opportunities.iter().take(config.execute_top) {
+            let strategy = opportunity.strategy;
+            let currency = opportunity.currency;
+            let account = self.account(strategy);
+            let label = account.label.as_str();
+            if account.read_only {
+                info!(target: "execution", account = %label, %strategy, "read-only account, skipping execution");
+                self.record_execution(label, opportunity, "skipped: read-only account".into());
+                self.watch_post_trade(opportunity, PostTradeOutcome::Missed);
+                continue;
+            }
+            let now = Utc::now();
+            let greeks = combo_greeks(opportunity, &snapshot.instruments, now);
+            if let Err(rejection) = risk.evaluate(config, opportunity, greeks, now) {
+                metrics().record_risk_rejection(rejection.label());
+                self.notify(Alert::risk_trip(&rejection).into_iter().collect());
+                self.record_execution(label, opportunity, format!("rejected: {rejection}"));
+                self.watch_post_trade(opportunity, PostTradeOutcome::Missed);
+                audit.record(AuditEvent::Rejected {
+                    account: label,
+                    opportunity_id: &opportunity.id,
+                    strategy,
+                    currency,
+                    net_edge_usd: opportunity.net_edge_usd,
+                    reason: rejection.to_string(),
+                });
+                continue;
+            }
+            audit.record(AuditEvent::Approved {
+                account: label,
+                opportunity_id: &opportunity.id,
+                strategy,
+                currency,
+                net_edge_usd: opportunity.net_edge_usd,
+            });
+            let key = opportunity.combo_key();
+            let planner = ExecutionPlanner::new(&account.client, config)
+                .with_combos(self.combos.clone())
+                .with_risk(risk.clone());
+            // Planner and client logs carry the account through this span.
+            let span = info_span!("account", account = %label);
+            let rechecked = match config.recheck_edge_fraction {
+                Some(fraction) => {
+                    match planner
+                        .recheck(self.detector, opportunity, &snapshot.instruments, fraction)
+                        .instrument(span.clone())
+                        .await
+                    {
+                        Ok(rechecked) => Some(rechecked),
+                        Err(rejection) => {
+                            self.abort_execution(label, opportunity, rejection.label(), &rejection);
+                            continue;
+                        }
+                    }
+                }
+                None => None,
+            };
+            let opportunity = rechecked.as_ref().unwrap_or(opportunity);
+            let opportunity = match snap_to_ticks(opportunity, &snapshot.instruments) {
+                Ok(snapped) => snapped,
+                Err(rejection) => {
+                    self.abort_execution(label, opportunity, rejection.label(), &rejection);
+                    continue;
+                }
+            };
+            let opportunity = &opportunity;
+            let planned = async {
+                let sized = planner.fit_trade_amounts(opportunity, &snapshot.instruments)?;
+                planner.plan(&sized).await
+            };
+            match planned.instrument(span).await {
+                Ok(report) => {
+                    self.watch_post_trade(
+                        opportunity,
+                        if report.submitted {
+                            PostTradeOutcome::Executed
+                        } else {
+                            PostTradeOutcome::Missed
+                        },
+                    );
+                    if report.submitted {
+                        risk.record_fill(&key, report.expected_edge_usd);
+                        let names = self.adverse.record_fill(opportunity, Utc::now());
+                        watch_trades(
+                            config.environment,
+                            names,
+                            Duration::from_secs(config.adverse_window_secs),
+                            self.trades.0.clone(),
+                        );
+                    } else {
+                        risk.release(&key);
+                    }
+                    info!(
+                        target: "execution.preview",
+                        account = %label,
+                        combo = ?report.combo_id,
+                        submitted = report.submitted,
+                        slippage_usd = %report.slippage_usd,
+                        expected_edge_usd = %report.expected_edge_usd,
+                        rfq_best_edge = ?report
+                            .block_rfq
+                            .as_ref()
+                            .and_then(|rfq| rfq.best_quote())
+                            .map(|quote| quote.net_edge_usd),
+                        "generated execution plan"
+                    );
+                    let outcome = format!(
+                        "{} {}",
+                        if report.submitted {
+                            "submitted"
+                        } else {
+                            "planned"
+                        },
+                        report.combo_id.as_deref().unwrap_or("-")
+                    );
+                    self.notify_execution(label, opportunity, &outcome);
+                    self.record_execution(label, opportunity, outcome);
+                    if let Some(ledger) = self.ledger.as_mut() {
+                        let now = Utc::now();
+                        ledger.settle_into(now, risk);
+                        ledger.fill(opportunity, report.expected_edge_usd, now);
+                    }
+                    audit.record(AuditEvent::Planned {
+                        account: label,
+                        opportunity_id: &opportunity.id,
+                        strategy,
+                        currency,
+                        report: &report,
+                    });
+                }
+                Err(err) => {
+                    risk.release(&key);
+                    error!(target: "execution", account = %label, error = %err, "failed to prepare execution plan");
+                    self.notify_execution(label, opportunity, &format!("failed: {err}"));
+                    self.record_execution(label, opportunity, format!("failed: {err}"));
+                    self.watch_post_trade(opportunity, PostTradeOutcome::Missed);
+                    audit.record(AuditEvent::PlanFailed {
+                        account: label,
+                        opportunity_id: &opportunity.id,
+                        strategy,
+                        currency,
+                        error: format!("{err:#}"),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Risk-checks new maker candidates and hands the approved set to the quoter.
+    async fn sync_maker(
+        &mut self,
+        candidates: Vec<StrategyOpportunity>,
+        instruments: &[InstrumentSnapshot],
+    ) {
+        let (config, risk, audit) = (self.config, self.risk, self.audit);
+        let Some(maker) = self.maker.as_ref() else {
+            return;
+        };
+        let mut targets = Vec::with_capacity(candidates.len());
+        let mut approved = Vec::new();
+        let mut rejected = Vec::new();
+        for opportunity in candidates {
+            if maker.is_resting(&opportunity) {
+                targets.push(opportunity);
+                continue;
+            }
+            let now = Utc::now();
+            let greeks = combo_greeks(&opportunity, instruments, now);
+            match risk.evaluate(config, &opportunity, greeks, now) {
+                Ok(()) => {
+                    audit.record(AuditEvent::Approved {
+                        account: DEFAULT_ACCOUNT,
+                        opportunity_id: &opportunity.id,
+                        strategy: opportunity.strategy,
+                        currency: opportunity.currency,
+                        net_edge_usd: opportunity.net_edge_usd,
+                    });
+                    approved.push(opportunity.combo_key());
+                    targets.push(opportunity);
+                }
+                Err(rejection) => {
+                    metrics().record_risk_rejection(rejection.label());
+                    self.notify(Alert::risk_trip(&rejection).into_iter().collect());
+                    audit.record(AuditEvent::Rejected {
+                        account: DEFAULT_ACCOUNT,
+                        opportunity_id: &opportunity.id,
+                        strategy: opportunity.strategy,
+                        currency: opportunity.currency,
+                        net_edge_usd: opportunity.net_edge_usd,
+                        reason: rejection.to_string(),
+                    });
+                    rejected.push((opportunity, rejection));
+                }
+            }
+        }
+        for (opportunity, rejection) in rejected {
+            self.record_execution(
+                DEFAULT_ACCOUNT,
+                &opportunity,
+                format!("rejected: {rejection}"),
+            );
+        }
+
+        let Some(maker) = self.maker.as_mut() else {
+            return;
+        };
+        let actions = maker.sync(&targets, instruments, Utc::now()).await;
+        // Approved slots whose order never made it onto the book are returned.
+        for key in approved {
+            if !maker.is_resting_key(&key) {
+                risk.release(&key);
+            }
+        }
+        for action in &actions {
+            self.record_maker_action(action);
+        }
+    }
+
+    pub(super) async fn shutdown(&mut self) {
+        let Some(maker) = self.maker.as_mut() else {
+            return;
+        };
+        let actions = maker.cancel_all(CancelReason::Shutdown).await;
+        for action in &actions {
+            self.record_maker_action(action);
+        }
+    }
+
+    fn record_maker_action(&self, action: &MakerAction) {
+        let outcome = match action {
+            MakerAction::Placed {
+                order_id, price, ..
+            } => format!("resting {order_id} @ {price}"),
+            MakerAction::Repriced { order_id, to, .. } => format!("repriced {order_id} @ {to}"),
+            MakerAction::Cancelled {
+                order_id,
+                key,
+                reason,
+                ..
+            } => {
+                self.risk.release(key);
+                format!("cancelled {order_id} ({reason})")
+            }
+        };
+        if let MakerAction::Placed { .. } = action {
+            let (strategy, currency) = action.subject();
+            self.notify(vec![Alert::Execution {
+                account: DEFAULT_ACCOUNT.to_string(),
+                strategy,
+                currency,
+                net_edge_usd: None,
+                outcome: outcome.clone(),
+            }]);
+        }
+        if let Some(dashboard) = self.dashboard {
+            let (strategy, currency) = action.subject();
+            dashboard.record_execution(ExecutionEntry {
+                at: Utc::now(),
+                account: DEFAULT_ACCOUNT.to_string(),
+                strategy,
+                currency,
+                outcome,
+            });
+        }
+        self.audit.record(AuditEvent::Maker { action });
+    }
+
+    /// The account `strategy` is routed to, falling back to the default one.
+    fn account(&self, strategy: StrategyKind) -> &'a TradingAccount {
+        let label = self.config.account_label(strategy);
+        self.accounts
+            .iter()
+            .find(|account| account.label == label)
+            .unwrap_or(&self.accounts[0])
+    }
+
+    /// Drops an approved combo between approval and planning, e.g. when a
+    /// recheck or tick rounding leaves too little edge.
+    fn abort_execution(
+        &self,
+        account: &str,
+        opportunity: &StrategyOpportunity,
+        label: &str,
+        rejection: &dyn std::fmt::Display,
+    ) {
+        self.risk.release(&opportunity.combo_key());
+        metrics().record_risk_rejection(label);
+        self.record_execution(account, opportunity, format!("aborted: {rejection}"));
+        self.watch_post_trade(opportunity, PostTradeOutcome::Missed);
+        self.audit.record(AuditEvent::Rejected {
+            account,
+            opportunity_id: &opportunity.id,
+            strategy: opportunity.strategy,
+            currency: opportunity.currency,
+            net_edge_usd: opportunity.net_edge_usd,
+            reason: rejection.to_string(),
+        });
+    }
+
+    fn watch_post_trade(&self, opportunity: &StrategyOpportunity, outcome: PostTradeOutcome) {
+        if let Some(validator) = &self.post_trade {
+            validator.watch(opportunity, outcome, Utc::now());
+        }
+    }
+
+    fn notify(&self, alerts: Vec<Alert>) {
+        if let Some(notify) = &self.notify {
+            notify.send(alerts);
+        }
+    }
+
+    fn notify_execution(&self, account: &str, opportunity: &StrategyOpportunity, outcome: &str) {
+        self.notify(vec![Alert::Execution {
+            account: account.to_string(),
+            strategy: opportunity.strategy,
+            currency: opportunity.currency,
+            net_edge_usd: Some(opportunity.net_edge_usd),
+            outcome: outcome.to_string(),
+        }]);
+    }
+
+    fn record_execution(&self, account: &str, opportunity: &StrategyOpportunity, outcome: String) {
+        if let Some(dashboard) = self.dashboard {
+            dashboard.record_execution(ExecutionEntry {
+                at: Utc::now(),
+                account: account.to_string(),
+                strategy: opportunity.strategy,
+                currency: opportunity.currency,
+                outcome,
+            });
+        }
+    }
+}
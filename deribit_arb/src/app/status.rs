@@ -0,0 +1,143 @@
+use super::ScanLoop;
+use crate::chain::OptionChain;
+use crate::client::ClientTelemetry;
+use crate::config::OutputFormat;
+use crate::detect::ScanSummary;
+use crate::metrics::metrics;
+use crate::render;
+use anyhow::Result;
+use chrono::Utc;
+use tokio::time::Duration;
+use tracing::{debug, info};
+
+/// Logs chain and client statistics every five seconds for the life of the
+/// process, evicting expired listings from the chain as it goes.
+pub(super) fn spawn_stats(chain_for_status: OptionChain, telemetry: ClientTelemetry) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            ticker.tick().await;
+            let evicted = chain_for_status.evict_expired(Utc::now());
+            if evicted > 0 {
+                info!(target: "chain", evicted, "evicted expired instruments");
+            }
+            let stats = chain_for_status.stats();
+            metrics().record_chain(&stats);
+            info!(
+                target: "scan.stats",
+                tot = stats.instrument_count,
+                quotes = stats.instruments_with_quotes,
+                fresh = stats.instruments_fresh_10s,
+                bid = stats.bid_levels,
+                ask = stats.ask_levels
+            );
+            let client = telemetry.stats();
+            let (calls, errors) = client.totals();
+            let (slowest, p99_ms) = client
+                .slowest()
+                .map_or(("-", 0.0), |(method, stats)| (method, stats.p99_ms));
+            info!(
+                target: "client.stats",
+                calls,
+                errors,
+                slowest,
+                p99_ms,
+                rate_limit_waits = client.rate_limit_waits,
+                rate_limit_wait_ms = client.rate_limit_wait_ms,
+                credits = ?client.credits_remaining
+            );
+            for (method, stats) in &client.methods {
+                debug!(
+                    target: "client.stats",
+                    method = %method,
+                    calls = stats.calls,
+                    errors = stats.errors,
+                    p50_ms = stats.p50_ms,
+                    p90_ms = stats.p90_ms,
+                    p99_ms = stats.p99_ms
+                );
+            }
+        }
+    });
+}
+
+impl ScanLoop<'_> {
+    /// Logs what the scan that just ran did (per strategy, and per reason
+    /// with `--diagnose-rejections`) and, in JSONL mode, writes it to stdout
+    /// as a `scan_summary` line.
+    pub(super) fn log_summary(&self, summary: &ScanSummary) -> Result<()> {
+        let rejected = &summary.rejections;
+        info!(
+            target: "scan.summary",
+            instruments = summary.instruments,
+            candidates = summary.candidates(),
+            rejected = rejected.total(),
+            no_depth = rejected.no_depth,
+            negative_edge = rejected.negative_edge,
+            below_min_edge = rejected.below_min_edge,
+            ratio_too_low = rejected.ratio_too_low,
+            carry_explained = rejected.carry_explained,
+            stale_quotes = rejected.stale_quotes,
+            index_divergence = rejected.index_divergence,
+            below_min_return = rejected.below_min_return,
+            opportunities = summary.opportunities,
+            best_edge_usd = ?summary.best_edge_usd,
+            duration_ms = summary.duration_ms,
+            "scan summary"
+        );
+        for stats in &summary.strategies {
+            info!(
+                target: "scan.summary",
+                strategy = %stats.strategy,
+                candidates = stats.candidates,
+                opportunities = stats.opportunities,
+                rejected = stats.rejections.total(),
+                "strategy summary"
+            );
+            if self.config.diagnose_rejections && stats.rejections.total() > 0 {
+                let counts = &stats.rejections;
+                info!(
+                    target: "scan.rejections",
+                    strategy = %stats.strategy,
+                    total = counts.total(),
+                    no_depth = counts.no_depth,
+                    negative_edge = counts.negative_edge,
+                    below_min_edge = counts.below_min_edge,
+                    ratio_too_low = counts.ratio_too_low,
+                    carry_explained = counts.carry_explained,
+                    stale_quotes = counts.stale_quotes,
+                    index_divergence = counts.index_divergence,
+                    below_min_return = counts.below_min_return,
+                    "rejected candidates"
+                );
+            }
+        }
+        if self.config.output == OutputFormat::Jsonl {
+            render::write_scan_summary(summary, std::io::stdout().lock())?;
+        }
+        Ok(())
+    }
+
+    /// Settles expired simulated combos against the daily loss limit and
+    /// logs the `--sim-capital` ledger.
+    pub(super) fn log_ledger(&mut self) {
+        let Some(ledger) = self.ledger.as_mut() else {
+            return;
+        };
+        ledger.settle_into(Utc::now(), self.risk);
+        let summary = ledger.summary();
+        info!(
+            target: "ledger",
+            cash_usd = %summary.cash_usd,
+            locked_usd = %summary.locked_usd,
+            open = summary.open_combos,
+            realized_pnl_usd = %summary.realized_pnl_usd,
+            unrealized_pnl_usd = %summary.unrealized_pnl_usd,
+            turnover_usd = %summary.turnover_usd,
+            utilization = summary.utilization,
+            peak_utilization = summary.peak_utilization,
+            skipped_for_capital = summary.skipped_for_capital,
+            "simulated ledger"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use super::{SAFETY_HEARTBEAT_SECS, WS_RECONNECT_DELAY_SECS};
+use crate::chain::{OptionChain, QuoteConflator};
+use crate::client::{DeribitCredentials, DeribitWsClient, WsEvent, WsRecorder};
+use crate::config::{AppConfig, Environment};
+use crate::metrics::metrics;
+use crate::model::Trade;
+use anyhow::{Context, Result};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// Opens the cancel-on-disconnect session, failing startup if it cannot be
+/// established, then keeps it open for the life of the process.
+pub(super) async fn hold_safety_session(
+    label: &str,
+    environment: Environment,
+    credentials: DeribitCredentials,
+) -> Result<()> {
+    let ws = DeribitWsClient::new(environment);
+    let mut rx = ws
+        .open_safety_session(&credentials, SAFETY_HEARTBEAT_SECS)
+        .await
+        .with_context(|| {
+            format!("enabling cancel-on-disconnect for account {label} (set CANCEL_ON_DISCONNECT=false to run without it)")
+        })?;
+    info!(target: "ws.session", account = %label, "cancel-on-disconnect enabled");
+    let label = label.to_string();
+    tokio::spawn(async move {
+        loop {
+            while let Some(event) = rx.recv().await {
+                if let WsEvent::Disconnected { reason } = event {
+                    warn!(target: "ws.session", account = %label, %reason, "safety session lost, exchange cancelled open orders");
+                }
+            }
+            rx = loop {
+                sleep(Duration::from_secs(WS_RECONNECT_DELAY_SECS)).await;
+                match ws
+                    .open_safety_session(&credentials, SAFETY_HEARTBEAT_SECS)
+                    .await
+                {
+                    Ok(rx) => break rx,
+                    Err(err) => {
+                        warn!(target: "ws.session", account = %label, error = %err, "safety session reconnect failed")
+                    }
+                }
+            };
+            metrics().record_ws_reconnect();
+            info!(target: "ws.session", account = %label, "cancel-on-disconnect re-enabled");
+        }
+    });
+    Ok(())
+}
+
+/// Keeps chain quotes current from the public ticker feed while looping.
+/// With a `conflator`, updates are buffered there until the next scan
+/// flushes them instead of being written into the chain one by one.
+pub(super) async fn stream_quotes(
+    config: &AppConfig,
+    chain: &OptionChain,
+    conflator: Option<QuoteConflator>,
+    recorder: Option<WsRecorder>,
+    names: Vec<String>,
+) -> Result<()> {
+    let channels = ticker_channels(&names);
+    let mut ws = DeribitWsClient::new(config.environment);
+    if let Some(recorder) = recorder {
+        ws = ws.with_recorder(recorder);
+    }
+    let mut rx = ws.subscribe(&channels).await?;
+    let chain = chain.clone();
+    tokio::spawn(async move {
+        loop {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    WsEvent::TickerUpdate {
+                        instrument_name,
+                        quote,
+                    } => match &conflator {
+                        Some(conflator) => conflator.push_quote(instrument_name, quote),
+                        None => chain.update_quote(&instrument_name, quote),
+                    },
+                    WsEvent::BookUpdate {
+                        instrument_name,
+                        book,
+                    } => match &conflator {
+                        Some(conflator) => conflator.push_order_book(instrument_name, book),
+                        None => chain.update_order_book(&instrument_name, book),
+                    },
+                    WsEvent::Disconnected { reason } => {
+                        warn!(target: "ws", %reason, "ticker stream disconnected");
+                    }
+                    WsEvent::TradeUpdate { .. }
+                    | WsEvent::OrderUpdate { .. }
+                    | WsEvent::Heartbeat => {}
+                }
+            }
+            warn!(target: "ws", "ticker stream closed, reconnecting");
+            rx = loop {
+                sleep(Duration::from_secs(WS_RECONNECT_DELAY_SECS)).await;
+                match ws.subscribe(&channels).await {
+                    Ok(rx) => break rx,
+                    Err(err) => warn!(target: "ws", error = %err, "reconnect failed"),
+                }
+            };
+            metrics().record_ws_reconnect();
+        }
+    });
+    Ok(())
+}
+
+/// Follows the public trades of `names` for `window`, forwarding every print
+/// to `tx`; the connection closes when the window ends.
+pub(super) fn watch_trades(
+    environment: Environment,
+    names: Vec<String>,
+    window: Duration,
+    tx: mpsc::UnboundedSender<Vec<Trade>>,
+) {
+    let channels: Vec<String> = names
+        .iter()
+        .map(|name| format!("trades.{name}.100ms"))
+        .collect();
+    tokio::spawn(async move {
+        let ws = DeribitWsClient::new(environment);
+        let mut rx = match ws.subscribe(&channels).await {
+            Ok(rx) => rx,
+            Err(err) => {
+                warn!(target: "execution.adverse", error = %err, "failed to subscribe to leg trades");
+                return;
+            }
+        };
+        let deadline = tokio::time::Instant::now() + window;
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, rx.recv()).await {
+            let trades = match event {
+                WsEvent::TradeUpdate { trades } => trades,
+                WsEvent::Disconnected { reason } => {
+                    warn!(target: "execution.adverse", %reason, "leg trade stream disconnected");
+                    return;
+                }
+                _ => continue,
+            };
+            if tx.send(trades).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+pub(super) fn ticker_channels<'a>(names: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    names
+        .into_iter()
+        .map(|name| format!("ticker.{name}.100ms"))
+        .collect()
+}
@@ -0,0 +1,143 @@
+use crate::chain::OptionChain;
+use crate::client::DeribitHttpClient;
+use crate::config::{AppConfig, OutputFormat, SweepArgs};
+use crate::detect::DetectorSuite;
+use crate::model::SettlementCurrency;
+use crate::render;
+use crate::sweep;
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::BTreeSet;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// The `sweep` subcommand: one `get_book_summary_by_currency` call per
+/// settlement book replaces per-instrument subscriptions, and tickers are
+/// read only for the expiry and strike slices around an anomalous book.
+pub(super) async fn sweep(config: &AppConfig, args: &SweepArgs) -> Result<()> {
+    let http_client = DeribitHttpClient::new(config.environment, None);
+    let chain = OptionChain::new();
+    let mut books: Vec<&str> = Vec::new();
+    for currency in &config.currencies {
+        let index = http_client.get_index_price(*currency).await.ok();
+        let now = Utc::now();
+        let mut instruments = Vec::new();
+        for book in currency.instrument_books(&config.settlements) {
+            if !books.contains(&book) {
+                books.push(book);
+            }
+            instruments.extend(http_client.get_instruments(book).await?);
+        }
+        let filter = &config.instrument_filter;
+        let nearest = filter.nearest_expiries(
+            instruments
+                .iter()
+                .filter(|inst| inst.currency == *currency)
+                .map(|inst| inst.expiry),
+            now,
+        );
+        for instrument in instruments {
+            if instrument.currency == *currency
+                && config.settlements.contains(&instrument.settlement_currency)
+                && filter.allows_expiry(instrument.expiry, now)
+                && nearest
+                    .as_ref()
+                    .is_none_or(|nearest| nearest.contains(&instrument.expiry))
+                && index.is_none_or(|index| filter.allows_strike(instrument.strike, index))
+            {
+                chain.upsert_instrument(instrument);
+            }
+        }
+    }
+    info!(target: "sweep", instruments = chain.snapshot().instruments.len(), "loaded instruments");
+
+    let detector = DetectorSuite::new(config);
+    loop {
+        if config.settlements.contains(&SettlementCurrency::Eurr) {
+            match http_client.get_eur_usd().await {
+                Ok(rate) => detector.set_eur_usd(rate),
+                Err(err) => warn!(target: "sweep", error = %err, "failed to fetch eur_usd index"),
+            }
+        }
+        let mut anomalies = Vec::new();
+        for book in &books {
+            match http_client.get_book_summaries(book).await {
+                Ok(summaries) => anomalies.extend(
+                    sweep::find_anomalies(&summaries)
+                        .into_iter()
+                        .filter(|anomaly| chain.get(&anomaly.instrument_name).is_some()),
+                ),
+                Err(err) => {
+                    warn!(target: "sweep", book, error = %err, "failed to load book summaries")
+                }
+            }
+        }
+
+        let mut stale = BTreeSet::new();
+        for anomaly in &anomalies {
+            info!(
+                target: "sweep",
+                instrument = %anomaly.instrument_name,
+                kind = %anomaly.kind,
+                bid = ?anomaly.bid_price,
+                ask = ?anomaly.ask_price,
+                mark = ?anomaly.mark_price,
+                "book anomaly"
+            );
+            let Some(snapshot) = chain.get(&anomaly.instrument_name) else {
+                continue;
+            };
+            let instrument = &snapshot.instrument;
+            let neighbours = chain
+                .expiry_slice(instrument.currency, instrument.expiry)
+                .into_iter()
+                .chain(chain.strike_slice(instrument.currency, instrument.strike));
+            stale.extend(neighbours.map(|inst| inst.instrument.instrument_name));
+        }
+
+        let stale: Vec<String> = stale.into_iter().collect();
+        let mut refreshed = Vec::with_capacity(stale.len());
+        let quotes = match http_client.get_tickers(&stale).await {
+            Ok(quotes) => quotes,
+            Err(err) => {
+                warn!(target: "ticker", error = %err, "failed to load tickers");
+                Vec::new()
+            }
+        };
+        for (name, quote) in stale.iter().zip(quotes) {
+            match quote {
+                Ok(quote) => {
+                    chain.update_quote(name, quote);
+                    refreshed.extend(chain.get(name));
+                }
+                Err(err) => {
+                    warn!(target: "ticker", instrument = %name, error = %err, "failed to load ticker")
+                }
+            }
+        }
+
+        let opportunities = detector.scan(&refreshed);
+        info!(
+            target: "sweep",
+            anomalies = anomalies.len(),
+            refreshed = refreshed.len(),
+            opportunities = opportunities.len(),
+            "sweep pass"
+        );
+        match config.output {
+            OutputFormat::Jsonl => render::write_jsonl(
+                &config.view.apply(&opportunities, usize::MAX),
+                std::io::stdout().lock(),
+            )?,
+            OutputFormat::Table if !opportunities.is_empty() => {
+                let shown = config.view.apply(&opportunities, render::DEFAULT_TOP_ROWS);
+                render::print_table(&shown, shown.len(), config.view.group_by)?
+            }
+            OutputFormat::Table => {}
+        }
+        if args.once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(args.interval_secs.max(1))).await;
+    }
+}
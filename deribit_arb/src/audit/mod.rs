@@ -0,0 +1,124 @@
+use crate::exec::{AdverseSelectionRow, ExecutionReport, MakerAction};
+use crate::model::{Currency, StrategyKind, StrategyOpportunity};
+use crate::posttrade::PostTradeReport;
+use crate::registry::DiffEvent;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::warn;
+
+/// One audited step in the detect → approve → plan pipeline. Later steps
+/// name their opportunity by [`StrategyOpportunity::id`].
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent<'a> {
+    Detected {
+        opportunity: &'a StrategyOpportunity,
+    },
+    Approved {
+        account: &'a str,
+        opportunity_id: &'a str,
+        strategy: StrategyKind,
+        currency: Currency,
+        net_edge_usd: Decimal,
+    },
+    Rejected {
+        account: &'a str,
+        opportunity_id: &'a str,
+        strategy: StrategyKind,
+        currency: Currency,
+        net_edge_usd: Decimal,
+        reason: String,
+    },
+    Planned {
+        account: &'a str,
+        opportunity_id: &'a str,
+        strategy: StrategyKind,
+        currency: Currency,
+        report: &'a ExecutionReport,
+    },
+    PlanFailed {
+        account: &'a str,
+        opportunity_id: &'a str,
+        strategy: StrategyKind,
+        currency: Currency,
+        error: String,
+    },
+    Disappeared {
+        strategy: StrategyKind,
+        key: &'a str,
+        id: &'a str,
+        first_seen: DateTime<Utc>,
+        persisted_ms: i64,
+    },
+    /// A combo appeared, moved by at least `--diff-min-change-usd`, or
+    /// vanished since the previous scan.
+    Diff { change: &'a DiffEvent },
+    /// A maker-mode order was placed, repriced or cancelled.
+    Maker { action: &'a MakerAction },
+    /// Cumulative per-strategy fill quality, written whenever the trade
+    /// window of an executed leg closes.
+    AdverseSelection { report: &'a [AdverseSelectionRow] },
+    /// Where an executed or missed combo's legs traded in the
+    /// `--post-trade-delay-secs` after the decision.
+    PostTrade { report: &'a PostTradeReport },
+}
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    ts: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a AuditEvent<'a>,
+}
+
+/// Append-only JSONL audit trail. Each record is flushed as it is written so
+/// the file stays readable if the process dies mid-cycle.
+pub struct AuditLog {
+    writer: Option<Mutex<BufWriter<File>>>,
+}
+
+impl AuditLog {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening audit log {}", path.display()))?;
+        Ok(Self {
+            writer: Some(Mutex::new(BufWriter::new(file))),
+        })
+    }
+
+    pub fn disabled() -> Self {
+        Self { writer: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Write failures are logged rather than propagated so auditing never
+    /// interrupts scanning or execution.
+    pub fn record(&self, event: AuditEvent<'_>) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let record = AuditRecord {
+            ts: Utc::now(),
+            event: &event,
+        };
+        let mut writer = writer.lock();
+        let result = serde_json::to_writer(&mut *writer, &record)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(writer.write_all(b"\n")?))
+            .and_then(|_| Ok(writer.flush()?));
+        if let Err(err) = result {
+            warn!(target: "audit", error = %err, "failed to write audit record");
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use super::{instrument_from_name, BacktestReport, BacktestWindow, Replay, StrategyTally};
+use crate::chain::OptionChain;
+use crate::client::{parse_ws_event, WsCapture, WsEvent};
+use crate::config::AppConfig;
+use crate::detect::DetectorSuite;
+use crate::ledger::SimulatedLedger;
+use crate::registry::OpportunityRegistry;
+use anyhow::{bail, Result};
+use chrono::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing::info;
+
+/// Feeds a `--record` capture back through the WS parser, the chain and the
+/// detectors. Scans run every `step` of recorded time from the first
+/// message, so the same capture always yields the same report; the clock
+/// never comes from the machine replaying it.
+pub fn replay_capture(config: &AppConfig, path: &Path, step: Duration) -> Result<BacktestReport> {
+    if step <= Duration::zero() {
+        bail!("replay step must be positive");
+    }
+    let chain = OptionChain::new();
+    let detector = DetectorSuite::new(config);
+    let mut registry =
+        OpportunityRegistry::new(Duration::seconds(config.dedup_cooldown_secs as i64));
+    let mut replay = Replay {
+        config,
+        chain: &chain,
+        detector: &detector,
+        registry: &mut registry,
+        step,
+        next_scan: Default::default(),
+        points: Vec::new(),
+        tallies: HashMap::new(),
+        ledger: config.sim_capital.map(SimulatedLedger::new),
+    };
+    let mut window: Option<BacktestWindow> = None;
+    let mut ticks_replayed = 0u64;
+    let mut known = HashSet::new();
+    let mut skipped = HashSet::new();
+
+    for message in WsCapture::open(path)? {
+        let message = message?;
+        let window = window.get_or_insert_with(|| {
+            replay.next_scan = message.at + step;
+            BacktestWindow {
+                from: message.at,
+                to: message.at,
+                step,
+            }
+        });
+        window.to = window.to.max(message.at);
+        replay.scan_until(message.at);
+        let Some(event) = parse_ws_event(&message.raw) else {
+            continue;
+        };
+        let instrument_name = match &event {
+            WsEvent::TickerUpdate {
+                instrument_name, ..
+            }
+            | WsEvent::BookUpdate {
+                instrument_name, ..
+            } => instrument_name.clone(),
+            _ => continue,
+        };
+        if !known.contains(&instrument_name) {
+            if skipped.contains(&instrument_name) {
+                continue;
+            }
+            match instrument_from_name(&instrument_name)
+                .ok()
+                .filter(|instrument| replay.wants(instrument))
+            {
+                Some(instrument) => {
+                    chain.upsert_instrument(instrument);
+                    known.insert(instrument_name.clone());
+                }
+                None => {
+                    skipped.insert(instrument_name);
+                    continue;
+                }
+            }
+        }
+        match event {
+            WsEvent::TickerUpdate { quote, .. } => chain.update_quote(&instrument_name, quote),
+            WsEvent::BookUpdate { book, .. } => chain.update_order_book(&instrument_name, book),
+            _ => continue,
+        }
+        ticks_replayed += 1;
+    }
+    let Some(window) = window else {
+        bail!("WS capture {} holds no messages", path.display());
+    };
+    replay.scan_until(window.to);
+    let ledger = replay.ledger.as_mut().map(|ledger| {
+        ledger.settle(window.to);
+        ledger.summary()
+    });
+
+    let mut by_strategy: Vec<StrategyTally> = replay.tallies.into_values().collect();
+    by_strategy.sort_by_key(|tally| std::cmp::Reverse(tally.edge_usd));
+    info!(
+        ticks_replayed,
+        scans = replay.points.len(),
+        "capture replay complete"
+    );
+    Ok(BacktestReport {
+        window,
+        ticks_replayed,
+        points: replay.points,
+        by_strategy,
+        ledger,
+        deliveries: Vec::new(),
+    })
+}
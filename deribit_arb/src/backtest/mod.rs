@@ -0,0 +1,380 @@
+use crate::chain::OptionChain;
+use crate::config::AppConfig;
+use crate::detect::DetectorSuite;
+use crate::ledger::{LedgerSummary, SimulatedLedger};
+use crate::model::{
+    Currency, Instrument, ParsedInstrumentName, Quote, QuoteLevel, SettlementCurrency, StrategyKind,
+};
+use crate::registry::OpportunityRegistry;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use optstore::schema::{event, Tick, PRICE_DECIMALS, SIZE_DECIMALS};
+use optstore::InstrumentDictionary;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+mod capture;
+mod record;
+mod research;
+
+pub use capture::replay_capture;
+pub use record::SnapshotRecorder;
+pub use research::ResearchData;
+
+/// Replay range `[from, to)` and the spacing between detector runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BacktestWindow {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    #[serde(rename = "step_secs", serialize_with = "serialize_secs")]
+    pub step: Duration,
+}
+
+fn serialize_secs<S: serde::Serializer>(step: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(step.num_seconds())
+}
+
+impl BacktestWindow {
+    /// Bounds are RFC 3339 timestamps or `YYYY-MM-DD`; a bare `to` date
+    /// includes that whole day.
+    pub fn parse(from: &str, to: &str, step_secs: u64) -> Result<Self> {
+        let from = parse_bound(from, false)?;
+        let to = parse_bound(to, true)?;
+        if to <= from {
+            bail!("backtest window is empty: --to must be after --from");
+        }
+        if step_secs == 0 {
+            bail!("--step-secs must be positive");
+        }
+        Ok(Self {
+            from,
+            to,
+            step: Duration::seconds(step_secs as i64),
+        })
+    }
+
+    fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        let last = (self.to - Duration::nanoseconds(1)).date_naive();
+        self.from
+            .date_naive()
+            .iter_days()
+            .take_while(move |day| *day <= last)
+    }
+}
+
+fn parse_bound(value: &str, end_of_day: bool) -> Result<DateTime<Utc>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d").with_context(|| {
+        format!("invalid backtest bound {value}, expected YYYY-MM-DD or RFC 3339")
+    })?;
+    let day = if end_of_day {
+        day.succ_opt()
+            .ok_or_else(|| anyhow!("backtest bound {value} out of range"))?
+    } else {
+        day
+    };
+    Ok(day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+/// One detector run at a replayed timestamp.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestPoint {
+    pub at: DateTime<Utc>,
+    pub instruments: usize,
+    pub opportunities: usize,
+    /// Opportunities not already alerted within the dedup cooldown.
+    pub fresh: usize,
+    pub fresh_edge_usd: Decimal,
+    pub best_edge_usd: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StrategyTally {
+    pub strategy: StrategyKind,
+    pub captures: usize,
+    pub edge_usd: Decimal,
+}
+
+/// Index price a replayed expiry settled at, from `--research` history.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpiryDelivery {
+    pub currency: Currency,
+    pub expiry: DateTime<Utc>,
+    pub delivery_price: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestReport {
+    pub window: BacktestWindow,
+    pub ticks_replayed: u64,
+    pub points: Vec<BacktestPoint>,
+    /// Fresh captures per strategy, largest edge first.
+    pub by_strategy: Vec<StrategyTally>,
+    /// Capital usage of the fresh captures under `--sim-capital`.
+    pub ledger: Option<LedgerSummary>,
+    /// Expiries inside the window with a known delivery price, oldest first.
+    pub deliveries: Vec<ExpiryDelivery>,
+}
+
+impl BacktestReport {
+    /// Hypothetical edge had every fresh opportunity been filled at the touch.
+    pub fn total_edge_usd(&self) -> Decimal {
+        self.by_strategy.iter().map(|tally| tally.edge_usd).sum()
+    }
+
+    pub fn total_captures(&self) -> usize {
+        self.by_strategy.iter().map(|tally| tally.captures).sum()
+    }
+}
+
+/// Rebuilds the chain from stored book ticks under `data` (optstore's
+/// `YYYY/MM/DD.opt` layout) and runs the detectors every `window.step`.
+/// Repeats are deduplicated with the live `--dedup-cooldown` registry, so an
+/// opportunity that persists across steps is only captured once.
+pub fn run(config: &AppConfig, data: &Path, window: BacktestWindow) -> Result<BacktestReport> {
+    run_with(config, data, window, None)
+}
+
+/// [`run`], taking listing metadata from `research` where it has the
+/// instrument and reporting the delivery price of each replayed expiry.
+pub fn run_with(
+    config: &AppConfig,
+    data: &Path,
+    window: BacktestWindow,
+    research: Option<&ResearchData>,
+) -> Result<BacktestReport> {
+    let chain = OptionChain::new();
+    let detector = DetectorSuite::new(config);
+    let mut registry =
+        OpportunityRegistry::new(Duration::seconds(config.dedup_cooldown_secs as i64));
+    let mut replay = Replay {
+        config,
+        chain: &chain,
+        detector: &detector,
+        registry: &mut registry,
+        step: window.step,
+        next_scan: window.from + window.step,
+        points: Vec::new(),
+        tallies: HashMap::new(),
+        ledger: config.sim_capital.map(SimulatedLedger::new),
+    };
+    let mut ticks_replayed = 0u64;
+    let from_ns = timestamp_ns(window.from);
+    let to_ns = timestamp_ns(window.to);
+    let mut expiries = HashSet::new();
+
+    for day in window.days() {
+        let path = optstore::util::day_to_path(data, &day.format("%Y-%m-%d").to_string());
+        // Rows and a `--record-snapshot-interval` book stream, merged by time.
+        let Some(ticks) = optstore::book::read_day(&path)? else {
+            warn!(file = %path.display(), "no optstore data for day, skipping");
+            continue;
+        };
+        let dictionary = InstrumentDictionary::load_for(&path)?;
+        let mut known = HashSet::new();
+        let mut skipped = HashSet::new();
+        for tick in ticks {
+            let tick = tick.with_context(|| format!("reading {}", path.display()))?;
+            if tick.event != event::BOOK || !(from_ns..to_ns).contains(&tick.ts_ns) {
+                continue;
+            }
+            let at = DateTime::from_timestamp_nanos(tick.ts_ns as i64);
+            replay.scan_until(at);
+            if !known.contains(&tick.instrument_id) {
+                if skipped.contains(&tick.instrument_id) {
+                    continue;
+                }
+                let instrument = dictionary
+                    .name(tick.instrument_id)
+                    .and_then(|name| {
+                        research
+                            .and_then(|research| research.instrument(name).cloned())
+                            .or_else(|| instrument_from_name(name).ok())
+                    })
+                    .filter(|instrument| replay.wants(instrument));
+                match instrument {
+                    Some(instrument) => {
+                        if (window.from..=window.to).contains(&instrument.expiry) {
+                            expiries.insert((instrument.currency, instrument.expiry));
+                        }
+                        chain.upsert_instrument(instrument);
+                        known.insert(tick.instrument_id);
+                    }
+                    None => {
+                        skipped.insert(tick.instrument_id);
+                        continue;
+                    }
+                }
+            }
+            if let Some(name) = dictionary.name(tick.instrument_id) {
+                chain.update_quote(name, quote_from_tick(&tick, at));
+                ticks_replayed += 1;
+            }
+        }
+    }
+    replay.scan_until(window.to);
+    let ledger = replay.ledger.as_mut().map(|ledger| {
+        ledger.settle(window.to);
+        ledger.summary()
+    });
+
+    let mut deliveries: Vec<ExpiryDelivery> = research
+        .map(|research| {
+            expiries
+                .into_iter()
+                .filter_map(|(currency, expiry)| {
+                    research
+                        .delivery_price(currency, expiry.date_naive())
+                        .map(|delivery_price| ExpiryDelivery {
+                            currency,
+                            expiry,
+                            delivery_price,
+                        })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    deliveries
+        .sort_by(|a, b| (a.expiry, a.currency.as_str()).cmp(&(b.expiry, b.currency.as_str())));
+
+    let mut by_strategy: Vec<StrategyTally> = replay.tallies.into_values().collect();
+    by_strategy.sort_by_key(|tally| std::cmp::Reverse(tally.edge_usd));
+    info!(
+        ticks_replayed,
+        scans = replay.points.len(),
+        "backtest complete"
+    );
+    Ok(BacktestReport {
+        window,
+        ticks_replayed,
+        points: replay.points,
+        by_strategy,
+        ledger,
+        deliveries,
+    })
+}
+
+struct Replay<'a> {
+    config: &'a AppConfig,
+    chain: &'a OptionChain,
+    detector: &'a DetectorSuite<'a>,
+    registry: &'a mut OpportunityRegistry,
+    step: Duration,
+    next_scan: DateTime<Utc>,
+    points: Vec<BacktestPoint>,
+    tallies: HashMap<StrategyKind, StrategyTally>,
+    ledger: Option<SimulatedLedger>,
+}
+
+impl Replay<'_> {
+    fn wants(&self, instrument: &Instrument) -> bool {
+        self.config.currencies.contains(&instrument.currency)
+            && self
+                .config
+                .settlements
+                .contains(&instrument.settlement_currency)
+    }
+
+    /// Runs every scan due at or before `at`, before ticks at `at` apply.
+    fn scan_until(&mut self, at: DateTime<Utc>) {
+        while self.next_scan <= at {
+            let now = self.next_scan;
+            self.scan(now);
+            self.next_scan = now + self.step;
+        }
+    }
+
+    fn scan(&mut self, now: DateTime<Utc>) {
+        self.chain.evict_expired(now);
+        let opportunities = self.detector.scan_chain_at(self.chain, now);
+        let best_edge_usd = opportunities
+            .first()
+            .map(|opp| opp.net_edge_usd)
+            .unwrap_or_default();
+        let detected = opportunities.len();
+        let update = self.registry.observe(opportunities, now);
+        for opp in &update.fresh {
+            let tally = self
+                .tallies
+                .entry(opp.strategy)
+                .or_insert_with(|| StrategyTally {
+                    strategy: opp.strategy,
+                    captures: 0,
+                    edge_usd: Decimal::ZERO,
+                });
+            tally.captures += 1;
+            tally.edge_usd += opp.net_edge_usd;
+            // Replays have no preview, so captures fill at the detected touch.
+            if let Some(ledger) = self.ledger.as_mut() {
+                ledger.settle(now);
+                ledger.fill(opp, opp.net_edge_usd, now);
+            }
+        }
+        self.points.push(BacktestPoint {
+            at: now,
+            instruments: self.chain.len(),
+            opportunities: detected,
+            fresh: update.fresh.len(),
+            fresh_edge_usd: update.fresh.iter().map(|opp| opp.net_edge_usd).sum(),
+            best_edge_usd,
+        });
+    }
+}
+
+fn timestamp_ns(ts: DateTime<Utc>) -> u64 {
+    ts.timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
+
+fn quote_from_tick(tick: &Tick, at: DateTime<Utc>) -> Quote {
+    let level = |price: i64, size: u32| {
+        (size > 0).then(|| QuoteLevel {
+            price: Decimal::new(price, PRICE_DECIMALS),
+            amount: Decimal::new(size as i64, SIZE_DECIMALS),
+        })
+    };
+    Quote {
+        best_bid: level(tick.bid_px_fp[0], tick.bid_sz[0]),
+        best_ask: level(tick.ask_px_fp[0], tick.ask_sz[0]),
+        mark_iv: None,
+        bid_iv: None,
+        ask_iv: None,
+        interest_rate: None,
+        timestamp: at,
+        index_price: Decimal::new(tick.price_fp, PRICE_DECIMALS),
+    }
+}
+
+/// Reconstructs instrument metadata from its name, since optstore only keeps
+/// names. `BTC_USDC-…` (or `_USDT`, `_EURR`) is linear; anything else is
+/// coin-settled.
+/// Contract sizes and tick sizes follow Deribit's listing defaults.
+pub fn instrument_from_name(name: &str) -> Result<Instrument> {
+    let parsed = ParsedInstrumentName::from_str(name)?;
+    let settlement = parsed.settlement;
+    let (contract_size, tick_size, min_trade_amount) = match settlement {
+        SettlementCurrency::Coin => (Decimal::ONE, dec!(0.0005), dec!(0.1)),
+        _ if parsed.currency == Currency::BTC => (dec!(0.01), dec!(5), dec!(0.01)),
+        _ if parsed.currency == Currency::ETH => (dec!(0.1), dec!(0.5), dec!(0.1)),
+        _ => (Decimal::ONE, dec!(0.01), Decimal::ONE),
+    };
+    Ok(Instrument {
+        instrument_name: name.to_string(),
+        currency: parsed.currency,
+        is_usdc_settled: settlement == SettlementCurrency::Usdc,
+        is_combo: false,
+        option_kind: parsed.option_kind,
+        strike: parsed.strike,
+        expiry: parsed.expiry_date()?,
+        contract_size,
+        settlement_currency: settlement,
+        tick_size,
+        min_trade_amount,
+    })
+}
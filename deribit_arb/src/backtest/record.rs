@@ -0,0 +1,177 @@
+use crate::model::{ChainSnapshot, InstrumentSnapshot, QuoteLevel};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use optstore::block::BlockLayout;
+use optstore::book::{book_path, BookWriter};
+use optstore::registry::InstrumentRegistry;
+use optstore::schema::{event, Tick, PRICE_DECIMALS, SIZE_DECIMALS};
+use optstore::{InstrumentDictionary, TickWriter};
+use rust_decimal::prelude::*;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Book levels an optstore tick can carry per side.
+const TICK_LEVELS: usize = 4;
+
+/// Appends scanned chain snapshots to optstore day files (`<dir>/YYYY/MM/DD.opt`
+/// plus the `.instruments.json` sidecar) so [`run`](super::run) can replay
+/// live sessions. Instrument ids come from `<dir>`'s instrument registry. Each quote becomes one book tick stamped with its own
+/// timestamp; quotes unchanged since the previous snapshot are skipped.
+/// New day files get `layout`; files from earlier sessions keep their own.
+/// With [`with_book_stream`](Self::with_book_stream) quotes go to the
+/// day's snapshot + delta `DD.book` stream instead.
+pub struct SnapshotRecorder {
+    dir: PathBuf,
+    layout: BlockLayout,
+    snapshot_interval: Option<u32>,
+    /// Open book streams; replaying one on reopen costs a full read.
+    books: HashMap<NaiveDate, BookWriter>,
+    last_recorded: HashMap<String, DateTime<Utc>>,
+    /// Loaded on the first snapshot.
+    registry: Option<InstrumentRegistry>,
+    /// Instrument ids already in each day's sidecar.
+    stored: HashMap<NaiveDate, HashSet<u32>>,
+}
+
+impl SnapshotRecorder {
+    pub fn new(dir: PathBuf, layout: BlockLayout) -> Self {
+        Self {
+            dir,
+            layout,
+            snapshot_interval: None,
+            books: HashMap::new(),
+            last_recorded: HashMap::new(),
+            registry: None,
+            stored: HashMap::new(),
+        }
+    }
+
+    /// Records to book streams with a full snapshot of each instrument every
+    /// `snapshot_interval` updates.
+    pub fn with_book_stream(mut self, snapshot_interval: u32) -> Self {
+        self.snapshot_interval = Some(snapshot_interval);
+        self
+    }
+
+    /// Writes the quotes that changed since the last call and returns how
+    /// many ticks were appended.
+    pub fn record(&mut self, snapshot: &ChainSnapshot) -> Result<u64> {
+        let registry = match &mut self.registry {
+            Some(registry) => registry,
+            None => self.registry.insert(InstrumentRegistry::load(&self.dir)?),
+        };
+        let registered = registry.ids.len();
+        let mut by_day: BTreeMap<NaiveDate, Vec<(&str, f64, Tick)>> = BTreeMap::new();
+        for inst in &snapshot.instruments {
+            let name = inst.instrument.instrument_name.as_str();
+            let at = inst.quote.timestamp;
+            if self.last_recorded.get(name).is_some_and(|last| *last >= at) {
+                continue;
+            }
+            let Some(tick) = book_tick(inst, registry.id(name)) else {
+                continue;
+            };
+            self.last_recorded.insert(name.to_string(), at);
+            by_day.entry(at.date_naive()).or_default().push((
+                name,
+                inst.instrument.contract_size.to_f64().unwrap_or(1.0),
+                tick,
+            ));
+        }
+
+        if registry.ids.len() > registered {
+            registry.store(&self.dir)?;
+        }
+
+        let mut written = 0;
+        for (day, mut ticks) in by_day {
+            ticks.sort_by_key(|(_, _, tick)| tick.ts_ns);
+            let path = optstore::util::day_to_path(&self.dir, &day.format("%Y-%m-%d").to_string());
+            let stored = self.stored.entry(day).or_default();
+            let mut added = InstrumentDictionary::default();
+            for (name, contract_size, tick) in &ticks {
+                if stored.insert(tick.instrument_id) {
+                    added.insert_as(tick.instrument_id, name)?;
+                    added
+                        .contract_sizes
+                        .insert(tick.instrument_id, *contract_size);
+                }
+            }
+            // Before any row: a day file from before the registry holds
+            // these names under hashed ids and fails the merge.
+            if !added.instruments.is_empty() {
+                added.store_for(&path)?;
+            }
+            if let Some(interval) = self.snapshot_interval {
+                // Days only move forward, so older streams are done.
+                self.books.retain(|open, _| *open >= day);
+                let book = match self.books.entry(day) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        entry.insert(BookWriter::append(&book_path(&path), interval)?)
+                    }
+                };
+                for (_, _, tick) in &ticks {
+                    book.write(tick)?;
+                }
+                book.flush()
+                    .with_context(|| format!("writing {}", book_path(&path).display()))?;
+                written += ticks.len() as u64;
+            } else {
+                let mut writer = if path.exists() {
+                    TickWriter::append(&path)?
+                } else {
+                    TickWriter::append_with_layout(&path, self.layout)?
+                };
+                for (_, _, tick) in &ticks {
+                    writer.write(tick)?;
+                }
+                written += writer
+                    .finish()
+                    .with_context(|| format!("writing {}", path.display()))?;
+            }
+        }
+        Ok(written)
+    }
+}
+
+fn book_tick(inst: &InstrumentSnapshot, instrument_id: u32) -> Option<Tick> {
+    let (bids, asks): (Vec<&QuoteLevel>, Vec<&QuoteLevel>) = match &inst.order_book {
+        Some(book) => (book.bids.iter().collect(), book.asks.iter().collect()),
+        None => (
+            inst.quote.best_bid.iter().collect(),
+            inst.quote.best_ask.iter().collect(),
+        ),
+    };
+    if bids.is_empty() && asks.is_empty() {
+        return None;
+    }
+    let mut tick = Tick {
+        ts_ns: inst.quote.timestamp.timestamp_nanos_opt()?.max(0) as u64,
+        instrument_id,
+        event: event::BOOK,
+        price_fp: fixed(inst.quote.index_price, PRICE_DECIMALS)?,
+        size: 0,
+        bid_px_fp: [0; TICK_LEVELS],
+        ask_px_fp: [0; TICK_LEVELS],
+        bid_sz: [0; TICK_LEVELS],
+        ask_sz: [0; TICK_LEVELS],
+        flags: 0,
+    };
+    for (slot, level) in bids.iter().take(TICK_LEVELS).enumerate() {
+        tick.bid_px_fp[slot] = fixed(level.price, PRICE_DECIMALS)?;
+        tick.bid_sz[slot] = fixed(level.amount, SIZE_DECIMALS)?.try_into().ok()?;
+    }
+    for (slot, level) in asks.iter().take(TICK_LEVELS).enumerate() {
+        tick.ask_px_fp[slot] = fixed(level.price, PRICE_DECIMALS)?;
+        tick.ask_sz[slot] = fixed(level.amount, SIZE_DECIMALS)?.try_into().ok()?;
+    }
+    Some(tick)
+}
+
+fn fixed(value: Decimal, decimals: u32) -> Option<i64> {
+    (value * Decimal::from(10i64.pow(decimals)))
+        .round()
+        .to_i64()
+}
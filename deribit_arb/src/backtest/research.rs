@@ -0,0 +1,70 @@
+use crate::client::DeribitHttpClient;
+use crate::config::AppConfig;
+use crate::model::{Currency, Instrument};
+use anyhow::Result;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Deribit keeps at most this many days of delivery prices per request.
+const DELIVERY_DAYS: u32 = 1000;
+
+/// Exchange metadata for listings that have since expired, plus the index
+/// delivery price of every past day, loaded once for a `--research` replay.
+#[derive(Debug, Clone, Default)]
+pub struct ResearchData {
+    instruments: HashMap<String, Instrument>,
+    deliveries: HashMap<(Currency, NaiveDate), Decimal>,
+}
+
+impl ResearchData {
+    /// Expired and live listings of every configured book, and the delivery
+    /// prices of every configured currency, from `client` (a client pointed
+    /// at the history host).
+    pub async fn load(client: &DeribitHttpClient, config: &AppConfig) -> Result<Self> {
+        let mut research = Self::default();
+        for currency in &config.currencies {
+            for book in currency.instrument_books(&config.settlements) {
+                research.insert_instruments(client.get_expired_instruments(book).await?);
+                research.insert_instruments(client.get_instruments(book).await?);
+            }
+            research.insert_deliveries(
+                *currency,
+                client.get_delivery_prices(*currency, DELIVERY_DAYS).await?,
+            );
+        }
+        info!(
+            instruments = research.instruments.len(),
+            deliveries = research.deliveries.len(),
+            "loaded research history"
+        );
+        Ok(research)
+    }
+
+    pub fn insert_instruments(&mut self, instruments: impl IntoIterator<Item = Instrument>) {
+        for instrument in instruments {
+            self.instruments
+                .insert(instrument.instrument_name.clone(), instrument);
+        }
+    }
+
+    pub fn insert_deliveries(
+        &mut self,
+        currency: Currency,
+        deliveries: impl IntoIterator<Item = (NaiveDate, Decimal)>,
+    ) {
+        for (date, price) in deliveries {
+            self.deliveries.insert((currency, date), price);
+        }
+    }
+
+    pub fn instrument(&self, name: &str) -> Option<&Instrument> {
+        self.instruments.get(name)
+    }
+
+    /// Index price options expiring on `date` settled at.
+    pub fn delivery_price(&self, currency: Currency, date: NaiveDate) -> Option<Decimal> {
+        self.deliveries.get(&(currency, date)).copied()
+    }
+}
@@ -0,0 +1,207 @@
+use crate::client::{WsRecorder, CAPTURE_SUFFIX};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// Instruments listed or delisted between two listing checks of the
+/// `capture` daemon.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ListingChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ListingChange {
+    pub fn between(before: &BTreeSet<String>, after: &BTreeSet<String>) -> Self {
+        Self {
+            added: after.difference(before).cloned().collect(),
+            removed: before.difference(after).cloned().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// One subscribed instrument at `GET /health`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstrumentHealth {
+    pub instrument: String,
+    /// Milliseconds since the last ticker or book frame arrived; `None`
+    /// before the first.
+    pub last_tick_age_ms: Option<i64>,
+}
+
+/// The body of `GET /health`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub file: String,
+    /// Messages written to `file`.
+    pub messages: u64,
+    pub subscribed: usize,
+    /// Subscriptions restarted after a listing change.
+    pub restarts: u64,
+    /// Age of the freshest tick across instruments.
+    pub last_tick_age_ms: Option<i64>,
+    /// Subscribed instruments, stalest first.
+    pub instruments: Vec<InstrumentHealth>,
+}
+
+#[derive(Default)]
+struct HealthState {
+    /// Per subscribed instrument; `None` until its first frame.
+    last_tick: BTreeMap<String, Option<DateTime<Utc>>>,
+    restarts: u64,
+}
+
+/// Receive times of the `capture` daemon's ticks per subscribed
+/// instrument, shared with its health endpoint.
+#[derive(Clone, Default)]
+pub struct CaptureHealth {
+    state: Arc<Mutex<HealthState>>,
+}
+
+impl CaptureHealth {
+    pub fn new(subscribed: &BTreeSet<String>) -> Self {
+        let health = Self::default();
+        health.subscribe(subscribed);
+        health
+    }
+
+    /// Replaces the subscribed set after a listing change. Instruments kept
+    /// keep their last tick.
+    pub fn resubscribe(&self, subscribed: &BTreeSet<String>) {
+        self.subscribe(subscribed);
+        self.state.lock().restarts += 1;
+    }
+
+    fn subscribe(&self, subscribed: &BTreeSet<String>) {
+        let mut state = self.state.lock();
+        let mut last_tick = std::mem::take(&mut state.last_tick);
+        state.last_tick = subscribed
+            .iter()
+            .map(|name| (name.clone(), last_tick.remove(name).flatten()))
+            .collect();
+    }
+
+    /// Notes a frame for `instrument`; unsubscribed names are ignored.
+    pub fn tick(&self, instrument: &str, at: DateTime<Utc>) {
+        if let Some(last) = self.state.lock().last_tick.get_mut(instrument) {
+            *last = (*last).max(Some(at));
+        }
+    }
+
+    pub fn report(&self, recorder: &WsRecorder, now: DateTime<Utc>) -> HealthReport {
+        let state = self.state.lock();
+        let age = |at: &Option<DateTime<Utc>>| at.map(|at| (now - at).num_milliseconds().max(0));
+        let mut instruments: Vec<InstrumentHealth> = state
+            .last_tick
+            .iter()
+            .map(|(instrument, at)| InstrumentHealth {
+                instrument: instrument.clone(),
+                last_tick_age_ms: age(at),
+            })
+            .collect();
+        // Never ticked sorts first, then oldest tick.
+        instruments
+            .sort_by_key(|inst| std::cmp::Reverse(inst.last_tick_age_ms.unwrap_or(i64::MAX)));
+        HealthReport {
+            file: recorder.path().display().to_string(),
+            messages: recorder.messages(),
+            subscribed: state.last_tick.len(),
+            restarts: state.restarts,
+            last_tick_age_ms: state.last_tick.values().max().and_then(age),
+            instruments,
+        }
+    }
+}
+
+/// Deletes captures under `dir` last written more than `max_age` before
+/// `now`, other than `current`, and returns their paths.
+pub fn prune(
+    dir: &Path,
+    max_age: Duration,
+    now: SystemTime,
+    current: &Path,
+) -> Result<Vec<PathBuf>> {
+    let Some(cutoff) = now.checked_sub(max_age) else {
+        return Ok(Vec::new());
+    };
+    let mut removed = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("listing {}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        let capture = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(CAPTURE_SUFFIX));
+        if !capture || path == current || !entry.file_type()?.is_file() {
+            continue;
+        }
+        if entry.metadata()?.modified()? < cutoff {
+            std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+            info!(target: "capture", path = %path.display(), "removed expired capture");
+            removed.push(path);
+        }
+    }
+    removed.sort();
+    Ok(removed)
+}
+
+/// Binds `addr` and serves [`HealthReport`] at `GET /health` on a
+/// background task.
+pub async fn serve(
+    addr: SocketAddr,
+    health: CaptureHealth,
+    recorder: WsRecorder,
+) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("binding health endpoint {addr}"))?;
+    let local = listener.local_addr()?;
+    info!(target: "capture", addr = %local, "serving /health");
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    warn!(target: "capture", error = %err, "accept failed");
+                    continue;
+                }
+            };
+            let health = health.clone();
+            let recorder = recorder.clone();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let read = match socket.read(&mut buf).await {
+                    Ok(read) => read,
+                    Err(_) => return,
+                };
+                let request = String::from_utf8_lossy(&buf[..read]);
+                let response = if request.starts_with("GET /health") {
+                    let body = serde_json::to_string(&health.report(&recorder, Utc::now()))
+                        .unwrap_or_default();
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    Ok(local)
+}
@@ -0,0 +1,64 @@
+use super::OptionChain;
+use crate::model::{OrderBook, Quote};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Updates received since the last flush, latest per instrument.
+#[derive(Default)]
+struct Pending {
+    quotes: HashMap<String, Quote>,
+    books: HashMap<String, OrderBook>,
+    received: u64,
+}
+
+/// What one [`QuoteConflator::flush`] wrote into the chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConflationStats {
+    pub quotes: usize,
+    pub books: usize,
+    /// Updates overwritten by a newer one for the same instrument before
+    /// reaching the chain.
+    pub coalesced: u64,
+}
+
+/// Buffers streamed tickers and books between scan ticks, keeping only the
+/// latest per instrument, so hundreds of `100ms` channels cost one chain
+/// write lock per scan instead of one per message.
+#[derive(Clone, Default)]
+pub struct QuoteConflator {
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl QuoteConflator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_quote(&self, instrument_name: String, quote: Quote) {
+        let mut pending = self.pending.lock();
+        pending.received += 1;
+        pending.quotes.insert(instrument_name, quote);
+    }
+
+    pub fn push_order_book(&self, instrument_name: String, order_book: OrderBook) {
+        let mut pending = self.pending.lock();
+        pending.received += 1;
+        pending.books.insert(instrument_name, order_book);
+    }
+
+    /// Writes the buffered updates into `chain` under one lock and empties
+    /// the buffer.
+    pub fn flush(&self, chain: &OptionChain) -> ConflationStats {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        let stats = ConflationStats {
+            quotes: pending.quotes.len(),
+            books: pending.books.len(),
+            coalesced: pending.received - (pending.quotes.len() + pending.books.len()) as u64,
+        };
+        if stats.quotes > 0 || stats.books > 0 {
+            chain.apply_updates(pending.quotes, pending.books);
+        }
+        stats
+    }
+}
@@ -1,15 +1,178 @@
-use crate::model::{ChainSnapshot, Instrument, InstrumentSnapshot, OrderBook, Quote};
-use chrono::{Duration, Utc};
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use crate::model::{ChainSnapshot, Currency, Instrument, InstrumentSnapshot, OrderBook, Quote};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
-#[derive(Clone, Default)]
+mod conflate;
+
+pub use conflate::{ConflationStats, QuoteConflator};
+
+/// Instrument shards; a quote write locks only the shard its name hashes to.
+const SHARDS: usize = 16;
+
+/// Thread-safe option chain. Instruments live in [`SHARDS`] independently
+/// locked maps keyed by name hash, so the stream writer and the scanner
+/// rarely wait on each other; the expiry and strike indices sit behind their
+/// own lock, written only when listings are added or evicted.
+#[derive(Clone)]
 pub struct OptionChain {
-    inner: Arc<RwLock<HashMap<String, InstrumentSnapshot>>>,
+    inner: Arc<ChainInner>,
+}
+
+impl Default for OptionChain {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(ChainInner {
+                hasher: RandomState::new(),
+                shards: (0..SHARDS).map(|_| RwLock::default()).collect(),
+                index: RwLock::default(),
+                groups: Mutex::default(),
+            }),
+        }
+    }
+}
+
+type ExpiryKey = (Currency, DateTime<Utc>);
+type StrikeKey = (Currency, Decimal);
+type VersionedSlice = (u64, Arc<[InstrumentSnapshot]>);
+
+/// Locks are always taken index first, then shards, then the group cache.
+struct ChainInner {
+    hasher: RandomState,
+    shards: Box<[RwLock<Shard>]>,
+    index: RwLock<ChainIndex>,
+    /// Copy-on-write expiry slices, rebuilt only once the group's version
+    /// moved on since they were taken.
+    groups: Mutex<HashMap<ExpiryKey, VersionedSlice>>,
+}
+
+#[derive(Default)]
+struct Shard {
+    instruments: HashMap<String, InstrumentSnapshot>,
+    /// Instruments quoted since the last [`OptionChain::take_dirty`].
+    dirty: HashSet<String>,
+}
+
+/// Names by `(currency, expiry)` and `(currency, strike)`, kept in step with
+/// the shards on every insert and eviction.
+#[derive(Default)]
+struct ChainIndex {
+    by_expiry: HashMap<ExpiryKey, ExpiryGroup>,
+    by_strike: HashMap<StrikeKey, HashSet<String>>,
+    next_expiry: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct ExpiryGroup {
+    names: HashSet<String>,
+    /// Bumped after every write to a member, under its shard lock.
+    version: AtomicU64,
+}
+
+impl ChainIndex {
+    fn touch(&self, key: ExpiryKey) {
+        if let Some(group) = self.by_expiry.get(&key) {
+            group.version.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    fn expires_by(&self, now: DateTime<Utc>) -> bool {
+        self.next_expiry.is_some_and(|next| next <= now)
+    }
+}
+
+impl ChainInner {
+    fn shard_of(&self, instrument_name: &str) -> usize {
+        self.hasher.hash_one(instrument_name) as usize % self.shards.len()
+    }
+
+    fn shard(&self, instrument_name: &str) -> &RwLock<Shard> {
+        &self.shards[self.shard_of(instrument_name)]
+    }
+
+    fn insert(&self, index: &mut ChainIndex, snapshot: InstrumentSnapshot) {
+        let instrument = &snapshot.instrument;
+        let name = instrument.instrument_name.clone();
+        index
+            .by_expiry
+            .entry((instrument.currency, instrument.expiry))
+            .or_default()
+            .names
+            .insert(name.clone());
+        index
+            .by_strike
+            .entry((instrument.currency, instrument.strike))
+            .or_default()
+            .insert(name.clone());
+        index.next_expiry = Some(
+            index
+                .next_expiry
+                .map_or(instrument.expiry, |next| next.min(instrument.expiry)),
+        );
+        index.touch((instrument.currency, instrument.expiry));
+        self.shard(&name).write().instruments.insert(name, snapshot);
+    }
+
+    /// Drops instruments expiring at or before `now`; returns how many went.
+    fn evict_expired(&self, index: &mut ChainIndex, now: DateTime<Utc>) -> usize {
+        if !index.expires_by(now) {
+            return 0;
+        }
+        let expired: Vec<ExpiryKey> = index
+            .by_expiry
+            .keys()
+            .filter(|(_, expiry)| *expiry <= now)
+            .copied()
+            .collect();
+        let mut evicted = 0;
+        for key in expired {
+            let group = index.by_expiry.remove(&key).unwrap_or_default();
+            self.groups.lock().remove(&key);
+            for name in group.names {
+                let mut shard = self.shard(&name).write();
+                let Some(snapshot) = shard.instruments.remove(&name) else {
+                    continue;
+                };
+                shard.dirty.remove(&name);
+                let strike_key = (key.0, snapshot.instrument.strike);
+                if let Some(names) = index.by_strike.get_mut(&strike_key) {
+                    names.remove(&name);
+                    if names.is_empty() {
+                        index.by_strike.remove(&strike_key);
+                    }
+                }
+                evicted += 1;
+            }
+        }
+        index.next_expiry = index.by_expiry.keys().map(|(_, expiry)| *expiry).min();
+        evicted
+    }
+
+    /// Evicts ahead of a write stamped `now`, taking the index write lock
+    /// only when something is actually due.
+    fn evict_due(&self, now: DateTime<Utc>) {
+        if self.index.read().expires_by(now) {
+            self.evict_expired(&mut self.index.write(), now);
+        }
+    }
+
+    fn collect<'a>(&self, names: impl IntoIterator<Item = &'a String>) -> Vec<InstrumentSnapshot> {
+        names
+            .into_iter()
+            .filter_map(|name| self.shard(name).read().instruments.get(name).cloned())
+            .collect()
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct ChainStats {
     pub instrument_count: usize,
     pub instruments_with_quotes: usize,
@@ -20,18 +183,26 @@ pub struct ChainStats {
 
 impl OptionChain {
     pub fn new() -> Self {
-        Self {
-            inner: Arc::new(RwLock::new(HashMap::new())),
-        }
+        Self::default()
     }
 
     pub fn upsert_instrument(&self, instrument: Instrument) {
-        let mut guard = self.inner.write();
-        guard
-            .entry(instrument.instrument_name.clone())
-            .and_modify(|snapshot| snapshot.instrument = instrument.clone())
-            .or_insert(InstrumentSnapshot {
-                instrument: instrument.clone(),
+        let inner = &self.inner;
+        let mut index = inner.index.write();
+        {
+            let mut shard = inner.shard(&instrument.instrument_name).write();
+            if let Some(snapshot) = shard.instruments.get_mut(&instrument.instrument_name) {
+                let key = (instrument.currency, instrument.expiry);
+                snapshot.instrument = instrument;
+                drop(shard);
+                index.touch(key);
+                return;
+            }
+        }
+        inner.insert(
+            &mut index,
+            InstrumentSnapshot {
+                instrument,
                 quote: Quote {
                     best_bid: None,
                     best_ask: None,
@@ -43,60 +214,251 @@ impl OptionChain {
                     index_price: Default::default(),
                 },
                 order_book: None,
-            });
+            },
+        );
     }
 
+    /// Applies a quote, first evicting anything that expired by the quote's
+    /// timestamp so the chain never outgrows the live listing. Using the quote
+    /// clock rather than the wall clock keeps backtest replays consistent.
     pub fn update_quote(&self, instrument_name: &str, quote: Quote) {
-        let mut guard = self.inner.write();
-        if let Some(snapshot) = guard.get_mut(instrument_name) {
+        self.inner.evict_due(quote.timestamp);
+        let index = self.inner.index.read();
+        let mut shard = self.inner.shard(instrument_name).write();
+        if let Some(snapshot) = shard.instruments.get_mut(instrument_name) {
             snapshot.quote = quote;
+            let key = (snapshot.instrument.currency, snapshot.instrument.expiry);
+            shard.dirty.insert(instrument_name.to_string());
+            index.touch(key);
+        }
+    }
+
+    /// [`Self::update_quote`] and [`Self::update_order_book`] for a batch,
+    /// taking each shard's lock once.
+    pub fn apply_updates(
+        &self,
+        quotes: impl IntoIterator<Item = (String, Quote)>,
+        books: impl IntoIterator<Item = (String, OrderBook)>,
+    ) {
+        let inner = &self.inner;
+        let quotes: Vec<_> = quotes.into_iter().collect();
+        if let Some(latest) = quotes.iter().map(|(_, quote)| quote.timestamp).max() {
+            inner.evict_due(latest);
+        }
+        let mut by_shard: Vec<(Vec<_>, Vec<_>)> = Vec::new();
+        by_shard.resize_with(inner.shards.len(), Default::default);
+        for (name, quote) in quotes {
+            by_shard[inner.shard_of(&name)].0.push((name, quote));
+        }
+        for (name, order_book) in books {
+            by_shard[inner.shard_of(&name)].1.push((name, order_book));
+        }
+        let index = inner.index.read();
+        for (shard, (quotes, books)) in inner.shards.iter().zip(by_shard) {
+            if quotes.is_empty() && books.is_empty() {
+                continue;
+            }
+            let mut shard = shard.write();
+            for (name, quote) in quotes {
+                if let Some(snapshot) = shard.instruments.get_mut(&name) {
+                    snapshot.quote = quote;
+                    index.touch((snapshot.instrument.currency, snapshot.instrument.expiry));
+                    shard.dirty.insert(name);
+                }
+            }
+            for (name, order_book) in books {
+                if let Some(snapshot) = shard.instruments.get_mut(&name) {
+                    snapshot.order_book = Some(order_book);
+                    index.touch((snapshot.instrument.currency, snapshot.instrument.expiry));
+                }
+            }
         }
     }
 
     pub fn update_order_book(&self, instrument_name: &str, order_book: OrderBook) {
-        let mut guard = self.inner.write();
-        if let Some(snapshot) = guard.get_mut(instrument_name) {
+        let index = self.inner.index.read();
+        let mut shard = self.inner.shard(instrument_name).write();
+        if let Some(snapshot) = shard.instruments.get_mut(instrument_name) {
             snapshot.order_book = Some(order_book);
+            index.touch((snapshot.instrument.currency, snapshot.instrument.expiry));
         }
     }
 
+    /// Drains the names of instruments whose quote changed since the last call.
+    pub fn take_dirty(&self) -> Vec<String> {
+        self.inner
+            .shards
+            .iter()
+            .flat_map(|shard| shard.write().dirty.drain().collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn get(&self, instrument_name: &str) -> Option<InstrumentSnapshot> {
+        self.inner
+            .shard(instrument_name)
+            .read()
+            .instruments
+            .get(instrument_name)
+            .cloned()
+    }
+
+    /// Removes every instrument expiring at or before `now`, returning the
+    /// number evicted.
+    pub fn evict_expired(&self, now: DateTime<Utc>) -> usize {
+        self.inner.evict_expired(&mut self.inner.index.write(), now)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner
+            .shards
+            .iter()
+            .map(|shard| shard.read().instruments.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     pub fn snapshot(&self) -> ChainSnapshot {
-        let guard = self.inner.read();
-        let instruments = guard.values().cloned().collect();
+        let instruments = self
+            .inner
+            .shards
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .instruments
+                    .values()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect();
         ChainSnapshot {
             timestamp: Utc::now(),
             instruments,
         }
     }
 
+    /// Listed `(currency, expiry)` pairs, in no particular order.
+    pub fn expiry_keys(&self) -> Vec<(Currency, DateTime<Utc>)> {
+        self.inner.index.read().by_expiry.keys().copied().collect()
+    }
+
+    /// Listed `(currency, strike)` pairs, in no particular order.
+    pub fn strike_keys(&self) -> Vec<(Currency, Decimal)> {
+        self.inner.index.read().by_strike.keys().copied().collect()
+    }
+
+    /// Every instrument of `currency` expiring at `expiry`, across strikes,
+    /// option kinds and settlement currencies.
+    pub fn expiry_slice(
+        &self,
+        currency: Currency,
+        expiry: DateTime<Utc>,
+    ) -> Vec<InstrumentSnapshot> {
+        self.expiry_group(currency, expiry).to_vec()
+    }
+
+    /// [`Self::expiry_slice`] as a shared copy-on-write snapshot: repeated
+    /// calls return the same allocation until a member is written to.
+    pub fn expiry_group(
+        &self,
+        currency: Currency,
+        expiry: DateTime<Utc>,
+    ) -> Arc<[InstrumentSnapshot]> {
+        let inner = &self.inner;
+        let index = inner.index.read();
+        let key = (currency, expiry);
+        let Some(group) = index.by_expiry.get(&key) else {
+            return Arc::from([]);
+        };
+        let version = group.version.load(Ordering::Acquire);
+        if let Some((cached, slice)) = inner.groups.lock().get(&key) {
+            if *cached == version {
+                return slice.clone();
+            }
+        }
+        // Taken after the version, so a concurrent write at worst leaves a
+        // snapshot that the next call rebuilds.
+        let slice: Arc<[InstrumentSnapshot]> = inner.collect(&group.names).into();
+        let mut groups = inner.groups.lock();
+        let entry = groups
+            .entry(key)
+            .or_insert_with(|| (version, slice.clone()));
+        if entry.0 < version {
+            *entry = (version, slice.clone());
+        }
+        slice
+    }
+
+    /// Every instrument of `currency` struck at `strike`, across expiries,
+    /// option kinds and settlement currencies.
+    pub fn strike_slice(&self, currency: Currency, strike: Decimal) -> Vec<InstrumentSnapshot> {
+        let index = self.inner.index.read();
+        index
+            .by_strike
+            .get(&(currency, strike))
+            .map(|names| self.inner.collect(names))
+            .unwrap_or_default()
+    }
+
+    /// Writes the current snapshot as JSON, replacing `path` atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let snapshot = self.snapshot();
+        let tmp = path.with_extension("tmp");
+        let file = std::fs::File::create(&tmp)
+            .with_context(|| format!("creating chain snapshot {}", tmp.display()))?;
+        serde_json::to_writer(std::io::BufWriter::new(file), &snapshot)
+            .with_context(|| format!("writing chain snapshot {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("replacing chain snapshot {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Rebuilds a chain from [`OptionChain::save`] output, skipping expired
+    /// instruments. Quotes keep their original timestamps, so staleness checks
+    /// see them as old until fresh ticks arrive.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("opening chain snapshot {}", path.display()))?;
+        let snapshot: ChainSnapshot = serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("parsing chain snapshot {}", path.display()))?;
+        let chain = Self::new();
+        {
+            let mut index = chain.inner.index.write();
+            for inst in snapshot.instruments {
+                chain.inner.insert(&mut index, inst);
+            }
+            chain.inner.evict_expired(&mut index, Utc::now());
+        }
+        Ok(chain)
+    }
+
     pub fn stats(&self) -> ChainStats {
-        let guard = self.inner.read();
         let now = Utc::now();
         let horizon = Duration::seconds(10);
-        let (with_quote, fresh, bid_levels, ask_levels) =
-            guard
-                .values()
-                .fold((0usize, 0usize, 0usize, 0usize), |acc, snapshot| {
-                    let mut with_quote = acc.0;
-                    let mut fresh = acc.1;
-                    let mut bids = acc.2;
-                    let mut asks = acc.3;
-                    if snapshot.quote.best_bid.is_some() || snapshot.quote.best_ask.is_some() {
-                        with_quote += 1;
-                    }
-                    if now - snapshot.quote.timestamp <= horizon {
-                        fresh += 1;
-                    }
-                    bids += snapshot.quote.best_bid.is_some() as usize;
-                    asks += snapshot.quote.best_ask.is_some() as usize;
-                    (with_quote, fresh, bids, asks)
-                });
-        ChainStats {
-            instrument_count: guard.len(),
-            instruments_with_quotes: with_quote,
-            instruments_fresh_10s: fresh,
-            bid_levels,
-            ask_levels,
+        let mut stats = ChainStats {
+            instrument_count: 0,
+            instruments_with_quotes: 0,
+            instruments_fresh_10s: 0,
+            bid_levels: 0,
+            ask_levels: 0,
+        };
+        for shard in self.inner.shards.iter() {
+            for snapshot in shard.read().instruments.values() {
+                let quote = &snapshot.quote;
+                stats.instrument_count += 1;
+                if quote.best_bid.is_some() || quote.best_ask.is_some() {
+                    stats.instruments_with_quotes += 1;
+                }
+                if now - quote.timestamp <= horizon {
+                    stats.instruments_fresh_10s += 1;
+                }
+                stats.bid_levels += quote.best_bid.is_some() as usize;
+                stats.ask_levels += quote.best_ask.is_some() as usize;
+            }
         }
+        stats
     }
 }
@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// zstd level for captures; ticker JSON compresses well even at the fast
+/// end.
+const CAPTURE_LEVEL: i32 = 3;
+/// How often buffered frames are flushed, bounding what a crash loses.
+const FLUSH_EVERY: Duration = Duration::from_secs(1);
+
+/// One WebSocket text frame as received, stamped with the local receive
+/// time. Captures are zstd-compressed JSON lines of these.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    #[serde(with = "chrono::serde::ts_microseconds")]
+    pub at: DateTime<Utc>,
+    pub raw: String,
+}
+
+type Encoder = zstd::stream::write::Encoder<'static, BufWriter<File>>;
+
+/// Suffix of capture files; daily captures are `YYYY-MM-DD.jsonl.zst`.
+pub const CAPTURE_SUFFIX: &str = ".jsonl.zst";
+
+struct RecorderState {
+    path: PathBuf,
+    /// Directory and UTC day of a daily capture.
+    daily: Option<(PathBuf, NaiveDate)>,
+    encoder: Option<Encoder>,
+    flushed_at: Instant,
+    messages: u64,
+}
+
+/// Writes every raw frame a [`super::DeribitWsClient`] receives to a
+/// capture file for `--replay`. Clones share the file, so reconnects keep
+/// appending to one capture. Write failures are logged and stop the
+/// recording rather than the stream.
+#[derive(Clone)]
+pub struct WsRecorder {
+    state: Arc<Mutex<RecorderState>>,
+}
+
+impl std::fmt::Debug for WsRecorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WsRecorder")
+            .field("path", &self.state.lock().path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WsRecorder {
+    /// Starts a capture at `path`, replacing any file already there.
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            state: Arc::new(Mutex::new(RecorderState {
+                path: path.to_path_buf(),
+                daily: None,
+                encoder: Some(open_encoder(path)?),
+                flushed_at: Instant::now(),
+                messages: 0,
+            })),
+        })
+    }
+
+    /// Starts a capture under `dir` that rotates to a new file at midnight
+    /// UTC, so each file holds one receive day. A day already captured by
+    /// an earlier run gets a numbered file (`YYYY-MM-DD.1.jsonl.zst`)
+    /// rather than being appended to, since its last frame may be torn.
+    pub fn daily(dir: &Path, now: DateTime<Utc>) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("creating capture directory {}", dir.display()))?;
+        let day = now.date_naive();
+        let path = daily_path(dir, day);
+        Ok(Self {
+            state: Arc::new(Mutex::new(RecorderState {
+                encoder: Some(open_encoder(&path)?),
+                path,
+                daily: Some((dir.to_path_buf(), day)),
+                flushed_at: Instant::now(),
+                messages: 0,
+            })),
+        })
+    }
+
+    /// The file being written.
+    pub fn path(&self) -> PathBuf {
+        self.state.lock().path.clone()
+    }
+
+    /// Moves a daily capture on to `now`'s file once its UTC day has
+    /// ended; [`record`](Self::record) does this itself, so this only
+    /// matters when the stream is quiet across midnight.
+    pub fn rotate(&self, now: DateTime<Utc>) {
+        rotate(&mut self.state.lock(), now);
+    }
+
+    pub fn record(&self, at: DateTime<Utc>, raw: &str) {
+        let mut state = self.state.lock();
+        rotate(&mut state, at);
+        let Some(encoder) = state.encoder.as_mut() else {
+            return;
+        };
+        let message = CapturedMessage {
+            at,
+            raw: raw.to_string(),
+        };
+        let mut result = serde_json::to_writer(&mut *encoder, &message)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(encoder.write_all(b"\n")?));
+        if result.is_ok() && state.flushed_at.elapsed() >= FLUSH_EVERY {
+            // Flushing ends a zstd block, so everything so far can be read
+            // back even if the process dies before `finish`.
+            result = state
+                .encoder
+                .as_mut()
+                .map_or(Ok(()), |encoder| Ok(encoder.flush()?));
+            state.flushed_at = Instant::now();
+        }
+        match result {
+            Ok(()) => state.messages += 1,
+            Err(err) => {
+                warn!(target: "ws.capture", path = %state.path.display(), error = %err, "failed to write WS capture, recording stopped");
+                state.encoder = None;
+            }
+        }
+    }
+
+    /// Messages written to the current file so far.
+    pub fn messages(&self) -> u64 {
+        self.state.lock().messages
+    }
+
+    /// Ends the zstd frame and flushes the file. Later records are dropped.
+    pub fn finish(&self) -> Result<()> {
+        finish(&mut self.state.lock())
+    }
+}
+
+fn open_encoder(path: &Path) -> Result<Encoder> {
+    let file =
+        File::create(path).with_context(|| format!("creating WS capture {}", path.display()))?;
+    let encoder =
+        Encoder::new(BufWriter::new(file), CAPTURE_LEVEL).context("starting zstd encoder")?;
+    info!(target: "ws.capture", path = %path.display(), "recording raw websocket messages");
+    Ok(encoder)
+}
+
+/// The first unused capture file name for `day` under `dir`.
+fn daily_path(dir: &Path, day: NaiveDate) -> PathBuf {
+    let day = day.format("%Y-%m-%d");
+    let path = dir.join(format!("{day}{CAPTURE_SUFFIX}"));
+    if !path.exists() {
+        return path;
+    }
+    (1..)
+        .map(|n| dir.join(format!("{day}.{n}{CAPTURE_SUFFIX}")))
+        .find(|path| !path.exists())
+        .expect("a free capture file name")
+}
+
+fn rotate(state: &mut RecorderState, now: DateTime<Utc>) {
+    let Some((dir, day)) = state.daily.clone() else {
+        return;
+    };
+    if now.date_naive() <= day || state.encoder.is_none() {
+        return;
+    }
+    if let Err(err) = finish(state) {
+        warn!(target: "ws.capture", error = %err, "failed to close WS capture");
+    }
+    let day = now.date_naive();
+    let path = daily_path(&dir, day);
+    match open_encoder(&path) {
+        Ok(encoder) => state.encoder = Some(encoder),
+        Err(err) => {
+            warn!(target: "ws.capture", path = %path.display(), error = %err, "failed to rotate WS capture, recording stopped")
+        }
+    }
+    state.path = path;
+    state.daily = Some((dir, day));
+    state.flushed_at = Instant::now();
+    state.messages = 0;
+}
+
+fn finish(state: &mut RecorderState) -> Result<()> {
+    let Some(encoder) = state.encoder.take() else {
+        return Ok(());
+    };
+    encoder
+        .finish()
+        .and_then(|mut writer| writer.flush())
+        .with_context(|| format!("finishing WS capture {}", state.path.display()))?;
+    info!(target: "ws.capture", path = %state.path.display(), messages = state.messages, "WS capture closed");
+    Ok(())
+}
+
+/// Reads a capture written by [`WsRecorder`] back in receive order.
+pub struct WsCapture {
+    path: PathBuf,
+    lines: std::io::Lines<BufReader<zstd::stream::read::Decoder<'static, BufReader<File>>>>,
+}
+
+impl WsCapture {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("opening WS capture {}", path.display()))?;
+        let decoder = zstd::stream::read::Decoder::new(file)
+            .with_context(|| format!("reading WS capture {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            lines: BufReader::new(decoder).lines(),
+        })
+    }
+}
+
+impl Iterator for WsCapture {
+    type Item = Result<CapturedMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            // A recorder killed mid-block leaves a truncated tail; what was
+            // flushed before it is still a valid capture.
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                warn!(target: "ws.capture", path = %self.path.display(), "WS capture ends mid-frame, stopping at the last full message");
+                return None;
+            }
+            Err(err) => {
+                return Some(
+                    Err(err).with_context(|| format!("reading WS capture {}", self.path.display())),
+                )
+            }
+        };
+        Some(
+            serde_json::from_str(&line)
+                .with_context(|| format!("parsing WS capture {}", self.path.display())),
+        )
+    }
+}
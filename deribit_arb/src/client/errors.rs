@@ -0,0 +1,10 @@
+use deribit_api::ApiError;
+pub use deribit_api::{RpcError as DeribitRpcError, RpcErrorClass};
+
+/// The typed RPC error behind `err`, if it is one: an [`ApiError::Rpc`]
+/// from an HTTP call, or a [`DeribitRpcError`] answered on a WebSocket.
+pub fn rpc_error(err: &anyhow::Error) -> Option<&DeribitRpcError> {
+    err.downcast_ref::<ApiError>()
+        .and_then(ApiError::rpc)
+        .or_else(|| err.downcast_ref::<DeribitRpcError>())
+}
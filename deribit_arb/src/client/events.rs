@@ -0,0 +1,157 @@
+use super::{parse_order_book, parse_quote_from_ticker};
+use crate::model::{ComboSide, OrderBook, OrderUpdate, Quote, Trade};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::*;
+use serde::Deserialize;
+
+/// A decoded WebSocket message. Subscription acks and unrecognised channels
+/// are dropped by the client rather than surfaced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsEvent {
+    TickerUpdate {
+        instrument_name: String,
+        quote: Quote,
+    },
+    BookUpdate {
+        instrument_name: String,
+        book: OrderBook,
+    },
+    TradeUpdate {
+        trades: Vec<Trade>,
+    },
+    OrderUpdate {
+        orders: Vec<OrderUpdate>,
+    },
+    /// Exchange `heartbeat` notifications and transport ping/pong frames.
+    Heartbeat,
+    /// Sent once when the connection closes or fails; the stream ends after it.
+    Disconnected {
+        reason: String,
+    },
+}
+
+/// Decodes one text frame of a `public/subscribe` or `private/subscribe`
+/// session into a [`WsEvent`].
+pub fn parse_ws_event(text: &str) -> Option<WsEvent> {
+    let payload: serde_json::Value = serde_json::from_str(text).ok()?;
+    match payload.get("method")?.as_str()? {
+        "heartbeat" => Some(WsEvent::Heartbeat),
+        "subscription" => parse_subscription(&payload),
+        _ => None,
+    }
+}
+
+fn parse_subscription(payload: &serde_json::Value) -> Option<WsEvent> {
+    let params = payload.get("params")?;
+    let channel = params.get("channel")?.as_str()?;
+    let data = params.get("data")?;
+    let mut parts = channel.split('.');
+    match parts.next()? {
+        "ticker" => Some(WsEvent::TickerUpdate {
+            instrument_name: parts.next()?.to_string(),
+            quote: parse_quote_from_ticker(payload)?,
+        }),
+        "book" => Some(WsEvent::BookUpdate {
+            instrument_name: parts.next()?.to_string(),
+            book: parse_order_book(data)?,
+        }),
+        "trades" => {
+            let trades: Vec<TradeDto> = serde_json::from_value(data.clone()).ok()?;
+            Some(WsEvent::TradeUpdate {
+                trades: trades
+                    .into_iter()
+                    .filter_map(TradeDto::into_trade)
+                    .collect(),
+            })
+        }
+        "user" if parts.next()? == "orders" => {
+            // Instrument channels push one order, kind/currency channels a batch.
+            let orders: Vec<OrderDto> = match data {
+                serde_json::Value::Array(_) => serde_json::from_value(data.clone()).ok()?,
+                _ => vec![serde_json::from_value(data.clone()).ok()?],
+            };
+            Some(WsEvent::OrderUpdate {
+                orders: orders
+                    .into_iter()
+                    .filter_map(OrderDto::into_update)
+                    .collect(),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn side(direction: &str) -> Option<ComboSide> {
+    match direction {
+        "buy" => Some(ComboSide::Buy),
+        "sell" => Some(ComboSide::Sell),
+        _ => None,
+    }
+}
+
+fn timestamp(millis: i64) -> Option<DateTime<Utc>> {
+    DateTime::<Utc>::from_timestamp_millis(millis)
+}
+
+#[derive(Deserialize)]
+struct TradeDto {
+    trade_id: String,
+    instrument_name: String,
+    price: f64,
+    amount: f64,
+    direction: String,
+    timestamp: i64,
+    index_price: Option<f64>,
+    iv: Option<f64>,
+}
+
+impl TradeDto {
+    fn into_trade(self) -> Option<Trade> {
+        Some(Trade {
+            trade_id: self.trade_id,
+            instrument_name: self.instrument_name,
+            price: Decimal::from_f64(self.price)?,
+            amount: Decimal::from_f64(self.amount)?,
+            direction: side(&self.direction)?,
+            timestamp: timestamp(self.timestamp)?,
+            index_price: self
+                .index_price
+                .and_then(Decimal::from_f64)
+                .unwrap_or_default(),
+            iv: self.iv,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct OrderDto {
+    order_id: String,
+    instrument_name: String,
+    order_state: String,
+    direction: String,
+    price: Option<serde_json::Value>,
+    amount: f64,
+    filled_amount: f64,
+    label: Option<String>,
+    last_update_timestamp: i64,
+}
+
+impl OrderDto {
+    fn into_update(self) -> Option<OrderUpdate> {
+        Some(OrderUpdate {
+            order_id: self.order_id,
+            instrument_name: self.instrument_name,
+            state: self.order_state,
+            direction: side(&self.direction)?,
+            // Market orders report `"market_price"` instead of a number.
+            price: self
+                .price
+                .and_then(|price| price.as_f64())
+                .and_then(Decimal::from_f64),
+            amount: Decimal::from_f64(self.amount)?,
+            filled_amount: Decimal::from_f64(self.filled_amount)?,
+            label: self.label.filter(|label| !label.is_empty()),
+            timestamp: timestamp(self.last_update_timestamp)?,
+        })
+    }
+}
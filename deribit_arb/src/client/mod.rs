@@ -1,292 +1,368 @@
 use crate::config::Environment;
+use crate::metrics::metrics;
 use crate::model::{
-    ComboDefinition, ComboLeg, ComboSide, Instrument, ParsedInstrumentName, Quote, QuoteLevel,
-    SettlementCurrency,
+    BlockRfqQuote, BookSummary, CarryCurve, ComboDefinition, ComboLeg, ComboSide, Currency,
+    Instrument, OrderBook, OrderRequest, ParsedInstrumentName, Position, Quote, QuoteLevel,
+    SettlementCurrency, Trade,
 };
 use anyhow::{anyhow, Context, Result};
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use deribit_api::{
+    ApiClient, ApiError, CallObserver, GetInstruments, GetLastTradesByInstrumentAndTime, Host,
+    InstrumentKind, TradeRecord,
+};
 use futures::{SinkExt, StreamExt};
-use parking_lot::RwLock;
-use reqwest::Client as HttpClient;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
-use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio_tungstenite::connect_async;
 use tokio_tungstenite::tungstenite::protocol::Message;
 use tracing::warn;
 
-const JSON_RPC_VERSION: &str = "2.0";
+pub mod capture;
+pub mod errors;
+pub mod events;
+pub mod telemetry;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JsonRpcRequest<T> {
-    pub jsonrpc: String,
-    pub id: u64,
-    pub method: String,
-    pub params: T,
-}
+pub use capture::{CapturedMessage, WsCapture, WsRecorder, CAPTURE_SUFFIX};
+pub use deribit_api::rpc::{JsonRpcError, JsonRpcRequest, JsonRpcResponse, JSON_RPC_VERSION};
+pub use deribit_api::{Credentials as DeribitCredentials, MAX_BATCH_CALLS};
+pub use errors::{rpc_error, DeribitRpcError, RpcErrorClass};
+pub use events::{parse_ws_event, WsEvent};
+pub use telemetry::{ClientStats, ClientTelemetry, MethodStats};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JsonRpcResponse<T> {
-    pub jsonrpc: String,
-    pub id: u64,
-    pub result: Option<T>,
-    pub error: Option<JsonRpcError>,
-}
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JsonRpcError {
-    pub code: i32,
-    pub message: String,
-    #[serde(default)]
-    pub data: Option<serde_json::Value>,
-}
+/// Feeds each call into the process-wide `/metrics` registry and the
+/// client's own telemetry.
+struct TelemetryObserver(ClientTelemetry);
 
-#[derive(Debug, Clone)]
-pub struct DeribitCredentials {
-    pub client_id: String,
-    pub client_secret: String,
-}
+impl CallObserver for TelemetryObserver {
+    fn on_response(
+        &self,
+        method: &str,
+        elapsed: std::time::Duration,
+        result: Result<(), &ApiError>,
+    ) {
+        // Rejected requests are our fault, not the API's, so they stay out
+        // of the error rate the circuit breaker watches.
+        let healthy = match result {
+            Ok(()) => true,
+            Err(err) => err.rpc().map(|rpc| rpc.class) == Some(RpcErrorClass::Validation),
+        };
+        metrics().record_api_call(method, elapsed, healthy);
+        self.0.record_call(method, elapsed, result.is_ok());
+    }
 
-#[derive(Debug, Clone)]
-struct AccessToken {
-    token: String,
-    expires_at: DateTime<Utc>,
+    fn on_backoff(&self, _method: &str, wait: std::time::Duration, err: &ApiError) {
+        if err.is_rate_limited() {
+            self.0.record_rate_limit_wait(wait);
+        }
+    }
+
+    fn on_credits(&self, remaining: u64) {
+        self.0.record_credits(remaining);
+    }
 }
 
+/// Deribit's HTTP API in this crate's model types, over the shared
+/// [`ApiClient`] transport.
 #[derive(Debug)]
 pub struct DeribitHttpClient {
-    http: HttpClient,
-    environment: Environment,
-    credentials: Option<DeribitCredentials>,
-    token: Arc<RwLock<Option<AccessToken>>>,
+    api: ApiClient,
+    telemetry: ClientTelemetry,
 }
 
 impl DeribitHttpClient {
     pub fn new(environment: Environment, credentials: Option<DeribitCredentials>) -> Self {
-        let http = HttpClient::builder()
-            .user_agent("deribit_arb/0.1")
-            .build()
-            .expect("failed to build http client");
+        let telemetry = ClientTelemetry::default();
         Self {
-            http,
-            environment,
-            credentials,
-            token: Arc::new(RwLock::new(None)),
+            api: ApiClient::new(environment.host())
+                .with_user_agent("deribit_arb/0.1")
+                .with_credentials(credentials)
+                .with_observer(TelemetryObserver(telemetry.clone())),
+            telemetry,
         }
     }
 
+    /// Sends every call to `base_url` instead, e.g. a local mock server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.api = self.api.with_base_url(base_url);
+        self
+    }
+
+    /// Sends every call to `host`, e.g. the history host for expired books.
+    pub fn with_host(mut self, host: Host) -> Self {
+        self.api = self.api.with_host(host);
+        self
+    }
+
+    /// Call counts, latency percentiles and rate-limit waits so far.
+    pub fn stats(&self) -> ClientStats {
+        self.telemetry.stats()
+    }
+
+    /// A handle on this client's telemetry that outlives borrows of it.
+    pub fn telemetry(&self) -> ClientTelemetry {
+        self.telemetry.clone()
+    }
+
+    /// Sends one JSON-RPC call, backing off and retrying when Deribit
+    /// answers `too_many_requests`, or an engine error on a public call.
+    /// Private calls may place orders, so an ambiguous engine failure there
+    /// is surfaced rather than resent.
     async fn call<T: Serialize + ?Sized, R: DeserializeOwned>(
         &self,
         method: &str,
         params: &T,
-        private: bool,
     ) -> Result<R> {
-        let call_id = rand::random::<u64>();
-        let mut body = json!({
-            "jsonrpc": JSON_RPC_VERSION,
-            "id": call_id,
-            "method": method,
-            "params": serde_json::to_value(params)?,
-        });
-
-        if private {
-            let token = self.ensure_token().await?;
-            body.as_object_mut()
-                .context("expected request object")?
-                .entry("params")
-                .or_insert_with(|| json!({}))
-                .as_object_mut()
-                .unwrap()
-                .insert("access_token".to_string(), json!(token));
-        }
+        Ok(self.api.call(method, params).await?)
+    }
 
-        let url = self.environment.http_base();
-        let res = self
-            .http
-            .post(url)
-            .json(&body)
-            .send()
-            .await
-            .with_context(|| format!("failed to call {method}"))?;
-        let status = res.status();
-        let text = res.text().await?;
-        if !status.is_success() {
-            return Err(anyhow!("HTTP {status} for {method}: {text}"));
-        }
-        let rpc: JsonRpcResponse<R> = serde_json::from_str(&text)
-            .with_context(|| format!("failed to parse response for {method}: {text}"))?;
-        if let Some(err) = rpc.error {
-            return Err(anyhow!(
-                "RPC error {method}: {} ({})",
-                err.message,
-                err.code
-            ));
-        }
-        rpc.result
-            .ok_or_else(|| anyhow!("missing result for {method}"))
+    /// Sends `params` as JSON-RPC batches of up to [`MAX_BATCH_CALLS`] calls
+    /// to `method` and returns one result per entry in `params` order, so a
+    /// call Deribit rejects does not fail the rest.
+    async fn call_batch<T: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &[T],
+    ) -> Result<Vec<Result<R>>> {
+        let results = self.api.call_batch(method, params).await?;
+        Ok(results
+            .into_iter()
+            .map(|result| result.map_err(anyhow::Error::from))
+            .collect())
     }
 
-    async fn ensure_token(&self) -> Result<String> {
-        if self.credentials.is_none() {
-            return Err(anyhow!("API key/secret required for private call"));
-        }
-        {
-            let guard = self.token.read();
-            if let Some(token) = &*guard {
-                if token.expires_at - Duration::seconds(30) > Utc::now() {
-                    return Ok(token.token.clone());
-                }
-            }
-        }
-        let creds = self.credentials.clone().unwrap();
-        let call_id = rand::random::<u64>();
-        let params = json!({
-            "grant_type": "client_credentials",
-            "client_id": creds.client_id,
-            "client_secret": creds.client_secret,
-        });
-        let body = json!({
-            "jsonrpc": JSON_RPC_VERSION,
-            "id": call_id,
-            "method": "public/auth",
-            "params": params,
-        });
-        let res = self
-            .http
-            .post(self.environment.http_base())
-            .json(&body)
-            .send()
-            .await
-            .context("auth request failed")?;
-        let status = res.status();
-        let text = res.text().await?;
-        if !status.is_success() {
-            return Err(anyhow!("auth HTTP {status}: {text}"));
-        }
-        let rpc: JsonRpcResponse<serde_json::Value> =
-            serde_json::from_str(&text).context("invalid auth response")?;
-        if let Some(err) = rpc.error {
-            return Err(anyhow!("auth error: {} ({})", err.message, err.code));
-        }
-        let result = rpc.result.ok_or_else(|| anyhow!("auth missing result"))?;
-        if let Some(access_token) = result.get("access_token").and_then(|v| v.as_str()) {
-            let expires_in = result
-                .get("expires_in")
-                .and_then(|v| v.as_i64())
-                .unwrap_or(3000);
-            let expiry = Utc::now() + Duration::seconds(expires_in.saturating_sub(30));
-            let mut guard = self.token.write();
-            *guard = Some(AccessToken {
-                token: access_token.to_string(),
-                expires_at: expiry,
-            });
-            return Ok(access_token.to_string());
-        }
-        Err(anyhow!("auth response missing access_token"))
+    /// Obtains (or reuses) an access token without making a private call.
+    pub async fn authenticate(&self) -> Result<()> {
+        Ok(self.api.authenticate().await?)
     }
 
     pub async fn get_instruments(&self, currency: &str) -> Result<Vec<Instrument>> {
-        #[derive(Deserialize)]
-        struct InstrumentDto {
-            instrument_name: String,
-            #[serde(rename = "option_type")]
-            #[allow(dead_code)]
-            option_type: Option<String>,
-            strike: f64,
-            tick_size: f64,
-            min_trade_amount: f64,
-            contract_size: f64,
-            is_combo: Option<bool>,
-            settlement_currency: String,
-            #[serde(rename = "option_kind")]
-            #[allow(dead_code)]
-            option_kind: Option<String>,
-            expiration_timestamp: i64,
-        }
+        self.fetch_instruments(currency, false).await
+    }
 
-        let params = json!({
-            "currency": currency,
-            "kind": "option",
-            "expired": false,
-        });
-        let instruments: Vec<InstrumentDto> =
-            self.call("public/get_instruments", &params, false).await?;
-        instruments
+    /// Options of the book that have already expired. Production keeps these
+    /// on the history host; point a client at [`Environment::history_host`]
+    /// to reach them.
+    pub async fn get_expired_instruments(&self, currency: &str) -> Result<Vec<Instrument>> {
+        self.fetch_instruments(currency, true).await
+    }
+
+    async fn fetch_instruments(&self, currency: &str, expired: bool) -> Result<Vec<Instrument>> {
+        let request = GetInstruments {
+            currency: currency.to_string(),
+            kind: InstrumentKind::Option,
+            expired,
+        };
+        let records = self.api.request(&request).await?;
+        records
             .into_iter()
-            .map(|dto| {
-                let parsed = ParsedInstrumentName::from_str(&dto.instrument_name)?;
-                let expiry = DateTime::<Utc>::from_timestamp(dto.expiration_timestamp / 1000, 0)
+            .filter_map(|record| match ParsedInstrumentName::from_str(&record.instrument_name) {
+                Ok(parsed) => Some((record, parsed)),
+                Err(err) => {
+                    warn!(instrument = %record.instrument_name, error = %err, "skipping unparseable instrument");
+                    None
+                }
+            })
+            .map(|(record, parsed)| {
+                let expiry = record
+                    .expiration_timestamp
+                    .and_then(|ms| DateTime::<Utc>::from_timestamp(ms / 1000, 0))
                     .ok_or_else(|| anyhow!("invalid timestamp"))?;
+                let settlement = record.settlement_currency.unwrap_or_default();
+                let decimal = |value: Option<f64>, default| {
+                    value.and_then(Decimal::from_f64).unwrap_or(default)
+                };
                 Ok(Instrument {
-                    instrument_name: dto.instrument_name,
                     currency: parsed.currency,
-                    is_usdc_settled: dto.settlement_currency.eq_ignore_ascii_case("usdc"),
-                    is_combo: dto.is_combo.unwrap_or(false),
+                    is_usdc_settled: settlement.eq_ignore_ascii_case("usdc"),
+                    is_combo: record.is_combo.unwrap_or(false),
                     option_kind: parsed.option_kind,
-                    strike: Decimal::from_f64(dto.strike).unwrap_or_default(),
+                    strike: parsed.strike,
                     expiry,
-                    contract_size: Decimal::from_f64(dto.contract_size).unwrap_or(dec!(1)),
-                    settlement_currency: if dto.settlement_currency.eq_ignore_ascii_case("usdc") {
-                        SettlementCurrency::Usdc
-                    } else {
-                        SettlementCurrency::Coin
-                    },
-                    tick_size: Decimal::from_f64(dto.tick_size).unwrap_or(dec!(0.1)),
-                    min_trade_amount: Decimal::from_f64(dto.min_trade_amount).unwrap_or(dec!(1)),
+                    contract_size: decimal(record.contract_size, dec!(1)),
+                    settlement_currency: SettlementCurrency::from_stablecoin(&settlement)
+                        .unwrap_or(parsed.settlement),
+                    tick_size: decimal(record.tick_size, dec!(0.1)),
+                    min_trade_amount: decimal(record.min_trade_amount, dec!(1)),
+                    instrument_name: record.instrument_name,
                 })
             })
             .collect()
     }
 
     pub async fn get_ticker(&self, instrument_name: &str) -> Result<Quote> {
+        let params = json!({ "instrument_name": instrument_name });
+        let dto: TickerDto = self.call("public/ticker", &params).await?;
+        dto.into_quote()
+    }
+
+    /// Tickers of `instrument_names` in as few round trips as
+    /// [`MAX_BATCH_CALLS`] allows, in order; an unknown instrument fails
+    /// only its own entry.
+    pub async fn get_tickers(&self, instrument_names: &[String]) -> Result<Vec<Result<Quote>>> {
+        let params: Vec<_> = instrument_names
+            .iter()
+            .map(|name| json!({ "instrument_name": name }))
+            .collect();
+        let dtos: Vec<Result<TickerDto>> = self.call_batch("public/ticker", &params).await?;
+        Ok(dtos
+            .into_iter()
+            .map(|dto| dto.and_then(TickerDto::into_quote))
+            .collect())
+    }
+
+    /// Up to `depth` price levels per side, best first.
+    pub async fn get_order_book(&self, instrument_name: &str, depth: u32) -> Result<OrderBook> {
+        let params = json!({ "instrument_name": instrument_name, "depth": depth });
+        let result: serde_json::Value = self.call("public/get_order_book", &params).await?;
+        parse_order_book(&result)
+            .ok_or_else(|| anyhow!("malformed order book for {instrument_name}"))
+    }
+
+    /// Current value of the currency's Deribit price index.
+    pub async fn get_index_price(&self, currency: Currency) -> Result<Decimal> {
+        self.index_price(&currency.index_name()).await
+    }
+
+    /// Current `eur_usd` index, the USD value of one euro; EURR books are
+    /// converted to USD through it.
+    pub async fn get_eur_usd(&self) -> Result<Decimal> {
+        self.index_price("eur_usd").await
+    }
+
+    async fn index_price(&self, index_name: &str) -> Result<Decimal> {
         #[derive(Deserialize)]
-        struct TickerDto {
-            best_bid_price: Option<f64>,
-            best_bid_amount: Option<f64>,
-            best_ask_price: Option<f64>,
-            best_ask_amount: Option<f64>,
-            mark_iv: Option<f64>,
-            bid_iv: Option<f64>,
-            ask_iv: Option<f64>,
-            interest_rate: Option<f64>,
-            #[serde(rename = "instrument_name")]
-            #[allow(dead_code)]
-            instrument_name: Option<String>,
-            timestamp: i64,
+        struct IndexPriceDto {
             index_price: f64,
         }
+        let params = json!({ "index_name": index_name });
+        let dto: IndexPriceDto = self.call("public/get_index_price", &params).await?;
+        Decimal::from_f64(dto.index_price)
+            .filter(|price| !price.is_zero())
+            .ok_or_else(|| anyhow!("invalid index price for {index_name}: {}", dto.index_price))
+    }
 
-        let params = json!({ "instrument_name": instrument_name });
-        let dto: TickerDto = self.call("public/ticker", &params, false).await?;
-        let timestamp = DateTime::<Utc>::from_timestamp(dto.timestamp / 1000, 0)
-            .ok_or_else(|| anyhow!("invalid ticker timestamp"))?;
-        let best_bid = dto
-            .best_bid_price
-            .zip(dto.best_bid_amount)
-            .map(|(price, amount)| QuoteLevel {
-                price: Decimal::from_f64(price).unwrap_or_default(),
-                amount: Decimal::from_f64(amount).unwrap_or_default(),
-            });
-        let best_ask = dto
-            .best_ask_price
-            .zip(dto.best_ask_amount)
-            .map(|(price, amount)| QuoteLevel {
-                price: Decimal::from_f64(price).unwrap_or_default(),
-                amount: Decimal::from_f64(amount).unwrap_or_default(),
-            });
-        Ok(Quote {
-            best_bid,
-            best_ask,
-            mark_iv: dto.mark_iv,
-            bid_iv: dto.bid_iv,
-            ask_iv: dto.ask_iv,
-            interest_rate: dto.interest_rate,
-            timestamp,
-            index_price: Decimal::from_f64(dto.index_price).unwrap_or(dec!(0)),
-        })
+    /// Daily delivery prices of the currency's index, the price expiring
+    /// options settle against, newest first. Returns up to `count` days.
+    pub async fn get_delivery_prices(
+        &self,
+        currency: Currency,
+        count: u32,
+    ) -> Result<Vec<(NaiveDate, Decimal)>> {
+        #[derive(Deserialize)]
+        struct DeliveryDto {
+            date: String,
+            delivery_price: f64,
+        }
+        #[derive(Deserialize)]
+        struct DeliveriesDto {
+            data: Vec<DeliveryDto>,
+        }
+        let params = json!({ "index_name": currency.index_name(), "count": count });
+        let dto: DeliveriesDto = self.call("public/get_delivery_prices", &params).await?;
+        dto.data
+            .into_iter()
+            .map(|delivery| {
+                let date = NaiveDate::parse_from_str(&delivery.date, "%Y-%m-%d")
+                    .with_context(|| format!("invalid delivery date {}", delivery.date))?;
+                let price = Decimal::from_f64(delivery.delivery_price).ok_or_else(|| {
+                    anyhow!(
+                        "invalid delivery price for {currency}: {}",
+                        delivery.delivery_price
+                    )
+                })?;
+                Ok((date, price))
+            })
+            .collect()
+    }
+
+    /// Public trade prints of `instrument_name` between `start` and `end`,
+    /// oldest first; at most `count` (Deribit caps it at 1000).
+    pub async fn get_last_trades_by_instrument_and_time(
+        &self,
+        instrument_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        count: u32,
+    ) -> Result<Vec<Trade>> {
+        let request = GetLastTradesByInstrumentAndTime {
+            instrument_name: instrument_name.to_string(),
+            start_timestamp: u64::try_from(start.timestamp_millis()).unwrap_or(0),
+            end_timestamp: Some(u64::try_from(end.timestamp_millis()).unwrap_or(0)),
+            count: Some(count.min(1000)),
+            include_oldest: Some(true),
+        };
+        let page = self.api.request(&request).await?;
+        page.trades
+            .into_iter()
+            .map(|record| {
+                trade_from_record(instrument_name, record)
+                    .ok_or_else(|| anyhow!("malformed trade print for {instrument_name}"))
+            })
+            .collect()
+    }
+
+    /// Hourly realized volatility (annualized, in percent), oldest first.
+    pub async fn get_historical_volatility(
+        &self,
+        currency: Currency,
+    ) -> Result<Vec<(DateTime<Utc>, f64)>> {
+        let params = json!({ "currency": currency.as_str() });
+        let result: serde_json::Value = self
+            .call("public/get_historical_volatility", &params)
+            .await?;
+        parse_historical_volatility(&result)
+            .ok_or_else(|| anyhow!("malformed historical volatility for {currency}"))
+    }
+
+    /// Latest one-minute close of the currency's DVOL index.
+    pub async fn get_dvol(&self, currency: Currency) -> Result<f64> {
+        let end = Utc::now();
+        let params = json!({
+            "currency": currency.as_str(),
+            "start_timestamp": (end - Duration::minutes(10)).timestamp_millis(),
+            "end_timestamp": end.timestamp_millis(),
+            "resolution": "60",
+        });
+        let result: serde_json::Value = self
+            .call("public/get_volatility_index_data", &params)
+            .await?;
+        parse_volatility_index(&result).ok_or_else(|| anyhow!("no DVOL data for {currency}"))
+    }
+
+    /// Dated futures basis and perpetual funding of `currency` from one
+    /// `public/get_book_summary_by_currency` call.
+    pub async fn get_carry_curve(&self, currency: Currency) -> Result<CarryCurve> {
+        let params = json!({ "currency": currency.as_str(), "kind": "future" });
+        let result: serde_json::Value = self
+            .call("public/get_book_summary_by_currency", &params)
+            .await?;
+        parse_carry_curve(&result)
+            .ok_or_else(|| anyhow!("malformed futures summary for {currency}"))
+    }
+
+    /// Top of book for every listed option of a currency in one request.
+    pub async fn get_book_summaries(&self, currency: &str) -> Result<Vec<BookSummary>> {
+        let params = json!({ "currency": currency, "kind": "option" });
+        let result: serde_json::Value = self
+            .call("public/get_book_summary_by_currency", &params)
+            .await?;
+        parse_book_summaries(&result)
+            .ok_or_else(|| anyhow!("malformed option summary for {currency}"))
+    }
+
+    /// Cross-collateral account equity in USD, across all currencies.
+    pub async fn get_total_equity_usd(&self) -> Result<Decimal> {
+        let params = json!({ "extended": true });
+        let result: serde_json::Value = self.call("private/get_account_summaries", &params).await?;
+        parse_total_equity_usd(&result)
+            .ok_or_else(|| anyhow!("account summary has no total_equity_usd"))
     }
 
     pub async fn get_combo_ids(&self, currency: &str) -> Result<Vec<String>> {
@@ -295,68 +371,50 @@ impl DeribitHttpClient {
             combo_id: String,
         }
         let params = json!({ "currency": currency });
-        let ids: Vec<ComboIdDto> = self.call("public/get_combo_ids", &params, false).await?;
+        let ids: Vec<ComboIdDto> = self.call("public/get_combo_ids", &params).await?;
         Ok(ids.into_iter().map(|id| id.combo_id).collect())
     }
 
     pub async fn get_combo_details(&self, combo_id: &str) -> Result<ComboDefinition> {
-        #[derive(Deserialize)]
-        struct ComboDto {
-            currency: String,
-            legs: Vec<ComboLegDto>,
-            description: String,
-            settlement_currency: String,
-        }
-
-        #[derive(Deserialize)]
-        struct ComboLegDto {
-            instrument_name: String,
-            ratio: i32,
-            direction: String,
-        }
-
         let params = json!({ "combo_id": combo_id });
-        let dto: ComboDto = self
-            .call("public/get_combo_details", &params, false)
-            .await?;
-        let legs = dto
-            .legs
-            .into_iter()
-            .map(|leg| ComboLeg {
-                instrument_name: leg.instrument_name,
-                ratio: leg.ratio,
-                side: match leg.direction.as_str() {
-                    "buy" => ComboSide::Buy,
-                    "sell" => ComboSide::Sell,
-                    other => {
-                        warn!("unknown combo leg direction {other}");
-                        ComboSide::Buy
-                    }
-                },
-            })
+        let dto: ComboDto = self.call("public/get_combo_details", &params).await?;
+        dto.into_definition(combo_id)
+    }
+
+    /// ID of a listed combo of `currency` on the `settlement` book with
+    /// exactly `legs`, in any order. Details of every listed combo are
+    /// fetched in batches, so this costs a few round trips; callers cache
+    /// the answer.
+    pub async fn find_combo(
+        &self,
+        currency: Currency,
+        settlement: SettlementCurrency,
+        legs: &[ComboLeg],
+    ) -> Result<Option<String>> {
+        let ids = self.get_combo_ids(&currency.to_string()).await?;
+        let params: Vec<_> = ids
+            .iter()
+            .map(|combo_id| json!({ "combo_id": combo_id }))
             .collect();
-        Ok(ComboDefinition {
-            combo_id: Some(combo_id.to_string()),
-            currency: match dto.currency.as_str() {
-                "BTC" => crate::model::Currency::BTC,
-                "ETH" => crate::model::Currency::ETH,
-                other => return Err(anyhow!("unknown currency {other}")),
-            },
-            settlement: if dto.settlement_currency.eq_ignore_ascii_case("usdc") {
-                SettlementCurrency::Usdc
-            } else {
-                SettlementCurrency::Coin
-            },
-            description: dto.description,
-            legs,
-        })
+        let details: Vec<Result<ComboDto>> =
+            self.call_batch("public/get_combo_details", &params).await?;
+        let wanted = sorted_legs(legs);
+        for (combo_id, dto) in ids.iter().zip(details) {
+            let Ok(definition) = dto.and_then(|dto| dto.into_definition(combo_id)) else {
+                continue;
+            };
+            if definition.settlement == settlement && sorted_legs(&definition.legs) == wanted {
+                return Ok(Some(combo_id.clone()));
+            }
+        }
+        Ok(None)
     }
 
     pub async fn create_combo(
         &self,
         name: &str,
         legs: &[ComboLeg],
-        is_usdc: bool,
+        settlement: SettlementCurrency,
     ) -> Result<String> {
         #[derive(Serialize)]
         struct CreateComboLeg<'a> {
@@ -366,7 +424,7 @@ impl DeribitHttpClient {
         }
         let params = json!({
             "name": name,
-            "settlement": if is_usdc { "usdc" } else { "coin" },
+            "settlement": settlement.stablecoin().unwrap_or("coin").to_ascii_lowercase(),
             "legs": legs
                 .iter()
                 .map(|leg| CreateComboLeg {
@@ -381,9 +439,10 @@ impl DeribitHttpClient {
         });
         #[derive(Deserialize)]
         struct CreateComboResponse {
+            #[serde(alias = "id")]
             combo_id: String,
         }
-        let resp: CreateComboResponse = self.call("private/create_combo", &params, true).await?;
+        let resp: CreateComboResponse = self.call("private/create_combo", &params).await?;
         Ok(resp.combo_id)
     }
 
@@ -396,30 +455,287 @@ impl DeribitHttpClient {
             "combo_id": combo_id,
             "amount": amount,
         });
-        self.call("private/get_leg_prices", &params, true).await
+        self.call("private/get_leg_prices", &params).await
+    }
+
+    /// [`Self::get_leg_prices`] for several `(combo_id, amount)` previews
+    /// in one round trip, in order.
+    pub async fn get_leg_prices_batch(
+        &self,
+        previews: &[(String, Decimal)],
+    ) -> Result<Vec<Result<serde_json::Value>>> {
+        let params: Vec<_> = previews
+            .iter()
+            .map(|(combo_id, amount)| json!({ "combo_id": combo_id, "amount": amount }))
+            .collect();
+        self.call_batch("private/get_leg_prices", &params).await
+    }
+
+    /// Places a limit order and returns its order id.
+    pub async fn place_order(&self, order: &OrderRequest) -> Result<String> {
+        #[derive(Deserialize)]
+        struct OrderDto {
+            order_id: String,
+        }
+        #[derive(Deserialize)]
+        struct PlaceOrderResponse {
+            order: OrderDto,
+        }
+        let method = match order.side {
+            ComboSide::Buy => "private/buy",
+            ComboSide::Sell => "private/sell",
+        };
+        let mut params = json!({
+            "instrument_name": order.instrument_name,
+            "amount": order.amount,
+            "type": "limit",
+            "price": order.price,
+            "time_in_force": order.tif.as_deribit(),
+            "post_only": order.post_only,
+        });
+        if let Some(label) = &order.label {
+            params["label"] = json!(label);
+        }
+        let resp: PlaceOrderResponse = self.call(method, &params).await?;
+        Ok(resp.order.order_id)
+    }
+
+    pub async fn edit_order(&self, order_id: &str, amount: Decimal, price: Decimal) -> Result<()> {
+        let params = json!({
+            "order_id": order_id,
+            "amount": amount,
+            "price": price,
+        });
+        let _: serde_json::Value = self.call("private/edit", &params).await?;
+        Ok(())
+    }
+
+    pub async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        let params = json!({ "order_id": order_id });
+        let _: serde_json::Value = self.call("private/cancel", &params).await?;
+        Ok(())
+    }
+
+    /// Open option positions in `currency`, skipping zero-size entries.
+    pub async fn get_positions(&self, currency: &str) -> Result<Vec<Position>> {
+        let params = json!({ "currency": currency, "kind": "option" });
+        let positions: Vec<Position> = self.call("private/get_positions", &params).await?;
+        Ok(positions
+            .into_iter()
+            .filter(|position| !position.size.is_zero())
+            .collect())
+    }
+
+    /// Opens a block RFQ to buy `amount` of the combo and returns its id.
+    pub async fn create_block_rfq(&self, legs: &[ComboLeg], amount: Decimal) -> Result<String> {
+        #[derive(Deserialize)]
+        struct CreateBlockRfqResponse {
+            block_rfq_id: serde_json::Value,
+        }
+        let params = json!({ "legs": block_rfq_legs(legs, amount) });
+        let resp: CreateBlockRfqResponse = self.call("private/create_block_rfq", &params).await?;
+        Ok(match resp.block_rfq_id {
+            serde_json::Value::String(id) => id,
+            other => other.to_string(),
+        })
+    }
+
+    /// Maker offers (asks) currently standing on an RFQ.
+    pub async fn get_block_rfq_quotes(&self, rfq_id: &str) -> Result<Vec<BlockRfqQuote>> {
+        #[derive(Deserialize)]
+        struct QuoteDto {
+            #[serde(default)]
+            block_rfq_quote_id: Option<serde_json::Value>,
+            price: f64,
+            amount: f64,
+        }
+        #[derive(Deserialize)]
+        struct BlockRfqDto {
+            #[serde(default)]
+            asks: Vec<QuoteDto>,
+        }
+        let params = json!({ "block_rfq_id": rfq_id });
+        let rfqs: Vec<BlockRfqDto> = self.call("private/get_block_rfqs", &params).await?;
+        Ok(rfqs
+            .into_iter()
+            .flat_map(|rfq| rfq.asks)
+            .map(|dto| BlockRfqQuote {
+                quote_id: dto.block_rfq_quote_id.map(|id| match id {
+                    serde_json::Value::String(id) => id,
+                    other => other.to_string(),
+                }),
+                price: Decimal::from_f64(dto.price).unwrap_or_default(),
+                amount: Decimal::from_f64(dto.amount).unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// Fill-or-kill acceptance of a quote; returns the exchange's response.
+    pub async fn accept_block_rfq(
+        &self,
+        rfq_id: &str,
+        legs: &[ComboLeg],
+        price: Decimal,
+        amount: Decimal,
+    ) -> Result<serde_json::Value> {
+        let params = json!({
+            "block_rfq_id": rfq_id,
+            "legs": block_rfq_legs(legs, amount),
+            "direction": "buy",
+            "price": price,
+            "amount": amount,
+            "time_in_force": "fill_or_kill",
+        });
+        self.call("private/accept_block_rfq", &params).await
+    }
+
+    pub async fn cancel_block_rfq(&self, rfq_id: &str) -> Result<()> {
+        let params = json!({ "block_rfq_id": rfq_id });
+        let _: serde_json::Value = self.call("private/cancel_block_rfq", &params).await?;
+        Ok(())
     }
 }
 
+fn block_rfq_legs(legs: &[ComboLeg], amount: Decimal) -> Vec<serde_json::Value> {
+    legs.iter()
+        .map(|leg| {
+            json!({
+                "instrument_name": leg.instrument_name,
+                "amount": amount * Decimal::from(leg.ratio),
+                "direction": match leg.side {
+                    ComboSide::Buy => "buy",
+                    ComboSide::Sell => "sell",
+                },
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct TickerDto {
+    best_bid_price: Option<f64>,
+    best_bid_amount: Option<f64>,
+    best_ask_price: Option<f64>,
+    best_ask_amount: Option<f64>,
+    mark_iv: Option<f64>,
+    bid_iv: Option<f64>,
+    ask_iv: Option<f64>,
+    interest_rate: Option<f64>,
+    timestamp: i64,
+    index_price: f64,
+}
+
+impl TickerDto {
+    fn into_quote(self) -> Result<Quote> {
+        let timestamp = DateTime::<Utc>::from_timestamp(self.timestamp / 1000, 0)
+            .ok_or_else(|| anyhow!("invalid ticker timestamp"))?;
+        let level = |(price, amount): (f64, f64)| QuoteLevel {
+            price: Decimal::from_f64(price).unwrap_or_default(),
+            amount: Decimal::from_f64(amount).unwrap_or_default(),
+        };
+        Ok(Quote {
+            best_bid: self.best_bid_price.zip(self.best_bid_amount).map(level),
+            best_ask: self.best_ask_price.zip(self.best_ask_amount).map(level),
+            mark_iv: self.mark_iv,
+            bid_iv: self.bid_iv,
+            ask_iv: self.ask_iv,
+            interest_rate: self.interest_rate,
+            timestamp,
+            index_price: Decimal::from_f64(self.index_price).unwrap_or(dec!(0)),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ComboDto {
+    currency: String,
+    legs: Vec<ComboLegDto>,
+    description: String,
+    settlement_currency: String,
+}
+
+#[derive(Deserialize)]
+struct ComboLegDto {
+    instrument_name: String,
+    ratio: i32,
+    direction: String,
+}
+
+impl ComboDto {
+    fn into_definition(self, combo_id: &str) -> Result<ComboDefinition> {
+        let legs = self
+            .legs
+            .into_iter()
+            .map(|leg| ComboLeg {
+                instrument_name: leg.instrument_name,
+                ratio: leg.ratio,
+                side: match leg.direction.as_str() {
+                    "buy" => ComboSide::Buy,
+                    "sell" => ComboSide::Sell,
+                    other => {
+                        warn!("unknown combo leg direction {other}");
+                        ComboSide::Buy
+                    }
+                },
+            })
+            .collect();
+        Ok(ComboDefinition {
+            combo_id: Some(combo_id.to_string()),
+            currency: self
+                .currency
+                .parse()
+                .map_err(|_| anyhow!("unknown currency {}", self.currency))?,
+            settlement: SettlementCurrency::from_stablecoin(&self.settlement_currency)
+                .unwrap_or(SettlementCurrency::Coin),
+            description: self.description,
+            legs,
+        })
+    }
+}
+
+fn sorted_legs(legs: &[ComboLeg]) -> Vec<(&str, i32, ComboSide)> {
+    let mut legs: Vec<_> = legs
+        .iter()
+        .map(|leg| (leg.instrument_name.as_str(), leg.ratio, leg.side))
+        .collect();
+    legs.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+    legs
+}
+
 #[derive(Debug)]
 pub struct DeribitWsClient {
     environment: Environment,
+    recorder: Option<WsRecorder>,
 }
 
 impl DeribitWsClient {
     pub fn new(environment: Environment) -> Self {
-        Self { environment }
+        Self {
+            environment,
+            recorder: None,
+        }
+    }
+
+    /// Writes every text frame received on this client's connections to
+    /// `recorder`, before it is parsed.
+    pub fn with_recorder(mut self, recorder: WsRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
     }
 
+    /// Subscribes to `subscriptions` on a fresh connection and streams decoded
+    /// events. The stream ends after a [`WsEvent::Disconnected`].
     pub async fn subscribe(
         &self,
         subscriptions: &[String],
-    ) -> Result<mpsc::UnboundedReceiver<serde_json::Value>> {
+    ) -> Result<mpsc::UnboundedReceiver<WsEvent>> {
         let url = self.environment.websocket_url();
         let (ws_stream, _) = connect_async(url)
             .await
             .context("failed to connect websocket")?;
         let channels: Vec<String> = subscriptions.to_vec();
         let (out_tx, out_rx) = mpsc::unbounded_channel();
+        let recorder = self.recorder.clone();
 
         tokio::spawn(async move {
             let (mut writer, mut reader) = ws_stream.split();
@@ -439,50 +755,182 @@ impl DeribitWsClient {
             };
             if let Err(err) = writer.send(Message::text(payload)).await {
                 warn!("ws_write_error" = %err, "failed to send subscribe request");
+                let _ = out_tx.send(WsEvent::Disconnected {
+                    reason: format!("subscribe failed: {err}"),
+                });
                 return;
             }
 
-            while let Some(msg) = reader.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
-                            let _ = out_tx.send(value);
-                        }
-                    }
+            let reason = loop {
+                // Short-lived subscriptions close their connection by dropping
+                // the receiver.
+                let msg = tokio::select! {
+                    msg = reader.next() => msg,
+                    _ = out_tx.closed() => break "receiver dropped".to_string(),
+                };
+                let Some(msg) = msg else {
+                    break "stream ended".to_string();
+                };
+                let text = match &msg {
+                    Ok(Message::Text(text)) => Some(text.as_str()),
+                    Ok(Message::Binary(bin)) => std::str::from_utf8(bin).ok(),
+                    _ => None,
+                };
+                if let (Some(recorder), Some(text)) = (&recorder, text) {
+                    recorder.record(Utc::now(), text);
+                }
+                let event = match msg {
+                    Ok(Message::Text(text)) => parse_ws_event(&text),
                     Ok(Message::Binary(bin)) => {
-                        if let Ok(text) = String::from_utf8(bin) {
-                            if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
-                                let _ = out_tx.send(value);
-                            }
-                        }
-                    }
-                    Ok(Message::Ping(payload)) => {
-                        let rendered = String::from_utf8_lossy(&payload).to_string();
-                        let _ = out_tx.send(json!({ "type": "ping", "payload": rendered }));
-                    }
-                    Ok(Message::Pong(payload)) => {
-                        let rendered = String::from_utf8_lossy(&payload).to_string();
-                        let _ = out_tx.send(json!({ "type": "pong", "payload": rendered }));
+                        std::str::from_utf8(&bin).ok().and_then(parse_ws_event)
                     }
+                    Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => Some(WsEvent::Heartbeat),
                     Ok(Message::Close(frame)) => {
-                        let payload = frame
-                            .as_ref()
-                            .map(|f| format!("{:?}", f))
-                            .unwrap_or_else(|| "None".to_string());
-                        let _ = out_tx.send(json!({ "type": "close", "payload": payload }));
-                        break;
+                        break frame
+                            .map(|f| format!("closed by server: {} {}", f.code, f.reason))
+                            .unwrap_or_else(|| "closed by server".to_string());
                     }
-                    Ok(Message::Frame(_)) => {}
+                    Ok(Message::Frame(_)) => None,
                     Err(err) => {
                         warn!("ws_read_error" = %err, "websocket read error");
-                        break;
+                        break format!("read error: {err}");
                     }
+                };
+                if let Some(event) = event {
+                    let _ = out_tx.send(event);
                 }
-            }
+            };
+            let _ = out_tx.send(WsEvent::Disconnected { reason });
         });
 
         Ok(out_rx)
     }
+
+    /// Opens an authenticated session with Deribit's cancel-on-disconnect
+    /// enabled for its connection, so every open order of the account is
+    /// cancelled if this process dies or loses the link. The session answers
+    /// heartbeat test requests every `heartbeat_secs`; the returned stream
+    /// ends with [`WsEvent::Disconnected`] once the connection drops, after
+    /// which Deribit has already cancelled the orders.
+    pub async fn open_safety_session(
+        &self,
+        credentials: &DeribitCredentials,
+        heartbeat_secs: u64,
+    ) -> Result<mpsc::UnboundedReceiver<WsEvent>> {
+        let url = self.environment.websocket_url();
+        let (mut ws_stream, _) = connect_async(url)
+            .await
+            .context("failed to connect websocket")?;
+        ws_request(
+            &mut ws_stream,
+            "public/auth",
+            json!({
+                "grant_type": "client_credentials",
+                "client_id": credentials.client_id,
+                "client_secret": credentials.client_secret,
+            }),
+        )
+        .await?;
+        ws_request(
+            &mut ws_stream,
+            "private/enable_cancel_on_disconnect",
+            json!({ "scope": "connection" }),
+        )
+        .await?;
+        ws_request(
+            &mut ws_stream,
+            "public/set_heartbeat",
+            json!({ "interval": heartbeat_secs }),
+        )
+        .await?;
+
+        let (out_tx, out_rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (mut writer, mut reader) = ws_stream.split();
+            let reason = loop {
+                let Some(msg) = reader.next().await else {
+                    break "stream ended".to_string();
+                };
+                let text = match msg {
+                    Ok(Message::Text(text)) => text,
+                    Ok(Message::Close(frame)) => {
+                        break frame
+                            .map(|f| format!("closed by server: {} {}", f.code, f.reason))
+                            .unwrap_or_else(|| "closed by server".to_string());
+                    }
+                    Ok(_) => continue,
+                    Err(err) => break format!("read error: {err}"),
+                };
+                if is_test_request(&text) {
+                    let reply = json!({
+                        "jsonrpc": JSON_RPC_VERSION,
+                        "id": rand::random::<u64>(),
+                        "method": "public/test",
+                        "params": {},
+                    });
+                    if let Err(err) = writer.send(Message::text(reply.to_string())).await {
+                        break format!("heartbeat reply failed: {err}");
+                    }
+                }
+                if let Some(event) = parse_ws_event(&text) {
+                    let _ = out_tx.send(event);
+                }
+            };
+            let _ = out_tx.send(WsEvent::Disconnected { reason });
+        });
+        Ok(out_rx)
+    }
+}
+
+/// Sends one JSON-RPC request on `ws` and waits for its response, skipping
+/// any notifications that arrive first.
+async fn ws_request(
+    ws: &mut WsStream,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value> {
+    let call_id = rand::random::<u64>();
+    let request = JsonRpcRequest {
+        jsonrpc: JSON_RPC_VERSION.to_string(),
+        id: call_id,
+        method: method.to_string(),
+        params,
+    };
+    ws.send(Message::text(serde_json::to_string(&request)?))
+        .await
+        .with_context(|| format!("failed to send {method}"))?;
+    while let Some(msg) = ws.next().await {
+        let Message::Text(text) =
+            msg.with_context(|| format!("websocket error awaiting {method}"))?
+        else {
+            continue;
+        };
+        let Ok(rpc) = serde_json::from_str::<JsonRpcResponse<serde_json::Value>>(&text) else {
+            continue;
+        };
+        if rpc.id != call_id {
+            continue;
+        }
+        if let Some(err) = rpc.error {
+            return Err(DeribitRpcError::new(method, err).into());
+        }
+        return rpc
+            .result
+            .ok_or_else(|| anyhow!("missing result for {method}"));
+    }
+    Err(anyhow!("websocket closed awaiting {method}"))
+}
+
+fn is_test_request(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|payload| {
+            Some(
+                payload.get("method")?.as_str()? == "heartbeat"
+                    && payload.get("params")?.get("type")?.as_str()? == "test_request",
+            )
+        })
+        .unwrap_or(false)
 }
 
 pub fn parse_quote_from_ticker(payload: &serde_json::Value) -> Option<Quote> {
@@ -526,3 +974,143 @@ pub fn parse_quote_from_ticker(payload: &serde_json::Value) -> Option<Quote> {
         index_price,
     })
 }
+
+/// Parses a `public/get_order_book` result (or `book` notification data):
+/// `bids`/`asks` as `[price, amount]` pairs and a millisecond `timestamp`.
+pub fn parse_order_book(data: &serde_json::Value) -> Option<OrderBook> {
+    let levels = |side: &str| -> Option<Vec<QuoteLevel>> {
+        data.get(side)?
+            .as_array()?
+            .iter()
+            .map(|level| {
+                let price = level.get(0)?.as_f64()?;
+                let amount = level.get(1)?.as_f64()?;
+                Some(QuoteLevel {
+                    price: Decimal::from_f64(price)?,
+                    amount: Decimal::from_f64(amount)?,
+                })
+            })
+            .collect()
+    };
+    let timestamp = data
+        .get("timestamp")
+        .and_then(|t| t.as_i64())
+        .and_then(DateTime::<Utc>::from_timestamp_millis)
+        .unwrap_or_else(Utc::now);
+    Some(OrderBook {
+        bids: levels("bids")?,
+        asks: levels("asks")?,
+        timestamp,
+    })
+}
+
+fn trade_from_record(instrument_name: &str, record: TradeRecord) -> Option<Trade> {
+    Some(Trade {
+        trade_id: record.trade_id,
+        instrument_name: record
+            .instrument_name
+            .unwrap_or_else(|| instrument_name.to_string()),
+        price: Decimal::from_f64(record.price)?,
+        amount: Decimal::from_f64(record.amount)?,
+        direction: match record.direction.as_str() {
+            "buy" => ComboSide::Buy,
+            "sell" => ComboSide::Sell,
+            _ => return None,
+        },
+        timestamp: DateTime::<Utc>::from_timestamp_millis(i64::try_from(record.timestamp).ok()?)?,
+        index_price: record
+            .index_price
+            .and_then(Decimal::from_f64)
+            .unwrap_or_default(),
+        iv: record.iv,
+    })
+}
+
+/// Parses a `public/get_historical_volatility` result: `[timestamp_ms, vol]`
+/// pairs.
+pub fn parse_historical_volatility(data: &serde_json::Value) -> Option<Vec<(DateTime<Utc>, f64)>> {
+    data.as_array()?
+        .iter()
+        .map(|point| {
+            let at = DateTime::<Utc>::from_timestamp_millis(point.get(0)?.as_i64()?)?;
+            Some((at, point.get(1)?.as_f64()?))
+        })
+        .collect()
+}
+
+/// Close of the newest candle in a `public/get_volatility_index_data` result,
+/// whose `data` rows are `[timestamp_ms, open, high, low, close]`.
+pub fn parse_volatility_index(data: &serde_json::Value) -> Option<f64> {
+    data.get("data")?
+        .as_array()?
+        .iter()
+        .filter_map(|candle| Some((candle.get(0)?.as_i64()?, candle.get(4)?.as_f64()?)))
+        .max_by_key(|(at, _)| *at)
+        .map(|(_, close)| close)
+}
+
+/// Reads `total_equity_usd` from a `private/get_account_summaries` result,
+/// falling back to the first per-currency summary that carries it.
+pub fn parse_total_equity_usd(data: &serde_json::Value) -> Option<Decimal> {
+    let total = data.get("total_equity_usd").or_else(|| {
+        data.get("summaries")?
+            .as_array()?
+            .iter()
+            .find_map(|summary| summary.get("total_equity_usd"))
+    })?;
+    Decimal::from_f64(total.as_f64()?)
+}
+
+/// Parses `public/get_book_summary_by_currency` option rows. Deribit sends
+/// `null` for an empty side, which maps to `None`.
+pub fn parse_book_summaries(data: &serde_json::Value) -> Option<Vec<BookSummary>> {
+    let price = |summary: &serde_json::Value, field: &str| {
+        summary
+            .get(field)
+            .and_then(|value| value.as_f64())
+            .and_then(Decimal::from_f64)
+    };
+    data.as_array()?
+        .iter()
+        .map(|summary| {
+            Some(BookSummary {
+                instrument_name: summary.get("instrument_name")?.as_str()?.to_string(),
+                bid_price: price(summary, "bid_price"),
+                ask_price: price(summary, "ask_price"),
+                mark_price: price(summary, "mark_price"),
+            })
+        })
+        .collect()
+}
+
+/// Builds a [`CarryCurve`] from a futures `public/get_book_summary_by_currency`
+/// result. Dated futures (`BTC-27DEC24`) expire at 08:00 UTC and contribute
+/// `mark_price / estimated_delivery_price`; the perpetual's `funding_8h` is
+/// annualized.
+pub fn parse_carry_curve(data: &serde_json::Value) -> Option<CarryCurve> {
+    let mut curve = CarryCurve::default();
+    for summary in data.as_array()? {
+        let name = summary.get("instrument_name")?.as_str()?;
+        if name.ends_with("PERPETUAL") {
+            if let Some(funding_8h) = summary.get("funding_8h").and_then(|value| value.as_f64()) {
+                curve.funding_rate = Some(funding_8h * 3.0 * 365.0);
+            }
+            continue;
+        }
+        let Some((_, expiry)) = name.rsplit_once('-') else {
+            continue;
+        };
+        let Ok(date) = NaiveDate::parse_from_str(expiry, "%d%b%y") else {
+            continue;
+        };
+        let mark = summary.get("mark_price").and_then(|value| value.as_f64());
+        let index = summary
+            .get("estimated_delivery_price")
+            .and_then(|value| value.as_f64());
+        if let (Some(mark), Some(index)) = (mark, index.filter(|index| *index > 0.0)) {
+            let expiry = date.and_hms_opt(8, 0, 0)?.and_utc();
+            curve.futures.push((expiry, mark / index));
+        }
+    }
+    Some(curve)
+}
@@ -0,0 +1,130 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Latency samples kept per method for percentiles; older calls roll off.
+const LATENCY_WINDOW: usize = 1024;
+
+/// Per-client call accounting behind [`super::DeribitHttpClient::stats`].
+/// Unlike the process-wide `/metrics` registry it is scoped to one client,
+/// so each account's rate-limit budget is visible on its own.
+#[derive(Debug, Clone, Default)]
+pub struct ClientTelemetry {
+    inner: Arc<Mutex<TelemetryState>>,
+}
+
+#[derive(Debug, Default)]
+struct TelemetryState {
+    methods: BTreeMap<String, MethodState>,
+    rate_limit_waits: u64,
+    rate_limit_wait: Duration,
+    credits_remaining: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct MethodState {
+    calls: u64,
+    errors: u64,
+    latencies: VecDeque<Duration>,
+}
+
+/// Point-in-time copy of a client's telemetry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ClientStats {
+    pub methods: BTreeMap<String, MethodStats>,
+    /// Backoffs taken after Deribit answered `too_many_requests`.
+    pub rate_limit_waits: u64,
+    pub rate_limit_wait_ms: u64,
+    /// Last request credit balance the gateway reported, if any.
+    pub credits_remaining: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MethodStats {
+    pub calls: u64,
+    pub errors: u64,
+    /// Latency percentiles over the last calls, in milliseconds.
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+}
+
+impl ClientStats {
+    /// `(calls, errors)` summed over every method.
+    pub fn totals(&self) -> (u64, u64) {
+        self.methods
+            .values()
+            .fold((0, 0), |(calls, errors), method| {
+                (calls + method.calls, errors + method.errors)
+            })
+    }
+
+    /// The method with the highest p99 latency, for a one-line summary.
+    pub fn slowest(&self) -> Option<(&str, &MethodStats)> {
+        self.methods
+            .iter()
+            .max_by(|a, b| a.1.p99_ms.total_cmp(&b.1.p99_ms))
+            .map(|(name, stats)| (name.as_str(), stats))
+    }
+}
+
+impl ClientTelemetry {
+    pub fn record_call(&self, method: &str, elapsed: Duration, ok: bool) {
+        let mut state = self.inner.lock();
+        let entry = state.methods.entry(method.to_string()).or_default();
+        entry.calls += 1;
+        if !ok {
+            entry.errors += 1;
+        }
+        if entry.latencies.len() == LATENCY_WINDOW {
+            entry.latencies.pop_front();
+        }
+        entry.latencies.push_back(elapsed);
+    }
+
+    pub fn record_rate_limit_wait(&self, wait: Duration) {
+        let mut state = self.inner.lock();
+        state.rate_limit_waits += 1;
+        state.rate_limit_wait += wait;
+    }
+
+    pub fn record_credits(&self, remaining: u64) {
+        self.inner.lock().credits_remaining = Some(remaining);
+    }
+
+    pub fn stats(&self) -> ClientStats {
+        let state = self.inner.lock();
+        ClientStats {
+            methods: state
+                .methods
+                .iter()
+                .map(|(name, method)| {
+                    let mut sorted: Vec<Duration> = method.latencies.iter().copied().collect();
+                    sorted.sort();
+                    let stats = MethodStats {
+                        calls: method.calls,
+                        errors: method.errors,
+                        p50_ms: percentile_ms(&sorted, 0.50),
+                        p90_ms: percentile_ms(&sorted, 0.90),
+                        p99_ms: percentile_ms(&sorted, 0.99),
+                    };
+                    (name.clone(), stats)
+                })
+                .collect(),
+            rate_limit_waits: state.rate_limit_waits,
+            rate_limit_wait_ms: state.rate_limit_wait.as_millis() as u64,
+            credits_remaining: state.credits_remaining,
+        }
+    }
+}
+
+/// Nearest-rank percentile of ascending `sorted` samples.
+fn percentile_ms(sorted: &[Duration], quantile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((quantile * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1].as_secs_f64() * 1e3
+}
@@ -1,11 +1,25 @@
-use crate::model::{Currency, SettlementCurrency, StrategyFilter, StrategyKind};
-use anyhow::{anyhow, Result};
-use clap::Parser;
+use crate::fees::FeeSchedule;
+use crate::model::{
+    Currency, GroupKey, InstrumentFilter, MinEdgeRequirements, OpportunityView, SettlementCurrency,
+    SortKey, StrategyFilter, StrategyKind, StrategyThresholds,
+};
+use crate::notify::NotifyFilter;
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use deribit_api::Host;
+use optstore::block::BlockLayout;
 use rust_decimal::Decimal;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::ffi::OsString;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use tracing::info;
+use url::Url;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum Environment {
@@ -21,10 +35,117 @@ impl Environment {
         }
     }
 
-    pub fn http_base(&self) -> &'static str {
+    /// HTTP API host of this environment's books.
+    pub fn host(&self) -> Host {
         match self {
-            Environment::Testnet => "https://test.deribit.com/api/v2",
-            Environment::Production => "https://www.deribit.com/api/v2",
+            Environment::Testnet => Host::Testnet,
+            Environment::Production => Host::Production,
+        }
+    }
+
+    /// Host serving expired instruments and their settlement history.
+    /// Testnet has no separate history host, so it answers from its own.
+    pub fn history_host(&self) -> Host {
+        self.host().history()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum OutputFormat {
+    Table,
+    Jsonl,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "jsonl" | "json" => Ok(OutputFormat::Jsonl),
+            other => Err(anyhow!("unknown output format: {other}")),
+        }
+    }
+}
+
+/// How approved opportunities are worked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ExecutionMode {
+    /// Cross the touch with IOC combo orders.
+    Taker,
+    /// Rest post-only GTC combo orders inside the touch and reprice each cycle.
+    Maker,
+}
+
+impl FromStr for ExecutionMode {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "taker" => Ok(ExecutionMode::Taker),
+            "maker" => Ok(ExecutionMode::Maker),
+            other => Err(anyhow!("unknown execution mode: {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+    Csv,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "html" => Ok(ReportFormat::Html),
+            "md" | "markdown" => Ok(ReportFormat::Markdown),
+            "csv" => Ok(ReportFormat::Csv),
+            other => Err(anyhow!("unknown report format: {other}")),
+        }
+    }
+}
+
+/// File format of the per-leg fee breakdown written next to each report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FeeReportFormat {
+    Csv,
+    Parquet,
+}
+
+impl FeeReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FeeReportFormat::Csv => "csv",
+            FeeReportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl FromStr for FeeReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(FeeReportFormat::Csv),
+            "parquet" if cfg!(feature = "export-polars") => Ok(FeeReportFormat::Parquet),
+            "parquet" => Err(anyhow!(
+                "parquet fee reports need a build with the `export-polars` feature"
+            )),
+            other => Err(anyhow!("unknown fee report format: {other}")),
         }
     }
 }
@@ -32,6 +153,21 @@ impl Environment {
 #[derive(Debug, Parser, Clone)]
 #[command(name = "deribit_arb", author, version, about = "Deribit options micro-arbitrage scanner", long_about = None)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// TOML file with named profiles; explicit flags and env vars override it
+    #[arg(long, env = "ARB_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Profile to load from `--config` (falls back to the file's `default-profile`)
+    #[arg(long, env = "ARB_PROFILE", requires = "config")]
+    pub profile: Option<String>,
+
+    /// Only log warnings and errors
+    #[arg(long, env = "QUIET")]
+    pub quiet: bool,
+
     #[arg(long, env = "DERIBIT_ENV", default_value = "test")]
     pub env: String,
 
@@ -79,6 +215,799 @@ pub struct Cli {
 
     #[arg(long, env = "MIN_DEPTH_CONTRACTS", default_value_t = 1u32)]
     pub min_depth_contracts: u32,
+
+    #[arg(long, env = "DELTA_HEDGE", default_value_t = true)]
+    pub delta_hedge: bool,
+
+    /// Per-strategy minimum net edge, e.g. `box=200,vertical=30`
+    #[arg(long, env = "STRATEGY_MIN_EDGE_USD", value_delimiter = ',')]
+    pub strategy_min_edge_usd: Vec<String>,
+
+    /// Per-strategy net edge ÷ fees ratio, e.g. `calendar=3`
+    #[arg(long, env = "STRATEGY_MIN_EDGE_RATIO", value_delimiter = ',')]
+    pub strategy_min_edge_ratio: Vec<String>,
+
+    /// Per-strategy size cap in contracts, e.g. `butterfly=5`
+    #[arg(long, env = "STRATEGY_MAX_CONTRACTS", value_delimiter = ',')]
+    pub strategy_max_contracts: Vec<String>,
+
+    /// Skip expiries closer than this many days
+    #[arg(long, env = "MIN_DTE")]
+    pub min_dte: Option<f64>,
+
+    /// Skip expiries further out than this many days
+    #[arg(long, env = "MAX_DTE")]
+    pub max_dte: Option<f64>,
+
+    /// Strike ÷ index band to scan, e.g. `0.7..1.3`
+    #[arg(long, env = "MONEYNESS_BAND")]
+    pub moneyness_band: Option<String>,
+
+    /// Only discover, subscribe to and scan these expiry dates
+    /// (`2024-12-27` or `27DEC24`)
+    #[arg(long, env = "EXPIRIES", value_delimiter = ',')]
+    pub expiries: Vec<String>,
+
+    /// Only discover, subscribe to and scan each currency's nearest N
+    /// expiries (after the other expiry filters)
+    #[arg(long, env = "NEAREST_EXPIRIES")]
+    pub nearest_expiries: Option<usize>,
+
+    /// Append detected opportunities and execution decisions to this JSONL file
+    #[arg(long, env = "AUDIT_LOG")]
+    pub audit_log: Option<PathBuf>,
+
+    /// Keep running and rescan every N seconds on streamed quotes
+    #[arg(long, env = "LOOP_INTERVAL_SECS")]
+    pub loop_interval: Option<u64>,
+
+    /// Suppress re-alerting an unchanged opportunity for this many seconds
+    #[arg(long, env = "DEDUP_COOLDOWN_SECS", default_value_t = 300u64)]
+    pub dedup_cooldown: u64,
+
+    /// Interactive terminal dashboard instead of printed tables (implies loop mode)
+    #[arg(long, env = "TUI", default_value_t = false)]
+    pub tui: bool,
+
+    /// `table` for the human view, `jsonl` for one opportunity per stdout line
+    #[arg(long, env = "OUTPUT", default_value = "table")]
+    pub output: String,
+
+    /// POST new opportunities as JSON to this URL
+    #[arg(long, env = "WEBHOOK_URL")]
+    pub webhook: Option<String>,
+
+    /// Minimum net USD edge to post to the webhook (defaults to --min-edge-usd)
+    #[arg(long, env = "WEBHOOK_MIN_EDGE_USD")]
+    pub webhook_min_edge_usd: Option<u64>,
+
+    /// Write a per-scan report into this directory
+    #[arg(long, env = "REPORT_DIR")]
+    pub report_dir: Option<PathBuf>,
+
+    /// `html`, `markdown` or `csv`
+    #[arg(long, env = "REPORT_FORMAT", default_value = "html")]
+    pub report_format: String,
+
+    /// Order printed and exported opportunities by `edge`, `bps`, `notional`
+    /// or `return` (annualized on locked capital)
+    #[arg(long, env = "SORT_BY", default_value = "edge")]
+    pub sort_by: String,
+
+    /// Only print and export these strategies
+    #[arg(long, env = "FILTER_STRATEGY", value_delimiter = ',')]
+    pub filter_strategy: Vec<String>,
+
+    /// Only print and export these currencies
+    #[arg(long, env = "FILTER_CURRENCY", value_delimiter = ',')]
+    pub filter_currency: Vec<String>,
+
+    /// Only print and export combos with a leg expiring on one of these
+    /// dates (`2024-12-27` or `27DEC24`)
+    #[arg(long, env = "FILTER_EXPIRY", value_delimiter = ',')]
+    pub filter_expiry: Vec<String>,
+
+    /// Group the printed table by `strategy` or `expiry`, closing each group
+    /// with a subtotal row (count, edge, fees and notional)
+    #[arg(long, env = "GROUP_BY")]
+    pub group_by: Option<String>,
+
+    /// Rows printed and exported per scan (default 10, all for JSONL)
+    #[arg(long, env = "TOP")]
+    pub top: Option<usize>,
+
+    /// Best detections per scan handed to execution or maker quoting
+    #[arg(long, env = "EXECUTE_TOP", default_value_t = 3)]
+    pub execute_top: usize,
+
+    /// Serve Prometheus metrics at `http://<addr>/metrics`, e.g. `127.0.0.1:9898`
+    #[arg(long, env = "METRICS_ADDR")]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// `taker` (IOC at the touch) or `maker` (resting GTC combo orders; needs loop mode)
+    #[arg(long, env = "EXECUTION_MODE", default_value = "taker")]
+    pub execution_mode: String,
+
+    /// Maker mode: ticks inside the touch to rest each combo order
+    #[arg(long, env = "MAKER_IMPROVE_TICKS", default_value_t = 1)]
+    pub maker_improve_ticks: u32,
+
+    /// Maker mode: cancel resting orders once any leg quote is older than this
+    #[arg(long, env = "MAKER_STALE_SECS", default_value_t = 10)]
+    pub maker_stale_secs: u64,
+
+    /// Also request a block RFQ for this many contracts when it exceeds screen depth
+    #[arg(long, env = "RFQ_CONTRACTS")]
+    pub rfq_contracts: Option<u32>,
+
+    /// Seconds to collect maker quotes before evaluating an RFQ
+    #[arg(long, env = "RFQ_WAIT_SECS", default_value_t = 5)]
+    pub rfq_wait_secs: u64,
+
+    /// Accept the best RFQ quote when it clears the edge threshold (ignored in dry-run)
+    #[arg(long, env = "RFQ_EXECUTE", default_value_t = false)]
+    pub rfq_execute: bool,
+
+    /// Cap on aggregate USD notional of open combos per currency
+    #[arg(long, env = "MAX_CURRENCY_NOTIONAL_USD")]
+    pub max_currency_notional: Option<u64>,
+
+    /// Cap on net USD delta of open combos per currency
+    #[arg(long, env = "MAX_NET_DELTA_USD")]
+    pub max_net_delta: Option<u64>,
+
+    /// Cap on net USD gamma (delta change per 1% move) of open combos per currency
+    #[arg(long, env = "MAX_NET_GAMMA_USD")]
+    pub max_net_gamma: Option<u64>,
+
+    /// Cap on net USD vega (per vol point) of open combos per currency
+    #[arg(long, env = "MAX_NET_VEGA_USD")]
+    pub max_net_vega: Option<u64>,
+
+    /// Stop approving new combos once realized PnL for the UTC day falls to minus this
+    #[arg(long, env = "DAILY_LOSS_LIMIT_USD")]
+    pub daily_loss_limit: Option<u64>,
+
+    /// Persist risk counters and open combos to this JSON file across restarts
+    #[arg(long, env = "RISK_STATE")]
+    pub risk_state: Option<PathBuf>,
+
+    /// Trip the circuit breaker when more than this fraction of API calls in a cycle fail
+    #[arg(long, env = "BREAKER_ERROR_RATE", default_value_t = 0.5)]
+    pub breaker_error_rate: f64,
+
+    /// Trip the circuit breaker when no quote in the chain is newer than this
+    #[arg(long, env = "BREAKER_STALE_SECS", default_value_t = 30)]
+    pub breaker_stale_secs: u64,
+
+    /// Trip the circuit breaker when index prices of one currency diverge by more than this
+    #[arg(long, env = "BREAKER_INDEX_BPS", default_value_t = 50.0)]
+    pub breaker_index_bps: f64,
+
+    /// Seconds without failures before a tripped circuit breaker re-enables execution
+    #[arg(long, env = "BREAKER_COOLDOWN_SECS", default_value_t = 300)]
+    pub breaker_cooldown_secs: u64,
+
+    /// TOML file of `[tiers.<name>]` fee rate tables
+    #[arg(long, env = "FEE_SCHEDULE")]
+    pub fee_schedule: Option<PathBuf>,
+
+    /// Fee tier to use from `--fee-schedule`; `default` is Deribit's base tier
+    #[arg(long, env = "FEE_TIER", default_value = "default")]
+    pub fee_tier: String,
+
+    /// Save the option chain to this JSON file after every scan
+    #[arg(long, env = "CHAIN_SNAPSHOT")]
+    pub chain_snapshot: Option<PathBuf>,
+
+    /// Start from the `--chain-snapshot` file instead of re-discovering the chain
+    #[arg(long, env = "WARM_START", default_value_t = false)]
+    pub warm_start: bool,
+
+    /// In loop mode, re-evaluate the strike neighbourhoods of updated quotes
+    /// every N milliseconds between full rescans
+    #[arg(long, env = "INCREMENTAL_MS")]
+    pub incremental_ms: Option<u64>,
+
+    /// Outside dry-run, hold a WebSocket session with Deribit's
+    /// cancel-on-disconnect enabled so a crash cancels all open orders
+    #[arg(long, env = "CANCEL_ON_DISCONNECT", default_value_t = true)]
+    pub cancel_on_disconnect: bool,
+
+    /// Before planning, refresh leg tickers and abort unless the re-detected
+    /// edge keeps at least this fraction of the original (0-1)
+    #[arg(long, env = "RECHECK_EDGE_FRACTION")]
+    pub recheck_edge_fraction: Option<f64>,
+
+    /// Abort a recheck when the oldest refreshed leg quote is older than this
+    #[arg(long, env = "LATENCY_BUDGET_MS", default_value_t = 1500u64)]
+    pub latency_budget_ms: u64,
+
+    /// Count why detectors drop candidates and log a per-strategy summary
+    /// after each scan
+    #[arg(long, env = "DIAGNOSE_REJECTIONS", default_value_t = false)]
+    pub diagnose_rejections: bool,
+
+    /// Append each full scan's changed quotes to optstore day files under
+    /// this directory, replayable with `backtest --data`
+    #[arg(long, env = "RECORD_DIR")]
+    pub record_dir: Option<PathBuf>,
+
+    /// Rows per optstore block in new --record-dir day files
+    #[arg(long, env = "RECORD_BLOCK_ROWS", default_value_t = optstore::block::DEFAULT_BLOCK_ROWS)]
+    pub record_block_rows: u32,
+
+    /// Byte ceiling per optstore block in new --record-dir day files
+    #[arg(long, env = "RECORD_BLOCK_BYTES", default_value_t = optstore::block::DEFAULT_BLOCK_BYTES)]
+    pub record_block_bytes: u64,
+
+    /// Record quotes to a snapshot + delta book stream (`DD.book`) instead of
+    /// full rows, with a full snapshot every N updates of an instrument
+    #[arg(long, env = "RECORD_SNAPSHOT_INTERVAL")]
+    pub record_snapshot_interval: Option<u32>,
+
+    /// Treat a currency as in a high-vol regime when its DVOL or latest hourly
+    /// realized volatility (annualized %) reaches this level
+    #[arg(long, env = "HIGH_VOL_THRESHOLD")]
+    pub high_vol_threshold: Option<f64>,
+
+    /// Multiply the strategy's min edge by this factor during high-vol regimes
+    #[arg(long, env = "HIGH_VOL_EDGE_MULTIPLIER", default_value_t = 2.0)]
+    pub high_vol_edge_multiplier: f64,
+
+    /// After a fill, follow the legs' public trades for this long to confirm
+    /// the fill and check whether the market traded through our level
+    #[arg(long, env = "ADVERSE_WINDOW_SECS", default_value_t = 10u64)]
+    pub adverse_window_secs: u64,
+
+    /// Reject combos once the estimated portfolio margin of open combos plus
+    /// the new one would exceed this fraction of account equity, e.g. `0.5`
+    #[arg(long, env = "MAX_MARGIN_UTILIZATION")]
+    pub max_margin_utilization: Option<f64>,
+
+    /// Value jelly rolls against the futures curve and perpetual funding,
+    /// dropping rolls whose credit is only carry
+    #[arg(long, env = "JELLY_CARRY", default_value_t = true)]
+    pub jelly_carry: bool,
+
+    /// Run as a daemon serving the REST control API at this address, e.g.
+    /// `127.0.0.1:9899`; implies loop mode
+    #[arg(long, env = "DAEMON_ADDR")]
+    pub daemon_addr: Option<SocketAddr>,
+
+    /// Drop candidates whose leg quotes were taken more than this many
+    /// milliseconds apart
+    #[arg(long, env = "MAX_LEG_SKEW_MS")]
+    pub max_leg_skew_ms: Option<u64>,
+
+    /// Deduct this many bps of notional from a candidate's edge for every
+    /// second its oldest leg quote has aged
+    #[arg(long, env = "STALENESS_PENALTY_BPS", default_value_t = 0.0)]
+    pub staleness_penalty_bps: f64,
+
+    /// Drop candidates whose legs' own index prices diverge from the scan's
+    /// reference index by more than this many bps
+    #[arg(long, env = "MAX_INDEX_DIVERGENCE_BPS")]
+    pub max_index_divergence_bps: Option<f64>,
+
+    /// In dry-run loops and backtests, simulate fills of every approved plan
+    /// against this much starting capital (USD) and report ledger PnL,
+    /// capital utilization and turnover
+    #[arg(long, env = "SIM_CAPITAL")]
+    pub sim_capital: Option<u64>,
+
+    /// Also write each scan's per-leg fee breakdown (`csv`, or `parquet` with the
+    /// `export-polars` feature) into --report-dir
+    #[arg(long, env = "FEE_REPORT")]
+    pub fee_report: Option<String>,
+
+    /// Loop mode: compare each scan with the previous one and emit new,
+    /// improved, degraded and gone events; edge moves below this many USD are
+    /// ignored, and the webhook only fires for new or improved combos
+    #[arg(long, env = "DIFF_MIN_CHANGE_USD")]
+    pub diff_min_change_usd: Option<u64>,
+
+    /// Drop combos returning less than this many percent a year on the
+    /// capital they lock until expiry (estimated margin, else notional)
+    #[arg(long, env = "MIN_ANNUALIZED_RETURN")]
+    pub min_annualized_return: Option<f64>,
+
+    /// Buffer streamed tickers and books between scan ticks, keeping the
+    /// latest per instrument, and write them into the chain in one batch
+    #[arg(long, env = "CONFLATE_QUOTES")]
+    pub conflate_quotes: bool,
+
+    /// Persist created combo IDs, keyed by leg signature, to this JSON file so restarts reuse them
+    #[arg(long, env = "COMBO_CACHE")]
+    pub combo_cache: Option<PathBuf>,
+
+    /// Cap on orders placed, edited or accepted per rolling minute; further orders wait for the window
+    #[arg(long, env = "MAX_ORDERS_PER_MINUTE")]
+    pub max_orders_per_minute: Option<u32>,
+
+    /// Cap on combos approved per rolling hour; further approvals wait for the window
+    #[arg(long, env = "MAX_COMBOS_PER_HOUR")]
+    pub max_combos_per_hour: Option<u32>,
+
+    /// Cap on USD notional approved per UTC day; further approvals wait for the next day
+    #[arg(long, env = "MAX_DAILY_NOTIONAL_USD")]
+    pub max_daily_notional: Option<u64>,
+
+    /// Write every raw WebSocket message, timestamped and zstd-compressed, to this file for --replay
+    #[arg(long, env = "WS_RECORD")]
+    pub record: Option<PathBuf>,
+
+    /// Replay a --record capture through the parser, chain and detectors instead of connecting
+    #[arg(long, env = "WS_REPLAY")]
+    pub replay: Option<PathBuf>,
+
+    /// This long after each executed or missed combo, fetch where its legs
+    /// actually traded and report whether the detected edge was real
+    #[arg(long, env = "POST_TRADE_DELAY_SECS")]
+    pub post_trade_delay_secs: Option<u64>,
+
+    /// Extra credential sets from the `--config` file's `[accounts.*]` tables
+    #[arg(skip)]
+    pub accounts: BTreeMap<String, AccountEntry>,
+
+    /// Alert routes from the `--config` file's `[notify.*]` tables
+    #[arg(skip)]
+    pub notify: BTreeMap<String, NotifyEntry>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Replay stored optstore book ticks through the detectors and report
+    /// hypothetical edge capture (scan flags such as --only still apply)
+    Backtest(BacktestArgs),
+    /// Poll exchange-wide option book summaries for crossed, locked or
+    /// through-mark books and run the detectors on just those slices
+    Sweep(SweepArgs),
+    /// Smoke-test the API credentials and endpoints the scanner relies on
+    /// (auth, instruments, ticker, WebSocket, combo create and preview)
+    Selftest(SelftestArgs),
+    /// Record raw ticker frames of the discovered instruments to daily
+    /// capture files until interrupted, following listing changes
+    Capture(CaptureArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct BacktestArgs {
+    /// optstore data directory (`<dir>/YYYY/MM/DD.opt`)
+    #[arg(long)]
+    pub data: PathBuf,
+
+    /// Replay start, `YYYY-MM-DD` or RFC 3339
+    #[arg(long)]
+    pub from: String,
+
+    /// Replay end (exclusive); a bare date includes that whole day
+    #[arg(long)]
+    pub to: String,
+
+    /// Seconds of replayed time between detector runs
+    #[arg(long, default_value_t = 60)]
+    pub step_secs: u64,
+
+    /// Load expired instruments and index delivery prices from the history
+    /// host, so replayed listings carry exchange metadata rather than
+    /// defaults reconstructed from their names, and past expiries report
+    /// the price they settled at
+    #[arg(long)]
+    pub research: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SweepArgs {
+    /// Seconds between book-summary polls
+    #[arg(long, default_value_t = 3)]
+    pub interval_secs: u64,
+
+    /// Run a single pass and exit
+    #[arg(long)]
+    pub once: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SelftestArgs {
+    /// Also send a one-tick IOC bid and rest and cancel a one-tick post-only
+    /// bid for the minimum amount; testnet only
+    #[arg(long)]
+    pub orders: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CaptureArgs {
+    /// Directory of daily captures (`<dir>/YYYY-MM-DD.jsonl.zst`), rotated
+    /// at midnight UTC and replayable with --replay
+    #[arg(long)]
+    pub dir: PathBuf,
+
+    /// Delete captures last written more than this many days ago
+    #[arg(long)]
+    pub max_age_days: Option<u64>,
+
+    /// Seconds between instrument listing checks; new or expired listings
+    /// restart the subscription
+    #[arg(long, default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+    pub relist_secs: u64,
+
+    /// Serve `GET /health` with the last tick age per instrument on this
+    /// address
+    #[arg(long)]
+    pub health_addr: Option<SocketAddr>,
+}
+
+impl Cli {
+    /// Parses process arguments and layers the selected config-file profile
+    /// underneath them. Exits on `--help` or usage errors like `Cli::parse`.
+    pub fn parse_layered() -> Result<Self> {
+        Self::layered(&Self::command().get_matches())
+    }
+
+    pub fn try_parse_layered_from<I, T>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        Self::layered(&Self::command().try_get_matches_from(args)?)
+    }
+
+    /// Builds the CLI from matches parsed elsewhere, e.g. by a binary that
+    /// embeds these arguments as a subcommand, and layers the profile.
+    pub fn layered(matches: &ArgMatches) -> Result<Self> {
+        let mut cli = Self::from_arg_matches(matches)?;
+        if let Some(path) = cli.config.clone() {
+            let file = ConfigFile::load(&path)?;
+            let profile = file.profile(cli.profile.as_deref())?;
+            profile.apply(&mut cli, matches);
+            cli.accounts = file.accounts;
+            cli.notify = file.notify;
+        }
+        Ok(cli)
+    }
+}
+
+/// On-disk `--config` file: a set of named profiles mapping onto [`Cli`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigFile {
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, AccountEntry>,
+    #[serde(default)]
+    pub notify: BTreeMap<String, NotifyEntry>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("reading config file {}", path.display()))?;
+        Self::from_toml(&raw).with_context(|| format!("parsing config file {}", path.display()))
+    }
+
+    pub fn from_toml(raw: &str) -> Result<Self> {
+        Ok(toml::from_str(raw)?)
+    }
+
+    pub fn profile(&self, name: Option<&str>) -> Result<&Profile> {
+        let name = name
+            .or(self.default_profile.as_deref())
+            .ok_or_else(|| anyhow!("no --profile given and config has no default-profile"))?;
+        self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            anyhow!("unknown profile {name} (available: {})", known.join(", "))
+        })
+    }
+}
+
+/// One `[accounts.<label>]` table. Secrets stay in the environment; the
+/// table only names the variables that hold them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AccountEntry {
+    /// Defaults to the selected profile's `env`.
+    pub env: Option<String>,
+    pub key_env: String,
+    pub secret_env: String,
+    #[serde(default)]
+    pub read_only: bool,
+    /// Strategies whose combos are executed on this account.
+    #[serde(default)]
+    pub strategies: Vec<String>,
+}
+
+/// One `[notify.<label>]` table. Bot tokens and webhook URLs stay in the
+/// environment; the table only names the variables that hold them.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct NotifyEntry {
+    /// `telegram`, `slack` or `webhook`.
+    pub kind: String,
+    /// Telegram: variable holding the bot token.
+    pub token_env: Option<String>,
+    /// Telegram: chat or channel to message.
+    pub chat_id: Option<String>,
+    /// Telegram: Bot API host; defaults to `https://api.telegram.org`.
+    pub api_url: Option<String>,
+    /// Slack or webhook: variable holding the URL to post to.
+    pub url_env: Option<String>,
+    /// Webhook: URL given inline, for endpoints that are not secret.
+    pub url: Option<String>,
+    /// `opportunity`, `execution`, `risk` and/or `breaker`; all by default.
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Opportunities below this net edge are not sent.
+    pub min_edge_usd: Option<u64>,
+    /// Most opportunities sent per scan, best edge first; default 3.
+    pub top: Option<usize>,
+}
+
+/// One named profile. Keys match the long CLI flag names.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    pub env: Option<String>,
+    pub currencies: Option<Vec<String>>,
+    pub linears: Option<Vec<String>>,
+    pub dry_run: Option<bool>,
+    pub max_ticket: Option<u64>,
+    pub min_edge_usd: Option<u64>,
+    pub min_edge_ratio: Option<f64>,
+    pub hold_to_expiry: Option<bool>,
+    pub only: Option<Vec<String>>,
+    pub max_concurrent_combos: Option<u32>,
+    pub min_depth_contracts: Option<u32>,
+    pub delta_hedge: Option<bool>,
+    pub strategy_min_edge_usd: Option<BTreeMap<String, Decimal>>,
+    pub strategy_min_edge_ratio: Option<BTreeMap<String, f64>>,
+    pub strategy_max_contracts: Option<BTreeMap<String, Decimal>>,
+    pub min_dte: Option<f64>,
+    pub max_dte: Option<f64>,
+    pub moneyness_band: Option<String>,
+    pub expiries: Option<Vec<String>>,
+    pub nearest_expiries: Option<usize>,
+    pub audit_log: Option<PathBuf>,
+    pub loop_interval: Option<u64>,
+    pub dedup_cooldown: Option<u64>,
+    pub tui: Option<bool>,
+    pub output: Option<String>,
+    pub webhook: Option<String>,
+    pub webhook_min_edge_usd: Option<u64>,
+    pub report_dir: Option<PathBuf>,
+    pub report_format: Option<String>,
+    pub sort_by: Option<String>,
+    pub filter_strategy: Option<Vec<String>>,
+    pub filter_currency: Option<Vec<String>>,
+    pub filter_expiry: Option<Vec<String>>,
+    pub group_by: Option<String>,
+    pub top: Option<usize>,
+    pub execute_top: Option<usize>,
+    pub metrics_addr: Option<SocketAddr>,
+    pub execution_mode: Option<String>,
+    pub maker_improve_ticks: Option<u32>,
+    pub maker_stale_secs: Option<u64>,
+    pub rfq_contracts: Option<u32>,
+    pub rfq_wait_secs: Option<u64>,
+    pub rfq_execute: Option<bool>,
+    pub max_currency_notional: Option<u64>,
+    pub max_net_delta: Option<u64>,
+    pub max_net_gamma: Option<u64>,
+    pub max_net_vega: Option<u64>,
+    pub daily_loss_limit: Option<u64>,
+    pub risk_state: Option<PathBuf>,
+    pub breaker_error_rate: Option<f64>,
+    pub breaker_stale_secs: Option<u64>,
+    pub breaker_index_bps: Option<f64>,
+    pub breaker_cooldown_secs: Option<u64>,
+    pub fee_schedule: Option<PathBuf>,
+    pub fee_tier: Option<String>,
+    pub chain_snapshot: Option<PathBuf>,
+    pub warm_start: Option<bool>,
+    pub incremental_ms: Option<u64>,
+    pub cancel_on_disconnect: Option<bool>,
+    pub recheck_edge_fraction: Option<f64>,
+    pub latency_budget_ms: Option<u64>,
+    pub diagnose_rejections: Option<bool>,
+    pub record_dir: Option<PathBuf>,
+    pub record_block_rows: Option<u32>,
+    pub record_block_bytes: Option<u64>,
+    pub record_snapshot_interval: Option<u32>,
+    pub high_vol_threshold: Option<f64>,
+    pub high_vol_edge_multiplier: Option<f64>,
+    pub adverse_window_secs: Option<u64>,
+    pub max_margin_utilization: Option<f64>,
+    pub jelly_carry: Option<bool>,
+    pub daemon_addr: Option<SocketAddr>,
+    pub max_leg_skew_ms: Option<u64>,
+    pub staleness_penalty_bps: Option<f64>,
+    pub max_index_divergence_bps: Option<f64>,
+    pub sim_capital: Option<u64>,
+    pub fee_report: Option<String>,
+    pub diff_min_change_usd: Option<u64>,
+    pub min_annualized_return: Option<f64>,
+    pub conflate_quotes: Option<bool>,
+    pub combo_cache: Option<PathBuf>,
+    pub max_orders_per_minute: Option<u32>,
+    pub max_combos_per_hour: Option<u32>,
+    pub max_daily_notional: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub post_trade_delay_secs: Option<u64>,
+}
+
+impl Profile {
+    /// Copies profile values into `cli` for every argument the user did not
+    /// set explicitly on the command line or through its env var.
+    pub fn apply(&self, cli: &mut Cli, matches: &ArgMatches) {
+        let explicit = |id: &str| {
+            matches!(
+                matches.value_source(id),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+        macro_rules! layer {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(value) = self.$field.clone() {
+                    if !explicit(stringify!($field)) {
+                        cli.$field = value.into();
+                    }
+                }
+            )*};
+        }
+        layer!(
+            env,
+            currencies,
+            linears,
+            dry_run,
+            max_ticket,
+            min_edge_usd,
+            min_edge_ratio,
+            hold_to_expiry,
+            only,
+            max_concurrent_combos,
+            min_depth_contracts,
+            delta_hedge,
+            min_dte,
+            max_dte,
+            moneyness_band,
+            expiries,
+            nearest_expiries,
+            audit_log,
+            loop_interval,
+            dedup_cooldown,
+            tui,
+            output,
+            webhook,
+            webhook_min_edge_usd,
+            report_dir,
+            report_format,
+            sort_by,
+            filter_strategy,
+            filter_currency,
+            filter_expiry,
+            group_by,
+            top,
+            execute_top,
+            metrics_addr,
+            execution_mode,
+            maker_improve_ticks,
+            maker_stale_secs,
+            rfq_contracts,
+            rfq_wait_secs,
+            rfq_execute,
+            max_currency_notional,
+            max_net_delta,
+            max_net_gamma,
+            max_net_vega,
+            daily_loss_limit,
+            risk_state,
+            breaker_error_rate,
+            breaker_stale_secs,
+            breaker_index_bps,
+            breaker_cooldown_secs,
+            fee_schedule,
+            fee_tier,
+            chain_snapshot,
+            warm_start,
+            incremental_ms,
+            cancel_on_disconnect,
+            recheck_edge_fraction,
+            latency_budget_ms,
+            diagnose_rejections,
+            record_dir,
+            record_block_rows,
+            record_block_bytes,
+            record_snapshot_interval,
+            high_vol_threshold,
+            high_vol_edge_multiplier,
+            adverse_window_secs,
+            max_margin_utilization,
+            jelly_carry,
+            daemon_addr,
+            max_leg_skew_ms,
+            staleness_penalty_bps,
+            max_index_divergence_bps,
+            sim_capital,
+            fee_report,
+            diff_min_change_usd,
+            min_annualized_return,
+            conflate_quotes,
+            combo_cache,
+            max_orders_per_minute,
+            max_combos_per_hour,
+            max_daily_notional,
+            record,
+            replay,
+            post_trade_delay_secs,
+        );
+
+        macro_rules! layer_strategy_map {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(map) = &self.$field {
+                    if !explicit(stringify!($field)) {
+                        cli.$field = map
+                            .iter()
+                            .map(|(strategy, value)| format!("{strategy}={value}"))
+                            .collect();
+                    }
+                }
+            )*};
+        }
+        layer_strategy_map!(
+            strategy_min_edge_usd,
+            strategy_min_edge_ratio,
+            strategy_max_contracts,
+        );
+    }
+}
+
+/// Label of the account built from `API_KEY`/`API_SECRET`, which executes
+/// every strategy not routed elsewhere.
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// An alert route from the config file's `[notify.*]` tables.
+#[derive(Debug, Clone, Serialize)]
+pub struct NotifierConfig {
+    pub label: String,
+    pub target: NotifyTarget,
+    pub filter: NotifyFilter,
+}
+
+/// Where a route delivers. Secrets are left out of the logged config.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotifyTarget {
+    Telegram {
+        api_url: Url,
+        #[serde(skip_serializing)]
+        token: String,
+        chat_id: String,
+    },
+    Slack {
+        #[serde(skip_serializing)]
+        url: Url,
+    },
+    Webhook {
+        #[serde(skip_serializing)]
+        url: Url,
+    },
+}
+
+/// A credential set from the config file's `[accounts.*]` tables.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountConfig {
+    pub label: String,
+    pub environment: Environment,
+    #[serde(skip_serializing)]
+    pub api_key: Option<String>,
+    #[serde(skip_serializing)]
+    pub api_secret: Option<String>,
+    /// Read-only accounts never submit orders; combos routed to them are skipped.
+    pub read_only: bool,
+    pub strategies: Vec<StrategyKind>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -96,15 +1025,106 @@ pub struct AppConfig {
     pub strategy_filter: StrategyFilter,
     pub max_concurrent_combos: u32,
     pub min_depth_contracts: u32,
+    pub delta_hedge: bool,
+    pub strategy_thresholds: HashMap<StrategyKind, StrategyThresholds>,
+    pub instrument_filter: InstrumentFilter,
+    pub audit_log: Option<PathBuf>,
+    pub loop_interval_secs: Option<u64>,
+    pub dedup_cooldown_secs: u64,
+    pub tui: bool,
+    pub output: OutputFormat,
+    /// Skipped when the config is logged: webhook URLs usually embed a token.
+    #[serde(skip_serializing)]
+    pub webhook_url: Option<Url>,
+    pub webhook_min_edge_usd: Decimal,
+    pub report_dir: Option<PathBuf>,
+    pub report_format: ReportFormat,
+    pub view: OpportunityView,
+    pub execute_top: usize,
+    pub metrics_addr: Option<SocketAddr>,
+    pub execution_mode: ExecutionMode,
+    pub maker_improve_ticks: u32,
+    pub maker_stale_secs: u64,
+    pub rfq_contracts: Option<Decimal>,
+    pub rfq_wait_secs: u64,
+    pub rfq_execute: bool,
+    pub max_currency_notional_usd: Option<Decimal>,
+    pub max_net_delta_usd: Option<Decimal>,
+    pub max_net_gamma_usd: Option<Decimal>,
+    pub max_net_vega_usd: Option<Decimal>,
+    pub daily_loss_limit_usd: Option<Decimal>,
+    pub risk_state: Option<PathBuf>,
+    pub breaker_error_rate: f64,
+    pub breaker_stale_secs: u64,
+    pub breaker_index_bps: f64,
+    pub breaker_cooldown_secs: u64,
+    pub fee_tier: String,
+    pub fee_schedule: FeeSchedule,
+    pub chain_snapshot: Option<PathBuf>,
+    pub warm_start: bool,
+    pub incremental_ms: Option<u64>,
+    pub cancel_on_disconnect: bool,
+    pub recheck_edge_fraction: Option<f64>,
+    pub latency_budget_ms: u64,
+    pub accounts: Vec<AccountConfig>,
+    pub notifiers: Vec<NotifierConfig>,
+    pub diagnose_rejections: bool,
+    pub record_dir: Option<PathBuf>,
+    /// Block targets of day files the recorder creates.
+    pub record_layout: BlockLayout,
+    /// Record to book streams with a snapshot every this many updates.
+    pub record_snapshot_interval: Option<u32>,
+    pub high_vol_threshold: Option<f64>,
+    pub high_vol_edge_multiplier: f64,
+    pub adverse_window_secs: u64,
+    pub daemon_addr: Option<SocketAddr>,
+    pub max_margin_utilization: Option<f64>,
+    pub jelly_carry: bool,
+    pub max_leg_skew_ms: Option<u64>,
+    pub staleness_penalty_bps: f64,
+    pub max_index_divergence_bps: Option<f64>,
+    pub sim_capital: Option<Decimal>,
+    pub fee_report: Option<FeeReportFormat>,
+    pub diff_min_change_usd: Option<Decimal>,
+    pub min_annualized_return: Option<f64>,
+    pub conflate_quotes: bool,
+    pub combo_cache: Option<PathBuf>,
+    pub max_orders_per_minute: Option<u32>,
+    pub max_combos_per_hour: Option<u32>,
+    pub max_daily_notional_usd: Option<Decimal>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub post_trade_delay_secs: Option<u64>,
 }
 
 impl AppConfig {
+    /// The account that executes `strategy`, or `None` for the default one.
+    pub fn account_for(&self, strategy: StrategyKind) -> Option<&AccountConfig> {
+        self.accounts
+            .iter()
+            .find(|account| account.strategies.contains(&strategy))
+    }
+
+    pub fn account_label(&self, strategy: StrategyKind) -> &str {
+        self.account_for(strategy)
+            .map_or(DEFAULT_ACCOUNT, |account| account.label.as_str())
+    }
+
+    pub fn requirements(&self, strategy: StrategyKind) -> MinEdgeRequirements {
+        let overrides = self.strategy_thresholds.get(&strategy);
+        MinEdgeRequirements {
+            min_edge_usd: overrides
+                .and_then(|o| o.min_edge_usd)
+                .unwrap_or(self.min_edge_usd),
+            min_edge_ratio: overrides
+                .and_then(|o| o.min_edge_ratio)
+                .unwrap_or(self.min_edge_ratio),
+            max_size_contracts: overrides.and_then(|o| o.max_size_contracts),
+        }
+    }
+
     pub fn from_cli(cli: Cli) -> Result<Self> {
-        let environment = match cli.env.to_ascii_lowercase().as_str() {
-            "test" | "testnet" => Environment::Testnet,
-            "prod" | "production" | "main" => Environment::Production,
-            other => return Err(anyhow!("unknown env: {other}")),
-        };
+        let environment = parse_environment(&cli.env)?;
 
         let api_key = env::var("API_KEY").ok();
         let api_secret = env::var("API_SECRET").ok();
@@ -118,11 +1138,7 @@ impl AppConfig {
         let settlements = cli
             .linears
             .iter()
-            .map(|s| match s.as_str() {
-                "usdc" => Ok(SettlementCurrency::Usdc),
-                "coin" => Ok(SettlementCurrency::Coin),
-                other => Err(anyhow!("unknown settlement: {other}")),
-            })
+            .map(|s| SettlementCurrency::from_str(s))
             .collect::<Result<Vec<_>, _>>()?;
 
         let max_ticket_usd = Decimal::from(cli.max_ticket);
@@ -131,20 +1147,48 @@ impl AppConfig {
         if cli.min_edge_ratio < 1.0 {
             return Err(anyhow!("min edge ratio must be >= 1.0"));
         }
+        if cli.warm_start && cli.chain_snapshot.is_none() {
+            return Err(anyhow!("--warm-start requires --chain-snapshot"));
+        }
+        if !(0.0..=1.0).contains(&cli.breaker_error_rate) {
+            return Err(anyhow!("breaker error rate must be between 0 and 1"));
+        }
+        if cli
+            .recheck_edge_fraction
+            .is_some_and(|fraction| !(0.0..=1.0).contains(&fraction))
+        {
+            return Err(anyhow!("recheck edge fraction must be between 0 and 1"));
+        }
+        if cli.high_vol_edge_multiplier < 1.0 {
+            return Err(anyhow!("high-vol edge multiplier must be at least 1"));
+        }
+        let record_layout = BlockLayout::new(cli.record_block_rows, cli.record_block_bytes)?;
+        if cli.record_snapshot_interval == Some(0) {
+            return Err(anyhow!("record snapshot interval must be at least 1"));
+        }
+        if cli.max_index_divergence_bps.is_some_and(|bps| bps < 0.0) {
+            return Err(anyhow!("max index divergence must not be negative"));
+        }
+        if cli.fee_report.is_some() && cli.report_dir.is_none() {
+            return Err(anyhow!("--fee-report requires --report-dir"));
+        }
+        if cli.staleness_penalty_bps < 0.0 {
+            return Err(anyhow!("staleness penalty must not be negative"));
+        }
+        if cli
+            .max_margin_utilization
+            .is_some_and(|cap| !(cap > 0.0 && cap <= 1.0))
+        {
+            return Err(anyhow!(
+                "max margin utilization must be above 0 and at most 1"
+            ));
+        }
 
         let strategy_filter = StrategyFilter {
             include: cli
                 .only
                 .iter()
-                .map(|s| match s.trim().to_ascii_lowercase().as_str() {
-                    "vertical" => Ok(StrategyKind::Vertical),
-                    "butterfly" => Ok(StrategyKind::Butterfly),
-                    "calendar" => Ok(StrategyKind::Calendar),
-                    "box" => Ok(StrategyKind::Box),
-                    "stale" | "stalequote" | "stale-quote" => Ok(StrategyKind::StaleQuote),
-                    "jelly" | "jellyroll" | "jelly-roll" => Ok(StrategyKind::JellyRoll),
-                    other => Err(anyhow!(format!("unknown strategy filter: {other}"))),
-                })
+                .map(|s| parse_strategy_kind(s))
                 .collect::<Result<Vec<_>, _>>()?,
         };
 
@@ -152,6 +1196,125 @@ impl AppConfig {
             return Err(anyhow!("must enable at least one detector"));
         }
 
+        let mut strategy_thresholds: HashMap<StrategyKind, StrategyThresholds> = HashMap::new();
+        for (strategy, value) in parse_strategy_values(&cli.strategy_min_edge_usd)? {
+            let min_edge = Decimal::from_str(&value)
+                .map_err(|_| anyhow!("invalid min edge for {strategy}: {value}"))?;
+            strategy_thresholds
+                .entry(strategy)
+                .or_default()
+                .min_edge_usd = Some(min_edge);
+        }
+        for (strategy, value) in parse_strategy_values(&cli.strategy_min_edge_ratio)? {
+            let ratio = value
+                .parse::<f64>()
+                .map_err(|_| anyhow!("invalid min edge ratio for {strategy}: {value}"))?;
+            if ratio < 1.0 {
+                return Err(anyhow!("min edge ratio for {strategy} must be >= 1.0"));
+            }
+            strategy_thresholds
+                .entry(strategy)
+                .or_default()
+                .min_edge_ratio = Some(ratio);
+        }
+        for (strategy, value) in parse_strategy_values(&cli.strategy_max_contracts)? {
+            let cap = Decimal::from_str(&value)
+                .map_err(|_| anyhow!("invalid max contracts for {strategy}: {value}"))?;
+            strategy_thresholds
+                .entry(strategy)
+                .or_default()
+                .max_size_contracts = Some(cap);
+        }
+
+        if cli.nearest_expiries == Some(0) {
+            return Err(anyhow!("--nearest-expiries must be positive"));
+        }
+        if let (Some(min), Some(max)) = (cli.min_dte, cli.max_dte) {
+            if min > max {
+                return Err(anyhow!("min dte {min} exceeds max dte {max}"));
+            }
+        }
+        let instrument_filter = InstrumentFilter {
+            min_dte: cli.min_dte,
+            max_dte: cli.max_dte,
+            moneyness_band: cli
+                .moneyness_band
+                .as_deref()
+                .map(parse_moneyness_band)
+                .transpose()?,
+            expiries: cli
+                .expiries
+                .iter()
+                .map(|e| parse_expiry_date(e))
+                .collect::<Result<Vec<_>, _>>()?,
+            nearest: cli.nearest_expiries,
+        };
+
+        let output = OutputFormat::from_str(&cli.output)?;
+        if cli.tui && output == OutputFormat::Jsonl {
+            return Err(anyhow!("--output jsonl cannot be combined with --tui"));
+        }
+        let webhook_url = cli
+            .webhook
+            .as_deref()
+            .map(|raw| Url::parse(raw).map_err(|e| anyhow!("invalid webhook url {raw}: {e}")))
+            .transpose()?;
+        let webhook_min_edge_usd = cli
+            .webhook_min_edge_usd
+            .map(Decimal::from)
+            .unwrap_or(min_edge_usd);
+        let execution_mode = ExecutionMode::from_str(&cli.execution_mode)?;
+        let looping =
+            cli.tui || cli.daemon_addr.is_some() || cli.loop_interval.is_some_and(|secs| secs > 0);
+        if execution_mode == ExecutionMode::Maker && !looping {
+            return Err(anyhow!(
+                "--execution-mode maker needs loop mode (--loop-interval, --tui or --daemon-addr) to manage resting orders"
+            ));
+        }
+        if cli.record.is_some() && !looping {
+            return Err(anyhow!(
+                "--record needs loop mode (--loop-interval, --tui or --daemon-addr) to stream quotes"
+            ));
+        }
+        if cli.post_trade_delay_secs.is_some_and(|secs| secs > 0) && !looping {
+            return Err(anyhow!(
+                "--post-trade-delay-secs needs loop mode (--loop-interval, --tui or --daemon-addr) to check past executions"
+            ));
+        }
+        if cli.record.is_some() && cli.replay.is_some() {
+            return Err(anyhow!("--record cannot be combined with --replay"));
+        }
+        let incremental_ms = cli.incremental_ms.filter(|ms| *ms > 0);
+        if incremental_ms.is_some() && !looping {
+            return Err(anyhow!(
+                "--incremental-ms needs loop mode (--loop-interval, --tui or --daemon-addr) to stream quotes"
+            ));
+        }
+
+        let accounts = parse_accounts(&cli.accounts, environment, cli.dry_run)?;
+        let notifiers = parse_notifiers(&cli.notify)?;
+
+        let view = OpportunityView {
+            sort_by: parse_sort_key(&cli.sort_by)?,
+            strategies: cli
+                .filter_strategy
+                .iter()
+                .map(|s| parse_strategy_kind(s))
+                .collect::<Result<Vec<_>, _>>()?,
+            currencies: cli
+                .filter_currency
+                .iter()
+                .map(|c| Currency::from_str(c))
+                .collect::<Result<Vec<_>, _>>()?,
+            expiries: cli
+                .filter_expiry
+                .iter()
+                .map(|e| parse_expiry_date(e))
+                .collect::<Result<Vec<_>, _>>()?,
+            top: cli.top,
+            group_by: cli.group_by.as_deref().map(parse_group_key).transpose()?,
+        };
+
         let config = AppConfig {
             environment,
             api_key,
@@ -166,6 +1329,82 @@ impl AppConfig {
             strategy_filter,
             max_concurrent_combos: cli.max_concurrent_combos,
             min_depth_contracts: cli.min_depth_contracts,
+            delta_hedge: cli.delta_hedge,
+            strategy_thresholds,
+            instrument_filter,
+            audit_log: cli.audit_log,
+            loop_interval_secs: cli.loop_interval.filter(|secs| *secs > 0),
+            dedup_cooldown_secs: cli.dedup_cooldown,
+            tui: cli.tui,
+            output,
+            webhook_url,
+            webhook_min_edge_usd,
+            report_dir: cli.report_dir,
+            report_format: ReportFormat::from_str(&cli.report_format)?,
+            view,
+            execute_top: cli.execute_top,
+            metrics_addr: cli.metrics_addr,
+            execution_mode,
+            maker_improve_ticks: cli.maker_improve_ticks,
+            maker_stale_secs: cli.maker_stale_secs,
+            rfq_contracts: cli
+                .rfq_contracts
+                .filter(|contracts| *contracts > 0)
+                .map(Decimal::from),
+            rfq_wait_secs: cli.rfq_wait_secs,
+            rfq_execute: cli.rfq_execute,
+            max_currency_notional_usd: cli.max_currency_notional.map(Decimal::from),
+            max_net_delta_usd: cli.max_net_delta.map(Decimal::from),
+            max_net_gamma_usd: cli.max_net_gamma.map(Decimal::from),
+            max_net_vega_usd: cli.max_net_vega.map(Decimal::from),
+            daily_loss_limit_usd: cli.daily_loss_limit.map(Decimal::from),
+            risk_state: cli.risk_state,
+            breaker_error_rate: cli.breaker_error_rate,
+            breaker_stale_secs: cli.breaker_stale_secs,
+            breaker_index_bps: cli.breaker_index_bps,
+            breaker_cooldown_secs: cli.breaker_cooldown_secs,
+            fee_schedule: FeeSchedule::resolve(cli.fee_schedule.as_deref(), &cli.fee_tier)?,
+            fee_tier: cli.fee_tier,
+            chain_snapshot: cli.chain_snapshot,
+            warm_start: cli.warm_start,
+            incremental_ms,
+            cancel_on_disconnect: cli.cancel_on_disconnect,
+            recheck_edge_fraction: cli.recheck_edge_fraction,
+            latency_budget_ms: cli.latency_budget_ms,
+            accounts,
+            notifiers,
+            diagnose_rejections: cli.diagnose_rejections,
+            record_dir: cli.record_dir,
+            record_layout,
+            record_snapshot_interval: cli.record_snapshot_interval,
+            high_vol_threshold: cli.high_vol_threshold,
+            high_vol_edge_multiplier: cli.high_vol_edge_multiplier,
+            adverse_window_secs: cli.adverse_window_secs,
+            daemon_addr: cli.daemon_addr,
+            max_margin_utilization: cli.max_margin_utilization,
+            jelly_carry: cli.jelly_carry,
+            max_leg_skew_ms: cli.max_leg_skew_ms,
+            staleness_penalty_bps: cli.staleness_penalty_bps,
+            max_index_divergence_bps: cli.max_index_divergence_bps,
+            sim_capital: cli
+                .sim_capital
+                .filter(|capital| *capital > 0)
+                .map(Decimal::from),
+            fee_report: cli
+                .fee_report
+                .as_deref()
+                .map(FeeReportFormat::from_str)
+                .transpose()?,
+            diff_min_change_usd: cli.diff_min_change_usd.map(Decimal::from),
+            min_annualized_return: cli.min_annualized_return,
+            conflate_quotes: cli.conflate_quotes,
+            combo_cache: cli.combo_cache,
+            max_orders_per_minute: cli.max_orders_per_minute,
+            max_combos_per_hour: cli.max_combos_per_hour,
+            max_daily_notional_usd: cli.max_daily_notional.map(Decimal::from),
+            record: cli.record,
+            replay: cli.replay,
+            post_trade_delay_secs: cli.post_trade_delay_secs.filter(|secs| *secs > 0),
         };
 
         info!(
@@ -175,3 +1414,201 @@ impl AppConfig {
         Ok(config)
     }
 }
+
+fn parse_environment(value: &str) -> Result<Environment> {
+    match value.to_ascii_lowercase().as_str() {
+        "test" | "testnet" => Ok(Environment::Testnet),
+        "prod" | "production" | "main" => Ok(Environment::Production),
+        other => Err(anyhow!("unknown env: {other}")),
+    }
+}
+
+/// Resolves `[accounts.*]` tables, reading credentials from the named env
+/// vars. Each strategy routes to at most one account, and live runs need
+/// credentials for every account that may trade.
+fn parse_accounts(
+    entries: &BTreeMap<String, AccountEntry>,
+    default_environment: Environment,
+    dry_run: bool,
+) -> Result<Vec<AccountConfig>> {
+    let mut routed: HashMap<StrategyKind, &str> = HashMap::new();
+    let mut accounts = Vec::with_capacity(entries.len());
+    for (label, entry) in entries {
+        if label == DEFAULT_ACCOUNT {
+            return Err(anyhow!(
+                "account label {DEFAULT_ACCOUNT} is reserved for API_KEY/API_SECRET"
+            ));
+        }
+        let environment = match &entry.env {
+            Some(env) => parse_environment(env)?,
+            None => default_environment,
+        };
+        let strategies = entry
+            .strategies
+            .iter()
+            .map(|s| parse_strategy_kind(s))
+            .collect::<Result<Vec<_>, _>>()?;
+        for strategy in &strategies {
+            if let Some(other) = routed.insert(*strategy, label) {
+                return Err(anyhow!(
+                    "strategy {strategy} routed to both accounts {other} and {label}"
+                ));
+            }
+        }
+        let api_key = env::var(&entry.key_env).ok();
+        let api_secret = env::var(&entry.secret_env).ok();
+        if !dry_run && !entry.read_only && (api_key.is_none() || api_secret.is_none()) {
+            return Err(anyhow!(
+                "account {label}: set {} and {} or mark it read-only",
+                entry.key_env,
+                entry.secret_env
+            ));
+        }
+        accounts.push(AccountConfig {
+            label: label.clone(),
+            environment,
+            api_key,
+            api_secret,
+            read_only: entry.read_only,
+            strategies,
+        });
+    }
+    Ok(accounts)
+}
+
+/// Resolves `[notify.*]` tables, reading secrets from the named env vars,
+/// which must be set: a route that cannot deliver fails at startup rather
+/// than on the first alert.
+fn parse_notifiers(entries: &BTreeMap<String, NotifyEntry>) -> Result<Vec<NotifierConfig>> {
+    let secret = |label: &str, name: &Option<String>, key: &str| -> Result<String> {
+        let name = name
+            .as_deref()
+            .ok_or_else(|| anyhow!("notify {label}: {key} is required"))?;
+        env::var(name).map_err(|_| anyhow!("notify {label}: set {name}"))
+    };
+    let parse_url = |label: &str, raw: &str| -> Result<Url> {
+        Url::parse(raw).map_err(|err| anyhow!("notify {label}: invalid URL: {err}"))
+    };
+    let mut notifiers = Vec::with_capacity(entries.len());
+    for (label, entry) in entries {
+        let target = match entry.kind.to_ascii_lowercase().as_str() {
+            "telegram" => NotifyTarget::Telegram {
+                api_url: parse_url(
+                    label,
+                    entry
+                        .api_url
+                        .as_deref()
+                        .unwrap_or("https://api.telegram.org"),
+                )?,
+                token: secret(label, &entry.token_env, "token-env")?,
+                chat_id: entry
+                    .chat_id
+                    .clone()
+                    .ok_or_else(|| anyhow!("notify {label}: chat-id is required"))?,
+            },
+            "slack" => NotifyTarget::Slack {
+                url: parse_url(label, &secret(label, &entry.url_env, "url-env")?)?,
+            },
+            "webhook" => {
+                let url = match &entry.url {
+                    Some(url) => url.clone(),
+                    None => secret(label, &entry.url_env, "url or url-env")?,
+                };
+                NotifyTarget::Webhook {
+                    url: parse_url(label, &url)?,
+                }
+            }
+            other => {
+                return Err(anyhow!(
+                    "notify {label}: unknown kind {other} (telegram, slack, webhook)"
+                ))
+            }
+        };
+        let mut filter = NotifyFilter::default();
+        if !entry.events.is_empty() {
+            filter.events = entry
+                .events
+                .iter()
+                .map(|event| event.parse())
+                .collect::<Result<_>>()
+                .with_context(|| format!("notify {label}"))?;
+        }
+        if let Some(min_edge) = entry.min_edge_usd {
+            filter.min_edge_usd = Decimal::from(min_edge);
+        }
+        if let Some(top) = entry.top {
+            filter.top = top;
+        }
+        notifiers.push(NotifierConfig {
+            label: label.clone(),
+            target,
+            filter,
+        });
+    }
+    Ok(notifiers)
+}
+
+fn parse_sort_key(value: &str) -> Result<SortKey> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "edge" => Ok(SortKey::Edge),
+        "bps" => Ok(SortKey::Bps),
+        "notional" => Ok(SortKey::Notional),
+        "return" | "annualized" => Ok(SortKey::AnnualizedReturn),
+        other => Err(anyhow!("unknown sort key: {other}")),
+    }
+}
+
+fn parse_group_key(value: &str) -> Result<GroupKey> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "strategy" => Ok(GroupKey::Strategy),
+        "expiry" => Ok(GroupKey::Expiry),
+        other => Err(anyhow!("unknown group key: {other}")),
+    }
+}
+
+/// `2024-12-27` or Deribit's `27DEC24`.
+fn parse_expiry_date(value: &str) -> Result<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%d%b%y"))
+        .map_err(|_| anyhow!("invalid expiry date: {value}"))
+}
+
+fn parse_strategy_kind(value: &str) -> Result<StrategyKind> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "vertical" => Ok(StrategyKind::Vertical),
+        "butterfly" => Ok(StrategyKind::Butterfly),
+        "calendar" => Ok(StrategyKind::Calendar),
+        "box" => Ok(StrategyKind::Box),
+        "stale" | "stalequote" | "stale-quote" => Ok(StrategyKind::StaleQuote),
+        "jelly" | "jellyroll" | "jelly-roll" => Ok(StrategyKind::JellyRoll),
+        other => Err(anyhow!(format!("unknown strategy filter: {other}"))),
+    }
+}
+
+fn parse_strategy_values(entries: &[String]) -> Result<Vec<(StrategyKind, String)>> {
+    entries
+        .iter()
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (strategy, value) = entry
+                .split_once('=')
+                .ok_or_else(|| anyhow!("expected strategy=value, got {entry}"))?;
+            Ok((parse_strategy_kind(strategy)?, value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn parse_moneyness_band(value: &str) -> Result<(Decimal, Decimal)> {
+    let (low, high) = value
+        .split_once("..")
+        .ok_or_else(|| anyhow!("expected moneyness band like 0.7..1.3, got {value}"))?;
+    let low = Decimal::from_str(low.trim()).map_err(|_| anyhow!("invalid moneyness: {low}"))?;
+    let high = Decimal::from_str(high.trim()).map_err(|_| anyhow!("invalid moneyness: {high}"))?;
+    if low <= Decimal::ZERO || low >= high {
+        return Err(anyhow!(
+            "moneyness band must satisfy 0 < low < high, got {value}"
+        ));
+    }
+    Ok((low, high))
+}
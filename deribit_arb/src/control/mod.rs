@@ -0,0 +1,213 @@
+use crate::chain::OptionChain;
+use crate::model::{RuntimeThresholds, StrategyOpportunity};
+use crate::risk::RiskManager;
+use anyhow::{Context, Result};
+use parking_lot::RwLock;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+
+/// Largest request the control API reads; threshold updates are tiny.
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+
+/// State shared between the scan loop and the `--daemon-addr` control API.
+/// The loop publishes each cycle's detections and reads back the pause flag
+/// and threshold overrides.
+#[derive(Clone)]
+pub struct ControlHandle {
+    inner: Arc<ControlState>,
+}
+
+struct ControlState {
+    chain: OptionChain,
+    risk: RiskManager,
+    paused: AtomicBool,
+    thresholds: RwLock<RuntimeThresholds>,
+    opportunities: RwLock<Vec<StrategyOpportunity>>,
+}
+
+impl ControlHandle {
+    pub fn new(chain: OptionChain, risk: RiskManager) -> Self {
+        Self {
+            inner: Arc::new(ControlState {
+                chain,
+                risk,
+                paused: AtomicBool::new(false),
+                thresholds: RwLock::new(RuntimeThresholds::default()),
+                opportunities: RwLock::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.inner.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn thresholds(&self) -> RuntimeThresholds {
+        *self.inner.thresholds.read()
+    }
+
+    pub fn set_thresholds(&self, thresholds: RuntimeThresholds) {
+        *self.inner.thresholds.write() = thresholds;
+    }
+
+    /// Replaces the detections served at `GET /opportunities`.
+    pub fn publish(&self, opportunities: &[StrategyOpportunity]) {
+        *self.inner.opportunities.write() = opportunities.to_vec();
+    }
+
+    /// Answers one request with a status code and JSON body.
+    fn respond(&self, method: &str, path: &str, body: &[u8]) -> (u16, serde_json::Value) {
+        match (method, path) {
+            ("GET", "/opportunities") => (200, json!(*self.inner.opportunities.read())),
+            ("GET", "/chain") => (200, json!(self.inner.chain.stats())),
+            ("GET", "/risk") => (200, json!(self.inner.risk.snapshot())),
+            ("GET", "/status") => (200, self.status()),
+            ("POST", "/pause") | ("POST", "/resume") => {
+                let paused = path == "/pause";
+                self.set_paused(paused);
+                info!(target: "control", paused, "execution pause toggled");
+                (200, self.status())
+            }
+            ("POST", "/thresholds") => match parse_thresholds(body) {
+                Ok(thresholds) => {
+                    self.set_thresholds(thresholds);
+                    info!(target: "control", ?thresholds, "thresholds updated");
+                    (200, self.status())
+                }
+                Err(err) => (400, json!({ "error": format!("{err:#}") })),
+            },
+            _ => (
+                404,
+                json!({ "error": format!("no route for {method} {path}") }),
+            ),
+        }
+    }
+
+    fn status(&self) -> serde_json::Value {
+        json!({
+            "paused": self.is_paused(),
+            "thresholds": self.thresholds(),
+        })
+    }
+}
+
+/// Parses a `POST /thresholds` body. Omitted or `null` fields clear the
+/// override and fall back to the configured value.
+fn parse_thresholds(body: &[u8]) -> Result<RuntimeThresholds> {
+    let thresholds: RuntimeThresholds =
+        serde_json::from_slice(body).context("expected {\"min_edge_usd\", \"min_edge_ratio\"}")?;
+    if thresholds
+        .min_edge_usd
+        .is_some_and(|usd| usd < Decimal::ZERO)
+    {
+        anyhow::bail!("min_edge_usd must not be negative");
+    }
+    if thresholds.min_edge_ratio.is_some_and(|ratio| ratio < 0.0) {
+        anyhow::bail!("min_edge_ratio must not be negative");
+    }
+    Ok(thresholds)
+}
+
+/// Binds `addr` and serves the control API on a background task:
+/// `GET /opportunities`, `/chain`, `/risk` and `/status`, plus
+/// `POST /pause`, `/resume` and `/thresholds`.
+pub async fn serve(addr: SocketAddr, handle: ControlHandle) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("binding control API {addr}"))?;
+    let local = listener.local_addr()?;
+    info!(target: "control", addr = %local, "serving control API");
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    warn!(target: "control", error = %err, "accept failed");
+                    continue;
+                }
+            };
+            let handle = handle.clone();
+            tokio::spawn(async move {
+                if let Err(err) = handle_connection(socket, &handle).await {
+                    warn!(target: "control", error = %err, "control request failed");
+                }
+            });
+        }
+    });
+    Ok(local)
+}
+
+async fn handle_connection(mut socket: TcpStream, handle: &ControlHandle) -> Result<()> {
+    let (status, body) = match read_request(&mut socket).await? {
+        Some((method, path, body)) => handle.respond(&method, &path, &body),
+        None => (400, json!({ "error": "malformed request" })),
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        _ => "Not Found",
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    socket.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Reads the request line, headers and a `content-length` body.
+async fn read_request(socket: &mut TcpStream) -> Result<Option<(String, String, Vec<u8>)>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let header_end = loop {
+        if let Some(at) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break at + 4;
+        }
+        if buf.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        let read = socket.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    };
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Ok(None);
+    };
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_BYTES {
+        return Ok(None);
+    }
+    while buf.len() < header_end + content_length {
+        let read = socket.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+    let body_end = buf.len().min(header_end + content_length);
+    Ok(Some((
+        method.to_string(),
+        path.to_string(),
+        buf[header_end..body_end].to_vec(),
+    )))
+}
@@ -0,0 +1,371 @@
+use super::{DetectorSuite, RejectionReason};
+use crate::config::AppConfig;
+use crate::fees::FeeEngine;
+use crate::model::{
+    Currency, InstrumentSnapshot, MinEdgeRequirements, OptionKind, SettlementCurrency,
+    StrategyKind, StrategyOpportunity,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// The part of the chain a detector's legs share, which decides the scan
+/// pass it runs in and how the chain may be sliced for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SliceKind {
+    /// Every leg has one expiry: verticals, butterflies, boxes.
+    Expiry,
+    /// Every leg has one strike: calendars, jelly rolls.
+    Strike,
+}
+
+/// A strategy searcher run by [`DetectorSuite`] on every scan. The built-in
+/// strategies implement it; downstream crates add their own with
+/// [`DetectorSuite::register`].
+pub trait Detector: Send + Sync {
+    /// Strategy the detector emits, which `--only` enables it by.
+    fn strategy(&self) -> StrategyKind;
+
+    fn slice(&self) -> SliceKind;
+
+    /// Candidates found in `view`. The suite then drops legs with diverging
+    /// indices, weighs quote staleness and attaches the ID, margin estimate
+    /// and size ladder, so detectors leave those fields at their defaults.
+    fn scan(&self, view: &ChainView<'_>) -> Vec<StrategyOpportunity>;
+}
+
+/// Listings of one option kind sharing an expiry and book, by ascending
+/// strike: the legs of verticals and butterflies.
+#[derive(Debug, Clone)]
+pub struct ExpiryLadder<'s> {
+    pub currency: Currency,
+    pub expiry: DateTime<Utc>,
+    pub settlement: SettlementCurrency,
+    pub option_kind: OptionKind,
+    pub legs: Vec<&'s InstrumentSnapshot>,
+}
+
+/// The call and put listed at one strike, expiry and book.
+#[derive(Debug, Clone, Copy)]
+pub struct StrikePair<'s> {
+    pub expiry: DateTime<Utc>,
+    pub strike: Decimal,
+    pub call: &'s InstrumentSnapshot,
+    pub put: &'s InstrumentSnapshot,
+}
+
+/// Strike pairs of one underlying and book that share either an expiry
+/// (by ascending strike, for boxes) or a strike (by ascending expiry, for
+/// jelly rolls).
+#[derive(Debug, Clone)]
+pub struct PairLadder<'s> {
+    pub currency: Currency,
+    pub settlement: SettlementCurrency,
+    pub pairs: Vec<StrikePair<'s>>,
+}
+
+/// One scan slice as a detector sees it: quoted instruments that passed the
+/// instrument filter, valued at the slice's reference index, plus the
+/// suite's configuration, fee engine and thresholds. The groupings are
+/// built on first use and shared by every detector of the scan.
+pub struct ChainView<'s> {
+    pub(super) suite: &'s DetectorSuite<'s>,
+    snapshot: &'s [InstrumentSnapshot],
+    now: DateTime<Utc>,
+    expiry_ladders: OnceLock<Vec<ExpiryLadder<'s>>>,
+    pairs_by_expiry: OnceLock<Vec<PairLadder<'s>>>,
+    pairs_by_strike: OnceLock<Vec<PairLadder<'s>>>,
+    calendar_pairs: OnceLock<Vec<(&'s InstrumentSnapshot, &'s InstrumentSnapshot)>>,
+}
+
+impl<'s> ChainView<'s> {
+    pub(super) fn new(
+        suite: &'s DetectorSuite<'s>,
+        snapshot: &'s [InstrumentSnapshot],
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            suite,
+            snapshot,
+            now,
+            expiry_ladders: OnceLock::new(),
+            pairs_by_expiry: OnceLock::new(),
+            pairs_by_strike: OnceLock::new(),
+            calendar_pairs: OnceLock::new(),
+        }
+    }
+
+    pub fn snapshot(&self) -> &'s [InstrumentSnapshot] {
+        self.snapshot
+    }
+
+    /// Time the scan runs as of; replayed timestamps in backtests.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now
+    }
+
+    pub fn config(&self) -> &AppConfig {
+        self.suite.config
+    }
+
+    pub fn fee_engine(&self) -> &FeeEngine {
+        &self.suite.fee_engine
+    }
+
+    /// Minimum edge for `strategy`, with runtime overrides applied.
+    pub fn requirements(&self, strategy: StrategyKind) -> MinEdgeRequirements {
+        self.suite.requirements(strategy)
+    }
+
+    /// Records a dropped candidate for the scan summary and, with
+    /// `--diagnose-rejections`, the per-reason diagnostics.
+    pub fn reject(&self, strategy: StrategyKind, reason: RejectionReason) {
+        self.suite.reject(strategy, reason);
+    }
+
+    pub fn expiry_ladders(&self) -> &[ExpiryLadder<'s>] {
+        self.expiry_ladders.get_or_init(|| {
+            let mut ladders: HashMap<_, ExpiryLadder<'s>> = HashMap::new();
+            for inst in self.snapshot {
+                let instrument = &inst.instrument;
+                ladders
+                    .entry((
+                        instrument.currency,
+                        instrument.expiry,
+                        instrument.settlement_currency,
+                        instrument.option_kind,
+                    ))
+                    .or_insert_with(|| ExpiryLadder {
+                        currency: instrument.currency,
+                        expiry: instrument.expiry,
+                        settlement: instrument.settlement_currency,
+                        option_kind: instrument.option_kind,
+                        legs: Vec::new(),
+                    })
+                    .legs
+                    .push(inst);
+            }
+            let mut ladders: Vec<_> = ladders.into_values().collect();
+            for ladder in &mut ladders {
+                ladder.legs.sort_by_key(|inst| inst.instrument.strike);
+            }
+            ladders
+        })
+    }
+
+    /// Strike pairs sharing an expiry, by ascending strike.
+    pub fn pairs_by_expiry(&self) -> &[PairLadder<'s>] {
+        self.pairs_by_expiry
+            .get_or_init(|| self.pair_ladders(|pair| pair.expiry, |pair| pair.strike))
+    }
+
+    /// Strike pairs sharing a strike, by ascending expiry.
+    pub fn pairs_by_strike(&self) -> &[PairLadder<'s>] {
+        self.pairs_by_strike
+            .get_or_init(|| self.pair_ladders(|pair| pair.strike, |pair| pair.expiry))
+    }
+
+    /// Near and far legs of every calendar: each listing paired with the
+    /// next later expiry of the same strike and option kind on every book.
+    /// Coin, USDC and USDT strikes pay the same USD at expiry and pair
+    /// across books; EURR strikes are in euros and only pair among
+    /// themselves.
+    pub fn calendar_pairs(&self) -> &[(&'s InstrumentSnapshot, &'s InstrumentSnapshot)] {
+        self.calendar_pairs.get_or_init(|| {
+            let mut grouped: HashMap<_, Vec<&'s InstrumentSnapshot>> = HashMap::new();
+            for inst in self.snapshot {
+                grouped
+                    .entry((
+                        inst.instrument.currency,
+                        inst.instrument.strike,
+                        inst.instrument.option_kind,
+                        inst.instrument.settlement_currency.pays_usd(),
+                    ))
+                    .or_default()
+                    .push(inst);
+            }
+            let mut pairs = Vec::new();
+            for mut instruments in grouped.into_values() {
+                instruments.sort_by_key(|inst| inst.instrument.expiry);
+                for (at, near) in instruments.iter().enumerate() {
+                    for book in [
+                        SettlementCurrency::Coin,
+                        SettlementCurrency::Usdc,
+                        SettlementCurrency::Usdt,
+                        SettlementCurrency::Eurr,
+                    ] {
+                        if let Some(far) = instruments[at + 1..].iter().find(|far| {
+                            far.instrument.settlement_currency == book
+                                && far.instrument.expiry > near.instrument.expiry
+                        }) {
+                            pairs.push((*near, *far));
+                        }
+                    }
+                }
+            }
+            pairs
+        })
+    }
+
+    /// Strike pairs of one underlying and book grouped by `shared` and
+    /// ordered by `rank`.
+    fn pair_ladders<K, R>(
+        &self,
+        shared: impl Fn(&StrikePair<'s>) -> K,
+        rank: impl Fn(&StrikePair<'s>) -> R,
+    ) -> Vec<PairLadder<'s>>
+    where
+        K: std::hash::Hash + Eq,
+        R: Ord,
+    {
+        let mut legs: HashMap<
+            _,
+            (
+                Option<&'s InstrumentSnapshot>,
+                Option<&'s InstrumentSnapshot>,
+            ),
+        > = HashMap::new();
+        for inst in self.snapshot {
+            let instrument = &inst.instrument;
+            let entry = legs
+                .entry((
+                    instrument.currency,
+                    instrument.settlement_currency,
+                    instrument.expiry,
+                    instrument.strike,
+                ))
+                .or_default();
+            match instrument.option_kind {
+                OptionKind::Call => entry.0 = Some(inst),
+                OptionKind::Put => entry.1 = Some(inst),
+            }
+        }
+        let mut ladders: HashMap<_, PairLadder<'s>> = HashMap::new();
+        for ((currency, settlement, expiry, strike), legs) in legs {
+            let (Some(call), Some(put)) = legs else {
+                continue;
+            };
+            let pair = StrikePair {
+                expiry,
+                strike,
+                call,
+                put,
+            };
+            ladders
+                .entry((currency, settlement, shared(&pair)))
+                .or_insert_with(|| PairLadder {
+                    currency,
+                    settlement,
+                    pairs: Vec::new(),
+                })
+                .pairs
+                .push(pair);
+        }
+        let mut ladders: Vec<_> = ladders.into_values().collect();
+        for ladder in &mut ladders {
+            ladder.pairs.sort_by_key(&rank);
+        }
+        ladders
+    }
+}
+
+pub(super) struct Verticals;
+
+impl Detector for Verticals {
+    fn strategy(&self) -> StrategyKind {
+        StrategyKind::Vertical
+    }
+
+    fn slice(&self) -> SliceKind {
+        SliceKind::Expiry
+    }
+
+    fn scan(&self, view: &ChainView<'_>) -> Vec<StrategyOpportunity> {
+        view.expiry_ladders()
+            .iter()
+            .flat_map(|ladder| {
+                view.suite
+                    .detect_verticals(ladder, view.now)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+pub(super) struct Butterflies;
+
+impl Detector for Butterflies {
+    fn strategy(&self) -> StrategyKind {
+        StrategyKind::Butterfly
+    }
+
+    fn slice(&self) -> SliceKind {
+        SliceKind::Expiry
+    }
+
+    fn scan(&self, view: &ChainView<'_>) -> Vec<StrategyOpportunity> {
+        view.expiry_ladders()
+            .iter()
+            .flat_map(|ladder| {
+                view.suite
+                    .detect_butterflies(ladder, view.now)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+pub(super) struct Boxes;
+
+impl Detector for Boxes {
+    fn strategy(&self) -> StrategyKind {
+        StrategyKind::Box
+    }
+
+    fn slice(&self) -> SliceKind {
+        SliceKind::Expiry
+    }
+
+    fn scan(&self, view: &ChainView<'_>) -> Vec<StrategyOpportunity> {
+        view.suite
+            .detect_boxes(view.pairs_by_expiry(), view.now)
+            .unwrap_or_default()
+    }
+}
+
+pub(super) struct Calendars;
+
+impl Detector for Calendars {
+    fn strategy(&self) -> StrategyKind {
+        StrategyKind::Calendar
+    }
+
+    fn slice(&self) -> SliceKind {
+        SliceKind::Strike
+    }
+
+    fn scan(&self, view: &ChainView<'_>) -> Vec<StrategyOpportunity> {
+        view.suite
+            .detect_calendars(view.calendar_pairs(), view.now)
+            .unwrap_or_default()
+    }
+}
+
+pub(super) struct JellyRolls;
+
+impl Detector for JellyRolls {
+    fn strategy(&self) -> StrategyKind {
+        StrategyKind::JellyRoll
+    }
+
+    fn slice(&self) -> SliceKind {
+        SliceKind::Strike
+    }
+
+    fn scan(&self, view: &ChainView<'_>) -> Vec<StrategyOpportunity> {
+        view.suite
+            .detect_jelly_rolls(view.pairs_by_strike(), view.now)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,221 @@
+use crate::model::{StrategyKind, StrategyOpportunity};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Why a detector dropped a candidate combo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionReason {
+    /// A leg had no quote on the needed side or less than `--min-depth-contracts`.
+    NoDepth,
+    /// Executable prices leave no edge, before or after fees.
+    NegativeEdge,
+    /// Positive edge below the strategy's `--min-edge-usd`.
+    BelowMinEdge,
+    /// Edge covers fees by less than the strategy's `--min-edge-ratio`.
+    RatioTooLow,
+    /// A jelly roll's credit is no more than the forward spread implied by
+    /// futures basis and perpetual funding.
+    CarryExplained,
+    /// Leg quotes were taken further apart than `--max-leg-skew-ms`, or the
+    /// staleness penalty ate the edge.
+    StaleQuotes,
+    /// A leg's own index price strayed from the scan's reference index by
+    /// more than `--max-index-divergence-bps`.
+    IndexDivergence,
+    /// Edge annualized over the capital locked until expiry falls short of
+    /// `--min-annualized-return`.
+    BelowMinReturn,
+}
+
+impl Display for RejectionReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectionReason::NoDepth => write!(f, "no_depth"),
+            RejectionReason::NegativeEdge => write!(f, "negative_edge"),
+            RejectionReason::BelowMinEdge => write!(f, "below_min_edge"),
+            RejectionReason::RatioTooLow => write!(f, "ratio_too_low"),
+            RejectionReason::CarryExplained => write!(f, "carry_explained"),
+            RejectionReason::StaleQuotes => write!(f, "stale_quotes"),
+            RejectionReason::IndexDivergence => write!(f, "index_divergence"),
+            RejectionReason::BelowMinReturn => write!(f, "below_min_return"),
+        }
+    }
+}
+
+/// Rejected candidates of one strategy, per reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RejectionCounts {
+    pub no_depth: u64,
+    pub negative_edge: u64,
+    pub below_min_edge: u64,
+    pub ratio_too_low: u64,
+    pub carry_explained: u64,
+    pub stale_quotes: u64,
+    pub index_divergence: u64,
+    pub below_min_return: u64,
+}
+
+impl RejectionCounts {
+    pub fn get(&self, reason: RejectionReason) -> u64 {
+        match reason {
+            RejectionReason::NoDepth => self.no_depth,
+            RejectionReason::NegativeEdge => self.negative_edge,
+            RejectionReason::BelowMinEdge => self.below_min_edge,
+            RejectionReason::RatioTooLow => self.ratio_too_low,
+            RejectionReason::CarryExplained => self.carry_explained,
+            RejectionReason::StaleQuotes => self.stale_quotes,
+            RejectionReason::IndexDivergence => self.index_divergence,
+            RejectionReason::BelowMinReturn => self.below_min_return,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.no_depth
+            + self.negative_edge
+            + self.below_min_edge
+            + self.ratio_too_low
+            + self.carry_explained
+            + self.stale_quotes
+            + self.index_divergence
+            + self.below_min_return
+    }
+
+    fn add(&mut self, other: &RejectionCounts) {
+        self.no_depth += other.no_depth;
+        self.negative_edge += other.negative_edge;
+        self.below_min_edge += other.below_min_edge;
+        self.ratio_too_low += other.ratio_too_low;
+        self.carry_explained += other.carry_explained;
+        self.stale_quotes += other.stale_quotes;
+        self.index_divergence += other.index_divergence;
+        self.below_min_return += other.below_min_return;
+    }
+
+    fn record(&mut self, reason: RejectionReason) {
+        match reason {
+            RejectionReason::NoDepth => self.no_depth += 1,
+            RejectionReason::NegativeEdge => self.negative_edge += 1,
+            RejectionReason::BelowMinEdge => self.below_min_edge += 1,
+            RejectionReason::RatioTooLow => self.ratio_too_low += 1,
+            RejectionReason::CarryExplained => self.carry_explained += 1,
+            RejectionReason::StaleQuotes => self.stale_quotes += 1,
+            RejectionReason::IndexDivergence => self.index_divergence += 1,
+            RejectionReason::BelowMinReturn => self.below_min_return += 1,
+        }
+    }
+}
+
+/// Rejection counts collected since the last
+/// [`DetectorSuite::take_rejections`](super::DetectorSuite::take_rejections)
+/// or [`DetectorSuite::take_scan_summary`](super::DetectorSuite::take_scan_summary).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RejectionSummary {
+    counts: HashMap<StrategyKind, RejectionCounts>,
+}
+
+impl RejectionSummary {
+    pub fn record(&mut self, strategy: StrategyKind, reason: RejectionReason) {
+        self.counts.entry(strategy).or_default().record(reason);
+    }
+
+    pub fn get(&self, strategy: StrategyKind, reason: RejectionReason) -> u64 {
+        self.counts
+            .get(&strategy)
+            .map_or(0, |counts| counts.get(reason))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Per-strategy counts, ordered by strategy name.
+    pub fn by_strategy(&self) -> Vec<(StrategyKind, RejectionCounts)> {
+        let mut rows: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(strategy, counts)| (*strategy, *counts))
+            .collect();
+        rows.sort_by_key(|(strategy, _)| strategy.to_string());
+        rows
+    }
+}
+
+/// One strategy's share of a [`ScanSummary`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StrategyScanStats {
+    pub strategy: StrategyKind,
+    /// Candidates that reached a verdict: opportunities plus rejections.
+    pub candidates: u64,
+    pub opportunities: u64,
+    pub rejections: RejectionCounts,
+}
+
+/// What one full scan did, logged after it and emitted as a JSONL line for
+/// dashboards.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScanSummary {
+    pub instruments: usize,
+    /// Strategies that evaluated at least one candidate, by name.
+    pub strategies: Vec<StrategyScanStats>,
+    /// Rejections of every strategy, per reason.
+    pub rejections: RejectionCounts,
+    pub opportunities: usize,
+    pub best_edge_usd: Option<Decimal>,
+    pub duration_ms: u64,
+}
+
+impl ScanSummary {
+    pub fn new(
+        instruments: usize,
+        opportunities: &[StrategyOpportunity],
+        rejections: &RejectionSummary,
+        duration: std::time::Duration,
+    ) -> Self {
+        let mut found: HashMap<StrategyKind, u64> = HashMap::new();
+        for opportunity in opportunities {
+            *found.entry(opportunity.strategy).or_default() += 1;
+        }
+        let mut strategies: Vec<StrategyScanStats> = rejections
+            .counts
+            .keys()
+            .chain(found.keys())
+            .copied()
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .map(|strategy| {
+                let rejected = rejections
+                    .counts
+                    .get(&strategy)
+                    .copied()
+                    .unwrap_or_default();
+                let opportunities = found.get(&strategy).copied().unwrap_or_default();
+                StrategyScanStats {
+                    strategy,
+                    candidates: opportunities + rejected.total(),
+                    opportunities,
+                    rejections: rejected,
+                }
+            })
+            .collect();
+        strategies.sort_by_key(|stats| stats.strategy.to_string());
+        let mut totals = RejectionCounts::default();
+        for counts in rejections.counts.values() {
+            totals.add(counts);
+        }
+        Self {
+            instruments,
+            strategies,
+            rejections: totals,
+            opportunities: opportunities.len(),
+            best_edge_usd: opportunities.iter().map(|opp| opp.net_edge_usd).max(),
+            duration_ms: duration.as_millis() as u64,
+        }
+    }
+
+    pub fn candidates(&self) -> u64 {
+        self.strategies.iter().map(|stats| stats.candidates).sum()
+    }
+}
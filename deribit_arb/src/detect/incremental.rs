@@ -0,0 +1,129 @@
+use super::DetectorSuite;
+use crate::chain::OptionChain;
+use crate::model::{
+    Currency, InstrumentSnapshot, OptionKind, SettlementCurrency, StrategyOpportunity,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Listed strikes on either side of an updated one that can share a combo
+/// with it: butterflies span three adjacent strikes, verticals and boxes two.
+const STRIKE_NEIGHBOURS: usize = 2;
+
+/// Keeps the latest detections between full rescans and refreshes only the
+/// combos a batch of quote updates can affect: the adjacent-strike
+/// neighbourhood within each updated expiry (verticals, butterflies, boxes)
+/// and the updated strike across expiries (calendars, jelly rolls).
+#[derive(Default)]
+pub struct IncrementalScanner {
+    current: HashMap<String, StrategyOpportunity>,
+}
+
+impl IncrementalScanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the cached detections with the result of a full scan.
+    pub fn reset(&mut self, detected: &[StrategyOpportunity]) {
+        self.current = detected
+            .iter()
+            .map(|opp| (opp.combo_key(), opp.clone()))
+            .collect();
+    }
+
+    /// Re-evaluates the neighbourhoods of `dirty` instruments and returns the
+    /// full current detection set, best net edge first.
+    pub fn update(
+        &mut self,
+        detector: &DetectorSuite<'_>,
+        chain: &OptionChain,
+        dirty: &[String],
+        now: DateTime<Utc>,
+    ) -> Vec<StrategyOpportunity> {
+        let touched: HashSet<&str> = dirty.iter().map(String::as_str).collect();
+        self.current.retain(|_, opp| {
+            !opp.legs
+                .iter()
+                .any(|leg| touched.contains(leg.instrument_name.as_str()))
+        });
+
+        let mut expiry_points: HashSet<(Currency, DateTime<Utc>, SettlementCurrency, Decimal)> =
+            HashSet::new();
+        let mut strikes: HashSet<(Currency, Decimal)> = HashSet::new();
+        for name in dirty {
+            let Some(inst) = chain.get(name) else {
+                continue;
+            };
+            let inst = inst.instrument;
+            expiry_points.insert((
+                inst.currency,
+                inst.expiry,
+                inst.settlement_currency,
+                inst.strike,
+            ));
+            strikes.insert((inst.currency, inst.strike));
+        }
+
+        let mut found = Vec::new();
+        let mut expiry_slices: HashMap<(Currency, DateTime<Utc>), Arc<[InstrumentSnapshot]>> =
+            HashMap::new();
+        for (currency, expiry, settlement, strike) in expiry_points {
+            let slice = expiry_slices
+                .entry((currency, expiry))
+                .or_insert_with(|| chain.expiry_group(currency, expiry));
+            let neighbourhood = strike_neighbourhood(slice, settlement, strike);
+            found.append(&mut detector.scan_expiry_slice(&neighbourhood, now));
+        }
+        for (currency, strike) in strikes {
+            let slice = chain.strike_slice(currency, strike);
+            found.append(&mut detector.scan_strike_slice(&slice, now));
+        }
+        for opp in found {
+            self.current.insert(opp.combo_key(), opp);
+        }
+
+        let mut opportunities: Vec<_> = self.current.values().cloned().collect();
+        opportunities.sort_by_key(|opp| std::cmp::Reverse(opp.net_edge_usd));
+        opportunities
+    }
+}
+
+/// Instruments of one settlement struck within [`STRIKE_NEIGHBOURS`] listed
+/// strikes of `strike`, per option kind. The result is a contiguous strike
+/// range, so adjacent pairs inside it are adjacent in the full expiry too.
+fn strike_neighbourhood(
+    slice: &[InstrumentSnapshot],
+    settlement: SettlementCurrency,
+    strike: Decimal,
+) -> Vec<InstrumentSnapshot> {
+    let (mut low, mut high) = (strike, strike);
+    for kind in [OptionKind::Call, OptionKind::Put] {
+        let mut listed: Vec<Decimal> = slice
+            .iter()
+            .filter(|inst| {
+                inst.instrument.settlement_currency == settlement
+                    && inst.instrument.option_kind == kind
+            })
+            .map(|inst| inst.instrument.strike)
+            .collect();
+        if listed.is_empty() {
+            continue;
+        }
+        listed.sort();
+        listed.dedup();
+        let at = listed.partition_point(|listed| *listed < strike);
+        low = low.min(listed[at.saturating_sub(STRIKE_NEIGHBOURS)]);
+        high = high.max(listed[(at + STRIKE_NEIGHBOURS).min(listed.len() - 1)]);
+    }
+    slice
+        .iter()
+        .filter(|inst| {
+            inst.instrument.settlement_currency == settlement
+                && (low..=high).contains(&inst.instrument.strike)
+        })
+        .cloned()
+        .collect()
+}
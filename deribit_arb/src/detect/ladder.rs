@@ -0,0 +1,76 @@
+use crate::model::{ComboSide, InstrumentSnapshot, QuoteLevel, SizeRung, StrategyOpportunity};
+use rust_decimal::prelude::*;
+use std::collections::HashMap;
+
+/// Sizes each ladder prices, as multiples of the detected `size_contracts`.
+pub const LADDER_MULTIPLES: [u32; 3] = [1, 2, 5];
+
+/// Edge at [`LADDER_MULTIPLES`] of the detected size. Each leg walks its
+/// cached L2 book (top of book when none is cached) and the cost of filling
+/// past the detected touch comes off the linearly scaled edge; fees scale
+/// with size. A rung has no edge when a book runs out before it fills.
+pub fn size_ladder(
+    opportunity: &StrategyOpportunity,
+    instruments: &[InstrumentSnapshot],
+) -> Vec<SizeRung> {
+    let by_name: HashMap<&str, &InstrumentSnapshot> = instruments
+        .iter()
+        .map(|inst| (inst.instrument.instrument_name.as_str(), inst))
+        .collect();
+    LADDER_MULTIPLES
+        .iter()
+        .map(|&multiple| {
+            let factor = Decimal::from(multiple);
+            let impact: Option<Decimal> = opportunity
+                .touches
+                .iter()
+                .map(|touch| {
+                    let inst = by_name.get(touch.instrument_name.as_str())?;
+                    let contracts = touch.size_contracts * factor;
+                    let filled = walk_book(book_side(inst, touch.side), contracts)?;
+                    let touched = touch.price * contracts;
+                    let native = match touch.side {
+                        ComboSide::Buy => filled - touched,
+                        ComboSide::Sell => touched - filled,
+                    };
+                    Some(native * opportunity.usd_per_unit(inst.instrument.settlement_currency))
+                })
+                .sum();
+            SizeRung {
+                depth_multiple: multiple,
+                size_contracts: opportunity.size_contracts * factor,
+                net_edge_usd: impact
+                    .map(|impact| (opportunity.net_edge_usd * factor - impact).round_dp(2)),
+            }
+        })
+        .collect()
+}
+
+/// Levels a leg takes from, best first: asks for buys, bids for sells.
+fn book_side(inst: &InstrumentSnapshot, side: ComboSide) -> Vec<QuoteLevel> {
+    let book = inst.order_book.as_ref();
+    let (levels, top) = match side {
+        ComboSide::Buy => (book.map(|book| &book.asks), &inst.quote.best_ask),
+        ComboSide::Sell => (book.map(|book| &book.bids), &inst.quote.best_bid),
+    };
+    match levels.filter(|levels| !levels.is_empty()) {
+        Some(levels) => levels.clone(),
+        None => top.iter().cloned().collect(),
+    }
+}
+
+/// Premium paid or received for `contracts` across `levels`; `None` when
+/// the levels hold fewer contracts.
+fn walk_book(levels: Vec<QuoteLevel>, contracts: Decimal) -> Option<Decimal> {
+    let mut remaining = contracts;
+    let mut premium = Decimal::ZERO;
+    for level in levels {
+        if remaining <= Decimal::ZERO {
+            break;
+        }
+        let take = remaining.min(level.amount);
+        premium += take * level.price;
+        remaining -= take;
+    }
+    (remaining <= Decimal::ZERO).then_some(premium)
+}
@@ -1,84 +1,507 @@
-use crate::config::AppConfig;
-use crate::fees::{FeeComputationContext, FeeEngine, LegFeeInput};
+use crate::chain::OptionChain;
+use crate::config::{AppConfig, ExecutionMode};
+use crate::fees::{native_from_usd, FeeComputationContext, FeeEngine, HedgeFeeInput, LegFeeInput};
+use crate::greeks::instrument_greeks;
+use crate::margin::estimate_margin;
 use crate::model::{
-    ComboExecutionPlan, ComboLeg, ComboSide, FillRole, InstrumentSnapshot, LegTouch, OptionKind,
-    OrderTimeInForce, SettlementCurrency, StrategyKind, StrategyOpportunity,
+    CarryCurve, ComboExecutionPlan, ComboLeg, ComboSide, ExecutionVenue, FillRole, HedgeLeg,
+    InstrumentSnapshot, LegTouch, MinEdgeRequirements, OptionKind, OrderTimeInForce,
+    RuntimeThresholds, SettlementCurrency, StrategyKind, StrategyOpportunity,
+    OPPORTUNITY_SCHEMA_VERSION,
 };
 use anyhow::Result;
 use chrono::{Duration, Utc};
+use parking_lot::Mutex;
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 use serde_json::json;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use tracing::debug;
+
+pub mod detector;
+pub mod diagnostics;
+pub mod incremental;
+pub mod ladder;
+
+pub use detector::{ChainView, Detector, ExpiryLadder, PairLadder, SliceKind, StrikePair};
+pub use diagnostics::{
+    RejectionCounts, RejectionReason, RejectionSummary, ScanSummary, StrategyScanStats,
+};
+pub use incremental::IncrementalScanner;
+use ladder::size_ladder;
+
+/// Deribit's minimum perpetual order is $10; smaller residual deltas stay unhedged.
+const MIN_HEDGE_NOTIONAL_USD: Decimal = dec!(10);
+
+/// Fills the per-opportunity estimates that need the whole slice, and the
+/// content ID.
+fn attach_estimates(
+    opportunities: &mut [StrategyOpportunity],
+    snapshot: &[InstrumentSnapshot],
+    now: chrono::DateTime<Utc>,
+) {
+    for opportunity in opportunities {
+        opportunity.id = opportunity.content_id();
+        opportunity.estimated_margin_usd =
+            estimate_margin(opportunity, snapshot, now).map(|margin| margin * opportunity.fx_usd);
+        opportunity.size_ladder = size_ladder(opportunity, snapshot);
+        opportunity.annualized_return = opportunity.annualized_return_at(now);
+    }
+}
+
+/// Records why a candidate was dropped and moves on to the next one.
+macro_rules! reject {
+    ($suite:ident, $strategy:ident, $reason:ident) => {{
+        $suite.reject(StrategyKind::$strategy, RejectionReason::$reason);
+        continue;
+    }};
+}
 
 pub struct DetectorSuite<'a> {
     config: &'a AppConfig,
     fee_engine: FeeEngine,
+    /// Built-in strategies first, then any registered ones, in run order.
+    detectors: Vec<Box<dyn Detector>>,
+    /// Always collected for the scan summary; `--diagnose-rejections` only
+    /// decides whether [`Self::take_rejections`] hands them out.
+    rejections: Mutex<RejectionSummary>,
+    overrides: Mutex<RuntimeThresholds>,
+    /// Forward curves jelly rolls are valued against; empty until the scan
+    /// loop fetches them.
+    carry: Mutex<HashMap<crate::model::Currency, CarryCurve>>,
+    /// USD per euro for EURR books; until the scan loop fetches it, EURR
+    /// listings are left out of detection.
+    eur_usd: Mutex<Option<Decimal>>,
 }
 
 impl<'a> DetectorSuite<'a> {
     pub fn new(config: &'a AppConfig) -> Self {
         Self {
             config,
-            fee_engine: FeeEngine::new(),
+            fee_engine: FeeEngine::with_schedule(config.fee_schedule.clone()),
+            detectors: vec![
+                Box::new(detector::Verticals),
+                Box::new(detector::Butterflies),
+                Box::new(detector::Boxes),
+                Box::new(detector::Calendars),
+                Box::new(detector::JellyRolls),
+            ],
+            rejections: Mutex::new(RejectionSummary::default()),
+            overrides: Mutex::new(RuntimeThresholds::default()),
+            carry: Mutex::new(HashMap::new()),
+            eur_usd: Mutex::new(None),
         }
     }
 
-    pub fn scan(&self, snapshot: &[InstrumentSnapshot]) -> Vec<StrategyOpportunity> {
-        let mut opportunities = Vec::new();
-        let groups = group_by_expiry(snapshot);
-        for ((currency, expiry, settlement, kind), instruments) in groups.iter() {
-            match kind {
-                StrategyKindKey::Call | StrategyKindKey::Put => {
-                    if self.config.strategy_filter.allows(StrategyKind::Vertical) {
-                        if let Ok(mut verts) =
-                            self.detect_verticals(instruments, *currency, *settlement, *expiry)
-                        {
-                            opportunities.append(&mut verts);
-                        }
-                    }
-                    if self.config.strategy_filter.allows(StrategyKind::Butterfly) {
-                        if let Ok(mut flies) =
-                            self.detect_butterflies(instruments, *currency, *settlement, *expiry)
-                        {
-                            opportunities.append(&mut flies);
-                        }
-                    }
-                }
+    /// Adds a detector run on every scan after the built-in ones, gated by
+    /// the strategy filter like them.
+    pub fn register(&mut self, detector: Box<dyn Detector>) {
+        self.detectors.push(detector);
+    }
+
+    /// Replaces the runtime threshold overrides applied on top of the config.
+    pub fn set_thresholds(&self, thresholds: RuntimeThresholds) {
+        *self.overrides.lock() = thresholds;
+    }
+
+    /// Sets the futures basis and perpetual funding jelly rolls of
+    /// `currency` are valued against.
+    pub fn set_carry(&self, currency: crate::model::Currency, curve: CarryCurve) {
+        self.carry.lock().insert(currency, curve);
+    }
+
+    /// Sets the `eur_usd` index EURR books are converted to USD through.
+    pub fn set_eur_usd(&self, rate: Decimal) {
+        *self.eur_usd.lock() = Some(rate).filter(|rate| *rate > Decimal::ZERO);
+    }
+
+    /// USD per unit of the currency `settlement`'s books quote in; `None`
+    /// for EURR until [`Self::set_eur_usd`] is called.
+    fn fx_usd(&self, settlement: SettlementCurrency) -> Option<Decimal> {
+        match settlement {
+            SettlementCurrency::Eurr => *self.eur_usd.lock(),
+            SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Coin => {
+                Some(Decimal::ONE)
             }
         }
+    }
 
-        if self.config.strategy_filter.allows(StrategyKind::Calendar) {
-            if let Ok(mut calendars) = self.detect_calendars(snapshot) {
-                opportunities.append(&mut calendars);
+    /// USD credit a fairly priced jelly roll earns per unit of underlying
+    /// from the forward spread between `near` and `far`. Coin-settled legs
+    /// are inverse, so their synthetic forward is worth `(F - K) / F` coin.
+    fn carry_credit(
+        &self,
+        currency: crate::model::Currency,
+        settlement: SettlementCurrency,
+        strike: Decimal,
+        (near, far): (chrono::DateTime<Utc>, chrono::DateTime<Utc>),
+        index: Decimal,
+        now: chrono::DateTime<Utc>,
+    ) -> Option<Decimal> {
+        let carry = self.carry.lock();
+        let curve = carry.get(&currency)?;
+        let near = curve.forward(near, index, now)?;
+        let far = curve.forward(far, index, now)?;
+        match settlement {
+            SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                Some(far - near)
+            }
+            SettlementCurrency::Coin if near.is_zero() || far.is_zero() => None,
+            SettlementCurrency::Coin => {
+                Some(index * strike * (Decimal::ONE / near - Decimal::ONE / far))
             }
         }
+    }
 
-        if self.config.strategy_filter.allows(StrategyKind::Box) {
-            if let Ok(mut boxes) = self.detect_boxes(snapshot) {
-                opportunities.append(&mut boxes);
-            }
+    fn requirements(&self, strategy: StrategyKind) -> MinEdgeRequirements {
+        let mut requirements = self.config.requirements(strategy);
+        let overrides = *self.overrides.lock();
+        if let Some(min_edge_usd) = overrides.min_edge_usd {
+            requirements.min_edge_usd = min_edge_usd;
+        }
+        if let Some(min_edge_ratio) = overrides.min_edge_ratio {
+            requirements.min_edge_ratio = min_edge_ratio;
+        }
+        requirements
+    }
+
+    /// [`Self::requirements`] with the minimum edge in the currency
+    /// `settlement`'s books quote in, which detectors value edges in until
+    /// [`Self::price_in_usd`] converts them.
+    fn requirements_in(
+        &self,
+        strategy: StrategyKind,
+        settlement: SettlementCurrency,
+    ) -> MinEdgeRequirements {
+        let mut requirements = self.requirements(strategy);
+        if let Some(rate) = self.fx_usd(settlement) {
+            requirements.min_edge_usd /= rate;
+        }
+        requirements
+    }
+
+    /// Rejections recorded since the previous call; `None` unless
+    /// `--diagnose-rejections` is set.
+    pub fn take_rejections(&self) -> Option<RejectionSummary> {
+        self.config
+            .diagnose_rejections
+            .then(|| std::mem::take(&mut *self.rejections.lock()))
+    }
+
+    /// Summarises the scan that found `opportunities` among `instruments`
+    /// quotes in `duration`, draining the rejections recorded since the
+    /// previous summary.
+    pub fn take_scan_summary(
+        &self,
+        instruments: usize,
+        opportunities: &[StrategyOpportunity],
+        duration: std::time::Duration,
+    ) -> ScanSummary {
+        let rejections = std::mem::take(&mut *self.rejections.lock());
+        ScanSummary::new(instruments, opportunities, &rejections, duration)
+    }
+
+    /// Drops the rejections recorded so far, e.g. by incremental passes
+    /// ahead of a full scan.
+    pub fn clear_rejections(&self) {
+        *self.rejections.lock() = RejectionSummary::default();
+    }
+
+    fn reject(&self, strategy: StrategyKind, reason: RejectionReason) {
+        self.rejections.lock().record(strategy, reason);
+    }
+
+    /// Maker mode rests orders on the book, so legs are charged maker rates.
+    fn fill_role(&self) -> FillRole {
+        match self.config.execution_mode {
+            ExecutionMode::Maker => FillRole::Maker,
+            ExecutionMode::Taker => FillRole::Taker,
         }
+    }
+
+    pub fn scan(&self, snapshot: &[InstrumentSnapshot]) -> Vec<StrategyOpportunity> {
+        self.scan_at(snapshot, Utc::now())
+    }
 
-        if self.config.strategy_filter.allows(StrategyKind::JellyRoll) {
-            if let Ok(mut rolls) = self.detect_jelly_rolls(snapshot) {
-                opportunities.append(&mut rolls);
+    /// Scans as of `now`, which drives DTE filtering, daily-option fee rules and
+    /// hedge greeks; backtests pass the replayed timestamp here.
+    pub fn scan_at(
+        &self,
+        snapshot: &[InstrumentSnapshot],
+        now: chrono::DateTime<Utc>,
+    ) -> Vec<StrategyOpportunity> {
+        let mut opportunities = self.scan_expiry_slice(snapshot, now);
+        opportunities.append(&mut self.scan_strike_slice(snapshot, now));
+        opportunities.sort_by_key(|opp| std::cmp::Reverse(opp.net_edge_usd));
+        opportunities
+    }
+
+    /// Scans the chain one index slice at a time instead of cloning the whole
+    /// snapshot; expiries outside the DTE filter are never materialised.
+    pub fn scan_chain_at(
+        &self,
+        chain: &OptionChain,
+        now: chrono::DateTime<Utc>,
+    ) -> Vec<StrategyOpportunity> {
+        let filter = &self.config.instrument_filter;
+        let mut opportunities = Vec::new();
+        for (currency, expiry) in chain.expiry_keys() {
+            if filter.allows_expiry(expiry, now) {
+                let slice = chain.expiry_group(currency, expiry);
+                opportunities.append(&mut self.scan_expiry_slice(&slice, now));
+            }
+        }
+        if self.enabled(SliceKind::Strike).next().is_some() {
+            for (currency, strike) in chain.strike_keys() {
+                let slice = chain.strike_slice(currency, strike);
+                opportunities.append(&mut self.scan_strike_slice(&slice, now));
             }
         }
+        opportunities.sort_by_key(|opp| std::cmp::Reverse(opp.net_edge_usd));
+        opportunities
+    }
 
-        opportunities.sort_by(|a, b| b.net_edge_usd.cmp(&a.net_edge_usd));
+    /// Verticals, butterflies and boxes, whose legs all share an expiry; any
+    /// set of whole expiry slices can be scanned independently. Unsorted.
+    pub fn scan_expiry_slice(
+        &self,
+        snapshot: &[InstrumentSnapshot],
+        now: chrono::DateTime<Utc>,
+    ) -> Vec<StrategyOpportunity> {
+        let quoted = self.filtered(snapshot, now);
+        let references = reference_indices(&quoted);
+        let snapshot = with_reference_index(&quoted, &references);
+        let snapshot = snapshot.as_ref();
+        let mut opportunities = self.run_detectors(SliceKind::Expiry, snapshot, now);
+        self.price_in_usd(&mut opportunities);
+        self.check_index_divergence(&mut opportunities, &quoted, &references);
+        self.weigh_staleness(&mut opportunities, snapshot, now);
+        attach_estimates(&mut opportunities, snapshot, now);
+        self.check_annualized_return(&mut opportunities);
         opportunities
     }
 
+    /// Calendars and jelly rolls, whose legs all share a strike; any set of
+    /// whole strike slices can be scanned independently. Unsorted.
+    pub fn scan_strike_slice(
+        &self,
+        snapshot: &[InstrumentSnapshot],
+        now: chrono::DateTime<Utc>,
+    ) -> Vec<StrategyOpportunity> {
+        let quoted = self.filtered(snapshot, now);
+        let references = reference_indices(&quoted);
+        let snapshot = with_reference_index(&quoted, &references);
+        let snapshot = snapshot.as_ref();
+        let mut opportunities = self.run_detectors(SliceKind::Strike, snapshot, now);
+        self.price_in_usd(&mut opportunities);
+        self.check_index_divergence(&mut opportunities, &quoted, &references);
+        self.weigh_staleness(&mut opportunities, snapshot, now);
+        attach_estimates(&mut opportunities, snapshot, now);
+        self.check_annualized_return(&mut opportunities);
+        opportunities
+    }
+
+    /// Detectors of `slice` the strategy filter lets run.
+    fn enabled(&self, slice: SliceKind) -> impl Iterator<Item = &dyn Detector> {
+        self.detectors
+            .iter()
+            .map(|detector| detector.as_ref())
+            .filter(move |detector| {
+                detector.slice() == slice && self.config.strategy_filter.allows(detector.strategy())
+            })
+    }
+
+    fn run_detectors(
+        &self,
+        slice: SliceKind,
+        snapshot: &[InstrumentSnapshot],
+        now: chrono::DateTime<Utc>,
+    ) -> Vec<StrategyOpportunity> {
+        let view = ChainView::new(self, snapshot, now);
+        self.enabled(slice)
+            .flat_map(|detector| detector.scan(&view))
+            .collect()
+    }
+
+    /// Converts the `_usd` figures of EURR opportunities, which detectors
+    /// value in euros, through the `eur_usd` index.
+    fn price_in_usd(&self, opportunities: &mut [StrategyOpportunity]) {
+        for opp in opportunities {
+            let Some(rate) = self
+                .fx_usd(opp.settlement)
+                .filter(|rate| *rate != Decimal::ONE)
+            else {
+                continue;
+            };
+            opp.fx_usd = rate;
+            opp.net_edge_usd *= rate;
+            opp.notional_usd *= rate;
+            let fees = &mut opp.fee_breakdown;
+            for leg in &mut fees.legs {
+                leg.trade_fee_usd *= rate;
+            }
+            fees.combo_discount_usd *= rate;
+            fees.delivery_fee_usd *= rate;
+            fees.hedge_fee_usd *= rate;
+            fees.total_usd *= rate;
+            if let Some(hedge) = opp.execution_plan.hedge.as_mut() {
+                hedge.notional_usd *= rate;
+            }
+        }
+    }
+
+    /// Drops candidates with a leg whose own quote carried an index price
+    /// more than `--max-index-divergence-bps` away from the slice's
+    /// reference; `quoted` is the slice before the reference was applied.
+    fn check_index_divergence(
+        &self,
+        opportunities: &mut Vec<StrategyOpportunity>,
+        quoted: &[InstrumentSnapshot],
+        references: &HashMap<IndexKey, Decimal>,
+    ) {
+        let Some(max_bps) = self.config.max_index_divergence_bps else {
+            return;
+        };
+        let own_index: HashMap<&str, (IndexKey, Decimal)> = quoted
+            .iter()
+            .map(|inst| {
+                (
+                    inst.instrument.instrument_name.as_str(),
+                    (index_key(inst), inst.quote.index_price),
+                )
+            })
+            .collect();
+        opportunities.retain(|opp| {
+            let diverged = opp.legs.iter().any(|leg| {
+                let Some((key, index)) = own_index.get(leg.instrument_name.as_str()) else {
+                    return false;
+                };
+                references
+                    .get(key)
+                    .and_then(|reference| index_divergence_bps(*index, *reference))
+                    .is_some_and(|bps| bps > max_bps)
+            });
+            if diverged {
+                self.reject(opp.strategy, RejectionReason::IndexDivergence);
+            }
+            !diverged
+        });
+    }
+
+    /// Drops candidates whose annualized return falls short of
+    /// `--min-annualized-return` (in percent), or that have none.
+    fn check_annualized_return(&self, opportunities: &mut Vec<StrategyOpportunity>) {
+        let Some(min_pct) = self.config.min_annualized_return else {
+            return;
+        };
+        opportunities.retain(|opp| {
+            let keep = opp
+                .annualized_return
+                .is_some_and(|rate| rate * 100.0 >= min_pct);
+            if !keep {
+                self.reject(opp.strategy, RejectionReason::BelowMinReturn);
+            }
+            keep
+        });
+    }
+
+    /// Records each leg's quote age, drops candidates whose legs were quoted
+    /// more than `--max-leg-skew-ms` apart, and charges the rest
+    /// `--staleness-penalty-bps` of notional per second of the oldest quote's
+    /// age, re-checking the discounted edge against the strategy's minimum.
+    fn weigh_staleness(
+        &self,
+        opportunities: &mut Vec<StrategyOpportunity>,
+        snapshot: &[InstrumentSnapshot],
+        now: chrono::DateTime<Utc>,
+    ) {
+        let quoted_at: HashMap<&str, chrono::DateTime<Utc>> = snapshot
+            .iter()
+            .map(|inst| {
+                (
+                    inst.instrument.instrument_name.as_str(),
+                    inst.quote.timestamp,
+                )
+            })
+            .collect();
+        opportunities.retain_mut(|opp| {
+            let stamps: Vec<_> = opp
+                .legs
+                .iter()
+                .filter_map(|leg| quoted_at.get(leg.instrument_name.as_str()).copied())
+                .collect();
+            let (Some(oldest), Some(newest)) = (stamps.iter().min(), stamps.iter().max()) else {
+                return true;
+            };
+            opp.leg_quote_ages_ms = stamps
+                .iter()
+                .map(|at| (now - *at).num_milliseconds().max(0))
+                .collect();
+            let skew_ms = (*newest - *oldest).num_milliseconds();
+            if let Some(max_skew_ms) = self.config.max_leg_skew_ms {
+                if skew_ms > max_skew_ms as i64 {
+                    self.reject(opp.strategy, RejectionReason::StaleQuotes);
+                    return false;
+                }
+            }
+            let age_secs = (now - *oldest).num_milliseconds().max(0) as f64 / 1000.0;
+            let penalty_bps = self.config.staleness_penalty_bps * age_secs;
+            let Some(penalty_usd) = Decimal::from_f64(penalty_bps / 10_000.0)
+                .map(|fraction| (opp.notional_usd * fraction).round_dp(6))
+                .filter(|penalty| *penalty > Decimal::ZERO)
+            else {
+                return true;
+            };
+            let edge_usd = opp.net_edge_usd - penalty_usd;
+            if edge_usd < self.requirements(opp.strategy).min_edge_usd {
+                self.reject(opp.strategy, RejectionReason::StaleQuotes);
+                return false;
+            }
+            if !opp.net_edge_usd.is_zero() {
+                opp.net_edge_native *= edge_usd / opp.net_edge_usd;
+            }
+            opp.net_edge_usd = edge_usd;
+            opp.staleness_penalty_usd = penalty_usd;
+            opp.edge_bps = compute_edge_bps(
+                edge_usd,
+                opp.size_contracts,
+                opp.reference_index * opp.fx_usd,
+                opp.settlement,
+            );
+            true
+        });
+    }
+
+    fn filtered<'s>(
+        &self,
+        snapshot: &'s [InstrumentSnapshot],
+        now: chrono::DateTime<Utc>,
+    ) -> Cow<'s, [InstrumentSnapshot]> {
+        let filter = &self.config.instrument_filter;
+        let priced =
+            |inst: &InstrumentSnapshot| self.fx_usd(inst.instrument.settlement_currency).is_some();
+        if filter.is_active() || !snapshot.iter().all(priced) {
+            Cow::Owned(
+                snapshot
+                    .iter()
+                    .filter(|inst| priced(inst) && filter.allows(inst, now))
+                    .cloned()
+                    .collect(),
+            )
+        } else {
+            Cow::Borrowed(snapshot)
+        }
+    }
+
     fn detect_verticals(
         &self,
-        instruments: &[InstrumentSnapshot],
-        currency: crate::model::Currency,
-        settlement: SettlementCurrency,
-        expiry: chrono::DateTime<Utc>,
+        ladder: &ExpiryLadder<'_>,
+        now: chrono::DateTime<Utc>,
     ) -> Result<Vec<StrategyOpportunity>> {
-        let mut by_strike: Vec<_> = instruments.iter().collect();
-        by_strike.sort_by(|a, b| a.instrument.strike.cmp(&b.instrument.strike));
+        let (currency, settlement, expiry) = (ladder.currency, ladder.settlement, ladder.expiry);
+        let requirements = self.requirements_in(StrategyKind::Vertical, settlement);
+        let by_strike = &ladder.legs;
         let mut results = Vec::new();
         let min_depth = Decimal::from(self.config.min_depth_contracts);
 
@@ -115,22 +538,22 @@ impl<'a> DetectorSuite<'a> {
                 OptionKind::Call => {
                     let ask_low = match ask_low {
                         Some(level) => level,
-                        None => continue,
+                        None => reject!(self, Vertical, NoDepth),
                     };
                     let bid_high = match bid_high {
                         Some(level) => level,
-                        None => continue,
+                        None => reject!(self, Vertical, NoDepth),
                     };
                     (low, ask_low, high, bid_high)
                 }
                 OptionKind::Put => {
                     let ask_high = match ask_high {
                         Some(level) => level,
-                        None => continue,
+                        None => reject!(self, Vertical, NoDepth),
                     };
                     let bid_low = match bid_low {
                         Some(level) => level,
-                        None => continue,
+                        None => reject!(self, Vertical, NoDepth),
                     };
                     (high, ask_high, low, bid_low)
                 }
@@ -139,9 +562,9 @@ impl<'a> DetectorSuite<'a> {
             let size_contracts = buy_quote
                 .amount
                 .min(sell_quote.amount)
-                .min(self.max_contracts_from_ticket(buy_inst));
+                .min(self.max_contracts(&requirements, buy_inst));
             if size_contracts <= Decimal::ZERO {
-                continue;
+                reject!(self, Vertical, NoDepth);
             }
 
             let debit_native = buy_quote.price * size_contracts * buy_inst.instrument.contract_size
@@ -152,7 +575,9 @@ impl<'a> DetectorSuite<'a> {
 
             let reference_index = buy_inst.quote.index_price;
             let debit_usd = match settlement {
-                SettlementCurrency::Usdc => debit_native,
+                SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                    debit_native
+                }
                 SettlementCurrency::Coin => debit_native * reference_index,
             };
 
@@ -163,11 +588,13 @@ impl<'a> DetectorSuite<'a> {
             let max_payout_usd = strikes_diff * size_contracts * low.instrument.contract_size;
             let tolerance_usd = Decimal::new(1, 6);
             if debit_usd > max_payout_usd + tolerance_usd {
-                continue;
+                reject!(self, Vertical, NegativeEdge);
             }
 
             let max_payout_native = match settlement {
-                SettlementCurrency::Usdc => max_payout_usd,
+                SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                    max_payout_usd
+                }
                 SettlementCurrency::Coin => {
                     if reference_index.is_zero() {
                         Decimal::ZERO
@@ -211,7 +638,8 @@ impl<'a> DetectorSuite<'a> {
                         instrument_name: buy_inst.instrument.instrument_name.clone(),
                         side: ComboSide::Buy,
                         settlement,
-                        role: FillRole::Taker,
+                        role: self.fill_role(),
+                        venue: ExecutionVenue::Screen,
                         option_price: buy_quote.price,
                         index_price: buy_inst.quote.index_price,
                         contracts: size_contracts,
@@ -220,13 +648,15 @@ impl<'a> DetectorSuite<'a> {
                         is_daily: is_daily_option(
                             &buy_inst.instrument.instrument_name,
                             buy_inst.instrument.expiry,
+                            now,
                         ),
                     },
                     LegFeeInput {
                         instrument_name: sell_inst.instrument.instrument_name.clone(),
                         side: ComboSide::Sell,
                         settlement,
-                        role: FillRole::Taker,
+                        role: self.fill_role(),
+                        venue: ExecutionVenue::Screen,
                         option_price: sell_quote.price,
                         index_price: sell_inst.quote.index_price,
                         contracts: size_contracts,
@@ -235,24 +665,26 @@ impl<'a> DetectorSuite<'a> {
                         is_daily: is_daily_option(
                             &sell_inst.instrument.instrument_name,
                             sell_inst.instrument.expiry,
+                            now,
                         ),
                     },
                 ],
                 hold_to_expiry: self.config.hold_to_expiry,
+                hedge: None,
             };
 
             let fee_breakdown = self.fee_engine.compute(fee_ctx)?;
             let net_edge_usd = max_payout_usd - debit_usd - fee_breakdown.total_usd;
             if net_edge_usd <= Decimal::ZERO {
-                continue;
+                reject!(self, Vertical, NegativeEdge);
             }
-            if net_edge_usd < self.config.min_edge_usd {
-                continue;
+            if net_edge_usd < requirements.min_edge_usd {
+                reject!(self, Vertical, BelowMinEdge);
             }
             let fee_guard = fee_breakdown.total_usd.max(dec!(0.01));
             let edge_ratio = (net_edge_usd / fee_guard).to_f64().unwrap_or(0.0);
-            if edge_ratio < self.config.min_edge_ratio {
-                continue;
+            if edge_ratio < requirements.min_edge_ratio {
+                reject!(self, Vertical, RatioTooLow);
             }
 
             let execution_plan = ComboExecutionPlan {
@@ -272,9 +704,12 @@ impl<'a> DetectorSuite<'a> {
                 tif: OrderTimeInForce::IOC,
                 price_limit: debit_native,
                 dry_run: self.config.dry_run,
+                hedge: None,
             };
 
             let opportunity = StrategyOpportunity {
+                id: String::new(),
+                schema_version: OPPORTUNITY_SCHEMA_VERSION,
                 strategy: StrategyKind::Vertical,
                 currency,
                 settlement,
@@ -286,7 +721,9 @@ impl<'a> DetectorSuite<'a> {
                 max_payout: max_payout_native,
                 fee_breakdown,
                 net_edge_native: match settlement {
-                    SettlementCurrency::Usdc => net_edge_usd,
+                    SettlementCurrency::Usdc
+                    | SettlementCurrency::Usdt
+                    | SettlementCurrency::Eurr => net_edge_usd,
                     SettlementCurrency::Coin => {
                         if reference_index.is_zero() {
                             Decimal::ZERO
@@ -306,6 +743,12 @@ impl<'a> DetectorSuite<'a> {
                 ),
                 size_contracts,
                 execution_plan,
+                estimated_margin_usd: None,
+                size_ladder: Vec::new(),
+                leg_quote_ages_ms: Vec::new(),
+                staleness_penalty_usd: Decimal::ZERO,
+                annualized_return: None,
+                fx_usd: Decimal::ONE,
             };
             results.push(opportunity);
         }
@@ -313,13 +756,12 @@ impl<'a> DetectorSuite<'a> {
     }
     fn detect_butterflies(
         &self,
-        instruments: &[InstrumentSnapshot],
-        currency: crate::model::Currency,
-        settlement: SettlementCurrency,
-        expiry: chrono::DateTime<Utc>,
+        ladder: &ExpiryLadder<'_>,
+        now: chrono::DateTime<Utc>,
     ) -> Result<Vec<StrategyOpportunity>> {
-        let mut by_strike: Vec<_> = instruments.iter().collect();
-        by_strike.sort_by(|a, b| a.instrument.strike.cmp(&b.instrument.strike));
+        let (currency, settlement, expiry) = (ladder.currency, ladder.settlement, ladder.expiry);
+        let requirements = self.requirements_in(StrategyKind::Butterfly, settlement);
+        let by_strike = &ladder.legs;
         let mut results = Vec::new();
         for window in by_strike.windows(3) {
             let low = window[0];
@@ -329,32 +771,34 @@ impl<'a> DetectorSuite<'a> {
                 Some(level) if level.amount >= Decimal::from(self.config.min_depth_contracts) => {
                     level
                 }
-                _ => continue,
+                _ => reject!(self, Butterfly, NoDepth),
             };
             let bid_mid = match &mid.quote.best_bid {
                 Some(level) if level.amount >= Decimal::from(self.config.min_depth_contracts) => {
                     level
                 }
-                _ => continue,
+                _ => reject!(self, Butterfly, NoDepth),
             };
             let ask_high = match &high.quote.best_ask {
                 Some(level) if level.amount >= Decimal::from(self.config.min_depth_contracts) => {
                     level
                 }
-                _ => continue,
+                _ => reject!(self, Butterfly, NoDepth),
             };
             let size_contracts = ask_low
                 .amount
                 .min(ask_high.amount)
                 .min(bid_mid.amount / dec!(2))
-                .min(self.max_contracts_from_ticket(low));
+                .min(self.max_contracts(&requirements, low));
             if size_contracts <= Decimal::ZERO {
-                continue;
+                reject!(self, Butterfly, NoDepth);
             }
             let fly_cost = ask_low.price + ask_high.price - (bid_mid.price * dec!(2));
             let debit_native = fly_cost * size_contracts * low.instrument.contract_size;
             let debit_usd = match settlement {
-                SettlementCurrency::Usdc => debit_native,
+                SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                    debit_native
+                }
                 SettlementCurrency::Coin => debit_native * low.quote.index_price,
             };
 
@@ -403,7 +847,8 @@ impl<'a> DetectorSuite<'a> {
                         instrument_name: low.instrument.instrument_name.clone(),
                         side: ComboSide::Buy,
                         settlement,
-                        role: FillRole::Taker,
+                        role: self.fill_role(),
+                        venue: ExecutionVenue::Screen,
                         option_price: ask_low.price,
                         index_price: low.quote.index_price,
                         contracts: size_contracts,
@@ -412,13 +857,15 @@ impl<'a> DetectorSuite<'a> {
                         is_daily: is_daily_option(
                             &low.instrument.instrument_name,
                             low.instrument.expiry,
+                            now,
                         ),
                     },
                     LegFeeInput {
                         instrument_name: mid.instrument.instrument_name.clone(),
                         side: ComboSide::Sell,
                         settlement,
-                        role: FillRole::Taker,
+                        role: self.fill_role(),
+                        venue: ExecutionVenue::Screen,
                         option_price: bid_mid.price,
                         index_price: mid.quote.index_price,
                         contracts: size_contracts * dec!(2),
@@ -427,13 +874,15 @@ impl<'a> DetectorSuite<'a> {
                         is_daily: is_daily_option(
                             &mid.instrument.instrument_name,
                             mid.instrument.expiry,
+                            now,
                         ),
                     },
                     LegFeeInput {
                         instrument_name: high.instrument.instrument_name.clone(),
                         side: ComboSide::Buy,
                         settlement,
-                        role: FillRole::Taker,
+                        role: self.fill_role(),
+                        venue: ExecutionVenue::Screen,
                         option_price: ask_high.price,
                         index_price: high.quote.index_price,
                         contracts: size_contracts,
@@ -442,24 +891,26 @@ impl<'a> DetectorSuite<'a> {
                         is_daily: is_daily_option(
                             &high.instrument.instrument_name,
                             high.instrument.expiry,
+                            now,
                         ),
                     },
                 ],
                 hold_to_expiry: self.config.hold_to_expiry,
+                hedge: None,
             };
             let fee_breakdown = self.fee_engine.compute(fee_ctx)?;
             let net_edge_usd = -(debit_usd + fee_breakdown.total_usd);
             if net_edge_usd <= Decimal::ZERO {
-                continue;
+                reject!(self, Butterfly, NegativeEdge);
             }
-            if net_edge_usd < self.config.min_edge_usd {
-                continue;
+            if net_edge_usd < requirements.min_edge_usd {
+                reject!(self, Butterfly, BelowMinEdge);
             }
             let edge_ratio = (net_edge_usd / fee_breakdown.total_usd.max(dec!(0.01)))
                 .to_f64()
                 .unwrap_or(0.0);
-            if edge_ratio < self.config.min_edge_ratio {
-                continue;
+            if edge_ratio < requirements.min_edge_ratio {
+                reject!(self, Butterfly, RatioTooLow);
             }
 
             let execution_plan = ComboExecutionPlan {
@@ -479,8 +930,11 @@ impl<'a> DetectorSuite<'a> {
                 tif: OrderTimeInForce::IOC,
                 price_limit: debit_native,
                 dry_run: self.config.dry_run,
+                hedge: None,
             };
             let opportunity = StrategyOpportunity {
+                id: String::new(),
+                schema_version: OPPORTUNITY_SCHEMA_VERSION,
                 strategy: StrategyKind::Butterfly,
                 currency,
                 settlement,
@@ -498,7 +952,9 @@ impl<'a> DetectorSuite<'a> {
                     * low.instrument.contract_size,
                 fee_breakdown,
                 net_edge_native: match settlement {
-                    SettlementCurrency::Usdc => net_edge_usd,
+                    SettlementCurrency::Usdc
+                    | SettlementCurrency::Usdt
+                    | SettlementCurrency::Eurr => net_edge_usd,
                     SettlementCurrency::Coin => {
                         if low.quote.index_price.is_zero() {
                             Decimal::ZERO
@@ -518,6 +974,12 @@ impl<'a> DetectorSuite<'a> {
                 ),
                 size_contracts,
                 execution_plan,
+                estimated_margin_usd: None,
+                size_ladder: Vec::new(),
+                leg_quote_ages_ms: Vec::new(),
+                staleness_penalty_usd: Decimal::ZERO,
+                annualized_return: None,
+                fx_usd: Decimal::ONE,
             };
             results.push(opportunity);
         }
@@ -526,262 +988,238 @@ impl<'a> DetectorSuite<'a> {
 
     fn detect_calendars(
         &self,
-        snapshot: &[InstrumentSnapshot],
+        pairs: &[(&InstrumentSnapshot, &InstrumentSnapshot)],
+        now: chrono::DateTime<Utc>,
     ) -> Result<Vec<StrategyOpportunity>> {
-        let mut grouped: HashMap<
-            (
-                crate::model::Currency,
-                Decimal,
-                SettlementCurrency,
-                OptionKind,
-            ),
-            Vec<&InstrumentSnapshot>,
-        > = HashMap::new();
-        for inst in snapshot {
-            grouped
-                .entry((
-                    inst.instrument.currency,
-                    inst.instrument.strike,
-                    inst.instrument.settlement_currency,
-                    inst.instrument.option_kind,
-                ))
-                .or_default()
-                .push(inst);
-        }
         let mut results = Vec::new();
-        for ((currency, _strike, settlement, _option_kind), instruments) in grouped {
-            if instruments.len() < 2 {
-                continue;
+        for &(near, far) in pairs {
+            let currency = near.instrument.currency;
+            let settlement = near.instrument.settlement_currency;
+            let requirements = self.requirements_in(StrategyKind::Calendar, settlement);
+            let index_price = near.quote.index_price;
+            let near_bid = match &near.quote.best_bid {
+                Some(level) if level.amount >= Decimal::from(self.config.min_depth_contracts) => {
+                    level
+                }
+                _ => reject!(self, Calendar, NoDepth),
+            };
+            let far_ask = match &far.quote.best_ask {
+                Some(level) if level.amount >= Decimal::from(self.config.min_depth_contracts) => {
+                    level
+                }
+                _ => reject!(self, Calendar, NoDepth),
+            };
+            // Books list different contract sizes, so both legs are
+            // sized to the same underlying amount.
+            let underlying = (near_bid.amount * near.instrument.contract_size)
+                .min(far_ask.amount * far.instrument.contract_size)
+                .min(self.max_contracts(&requirements, near) * near.instrument.contract_size);
+            let size_contracts = underlying / near.instrument.contract_size;
+            let far_contracts = underlying / far.instrument.contract_size;
+            if size_contracts <= Decimal::ZERO {
+                reject!(self, Calendar, NoDepth);
             }
-            if self.config.strategy_filter.allows(StrategyKind::Calendar) {
-                let mut by_expiry: Vec<_> = instruments.iter().collect();
-                by_expiry.sort_by(|a, b| a.instrument.expiry.cmp(&b.instrument.expiry));
-                for window in by_expiry.windows(2) {
-                    let near = window[0];
-                    let far = window[1];
-                    if near.instrument.expiry == far.instrument.expiry {
-                        continue;
-                    }
-                    let near_bid = match &near.quote.best_bid {
-                        Some(level)
-                            if level.amount >= Decimal::from(self.config.min_depth_contracts) =>
-                        {
-                            level
-                        }
-                        _ => continue,
-                    };
-                    let far_ask = match &far.quote.best_ask {
-                        Some(level)
-                            if level.amount >= Decimal::from(self.config.min_depth_contracts) =>
-                        {
-                            level
-                        }
-                        _ => continue,
-                    };
-                    let size_contracts = near_bid
-                        .amount
-                        .min(far_ask.amount)
-                        .min(self.max_contracts_from_ticket(near));
-                    if size_contracts <= Decimal::ZERO {
-                        continue;
-                    }
-                    let credit_native =
-                        near_bid.price * size_contracts * near.instrument.contract_size
-                            - far_ask.price * size_contracts * far.instrument.contract_size;
-                    let credit_usd = match settlement {
-                        SettlementCurrency::Usdc => credit_native,
-                        SettlementCurrency::Coin => credit_native * near.quote.index_price,
-                    };
+            let far_native = far_ask.price * far_contracts * far.instrument.contract_size;
+            let far_cost = if far.instrument.settlement_currency == settlement {
+                far_native
+            } else {
+                let far_usd = match far.instrument.settlement_currency {
+                    SettlementCurrency::Usdc
+                    | SettlementCurrency::Usdt
+                    | SettlementCurrency::Eurr => far_native,
+                    SettlementCurrency::Coin => far_native * index_price,
+                };
+                native_from_usd(far_usd, settlement, index_price)
+            };
+            let credit_native =
+                near_bid.price * size_contracts * near.instrument.contract_size - far_cost;
+            let credit_usd = match settlement {
+                SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                    credit_native
+                }
+                SettlementCurrency::Coin => credit_native * index_price,
+            };
 
-                    if credit_usd <= Decimal::ZERO {
-                        continue;
-                    }
+            if credit_usd <= Decimal::ZERO {
+                reject!(self, Calendar, NegativeEdge);
+            }
 
-                    let legs = vec![
-                        ComboLeg {
-                            instrument_name: near.instrument.instrument_name.clone(),
-                            ratio: 1,
-                            side: ComboSide::Sell,
-                        },
-                        ComboLeg {
-                            instrument_name: far.instrument.instrument_name.clone(),
-                            ratio: 1,
-                            side: ComboSide::Buy,
-                        },
-                    ];
-                    let touches = vec![
-                        LegTouch {
-                            instrument_name: near.instrument.instrument_name.clone(),
-                            side: ComboSide::Sell,
-                            price: near_bid.price,
-                            size_contracts,
-                        },
-                        LegTouch {
-                            instrument_name: far.instrument.instrument_name.clone(),
-                            side: ComboSide::Buy,
-                            price: far_ask.price,
-                            size_contracts,
-                        },
-                    ];
-                    let fee_ctx = FeeComputationContext {
-                        legs: vec![
-                            LegFeeInput {
-                                instrument_name: near.instrument.instrument_name.clone(),
-                                side: ComboSide::Sell,
-                                settlement,
-                                role: FillRole::Taker,
-                                option_price: near_bid.price,
-                                index_price: near.quote.index_price,
-                                contracts: size_contracts,
-                                contract_size: near.instrument.contract_size,
-                                expiry: near.instrument.expiry,
-                                is_daily: is_daily_option(
-                                    &near.instrument.instrument_name,
-                                    near.instrument.expiry,
-                                ),
-                            },
-                            LegFeeInput {
-                                instrument_name: far.instrument.instrument_name.clone(),
-                                side: ComboSide::Buy,
-                                settlement,
-                                role: FillRole::Taker,
-                                option_price: far_ask.price,
-                                index_price: far.quote.index_price,
-                                contracts: size_contracts,
-                                contract_size: far.instrument.contract_size,
-                                expiry: far.instrument.expiry,
-                                is_daily: is_daily_option(
-                                    &far.instrument.instrument_name,
-                                    far.instrument.expiry,
-                                ),
-                            },
-                        ],
-                        hold_to_expiry: self.config.hold_to_expiry,
-                    };
-                    let fee_breakdown = self.fee_engine.compute(fee_ctx)?;
-                    let net_edge_usd = credit_usd - fee_breakdown.total_usd;
-                    if net_edge_usd <= Decimal::ZERO {
-                        continue;
-                    }
-                    if net_edge_usd < self.config.min_edge_usd {
-                        continue;
-                    }
-                    let edge_ratio = (net_edge_usd / fee_breakdown.total_usd.max(dec!(0.01)))
-                        .to_f64()
-                        .unwrap_or(0.0);
-                    if edge_ratio < self.config.min_edge_ratio {
-                        continue;
-                    }
-                    let execution_plan = ComboExecutionPlan {
-                        create_payload: json!({
-                            "legs": legs.iter().map(|leg| {
-                                json!({
-                                    "instrument_name": leg.instrument_name,
-                                    "ratio": leg.ratio,
-                                    "direction": match leg.side {
-                                        ComboSide::Buy => "buy",
-                                        ComboSide::Sell => "sell",
-                                    },
-                                })
-                            }).collect::<Vec<_>>(),
-                            "amount": size_contracts,
-                        }),
-                        tif: OrderTimeInForce::IOC,
-                        price_limit: credit_native,
-                        dry_run: self.config.dry_run,
-                    };
-                    let opportunity = StrategyOpportunity {
-                        strategy: StrategyKind::Calendar,
-                        currency,
+            let legs = vec![
+                ComboLeg {
+                    instrument_name: near.instrument.instrument_name.clone(),
+                    ratio: 1,
+                    side: ComboSide::Sell,
+                },
+                ComboLeg {
+                    instrument_name: far.instrument.instrument_name.clone(),
+                    ratio: 1,
+                    side: ComboSide::Buy,
+                },
+            ];
+            let touches = vec![
+                LegTouch {
+                    instrument_name: near.instrument.instrument_name.clone(),
+                    side: ComboSide::Sell,
+                    price: near_bid.price,
+                    size_contracts,
+                },
+                LegTouch {
+                    instrument_name: far.instrument.instrument_name.clone(),
+                    side: ComboSide::Buy,
+                    price: far_ask.price,
+                    size_contracts: far_contracts,
+                },
+            ];
+            let hedge = self.delta_hedge(
+                &[
+                    (near, ComboSide::Sell, size_contracts, near_bid.price),
+                    (far, ComboSide::Buy, far_contracts, far_ask.price),
+                ],
+                currency,
+                settlement,
+                index_price,
+                now,
+            );
+            let fee_ctx = FeeComputationContext {
+                legs: vec![
+                    LegFeeInput {
+                        instrument_name: near.instrument.instrument_name.clone(),
+                        side: ComboSide::Sell,
                         settlement,
-                        expiry: vec![near.instrument.expiry, far.instrument.expiry],
-                        strikes: vec![near.instrument.strike],
-                        legs,
-                        touches,
-                        total_cost: credit_native,
-                        max_payout: Decimal::ZERO,
-                        fee_breakdown,
-                        net_edge_native: match settlement {
-                            SettlementCurrency::Usdc => net_edge_usd,
-                            SettlementCurrency::Coin => {
-                                if near.quote.index_price.is_zero() {
-                                    Decimal::ZERO
-                                } else {
-                                    net_edge_usd / near.quote.index_price
-                                }
-                            }
-                        },
-                        net_edge_usd,
-                        notional_usd: near.quote.index_price
-                            * size_contracts
-                            * near.instrument.contract_size,
-                        reference_index: near.quote.index_price,
-                        edge_bps: compute_edge_bps(
-                            net_edge_usd,
-                            size_contracts,
-                            near.quote.index_price,
-                            settlement,
+                        role: self.fill_role(),
+                        venue: ExecutionVenue::Screen,
+                        option_price: near_bid.price,
+                        index_price: near.quote.index_price,
+                        contracts: size_contracts,
+                        contract_size: near.instrument.contract_size,
+                        expiry: near.instrument.expiry,
+                        is_daily: is_daily_option(
+                            &near.instrument.instrument_name,
+                            near.instrument.expiry,
+                            now,
                         ),
-                        size_contracts,
-                        execution_plan,
-                    };
-                    results.push(opportunity);
-                }
+                    },
+                    LegFeeInput {
+                        instrument_name: far.instrument.instrument_name.clone(),
+                        side: ComboSide::Buy,
+                        settlement: far.instrument.settlement_currency,
+                        role: self.fill_role(),
+                        venue: ExecutionVenue::Screen,
+                        option_price: far_ask.price,
+                        index_price: far.quote.index_price,
+                        contracts: far_contracts,
+                        contract_size: far.instrument.contract_size,
+                        expiry: far.instrument.expiry,
+                        is_daily: is_daily_option(
+                            &far.instrument.instrument_name,
+                            far.instrument.expiry,
+                            now,
+                        ),
+                    },
+                ],
+                hold_to_expiry: self.config.hold_to_expiry,
+                hedge: hedge.as_ref().map(|(_, fee_input)| fee_input.clone()),
+            };
+            let fee_breakdown = self.fee_engine.compute(fee_ctx)?;
+            let net_edge_usd = credit_usd - fee_breakdown.total_usd;
+            if net_edge_usd <= Decimal::ZERO {
+                reject!(self, Calendar, NegativeEdge);
+            }
+            if net_edge_usd < requirements.min_edge_usd {
+                reject!(self, Calendar, BelowMinEdge);
+            }
+            let edge_ratio = (net_edge_usd / fee_breakdown.total_usd.max(dec!(0.01)))
+                .to_f64()
+                .unwrap_or(0.0);
+            if edge_ratio < requirements.min_edge_ratio {
+                reject!(self, Calendar, RatioTooLow);
             }
+            let execution_plan = ComboExecutionPlan {
+                create_payload: json!({
+                    "legs": legs.iter().map(|leg| {
+                        json!({
+                            "instrument_name": leg.instrument_name,
+                            "ratio": leg.ratio,
+                            "direction": match leg.side {
+                                ComboSide::Buy => "buy",
+                                ComboSide::Sell => "sell",
+                            },
+                        })
+                    }).collect::<Vec<_>>(),
+                    "amount": size_contracts,
+                }),
+                tif: OrderTimeInForce::IOC,
+                price_limit: credit_native,
+                dry_run: self.config.dry_run,
+                hedge: hedge.map(|(leg, _)| leg),
+            };
+            let opportunity = StrategyOpportunity {
+                id: String::new(),
+                schema_version: OPPORTUNITY_SCHEMA_VERSION,
+                strategy: StrategyKind::Calendar,
+                currency,
+                settlement,
+                expiry: vec![near.instrument.expiry, far.instrument.expiry],
+                strikes: vec![near.instrument.strike],
+                legs,
+                touches,
+                total_cost: credit_native,
+                max_payout: Decimal::ZERO,
+                fee_breakdown,
+                net_edge_native: match settlement {
+                    SettlementCurrency::Usdc
+                    | SettlementCurrency::Usdt
+                    | SettlementCurrency::Eurr => net_edge_usd,
+                    SettlementCurrency::Coin => {
+                        if near.quote.index_price.is_zero() {
+                            Decimal::ZERO
+                        } else {
+                            net_edge_usd / near.quote.index_price
+                        }
+                    }
+                },
+                net_edge_usd,
+                notional_usd: near.quote.index_price
+                    * size_contracts
+                    * near.instrument.contract_size,
+                reference_index: near.quote.index_price,
+                edge_bps: compute_edge_bps(
+                    net_edge_usd,
+                    size_contracts,
+                    near.quote.index_price,
+                    settlement,
+                ),
+                size_contracts,
+                execution_plan,
+                estimated_margin_usd: None,
+                size_ladder: Vec::new(),
+                leg_quote_ages_ms: Vec::new(),
+                staleness_penalty_usd: Decimal::ZERO,
+                annualized_return: None,
+                fx_usd: Decimal::ONE,
+            };
+            results.push(opportunity);
         }
         Ok(results)
     }
 
-    fn detect_boxes(&self, snapshot: &[InstrumentSnapshot]) -> Result<Vec<StrategyOpportunity>> {
-        let mut by_expiry: HashMap<
-            (
-                chrono::DateTime<Utc>,
-                SettlementCurrency,
-                crate::model::Currency,
-            ),
-            Vec<&InstrumentSnapshot>,
-        > = HashMap::new();
-        for inst in snapshot {
-            if inst.instrument.settlement_currency != SettlementCurrency::Usdc {
+    /// Boxes across adjacent strikes listing both a call and a put, on
+    /// linear books only.
+    fn detect_boxes(
+        &self,
+        ladders: &[PairLadder<'_>],
+        now: chrono::DateTime<Utc>,
+    ) -> Result<Vec<StrategyOpportunity>> {
+        let mut results = Vec::new();
+        for ladder in ladders {
+            if !ladder.settlement.is_linear() {
                 continue;
             }
-            by_expiry
-                .entry((
-                    inst.instrument.expiry,
-                    inst.instrument.settlement_currency,
-                    inst.instrument.currency,
-                ))
-                .or_default()
-                .push(inst);
-        }
-        let mut results = Vec::new();
-        for ((_expiry, settlement, currency), instruments) in by_expiry {
-            let mut calls: Vec<_> = instruments
-                .iter()
-                .filter(|inst| {
-                    matches!(inst.instrument.option_kind, crate::model::OptionKind::Call)
-                })
-                .collect();
-            let mut puts: Vec<_> = instruments
-                .iter()
-                .filter(|inst| matches!(inst.instrument.option_kind, crate::model::OptionKind::Put))
-                .collect();
-            calls.sort_by(|a, b| a.instrument.strike.cmp(&b.instrument.strike));
-            puts.sort_by(|a, b| a.instrument.strike.cmp(&b.instrument.strike));
-            for call_window in calls.windows(2) {
-                let c_low = call_window[0];
-                let c_high = call_window[1];
-                let p_low = puts
-                    .iter()
-                    .find(|p| p.instrument.strike == c_low.instrument.strike);
-                let p_high = puts
-                    .iter()
-                    .find(|p| p.instrument.strike == c_high.instrument.strike);
-                if p_low.is_none() || p_high.is_none() {
-                    continue;
-                }
-                let p_low = *p_low.unwrap();
-                let p_high = *p_high.unwrap();
+            let (settlement, currency) = (ladder.settlement, ladder.currency);
+            let requirements = self.requirements_in(StrategyKind::Box, settlement);
+            for window in ladder.pairs.windows(2) {
+                let (c_low, p_low) = (window[0].call, window[0].put);
+                let (c_high, p_high) = (window[1].call, window[1].put);
 
                 let ask_call_low = match &c_low.quote.best_ask {
                     Some(level)
@@ -789,7 +1227,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, Box, NoDepth),
                 };
                 let bid_call_high = match &c_high.quote.best_bid {
                     Some(level)
@@ -797,7 +1235,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, Box, NoDepth),
                 };
                 let ask_put_high = match &p_high.quote.best_ask {
                     Some(level)
@@ -805,7 +1243,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, Box, NoDepth),
                 };
                 let bid_put_low = match &p_low.quote.best_bid {
                     Some(level)
@@ -813,7 +1251,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, Box, NoDepth),
                 };
 
                 let size_contracts = ask_call_low
@@ -821,9 +1259,9 @@ impl<'a> DetectorSuite<'a> {
                     .min(bid_call_high.amount)
                     .min(ask_put_high.amount)
                     .min(bid_put_low.amount)
-                    .min(self.max_contracts_from_ticket(c_low));
+                    .min(self.max_contracts(&requirements, c_low));
                 if size_contracts <= Decimal::ZERO {
-                    continue;
+                    reject!(self, Box, NoDepth);
                 }
 
                 let legs = vec![
@@ -882,7 +1320,8 @@ impl<'a> DetectorSuite<'a> {
                             instrument_name: c_low.instrument.instrument_name.clone(),
                             side: ComboSide::Buy,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: ask_call_low.price,
                             index_price: c_low.quote.index_price,
                             contracts: size_contracts,
@@ -891,13 +1330,15 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &c_low.instrument.instrument_name,
                                 c_low.instrument.expiry,
+                                now,
                             ),
                         },
                         LegFeeInput {
                             instrument_name: c_high.instrument.instrument_name.clone(),
                             side: ComboSide::Sell,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: bid_call_high.price,
                             index_price: c_high.quote.index_price,
                             contracts: size_contracts,
@@ -906,13 +1347,15 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &c_high.instrument.instrument_name,
                                 c_high.instrument.expiry,
+                                now,
                             ),
                         },
                         LegFeeInput {
                             instrument_name: p_low.instrument.instrument_name.clone(),
                             side: ComboSide::Sell,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: bid_put_low.price,
                             index_price: p_low.quote.index_price,
                             contracts: size_contracts,
@@ -921,13 +1364,15 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &p_low.instrument.instrument_name,
                                 p_low.instrument.expiry,
+                                now,
                             ),
                         },
                         LegFeeInput {
                             instrument_name: p_high.instrument.instrument_name.clone(),
                             side: ComboSide::Buy,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: ask_put_high.price,
                             index_price: p_high.quote.index_price,
                             contracts: size_contracts,
@@ -936,10 +1381,12 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &p_high.instrument.instrument_name,
                                 p_high.instrument.expiry,
+                                now,
                             ),
                         },
                     ],
                     hold_to_expiry: self.config.hold_to_expiry,
+                    hedge: None,
                 };
                 let fee_breakdown = self.fee_engine.compute(fee_ctx)?;
 
@@ -952,10 +1399,10 @@ impl<'a> DetectorSuite<'a> {
                 let combo_price_usd = combo_price * size_contracts * c_low.instrument.contract_size;
                 let net_edge_usd = fair_value - combo_price_usd - fee_breakdown.total_usd;
                 if net_edge_usd <= Decimal::ZERO {
-                    continue;
+                    reject!(self, Box, NegativeEdge);
                 }
-                if net_edge_usd < self.config.min_edge_usd {
-                    continue;
+                if net_edge_usd < requirements.min_edge_usd {
+                    reject!(self, Box, BelowMinEdge);
                 }
 
                 let execution_plan = ComboExecutionPlan {
@@ -975,9 +1422,12 @@ impl<'a> DetectorSuite<'a> {
                     tif: OrderTimeInForce::IOC,
                     price_limit: combo_price * size_contracts,
                     dry_run: self.config.dry_run,
+                    hedge: None,
                 };
 
                 let opportunity = StrategyOpportunity {
+                    id: String::new(),
+                    schema_version: OPPORTUNITY_SCHEMA_VERSION,
                     strategy: StrategyKind::Box,
                     currency,
                     settlement,
@@ -1002,6 +1452,12 @@ impl<'a> DetectorSuite<'a> {
                     ),
                     size_contracts,
                     execution_plan,
+                    estimated_margin_usd: None,
+                    size_ladder: Vec::new(),
+                    leg_quote_ages_ms: Vec::new(),
+                    staleness_penalty_usd: Decimal::ZERO,
+                    annualized_return: None,
+                    fx_usd: Decimal::ONE,
                 };
                 results.push(opportunity);
             }
@@ -1011,50 +1467,27 @@ impl<'a> DetectorSuite<'a> {
 
     fn detect_jelly_rolls(
         &self,
-        snapshot: &[InstrumentSnapshot],
+        ladders: &[PairLadder<'_>],
+        now: chrono::DateTime<Utc>,
     ) -> Result<Vec<StrategyOpportunity>> {
-        #[derive(Default)]
-        struct ExpiryBucket<'a> {
-            call: Option<&'a InstrumentSnapshot>,
-            put: Option<&'a InstrumentSnapshot>,
-        }
-
-        let mut buckets: HashMap<
-            (crate::model::Currency, Decimal, SettlementCurrency),
-            HashMap<chrono::DateTime<Utc>, ExpiryBucket>,
-        > = HashMap::new();
-
-        for inst in snapshot {
-            let key = (
-                inst.instrument.currency,
-                inst.instrument.strike,
-                inst.instrument.settlement_currency,
-            );
-            let expiry_map = buckets.entry(key).or_default();
-            let bucket = expiry_map.entry(inst.instrument.expiry).or_default();
-            match inst.instrument.option_kind {
-                OptionKind::Call => bucket.call = Some(inst),
-                OptionKind::Put => bucket.put = Some(inst),
-            }
-        }
-
         let mut results = Vec::new();
 
-        for ((currency, strike, settlement), expiry_map) in buckets {
-            let mut expiries: Vec<_> = expiry_map
-                .into_iter()
-                .filter_map(|(expiry, bucket)| Some((expiry, bucket.call?, bucket.put?)))
-                .collect();
-
-            if expiries.len() < 2 {
-                continue;
-            }
-
-            expiries.sort_by(|a, b| a.0.cmp(&b.0));
-
-            for window in expiries.windows(2) {
-                let (near_expiry, near_call, near_put) = window[0];
-                let (far_expiry, far_call, far_put) = window[1];
+        for ladder in ladders {
+            let (currency, settlement) = (ladder.currency, ladder.settlement);
+            let requirements = self.requirements_in(StrategyKind::JellyRoll, settlement);
+            for window in ladder.pairs.windows(2) {
+                let StrikePair {
+                    expiry: near_expiry,
+                    strike,
+                    call: near_call,
+                    put: near_put,
+                } = window[0];
+                let StrikePair {
+                    expiry: far_expiry,
+                    call: far_call,
+                    put: far_put,
+                    ..
+                } = window[1];
 
                 let ask_call_near = match &near_call.quote.best_ask {
                     Some(level)
@@ -1062,7 +1495,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, JellyRoll, NoDepth),
                 };
                 let bid_put_near = match &near_put.quote.best_bid {
                     Some(level)
@@ -1070,7 +1503,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, JellyRoll, NoDepth),
                 };
                 let bid_call_far = match &far_call.quote.best_bid {
                     Some(level)
@@ -1078,7 +1511,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, JellyRoll, NoDepth),
                 };
                 let ask_put_far = match &far_put.quote.best_ask {
                     Some(level)
@@ -1086,7 +1519,7 @@ impl<'a> DetectorSuite<'a> {
                     {
                         level
                     }
-                    _ => continue,
+                    _ => reject!(self, JellyRoll, NoDepth),
                 };
 
                 let size_contracts = ask_call_near
@@ -1094,10 +1527,10 @@ impl<'a> DetectorSuite<'a> {
                     .min(bid_put_near.amount)
                     .min(bid_call_far.amount)
                     .min(ask_put_far.amount)
-                    .min(self.max_contracts_from_ticket(near_call));
+                    .min(self.max_contracts(&requirements, near_call));
 
                 if size_contracts <= Decimal::ZERO {
-                    continue;
+                    reject!(self, JellyRoll, NoDepth);
                 }
 
                 let debit_native =
@@ -1108,21 +1541,52 @@ impl<'a> DetectorSuite<'a> {
 
                 let reference_index = near_call.quote.index_price;
                 let debit_usd = match settlement {
-                    SettlementCurrency::Usdc => debit_native,
+                    SettlementCurrency::Usdc
+                    | SettlementCurrency::Usdt
+                    | SettlementCurrency::Eurr => debit_native,
                     SettlementCurrency::Coin => debit_native * reference_index,
                 };
 
                 if debit_usd >= Decimal::ZERO {
-                    continue;
+                    reject!(self, JellyRoll, NegativeEdge);
                 }
 
+                let hedge = self.delta_hedge(
+                    &[
+                        (
+                            near_call,
+                            ComboSide::Buy,
+                            size_contracts,
+                            ask_call_near.price,
+                        ),
+                        (
+                            near_put,
+                            ComboSide::Sell,
+                            size_contracts,
+                            bid_put_near.price,
+                        ),
+                        (
+                            far_call,
+                            ComboSide::Sell,
+                            size_contracts,
+                            bid_call_far.price,
+                        ),
+                        (far_put, ComboSide::Buy, size_contracts, ask_put_far.price),
+                    ],
+                    currency,
+                    settlement,
+                    reference_index,
+                    now,
+                );
+
                 let fee_ctx = FeeComputationContext {
                     legs: vec![
                         LegFeeInput {
                             instrument_name: near_call.instrument.instrument_name.clone(),
                             side: ComboSide::Buy,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: ask_call_near.price,
                             index_price: near_call.quote.index_price,
                             contracts: size_contracts,
@@ -1131,13 +1595,15 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &near_call.instrument.instrument_name,
                                 near_call.instrument.expiry,
+                                now,
                             ),
                         },
                         LegFeeInput {
                             instrument_name: near_put.instrument.instrument_name.clone(),
                             side: ComboSide::Sell,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: bid_put_near.price,
                             index_price: near_put.quote.index_price,
                             contracts: size_contracts,
@@ -1146,13 +1612,15 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &near_put.instrument.instrument_name,
                                 near_put.instrument.expiry,
+                                now,
                             ),
                         },
                         LegFeeInput {
                             instrument_name: far_call.instrument.instrument_name.clone(),
                             side: ComboSide::Sell,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: bid_call_far.price,
                             index_price: far_call.quote.index_price,
                             contracts: size_contracts,
@@ -1161,13 +1629,15 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &far_call.instrument.instrument_name,
                                 far_call.instrument.expiry,
+                                now,
                             ),
                         },
                         LegFeeInput {
                             instrument_name: far_put.instrument.instrument_name.clone(),
                             side: ComboSide::Buy,
                             settlement,
-                            role: FillRole::Taker,
+                            role: self.fill_role(),
+                            venue: ExecutionVenue::Screen,
                             option_price: ask_put_far.price,
                             index_price: far_put.quote.index_price,
                             contracts: size_contracts,
@@ -1176,26 +1646,53 @@ impl<'a> DetectorSuite<'a> {
                             is_daily: is_daily_option(
                                 &far_put.instrument.instrument_name,
                                 far_put.instrument.expiry,
+                                now,
                             ),
                         },
                     ],
                     hold_to_expiry: self.config.hold_to_expiry,
+                    hedge: hedge.as_ref().map(|(_, fee_input)| fee_input.clone()),
                 };
 
                 let fee_breakdown = self.fee_engine.compute(fee_ctx)?;
-                let net_edge_usd = (-debit_usd) - fee_breakdown.total_usd;
-
+                // The roll is long the near forward and short the far one, so
+                // a contango curve alone pays a credit; only the rest is edge.
+                let carry_usd = self
+                    .carry_credit(
+                        currency,
+                        settlement,
+                        strike,
+                        (near_expiry, far_expiry),
+                        reference_index,
+                        now,
+                    )
+                    .map(|per_unit| per_unit * size_contracts * near_call.instrument.contract_size)
+                    .unwrap_or_default();
+                let gross_edge_usd = (-debit_usd) - fee_breakdown.total_usd;
+                let net_edge_usd = gross_edge_usd - carry_usd;
+
+                if gross_edge_usd <= Decimal::ZERO {
+                    reject!(self, JellyRoll, NegativeEdge);
+                }
                 if net_edge_usd <= Decimal::ZERO {
-                    continue;
+                    debug!(
+                        target: "detect.carry",
+                        near = %near_call.instrument.instrument_name,
+                        far = %far_call.instrument.instrument_name,
+                        credit_usd = %(-debit_usd),
+                        carry_usd = %carry_usd,
+                        "jelly roll credit explained by carry"
+                    );
+                    reject!(self, JellyRoll, CarryExplained);
                 }
-                if net_edge_usd < self.config.min_edge_usd {
-                    continue;
+                if net_edge_usd < requirements.min_edge_usd {
+                    reject!(self, JellyRoll, BelowMinEdge);
                 }
                 let edge_ratio = (net_edge_usd / fee_breakdown.total_usd.max(dec!(0.01)))
                     .to_f64()
                     .unwrap_or(0.0);
-                if edge_ratio < self.config.min_edge_ratio {
-                    continue;
+                if edge_ratio < requirements.min_edge_ratio {
+                    reject!(self, JellyRoll, RatioTooLow);
                 }
 
                 let legs = vec![
@@ -1265,6 +1762,7 @@ impl<'a> DetectorSuite<'a> {
                     tif: OrderTimeInForce::IOC,
                     price_limit: debit_native,
                     dry_run: self.config.dry_run,
+                    hedge: hedge.map(|(leg, _)| leg),
                 };
 
                 let notional_usd = near_call.quote.index_price
@@ -1272,6 +1770,8 @@ impl<'a> DetectorSuite<'a> {
                     * near_call.instrument.contract_size;
 
                 let opportunity = StrategyOpportunity {
+                    id: String::new(),
+                    schema_version: OPPORTUNITY_SCHEMA_VERSION,
                     strategy: StrategyKind::JellyRoll,
                     currency,
                     settlement,
@@ -1283,7 +1783,9 @@ impl<'a> DetectorSuite<'a> {
                     max_payout: Decimal::ZERO,
                     fee_breakdown,
                     net_edge_native: match settlement {
-                        SettlementCurrency::Usdc => net_edge_usd,
+                        SettlementCurrency::Usdc
+                        | SettlementCurrency::Usdt
+                        | SettlementCurrency::Eurr => net_edge_usd,
                         SettlementCurrency::Coin => {
                             if reference_index.is_zero() {
                                 Decimal::ZERO
@@ -1303,6 +1805,12 @@ impl<'a> DetectorSuite<'a> {
                     ),
                     size_contracts,
                     execution_plan,
+                    estimated_margin_usd: None,
+                    size_ladder: Vec::new(),
+                    leg_quote_ages_ms: Vec::new(),
+                    staleness_penalty_usd: Decimal::ZERO,
+                    annualized_return: None,
+                    fx_usd: Decimal::ONE,
                 };
                 results.push(opportunity);
             }
@@ -1311,12 +1819,86 @@ impl<'a> DetectorSuite<'a> {
         Ok(results)
     }
 
+    fn delta_hedge(
+        &self,
+        legs: &[(&InstrumentSnapshot, ComboSide, Decimal, Decimal)],
+        currency: crate::model::Currency,
+        settlement: SettlementCurrency,
+        reference_index: Decimal,
+        now: chrono::DateTime<Utc>,
+    ) -> Option<(HedgeLeg, HedgeFeeInput)> {
+        if !self.config.delta_hedge || reference_index.is_zero() {
+            return None;
+        }
+        let mut net_delta = 0.0;
+        for (inst, side, contracts, price) in legs {
+            let greeks = instrument_greeks(inst, *price, now)?;
+            let sign = match side {
+                ComboSide::Buy => 1.0,
+                ComboSide::Sell => -1.0,
+            };
+            net_delta += sign * contracts.to_f64()? * greeks.delta;
+        }
+        let combo_delta = Decimal::from_f64(net_delta)?.round_dp(6);
+        let notional_usd = combo_delta.abs() * reference_index;
+        let min_notional = self
+            .fx_usd(settlement)
+            .map_or(MIN_HEDGE_NOTIONAL_USD, |rate| MIN_HEDGE_NOTIONAL_USD / rate);
+        if notional_usd < min_notional {
+            return None;
+        }
+        let amount = match settlement {
+            SettlementCurrency::Coin => notional_usd,
+            SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                combo_delta.abs()
+            }
+        };
+        let instrument_name = currency.perpetual_instrument(settlement);
+        Some((
+            HedgeLeg {
+                instrument_name: instrument_name.clone(),
+                side: if combo_delta > Decimal::ZERO {
+                    ComboSide::Sell
+                } else {
+                    ComboSide::Buy
+                },
+                amount,
+                combo_delta,
+                notional_usd,
+            },
+            HedgeFeeInput {
+                instrument_name,
+                settlement,
+                venue: ExecutionVenue::Screen,
+                notional_usd,
+                index_price: reference_index,
+                expiry: None,
+            },
+        ))
+    }
+
+    fn max_contracts(
+        &self,
+        requirements: &MinEdgeRequirements,
+        inst: &InstrumentSnapshot,
+    ) -> Decimal {
+        let from_ticket = self.max_contracts_from_ticket(inst);
+        match requirements.max_size_contracts {
+            Some(cap) => from_ticket.min(cap),
+            None => from_ticket,
+        }
+    }
+
     fn max_contracts_from_ticket(&self, inst: &InstrumentSnapshot) -> Decimal {
         let index_price = inst.quote.index_price;
         if index_price.is_zero() {
             return Decimal::from(self.config.min_depth_contracts);
         }
-        let ticket_cap = self.config.max_ticket_usd;
+        let ticket_cap = self
+            .fx_usd(inst.instrument.settlement_currency)
+            .map_or(self.config.max_ticket_usd, |rate| {
+                self.config.max_ticket_usd / rate
+            });
         let notional_per_contract = index_price * inst.instrument.contract_size;
         if notional_per_contract.is_zero() {
             return Decimal::from(self.config.min_depth_contracts);
@@ -1333,39 +1915,72 @@ impl<'a> DetectorSuite<'a> {
     }
 }
 
-#[derive(Hash, Eq, PartialEq, Clone, Copy)]
-enum StrategyKindKey {
-    Call,
-    Put,
-}
+/// A currency's index in USD, or in euros for EURR books.
+type IndexKey = (crate::model::Currency, bool);
 
-fn group_by_expiry(
-    snapshot: &[InstrumentSnapshot],
-) -> HashMap<
+fn index_key(inst: &InstrumentSnapshot) -> IndexKey {
     (
-        crate::model::Currency,
-        chrono::DateTime<Utc>,
-        SettlementCurrency,
-        StrategyKindKey,
-    ),
-    Vec<InstrumentSnapshot>,
-> {
-    let mut map: HashMap<_, Vec<InstrumentSnapshot>> = HashMap::new();
-    for inst in snapshot.iter() {
-        let kind_key = match inst.instrument.option_kind {
-            crate::model::OptionKind::Call => StrategyKindKey::Call,
-            crate::model::OptionKind::Put => StrategyKindKey::Put,
-        };
-        map.entry((
-            inst.instrument.currency,
-            inst.instrument.expiry,
-            inst.instrument.settlement_currency,
-            kind_key,
-        ))
-        .or_default()
-        .push(inst.clone());
+        inst.instrument.currency,
+        inst.instrument.settlement_currency.pays_usd(),
+    )
+}
+
+/// The index price each currency's edge and fees are converted at: the one
+/// carried by the slice's freshest positive-index quote, so every leg of a
+/// combo is valued against the same (currency, timestamp) reference. EURR
+/// books quote a euro index and keep a reference of their own.
+fn reference_indices(snapshot: &[InstrumentSnapshot]) -> HashMap<IndexKey, Decimal> {
+    let mut freshest: HashMap<IndexKey, (chrono::DateTime<Utc>, Decimal)> = HashMap::new();
+    for inst in snapshot {
+        if inst.quote.index_price <= Decimal::ZERO {
+            continue;
+        }
+        let entry = freshest
+            .entry(index_key(inst))
+            .or_insert((inst.quote.timestamp, inst.quote.index_price));
+        if inst.quote.timestamp > entry.0 {
+            *entry = (inst.quote.timestamp, inst.quote.index_price);
+        }
+    }
+    freshest
+        .into_iter()
+        .map(|(key, (_, index))| (key, index))
+        .collect()
+}
+
+/// `snapshot` with every quote's index price replaced by its currency's
+/// reference; borrowed when they already agree.
+fn with_reference_index<'s>(
+    snapshot: &'s [InstrumentSnapshot],
+    references: &HashMap<IndexKey, Decimal>,
+) -> Cow<'s, [InstrumentSnapshot]> {
+    let reference = |inst: &InstrumentSnapshot| references.get(&index_key(inst)).copied();
+    if snapshot
+        .iter()
+        .all(|inst| reference(inst).is_none_or(|index| index == inst.quote.index_price))
+    {
+        return Cow::Borrowed(snapshot);
     }
-    map
+    Cow::Owned(
+        snapshot
+            .iter()
+            .map(|inst| {
+                let mut inst = inst.clone();
+                if let Some(index) = reference(&inst) {
+                    inst.quote.index_price = index;
+                }
+                inst
+            })
+            .collect(),
+    )
+}
+
+/// Absolute distance of `index` from `reference`, in bps of the reference.
+fn index_divergence_bps(index: Decimal, reference: Decimal) -> Option<f64> {
+    if reference.is_zero() {
+        return None;
+    }
+    ((index - reference).abs() / reference * dec!(10000)).to_f64()
 }
 
 fn compute_edge_bps(
@@ -1378,15 +1993,17 @@ fn compute_edge_bps(
         return 0.0;
     }
     let base = match settlement {
-        SettlementCurrency::Usdc => index_price * contracts,
+        SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+            index_price * contracts
+        }
         SettlementCurrency::Coin => index_price * contracts,
     };
     (net_edge_usd / base).to_f64().unwrap_or(0.0) * 10_000.0
 }
 
-fn is_daily_option(name: &str, expiry: chrono::DateTime<Utc>) -> bool {
+fn is_daily_option(name: &str, expiry: chrono::DateTime<Utc>, now: chrono::DateTime<Utc>) -> bool {
     if name.contains("-D") {
         return true;
     }
-    expiry - Utc::now() <= Duration::days(1)
+    expiry - now <= Duration::days(1)
 }
@@ -0,0 +1,143 @@
+use crate::model::{ComboSide, StrategyKind, StrategyOpportunity, Trade};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One executed leg waiting out the observation window.
+#[derive(Debug, Clone)]
+struct WatchedLeg {
+    strategy: StrategyKind,
+    instrument_name: String,
+    side: ComboSide,
+    price: Decimal,
+    filled_at: DateTime<Utc>,
+    confirmed: bool,
+    traded_through: bool,
+}
+
+/// Fill quality of one strategy's executed legs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct AdverseSelectionStats {
+    pub legs: u64,
+    /// Legs with a public print at our level inside the window.
+    pub confirmed: u64,
+    /// Legs the market traded through inside the window: below our price
+    /// after a buy, above it after a sell.
+    pub traded_through: u64,
+}
+
+impl AdverseSelectionStats {
+    pub fn traded_through_rate(&self) -> f64 {
+        if self.legs == 0 {
+            return 0.0;
+        }
+        self.traded_through as f64 / self.legs as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AdverseSelectionRow {
+    pub strategy: StrategyKind,
+    #[serde(flatten)]
+    pub stats: AdverseSelectionStats,
+    pub traded_through_rate: f64,
+}
+
+/// Watches the public trade prints of executed legs for `window` after each
+/// fill. A print at our level confirms the fill; a print through it means
+/// the market moved against us right away, i.e. we were picked off.
+pub struct AdverseSelectionTracker {
+    window: Duration,
+    watched: Vec<WatchedLeg>,
+    stats: HashMap<StrategyKind, AdverseSelectionStats>,
+}
+
+impl AdverseSelectionTracker {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            watched: Vec::new(),
+            stats: HashMap::new(),
+        }
+    }
+
+    /// Starts watching every leg of a filled combo at its touch price.
+    /// Returns the instruments whose `trades.*` channel should be followed.
+    pub fn record_fill(
+        &mut self,
+        opportunity: &StrategyOpportunity,
+        filled_at: DateTime<Utc>,
+    ) -> Vec<String> {
+        opportunity
+            .touches
+            .iter()
+            .map(|touch| {
+                self.watched.push(WatchedLeg {
+                    strategy: opportunity.strategy,
+                    instrument_name: touch.instrument_name.clone(),
+                    side: touch.side,
+                    price: touch.price,
+                    filled_at,
+                    confirmed: false,
+                    traded_through: false,
+                });
+                touch.instrument_name.clone()
+            })
+            .collect()
+    }
+
+    pub fn on_trades(&mut self, trades: &[Trade]) {
+        for trade in trades {
+            for leg in &mut self.watched {
+                if leg.instrument_name != trade.instrument_name
+                    || trade.timestamp < leg.filled_at
+                    || trade.timestamp > leg.filled_at + self.window
+                {
+                    continue;
+                }
+                if trade.price == leg.price {
+                    leg.confirmed = true;
+                }
+                leg.traded_through |= match leg.side {
+                    ComboSide::Buy => trade.price < leg.price,
+                    ComboSide::Sell => trade.price > leg.price,
+                };
+            }
+        }
+    }
+
+    /// Folds legs whose window has closed by `now` into the per-strategy
+    /// counts. Returns whether any leg was settled.
+    pub fn settle(&mut self, now: DateTime<Utc>) -> bool {
+        let window = self.window;
+        let before = self.watched.len();
+        let stats = &mut self.stats;
+        self.watched.retain(|leg| {
+            if now <= leg.filled_at + window {
+                return true;
+            }
+            let entry = stats.entry(leg.strategy).or_default();
+            entry.legs += 1;
+            entry.confirmed += u64::from(leg.confirmed);
+            entry.traded_through += u64::from(leg.traded_through);
+            false
+        });
+        self.watched.len() < before
+    }
+
+    /// Cumulative counts of settled legs, ordered by strategy name.
+    pub fn report(&self) -> Vec<AdverseSelectionRow> {
+        let mut rows: Vec<AdverseSelectionRow> = self
+            .stats
+            .iter()
+            .map(|(strategy, stats)| AdverseSelectionRow {
+                strategy: *strategy,
+                stats: *stats,
+                traded_through_rate: stats.traded_through_rate(),
+            })
+            .collect();
+        rows.sort_by_key(|row| row.strategy.to_string());
+        rows
+    }
+}
@@ -0,0 +1,104 @@
+use crate::model::{ComboLeg, ComboSide, SettlementCurrency};
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Identifies a combo by what it trades rather than by name: the settlement
+/// book plus each leg as `side:ratio:instrument`, sorted so leg order does
+/// not matter. `"usdc|buy:1:BTC_USDC-27DEC30-40000-C,sell:1:..."`.
+pub fn combo_signature(settlement: SettlementCurrency, legs: &[ComboLeg]) -> String {
+    let mut legs: Vec<String> = legs
+        .iter()
+        .map(|leg| {
+            let side = match leg.side {
+                ComboSide::Buy => "buy",
+                ComboSide::Sell => "sell",
+            };
+            format!("{side}:{}:{}", leg.ratio, leg.instrument_name)
+        })
+        .collect();
+    legs.sort();
+    let book = settlement
+        .stablecoin()
+        .unwrap_or("coin")
+        .to_ascii_lowercase();
+    format!("{book}|{}", legs.join(","))
+}
+
+/// Combo IDs by [`combo_signature`], shared by every planner of a run so a
+/// combo is created once and reused by later cycles, accounts and retries.
+/// Opened from a file, entries are rewritten on every insert and reloaded
+/// at startup, so restarts reuse combos too.
+#[derive(Debug, Clone, Default)]
+pub struct ComboCache {
+    ids: Arc<Mutex<BTreeMap<String, String>>>,
+    store: Option<PathBuf>,
+}
+
+impl ComboCache {
+    /// In-memory cache, forgotten on exit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache persisted to `path`; a missing file starts empty.
+    pub fn open(path: &Path) -> Result<Self> {
+        let ids: BTreeMap<String, String> = match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("parsing combo cache {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading combo cache {}", path.display()))
+            }
+        };
+        info!(
+            target: "execution.combo",
+            path = %path.display(),
+            combos = ids.len(),
+            "combo cache loaded"
+        );
+        Ok(Self {
+            ids: Arc::new(Mutex::new(ids)),
+            store: Some(path.to_path_buf()),
+        })
+    }
+
+    pub fn get(&self, signature: &str) -> Option<String> {
+        self.ids.lock().get(signature).cloned()
+    }
+
+    pub fn insert(&self, signature: String, combo_id: String) {
+        let mut ids = self.ids.lock();
+        if ids.get(&signature) == Some(&combo_id) {
+            return;
+        }
+        ids.insert(signature, combo_id);
+        self.persist(&ids);
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.lock().is_empty()
+    }
+
+    fn persist(&self, ids: &BTreeMap<String, String>) {
+        let Some(path) = &self.store else {
+            return;
+        };
+        // Write-then-rename so a crash mid-write never leaves a torn file.
+        let tmp = path.with_extension("tmp");
+        let result = serde_json::to_vec_pretty(ids)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(std::fs::write(&tmp, bytes)?))
+            .and_then(|_| Ok(std::fs::rename(&tmp, path)?));
+        if let Err(err) = result {
+            warn!(target: "execution.combo", path = %path.display(), error = %err, "failed to persist combo cache");
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use super::{touch_price, ComboApi, ComboCache, ExecutionPlanner};
+use crate::config::AppConfig;
+use crate::model::{
+    ComboSide, Currency, InstrumentSnapshot, OrderRequest, OrderTimeInForce, StrategyKind,
+    StrategyOpportunity,
+};
+use crate::risk::RiskManager;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use tracing::{info, warn};
+
+/// A post-only GTC combo order currently resting on the book.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RestingOrder {
+    pub order_id: String,
+    pub combo_id: String,
+    pub strategy: StrategyKind,
+    pub currency: Currency,
+    pub price: Decimal,
+    pub amount: Decimal,
+    pub placed_at: DateTime<Utc>,
+    pub repriced_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelReason {
+    /// The opportunity is no longer among the approved targets.
+    EdgeGone,
+    /// A leg quote is older than `--maker-stale-secs`.
+    StaleQuotes,
+    /// The health monitor disabled execution.
+    CircuitBreaker,
+    /// Execution was paused through the daemon control API.
+    Paused,
+    Shutdown,
+}
+
+impl Display for CancelReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CancelReason::EdgeGone => write!(f, "edge gone"),
+            CancelReason::StaleQuotes => write!(f, "stale quotes"),
+            CancelReason::CircuitBreaker => write!(f, "circuit breaker"),
+            CancelReason::Paused => write!(f, "paused"),
+            CancelReason::Shutdown => write!(f, "shutdown"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MakerAction {
+    Placed {
+        order_id: String,
+        combo_id: String,
+        strategy: StrategyKind,
+        currency: Currency,
+        price: Decimal,
+        amount: Decimal,
+    },
+    Repriced {
+        order_id: String,
+        strategy: StrategyKind,
+        currency: Currency,
+        from: Decimal,
+        to: Decimal,
+    },
+    Cancelled {
+        order_id: String,
+        /// [`StrategyOpportunity::combo_key`] of the cancelled order.
+        key: String,
+        strategy: StrategyKind,
+        currency: Currency,
+        reason: CancelReason,
+    },
+}
+
+impl MakerAction {
+    pub fn subject(&self) -> (StrategyKind, Currency) {
+        match self {
+            MakerAction::Placed {
+                strategy, currency, ..
+            }
+            | MakerAction::Repriced {
+                strategy, currency, ..
+            }
+            | MakerAction::Cancelled {
+                strategy, currency, ..
+            } => (*strategy, *currency),
+        }
+    }
+}
+
+/// Maker-mode execution: keeps one resting combo order per opportunity,
+/// priced `--maker-improve-ticks` inside the touch so the fill keeps more
+/// than the detected edge. Each [`MakerQuoter::sync`] reprices orders whose
+/// touch moved and cancels those whose edge vanished or whose quotes are stale.
+pub struct MakerQuoter<'a, A: ComboApi + ?Sized> {
+    planner: ExecutionPlanner<'a, A>,
+    resting: HashMap<String, RestingOrder>,
+    dry_run_orders: u64,
+}
+
+impl<'a, A: ComboApi + ?Sized> MakerQuoter<'a, A> {
+    pub fn new(client: &'a A, config: &'a AppConfig) -> Self {
+        Self {
+            planner: ExecutionPlanner::new(client, config),
+            resting: HashMap::new(),
+            dry_run_orders: 0,
+        }
+    }
+
+    /// Shares `combos` with the taking planners.
+    pub fn with_combos(mut self, combos: ComboCache) -> Self {
+        self.planner = self.planner.with_combos(combos);
+        self
+    }
+
+    /// Counts placements and reprices against `risk`'s order budget.
+    pub fn with_risk(mut self, risk: RiskManager) -> Self {
+        self.planner = self.planner.with_risk(risk);
+        self
+    }
+
+    pub fn is_resting(&self, opportunity: &StrategyOpportunity) -> bool {
+        self.is_resting_key(&opportunity.combo_key())
+    }
+
+    pub fn is_resting_key(&self, key: &str) -> bool {
+        self.resting.contains_key(key)
+    }
+
+    pub fn resting(&self) -> impl Iterator<Item = &RestingOrder> {
+        self.resting.values()
+    }
+
+    /// Bid `improve_ticks` below the touch, floored onto the tick grid.
+    pub fn maker_price(
+        opportunity: &StrategyOpportunity,
+        tick: Decimal,
+        improve_ticks: u32,
+    ) -> Decimal {
+        let target = touch_price(opportunity) - tick * Decimal::from(improve_ticks);
+        if tick.is_zero() {
+            return target;
+        }
+        (target / tick).floor() * tick
+    }
+
+    /// Brings resting orders in line with this cycle's approved `targets`.
+    pub async fn sync(
+        &mut self,
+        targets: &[StrategyOpportunity],
+        instruments: &[InstrumentSnapshot],
+        now: DateTime<Utc>,
+    ) -> Vec<MakerAction> {
+        let config = self.planner.config;
+        let by_name: HashMap<&str, &InstrumentSnapshot> = instruments
+            .iter()
+            .map(|inst| (inst.instrument.instrument_name.as_str(), inst))
+            .collect();
+        let horizon = Duration::seconds(config.maker_stale_secs as i64);
+        let mut actions = Vec::new();
+        let mut wanted = HashSet::new();
+
+        for opportunity in targets {
+            let key = opportunity.combo_key();
+            if !wanted.insert(key.clone()) {
+                continue;
+            }
+            let legs: Option<Vec<&InstrumentSnapshot>> = opportunity
+                .legs
+                .iter()
+                .map(|leg| by_name.get(leg.instrument_name.as_str()).copied())
+                .collect();
+            let fresh = legs.as_ref().is_some_and(|legs| {
+                legs.iter()
+                    .all(|inst| now - inst.quote.timestamp <= horizon)
+            });
+            let Some(legs) = legs.filter(|_| fresh) else {
+                actions.extend(self.cancel(&key, CancelReason::StaleQuotes).await);
+                continue;
+            };
+            let tick = legs
+                .iter()
+                .map(|inst| inst.instrument.tick_size)
+                .max()
+                .unwrap_or_default();
+            let price = Self::maker_price(opportunity, tick, config.maker_improve_ticks);
+            let action = match self.resting.get(&key) {
+                Some(order)
+                    if order.price == price && order.amount == opportunity.size_contracts =>
+                {
+                    None
+                }
+                Some(_) => self.reprice(&key, opportunity, price, now).await,
+                None => self.place(key, opportunity, price, now).await,
+            };
+            actions.extend(action);
+        }
+
+        let gone: Vec<String> = self
+            .resting
+            .keys()
+            .filter(|key| !wanted.contains(*key))
+            .cloned()
+            .collect();
+        for key in gone {
+            actions.extend(self.cancel(&key, CancelReason::EdgeGone).await);
+        }
+        actions
+    }
+
+    /// Cancels everything still resting, e.g. before exiting loop mode.
+    pub async fn cancel_all(&mut self, reason: CancelReason) -> Vec<MakerAction> {
+        let keys: Vec<String> = self.resting.keys().cloned().collect();
+        let mut actions = Vec::new();
+        for key in keys {
+            actions.extend(self.cancel(&key, reason).await);
+        }
+        actions
+    }
+
+    async fn place(
+        &mut self,
+        key: String,
+        opportunity: &StrategyOpportunity,
+        price: Decimal,
+        now: DateTime<Utc>,
+    ) -> Option<MakerAction> {
+        // The budget logs the deferral; the next sync tries again.
+        self.planner.take_orders(1, now).ok()?;
+        let (combo_id, order_id) = if self.planner.config.dry_run {
+            self.dry_run_orders += 1;
+            info!(
+                target: "execution.maker",
+                strategy = %opportunity.strategy,
+                %price,
+                "dry run only, not placing maker order"
+            );
+            (
+                format!("dry-run-combo-{}", self.dry_run_orders),
+                format!("dry-run-{}", self.dry_run_orders),
+            )
+        } else {
+            let combo_id = match self.planner.ensure_combo(opportunity).await {
+                Ok(id) => id,
+                Err(err) => {
+                    warn!(target: "execution.maker", error = %err, "failed to create combo");
+                    return None;
+                }
+            };
+            let order = OrderRequest {
+                instrument_name: combo_id.clone(),
+                side: ComboSide::Buy,
+                amount: opportunity.size_contracts,
+                price,
+                tif: OrderTimeInForce::GTC,
+                post_only: true,
+                label: Some(format!("arb-maker-{}", opportunity.strategy)),
+            };
+            match self.planner.client.place_order(&order).await {
+                Ok(order_id) => (combo_id, order_id),
+                Err(err) => {
+                    warn!(target: "execution.maker", combo = %combo_id, error = %err, "failed to place maker order");
+                    return None;
+                }
+            }
+        };
+        info!(target: "execution.maker", order = %order_id, combo = %combo_id, %price, "maker order resting");
+        self.resting.insert(
+            key,
+            RestingOrder {
+                order_id: order_id.clone(),
+                combo_id: combo_id.clone(),
+                strategy: opportunity.strategy,
+                currency: opportunity.currency,
+                price,
+                amount: opportunity.size_contracts,
+                placed_at: now,
+                repriced_at: now,
+            },
+        );
+        Some(MakerAction::Placed {
+            order_id,
+            combo_id,
+            strategy: opportunity.strategy,
+            currency: opportunity.currency,
+            price,
+            amount: opportunity.size_contracts,
+        })
+    }
+
+    async fn reprice(
+        &mut self,
+        key: &str,
+        opportunity: &StrategyOpportunity,
+        price: Decimal,
+        now: DateTime<Utc>,
+    ) -> Option<MakerAction> {
+        let dry_run = self.planner.config.dry_run;
+        let client = self.planner.client;
+        if !self.resting.contains_key(key) {
+            return None;
+        }
+        self.planner.take_orders(1, now).ok()?;
+        let order = self.resting.get_mut(key)?;
+        if !dry_run {
+            if let Err(err) = client
+                .edit_order(&order.order_id, opportunity.size_contracts, price)
+                .await
+            {
+                warn!(target: "execution.maker", order = %order.order_id, error = %err, "failed to reprice maker order");
+                return None;
+            }
+        }
+        let from = order.price;
+        order.price = price;
+        order.amount = opportunity.size_contracts;
+        order.repriced_at = now;
+        info!(target: "execution.maker", order = %order.order_id, %from, to = %price, "maker order repriced");
+        Some(MakerAction::Repriced {
+            order_id: order.order_id.clone(),
+            strategy: order.strategy,
+            currency: order.currency,
+            from,
+            to: price,
+        })
+    }
+
+    async fn cancel(&mut self, key: &str, reason: CancelReason) -> Option<MakerAction> {
+        let order = self.resting.get(key)?;
+        if !self.planner.config.dry_run {
+            if let Err(err) = self.planner.client.cancel_order(&order.order_id).await {
+                // Keep tracking it so the next cycle retries the cancel.
+                warn!(target: "execution.maker", order = %order.order_id, error = %err, "failed to cancel maker order");
+                return None;
+            }
+        }
+        let order = self.resting.remove(key)?;
+        info!(target: "execution.maker", order = %order.order_id, %reason, "maker order cancelled");
+        Some(MakerAction::Cancelled {
+            order_id: order.order_id,
+            key: key.to_string(),
+            strategy: order.strategy,
+            currency: order.currency,
+            reason,
+        })
+    }
+}
@@ -1,6 +1,10 @@
 use crate::client::DeribitHttpClient;
 use crate::config::AppConfig;
-use crate::model::{ComboLeg, SettlementCurrency, StrategyOpportunity};
+use crate::model::{
+    BlockRfqQuote, ComboLeg, Currency, OrderRequest, Quote, SettlementCurrency, StrategyKind,
+    StrategyOpportunity,
+};
+use crate::risk::{RiskManager, RiskRejection};
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use rust_decimal::Decimal;
@@ -8,38 +12,206 @@ use serde::Serialize;
 use serde_json::json;
 use tracing::{info, warn};
 
+pub mod adverse;
+pub mod combos;
+pub mod maker;
+pub mod recheck;
+pub mod rfq;
+pub mod sizing;
+pub mod slippage;
+pub mod ticks;
+
+pub use adverse::{AdverseSelectionRow, AdverseSelectionStats, AdverseSelectionTracker};
+pub use combos::{combo_signature, ComboCache};
+pub use maker::{CancelReason, MakerAction, MakerQuoter, RestingOrder};
+pub use recheck::RecheckRejection;
+pub use rfq::{BlockRfqReport, RfqQuoteEvaluation};
+pub use slippage::{leg_usd, preview_legs, LegPreview};
+pub use ticks::{snap_price, snap_to_ticks, TickRejection};
+
 #[async_trait]
 pub trait ComboApi: Send + Sync {
-    async fn create_combo(&self, name: &str, legs: &[ComboLeg], is_usdc: bool) -> Result<String>;
+    async fn create_combo(
+        &self,
+        name: &str,
+        legs: &[ComboLeg],
+        settlement: SettlementCurrency,
+    ) -> Result<String>;
+
+    /// ID of an already listed combo with exactly `legs`, if the client can
+    /// look one up.
+    async fn find_combo(
+        &self,
+        _currency: Currency,
+        _settlement: SettlementCurrency,
+        _legs: &[ComboLeg],
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
+
     async fn get_leg_prices(&self, combo_id: &str, amount: Decimal) -> Result<serde_json::Value>;
+    async fn place_order(&self, order: &OrderRequest) -> Result<String>;
+    async fn edit_order(&self, order_id: &str, amount: Decimal, price: Decimal) -> Result<()>;
+    async fn cancel_order(&self, order_id: &str) -> Result<()>;
+    async fn create_block_rfq(&self, legs: &[ComboLeg], amount: Decimal) -> Result<String>;
+    async fn get_block_rfq_quotes(&self, rfq_id: &str) -> Result<Vec<BlockRfqQuote>>;
+    async fn accept_block_rfq(
+        &self,
+        rfq_id: &str,
+        legs: &[ComboLeg],
+        price: Decimal,
+        amount: Decimal,
+    ) -> Result<serde_json::Value>;
+    async fn cancel_block_rfq(&self, rfq_id: &str) -> Result<()>;
+    async fn get_ticker(&self, instrument_name: &str) -> Result<Quote>;
+
+    /// Tickers of `instrument_names`, in order; one call each unless the
+    /// client batches them into one round trip.
+    async fn get_tickers(&self, instrument_names: &[String]) -> Result<Vec<Result<Quote>>> {
+        let mut quotes = Vec::with_capacity(instrument_names.len());
+        for name in instrument_names {
+            quotes.push(self.get_ticker(name).await);
+        }
+        Ok(quotes)
+    }
 }
 
 #[async_trait]
 impl ComboApi for DeribitHttpClient {
-    async fn create_combo(&self, name: &str, legs: &[ComboLeg], is_usdc: bool) -> Result<String> {
-        self.create_combo(name, legs, is_usdc).await
+    async fn create_combo(
+        &self,
+        name: &str,
+        legs: &[ComboLeg],
+        settlement: SettlementCurrency,
+    ) -> Result<String> {
+        self.create_combo(name, legs, settlement).await
+    }
+
+    async fn find_combo(
+        &self,
+        currency: Currency,
+        settlement: SettlementCurrency,
+        legs: &[ComboLeg],
+    ) -> Result<Option<String>> {
+        self.find_combo(currency, settlement, legs).await
     }
 
     async fn get_leg_prices(&self, combo_id: &str, amount: Decimal) -> Result<serde_json::Value> {
         self.get_leg_prices(combo_id, amount).await
     }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<String> {
+        self.place_order(order).await
+    }
+
+    async fn edit_order(&self, order_id: &str, amount: Decimal, price: Decimal) -> Result<()> {
+        self.edit_order(order_id, amount, price).await
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        self.cancel_order(order_id).await
+    }
+
+    async fn create_block_rfq(&self, legs: &[ComboLeg], amount: Decimal) -> Result<String> {
+        self.create_block_rfq(legs, amount).await
+    }
+
+    async fn get_block_rfq_quotes(&self, rfq_id: &str) -> Result<Vec<BlockRfqQuote>> {
+        self.get_block_rfq_quotes(rfq_id).await
+    }
+
+    async fn accept_block_rfq(
+        &self,
+        rfq_id: &str,
+        legs: &[ComboLeg],
+        price: Decimal,
+        amount: Decimal,
+    ) -> Result<serde_json::Value> {
+        self.accept_block_rfq(rfq_id, legs, price, amount).await
+    }
+
+    async fn cancel_block_rfq(&self, rfq_id: &str) -> Result<()> {
+        self.cancel_block_rfq(rfq_id).await
+    }
+
+    async fn get_ticker(&self, instrument_name: &str) -> Result<Quote> {
+        self.get_ticker(instrument_name).await
+    }
+
+    async fn get_tickers(&self, instrument_names: &[String]) -> Result<Vec<Result<Quote>>> {
+        self.get_tickers(instrument_names).await
+    }
+}
+
+/// Per-contract price to buy the combo as defined: a debit is positive,
+/// a credit (calendars) negative.
+pub fn touch_price(opportunity: &StrategyOpportunity) -> Decimal {
+    if opportunity.size_contracts.is_zero() {
+        return Decimal::ZERO;
+    }
+    let per_contract = opportunity.execution_plan.price_limit / opportunity.size_contracts;
+    match opportunity.strategy {
+        StrategyKind::Calendar => -per_contract,
+        _ => per_contract,
+    }
 }
 
 #[derive(Debug, Serialize)]
 pub struct ExecutionReport {
     pub combo_id: Option<String>,
     pub preview: Option<serde_json::Value>,
+    /// Touch vs preview price per leg, from [`preview_legs`].
+    pub legs: Vec<LegPreview>,
+    /// Summed slippage of the previewed legs.
+    pub slippage_usd: Decimal,
+    /// Detected net edge left after that slippage.
+    pub expected_edge_usd: Decimal,
     pub submitted: bool,
+    pub order_ids: Vec<String>,
+    pub block_rfq: Option<BlockRfqReport>,
 }
 
 pub struct ExecutionPlanner<'a, A: ComboApi + ?Sized> {
     client: &'a A,
     config: &'a AppConfig,
+    combos: ComboCache,
+    risk: Option<RiskManager>,
 }
 
 impl<'a, A: ComboApi + ?Sized> ExecutionPlanner<'a, A> {
     pub fn new(client: &'a A, config: &'a AppConfig) -> Self {
-        Self { client, config }
+        Self {
+            client,
+            config,
+            combos: ComboCache::new(),
+            risk: None,
+        }
+    }
+
+    /// Shares `combos` with other planners instead of a private cache.
+    pub fn with_combos(mut self, combos: ComboCache) -> Self {
+        self.combos = combos;
+        self
+    }
+
+    /// Counts orders against `risk`'s `--max-orders-per-minute` budget;
+    /// without it orders are not throttled.
+    pub fn with_risk(mut self, risk: RiskManager) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Takes `count` orders from the per-minute budget, or refuses them
+    /// until the window has room.
+    pub fn take_orders(
+        &self,
+        count: usize,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), RiskRejection> {
+        match &self.risk {
+            Some(risk) => risk.take_orders(self.config, count, now),
+            None => Ok(()),
+        }
     }
 
     pub async fn plan(&self, opportunity: &StrategyOpportunity) -> Result<ExecutionReport> {
@@ -52,21 +224,61 @@ impl<'a, A: ComboApi + ?Sized> ExecutionPlanner<'a, A> {
             .get_leg_prices(&combo_id, opportunity.size_contracts)
             .await
             .context("failed to preview leg prices")?;
+        let legs = preview_legs(opportunity, &preview);
+        let slippage_usd: Decimal = legs.iter().filter_map(|leg| leg.slippage_usd).sum();
+        let expected_edge_usd = opportunity.net_edge_usd - slippage_usd;
+
+        if let Some(hedge) = &opportunity.execution_plan.hedge {
+            info!(
+                target: "execution.hedge",
+                instrument = %hedge.instrument_name,
+                side = %hedge.side,
+                amount = %hedge.amount,
+                delta = %hedge.combo_delta,
+                "delta hedge leg planned"
+            );
+        }
+
+        let block_rfq = match self.config.rfq_contracts {
+            Some(contracts) if contracts > opportunity.size_contracts => {
+                match self.request_block(opportunity, contracts).await {
+                    Ok(report) => Some(report),
+                    Err(err) => {
+                        warn!(target: "execution.rfq", error = %err, "block RFQ failed");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
 
         if self.config.dry_run {
             info!("combo" = combo_id, "dry run only, not submitting order");
             return Ok(ExecutionReport {
                 combo_id: Some(combo_id),
                 preview: Some(preview),
+                legs,
+                slippage_usd,
+                expected_edge_usd,
                 submitted: false,
+                order_ids: Vec::new(),
+                block_rfq,
             });
         }
 
-        warn!("execution" = ?opportunity.strategy, "Auto-submission not yet implemented; dry-run recommended");
+        let submitted = block_rfq.as_ref().is_some_and(|rfq| rfq.accepted.is_some());
+        if !submitted {
+            warn!("execution" = ?opportunity.strategy, "Auto-submission not yet implemented; dry-run recommended");
+        }
         Ok(ExecutionReport {
             combo_id: Some(combo_id),
             preview: Some(preview),
-            submitted: false,
+            legs,
+            slippage_usd,
+            expected_edge_usd,
+            submitted,
+            order_ids: Vec::new(),
+            block_rfq,
         })
     }
 
@@ -79,7 +291,18 @@ impl<'a, A: ComboApi + ?Sized> ExecutionPlanner<'a, A> {
         {
             return Ok(existing_id.to_string());
         }
-        let is_usdc = matches!(opportunity.settlement, SettlementCurrency::Usdc);
+        if opportunity.spans_settlements() {
+            bail!("legs settle on different books and cannot form one combo");
+        }
+        let signature = combo_signature(opportunity.settlement, &opportunity.legs);
+        if let Some(combo_id) = self.combos.get(&signature) {
+            return Ok(combo_id);
+        }
+        if let Some(combo_id) = self.find_listed_combo(opportunity).await {
+            info!(target: "execution.combo", combo = %combo_id, "reusing listed combo");
+            self.combos.insert(signature, combo_id.clone());
+            return Ok(combo_id);
+        }
         let name = format!(
             "{}-{}-{}-{}",
             opportunity.strategy,
@@ -91,38 +314,164 @@ impl<'a, A: ComboApi + ?Sized> ExecutionPlanner<'a, A> {
                 .unwrap_or_else(|| "NA".into()),
             opportunity.legs.len()
         );
-        self.client
-            .create_combo(&name, &opportunity.legs, is_usdc)
+        let combo_id = match self
+            .client
+            .create_combo(&name, &opportunity.legs, opportunity.settlement)
+            .await
+        {
+            Ok(combo_id) => combo_id,
+            // The exchange may have created the combo before the reply was
+            // lost; a retry must not create a second one.
+            Err(err) => match self.find_listed_combo(opportunity).await {
+                Some(combo_id) => {
+                    warn!(target: "execution.combo", combo = %combo_id, error = %err, "create_combo failed but the combo is listed");
+                    combo_id
+                }
+                None => return Err(err),
+            },
+        };
+        self.combos.insert(signature, combo_id.clone());
+        Ok(combo_id)
+    }
+
+    async fn find_listed_combo(&self, opportunity: &StrategyOpportunity) -> Option<String> {
+        match self
+            .client
+            .find_combo(
+                opportunity.currency,
+                opportunity.settlement,
+                &opportunity.legs,
+            )
             .await
+        {
+            Ok(found) => found,
+            Err(err) => {
+                warn!(target: "execution.combo", error = %err, "combo lookup failed");
+                None
+            }
+        }
     }
 }
 
+#[derive(Default)]
 pub struct MockComboApi {
-    pub combos: parking_lot::Mutex<Vec<(String, Vec<ComboLeg>, bool)>>,
+    pub combos: parking_lot::Mutex<Vec<(String, Vec<ComboLeg>, SettlementCurrency)>>,
+    /// Placed orders; the order id is `order-<index + 1>`.
+    pub orders: parking_lot::Mutex<Vec<OrderRequest>>,
+    pub edits: parking_lot::Mutex<Vec<(String, Decimal, Decimal)>>,
+    pub cancels: parking_lot::Mutex<Vec<String>>,
+    /// Asks returned for every block RFQ; RFQ ids are `rfq-<index + 1>`.
+    pub rfq_quotes: parking_lot::Mutex<Vec<BlockRfqQuote>>,
+    pub rfqs: parking_lot::Mutex<Vec<(Vec<ComboLeg>, Decimal)>>,
+    pub accepted_rfqs: parking_lot::Mutex<Vec<(String, Decimal, Decimal)>>,
+    pub cancelled_rfqs: parking_lot::Mutex<Vec<String>>,
+    /// Leg prices returned by `get_leg_prices`, keyed by instrument.
+    pub leg_prices: parking_lot::Mutex<std::collections::HashMap<String, Decimal>>,
+    /// Quotes served by `get_ticker`; unknown instruments return an error.
+    pub tickers: parking_lot::Mutex<std::collections::HashMap<String, Quote>>,
 }
 
 impl MockComboApi {
     pub fn new() -> Self {
-        Self {
-            combos: parking_lot::Mutex::new(Vec::new()),
-        }
+        Self::default()
     }
 }
 
 #[async_trait]
 impl ComboApi for MockComboApi {
-    async fn create_combo(&self, name: &str, legs: &[ComboLeg], is_usdc: bool) -> Result<String> {
+    async fn create_combo(
+        &self,
+        name: &str,
+        legs: &[ComboLeg],
+        settlement: SettlementCurrency,
+    ) -> Result<String> {
         self.combos
             .lock()
-            .push((name.to_string(), legs.to_vec(), is_usdc));
+            .push((name.to_string(), legs.to_vec(), settlement));
         Ok(format!("combo-{}", self.combos.lock().len()))
     }
 
+    async fn find_combo(
+        &self,
+        _currency: Currency,
+        settlement: SettlementCurrency,
+        legs: &[ComboLeg],
+    ) -> Result<Option<String>> {
+        let signature = combo_signature(settlement, legs);
+        Ok(self
+            .combos
+            .lock()
+            .iter()
+            .position(|(_, listed, book)| combo_signature(*book, listed) == signature)
+            .map(|index| format!("combo-{}", index + 1)))
+    }
+
     async fn get_leg_prices(&self, combo_id: &str, amount: Decimal) -> Result<serde_json::Value> {
+        let legs: Vec<serde_json::Value> = self
+            .leg_prices
+            .lock()
+            .iter()
+            .map(|(name, price)| json!({ "instrument_name": name, "price": price }))
+            .collect();
         Ok(json!({
             "combo_id": combo_id,
             "amount": amount,
             "fees": 0,
+            "legs": legs,
         }))
     }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<String> {
+        let mut orders = self.orders.lock();
+        orders.push(order.clone());
+        Ok(format!("order-{}", orders.len()))
+    }
+
+    async fn edit_order(&self, order_id: &str, amount: Decimal, price: Decimal) -> Result<()> {
+        self.edits
+            .lock()
+            .push((order_id.to_string(), amount, price));
+        Ok(())
+    }
+
+    async fn cancel_order(&self, order_id: &str) -> Result<()> {
+        self.cancels.lock().push(order_id.to_string());
+        Ok(())
+    }
+
+    async fn create_block_rfq(&self, legs: &[ComboLeg], amount: Decimal) -> Result<String> {
+        let mut rfqs = self.rfqs.lock();
+        rfqs.push((legs.to_vec(), amount));
+        Ok(format!("rfq-{}", rfqs.len()))
+    }
+
+    async fn get_block_rfq_quotes(&self, _rfq_id: &str) -> Result<Vec<BlockRfqQuote>> {
+        Ok(self.rfq_quotes.lock().clone())
+    }
+
+    async fn accept_block_rfq(
+        &self,
+        rfq_id: &str,
+        _legs: &[ComboLeg],
+        price: Decimal,
+        amount: Decimal,
+    ) -> Result<serde_json::Value> {
+        self.accepted_rfqs
+            .lock()
+            .push((rfq_id.to_string(), price, amount));
+        Ok(json!({ "block_trade_id": format!("block-{rfq_id}") }))
+    }
+
+    async fn cancel_block_rfq(&self, rfq_id: &str) -> Result<()> {
+        self.cancelled_rfqs.lock().push(rfq_id.to_string());
+        Ok(())
+    }
+
+    async fn get_ticker(&self, instrument_name: &str) -> Result<Quote> {
+        self.tickers
+            .lock()
+            .get(instrument_name)
+            .cloned()
+            .with_context(|| format!("no ticker for {instrument_name}"))
+    }
 }
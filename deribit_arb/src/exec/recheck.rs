@@ -0,0 +1,103 @@
+use super::{ComboApi, ExecutionPlanner};
+use crate::detect::DetectorSuite;
+use crate::model::{InstrumentSnapshot, StrategyOpportunity};
+use chrono::Utc;
+use rust_decimal::prelude::*;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RecheckRejection {
+    #[error("leg {0} missing from the chain")]
+    MissingLeg(String),
+    #[error("ticker refresh for {instrument} failed: {error}")]
+    TickerFailed { instrument: String, error: String },
+    #[error("refreshed quotes are {age_ms}ms old, budget {budget_ms}ms")]
+    LatencyBudget { age_ms: i64, budget_ms: u64 },
+    #[error("combo no longer detected on refreshed quotes")]
+    Vanished,
+    #[error("edge fell from {detected} to {rechecked} USD, below {min_fraction} of detection")]
+    EdgeDegraded {
+        detected: Decimal,
+        rechecked: Decimal,
+        min_fraction: f64,
+    },
+}
+
+impl RecheckRejection {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RecheckRejection::MissingLeg(_) => "recheck_missing_leg",
+            RecheckRejection::TickerFailed { .. } => "recheck_ticker_failed",
+            RecheckRejection::LatencyBudget { .. } => "recheck_latency",
+            RecheckRejection::Vanished => "recheck_vanished",
+            RecheckRejection::EdgeDegraded { .. } => "recheck_edge_degraded",
+        }
+    }
+}
+
+impl<A: ComboApi + ?Sized> ExecutionPlanner<'_, A> {
+    /// Refreshes every leg's ticker and re-runs detection on just those legs.
+    /// Returns the re-priced opportunity when it still keeps at least
+    /// `--recheck-edge-fraction` of the detected edge and the oldest refreshed
+    /// quote is within `--latency-budget-ms`.
+    pub async fn recheck(
+        &self,
+        detector: &DetectorSuite<'_>,
+        opportunity: &StrategyOpportunity,
+        instruments: &[InstrumentSnapshot],
+        min_fraction: f64,
+    ) -> Result<StrategyOpportunity, RecheckRejection> {
+        let mut legs = Vec::with_capacity(opportunity.legs.len());
+        for leg in &opportunity.legs {
+            let inst = instruments
+                .iter()
+                .find(|inst| inst.instrument.instrument_name == leg.instrument_name)
+                .cloned()
+                .ok_or_else(|| RecheckRejection::MissingLeg(leg.instrument_name.clone()))?;
+            legs.push(inst);
+        }
+        // Every leg in one round trip, so the quotes are as close in time as
+        // the API allows.
+        let names: Vec<String> = opportunity
+            .legs
+            .iter()
+            .map(|leg| leg.instrument_name.clone())
+            .collect();
+        let failed = |instrument: &str, err: anyhow::Error| RecheckRejection::TickerFailed {
+            instrument: instrument.to_string(),
+            error: format!("{err:#}"),
+        };
+        let quotes = self
+            .client
+            .get_tickers(&names)
+            .await
+            .map_err(|err| failed(&names.join(","), err))?;
+        for ((inst, quote), name) in legs.iter_mut().zip(quotes).zip(&names) {
+            inst.quote = quote.map_err(|err| failed(name, err))?;
+        }
+
+        let now = Utc::now();
+        let oldest = legs.iter().map(|inst| inst.quote.timestamp).min();
+        let age_ms = oldest.map_or(0, |oldest| (now - oldest).num_milliseconds());
+        let budget_ms = self.config.latency_budget_ms;
+        if age_ms > budget_ms as i64 {
+            return Err(RecheckRejection::LatencyBudget { age_ms, budget_ms });
+        }
+
+        let key = opportunity.combo_key();
+        let rechecked = detector
+            .scan_at(&legs, now)
+            .into_iter()
+            .find(|opp| opp.combo_key() == key)
+            .ok_or(RecheckRejection::Vanished)?;
+        let floor = opportunity.net_edge_usd * Decimal::from_f64(min_fraction).unwrap_or_default();
+        if rechecked.net_edge_usd < floor {
+            return Err(RecheckRejection::EdgeDegraded {
+                detected: opportunity.net_edge_usd,
+                rechecked: rechecked.net_edge_usd,
+                min_fraction,
+            });
+        }
+        Ok(rechecked)
+    }
+}
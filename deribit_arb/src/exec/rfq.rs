@@ -0,0 +1,176 @@
+use super::{touch_price, ComboApi, ExecutionPlanner};
+use crate::model::{BlockRfqQuote, StrategyOpportunity};
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// A maker quote scored against the screen opportunity it would replace.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RfqQuoteEvaluation {
+    pub quote: BlockRfqQuote,
+    /// Contracts this quote would fill, capped at the requested size.
+    pub contracts: Decimal,
+    pub block_fees_usd: Decimal,
+    pub net_edge_usd: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BlockRfqReport {
+    pub rfq_id: String,
+    pub contracts: Decimal,
+    pub quotes: Vec<RfqQuoteEvaluation>,
+    /// Index into `quotes` of the highest-edge quote.
+    pub best: Option<usize>,
+    /// Exchange response when the best quote was accepted.
+    pub accepted: Option<serde_json::Value>,
+}
+
+impl BlockRfqReport {
+    pub fn best_quote(&self) -> Option<&RfqQuoteEvaluation> {
+        self.best.and_then(|index| self.quotes.get(index))
+    }
+}
+
+impl<A: ComboApi + ?Sized> ExecutionPlanner<'_, A> {
+    /// Requests a block RFQ for `contracts` of the combo, waits
+    /// `--rfq-wait-secs` for maker quotes and scores each one against the
+    /// detected edge. The best quote is accepted only with `--rfq-execute`
+    /// outside dry-run and when it clears the strategy's min edge; otherwise
+    /// the RFQ is cancelled.
+    pub async fn request_block(
+        &self,
+        opportunity: &StrategyOpportunity,
+        contracts: Decimal,
+    ) -> Result<BlockRfqReport> {
+        let rfq_id = self
+            .client
+            .create_block_rfq(&opportunity.legs, contracts)
+            .await
+            .context("failed to create block RFQ")?;
+        info!(target: "execution.rfq", rfq = %rfq_id, %contracts, "block RFQ opened");
+        if self.config.rfq_wait_secs > 0 {
+            sleep(Duration::from_secs(self.config.rfq_wait_secs)).await;
+        }
+        let quotes = match self.client.get_block_rfq_quotes(&rfq_id).await {
+            Ok(quotes) => quotes,
+            Err(err) => {
+                self.close_rfq(&rfq_id).await;
+                return Err(err.context("failed to fetch block RFQ quotes"));
+            }
+        };
+
+        let quotes: Vec<RfqQuoteEvaluation> = quotes
+            .into_iter()
+            .map(|quote| evaluate_quote(opportunity, quote, contracts))
+            .collect();
+        let best = quotes
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, eval)| eval.net_edge_usd)
+            .map(|(index, _)| index);
+        let mut report = BlockRfqReport {
+            rfq_id,
+            contracts,
+            quotes,
+            best,
+            accepted: None,
+        };
+
+        let min_edge = self.config.requirements(opportunity.strategy).min_edge_usd;
+        let acceptable = report
+            .best_quote()
+            .filter(|eval| eval.net_edge_usd > Decimal::ZERO && eval.net_edge_usd >= min_edge)
+            .cloned();
+        match acceptable {
+            // Over the order budget: the deferral is logged by the budget.
+            Some(_)
+                if self.config.rfq_execute
+                    && !self.config.dry_run
+                    && self.take_orders(1, chrono::Utc::now()).is_err() =>
+            {
+                self.close_rfq(&report.rfq_id).await;
+            }
+            Some(eval) if self.config.rfq_execute && !self.config.dry_run => {
+                let accepted = self
+                    .client
+                    .accept_block_rfq(
+                        &report.rfq_id,
+                        &opportunity.legs,
+                        eval.quote.price,
+                        eval.contracts,
+                    )
+                    .await;
+                match accepted {
+                    Ok(response) => {
+                        info!(
+                            target: "execution.rfq",
+                            rfq = %report.rfq_id,
+                            price = %eval.quote.price,
+                            edge = %eval.net_edge_usd,
+                            "block RFQ quote accepted"
+                        );
+                        report.accepted = Some(response);
+                    }
+                    Err(err) => {
+                        warn!(target: "execution.rfq", rfq = %report.rfq_id, error = %err, "failed to accept block RFQ quote");
+                        self.close_rfq(&report.rfq_id).await;
+                    }
+                }
+            }
+            Some(eval) => {
+                info!(
+                    target: "execution.rfq",
+                    rfq = %report.rfq_id,
+                    price = %eval.quote.price,
+                    edge = %eval.net_edge_usd,
+                    "block RFQ quote clears edge; not executing"
+                );
+                self.close_rfq(&report.rfq_id).await;
+            }
+            None => {
+                info!(target: "execution.rfq", rfq = %report.rfq_id, quotes = report.quotes.len(), "no block RFQ quote clears edge");
+                self.close_rfq(&report.rfq_id).await;
+            }
+        }
+        Ok(report)
+    }
+
+    async fn close_rfq(&self, rfq_id: &str) {
+        if let Err(err) = self.client.cancel_block_rfq(rfq_id).await {
+            warn!(target: "execution.rfq", rfq = %rfq_id, error = %err, "failed to cancel block RFQ");
+        }
+    }
+}
+
+/// Edge of filling at the quote instead of the screen touch. Block trades
+/// pay the full per-leg taker fee (no combo discount), so the discount the
+/// screen combo earned is added back before scaling to the quoted size.
+pub fn evaluate_quote(
+    opportunity: &StrategyOpportunity,
+    quote: BlockRfqQuote,
+    contracts: Decimal,
+) -> RfqQuoteEvaluation {
+    let fees = &opportunity.fee_breakdown;
+    let size = opportunity.size_contracts;
+    let filled = quote.amount.min(contracts);
+    if size.is_zero() {
+        return RfqQuoteEvaluation {
+            quote,
+            contracts: filled,
+            block_fees_usd: Decimal::ZERO,
+            net_edge_usd: Decimal::ZERO,
+        };
+    }
+    let usd_per_native = opportunity.usd_per_unit(opportunity.settlement);
+    let gross_per_contract = (opportunity.net_edge_usd + fees.total_usd) / size
+        + (touch_price(opportunity) - quote.price) * usd_per_native;
+    let block_fees_per_contract = (fees.total_usd + fees.combo_discount_usd) / size;
+    RfqQuoteEvaluation {
+        quote,
+        contracts: filled,
+        block_fees_usd: block_fees_per_contract * filled,
+        net_edge_usd: (gross_per_contract - block_fees_per_contract) * filled,
+    }
+}
@@ -0,0 +1,139 @@
+use super::{ComboApi, ExecutionPlanner};
+use crate::model::{InstrumentSnapshot, StrategyOpportunity};
+use anyhow::{anyhow, bail, Result};
+use rust_decimal::prelude::*;
+use serde_json::json;
+
+impl<A: ComboApi + ?Sized> ExecutionPlanner<'_, A> {
+    /// Shrinks `size_contracts` to the largest size at which every leg trades
+    /// a whole multiple of its `min_trade_amount`. Deribit counts option
+    /// amounts in the underlying, so a leg's amount is its contracts times
+    /// the instrument's `contract_size`. Edge, fees and costs scale with the
+    /// size; fails when no legal size is left.
+    pub fn fit_trade_amounts(
+        &self,
+        opportunity: &StrategyOpportunity,
+        instruments: &[InstrumentSnapshot],
+    ) -> Result<StrategyOpportunity> {
+        let size = opportunity.size_contracts;
+        if size <= Decimal::ZERO {
+            bail!("combo has no size to trade");
+        }
+        let mut step = Decimal::ZERO;
+        for leg in &opportunity.legs {
+            let inst = instruments
+                .iter()
+                .find(|inst| inst.instrument.instrument_name == leg.instrument_name)
+                .ok_or_else(|| anyhow!("leg {} missing from the chain", leg.instrument_name))?;
+            // Cross-book legs carry their own contract count in the touches.
+            let contracts_per_combo = opportunity
+                .touches
+                .iter()
+                .find(|touch| touch.instrument_name == leg.instrument_name)
+                .map(|touch| touch.size_contracts / size)
+                .unwrap_or_else(|| Decimal::from(leg.ratio));
+            let per_combo = contracts_per_combo * inst.instrument.contract_size;
+            let min_trade = inst.instrument.min_trade_amount;
+            if per_combo.is_zero() || min_trade <= Decimal::ZERO {
+                continue;
+            }
+            let leg_step = (min_trade / per_combo).normalize();
+            step = if step.is_zero() {
+                leg_step
+            } else {
+                decimal_lcm(step, leg_step).ok_or_else(|| {
+                    anyhow!("no common trade amount step across legs ({step} and {leg_step})")
+                })?
+            };
+        }
+        if step.is_zero() {
+            return Ok(opportunity.clone());
+        }
+
+        let legal = ((size / step).floor() * step).normalize();
+        if legal.is_zero() {
+            bail!(
+                "size {size} is below the smallest legal combo size {step} given leg min_trade_amount"
+            );
+        }
+        if legal < Decimal::from(self.config.min_depth_contracts) {
+            bail!(
+                "legal size {legal} (step {step}) is below min depth of {} contracts",
+                self.config.min_depth_contracts
+            );
+        }
+        if legal == size {
+            return Ok(opportunity.clone());
+        }
+        let sized = scale(opportunity, legal);
+        if sized.net_edge_usd <= Decimal::ZERO {
+            bail!("no positive edge left at legal size {legal}");
+        }
+        Ok(sized)
+    }
+}
+
+/// Resizes a combo to `size` contracts, keeping its per-contract economics.
+fn scale(opportunity: &StrategyOpportunity, size: Decimal) -> StrategyOpportunity {
+    let factor = size / opportunity.size_contracts;
+    let mut sized = opportunity.clone();
+    sized.size_contracts = size;
+    for touch in &mut sized.touches {
+        touch.size_contracts *= factor;
+    }
+    sized.total_cost *= factor;
+    sized.max_payout *= factor;
+    sized.net_edge_native *= factor;
+    sized.net_edge_usd *= factor;
+    sized.notional_usd *= factor;
+    if let Some(margin) = &mut sized.estimated_margin_usd {
+        *margin *= factor;
+    }
+
+    let fees = &mut sized.fee_breakdown;
+    for leg in &mut fees.legs {
+        leg.trade_fee_native *= factor;
+        leg.trade_fee_usd *= factor;
+    }
+    for value in [
+        &mut fees.combo_discount,
+        &mut fees.combo_discount_usd,
+        &mut fees.delivery_fee,
+        &mut fees.delivery_fee_usd,
+        &mut fees.hedge_fee,
+        &mut fees.hedge_fee_usd,
+        &mut fees.total_native,
+        &mut fees.total_usd,
+    ] {
+        *value *= factor;
+    }
+
+    let plan = &mut sized.execution_plan;
+    plan.price_limit *= factor;
+    if let Some(amount) = plan.create_payload.get_mut("amount") {
+        *amount = json!(sized.size_contracts);
+    }
+    if let Some(hedge) = &mut plan.hedge {
+        hedge.amount *= factor;
+        hedge.combo_delta *= factor;
+        hedge.notional_usd *= factor;
+    }
+    sized
+}
+
+/// Least common multiple of two positive decimals, e.g. 0.1 and 0.25 give 0.5.
+fn decimal_lcm(a: Decimal, b: Decimal) -> Option<Decimal> {
+    let scale = a.scale().max(b.scale());
+    let as_int = |value: Decimal| -> Option<i128> {
+        let mut value = value;
+        value.rescale(scale);
+        (value.scale() == scale).then(|| value.mantissa())
+    };
+    let (x, y) = (as_int(a)?, as_int(b)?);
+    let (mut p, mut q) = (x, y);
+    while q != 0 {
+        (p, q) = (q, p % q);
+    }
+    let lcm = (x / p).checked_mul(y)?;
+    Decimal::try_from_i128_with_scale(lcm, scale).ok()
+}
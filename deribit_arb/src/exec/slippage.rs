@@ -0,0 +1,98 @@
+use crate::model::{ComboSide, StrategyOpportunity};
+use rust_decimal::prelude::*;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+/// One leg's detected touch against the price `get_leg_prices` previewed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LegPreview {
+    pub instrument_name: String,
+    pub side: ComboSide,
+    pub touch_price: Decimal,
+    /// `None` when the preview did not price this leg.
+    pub preview_price: Option<Decimal>,
+    /// Cost of filling at the preview instead of the touch for the whole
+    /// leg; positive when the preview is worse.
+    pub slippage_usd: Option<Decimal>,
+    pub slippage_bps: Option<f64>,
+}
+
+/// Pairs each touched leg with its price in a `private/get_leg_prices`
+/// response (`{"legs": [{"instrument_name", "price", ..}]}`).
+pub fn preview_legs(
+    opportunity: &StrategyOpportunity,
+    preview: &serde_json::Value,
+) -> Vec<LegPreview> {
+    let priced: Vec<(&str, Decimal)> = preview
+        .get("legs")
+        .and_then(|legs| legs.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|leg| {
+            let name = leg.get("instrument_name")?.as_str()?;
+            Some((name, json_decimal(leg.get("price")?)?))
+        })
+        .collect();
+    opportunity
+        .touches
+        .iter()
+        .map(|touch| {
+            let preview_price = priced
+                .iter()
+                .find(|(name, _)| *name == touch.instrument_name)
+                .map(|(_, price)| *price);
+            let adverse = preview_price.map(|price| match touch.side {
+                ComboSide::Buy => price - touch.price,
+                ComboSide::Sell => touch.price - price,
+            });
+            let slippage_usd = adverse.map(|per_contract| {
+                leg_usd(
+                    opportunity,
+                    &touch.instrument_name,
+                    per_contract * touch.size_contracts,
+                )
+            });
+            let slippage_bps = adverse
+                .filter(|_| !touch.price.is_zero())
+                .and_then(|per_contract| (per_contract / touch.price * dec!(10000)).to_f64());
+            LegPreview {
+                instrument_name: touch.instrument_name.clone(),
+                side: touch.side,
+                touch_price: touch.price,
+                preview_price,
+                slippage_usd,
+                slippage_bps,
+            }
+        })
+        .collect()
+}
+
+/// Converts a premium amount of one of `opportunity`'s legs to USD: coin
+/// legs at the detection index, stablecoin legs at
+/// [`StrategyOpportunity::fx_usd`].
+pub fn leg_usd(
+    opportunity: &StrategyOpportunity,
+    instrument_name: &str,
+    native: Decimal,
+) -> Decimal {
+    let settlement = opportunity
+        .fee_breakdown
+        .legs
+        .iter()
+        .find(|leg| leg.instrument_name == instrument_name)
+        .map_or(opportunity.settlement, |leg| leg.settlement);
+    native * opportunity.usd_per_unit(settlement)
+}
+
+fn json_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(text) => Decimal::from_str(text).ok(),
+        serde_json::Value::Number(number) => {
+            let text = number.to_string();
+            Decimal::from_str(&text)
+                .or_else(|_| Decimal::from_scientific(&text))
+                .ok()
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,91 @@
+use super::touch_price;
+use crate::model::{ComboSide, InstrumentSnapshot, StrategyKind, StrategyOpportunity};
+use rust_decimal::prelude::*;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TickRejection {
+    #[error("leg {0} missing from the chain")]
+    MissingLeg(String),
+    #[error("rounding to tick {tick} costs {cost_usd} USD, erasing the {net_edge_usd} USD edge")]
+    EdgeErased {
+        tick: Decimal,
+        cost_usd: Decimal,
+        net_edge_usd: Decimal,
+    },
+}
+
+impl TickRejection {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TickRejection::MissingLeg(_) => "tick_missing_leg",
+            TickRejection::EdgeErased { .. } => "tick_edge_erased",
+        }
+    }
+}
+
+/// Snaps `price` onto the `tick` grid, up when `side` buys and down when it
+/// sells, so the limit still crosses the price it was derived from.
+pub fn snap_price(price: Decimal, tick: Decimal, side: ComboSide) -> Decimal {
+    if tick <= Decimal::ZERO {
+        return price;
+    }
+    let ticks = price / tick;
+    let ticks = match side {
+        ComboSide::Buy => ticks.ceil(),
+        ComboSide::Sell => ticks.floor(),
+    };
+    ticks * tick
+}
+
+/// Rounds leg touches and the combo limit onto the tick grid before an
+/// order is built. The combo trades on the coarsest leg tick; snapping in
+/// the taker's unfavourable direction keeps the IOC limit marketable, and
+/// the extra cost comes out of the net edge. Plans left without edge are
+/// rejected.
+pub fn snap_to_ticks(
+    opportunity: &StrategyOpportunity,
+    instruments: &[InstrumentSnapshot],
+) -> Result<StrategyOpportunity, TickRejection> {
+    let mut snapped = opportunity.clone();
+    let mut tick = Decimal::ZERO;
+    for touch in &mut snapped.touches {
+        let inst = instruments
+            .iter()
+            .find(|inst| inst.instrument.instrument_name == touch.instrument_name)
+            .ok_or_else(|| TickRejection::MissingLeg(touch.instrument_name.clone()))?;
+        tick = tick.max(inst.instrument.tick_size);
+        touch.price = snap_price(touch.price, inst.instrument.tick_size, touch.side);
+    }
+    if tick.is_zero() || opportunity.size_contracts.is_zero() {
+        return Ok(snapped);
+    }
+
+    let price = touch_price(opportunity);
+    let limit = snap_price(price, tick, ComboSide::Buy);
+    let cost_native = (limit - price) * opportunity.size_contracts;
+    if cost_native.is_zero() {
+        return Ok(snapped);
+    }
+    let cost_usd = cost_native * opportunity.usd_per_unit(opportunity.settlement);
+    let net_edge_usd = opportunity.net_edge_usd - cost_usd;
+    if net_edge_usd <= Decimal::ZERO {
+        return Err(TickRejection::EdgeErased {
+            tick,
+            cost_usd,
+            net_edge_usd: opportunity.net_edge_usd,
+        });
+    }
+    // `price_limit` is the combo's total debit, or total credit for calendars.
+    let limit_total = limit * opportunity.size_contracts;
+    snapped.execution_plan.price_limit = match opportunity.strategy {
+        StrategyKind::Calendar => -limit_total,
+        _ => limit_total,
+    };
+    snapped.net_edge_native -= cost_native;
+    snapped.edge_bps *= (net_edge_usd / opportunity.net_edge_usd)
+        .to_f64()
+        .unwrap_or(0.0);
+    snapped.net_edge_usd = net_edge_usd;
+    Ok(snapped)
+}
@@ -1,8 +1,161 @@
-use crate::model::{ComboSide, FeeBreakdown, FillRole, LegFee, SettlementCurrency};
-use anyhow::{anyhow, Result};
+use crate::model::{ComboSide, ExecutionVenue, FeeBreakdown, FillRole, LegFee, SettlementCurrency};
+use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, Utc};
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// Option fee rates for one settlement type. Rates are fractions of the
+/// underlying (coin) or index (USDC) per contract; caps are fractions of the
+/// option premium.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FeeRates {
+    pub taker_rate: Decimal,
+    pub maker_rate: Decimal,
+    /// Charged on both sides of a block trade, under the same premium cap.
+    pub block_rate: Decimal,
+    pub premium_cap: Decimal,
+    pub delivery_rate: Decimal,
+    pub delivery_cap: Decimal,
+}
+
+impl Default for FeeRates {
+    /// Deribit's published base tier; options charge makers and takers alike.
+    fn default() -> Self {
+        Self {
+            taker_rate: dec!(0.0003),
+            maker_rate: dec!(0.0003),
+            block_rate: dec!(0.0003),
+            premium_cap: dec!(0.125),
+            delivery_rate: dec!(0.00015),
+            delivery_cap: dec!(0.125),
+        }
+    }
+}
+
+impl FeeRates {
+    pub fn trade_rate(&self, role: FillRole, venue: ExecutionVenue) -> Decimal {
+        match (venue, role) {
+            (ExecutionVenue::Block, _) => self.block_rate,
+            (ExecutionVenue::Screen, FillRole::Maker) => self.maker_rate,
+            (ExecutionVenue::Screen, FillRole::Taker) => self.taker_rate,
+        }
+    }
+}
+
+/// Fee rates for one account tier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FeeSchedule {
+    pub coin: FeeRates,
+    /// Rates of every linear book; USDT and EURR listings share them.
+    pub usdc: FeeRates,
+    /// Zero the cheaper side of a combo, as Deribit does for option combos
+    /// traded on screen. Block trades never get it.
+    pub combo_discount: bool,
+    /// Most distinct instruments a combo may hold and still get the discount.
+    pub combo_discount_max_legs: usize,
+    /// Only combos whose legs share one expiry get the discount, which
+    /// excludes calendars and jelly rolls.
+    pub combo_discount_same_expiry: bool,
+    /// Taker rate for perpetual and dated futures hedge legs.
+    pub perpetual_taker_rate: Decimal,
+    pub futures_block_rate: Decimal,
+    /// Charged on the notional of dated futures held to settlement.
+    pub futures_settlement_rate: Decimal,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            coin: FeeRates::default(),
+            usdc: FeeRates::default(),
+            combo_discount: true,
+            combo_discount_max_legs: 4,
+            combo_discount_same_expiry: true,
+            perpetual_taker_rate: dec!(0.0005),
+            futures_block_rate: dec!(0.00025),
+            futures_settlement_rate: dec!(0.00025),
+        }
+    }
+}
+
+impl FeeSchedule {
+    pub fn futures_rate(&self, venue: ExecutionVenue) -> Decimal {
+        match venue {
+            ExecutionVenue::Screen => self.perpetual_taker_rate,
+            ExecutionVenue::Block => self.futures_block_rate,
+        }
+    }
+
+    /// Whether `legs` qualify for the combo discount: on-screen options on a
+    /// single settlement book, buying and selling, within the instrument
+    /// count and, if configured, sharing one expiry.
+    pub fn combo_discount_applies(&self, legs: &[LegFeeInput]) -> bool {
+        let Some(first) = legs.first() else {
+            return false;
+        };
+        let instruments: BTreeSet<&str> = legs
+            .iter()
+            .map(|leg| leg.instrument_name.as_str())
+            .collect();
+        self.combo_discount
+            && instruments.len() <= self.combo_discount_max_legs
+            && legs.iter().any(|leg| leg.side == ComboSide::Buy)
+            && legs.iter().any(|leg| leg.side == ComboSide::Sell)
+            && legs.iter().all(|leg| {
+                leg.venue == ExecutionVenue::Screen
+                    // Deribit combos live on one settlement's book, so legs
+                    // spanning books trade separately.
+                    && leg.settlement == first.settlement
+                    && (!self.combo_discount_same_expiry || leg.expiry == first.expiry)
+            })
+    }
+
+    pub fn rates(&self, settlement: SettlementCurrency) -> &FeeRates {
+        match settlement {
+            SettlementCurrency::Coin => &self.coin,
+            SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                &self.usdc
+            }
+        }
+    }
+
+    /// Loads `tier` from a `--fee-schedule` TOML file of `[tiers.<name>]`
+    /// tables. Without a file only the built-in `default` tier exists.
+    pub fn resolve(path: Option<&Path>, tier: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct FeeScheduleFile {
+            #[serde(default)]
+            tiers: BTreeMap<String, FeeSchedule>,
+        }
+        let mut tiers = match path {
+            Some(path) => {
+                let raw = std::fs::read_to_string(path)
+                    .with_context(|| format!("reading fee schedule {}", path.display()))?;
+                toml::from_str::<FeeScheduleFile>(&raw)
+                    .with_context(|| format!("parsing fee schedule {}", path.display()))?
+                    .tiers
+            }
+            None => BTreeMap::new(),
+        };
+        match tiers.remove(tier) {
+            Some(schedule) => Ok(schedule),
+            None if tier == "default" => Ok(Self::default()),
+            None => {
+                let known: Vec<&str> = tiers.keys().map(String::as_str).collect();
+                Err(anyhow!(
+                    "unknown fee tier {tier} (available: default, {})",
+                    known.join(", ")
+                ))
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct LegFeeInput {
@@ -10,6 +163,7 @@ pub struct LegFeeInput {
     pub side: ComboSide,
     pub settlement: SettlementCurrency,
     pub role: FillRole,
+    pub venue: ExecutionVenue,
     pub option_price: Decimal,
     pub index_price: Decimal,
     pub contracts: Decimal,
@@ -18,32 +172,61 @@ pub struct LegFeeInput {
     pub is_daily: bool,
 }
 
+/// A perpetual or dated futures leg. Dated futures (`expiry` set) also pay
+/// the settlement fee when the combo is held to expiry.
+#[derive(Debug, Clone)]
+pub struct HedgeFeeInput {
+    pub instrument_name: String,
+    pub settlement: SettlementCurrency,
+    pub venue: ExecutionVenue,
+    pub notional_usd: Decimal,
+    pub index_price: Decimal,
+    pub expiry: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct FeeComputationContext {
     pub legs: Vec<LegFeeInput>,
     pub hold_to_expiry: bool,
+    pub hedge: Option<HedgeFeeInput>,
 }
 
-pub struct FeeEngine;
+#[derive(Default)]
+pub struct FeeEngine {
+    schedule: FeeSchedule,
+}
 
 impl FeeEngine {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    pub fn with_schedule(schedule: FeeSchedule) -> Self {
+        Self { schedule }
     }
 
+    /// Legs may settle in different currencies: each [`LegFee`] stays in its
+    /// own settlement, while the native totals are expressed in the first
+    /// leg's settlement, converting other legs through their USD value at the
+    /// first leg's index price.
     pub fn compute(&self, ctx: FeeComputationContext) -> Result<FeeBreakdown> {
         if ctx.legs.is_empty() {
             return Err(anyhow!("no legs provided"));
         }
         let settlement = ctx.legs[0].settlement;
-        if !ctx.legs.iter().all(|leg| leg.settlement == settlement) {
-            return Err(anyhow!("mixed settlement combos not supported"));
-        }
+        let index_price = ctx.legs[0].index_price;
+        let in_settlement = |fee_settlement: SettlementCurrency, native: Decimal, usd: Decimal| {
+            if fee_settlement == settlement {
+                native
+            } else {
+                native_from_usd(usd, settlement, index_price)
+            }
+        };
 
         let mut leg_fees: Vec<LegFee> = ctx
             .legs
             .iter()
-            .map(|leg| compute_trade_fee(leg))
+            .map(|leg| compute_trade_fee(leg, self.schedule.rates(leg.settlement)))
             .collect::<Result<Vec<_>>>()?;
 
         let mut buy_total_native = Decimal::ZERO;
@@ -53,35 +236,40 @@ impl FeeEngine {
         for fee in &leg_fees {
             match fee.side {
                 ComboSide::Buy => {
-                    buy_total_native += fee.trade_fee_native;
+                    buy_total_native +=
+                        in_settlement(fee.settlement, fee.trade_fee_native, fee.trade_fee_usd);
                     buy_total_usd += fee.trade_fee_usd;
                 }
                 ComboSide::Sell => {
-                    sell_total_native += fee.trade_fee_native;
+                    sell_total_native +=
+                        in_settlement(fee.settlement, fee.trade_fee_native, fee.trade_fee_usd);
                     sell_total_usd += fee.trade_fee_usd;
                 }
             }
         }
 
-        let (combo_discount_native, combo_discount_usd) = if buy_total_usd <= sell_total_usd {
-            for fee in leg_fees
-                .iter_mut()
-                .filter(|f| matches!(f.side, ComboSide::Buy))
-            {
-                fee.trade_fee_native = Decimal::ZERO;
-                fee.trade_fee_usd = Decimal::ZERO;
-            }
-            (buy_total_native, buy_total_usd)
-        } else {
-            for fee in leg_fees
-                .iter_mut()
-                .filter(|f| matches!(f.side, ComboSide::Sell))
-            {
-                fee.trade_fee_native = Decimal::ZERO;
-                fee.trade_fee_usd = Decimal::ZERO;
-            }
-            (sell_total_native, sell_total_usd)
-        };
+        let (combo_discount_native, combo_discount_usd) =
+            if !self.schedule.combo_discount_applies(&ctx.legs) {
+                (Decimal::ZERO, Decimal::ZERO)
+            } else if buy_total_usd <= sell_total_usd {
+                for fee in leg_fees
+                    .iter_mut()
+                    .filter(|f| matches!(f.side, ComboSide::Buy))
+                {
+                    fee.trade_fee_native = Decimal::ZERO;
+                    fee.trade_fee_usd = Decimal::ZERO;
+                }
+                (buy_total_native, buy_total_usd)
+            } else {
+                for fee in leg_fees
+                    .iter_mut()
+                    .filter(|f| matches!(f.side, ComboSide::Sell))
+                {
+                    fee.trade_fee_native = Decimal::ZERO;
+                    fee.trade_fee_usd = Decimal::ZERO;
+                }
+                (sell_total_native, sell_total_usd)
+            };
 
         let mut delivery_native = Decimal::ZERO;
         let mut delivery_usd = Decimal::ZERO;
@@ -95,34 +283,40 @@ impl FeeEngine {
                 let option_value_usd = leg.option_price
                     * contracts
                     * match leg.settlement {
-                        SettlementCurrency::Usdc => Decimal::ONE,
+                        SettlementCurrency::Usdc
+                        | SettlementCurrency::Usdt
+                        | SettlementCurrency::Eurr => Decimal::ONE,
                         SettlementCurrency::Coin => leg.index_price,
                     };
+                let rates = self.schedule.rates(leg.settlement);
                 let delivery_fee_usd =
-                    (notional_usd * dec!(0.00015)).min(option_value_usd * dec!(0.125));
-                let delivery_fee_native = match leg.settlement {
-                    SettlementCurrency::Usdc => delivery_fee_usd,
-                    SettlementCurrency::Coin => {
-                        if leg.index_price.is_zero() {
-                            Decimal::ZERO
-                        } else {
-                            delivery_fee_usd / leg.index_price
-                        }
-                    }
-                };
+                    (notional_usd * rates.delivery_rate).min(option_value_usd * rates.delivery_cap);
+                let delivery_fee_native =
+                    native_from_usd(delivery_fee_usd, leg.settlement, leg.index_price);
                 delivery_usd += delivery_fee_usd;
-                delivery_native += delivery_fee_native;
+                delivery_native +=
+                    in_settlement(leg.settlement, delivery_fee_native, delivery_fee_usd);
             }
         }
 
-        let total_native = leg_fees
-            .iter()
-            .fold(Decimal::ZERO, |acc, fee| acc + fee.trade_fee_native)
-            + delivery_native;
+        let (hedge_native, hedge_usd) = ctx
+            .hedge
+            .as_ref()
+            .map(|hedge| {
+                let (native, usd) = compute_hedge_fee(hedge, &self.schedule, ctx.hold_to_expiry);
+                (in_settlement(hedge.settlement, native, usd), usd)
+            })
+            .unwrap_or((Decimal::ZERO, Decimal::ZERO));
+
+        let total_native = leg_fees.iter().fold(Decimal::ZERO, |acc, fee| {
+            acc + in_settlement(fee.settlement, fee.trade_fee_native, fee.trade_fee_usd)
+        }) + delivery_native
+            + hedge_native;
         let total_usd = leg_fees
             .iter()
             .fold(Decimal::ZERO, |acc, fee| acc + fee.trade_fee_usd)
-            + delivery_usd;
+            + delivery_usd
+            + hedge_usd;
 
         Ok(FeeBreakdown {
             legs: leg_fees,
@@ -130,13 +324,15 @@ impl FeeEngine {
             combo_discount_usd,
             delivery_fee: delivery_native,
             delivery_fee_usd: delivery_usd,
+            hedge_fee: hedge_native,
+            hedge_fee_usd: hedge_usd,
             total_native,
             total_usd,
         })
     }
 }
 
-fn compute_trade_fee(input: &LegFeeInput) -> Result<LegFee> {
+fn compute_trade_fee(input: &LegFeeInput, rates: &FeeRates) -> Result<LegFee> {
     let contracts = input.contracts.abs();
     if contracts.is_zero() {
         return Ok(LegFee {
@@ -149,21 +345,18 @@ fn compute_trade_fee(input: &LegFeeInput) -> Result<LegFee> {
         });
     }
 
+    let rate = rates.trade_rate(input.role, input.venue);
     let (fee_native, fee_usd) = match input.settlement {
         SettlementCurrency::Coin => {
-            let max_pct = input.option_price * dec!(0.125);
-            let per_contract_fee = if dec!(0.0003) < max_pct {
-                dec!(0.0003)
-            } else {
-                max_pct
-            };
+            let max_pct = input.option_price * rates.premium_cap;
+            let per_contract_fee = if rate < max_pct { rate } else { max_pct };
             let total_native = per_contract_fee * contracts * input.contract_size;
             let total_usd = total_native * input.index_price;
             (total_native, total_usd)
         }
-        SettlementCurrency::Usdc => {
-            let cap = input.option_price * dec!(0.125);
-            let base = input.index_price * dec!(0.0003);
+        SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+            let cap = input.option_price * rates.premium_cap;
+            let base = input.index_price * rate;
             let per_contract_fee = if base < cap { base } else { cap };
             let total_native = per_contract_fee * contracts * input.contract_size;
             (total_native, total_native)
@@ -180,6 +373,44 @@ fn compute_trade_fee(input: &LegFeeInput) -> Result<LegFee> {
     })
 }
 
+// Futures trade fee (0.05% base-tier taker, 0.025% block) of traded notional,
+// plus the settlement fee for dated futures held to expiry, charged in the
+// settlement currency.
+fn compute_hedge_fee(
+    input: &HedgeFeeInput,
+    schedule: &FeeSchedule,
+    hold_to_expiry: bool,
+) -> (Decimal, Decimal) {
+    let notional_usd = input.notional_usd.abs();
+    let mut fee_usd = notional_usd * schedule.futures_rate(input.venue);
+    if hold_to_expiry && input.expiry.is_some() {
+        fee_usd += notional_usd * schedule.futures_settlement_rate;
+    }
+    (
+        native_from_usd(fee_usd, input.settlement, input.index_price),
+        fee_usd,
+    )
+}
+
+/// Converts a USD amount into `settlement` units at `index_price`; coin
+/// amounts are zero when the index is unknown.
+pub fn native_from_usd(
+    usd: Decimal,
+    settlement: SettlementCurrency,
+    index_price: Decimal,
+) -> Decimal {
+    match settlement {
+        SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => usd,
+        SettlementCurrency::Coin => {
+            if index_price.is_zero() {
+                Decimal::ZERO
+            } else {
+                usd / index_price
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,6 +430,7 @@ mod tests {
             side: ComboSide::Buy,
             settlement,
             role: FillRole::Taker,
+            venue: ExecutionVenue::Screen,
             option_price,
             index_price,
             contracts,
@@ -206,7 +438,7 @@ mod tests {
             expiry: Utc::now(),
             is_daily: false,
         };
-        let fee = compute_trade_fee(&input).expect("fee");
+        let fee = compute_trade_fee(&input, &FeeRates::default()).expect("fee");
         assert!(fee.trade_fee_native >= Decimal::ZERO);
         assert!(fee.trade_fee_usd >= Decimal::ZERO);
     }
@@ -0,0 +1,181 @@
+use crate::model::{
+    ComboSide, InstrumentSnapshot, OptionKind, SettlementCurrency, StrategyOpportunity,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal};
+use std::collections::HashMap;
+
+pub(crate) const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    /// Sensitivity per 1 vol point (0.01 absolute volatility).
+    pub vega: f64,
+}
+
+/// Black-Scholes greeks for a European option on a unit of the underlying.
+pub fn black_scholes(
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    years: f64,
+    vol: f64,
+    rate: f64,
+) -> Greeks {
+    if spot <= 0.0 || strike <= 0.0 {
+        return Greeks {
+            delta: 0.0,
+            gamma: 0.0,
+            vega: 0.0,
+        };
+    }
+    if years <= 0.0 || vol <= 0.0 {
+        let itm = match kind {
+            OptionKind::Call => spot > strike,
+            OptionKind::Put => spot < strike,
+        };
+        let delta = match (kind, itm) {
+            (OptionKind::Call, true) => 1.0,
+            (OptionKind::Put, true) => -1.0,
+            _ => 0.0,
+        };
+        return Greeks {
+            delta,
+            gamma: 0.0,
+            vega: 0.0,
+        };
+    }
+
+    let normal = Normal::new(0.0, 1.0).expect("standard normal");
+    let sqrt_t = years.sqrt();
+    let d1 = ((spot / strike).ln() + (rate + 0.5 * vol * vol) * years) / (vol * sqrt_t);
+    let delta = match kind {
+        OptionKind::Call => normal.cdf(d1),
+        OptionKind::Put => normal.cdf(d1) - 1.0,
+    };
+    let pdf = normal.pdf(d1);
+    Greeks {
+        delta,
+        gamma: pdf / (spot * vol * sqrt_t),
+        vega: spot * pdf * sqrt_t / 100.0,
+    }
+}
+
+/// Black-Scholes value of a European option on a unit of the underlying,
+/// in the underlying's quote currency; intrinsic value at or past expiry.
+pub fn black_scholes_price(
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    years: f64,
+    vol: f64,
+    rate: f64,
+) -> f64 {
+    let intrinsic = match kind {
+        OptionKind::Call => (spot - strike).max(0.0),
+        OptionKind::Put => (strike - spot).max(0.0),
+    };
+    if spot <= 0.0 || strike <= 0.0 || years <= 0.0 || vol <= 0.0 {
+        return intrinsic;
+    }
+    let normal = Normal::new(0.0, 1.0).expect("standard normal");
+    let sqrt_t = years.sqrt();
+    let d1 = ((spot / strike).ln() + (rate + 0.5 * vol * vol) * years) / (vol * sqrt_t);
+    let d2 = d1 - vol * sqrt_t;
+    let discounted = strike * (-rate * years).exp();
+    match kind {
+        OptionKind::Call => spot * normal.cdf(d1) - discounted * normal.cdf(d2),
+        OptionKind::Put => discounted * normal.cdf(-d2) - spot * normal.cdf(-d1),
+    }
+}
+
+/// Greeks for one contract of a listed instrument, using its mark IV and index.
+///
+/// Coin-settled options are inverse: the premium is paid in the underlying, so
+/// the hedgeable delta is the Black-Scholes delta less the coin premium.
+pub fn instrument_greeks(
+    snapshot: &InstrumentSnapshot,
+    premium: Decimal,
+    now: DateTime<Utc>,
+) -> Option<Greeks> {
+    let vol = snapshot.quote.mark_iv? / 100.0;
+    let spot = snapshot.quote.index_price.to_f64()?;
+    let strike = snapshot.instrument.strike.to_f64()?;
+    let contract_size = snapshot.instrument.contract_size.to_f64()?;
+    let years = (snapshot.instrument.expiry - now).num_seconds() as f64 / SECONDS_PER_YEAR;
+    let rate = snapshot.quote.interest_rate.unwrap_or(0.0);
+    let mut greeks = black_scholes(
+        snapshot.instrument.option_kind,
+        spot,
+        strike,
+        years,
+        vol,
+        rate,
+    );
+    if snapshot.instrument.settlement_currency == SettlementCurrency::Coin {
+        greeks.delta -= premium.to_f64().unwrap_or(0.0);
+    }
+    greeks.delta *= contract_size;
+    greeks.gamma *= contract_size;
+    greeks.vega *= contract_size;
+    Some(greeks)
+}
+
+/// Net greeks of a combo position in USD, so caps compare across currencies:
+/// delta is the USD value of the underlying exposure, gamma the change in
+/// USD delta for a 1% move, vega the USD change per vol point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PositionGreeks {
+    pub delta_usd: f64,
+    pub gamma_usd: f64,
+    pub vega_usd: f64,
+}
+
+impl PositionGreeks {
+    pub fn add(&mut self, other: &PositionGreeks) {
+        self.delta_usd += other.delta_usd;
+        self.gamma_usd += other.gamma_usd;
+        self.vega_usd += other.vega_usd;
+    }
+}
+
+/// Greeks of the full opportunity size, including its delta hedge. `None`
+/// when a leg is missing from `instruments` or has no mark IV.
+pub fn combo_greeks(
+    opportunity: &StrategyOpportunity,
+    instruments: &[InstrumentSnapshot],
+    now: DateTime<Utc>,
+) -> Option<PositionGreeks> {
+    let by_name: HashMap<&str, &InstrumentSnapshot> = instruments
+        .iter()
+        .map(|inst| (inst.instrument.instrument_name.as_str(), inst))
+        .collect();
+    let mut net = PositionGreeks::default();
+    for leg in &opportunity.legs {
+        let inst = by_name.get(leg.instrument_name.as_str())?;
+        let premium = opportunity
+            .touches
+            .iter()
+            .find(|touch| touch.instrument_name == leg.instrument_name)
+            .map(|touch| touch.price)
+            .unwrap_or_default();
+        let greeks = instrument_greeks(inst, premium, now)?;
+        let spot = inst.quote.index_price.to_f64()?;
+        let sign = match leg.side {
+            ComboSide::Buy => 1.0,
+            ComboSide::Sell => -1.0,
+        };
+        let contracts = sign * f64::from(leg.ratio) * opportunity.size_contracts.to_f64()?;
+        net.delta_usd += contracts * greeks.delta * spot;
+        net.gamma_usd += contracts * greeks.gamma * spot * spot / 100.0;
+        net.vega_usd += contracts * greeks.vega;
+    }
+    if let Some(hedge) = &opportunity.execution_plan.hedge {
+        net.delta_usd -= (hedge.combo_delta * opportunity.reference_index).to_f64()?;
+    }
+    Some(net)
+}
@@ -0,0 +1,175 @@
+use crate::config::AppConfig;
+use crate::model::{ChainSnapshot, Currency};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use rust_decimal::prelude::*;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use tracing::{info, warn};
+
+/// Calls needed within one check before the error rate is trusted.
+const MIN_CALLS_FOR_ERROR_RATE: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TripReason {
+    ApiErrors { errors: u64, calls: u64 },
+    StaleData { age_secs: i64 },
+    IndexDivergence { currency: Currency, bps: f64 },
+}
+
+impl Display for TripReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TripReason::ApiErrors { errors, calls } => {
+                write!(f, "{errors}/{calls} API calls failed")
+            }
+            TripReason::StaleData { age_secs } => {
+                write!(f, "freshest quote is {age_secs}s old")
+            }
+            TripReason::IndexDivergence { currency, bps } => {
+                write!(f, "{currency} index prices diverge by {bps:.1} bps")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthTransition {
+    Tripped(TripReason),
+    Reset,
+}
+
+#[derive(Default)]
+struct HealthState {
+    tripped: Option<Tripped>,
+    /// Cumulative `(calls, errors)` at the previous check.
+    api_totals: (u64, u64),
+}
+
+struct Tripped {
+    reason: TripReason,
+    until: DateTime<Utc>,
+}
+
+/// Circuit breaker over data and API health. While tripped, execution is
+/// disabled but scanning continues; it resets once the cool-down has passed
+/// without any check failing.
+#[derive(Default)]
+pub struct HealthMonitor {
+    state: Mutex<HealthState>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.state.lock().tripped.is_some()
+    }
+
+    pub fn trip_reason(&self) -> Option<TripReason> {
+        self.state
+            .lock()
+            .tripped
+            .as_ref()
+            .map(|tripped| tripped.reason.clone())
+    }
+
+    /// Runs every check against this cycle's chain snapshot and the
+    /// cumulative API call totals, returning a transition when the breaker
+    /// changes state.
+    pub fn check(
+        &self,
+        config: &AppConfig,
+        snapshot: &ChainSnapshot,
+        api_totals: (u64, u64),
+        now: DateTime<Utc>,
+    ) -> Option<HealthTransition> {
+        let mut state = self.state.lock();
+        let (calls, errors) = (
+            api_totals.0.saturating_sub(state.api_totals.0),
+            api_totals.1.saturating_sub(state.api_totals.1),
+        );
+        state.api_totals = api_totals;
+
+        let failure = api_failure(config, calls, errors)
+            .or_else(|| staleness_failure(config, snapshot, now))
+            .or_else(|| index_failure(config, snapshot, now));
+        let cooldown = Duration::seconds(config.breaker_cooldown_secs as i64);
+        match (failure, state.tripped.as_mut()) {
+            (Some(reason), Some(tripped)) => {
+                tripped.reason = reason;
+                tripped.until = now + cooldown;
+                None
+            }
+            (Some(reason), None) => {
+                warn!(
+                    target: "health",
+                    %reason,
+                    cooldown_secs = config.breaker_cooldown_secs,
+                    "circuit breaker tripped, execution disabled"
+                );
+                state.tripped = Some(Tripped {
+                    reason: reason.clone(),
+                    until: now + cooldown,
+                });
+                Some(HealthTransition::Tripped(reason))
+            }
+            (None, Some(tripped)) if now >= tripped.until => {
+                info!(target: "health", "circuit breaker reset, execution enabled");
+                state.tripped = None;
+                Some(HealthTransition::Reset)
+            }
+            (None, _) => None,
+        }
+    }
+}
+
+fn api_failure(config: &AppConfig, calls: u64, errors: u64) -> Option<TripReason> {
+    if calls < MIN_CALLS_FOR_ERROR_RATE {
+        return None;
+    }
+    (errors as f64 / calls as f64 > config.breaker_error_rate)
+        .then_some(TripReason::ApiErrors { errors, calls })
+}
+
+fn staleness_failure(
+    config: &AppConfig,
+    snapshot: &ChainSnapshot,
+    now: DateTime<Utc>,
+) -> Option<TripReason> {
+    let freshest = snapshot
+        .instruments
+        .iter()
+        .map(|inst| inst.quote.timestamp)
+        .max()?;
+    let age_secs = (now - freshest).num_seconds();
+    (age_secs > config.breaker_stale_secs as i64).then_some(TripReason::StaleData { age_secs })
+}
+
+/// Compares the index price carried by fresh quotes of each currency; the
+/// coin and USDC ticker feeds should agree to within `--breaker-index-bps`.
+fn index_failure(
+    config: &AppConfig,
+    snapshot: &ChainSnapshot,
+    now: DateTime<Utc>,
+) -> Option<TripReason> {
+    let horizon = Duration::seconds(config.breaker_stale_secs as i64);
+    let mut ranges: HashMap<Currency, (Decimal, Decimal)> = HashMap::new();
+    for inst in &snapshot.instruments {
+        let index = inst.quote.index_price;
+        if index <= Decimal::ZERO || now - inst.quote.timestamp > horizon {
+            continue;
+        }
+        let range = ranges
+            .entry(inst.instrument.currency)
+            .or_insert((index, index));
+        range.0 = range.0.min(index);
+        range.1 = range.1.max(index);
+    }
+    ranges.into_iter().find_map(|(currency, (low, high))| {
+        let bps = ((high - low) / low * Decimal::from(10_000)).to_f64()?;
+        (bps > config.breaker_index_bps).then_some(TripReason::IndexDivergence { currency, bps })
+    })
+}
@@ -0,0 +1,176 @@
+use crate::model::{StrategyKind, StrategyOpportunity};
+use crate::risk::RiskManager;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use tracing::debug;
+
+/// A simulated fill holding capital until its last leg expires.
+#[derive(Debug, Clone)]
+struct SimulatedCombo {
+    id: String,
+    strategy: StrategyKind,
+    locked_usd: Decimal,
+    pnl_usd: Decimal,
+    settles_at: DateTime<Utc>,
+}
+
+/// Point-in-time view of a [`SimulatedLedger`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LedgerSummary {
+    pub starting_capital_usd: Decimal,
+    /// Capital not locked in open combos.
+    pub cash_usd: Decimal,
+    pub locked_usd: Decimal,
+    pub open_combos: usize,
+    /// PnL of combos that reached expiry.
+    pub realized_pnl_usd: Decimal,
+    /// PnL still to be collected from open combos.
+    pub unrealized_pnl_usd: Decimal,
+    /// Capital locked when each combo opened, summed.
+    pub turnover_usd: Decimal,
+    /// Locked capital over equity (starting capital plus realized PnL).
+    pub utilization: f64,
+    pub peak_utilization: f64,
+    pub fills: u64,
+    pub settled: u64,
+    /// Fills skipped because free cash could not cover the capital.
+    pub skipped_for_capital: u64,
+}
+
+impl LedgerSummary {
+    /// Realized PnL over the starting capital.
+    pub fn return_on_capital(&self) -> f64 {
+        if self.starting_capital_usd.is_zero() {
+            return 0.0;
+        }
+        (self.realized_pnl_usd / self.starting_capital_usd)
+            .to_f64()
+            .unwrap_or(0.0)
+    }
+}
+
+/// Dry-run account assuming every approved plan filled at its previewed
+/// prices: capital is locked at the combo's estimated margin (its notional
+/// when no margin estimate exists) and released with the expected PnL when
+/// the last leg expires. A realism layer over per-opportunity edge, showing
+/// how much of it a fixed capital base could actually have captured.
+#[derive(Debug, Clone)]
+pub struct SimulatedLedger {
+    starting_capital_usd: Decimal,
+    cash_usd: Decimal,
+    open: Vec<SimulatedCombo>,
+    realized_pnl_usd: Decimal,
+    turnover_usd: Decimal,
+    peak_utilization: f64,
+    fills: u64,
+    settled: u64,
+    skipped_for_capital: u64,
+}
+
+impl SimulatedLedger {
+    pub fn new(starting_capital_usd: Decimal) -> Self {
+        Self {
+            starting_capital_usd,
+            cash_usd: starting_capital_usd,
+            open: Vec::new(),
+            realized_pnl_usd: Decimal::ZERO,
+            turnover_usd: Decimal::ZERO,
+            peak_utilization: 0.0,
+            fills: 0,
+            settled: 0,
+            skipped_for_capital: 0,
+        }
+    }
+
+    /// Books `opportunity` as filled, earning `pnl_usd` at expiry (its
+    /// edge net of preview slippage). Returns `false`, booking nothing, when
+    /// free cash cannot cover the capital it locks. Expired combos keep
+    /// their capital until [`Self::settle`] releases it.
+    pub fn fill(
+        &mut self,
+        opportunity: &StrategyOpportunity,
+        pnl_usd: Decimal,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let locked_usd = opportunity.locked_capital_usd().max(Decimal::ZERO);
+        if locked_usd > self.cash_usd {
+            self.skipped_for_capital += 1;
+            return false;
+        }
+        let settles_at = opportunity.expiry.iter().max().copied().unwrap_or(now);
+        self.cash_usd -= locked_usd;
+        self.turnover_usd += locked_usd;
+        self.fills += 1;
+        self.open.push(SimulatedCombo {
+            id: opportunity.id.clone(),
+            strategy: opportunity.strategy,
+            locked_usd,
+            pnl_usd,
+            settles_at,
+        });
+        self.peak_utilization = self.peak_utilization.max(self.utilization());
+        true
+    }
+
+    /// Releases the capital and PnL of every combo expired by `now`,
+    /// returning the PnL each one realized.
+    pub fn settle(&mut self, now: DateTime<Utc>) -> Vec<Decimal> {
+        let (expired, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.open)
+            .into_iter()
+            .partition(|combo| combo.settles_at <= now);
+        self.open = open;
+        let mut realized = Vec::with_capacity(expired.len());
+        for combo in expired {
+            debug!(
+                target: "ledger",
+                id = %combo.id,
+                strategy = %combo.strategy,
+                pnl_usd = %combo.pnl_usd,
+                "simulated combo settled"
+            );
+            self.cash_usd += combo.locked_usd + combo.pnl_usd;
+            self.realized_pnl_usd += combo.pnl_usd;
+            self.settled += 1;
+            realized.push(combo.pnl_usd);
+        }
+        realized
+    }
+
+    /// [`Self::settle`], booking each realized PnL with `risk` so the
+    /// daily loss limit sees it.
+    pub fn settle_into(&mut self, now: DateTime<Utc>, risk: &RiskManager) {
+        for pnl_usd in self.settle(now) {
+            risk.record_pnl(pnl_usd, now);
+        }
+    }
+
+    fn locked_usd(&self) -> Decimal {
+        self.open.iter().map(|combo| combo.locked_usd).sum()
+    }
+
+    fn utilization(&self) -> f64 {
+        let equity = self.starting_capital_usd + self.realized_pnl_usd;
+        if equity <= Decimal::ZERO {
+            return 0.0;
+        }
+        (self.locked_usd() / equity).to_f64().unwrap_or(0.0)
+    }
+
+    pub fn summary(&self) -> LedgerSummary {
+        LedgerSummary {
+            starting_capital_usd: self.starting_capital_usd,
+            cash_usd: self.cash_usd,
+            locked_usd: self.locked_usd(),
+            open_combos: self.open.len(),
+            realized_pnl_usd: self.realized_pnl_usd,
+            unrealized_pnl_usd: self.open.iter().map(|combo| combo.pnl_usd).sum(),
+            turnover_usd: self.turnover_usd,
+            utilization: self.utilization(),
+            peak_utilization: self.peak_utilization,
+            fills: self.fills,
+            settled: self.settled,
+            skipped_for_capital: self.skipped_for_capital,
+        }
+    }
+}
@@ -1,10 +1,28 @@
+pub mod app;
+pub mod audit;
+pub mod backtest;
+pub mod capture;
 pub mod chain;
 pub mod client;
+pub mod control;
 pub mod detect;
 pub mod exec;
 pub mod fees;
+pub mod greeks;
+pub mod health;
+pub mod ledger;
+pub mod margin;
+pub mod metrics;
 pub mod model;
+pub mod notify;
+pub mod posttrade;
+pub mod registry;
 pub mod render;
 pub mod risk;
+pub mod selftest;
+pub mod sweep;
+pub mod testkit;
+pub mod tui;
+pub mod webhook;
 
 pub mod config;
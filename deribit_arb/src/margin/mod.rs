@@ -0,0 +1,91 @@
+use crate::greeks::{black_scholes_price, SECONDS_PER_YEAR};
+use crate::model::{ComboSide, InstrumentSnapshot, StrategyOpportunity};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::*;
+use std::collections::HashMap;
+
+/// Underlying moves of the scenario grid, mirroring the ±16% range Deribit's
+/// portfolio margin model uses for BTC and ETH.
+pub const PRICE_SHOCKS: [f64; 9] = [-0.16, -0.12, -0.08, -0.04, 0.0, 0.04, 0.08, 0.12, 0.16];
+
+/// Relative implied vol shocks applied at every price point.
+pub const VOL_SHOCKS: [f64; 3] = [-0.3, 0.0, 0.3];
+
+/// Approximates Deribit's portfolio margin for a combo: each leg is
+/// revalued with Black-Scholes at its mark IV across [`PRICE_SHOCKS`] ×
+/// [`VOL_SHOCKS`], the delta hedge moves linearly with the index, and the
+/// margin is the worst USD loss over the grid. Ignores Deribit's extended
+/// dampener and minimum per-contract charges, so it is a lower bound for
+/// short-premium combos. `None` when a leg is missing or has no mark IV.
+pub fn estimate_margin(
+    opportunity: &StrategyOpportunity,
+    instruments: &[InstrumentSnapshot],
+    now: DateTime<Utc>,
+) -> Option<Decimal> {
+    let by_name: HashMap<&str, &InstrumentSnapshot> = instruments
+        .iter()
+        .map(|inst| (inst.instrument.instrument_name.as_str(), inst))
+        .collect();
+    let mut legs = Vec::with_capacity(opportunity.legs.len());
+    for leg in &opportunity.legs {
+        let inst = by_name.get(leg.instrument_name.as_str())?;
+        // Cross-book legs carry their own contract count in the touches.
+        let contracts = opportunity
+            .touches
+            .iter()
+            .find(|touch| touch.instrument_name == leg.instrument_name)
+            .map(|touch| touch.size_contracts)
+            .unwrap_or_else(|| Decimal::from(leg.ratio) * opportunity.size_contracts);
+        let sign = match leg.side {
+            ComboSide::Buy => 1.0,
+            ComboSide::Sell => -1.0,
+        };
+        legs.push(LegPosition {
+            inst,
+            units: sign * (contracts * inst.instrument.contract_size).to_f64()?,
+            vol: inst.quote.mark_iv? / 100.0,
+        });
+    }
+    let spot = opportunity.reference_index.to_f64()?;
+    let hedge_delta = match &opportunity.execution_plan.hedge {
+        Some(hedge) => hedge.combo_delta.to_f64()?,
+        None => 0.0,
+    };
+
+    let mut worst = 0.0f64;
+    for price_shock in PRICE_SHOCKS {
+        for vol_shock in VOL_SHOCKS {
+            let mut pnl = -hedge_delta * spot * price_shock;
+            for leg in &legs {
+                pnl +=
+                    leg.units * (leg.value(price_shock, vol_shock, now) - leg.value(0.0, 0.0, now));
+            }
+            worst = worst.min(pnl);
+        }
+    }
+    Decimal::from_f64(-worst).map(|margin| margin.round_dp(2))
+}
+
+struct LegPosition<'a> {
+    inst: &'a InstrumentSnapshot,
+    /// Signed position in units of the underlying.
+    units: f64,
+    vol: f64,
+}
+
+impl LegPosition<'_> {
+    /// USD value of one unit of the underlying's option under a shock.
+    fn value(&self, price_shock: f64, vol_shock: f64, now: DateTime<Utc>) -> f64 {
+        let instrument = &self.inst.instrument;
+        let spot = self.inst.quote.index_price.to_f64().unwrap_or(0.0) * (1.0 + price_shock);
+        let years = (instrument.expiry - now).num_seconds() as f64 / SECONDS_PER_YEAR;
+        black_scholes_price(
+            instrument.option_kind,
+            spot,
+            instrument.strike.to_f64().unwrap_or(0.0),
+            years,
+            self.vol * (1.0 + vol_shock),
+            self.inst.quote.interest_rate.unwrap_or(0.0),
+        )
+    }
+}
@@ -0,0 +1,295 @@
+use crate::chain::ChainStats;
+use crate::model::{ChainSnapshot, StrategyOpportunity};
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+const STALENESS_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0];
+const LATENCY_BUCKETS: &[f64] = &[0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+static METRICS: Lazy<Metrics> = Lazy::new(Metrics::default);
+
+/// Process-wide metrics registry. Recording is always on and cheap; the
+/// `/metrics` endpoint that exposes it is opt-in via `--metrics-addr`.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            buckets,
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.buckets.iter().zip(self.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in self.buckets.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{bound}\"}} {count}");
+        }
+        let _ = writeln!(
+            out,
+            "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}",
+            self.count
+        );
+        let braced = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{labels}}}")
+        };
+        let _ = writeln!(out, "{name}_sum{braced} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{braced} {}", self.count);
+    }
+}
+
+#[derive(Debug)]
+pub struct Metrics {
+    chain_instruments: AtomicU64,
+    chain_quoted: AtomicU64,
+    chain_fresh: AtomicU64,
+    quote_staleness: Mutex<Histogram>,
+    opportunities: Mutex<BTreeMap<String, u64>>,
+    risk_rejections: Mutex<BTreeMap<String, u64>>,
+    api_latency: Mutex<BTreeMap<String, Histogram>>,
+    api_errors: Mutex<BTreeMap<String, u64>>,
+    ws_reconnects: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            chain_instruments: AtomicU64::new(0),
+            chain_quoted: AtomicU64::new(0),
+            chain_fresh: AtomicU64::new(0),
+            quote_staleness: Mutex::new(Histogram::new(STALENESS_BUCKETS)),
+            opportunities: Mutex::new(BTreeMap::new()),
+            risk_rejections: Mutex::new(BTreeMap::new()),
+            api_latency: Mutex::new(BTreeMap::new()),
+            api_errors: Mutex::new(BTreeMap::new()),
+            ws_reconnects: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Metrics {
+    pub fn record_chain(&self, stats: &ChainStats) {
+        self.chain_instruments
+            .store(stats.instrument_count as u64, Ordering::Relaxed);
+        self.chain_quoted
+            .store(stats.instruments_with_quotes as u64, Ordering::Relaxed);
+        self.chain_fresh
+            .store(stats.instruments_fresh_10s as u64, Ordering::Relaxed);
+    }
+
+    /// Observes the age of every quoted instrument at scan time.
+    pub fn record_staleness(&self, snapshot: &ChainSnapshot) {
+        let mut histogram = self.quote_staleness.lock();
+        for inst in &snapshot.instruments {
+            if inst.quote.best_bid.is_none() && inst.quote.best_ask.is_none() {
+                continue;
+            }
+            let age = (snapshot.timestamp - inst.quote.timestamp).num_milliseconds() as f64 / 1e3;
+            histogram.observe(age.max(0.0));
+        }
+    }
+
+    pub fn record_opportunities(&self, opportunities: &[StrategyOpportunity]) {
+        let mut counts = self.opportunities.lock();
+        for opp in opportunities {
+            *counts.entry(opp.strategy.to_string()).or_default() += 1;
+        }
+    }
+
+    pub fn record_risk_rejection(&self, reason: &str) {
+        *self
+            .risk_rejections
+            .lock()
+            .entry(reason.to_string())
+            .or_default() += 1;
+    }
+
+    pub fn record_api_call(&self, method: &str, elapsed: Duration, ok: bool) {
+        self.api_latency
+            .lock()
+            .entry(method.to_string())
+            .or_insert_with(|| Histogram::new(LATENCY_BUCKETS))
+            .observe(elapsed.as_secs_f64());
+        if !ok {
+            *self
+                .api_errors
+                .lock()
+                .entry(method.to_string())
+                .or_default() += 1;
+        }
+    }
+
+    /// Cumulative `(calls, errors)` across every JSON-RPC method.
+    pub fn api_call_totals(&self) -> (u64, u64) {
+        let calls = self
+            .api_latency
+            .lock()
+            .values()
+            .map(|histogram| histogram.count)
+            .sum();
+        let errors = self.api_errors.lock().values().sum();
+        (calls, errors)
+    }
+
+    pub fn record_ws_reconnect(&self) {
+        self.ws_reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Prometheus text exposition format (version 0.0.4).
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let gauges = [
+            (
+                "deribit_arb_chain_instruments",
+                "Instruments tracked in the option chain",
+                &self.chain_instruments,
+            ),
+            (
+                "deribit_arb_chain_quoted_instruments",
+                "Instruments with a bid or ask",
+                &self.chain_quoted,
+            ),
+            (
+                "deribit_arb_chain_fresh_instruments",
+                "Instruments quoted within the last 10s",
+                &self.chain_fresh,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+            let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+        }
+
+        let name = "deribit_arb_quote_staleness_seconds";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Quote age observed at scan time\n# TYPE {name} histogram"
+        );
+        self.quote_staleness.lock().render(&mut out, name, "");
+
+        render_counters(
+            &mut out,
+            "deribit_arb_opportunities_total",
+            "Opportunities detected",
+            "strategy",
+            &self.opportunities.lock(),
+        );
+        render_counters(
+            &mut out,
+            "deribit_arb_risk_rejections_total",
+            "Opportunities rejected by the risk manager",
+            "reason",
+            &self.risk_rejections.lock(),
+        );
+
+        let name = "deribit_arb_api_request_duration_seconds";
+        let _ = writeln!(
+            out,
+            "# HELP {name} JSON-RPC request latency\n# TYPE {name} histogram"
+        );
+        for (method, histogram) in self.api_latency.lock().iter() {
+            histogram.render(&mut out, name, &format!("method=\"{method}\""));
+        }
+        render_counters(
+            &mut out,
+            "deribit_arb_api_errors_total",
+            "Failed JSON-RPC requests",
+            "method",
+            &self.api_errors.lock(),
+        );
+
+        let name = "deribit_arb_ws_reconnects_total";
+        let _ = writeln!(
+            out,
+            "# HELP {name} WebSocket reconnects\n# TYPE {name} counter"
+        );
+        let _ = writeln!(out, "{name} {}", self.ws_reconnects.load(Ordering::Relaxed));
+        out
+    }
+}
+
+fn render_counters(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    label: &str,
+    values: &BTreeMap<String, u64>,
+) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+    for (value, count) in values {
+        let _ = writeln!(out, "{name}{{{label}=\"{value}\"}} {count}");
+    }
+}
+
+/// Binds `addr` and serves the registry at `GET /metrics` on a background task.
+pub async fn serve(addr: SocketAddr) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("binding metrics endpoint {addr}"))?;
+    let local = listener.local_addr()?;
+    info!(target: "metrics", addr = %local, "serving /metrics");
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    warn!(target: "metrics", error = %err, "accept failed");
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                let read = match socket.read(&mut buf).await {
+                    Ok(read) => read,
+                    Err(_) => return,
+                };
+                let request = String::from_utf8_lossy(&buf[..read]);
+                let response = if request.starts_with("GET /metrics") {
+                    let body = metrics().render();
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/plain; version=0.0.4\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    Ok(local)
+}
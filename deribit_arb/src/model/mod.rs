@@ -1,22 +1,102 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter};
+use std::fmt::{Display, Formatter, Write};
 use std::str::FromStr;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub enum Currency {
-    BTC,
-    ETH,
+const CURRENCY_CODE_MAX: usize = 10;
+
+/// Version of the serialized [`StrategyOpportunity`] layout, bumped on any
+/// change a JSONL, audit or webhook consumer must handle. Records written
+/// before versioning deserialize as `0`.
+pub const OPPORTUNITY_SCHEMA_VERSION: u32 = 1;
+
+/// Underlying currency code (BTC, ETH, SOL, ...). Stored inline so it stays `Copy`
+/// and can key detector groupings; codes are validated as 2-10 ASCII alphanumerics.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency {
+    code: [u8; CURRENCY_CODE_MAX],
+    len: u8,
+}
+
+impl Currency {
+    pub const BTC: Currency = Currency::from_static("BTC");
+    pub const ETH: Currency = Currency::from_static("ETH");
+    pub const SOL: Currency = Currency::from_static("SOL");
+    pub const XRP: Currency = Currency::from_static("XRP");
+
+    const fn from_static(code: &str) -> Self {
+        let bytes = code.as_bytes();
+        let mut buf = [0u8; CURRENCY_CODE_MAX];
+        let mut i = 0;
+        while i < bytes.len() {
+            buf[i] = bytes[i];
+            i += 1;
+        }
+        Self {
+            code: buf,
+            len: bytes.len() as u8,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.code[..self.len as usize]).unwrap_or_default()
+    }
+
+    /// BTC and ETH list coin-settled books under their own currency; every other
+    /// underlying is only listed as USDC-linear under the `USDC` currency.
+    pub fn instruments_query_currency(&self) -> &str {
+        if *self == Currency::BTC || *self == Currency::ETH {
+            self.as_str()
+        } else {
+            "USDC"
+        }
+    }
+
+    /// Deribit currencies listing this underlying's books in `settlements`:
+    /// its own code for the coin-settled book (BTC and ETH only) and the
+    /// stablecoin of each linear book.
+    pub fn instrument_books(&self, settlements: &[SettlementCurrency]) -> Vec<&str> {
+        let has_coin_book = *self == Currency::BTC || *self == Currency::ETH;
+        settlements
+            .iter()
+            .filter_map(|settlement| match settlement.stablecoin() {
+                Some(stablecoin) => Some(stablecoin),
+                None if has_coin_book => Some(self.as_str()),
+                None => None,
+            })
+            .collect()
+    }
+
+    /// Name of the Deribit price index for `public/get_index_price`: the USD
+    /// index for BTC/ETH, the USDC index for linear-only altcoins.
+    pub fn index_name(&self) -> String {
+        let code = self.as_str().to_ascii_lowercase();
+        if *self == Currency::BTC || *self == Currency::ETH {
+            format!("{code}_usd")
+        } else {
+            format!("{code}_usdc")
+        }
+    }
+
+    pub fn perpetual_instrument(&self, settlement: SettlementCurrency) -> String {
+        match settlement.stablecoin() {
+            None => format!("{self}-PERPETUAL"),
+            Some(stablecoin) => format!("{self}_{stablecoin}-PERPETUAL"),
+        }
+    }
 }
 
 impl Display for Currency {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Currency::BTC => write!(f, "BTC"),
-            Currency::ETH => write!(f, "ETH"),
-        }
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::fmt::Debug for Currency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
@@ -24,11 +104,31 @@ impl FromStr for Currency {
     type Err = ParseInstrumentError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_ascii_uppercase().as_str() {
-            "BTC" => Ok(Currency::BTC),
-            "ETH" => Ok(Currency::ETH),
-            _ => Err(ParseInstrumentError::UnknownCurrency(s.to_string())),
+        let code = s.trim().to_ascii_uppercase();
+        if !(2..=CURRENCY_CODE_MAX).contains(&code.len())
+            || !code.bytes().all(|b| b.is_ascii_alphanumeric())
+        {
+            return Err(ParseInstrumentError::UnknownCurrency(s.to_string()));
         }
+        let mut buf = [0u8; CURRENCY_CODE_MAX];
+        buf[..code.len()].copy_from_slice(code.as_bytes());
+        Ok(Self {
+            code: buf,
+            len: code.len() as u8,
+        })
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        code.parse().map_err(serde::de::Error::custom)
     }
 }
 
@@ -59,18 +159,66 @@ impl FromStr for OptionKind {
     }
 }
 
+/// Book an instrument settles in: its underlying coin, or a stablecoin for
+/// linear listings. USDC and USDT amounts are valued at par with the USD;
+/// EURR books quote in euros, converted through the `eur_usd` index (see
+/// [`StrategyOpportunity::fx_usd`]).
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum SettlementCurrency {
     Usdc,
     Coin,
+    Usdt,
+    Eurr,
 }
 
-impl Display for SettlementCurrency {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl SettlementCurrency {
+    /// The stablecoin a linear book settles in, as Deribit spells it in
+    /// instrument names and `settlement_currency`; `None` for coin books.
+    pub fn stablecoin(self) -> Option<&'static str> {
         match self {
-            SettlementCurrency::Usdc => write!(f, "USDC"),
-            SettlementCurrency::Coin => write!(f, "COIN"),
+            SettlementCurrency::Coin => None,
+            SettlementCurrency::Usdc => Some("USDC"),
+            SettlementCurrency::Usdt => Some("USDT"),
+            SettlementCurrency::Eurr => Some("EURR"),
+        }
+    }
+
+    /// The linear book settling in `code`, if it is a known stablecoin.
+    pub fn from_stablecoin(code: &str) -> Option<Self> {
+        match code.to_ascii_uppercase().as_str() {
+            "USDC" => Some(SettlementCurrency::Usdc),
+            "USDT" => Some(SettlementCurrency::Usdt),
+            "EURR" => Some(SettlementCurrency::Eurr),
+            _ => None,
+        }
+    }
+
+    pub fn is_linear(self) -> bool {
+        self != SettlementCurrency::Coin
+    }
+
+    /// Whether the book pays out in USD terms at expiry: coin books through
+    /// the USD index, USDC and USDT at par. Strikes of such books compare.
+    pub fn pays_usd(self) -> bool {
+        self != SettlementCurrency::Eurr
+    }
+}
+
+impl FromStr for SettlementCurrency {
+    type Err = ParseInstrumentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("coin") {
+            return Ok(SettlementCurrency::Coin);
         }
+        SettlementCurrency::from_stablecoin(s.trim())
+            .ok_or_else(|| ParseInstrumentError::UnknownSettlement(s.to_string()))
+    }
+}
+
+impl Display for SettlementCurrency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.stablecoin().unwrap_or("COIN"))
     }
 }
 
@@ -159,6 +307,8 @@ pub struct FeeBreakdown {
     pub combo_discount_usd: Decimal,
     pub delivery_fee: Decimal,
     pub delivery_fee_usd: Decimal,
+    pub hedge_fee: Decimal,
+    pub hedge_fee_usd: Decimal,
     pub total_native: Decimal,
     pub total_usd: Decimal,
 }
@@ -179,8 +329,30 @@ pub enum FillRole {
     Taker,
 }
 
+impl Display for FillRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FillRole::Maker => write!(f, "maker"),
+            FillRole::Taker => write!(f, "taker"),
+        }
+    }
+}
+
+/// Where a leg trades: the public order/combo book or a block trade.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ExecutionVenue {
+    Screen,
+    Block,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct StrategyOpportunity {
+    /// Content hash of the detected combo, see [`Self::content_id`]; stable
+    /// across process restarts.
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub schema_version: u32,
     pub strategy: StrategyKind,
     pub currency: Currency,
     pub settlement: SettlementCurrency,
@@ -198,6 +370,125 @@ pub struct StrategyOpportunity {
     pub edge_bps: f64,
     pub size_contracts: Decimal,
     pub execution_plan: ComboExecutionPlan,
+    /// Worst loss over the portfolio margin scenario grid, see
+    /// [`crate::margin::estimate_margin`]; `None` when a leg has no mark IV.
+    #[serde(default)]
+    pub estimated_margin_usd: Option<Decimal>,
+    /// Edge at larger multiples of `size_contracts`, walking the L2 book;
+    /// see [`crate::detect::ladder::size_ladder`].
+    #[serde(default)]
+    pub size_ladder: Vec<SizeRung>,
+    /// Age of each leg's quote at detection, in `legs` order.
+    #[serde(default)]
+    pub leg_quote_ages_ms: Vec<i64>,
+    /// Edge already deducted from `net_edge_usd` for the oldest leg quote's
+    /// age, see `--staleness-penalty-bps`.
+    #[serde(default)]
+    pub staleness_penalty_usd: Decimal,
+    /// Net edge over the capital held until the last leg expires, per year;
+    /// see [`Self::annualized_return_at`].
+    #[serde(default)]
+    pub annualized_return: Option<f64>,
+    /// USD value of one unit of the currency the strikes, `reference_index`
+    /// and linear premiums are quoted in: the `eur_usd` index for EURR
+    /// books, 1 for every other book.
+    #[serde(default = "par")]
+    pub fx_usd: Decimal,
+}
+
+fn par() -> Decimal {
+    Decimal::ONE
+}
+
+/// One rung of an opportunity's edge-vs-size ladder.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SizeRung {
+    /// Multiple of the detected size, e.g. `2` for twice the touched depth.
+    pub depth_multiple: u32,
+    pub size_contracts: Decimal,
+    /// `None` when a leg's book cannot fill this size.
+    pub net_edge_usd: Option<Decimal>,
+}
+
+impl StrategyOpportunity {
+    /// Deterministic ID over the strategy, legs, touched prices and sizes:
+    /// a 64-bit FNV-1a hash in hex. Edge and fees are left out, so the same
+    /// quotes give the same ID under any fee schedule.
+    pub fn content_id(&self) -> String {
+        let mut content = self.strategy.to_string();
+        for leg in &self.legs {
+            let _ = write!(
+                content,
+                "|{}:{}x{}",
+                leg.side, leg.instrument_name, leg.ratio
+            );
+        }
+        for touch in &self.touches {
+            let _ = write!(
+                content,
+                "|{}@{}x{}",
+                touch.instrument_name,
+                touch.price.normalize(),
+                touch.size_contracts.normalize()
+            );
+        }
+        let _ = write!(content, "|{}", self.size_contracts.normalize());
+        let hash = content
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("{hash:016x}")
+    }
+
+    /// Capital the combo ties up until expiry: its estimated margin, or its
+    /// notional when no margin estimate exists.
+    pub fn locked_capital_usd(&self) -> Decimal {
+        self.estimated_margin_usd.unwrap_or(self.notional_usd)
+    }
+
+    /// `net_edge_usd / locked capital * 365 / days to the last expiry` as a
+    /// fraction, with at least one day to expiry so same-day combos do not
+    /// annualize without bound. `None` without positive capital.
+    pub fn annualized_return_at(&self, now: DateTime<Utc>) -> Option<f64> {
+        let capital = self.locked_capital_usd();
+        if capital <= Decimal::ZERO {
+            return None;
+        }
+        let last_expiry = self.expiry.iter().max()?;
+        let days = ((*last_expiry - now).num_seconds() as f64 / 86_400.0).max(1.0);
+        Some((self.net_edge_usd / capital).to_f64()? * 365.0 / days)
+    }
+
+    /// Identifies a combo across scans by its legs, not its prices.
+    pub fn combo_key(&self) -> String {
+        let legs: Vec<String> = self
+            .legs
+            .iter()
+            .map(|leg| format!("{}:{}x{}", leg.side, leg.instrument_name, leg.ratio))
+            .collect();
+        format!("{}|{}", self.strategy, legs.join(","))
+    }
+
+    /// USD value of one unit of `settlement`'s premium: coin at the
+    /// reference index, stablecoins at [`Self::fx_usd`].
+    pub fn usd_per_unit(&self, settlement: SettlementCurrency) -> Decimal {
+        match settlement {
+            SettlementCurrency::Coin => self.reference_index * self.fx_usd,
+            SettlementCurrency::Usdc | SettlementCurrency::Usdt | SettlementCurrency::Eurr => {
+                self.fx_usd
+            }
+        }
+    }
+
+    /// Whether the legs settle on different books (coin and USDC), which no
+    /// single Deribit combo can hold.
+    pub fn spans_settlements(&self) -> bool {
+        self.fee_breakdown
+            .legs
+            .iter()
+            .any(|leg| leg.settlement != self.settlement)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -214,6 +505,17 @@ pub struct ComboExecutionPlan {
     pub tif: OrderTimeInForce,
     pub price_limit: Decimal,
     pub dry_run: bool,
+    pub hedge: Option<HedgeLeg>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HedgeLeg {
+    pub instrument_name: String,
+    pub side: ComboSide,
+    /// Order amount in the perpetual's own units (USD for inverse, coin for linear).
+    pub amount: Decimal,
+    pub combo_delta: Decimal,
+    pub notional_usd: Decimal,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -223,6 +525,17 @@ pub enum OrderTimeInForce {
     GTC,
 }
 
+impl OrderTimeInForce {
+    /// `time_in_force` value for `private/buy` and `private/sell`.
+    pub fn as_deribit(&self) -> &'static str {
+        match self {
+            OrderTimeInForce::IOC => "immediate_or_cancel",
+            OrderTimeInForce::FOK => "fill_or_kill",
+            OrderTimeInForce::GTC => "good_til_cancelled",
+        }
+    }
+}
+
 impl Display for OrderTimeInForce {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -233,6 +546,64 @@ impl Display for OrderTimeInForce {
     }
 }
 
+/// Limit order on an instrument or combo (combo ids are instrument names).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderRequest {
+    pub instrument_name: String,
+    pub side: ComboSide,
+    pub amount: Decimal,
+    pub price: Decimal,
+    pub tif: OrderTimeInForce,
+    pub post_only: bool,
+    pub label: Option<String>,
+}
+
+/// A maker's offer to sell the whole combo in a block RFQ.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BlockRfqQuote {
+    pub quote_id: Option<String>,
+    /// Per-contract combo price, in the same units as the screen touch.
+    pub price: Decimal,
+    pub amount: Decimal,
+}
+
+/// A public trade print from a `trades.*` channel.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Trade {
+    pub trade_id: String,
+    pub instrument_name: String,
+    pub price: Decimal,
+    pub amount: Decimal,
+    /// Taker side of the print.
+    pub direction: ComboSide,
+    pub timestamp: DateTime<Utc>,
+    pub index_price: Decimal,
+    pub iv: Option<f64>,
+}
+
+/// An order state change from a `user.orders.*` channel.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderUpdate {
+    pub order_id: String,
+    pub instrument_name: String,
+    /// Deribit `order_state`: `open`, `filled`, `cancelled`, `rejected`, ...
+    pub state: String,
+    pub direction: ComboSide,
+    pub price: Option<Decimal>,
+    pub amount: Decimal,
+    pub filled_amount: Decimal,
+    pub label: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// An open account position from `private/get_positions`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Position {
+    pub instrument_name: String,
+    /// Signed size; negative for shorts.
+    pub size: Decimal,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ChainSnapshot {
     pub timestamp: DateTime<Utc>,
@@ -251,11 +622,15 @@ pub enum ParseInstrumentError {
     InvalidExpiry(String),
     #[error("invalid strike: {0}")]
     InvalidStrike(String),
+    #[error("unknown settlement: {0}")]
+    UnknownSettlement(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParsedInstrumentName {
     pub currency: Currency,
+    /// From the `_USDC`-style suffix of the underlying; coin without one.
+    pub settlement: SettlementCurrency,
     pub day: u32,
     pub month: String,
     pub year: u32,
@@ -294,41 +669,94 @@ impl ParsedInstrumentName {
     }
 }
 
+/// A positive strike as listed: `42000`, or `0d625` for 0.625.
+fn parse_strike(code: &str) -> Option<Decimal> {
+    if code.contains('.') || code.matches('d').count() > 1 {
+        return None;
+    }
+    Decimal::from_str(&code.replace('d', "."))
+        .ok()
+        .filter(|strike| *strike > Decimal::ZERO)
+}
+
+/// `strike` the way Deribit lists it, with `d` for the decimal point.
+pub fn format_strike(strike: Decimal) -> String {
+    strike.normalize().to_string().replace('.', "d")
+}
+
+impl Display for ParsedInstrumentName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.currency)?;
+        if let Some(stablecoin) = self.settlement.stablecoin() {
+            write!(f, "_{stablecoin}")?;
+        }
+        let kind = match self.option_kind {
+            OptionKind::Call => "C",
+            OptionKind::Put => "P",
+        };
+        write!(
+            f,
+            "-{}{}{:02}-{}-{kind}",
+            self.day,
+            self.month,
+            self.year % 100,
+            format_strike(self.strike)
+        )
+    }
+}
+
 impl FromStr for ParsedInstrumentName {
     type Err = ParseInstrumentError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Format e.g. BTC-25MAR23-42000-C
+        // Format e.g. BTC-25MAR23-42000-C, or BTC_USDC-25MAR23-42000-C for
+        // linear listings settled in USDC, USDT or EURR. Dailies drop the
+        // day's leading zero (BTC-7JUN24-...) and low-priced underlyings
+        // write the strike's decimal point as `d` (XRP_USDC-7JUN24-0d625-C).
         let parts: Vec<&str> = s.split('-').collect();
         if parts.len() != 4 {
             return Err(ParseInstrumentError::InvalidFormat(s.to_string()));
         }
-        let currency = parts[0].parse()?;
+        let (code, settlement) = match parts[0].split_once('_') {
+            Some((code, stablecoin)) => (
+                code,
+                SettlementCurrency::from_stablecoin(stablecoin).ok_or_else(|| {
+                    ParseInstrumentError::UnknownSettlement(stablecoin.to_string())
+                })?,
+            ),
+            None => (parts[0], SettlementCurrency::Coin),
+        };
+        let currency = code.parse()?;
+        // DMMMYY or DDMMMYY; checked as ASCII before slicing by byte.
         let date_part = parts[1];
-        if date_part.len() < 6 {
-            return Err(ParseInstrumentError::InvalidExpiry(date_part.to_string()));
+        let invalid_expiry = || ParseInstrumentError::InvalidExpiry(date_part.to_string());
+        if !(6..=7).contains(&date_part.len()) || !date_part.is_ascii() {
+            return Err(invalid_expiry());
         }
-        let year_suffix = &date_part[date_part.len() - 2..];
-        let month = date_part[date_part.len() - 5..date_part.len() - 2].to_ascii_uppercase();
-        let day_str = &date_part[..date_part.len() - 5];
-        let day = day_str
-            .parse()
-            .map_err(|_| ParseInstrumentError::InvalidExpiry(date_part.to_string()))?;
-        let year = format!("20{}", year_suffix)
-            .parse()
-            .map_err(|_| ParseInstrumentError::InvalidExpiry(date_part.to_string()))?;
-        let strike = Decimal::from_str(parts[2])
-            .map_err(|_| ParseInstrumentError::InvalidStrike(parts[2].to_string()))?;
+        let (day_str, rest) = date_part.split_at(date_part.len() - 5);
+        let (month, year_suffix) = rest.split_at(3);
+        if !day_str.bytes().all(|b| b.is_ascii_digit())
+            || !year_suffix.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid_expiry());
+        }
+        let day = day_str.parse().map_err(|_| invalid_expiry())?;
+        let year = 2000 + year_suffix.parse::<u32>().map_err(|_| invalid_expiry())?;
+        let strike = parse_strike(parts[2])
+            .ok_or_else(|| ParseInstrumentError::InvalidStrike(parts[2].to_string()))?;
         let option_kind = parts[3].parse()?;
 
-        Ok(Self {
+        let parsed = Self {
             currency,
+            settlement,
             day,
-            month,
+            month: month.to_ascii_uppercase(),
             year,
             strike,
             option_kind,
-        })
+        };
+        parsed.expiry_date()?;
+        Ok(parsed)
     }
 }
 
@@ -336,9 +764,88 @@ impl FromStr for ParsedInstrumentName {
 pub struct MinEdgeRequirements {
     pub min_edge_usd: Decimal,
     pub min_edge_ratio: f64,
+    pub max_size_contracts: Option<Decimal>,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+/// One option's top of book from `public/get_book_summary_by_currency`,
+/// priced in the instrument's settlement currency like its ticker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookSummary {
+    pub instrument_name: String,
+    pub bid_price: Option<Decimal>,
+    pub ask_price: Option<Decimal>,
+    pub mark_price: Option<Decimal>,
+}
+
+/// A currency's forward curve, for valuing combos that trade the forward.
+/// Dated futures are stored as their premium to the index, so the curve
+/// follows the live index between refreshes.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CarryCurve {
+    /// Dated futures as `(expiry, mark / index)`, in any order.
+    pub futures: Vec<(DateTime<Utc>, f64)>,
+    /// Annualized perpetual funding rate, e.g. `0.1` for 10%.
+    pub funding_rate: Option<f64>,
+}
+
+impl CarryCurve {
+    /// Forward price for `expiry`: interpolated linearly in time between the
+    /// index today and the dated futures around it, and extrapolated with
+    /// perpetual funding past the last future. `None` without enough data.
+    pub fn forward(
+        &self,
+        expiry: DateTime<Utc>,
+        index: Decimal,
+        now: DateTime<Utc>,
+    ) -> Option<Decimal> {
+        const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+        let years = |at: DateTime<Utc>| (at - now).num_seconds() as f64 / SECONDS_PER_YEAR;
+        let mut points: Vec<(f64, f64)> = vec![(0.0, 1.0)];
+        let mut futures: Vec<(f64, f64)> = self
+            .futures
+            .iter()
+            .map(|(at, ratio)| (years(*at), *ratio))
+            .filter(|(t, ratio)| *t > 0.0 && *ratio > 0.0)
+            .collect();
+        futures.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.extend(futures);
+        let target = years(expiry).max(0.0);
+        let ratio = match points.windows(2).find(|pair| target <= pair[1].0) {
+            Some(pair) => {
+                let ((t0, r0), (t1, r1)) = (pair[0], pair[1]);
+                r0 + (r1 - r0) * (target - t0) / (t1 - t0)
+            }
+            None => {
+                let (last_t, last_ratio) = *points.last()?;
+                if target == last_t {
+                    last_ratio
+                } else {
+                    last_ratio * (1.0 + self.funding_rate? * (target - last_t))
+                }
+            }
+        };
+        Decimal::from_f64(ratio).map(|ratio| index * ratio)
+    }
+}
+
+/// Edge thresholds changed at runtime through the daemon control API; set
+/// fields replace the configured values for every strategy.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RuntimeThresholds {
+    pub min_edge_usd: Option<Decimal>,
+    pub min_edge_ratio: Option<f64>,
+}
+
+/// Per-strategy overrides of the global edge thresholds; unset fields fall back to the globals.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StrategyThresholds {
+    pub min_edge_usd: Option<Decimal>,
+    pub min_edge_ratio: Option<f64>,
+    pub max_size_contracts: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum StrategyKind {
     Vertical,
     Butterfly,
@@ -371,3 +878,140 @@ impl StrategyFilter {
         self.include.contains(&strategy)
     }
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct InstrumentFilter {
+    pub min_dte: Option<f64>,
+    pub max_dte: Option<f64>,
+    /// Inclusive strike ÷ index band, e.g. `(0.7, 1.3)`.
+    pub moneyness_band: Option<(Decimal, Decimal)>,
+    /// Expiry dates to scan; empty scans every date.
+    #[serde(default)]
+    pub expiries: Vec<NaiveDate>,
+    /// Keep only this many of each currency's listed expiries, nearest
+    /// first; applied at discovery, see [`Self::nearest_expiries`].
+    #[serde(default)]
+    pub nearest: Option<usize>,
+}
+
+impl InstrumentFilter {
+    pub fn is_active(&self) -> bool {
+        self.min_dte.is_some()
+            || self.max_dte.is_some()
+            || self.moneyness_band.is_some()
+            || !self.expiries.is_empty()
+    }
+
+    pub fn allows_expiry(&self, expiry: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let dte = (expiry - now).num_seconds() as f64 / 86_400.0;
+        self.min_dte.is_none_or(|min| dte >= min)
+            && self.max_dte.is_none_or(|max| dte <= max)
+            && (self.expiries.is_empty() || self.expiries.contains(&expiry.date_naive()))
+    }
+
+    /// The nearest `--nearest-expiries` of `listed` that pass the other
+    /// expiry rules, or `None` when no such limit is set. Discovery applies
+    /// it per currency, so neither quotes nor detection touch the rest.
+    pub fn nearest_expiries(
+        &self,
+        listed: impl IntoIterator<Item = DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<Vec<DateTime<Utc>>> {
+        let nearest = self.nearest?;
+        let mut expiries: Vec<_> = listed
+            .into_iter()
+            .filter(|expiry| *expiry > now && self.allows_expiry(*expiry, now))
+            .collect();
+        expiries.sort();
+        expiries.dedup();
+        expiries.truncate(nearest);
+        Some(expiries)
+    }
+
+    pub fn allows_strike(&self, strike: Decimal, index_price: Decimal) -> bool {
+        match self.moneyness_band {
+            Some((low, high)) if !index_price.is_zero() => {
+                let moneyness = strike / index_price;
+                moneyness >= low && moneyness <= high
+            }
+            _ => true,
+        }
+    }
+
+    pub fn allows(&self, snapshot: &InstrumentSnapshot, now: DateTime<Utc>) -> bool {
+        self.allows_expiry(snapshot.instrument.expiry, now)
+            && self.allows_strike(snapshot.instrument.strike, snapshot.quote.index_price)
+    }
+}
+
+/// Column that rendered opportunities are ordered by, largest first.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Edge,
+    Bps,
+    Notional,
+    /// Annualized return on locked capital; combos without one sort last.
+    AnnualizedReturn,
+}
+
+/// Column the printed table groups opportunities by, with a subtotal row
+/// closing each group.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GroupKey {
+    Strategy,
+    /// The nearest leg expiry, so calendars group under their near leg.
+    Expiry,
+}
+
+/// Which detections are printed and exported, and in what order. Empty
+/// filter lists allow everything; execution always sees the full set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OpportunityView {
+    pub sort_by: SortKey,
+    pub strategies: Vec<StrategyKind>,
+    pub currencies: Vec<Currency>,
+    /// Matches when any leg expires on one of these dates.
+    pub expiries: Vec<NaiveDate>,
+    /// Rows to keep; `None` leaves each output's own default.
+    pub top: Option<usize>,
+    /// Group the printed table, keeping the sort order within each group.
+    pub group_by: Option<GroupKey>,
+}
+
+impl OpportunityView {
+    pub fn allows(&self, opportunity: &StrategyOpportunity) -> bool {
+        (self.strategies.is_empty() || self.strategies.contains(&opportunity.strategy))
+            && (self.currencies.is_empty() || self.currencies.contains(&opportunity.currency))
+            && (self.expiries.is_empty()
+                || opportunity
+                    .expiry
+                    .iter()
+                    .any(|expiry| self.expiries.contains(&expiry.date_naive())))
+    }
+
+    /// Filters and sorts `opportunities`, keeping `--top` rows or
+    /// `default_top` when it is unset.
+    pub fn apply(
+        &self,
+        opportunities: &[StrategyOpportunity],
+        default_top: usize,
+    ) -> Vec<StrategyOpportunity> {
+        let mut rows: Vec<StrategyOpportunity> = opportunities
+            .iter()
+            .filter(|opp| self.allows(opp))
+            .cloned()
+            .collect();
+        match self.sort_by {
+            SortKey::Edge => rows.sort_by_key(|opp| std::cmp::Reverse(opp.net_edge_usd)),
+            SortKey::Bps => rows.sort_by(|a, b| b.edge_bps.total_cmp(&a.edge_bps)),
+            SortKey::Notional => rows.sort_by_key(|opp| std::cmp::Reverse(opp.notional_usd)),
+            SortKey::AnnualizedReturn => rows.sort_by(|a, b| {
+                let rate = |opp: &StrategyOpportunity| opp.annualized_return.unwrap_or(f64::MIN);
+                rate(b).total_cmp(&rate(a))
+            }),
+        }
+        rows.truncate(self.top.unwrap_or(default_top));
+        rows
+    }
+}
@@ -0,0 +1,366 @@
+use crate::config::{NotifierConfig, NotifyTarget};
+use crate::health::HealthTransition;
+use crate::model::{Currency, StrategyKind, StrategyOpportunity};
+use crate::render::format_decimal;
+use crate::risk::RiskRejection;
+use crate::webhook::WebhookSink;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+mod sinks;
+
+pub use sinks::{HttpSink, SlackSink, TelegramSink};
+
+/// How long an opportunity or risk trip stays quiet on a route after it
+/// was sent, so a combo that persists across cycles alerts once.
+pub const REPEAT_AFTER_SECS: i64 = 15 * 60;
+
+/// Event classes a `[notify.*]` route can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    Opportunity,
+    Execution,
+    Risk,
+    Breaker,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 4] = [
+        AlertKind::Opportunity,
+        AlertKind::Execution,
+        AlertKind::Risk,
+        AlertKind::Breaker,
+    ];
+}
+
+impl Display for AlertKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            AlertKind::Opportunity => "opportunity",
+            AlertKind::Execution => "execution",
+            AlertKind::Risk => "risk",
+            AlertKind::Breaker => "breaker",
+        };
+        f.write_str(label)
+    }
+}
+
+impl FromStr for AlertKind {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        AlertKind::ALL
+            .into_iter()
+            .find(|kind| kind.to_string() == value.trim().to_ascii_lowercase())
+            .ok_or_else(|| anyhow!("unknown notify event: {value}"))
+    }
+}
+
+/// Something worth telling a human about.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Alert {
+    /// A detected combo, new or improved this cycle.
+    Opportunity {
+        opportunity: Box<StrategyOpportunity>,
+    },
+    /// A combo planned, submitted, rested or failed on an account.
+    Execution {
+        account: String,
+        strategy: StrategyKind,
+        currency: Currency,
+        /// Detected edge; unknown for maker order events.
+        net_edge_usd: Option<Decimal>,
+        outcome: String,
+    },
+    /// A risk limit that halts approvals rather than refusing one combo.
+    RiskTrip { limit: String, reason: String },
+    /// The circuit breaker tripped (`reason` set) or reset.
+    Breaker {
+        tripped: bool,
+        reason: Option<String>,
+    },
+}
+
+impl Alert {
+    pub fn opportunity(opportunity: &StrategyOpportunity) -> Self {
+        Alert::Opportunity {
+            opportunity: Box::new(opportunity.clone()),
+        }
+    }
+
+    /// The alert for `rejection` when it is a trip: the daily loss limit,
+    /// the PnL kill switch or a spent execution budget. Other rejections
+    /// refuse a single combo and are left to the audit log.
+    pub fn risk_trip(rejection: &RiskRejection) -> Option<Self> {
+        match rejection {
+            RiskRejection::DailyLossLimit { .. }
+            | RiskRejection::NegativePnl { .. }
+            | RiskRejection::Budget { .. } => Some(Alert::RiskTrip {
+                limit: rejection.label().to_string(),
+                reason: rejection.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn breaker(transition: &HealthTransition) -> Self {
+        match transition {
+            HealthTransition::Tripped(reason) => Alert::Breaker {
+                tripped: true,
+                reason: Some(reason.to_string()),
+            },
+            HealthTransition::Reset => Alert::Breaker {
+                tripped: false,
+                reason: None,
+            },
+        }
+    }
+
+    pub fn kind(&self) -> AlertKind {
+        match self {
+            Alert::Opportunity { .. } => AlertKind::Opportunity,
+            Alert::Execution { .. } => AlertKind::Execution,
+            Alert::RiskTrip { .. } => AlertKind::Risk,
+            Alert::Breaker { .. } => AlertKind::Breaker,
+        }
+    }
+
+    /// One-line message for chat sinks.
+    pub fn text(&self) -> String {
+        match self {
+            Alert::Opportunity { opportunity } => WebhookSink::summary(opportunity),
+            Alert::Execution {
+                account,
+                strategy,
+                currency,
+                net_edge_usd,
+                outcome,
+            } => match net_edge_usd {
+                Some(edge) => format!(
+                    "[{account}] {strategy} {currency} (edge ${}): {outcome}",
+                    format_decimal(*edge)
+                ),
+                None => format!("[{account}] {strategy} {currency}: {outcome}"),
+            },
+            Alert::RiskTrip { reason, .. } => format!("Risk halt: {reason}"),
+            Alert::Breaker {
+                tripped: true,
+                reason,
+            } => format!(
+                "Circuit breaker tripped, execution disabled: {}",
+                reason.as_deref().unwrap_or("unknown")
+            ),
+            Alert::Breaker { tripped: false, .. } => {
+                "Circuit breaker reset, execution enabled".to_string()
+            }
+        }
+    }
+
+    /// Identity for repeat suppression; executions and breaker transitions
+    /// are always sent.
+    fn repeat_key(&self) -> Option<String> {
+        match self {
+            Alert::Opportunity { opportunity } => Some(opportunity.combo_key()),
+            Alert::RiskTrip { limit, .. } => Some(format!("risk:{limit}")),
+            Alert::Execution { .. } | Alert::Breaker { .. } => None,
+        }
+    }
+}
+
+/// Delivers an alert to one chat or endpoint.
+#[async_trait]
+pub trait NotifySink: Send + Sync {
+    async fn send(&self, alert: &Alert) -> Result<()>;
+}
+
+/// Which alerts a route passes to its sink.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NotifyFilter {
+    pub events: Vec<AlertKind>,
+    /// Opportunities below this net edge are not sent.
+    pub min_edge_usd: Decimal,
+    /// Most opportunities sent per batch, best edge first.
+    pub top: usize,
+}
+
+impl Default for NotifyFilter {
+    fn default() -> Self {
+        Self {
+            events: AlertKind::ALL.to_vec(),
+            min_edge_usd: Decimal::ZERO,
+            top: 3,
+        }
+    }
+}
+
+struct Route {
+    label: String,
+    filter: NotifyFilter,
+    sink: Box<dyn NotifySink>,
+}
+
+/// Fans alerts out to the configured sinks. Each route filters by event
+/// class and edge, keeps the best `top` opportunities of a batch, and
+/// suppresses repeats for [`REPEAT_AFTER_SECS`].
+#[derive(Default)]
+pub struct Notifier {
+    routes: Vec<Route>,
+    sent: Mutex<HashMap<(usize, String), DateTime<Utc>>>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One route per `[notify.*]` table.
+    pub fn from_config(configs: &[NotifierConfig]) -> Result<Self> {
+        let mut notifier = Self::new();
+        for config in configs {
+            notifier = match &config.target {
+                NotifyTarget::Telegram {
+                    api_url,
+                    token,
+                    chat_id,
+                } => notifier.route(
+                    &config.label,
+                    config.filter.clone(),
+                    TelegramSink::new(api_url.clone(), token.clone(), chat_id.clone())?,
+                ),
+                NotifyTarget::Slack { url } => notifier.route(
+                    &config.label,
+                    config.filter.clone(),
+                    SlackSink::new(url.clone())?,
+                ),
+                NotifyTarget::Webhook { url } => notifier.route(
+                    &config.label,
+                    config.filter.clone(),
+                    HttpSink::new(url.clone())?,
+                ),
+            };
+        }
+        Ok(notifier)
+    }
+
+    /// Adds a route to `sink`, which may be any [`NotifySink`].
+    pub fn route(
+        mut self,
+        label: impl Into<String>,
+        filter: NotifyFilter,
+        sink: impl NotifySink + 'static,
+    ) -> Self {
+        self.routes.push(Route {
+            label: label.into(),
+            filter,
+            sink: Box::new(sink),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Sends `alerts` through every route; returns how many messages were
+    /// delivered. Failures are logged and skipped, like the webhook, and
+    /// retried when the alert comes up again.
+    pub async fn deliver(&self, alerts: &[Alert], now: DateTime<Utc>) -> usize {
+        self.sent
+            .lock()
+            .retain(|_, at| now - *at < Duration::seconds(REPEAT_AFTER_SECS));
+        let mut delivered = 0;
+        for (index, route) in self.routes.iter().enumerate() {
+            for alert in self.select(index, route, alerts) {
+                match route.sink.send(alert).await {
+                    Ok(()) => {
+                        delivered += 1;
+                        if let Some(key) = alert.repeat_key() {
+                            self.sent.lock().insert((index, key), now);
+                        }
+                    }
+                    Err(err) => {
+                        warn!(target: "notify", route = %route.label, event = %alert.kind(), error = %err, "failed to deliver alert");
+                    }
+                }
+            }
+        }
+        if delivered > 0 {
+            info!(target: "notify", delivered, "sent alerts");
+        }
+        delivered
+    }
+
+    /// The alerts of `alerts` that `route` sends: subscribed, not sent in
+    /// the repeat window, and for opportunities above the edge floor and
+    /// among the best `top` left.
+    fn select<'b>(&self, index: usize, route: &Route, alerts: &'b [Alert]) -> Vec<&'b Alert> {
+        let sent = self.sent.lock();
+        let fresh = |alert: &Alert| {
+            alert
+                .repeat_key()
+                .is_none_or(|key| !sent.contains_key(&(index, key)))
+        };
+        let mut best: Vec<&StrategyOpportunity> = alerts
+            .iter()
+            .filter(|alert| fresh(alert))
+            .filter_map(|alert| match alert {
+                Alert::Opportunity { opportunity }
+                    if opportunity.net_edge_usd >= route.filter.min_edge_usd =>
+                {
+                    Some(opportunity.as_ref())
+                }
+                _ => None,
+            })
+            .collect();
+        best.sort_by_key(|opportunity| std::cmp::Reverse(opportunity.net_edge_usd));
+        best.truncate(route.filter.top);
+        alerts
+            .iter()
+            .filter(|alert| route.filter.events.contains(&alert.kind()))
+            .filter(|alert| match alert {
+                Alert::Opportunity { opportunity } => best
+                    .iter()
+                    .any(|kept| std::ptr::eq(*kept, opportunity.as_ref())),
+                other => fresh(other),
+            })
+            .collect()
+    }
+
+    /// Moves delivery onto a background task, so the scan loop never waits
+    /// on a chat API.
+    pub fn spawn(self) -> NotifyHandle {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Vec<Alert>>();
+        tokio::spawn(async move {
+            while let Some(alerts) = rx.recv().await {
+                self.deliver(&alerts, Utc::now()).await;
+            }
+        });
+        NotifyHandle { tx: Arc::new(tx) }
+    }
+}
+
+/// Queues alerts for a [`Notifier`] running in the background.
+#[derive(Debug, Clone)]
+pub struct NotifyHandle {
+    tx: Arc<mpsc::UnboundedSender<Vec<Alert>>>,
+}
+
+impl NotifyHandle {
+    pub fn send(&self, alerts: Vec<Alert>) {
+        if !alerts.is_empty() && self.tx.send(alerts).is_err() {
+            warn!(target: "notify", "notifier task stopped, dropping alerts");
+        }
+    }
+}
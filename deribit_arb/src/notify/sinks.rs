@@ -0,0 +1,132 @@
+use super::{Alert, NotifySink};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::json;
+use std::time::Duration;
+use url::Url;
+
+fn http_client() -> Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .context("building notifier client")
+}
+
+/// Errors drop the URL: Telegram's carries the bot token and a Slack
+/// webhook URL is itself the secret.
+async fn post<T: Serialize + ?Sized>(client: &Client, url: Url, body: &T) -> Result<()> {
+    client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| err.without_url())?;
+    Ok(())
+}
+
+/// Messages a chat through a Telegram bot (`sendMessage`). Not `Debug`,
+/// since the endpoint holds the token.
+#[derive(Clone)]
+pub struct TelegramSink {
+    client: Client,
+    endpoint: Url,
+    chat_id: String,
+}
+
+impl TelegramSink {
+    /// `api_url` is the Bot API host, `https://api.telegram.org` outside
+    /// tests.
+    pub fn new(api_url: Url, token: String, chat_id: String) -> Result<Self> {
+        let endpoint = api_url
+            .join(&format!("bot{token}/sendMessage"))
+            .context("building Telegram endpoint")?;
+        Ok(Self {
+            client: http_client()?,
+            endpoint,
+            chat_id,
+        })
+    }
+}
+
+#[async_trait]
+impl NotifySink for TelegramSink {
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        let body = json!({
+            "chat_id": self.chat_id,
+            "text": alert.text(),
+            "disable_web_page_preview": true,
+        });
+        post(&self.client, self.endpoint.clone(), &body)
+            .await
+            .context("Telegram sendMessage failed")
+    }
+}
+
+/// Posts to a Slack incoming webhook.
+#[derive(Clone)]
+pub struct SlackSink {
+    client: Client,
+    url: Url,
+}
+
+impl SlackSink {
+    pub fn new(url: Url) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            url,
+        })
+    }
+}
+
+#[async_trait]
+impl NotifySink for SlackSink {
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        post(
+            &self.client,
+            self.url.clone(),
+            &json!({ "text": alert.text() }),
+        )
+        .await
+        .context("Slack webhook failed")
+    }
+}
+
+#[derive(Serialize)]
+struct HttpPayload<'a> {
+    text: String,
+    #[serde(flatten)]
+    alert: &'a Alert,
+}
+
+/// POSTs each alert as JSON: `text` plus the alert's fields, tagged by
+/// `event`.
+#[derive(Debug, Clone)]
+pub struct HttpSink {
+    client: Client,
+    url: Url,
+}
+
+impl HttpSink {
+    pub fn new(url: Url) -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            url,
+        })
+    }
+}
+
+#[async_trait]
+impl NotifySink for HttpSink {
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        let payload = HttpPayload {
+            text: alert.text(),
+            alert,
+        };
+        post(&self.client, self.url.clone(), &payload)
+            .await
+            .context("notify webhook failed")
+    }
+}
@@ -0,0 +1,283 @@
+use crate::client::DeribitHttpClient;
+use crate::exec::leg_usd;
+use crate::model::{ComboSide, Currency, LegTouch, StrategyKind, StrategyOpportunity, Trade};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Prints fetched per leg; Deribit caps one page at 1000.
+const MAX_PRINTS: u32 = 1000;
+
+/// Where a leg's public prints come from once the check is due.
+#[async_trait]
+pub trait TradeHistory: Send + Sync {
+    async fn trades_between(
+        &self,
+        instrument_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Trade>>;
+}
+
+#[async_trait]
+impl TradeHistory for DeribitHttpClient {
+    async fn trades_between(
+        &self,
+        instrument_name: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Trade>> {
+        self.get_last_trades_by_instrument_and_time(instrument_name, start, end, MAX_PRINTS)
+            .await
+    }
+}
+
+/// What happened to a detected combo when it reached execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostTradeOutcome {
+    /// Orders were submitted.
+    Executed,
+    /// Skipped, rejected, aborted, failed or only planned (dry run).
+    Missed,
+}
+
+impl std::fmt::Display for PostTradeOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PostTradeOutcome::Executed => "executed",
+            PostTradeOutcome::Missed => "missed",
+        })
+    }
+}
+
+/// Whether the legs' prints after the decision bear out the detected edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostTradeVerdict {
+    /// Every leg printed and the combo still had edge at those prints.
+    Real,
+    /// Every leg printed, but at prices that leave no edge.
+    Vanished,
+    /// At least one leg did not trade inside the window.
+    Unverified,
+}
+
+/// One combo waiting for its window to close.
+#[derive(Debug, Clone)]
+pub struct PendingCheck {
+    pub opportunity: StrategyOpportunity,
+    pub outcome: PostTradeOutcome,
+    pub at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+}
+
+/// A leg's detected touch against where it traded inside the window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LegRealization {
+    pub instrument_name: String,
+    pub side: ComboSide,
+    pub touch_price: Decimal,
+    pub prints: usize,
+    /// Volume-weighted print price; `None` without prints.
+    pub vwap: Option<Decimal>,
+    /// Cost of trading the whole leg at the VWAP instead of the touch;
+    /// positive when the prints were worse.
+    pub slippage_usd: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PostTradeReport {
+    pub opportunity_id: String,
+    pub strategy: StrategyKind,
+    pub currency: Currency,
+    pub outcome: PostTradeOutcome,
+    pub at: DateTime<Utc>,
+    pub window_secs: i64,
+    pub detected_edge_usd: Decimal,
+    /// Detected edge less the slippage of the legs that printed.
+    pub edge_at_prints_usd: Decimal,
+    pub verdict: PostTradeVerdict,
+    pub legs: Vec<LegRealization>,
+}
+
+/// Cumulative verdicts of one outcome.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PostTradeStats {
+    pub checks: u64,
+    pub real: u64,
+    pub vanished: u64,
+    pub unverified: u64,
+    pub detected_edge_usd: Decimal,
+    /// Summed over verified checks only.
+    pub edge_at_prints_usd: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PostTradeRow {
+    pub outcome: PostTradeOutcome,
+    #[serde(flatten)]
+    pub stats: PostTradeStats,
+}
+
+/// Queues executed and missed combos and, `delay` after each decision,
+/// hands them back to be checked against the legs' public prints in
+/// between. Shared behind a lock so every execution path can queue.
+pub struct PostTradeValidator {
+    delay: Duration,
+    pending: Mutex<Vec<PendingCheck>>,
+    stats: Mutex<HashMap<PostTradeOutcome, PostTradeStats>>,
+}
+
+impl PostTradeValidator {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: Mutex::new(Vec::new()),
+            stats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn watch(
+        &self,
+        opportunity: &StrategyOpportunity,
+        outcome: PostTradeOutcome,
+        at: DateTime<Utc>,
+    ) {
+        self.pending.lock().push(PendingCheck {
+            opportunity: opportunity.clone(),
+            outcome,
+            at,
+            due_at: at + self.delay,
+        });
+    }
+
+    /// Removes and returns the checks whose window has closed by `now`.
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<PendingCheck> {
+        let mut pending = self.pending.lock();
+        let (due, waiting) = pending.drain(..).partition(|check| check.due_at <= now);
+        *pending = waiting;
+        due
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.lock().len()
+    }
+
+    /// Folds a finished check into the per-outcome counts.
+    pub fn record(&self, report: &PostTradeReport) {
+        let mut stats = self.stats.lock();
+        let entry = stats.entry(report.outcome).or_default();
+        entry.checks += 1;
+        entry.detected_edge_usd += report.detected_edge_usd;
+        match report.verdict {
+            PostTradeVerdict::Real => entry.real += 1,
+            PostTradeVerdict::Vanished => entry.vanished += 1,
+            PostTradeVerdict::Unverified => entry.unverified += 1,
+        }
+        if report.verdict != PostTradeVerdict::Unverified {
+            entry.edge_at_prints_usd += report.edge_at_prints_usd;
+        }
+    }
+
+    /// Cumulative counts, executed before missed.
+    pub fn report(&self) -> Vec<PostTradeRow> {
+        let stats = self.stats.lock();
+        [PostTradeOutcome::Executed, PostTradeOutcome::Missed]
+            .into_iter()
+            .filter_map(|outcome| {
+                stats.get(&outcome).map(|stats| PostTradeRow {
+                    outcome,
+                    stats: *stats,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Fetches each leg's prints between the decision and the due time and
+/// reprices the combo at their VWAPs.
+pub async fn validate<H: TradeHistory + ?Sized>(
+    history: &H,
+    check: &PendingCheck,
+) -> Result<PostTradeReport> {
+    let mut legs = Vec::with_capacity(check.opportunity.touches.len());
+    for touch in &check.opportunity.touches {
+        let trades = history
+            .trades_between(&touch.instrument_name, check.at, check.due_at)
+            .await?;
+        legs.push(realize_leg(&check.opportunity, touch, &trades));
+    }
+    Ok(report(check, legs))
+}
+
+/// Compares one touch with the prints of its instrument; prints of other
+/// instruments are ignored.
+pub fn realize_leg(
+    opportunity: &StrategyOpportunity,
+    touch: &LegTouch,
+    trades: &[Trade],
+) -> LegRealization {
+    let (volume, notional, prints) = trades
+        .iter()
+        .filter(|trade| trade.instrument_name == touch.instrument_name)
+        .fold(
+            (Decimal::ZERO, Decimal::ZERO, 0),
+            |(volume, notional, prints), trade| {
+                (
+                    volume + trade.amount,
+                    notional + trade.price * trade.amount,
+                    prints + 1,
+                )
+            },
+        );
+    let vwap = (!volume.is_zero()).then(|| notional / volume);
+    let slippage_usd = vwap.map(|vwap| {
+        let per_contract = match touch.side {
+            ComboSide::Buy => vwap - touch.price,
+            ComboSide::Sell => touch.price - vwap,
+        };
+        leg_usd(
+            opportunity,
+            &touch.instrument_name,
+            per_contract * touch.size_contracts,
+        )
+    });
+    LegRealization {
+        instrument_name: touch.instrument_name.clone(),
+        side: touch.side,
+        touch_price: touch.price,
+        prints,
+        vwap,
+        slippage_usd,
+    }
+}
+
+pub fn report(check: &PendingCheck, legs: Vec<LegRealization>) -> PostTradeReport {
+    let opportunity = &check.opportunity;
+    let slippage: Decimal = legs.iter().filter_map(|leg| leg.slippage_usd).sum();
+    let edge_at_prints_usd = opportunity.net_edge_usd - slippage;
+    let verdict = if legs.iter().any(|leg| leg.vwap.is_none()) {
+        PostTradeVerdict::Unverified
+    } else if edge_at_prints_usd > Decimal::ZERO {
+        PostTradeVerdict::Real
+    } else {
+        PostTradeVerdict::Vanished
+    };
+    PostTradeReport {
+        opportunity_id: opportunity.id.clone(),
+        strategy: opportunity.strategy,
+        currency: opportunity.currency,
+        outcome: check.outcome,
+        at: check.at,
+        window_secs: (check.due_at - check.at).num_seconds(),
+        detected_edge_usd: opportunity.net_edge_usd,
+        edge_at_prints_usd,
+        verdict,
+        legs,
+    }
+}
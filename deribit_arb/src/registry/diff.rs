@@ -0,0 +1,127 @@
+use crate::model::{StrategyKind, StrategyOpportunity};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How a combo changed between consecutive scans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    New,
+    Improved,
+    Degraded,
+    Gone,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffEvent {
+    pub kind: DiffKind,
+    /// [`StrategyOpportunity::combo_key`]: strategy and legs, which survive
+    /// re-pricing where the content ID does not.
+    pub key: String,
+    /// [`StrategyOpportunity::id`] of the latest sighting.
+    pub id: String,
+    pub strategy: StrategyKind,
+    pub net_edge_usd: Decimal,
+    /// Edge when the combo was last reported; `None` for new combos.
+    pub previous_edge_usd: Option<Decimal>,
+}
+
+impl DiffEvent {
+    /// Whether the event is worth alerting on: a new or better edge.
+    pub fn alerts(&self) -> bool {
+        matches!(self.kind, DiffKind::New | DiffKind::Improved)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Reported {
+    id: String,
+    strategy: StrategyKind,
+    net_edge_usd: Decimal,
+}
+
+/// Compares each scan with the previous one. A combo's edge is measured
+/// against the edge it was last reported at, so a slow drift is reported
+/// once it adds up to `min_change_usd` rather than never.
+#[derive(Debug)]
+pub struct OpportunityDiff {
+    min_change_usd: Decimal,
+    reported: HashMap<String, Reported>,
+}
+
+impl OpportunityDiff {
+    pub fn new(min_change_usd: Decimal) -> Self {
+        Self {
+            min_change_usd,
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Folds one scan into the baseline and returns what changed: new,
+    /// improved and degraded combos in scan order, then the gone ones.
+    pub fn observe(&mut self, opportunities: &[StrategyOpportunity]) -> Vec<DiffEvent> {
+        let mut events = Vec::new();
+        let mut seen = HashSet::with_capacity(opportunities.len());
+        for opportunity in opportunities {
+            let key = opportunity.combo_key();
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            let edge = opportunity.net_edge_usd;
+            let kind = match self.reported.get_mut(&key) {
+                None => Some(DiffKind::New),
+                Some(reported) => {
+                    reported.id.clone_from(&opportunity.id);
+                    let change = edge - reported.net_edge_usd;
+                    if change >= self.min_change_usd && change > Decimal::ZERO {
+                        Some(DiffKind::Improved)
+                    } else if -change >= self.min_change_usd && change < Decimal::ZERO {
+                        Some(DiffKind::Degraded)
+                    } else {
+                        None
+                    }
+                }
+            };
+            let Some(kind) = kind else {
+                continue;
+            };
+            let previous = self.reported.insert(
+                key.clone(),
+                Reported {
+                    id: opportunity.id.clone(),
+                    strategy: opportunity.strategy,
+                    net_edge_usd: edge,
+                },
+            );
+            events.push(DiffEvent {
+                kind,
+                key,
+                id: opportunity.id.clone(),
+                strategy: opportunity.strategy,
+                net_edge_usd: edge,
+                previous_edge_usd: previous.map(|reported| reported.net_edge_usd),
+            });
+        }
+
+        let gone: Vec<String> = self
+            .reported
+            .keys()
+            .filter(|key| !seen.contains(*key))
+            .cloned()
+            .collect();
+        for key in gone {
+            if let Some(reported) = self.reported.remove(&key) {
+                events.push(DiffEvent {
+                    kind: DiffKind::Gone,
+                    key,
+                    id: reported.id,
+                    strategy: reported.strategy,
+                    net_edge_usd: Decimal::ZERO,
+                    previous_edge_usd: Some(reported.net_edge_usd),
+                });
+            }
+        }
+        events
+    }
+}
@@ -0,0 +1,143 @@
+use crate::model::{StrategyKind, StrategyOpportunity};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+pub mod diff;
+
+pub use diff::{DiffEvent, DiffKind, OpportunityDiff};
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// [`StrategyOpportunity::id`] of the latest sighting.
+    id: String,
+    strategy: StrategyKind,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    last_alerted: DateTime<Utc>,
+}
+
+/// An opportunity that was present in an earlier cycle but not the latest one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Disappeared {
+    pub key: String,
+    /// [`StrategyOpportunity::id`] of the last sighting before it vanished.
+    pub id: String,
+    pub strategy: StrategyKind,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub persisted: Duration,
+}
+
+#[derive(Debug, Default)]
+pub struct RegistryUpdate {
+    /// New opportunities, or repeats whose cooldown has elapsed.
+    pub fresh: Vec<StrategyOpportunity>,
+    /// Repeats still inside the cooldown window.
+    pub suppressed: usize,
+    pub disappeared: Vec<Disappeared>,
+}
+
+/// Tracks opportunities across scan cycles so a combo that stays on the book
+/// is alerted and planned once per cooldown window instead of every cycle.
+#[derive(Debug)]
+pub struct OpportunityRegistry {
+    cooldown: Duration,
+    entries: HashMap<String, Entry>,
+}
+
+impl OpportunityRegistry {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Identity of an opportunity: strategy, legs, and the touched prices.
+    /// A re-priced combo is treated as a new opportunity.
+    pub fn key(opportunity: &StrategyOpportunity) -> String {
+        let mut key = opportunity.strategy.to_string();
+        for leg in &opportunity.legs {
+            let _ = write!(key, "|{}:{}:{}", leg.instrument_name, leg.side, leg.ratio);
+        }
+        for touch in &opportunity.touches {
+            let _ = write!(
+                key,
+                "|{}@{}",
+                touch.instrument_name,
+                touch.price.normalize()
+            );
+        }
+        key
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Folds one scan cycle into the registry.
+    pub fn observe(
+        &mut self,
+        opportunities: Vec<StrategyOpportunity>,
+        now: DateTime<Utc>,
+    ) -> RegistryUpdate {
+        let mut update = RegistryUpdate::default();
+        let mut seen = HashSet::with_capacity(opportunities.len());
+        for opportunity in opportunities {
+            let key = Self::key(&opportunity);
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            match self.entries.get_mut(&key) {
+                Some(entry) => {
+                    entry.id.clone_from(&opportunity.id);
+                    entry.last_seen = now;
+                    if now - entry.last_alerted >= self.cooldown {
+                        entry.last_alerted = now;
+                        update.fresh.push(opportunity);
+                    } else {
+                        update.suppressed += 1;
+                    }
+                }
+                None => {
+                    self.entries.insert(
+                        key,
+                        Entry {
+                            id: opportunity.id.clone(),
+                            strategy: opportunity.strategy,
+                            first_seen: now,
+                            last_seen: now,
+                            last_alerted: now,
+                        },
+                    );
+                    update.fresh.push(opportunity);
+                }
+            }
+        }
+
+        let gone: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|key| !seen.contains(*key))
+            .cloned()
+            .collect();
+        for key in gone {
+            if let Some(entry) = self.entries.remove(&key) {
+                update.disappeared.push(Disappeared {
+                    key,
+                    id: entry.id,
+                    strategy: entry.strategy,
+                    first_seen: entry.first_seen,
+                    last_seen: entry.last_seen,
+                    persisted: entry.last_seen - entry.first_seen,
+                });
+            }
+        }
+        update
+    }
+}
@@ -1,13 +1,91 @@
-use crate::model::{StrategyKind, StrategyOpportunity};
+use crate::backtest::BacktestReport;
+use crate::detect::ScanSummary;
+use crate::model::{GroupKey, LegFee, StrategyKind, StrategyOpportunity};
+use crate::selftest::{SelfTest, StepStatus};
 use anyhow::Result;
+use chrono::Utc;
 use comfy_table::{presets::UTF8_BORDERS_ONLY, Cell, Table};
 use csv::Writer;
-use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use serde::Serialize;
+use std::fmt::Write as _;
 use std::fs::File;
+use std::io::Write;
 use std::path::Path;
 use tracing::info;
 
-pub fn print_table(opportunities: &[StrategyOpportunity], limit: usize) -> Result<()> {
+/// Rows printed and exported per scan when `--top` is unset.
+pub const DEFAULT_TOP_ROWS: usize = 10;
+
+/// Opportunities sharing one [`GroupKey`] value, in their sorted order.
+#[derive(Debug, Clone)]
+pub struct OpportunityGroup<'a> {
+    pub label: String,
+    pub rows: Vec<&'a StrategyOpportunity>,
+}
+
+impl OpportunityGroup<'_> {
+    pub fn edge_usd(&self) -> Decimal {
+        self.rows.iter().map(|opp| opp.net_edge_usd).sum()
+    }
+
+    pub fn fees_usd(&self) -> Decimal {
+        self.rows
+            .iter()
+            .map(|opp| opp.fee_breakdown.total_usd)
+            .sum()
+    }
+
+    pub fn notional_usd(&self) -> Decimal {
+        self.rows.iter().map(|opp| opp.notional_usd).sum()
+    }
+
+    /// Total edge over total notional.
+    pub fn edge_bps(&self) -> f64 {
+        let notional = self.notional_usd();
+        if notional.is_zero() {
+            return 0.0;
+        }
+        (self.edge_usd() / notional * Decimal::from(10_000))
+            .to_f64()
+            .unwrap_or(0.0)
+    }
+}
+
+/// Splits `opportunities` by `key`. Groups are ordered by their first row,
+/// so the best group under the view's sort comes first.
+pub fn group_opportunities(
+    opportunities: &[StrategyOpportunity],
+    key: GroupKey,
+) -> Vec<OpportunityGroup<'_>> {
+    let mut groups: Vec<OpportunityGroup<'_>> = Vec::new();
+    for opp in opportunities {
+        let label = match key {
+            GroupKey::Strategy => format_strategy(opp.strategy).to_string(),
+            GroupKey::Expiry => opp
+                .expiry
+                .iter()
+                .min()
+                .map_or("-".to_string(), |dt| dt.format("%Y-%m-%d").to_string()),
+        };
+        match groups.iter_mut().find(|group| group.label == label) {
+            Some(group) => group.rows.push(opp),
+            None => groups.push(OpportunityGroup {
+                label,
+                rows: vec![opp],
+            }),
+        }
+    }
+    groups
+}
+
+/// Prints up to `limit` rows, grouped with a subtotal row per group when
+/// `group_by` is set.
+pub fn print_table(
+    opportunities: &[StrategyOpportunity],
+    limit: usize,
+    group_by: Option<GroupKey>,
+) -> Result<()> {
     let mut table = Table::new();
     table.load_preset(UTF8_BORDERS_ONLY);
     table.set_header(vec![
@@ -23,64 +101,238 @@ pub fn print_table(opportunities: &[StrategyOpportunity], limit: usize) -> Resul
         "Net Edge ($)",
         "Fees ($)",
         "Edge bps",
+        "Ann. Return %",
     ]);
 
-    for opp in opportunities.iter().take(limit) {
-        let legs_desc = opp
-            .legs
-            .iter()
-            .map(|leg| format!("{}:{}@{}", leg.side, leg.instrument_name, leg.ratio))
-            .collect::<Vec<_>>()
-            .join(" ");
-        let price_desc = if opp.touches.is_empty() {
-            "-".to_string()
-        } else {
-            opp.touches
-                .iter()
-                .map(|touch| {
-                    format!(
-                        "{}:{}@{} ({}c)",
-                        touch.side,
-                        touch.instrument_name,
-                        format_decimal(touch.price),
-                        format_decimal(touch.size_contracts)
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join(" ")
-        };
-        let expiries = opp
-            .expiry
-            .iter()
-            .map(|dt| dt.format("%Y-%m-%d").to_string())
-            .collect::<Vec<_>>()
-            .join("/");
-        let strikes = opp
-            .strikes
+    let shown = &opportunities[..limit.min(opportunities.len())];
+    match group_by {
+        None => {
+            for opp in shown {
+                table.add_row(table_row(opp));
+            }
+        }
+        Some(key) => {
+            for group in group_opportunities(shown, key) {
+                for opp in &group.rows {
+                    table.add_row(table_row(opp));
+                }
+                table.add_row(vec![
+                    Cell::new(format!("Subtotal {}", group.label)),
+                    Cell::new(""),
+                    Cell::new(""),
+                    Cell::new(""),
+                    Cell::new(""),
+                    Cell::new(format!("{} combos", group.rows.len())),
+                    Cell::new(""),
+                    Cell::new(""),
+                    Cell::new(format_decimal(group.notional_usd())),
+                    Cell::new(format_decimal(group.edge_usd())),
+                    Cell::new(format_decimal(group.fees_usd())),
+                    Cell::new(format!("{:.2}", group.edge_bps())),
+                    Cell::new(""),
+                ]);
+            }
+        }
+    }
+
+    println!("{}", table);
+    Ok(())
+}
+
+fn table_row(opp: &StrategyOpportunity) -> Vec<Cell> {
+    let legs_desc = opp
+        .legs
+        .iter()
+        .map(|leg| format!("{}:{}@{}", leg.side, leg.instrument_name, leg.ratio))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let price_desc = if opp.touches.is_empty() {
+        "-".to_string()
+    } else {
+        opp.touches
             .iter()
-            .map(|s| s.normalize().to_string())
+            .map(|touch| {
+                format!(
+                    "{}:{}@{} ({}c)",
+                    touch.side,
+                    touch.instrument_name,
+                    format_decimal(touch.price),
+                    format_decimal(touch.size_contracts)
+                )
+            })
             .collect::<Vec<_>>()
-            .join("/");
+            .join(" ")
+    };
+    let expiries = opp
+        .expiry
+        .iter()
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    let strikes = opp
+        .strikes
+        .iter()
+        .map(|s| s.normalize().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    vec![
+        Cell::new(format_strategy(opp.strategy)),
+        Cell::new(opp.currency.to_string()),
+        Cell::new(opp.settlement.to_string()),
+        Cell::new(expiries),
+        Cell::new(strikes),
+        Cell::new(opp.legs.len().to_string()),
+        Cell::new(legs_desc),
+        Cell::new(price_desc),
+        Cell::new(format_decimal(opp.notional_usd)),
+        Cell::new(format_decimal(opp.net_edge_usd)),
+        Cell::new(format_decimal(opp.fee_breakdown.total_usd)),
+        Cell::new(format!("{:.2}", opp.edge_bps)),
+        Cell::new(format_annualized_return(opp.annualized_return)),
+    ]
+}
+
+/// An annualized return fraction as a percentage, or `-` without one.
+fn format_annualized_return(rate: Option<f64>) -> String {
+    rate.map_or("-".to_string(), |rate| format!("{:.2}", rate * 100.0))
+}
+
+/// Summary of a `backtest` run: captures and edge per strategy, then totals.
+pub fn print_backtest(report: &BacktestReport) -> Result<()> {
+    let mut table = Table::new();
+    table.load_preset(UTF8_BORDERS_ONLY);
+    table.set_header(vec!["Strategy", "Captures", "Edge ($)", "Avg Edge ($)"]);
+    for tally in &report.by_strategy {
         table.add_row(vec![
-            Cell::new(format_strategy(opp.strategy)),
-            Cell::new(opp.currency.to_string()),
-            Cell::new(opp.settlement.to_string()),
-            Cell::new(expiries),
-            Cell::new(strikes),
-            Cell::new(opp.legs.len().to_string()),
-            Cell::new(legs_desc),
-            Cell::new(price_desc),
-            Cell::new(format_decimal(opp.notional_usd)),
-            Cell::new(format_decimal(opp.net_edge_usd)),
-            Cell::new(format_decimal(opp.fee_breakdown.total_usd)),
-            Cell::new(format!("{:.2}", opp.edge_bps)),
+            Cell::new(format_strategy(tally.strategy)),
+            Cell::new(tally.captures.to_string()),
+            Cell::new(format_decimal(tally.edge_usd)),
+            Cell::new(format_decimal(
+                tally.edge_usd / Decimal::from(tally.captures.max(1)),
+            )),
         ]);
     }
+    table.add_row(vec![
+        Cell::new("Total"),
+        Cell::new(report.total_captures().to_string()),
+        Cell::new(format_decimal(report.total_edge_usd())),
+        Cell::new("-"),
+    ]);
+
+    let active = report
+        .points
+        .iter()
+        .filter(|point| point.opportunities > 0)
+        .count();
+    println!(
+        "Backtest {} → {} ({} scans every {}s, {} with opportunities, {} book ticks)",
+        report.window.from.format("%Y-%m-%d %H:%M"),
+        report.window.to.format("%Y-%m-%d %H:%M"),
+        report.points.len(),
+        report.window.step.num_seconds(),
+        active,
+        report.ticks_replayed,
+    );
+    println!("{}", table);
+    if let Some(ledger) = &report.ledger {
+        println!(
+            "Simulated ledger: ${} capital, {} fills ({} skipped for capital), realized ${} ({:.2}%), open ${} locked / ${} pending, turnover ${}, peak utilization {:.1}%",
+            format_decimal(ledger.starting_capital_usd),
+            ledger.fills,
+            ledger.skipped_for_capital,
+            format_decimal(ledger.realized_pnl_usd),
+            ledger.return_on_capital() * 100.0,
+            format_decimal(ledger.locked_usd),
+            format_decimal(ledger.unrealized_pnl_usd),
+            format_decimal(ledger.turnover_usd),
+            ledger.peak_utilization * 100.0,
+        );
+    }
+    for delivery in &report.deliveries {
+        println!(
+            "{} {} settled at ${}",
+            delivery.currency,
+            delivery.expiry.format("%Y-%m-%d %H:%M"),
+            format_decimal(delivery.delivery_price),
+        );
+    }
+    Ok(())
+}
 
+/// Per-step outcome of a `selftest` run.
+pub fn print_selftest(report: &SelfTest) -> Result<()> {
+    let mut table = Table::new();
+    table.load_preset(UTF8_BORDERS_ONLY);
+    table.set_header(vec!["Step", "Result", "Time (ms)", "Detail"]);
+    for step in &report.steps {
+        let status = match step.status {
+            StepStatus::Passed => "pass",
+            StepStatus::Failed => "FAIL",
+            StepStatus::Skipped => "skip",
+        };
+        table.add_row(vec![
+            Cell::new(step.name),
+            Cell::new(status),
+            Cell::new(step.elapsed_ms.to_string()),
+            Cell::new(&step.detail),
+        ]);
+    }
     println!("{}", table);
     Ok(())
 }
 
+/// Streams one JSON object per opportunity, newline-delimited.
+pub fn write_jsonl<W: Write>(opportunities: &[StrategyOpportunity], mut writer: W) -> Result<()> {
+    for opp in opportunities {
+        serde_json::to_writer(&mut writer, opp)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// One `{"type": "scan_summary", ...}` line, told apart from the
+/// opportunity lines around it by its `type`.
+pub fn write_scan_summary<W: Write>(summary: &ScanSummary, mut writer: W) -> Result<()> {
+    #[derive(Serialize)]
+    struct Line<'a> {
+        r#type: &'static str,
+        #[serde(flatten)]
+        summary: &'a ScanSummary,
+    }
+    serde_json::to_writer(
+        &mut writer,
+        &Line {
+            r#type: "scan_summary",
+            summary,
+        },
+    )?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The backtest summary as one `{"type": "backtest", ...}` line, for
+/// `--output jsonl`.
+pub fn write_backtest_json<W: Write>(report: &BacktestReport, mut writer: W) -> Result<()> {
+    #[derive(Serialize)]
+    struct Line<'a> {
+        r#type: &'static str,
+        #[serde(flatten)]
+        report: &'a BacktestReport,
+    }
+    serde_json::to_writer(
+        &mut writer,
+        &Line {
+            r#type: "backtest",
+            report,
+        },
+    )?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
 pub fn export_csv<P: AsRef<Path>>(opportunities: &[StrategyOpportunity], path: P) -> Result<()> {
     let mut writer = Writer::from_writer(File::create(path)?);
     writer.write_record([
@@ -95,6 +347,8 @@ pub fn export_csv<P: AsRef<Path>>(opportunities: &[StrategyOpportunity], path: P
         "notional_usd",
         "fees_usd",
         "size_contracts",
+        "size_ladder",
+        "annualized_return",
     ])?;
     for opp in opportunities {
         let expiry = opp
@@ -136,6 +390,18 @@ pub fn export_csv<P: AsRef<Path>>(opportunities: &[StrategyOpportunity], path: P
             opp.notional_usd.normalize().to_string(),
             opp.fee_breakdown.total_usd.normalize().to_string(),
             opp.size_contracts.normalize().to_string(),
+            opp.size_ladder
+                .iter()
+                .map(|rung| {
+                    let edge = rung
+                        .net_edge_usd
+                        .map_or("-".to_string(), |edge| edge.normalize().to_string());
+                    format!("{}x:{edge}", rung.depth_multiple)
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+            opp.annualized_return
+                .map_or(String::new(), |rate| rate.to_string()),
         ];
         writer.write_record(record)?;
     }
@@ -144,7 +410,437 @@ pub fn export_csv<P: AsRef<Path>>(opportunities: &[StrategyOpportunity], path: P
     Ok(())
 }
 
-fn format_strategy(strategy: StrategyKind) -> &'static str {
+const FEE_COLUMNS: [&str; 17] = [
+    "opportunity_id",
+    "strategy",
+    "currency",
+    "instrument",
+    "side",
+    "settlement",
+    "role",
+    "trade_fee_native",
+    "trade_fee_usd",
+    "combo_discount",
+    "combo_discount_usd",
+    "delivery_fee",
+    "delivery_fee_usd",
+    "hedge_fee",
+    "hedge_fee_usd",
+    "total_fee_native",
+    "total_fee_usd",
+];
+
+/// One leg of an opportunity's [`FeeBreakdown`](crate::model::FeeBreakdown),
+/// with the combo-level components repeated on every leg of the combo.
+struct FeeRow<'a> {
+    opportunity: &'a StrategyOpportunity,
+    leg: &'a LegFee,
+}
+
+impl FeeRow<'_> {
+    fn text(&self) -> [String; 7] {
+        [
+            self.opportunity.id.clone(),
+            format_strategy(self.opportunity.strategy).to_string(),
+            self.opportunity.currency.to_string(),
+            self.leg.instrument_name.clone(),
+            self.leg.side.to_string(),
+            self.leg.settlement.to_string(),
+            self.leg.execution_role.to_string(),
+        ]
+    }
+
+    fn amounts(&self) -> [Decimal; 10] {
+        let fees = &self.opportunity.fee_breakdown;
+        [
+            self.leg.trade_fee_native,
+            self.leg.trade_fee_usd,
+            fees.combo_discount,
+            fees.combo_discount_usd,
+            fees.delivery_fee,
+            fees.delivery_fee_usd,
+            fees.hedge_fee,
+            fees.hedge_fee_usd,
+            fees.total_native,
+            fees.total_usd,
+        ]
+    }
+}
+
+fn leg_fee_rows(opportunities: &[StrategyOpportunity]) -> Vec<FeeRow<'_>> {
+    opportunities
+        .iter()
+        .flat_map(|opportunity| {
+            opportunity
+                .fee_breakdown
+                .legs
+                .iter()
+                .map(move |leg| FeeRow { opportunity, leg })
+        })
+        .collect()
+}
+
+/// Writes the fee model's full breakdown, one row per leg: the leg's trade
+/// fee (after any combo discount) with its settlement currency and fill
+/// role, followed by the combo's discount, delivery and hedge fees and
+/// totals. Meant for reconciling the fee model against exchange statements;
+/// sum the combo-level columns once per `opportunity_id`, not per row.
+pub fn export_fee_csv<P: AsRef<Path>>(
+    opportunities: &[StrategyOpportunity],
+    path: P,
+) -> Result<()> {
+    let mut writer = Writer::from_writer(File::create(path)?);
+    writer.write_record(FEE_COLUMNS)?;
+    for row in leg_fee_rows(opportunities) {
+        let amounts = row.amounts().map(|amount| amount.normalize().to_string());
+        writer.write_record(row.text().iter().chain(amounts.iter()))?;
+    }
+    writer.flush()?;
+    info!(target: "export.csv", "wrote fee breakdown to disk");
+    Ok(())
+}
+
+/// [`export_fee_csv`] as Parquet, with the amounts as `f64` columns.
+#[cfg(feature = "export-polars")]
+pub fn export_fee_parquet<P: AsRef<Path>>(
+    opportunities: &[StrategyOpportunity],
+    path: P,
+) -> Result<()> {
+    use polars::prelude::{DataFrame, NamedFrom, ParquetWriter, Series};
+    use rust_decimal::prelude::ToPrimitive;
+
+    let rows = leg_fee_rows(opportunities);
+    let text = rows.iter().map(FeeRow::text).collect::<Vec<_>>();
+    let amounts = rows.iter().map(FeeRow::amounts).collect::<Vec<_>>();
+    let mut columns = Vec::with_capacity(FEE_COLUMNS.len());
+    for (index, name) in FEE_COLUMNS.iter().take(7).enumerate() {
+        let values = text
+            .iter()
+            .map(|row| row[index].as_str())
+            .collect::<Vec<_>>();
+        columns.push(Series::new(name, values));
+    }
+    for (index, name) in FEE_COLUMNS.iter().skip(7).enumerate() {
+        let values = amounts
+            .iter()
+            .map(|row| row[index].to_f64())
+            .collect::<Vec<_>>();
+        columns.push(Series::new(name, values));
+    }
+    let mut frame = DataFrame::new(columns)?;
+    ParquetWriter::new(File::create(path)?).finish(&mut frame)?;
+    info!(target: "export.parquet", "wrote fee breakdown to disk");
+    Ok(())
+}
+
+/// Per-scan headline numbers shared by the HTML and Markdown reports.
+struct ReportSummary {
+    count: usize,
+    total_edge_usd: Decimal,
+    total_fees_usd: Decimal,
+    total_notional_usd: Decimal,
+    best_edge_usd: Decimal,
+    by_strategy: Vec<(StrategyKind, usize, Decimal)>,
+}
+
+impl ReportSummary {
+    fn from(opportunities: &[StrategyOpportunity]) -> Self {
+        let mut by_strategy: Vec<(StrategyKind, usize, Decimal)> = Vec::new();
+        for opp in opportunities {
+            match by_strategy
+                .iter_mut()
+                .find(|(kind, _, _)| *kind == opp.strategy)
+            {
+                Some(entry) => {
+                    entry.1 += 1;
+                    entry.2 += opp.net_edge_usd;
+                }
+                None => by_strategy.push((opp.strategy, 1, opp.net_edge_usd)),
+            }
+        }
+        Self {
+            count: opportunities.len(),
+            total_edge_usd: opportunities.iter().map(|o| o.net_edge_usd).sum(),
+            total_fees_usd: opportunities
+                .iter()
+                .map(|o| o.fee_breakdown.total_usd)
+                .sum(),
+            total_notional_usd: opportunities.iter().map(|o| o.notional_usd).sum(),
+            best_edge_usd: opportunities
+                .iter()
+                .map(|o| o.net_edge_usd)
+                .max()
+                .unwrap_or_default(),
+            by_strategy,
+        }
+    }
+}
+
+fn opportunity_title(rank: usize, opp: &StrategyOpportunity) -> String {
+    let expiries = opp
+        .expiry
+        .iter()
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    let strikes = opp
+        .strikes
+        .iter()
+        .map(|s| s.normalize().to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!(
+        "{rank}. {} {} {} {expiries} {strikes}",
+        format_strategy(opp.strategy),
+        opp.currency,
+        opp.settlement
+    )
+}
+
+/// Fee rows as (label, native, usd), excluding zero adjustments.
+fn fee_rows(opp: &StrategyOpportunity) -> Vec<(String, Decimal, Decimal)> {
+    let fees = &opp.fee_breakdown;
+    let mut rows: Vec<(String, Decimal, Decimal)> = fees
+        .legs
+        .iter()
+        .map(|leg| {
+            (
+                format!(
+                    "{} {} ({:?}, {})",
+                    leg.side, leg.instrument_name, leg.execution_role, leg.settlement
+                ),
+                leg.trade_fee_native,
+                leg.trade_fee_usd,
+            )
+        })
+        .collect();
+    if !fees.combo_discount_usd.is_zero() {
+        rows.push((
+            "Combo discount".into(),
+            -fees.combo_discount,
+            -fees.combo_discount_usd,
+        ));
+    }
+    if !fees.delivery_fee_usd.is_zero() {
+        rows.push(("Delivery".into(), fees.delivery_fee, fees.delivery_fee_usd));
+    }
+    if !fees.hedge_fee_usd.is_zero() {
+        rows.push(("Hedge".into(), fees.hedge_fee, fees.hedge_fee_usd));
+    }
+    rows.push(("Total".into(), fees.total_native, fees.total_usd));
+    rows
+}
+
+/// Self-contained Markdown report of a scan: summary, then the top `limit`
+/// opportunities with their leg touches and fee breakdowns.
+pub fn render_markdown(opportunities: &[StrategyOpportunity], limit: usize) -> String {
+    let summary = ReportSummary::from(opportunities);
+    let mut out = String::new();
+    let _ = writeln!(out, "# deribit_arb scan report\n");
+    let _ = writeln!(out, "Generated {}\n", Utc::now().to_rfc3339());
+    let _ = writeln!(
+        out,
+        "| Opportunities | Total edge ($) | Best edge ($) | Total fees ($) | Notional ($) |"
+    );
+    let _ = writeln!(out, "|---|---|---|---|---|");
+    let _ = writeln!(
+        out,
+        "| {} | {} | {} | {} | {} |\n",
+        summary.count,
+        format_decimal(summary.total_edge_usd),
+        format_decimal(summary.best_edge_usd),
+        format_decimal(summary.total_fees_usd),
+        format_decimal(summary.total_notional_usd)
+    );
+    let _ = writeln!(out, "| Strategy | Count | Edge ($) |");
+    let _ = writeln!(out, "|---|---|---|");
+    for (kind, count, edge) in &summary.by_strategy {
+        let _ = writeln!(
+            out,
+            "| {} | {count} | {} |",
+            format_strategy(*kind),
+            format_decimal(*edge)
+        );
+    }
+
+    for (idx, opp) in opportunities.iter().take(limit).enumerate() {
+        let _ = writeln!(out, "\n## {}\n", opportunity_title(idx + 1, opp));
+        let _ = writeln!(
+            out,
+            "Net edge **${}** ({:.2} bps) on ${} notional, size {} contracts.\n",
+            format_decimal(opp.net_edge_usd),
+            opp.edge_bps,
+            format_decimal(opp.notional_usd),
+            format_decimal(opp.size_contracts)
+        );
+        let _ = writeln!(out, "| Side | Instrument | Price | Contracts |");
+        let _ = writeln!(out, "|---|---|---|---|");
+        for touch in &opp.touches {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                touch.side,
+                touch.instrument_name,
+                format_decimal(touch.price),
+                format_decimal(touch.size_contracts)
+            );
+        }
+        if !opp.size_ladder.is_empty() {
+            let _ = writeln!(out, "\n| Size | Contracts | Net edge ($) |");
+            let _ = writeln!(out, "|---|---|---|");
+            for rung in &opp.size_ladder {
+                let _ = writeln!(
+                    out,
+                    "| {}x | {} | {} |",
+                    rung.depth_multiple,
+                    format_decimal(rung.size_contracts),
+                    format_rung_edge(rung.net_edge_usd)
+                );
+            }
+        }
+        let _ = writeln!(out, "\n| Fee | Native | USD |");
+        let _ = writeln!(out, "|---|---|---|");
+        for (label, native, usd) in fee_rows(opp) {
+            let _ = writeln!(
+                out,
+                "| {label} | {} | {} |",
+                format_decimal(native),
+                format_decimal(usd)
+            );
+        }
+    }
+    out
+}
+
+/// HTML counterpart of [`render_markdown`] with inline styles, so the file
+/// renders on its own in a browser or mail client.
+pub fn render_html(opportunities: &[StrategyOpportunity], limit: usize) -> String {
+    let summary = ReportSummary::from(opportunities);
+    let mut out = String::new();
+    out.push_str(concat!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">",
+        "<title>deribit_arb scan report</title><style>",
+        "body{font-family:sans-serif;margin:2em}",
+        "table{border-collapse:collapse;margin:0.5em 0 1.5em}",
+        "th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}",
+        "th:first-child,td:first-child{text-align:left}",
+        "</style></head><body>\n"
+    ));
+    let _ = writeln!(out, "<h1>deribit_arb scan report</h1>");
+    let _ = writeln!(out, "<p>Generated {}</p>", Utc::now().to_rfc3339());
+    let _ = writeln!(
+        out,
+        "<table><tr><th>Opportunities</th><th>Total edge ($)</th><th>Best edge ($)</th><th>Total fees ($)</th><th>Notional ($)</th></tr>"
+    );
+    let _ = writeln!(
+        out,
+        "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr></table>",
+        summary.count,
+        format_decimal(summary.total_edge_usd),
+        format_decimal(summary.best_edge_usd),
+        format_decimal(summary.total_fees_usd),
+        format_decimal(summary.total_notional_usd)
+    );
+    let _ = writeln!(
+        out,
+        "<table><tr><th>Strategy</th><th>Count</th><th>Edge ($)</th></tr>"
+    );
+    for (kind, count, edge) in &summary.by_strategy {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{count}</td><td>{}</td></tr>",
+            format_strategy(*kind),
+            format_decimal(*edge)
+        );
+    }
+    out.push_str("</table>\n");
+
+    for (idx, opp) in opportunities.iter().take(limit).enumerate() {
+        let _ = writeln!(
+            out,
+            "<h2>{}</h2>",
+            escape_html(&opportunity_title(idx + 1, opp))
+        );
+        let _ = writeln!(
+            out,
+            "<p>Net edge <b>${}</b> ({:.2} bps) on ${} notional, size {} contracts.</p>",
+            format_decimal(opp.net_edge_usd),
+            opp.edge_bps,
+            format_decimal(opp.notional_usd),
+            format_decimal(opp.size_contracts)
+        );
+        out.push_str(
+            "<table><tr><th>Side</th><th>Instrument</th><th>Price</th><th>Contracts</th></tr>\n",
+        );
+        for touch in &opp.touches {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                touch.side,
+                escape_html(&touch.instrument_name),
+                format_decimal(touch.price),
+                format_decimal(touch.size_contracts)
+            );
+        }
+        out.push_str("</table>\n");
+        if !opp.size_ladder.is_empty() {
+            out.push_str("<table><tr><th>Size</th><th>Contracts</th><th>Net edge ($)</th></tr>\n");
+            for rung in &opp.size_ladder {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}x</td><td>{}</td><td>{}</td></tr>",
+                    rung.depth_multiple,
+                    format_decimal(rung.size_contracts),
+                    format_rung_edge(rung.net_edge_usd)
+                );
+            }
+            out.push_str("</table>\n");
+        }
+        out.push_str("<table><tr><th>Fee</th><th>Native</th><th>USD</th></tr>\n");
+        for (label, native, usd) in fee_rows(opp) {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&label),
+                format_decimal(native),
+                format_decimal(usd)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+pub fn export_markdown<P: AsRef<Path>>(
+    opportunities: &[StrategyOpportunity],
+    limit: usize,
+    path: P,
+) -> Result<()> {
+    std::fs::write(path, render_markdown(opportunities, limit))?;
+    info!(target: "export.markdown", "wrote scan report to disk");
+    Ok(())
+}
+
+pub fn export_html<P: AsRef<Path>>(
+    opportunities: &[StrategyOpportunity],
+    limit: usize,
+    path: P,
+) -> Result<()> {
+    std::fs::write(path, render_html(opportunities, limit))?;
+    info!(target: "export.html", "wrote scan report to disk");
+    Ok(())
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub(crate) fn format_strategy(strategy: StrategyKind) -> &'static str {
     match strategy {
         StrategyKind::Vertical => "Vertical",
         StrategyKind::Butterfly => "Butterfly",
@@ -155,7 +851,12 @@ fn format_strategy(strategy: StrategyKind) -> &'static str {
     }
 }
 
-fn format_decimal(value: Decimal) -> String {
+/// A ladder rung's edge, or `-` when the book cannot fill it.
+fn format_rung_edge(edge: Option<Decimal>) -> String {
+    edge.map_or("-".to_string(), format_decimal)
+}
+
+pub(crate) fn format_decimal(value: Decimal) -> String {
     if value.abs() < Decimal::new(1, 2) {
         format!("{:.4}", value)
     } else {
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+/// One of the execution budgets that bound how fast the loop may trade,
+/// whatever the detectors report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    /// Orders placed, edited or accepted in the last minute.
+    OrdersPerMinute,
+    /// Combos approved in the last hour.
+    CombosPerHour,
+    /// USD notional approved during the UTC day.
+    NotionalPerDay,
+}
+
+impl Display for BudgetKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            BudgetKind::OrdersPerMinute => "orders/min",
+            BudgetKind::CombosPerHour => "combos/hour",
+            BudgetKind::NotionalPerDay => "notional/day",
+        };
+        f.write_str(label)
+    }
+}
+
+/// A budget with no room left for the request, and when the window frees
+/// enough of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BudgetExhausted {
+    pub budget: BudgetKind,
+    pub used: Decimal,
+    pub max: Decimal,
+    pub resumes_at: DateTime<Utc>,
+}
+
+/// Usage counted against the budgets: sliding windows for orders and
+/// combos, a UTC-day total for notional.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BudgetWindows {
+    #[serde(default)]
+    orders: VecDeque<DateTime<Utc>>,
+    #[serde(default)]
+    combos: VecDeque<DateTime<Utc>>,
+    #[serde(default)]
+    notional_day: Option<NaiveDate>,
+    #[serde(default)]
+    notional_usd: Decimal,
+}
+
+impl BudgetWindows {
+    fn roll(&mut self, now: DateTime<Utc>) {
+        let minute_ago = now - Duration::minutes(1);
+        while self.orders.front().is_some_and(|at| *at <= minute_ago) {
+            self.orders.pop_front();
+        }
+        let hour_ago = now - Duration::hours(1);
+        while self.combos.front().is_some_and(|at| *at <= hour_ago) {
+            self.combos.pop_front();
+        }
+        if self.notional_day != Some(now.date_naive()) {
+            self.notional_day = Some(now.date_naive());
+            self.notional_usd = Decimal::ZERO;
+        }
+    }
+
+    /// Counts `count` orders unless that would exceed `max` in the last
+    /// minute; nothing is counted on refusal.
+    pub(crate) fn take_orders(
+        &mut self,
+        max: Option<u32>,
+        count: usize,
+        now: DateTime<Utc>,
+    ) -> Result<(), BudgetExhausted> {
+        self.roll(now);
+        if let Some(max) = max {
+            let max = max as usize;
+            if self.orders.len() + count > max {
+                // The window frees an order when its oldest entry turns a
+                // minute old; a request larger than the budget never fits.
+                let excess = self.orders.len() + count - max;
+                let resumes_at = match self.orders.get(excess - 1) {
+                    Some(at) if count <= max => *at + Duration::minutes(1),
+                    _ => now + Duration::minutes(1),
+                };
+                return Err(BudgetExhausted {
+                    budget: BudgetKind::OrdersPerMinute,
+                    used: Decimal::from(self.orders.len()),
+                    max: Decimal::from(max),
+                    resumes_at,
+                });
+            }
+        }
+        self.orders.extend(std::iter::repeat_n(now, count));
+        Ok(())
+    }
+
+    /// Checks one more combo of `notional_usd` against both approval
+    /// budgets without counting it.
+    pub(crate) fn check_combo(
+        &mut self,
+        max_combos: Option<u32>,
+        max_notional_usd: Option<Decimal>,
+        notional_usd: Decimal,
+        now: DateTime<Utc>,
+    ) -> Result<(), BudgetExhausted> {
+        self.roll(now);
+        if let Some(max) = max_combos {
+            if self.combos.len() >= max as usize {
+                return Err(BudgetExhausted {
+                    budget: BudgetKind::CombosPerHour,
+                    used: Decimal::from(self.combos.len()),
+                    max: Decimal::from(max),
+                    resumes_at: self
+                        .combos
+                        .front()
+                        .map_or(now, |at| *at + Duration::hours(1)),
+                });
+            }
+        }
+        if let Some(max) = max_notional_usd {
+            if self.notional_usd + notional_usd > max {
+                let tomorrow = now.date_naive() + Duration::days(1);
+                return Err(BudgetExhausted {
+                    budget: BudgetKind::NotionalPerDay,
+                    used: self.notional_usd,
+                    max,
+                    resumes_at: tomorrow.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record_combo(&mut self, notional_usd: Decimal, now: DateTime<Utc>) {
+        self.combos.push_back(now);
+        self.notional_usd += notional_usd;
+    }
+}
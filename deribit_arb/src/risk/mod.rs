@@ -1,38 +1,361 @@
 use crate::config::AppConfig;
-use crate::model::StrategyOpportunity;
+use crate::greeks::PositionGreeks;
+use crate::model::{Currency, Position, StrategyOpportunity};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
 use parking_lot::Mutex;
-use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use thiserror::Error;
 use tracing::{info, warn};
 
-#[derive(Default)]
+mod budget;
+
+use budget::BudgetWindows;
+pub use budget::{BudgetExhausted, BudgetKind};
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RiskRejection {
+    #[error("concurrent combo limit reached ({current}/{max})")]
+    MaxCombos { current: u32, max: u32 },
+    #[error("ticket {notional} exceeds cap {max}")]
+    TicketCap { notional: Decimal, max: Decimal },
+    #[error("{currency} notional {notional} would exceed cap {max}")]
+    CurrencyNotional {
+        currency: Currency,
+        notional: Decimal,
+        max: Decimal,
+    },
+    #[error("{currency} net {greek} {projected:.2} would exceed cap {max}")]
+    NetGreek {
+        currency: Currency,
+        greek: &'static str,
+        projected: f64,
+        max: Decimal,
+    },
+    #[error("greeks unavailable for greek caps")]
+    GreeksUnavailable,
+    #[error("daily realized loss {realized} hit limit {limit}")]
+    DailyLossLimit { realized: Decimal, limit: Decimal },
+    #[error("recent PnL negative (ewma {ewma})")]
+    NegativePnl { ewma: Decimal },
+    #[error(
+        "{currency} vol {vol:.1} in high-vol regime, edge {edge} below widened minimum {required}"
+    )]
+    VolRegime {
+        currency: Currency,
+        vol: f64,
+        edge: Decimal,
+        required: Decimal,
+    },
+    #[error("margin utilization {projected:.3} would exceed cap {max}")]
+    MarginUtilization { projected: f64, max: f64 },
+    #[error("margin estimate or account equity unavailable for the margin cap")]
+    MarginUnavailable,
+    #[error("{budget} budget spent ({used}/{max}), deferred until {resumes_at}")]
+    Budget {
+        budget: BudgetKind,
+        used: Decimal,
+        max: Decimal,
+        resumes_at: DateTime<Utc>,
+    },
+}
+
+impl RiskRejection {
+    /// Stable snake_case identifier for metrics labels.
+    pub fn label(&self) -> &'static str {
+        match self {
+            RiskRejection::MaxCombos { .. } => "max_combos",
+            RiskRejection::TicketCap { .. } => "ticket_cap",
+            RiskRejection::CurrencyNotional { .. } => "currency_notional",
+            RiskRejection::NetGreek { .. } => "net_greek",
+            RiskRejection::GreeksUnavailable => "greeks_unavailable",
+            RiskRejection::DailyLossLimit { .. } => "daily_loss_limit",
+            RiskRejection::NegativePnl { .. } => "negative_pnl",
+            RiskRejection::VolRegime { .. } => "vol_regime",
+            RiskRejection::MarginUtilization { .. } => "margin_utilization",
+            RiskRejection::MarginUnavailable => "margin_unavailable",
+            RiskRejection::Budget { .. } => "budget",
+        }
+    }
+}
+
+/// An approved combo counted against the caps. It holds a combo slot until
+/// released or filled; filled combos keep their exposure until expiry.
+#[derive(Serialize, Deserialize)]
+struct OpenCombo {
+    key: String,
+    instruments: Vec<String>,
+    currency: Currency,
+    notional_usd: Decimal,
+    greeks: PositionGreeks,
+    /// Estimated portfolio margin; zero for combos saved before it existed.
+    #[serde(default)]
+    margin_usd: Decimal,
+    expires: DateTime<Utc>,
+    holds_slot: bool,
+    /// PnL the fill locked in, booked as realized when the combo expires.
+    #[serde(default)]
+    pnl_usd: Decimal,
+}
+
+#[derive(Default, Serialize, Deserialize)]
 struct RiskState {
-    live_combos: u32,
+    open: Vec<OpenCombo>,
     ewma_pnl: Decimal,
+    pnl_day: Option<NaiveDate>,
+    daily_pnl: Decimal,
+    #[serde(default)]
+    budget: BudgetWindows,
+}
+
+impl RiskState {
+    fn live_combos(&self) -> u32 {
+        self.open.iter().filter(|combo| combo.holds_slot).count() as u32
+    }
+
+    /// Starts a new PnL day when `now` crosses midnight UTC, then settles
+    /// filled combos expired by `now`, booking their PnL. Returns whether
+    /// any combo settled.
+    fn roll(&mut self, now: DateTime<Utc>) -> bool {
+        if self.pnl_day != Some(now.date_naive()) {
+            self.pnl_day = Some(now.date_naive());
+            self.daily_pnl = Decimal::ZERO;
+        }
+        let (settled, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.open)
+            .into_iter()
+            .partition(|combo| !combo.holds_slot && combo.expires <= now);
+        self.open = open;
+        for combo in &settled {
+            info!(
+                target: "risk.state",
+                key = %combo.key,
+                pnl_usd = %combo.pnl_usd,
+                "filled combo settled"
+            );
+            self.book(combo.pnl_usd);
+        }
+        !settled.is_empty()
+    }
+
+    fn book(&mut self, pnl_usd: Decimal) {
+        let alpha = Decimal::new(2, 1); // 0.2 smoothing
+        self.ewma_pnl = (Decimal::ONE - alpha) * self.ewma_pnl + alpha * pnl_usd;
+        self.daily_pnl += pnl_usd;
+    }
+
+    fn exposure(&self, currency: Currency) -> (Decimal, PositionGreeks) {
+        let mut notional = Decimal::ZERO;
+        let mut greeks = PositionGreeks::default();
+        for combo in self.open.iter().filter(|combo| combo.currency == currency) {
+            notional += combo.notional_usd;
+            greeks.add(&combo.greeks);
+        }
+        (notional, greeks)
+    }
+
+    fn margin_usd(&self) -> Decimal {
+        self.open.iter().map(|combo| combo.margin_usd).sum()
+    }
+}
+
+/// Point-in-time view of the risk counters for display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct RiskSnapshot {
+    pub live_combos: u32,
+    pub ewma_pnl: Decimal,
+    pub daily_pnl: Decimal,
 }
 
 #[derive(Clone, Default)]
 pub struct RiskManager {
     state: Arc<Mutex<RiskState>>,
+    store: Option<PathBuf>,
+    /// Latest volatility reading per currency, annualized percent.
+    volatility: Arc<Mutex<HashMap<Currency, f64>>>,
+    /// Latest account equity for the margin utilization cap.
+    equity_usd: Arc<Mutex<Option<Decimal>>>,
+}
+
+fn defer(exhausted: BudgetExhausted) -> RiskRejection {
+    warn!(
+        target: "risk.budget",
+        budget = %exhausted.budget,
+        used = exhausted.used.to_string(),
+        max = exhausted.max.to_string(),
+        resumes_at = %exhausted.resumes_at,
+        "execution budget spent, deferring to the next window"
+    );
+    RiskRejection::Budget {
+        budget: exhausted.budget,
+        used: exhausted.used,
+        max: exhausted.max,
+        resumes_at: exhausted.resumes_at,
+    }
 }
 
 impl RiskManager {
     pub fn new() -> Self {
         Self {
             state: Arc::new(Mutex::new(RiskState::default())),
+            store: None,
+            volatility: Arc::default(),
+            equity_usd: Arc::default(),
+        }
+    }
+
+    /// Restores state saved at `path` (if any) and rewrites it on every
+    /// change, so a restart keeps open combos and loss counters.
+    pub fn open(path: &Path) -> Result<Self> {
+        let state = match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("parsing risk state {}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => RiskState::default(),
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading risk state {}", path.display()))
+            }
+        };
+        info!(
+            target: "risk.state",
+            path = %path.display(),
+            open_combos = state.open.len(),
+            "risk state loaded"
+        );
+        Ok(Self {
+            state: Arc::new(Mutex::new(state)),
+            store: Some(path.to_path_buf()),
+            volatility: Arc::default(),
+            equity_usd: Arc::default(),
+        })
+    }
+
+    /// Drops restored combos with a leg missing from the account's open
+    /// `positions`, except those expired by `now`, which settle with their
+    /// PnL on the next check. Combos that still held a slot when the process
+    /// stopped are no longer tracked by any order, so survivors count as
+    /// filled. Returns how many combos were dropped.
+    pub fn reconcile(&self, positions: &[Position], now: DateTime<Utc>) -> usize {
+        let held: HashSet<&str> = positions
+            .iter()
+            .map(|position| position.instrument_name.as_str())
+            .collect();
+        let mut state = self.state.lock();
+        let before = state.open.len();
+        state.open.retain(|combo| {
+            combo.expires <= now
+                || combo
+                    .instruments
+                    .iter()
+                    .all(|name| held.contains(name.as_str()))
+        });
+        for combo in &mut state.open {
+            combo.holds_slot = false;
         }
+        let dropped = before - state.open.len();
+        if dropped > 0 {
+            warn!(target: "risk.state", dropped, "open combos without positions dropped");
+        }
+        self.persist(&state);
+        dropped
+    }
+
+    /// Keeps state in memory only from now on, so a dry run can read the
+    /// live `--risk-state` without writing simulated PnL back to it.
+    pub fn in_memory(mut self) -> Self {
+        self.store = None;
+        self
     }
 
-    pub fn approve(&self, config: &AppConfig, opp: &StrategyOpportunity) -> bool {
+    /// Records `currency`'s current volatility (the higher of DVOL and hourly
+    /// realized vol) for the `--high-vol-threshold` regime check.
+    pub fn set_volatility(&self, currency: Currency, vol: f64) {
+        self.volatility.lock().insert(currency, vol);
+    }
+
+    /// Records the account equity that `--max-margin-utilization` divides
+    /// the estimated margin of open combos by.
+    pub fn set_equity(&self, equity_usd: Decimal) {
+        *self.equity_usd.lock() = Some(equity_usd);
+    }
+
+    pub fn approve(
+        &self,
+        config: &AppConfig,
+        opp: &StrategyOpportunity,
+        greeks: Option<PositionGreeks>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        self.evaluate(config, opp, greeks, now).is_ok()
+    }
+
+    /// Like [`RiskManager::approve`], but reports why an opportunity was
+    /// turned down. An `Ok` result reserves a combo slot and adds the
+    /// opportunity's notional and `greeks` to its currency's exposure.
+    pub fn evaluate(
+        &self,
+        config: &AppConfig,
+        opp: &StrategyOpportunity,
+        greeks: Option<PositionGreeks>,
+        now: DateTime<Utc>,
+    ) -> Result<(), RiskRejection> {
         let mut state = self.state.lock();
-        if state.live_combos >= config.max_concurrent_combos {
+        if state.roll(now) {
+            self.persist(&state);
+        }
+        if let Some(limit) = config.daily_loss_limit_usd {
+            if state.daily_pnl <= -limit {
+                warn!(
+                    target: "risk.daily_loss",
+                    realized = state.daily_pnl.to_string(),
+                    limit = limit.to_string(),
+                    "daily loss limit hit, halting approvals"
+                );
+                return Err(RiskRejection::DailyLossLimit {
+                    realized: state.daily_pnl,
+                    limit,
+                });
+            }
+        }
+        // Fast markets leave more quotes behind the index, so apparent edges
+        // need a wider margin before they count.
+        if let Some(threshold) = config.high_vol_threshold {
+            let vol = self.volatility.lock().get(&opp.currency).copied();
+            if let Some(vol) = vol.filter(|vol| *vol >= threshold) {
+                let required = config.requirements(opp.strategy).min_edge_usd
+                    * Decimal::from_f64(config.high_vol_edge_multiplier).unwrap_or(Decimal::ONE);
+                if opp.net_edge_usd < required {
+                    warn!(
+                        target: "risk.vol_regime",
+                        currency = %opp.currency,
+                        vol,
+                        edge = opp.net_edge_usd.to_string(),
+                        required = required.to_string(),
+                        "edge below high-vol minimum"
+                    );
+                    return Err(RiskRejection::VolRegime {
+                        currency: opp.currency,
+                        vol,
+                        edge: opp.net_edge_usd,
+                        required,
+                    });
+                }
+            }
+        }
+        let live_combos = state.live_combos();
+        if live_combos >= config.max_concurrent_combos {
             warn!(
                 target: "risk.max_combos",
-                current = state.live_combos,
+                current = live_combos,
                 max = config.max_concurrent_combos,
                 "concurrent combo limit reached"
             );
-            return false;
+            return Err(RiskRejection::MaxCombos {
+                current: live_combos,
+                max: config.max_concurrent_combos,
+            });
         }
         if opp.notional_usd > config.max_ticket_usd {
             warn!(
@@ -41,7 +364,94 @@ impl RiskManager {
                 max = config.max_ticket_usd.to_string(),
                 "ticket exceeds cap"
             );
-            return false;
+            return Err(RiskRejection::TicketCap {
+                notional: opp.notional_usd,
+                max: config.max_ticket_usd,
+            });
+        }
+        let (notional, net) = state.exposure(opp.currency);
+        if let Some(max) = config.max_currency_notional_usd {
+            let projected = notional + opp.notional_usd;
+            if projected > max {
+                warn!(
+                    target: "risk.notional",
+                    currency = %opp.currency,
+                    projected = projected.to_string(),
+                    max = max.to_string(),
+                    "currency notional exceeds cap"
+                );
+                return Err(RiskRejection::CurrencyNotional {
+                    currency: opp.currency,
+                    notional: projected,
+                    max,
+                });
+            }
+        }
+        let greek_caps = [
+            config.max_net_delta_usd,
+            config.max_net_gamma_usd,
+            config.max_net_vega_usd,
+        ];
+        let greeks = match greeks {
+            Some(greeks) => greeks,
+            None if greek_caps.iter().any(Option::is_some) => {
+                warn!(target: "risk.greeks", strategy = %opp.strategy, "greeks unavailable, cannot check greek caps");
+                return Err(RiskRejection::GreeksUnavailable);
+            }
+            None => PositionGreeks::default(),
+        };
+        let checks = [
+            ("delta", net.delta_usd, greeks.delta_usd),
+            ("gamma", net.gamma_usd, greeks.gamma_usd),
+            ("vega", net.vega_usd, greeks.vega_usd),
+        ];
+        for ((greek, current, added), max) in checks.into_iter().zip(greek_caps) {
+            let Some(max) = max else {
+                continue;
+            };
+            let projected = current + added;
+            // Trades that shrink an already-breached net greek stay allowed.
+            if projected.abs() > max.to_f64().unwrap_or(f64::MAX) && projected.abs() > current.abs()
+            {
+                warn!(
+                    target: "risk.greeks",
+                    currency = %opp.currency,
+                    greek,
+                    projected,
+                    max = max.to_string(),
+                    "net greek exceeds cap"
+                );
+                return Err(RiskRejection::NetGreek {
+                    currency: opp.currency,
+                    greek,
+                    projected,
+                    max,
+                });
+            }
+        }
+        let margin_usd = opp.estimated_margin_usd.unwrap_or_default();
+        if let Some(max) = config.max_margin_utilization {
+            let equity = self
+                .equity_usd
+                .lock()
+                .filter(|equity| *equity > Decimal::ZERO);
+            let (Some(equity), Some(_)) = (equity, opp.estimated_margin_usd) else {
+                warn!(target: "risk.margin", strategy = %opp.strategy, "margin estimate or equity unavailable, cannot check margin cap");
+                return Err(RiskRejection::MarginUnavailable);
+            };
+            let projected = ((state.margin_usd() + margin_usd) / equity)
+                .to_f64()
+                .unwrap_or(f64::MAX);
+            if projected > max {
+                warn!(
+                    target: "risk.margin",
+                    projected,
+                    max,
+                    equity = equity.to_string(),
+                    "margin utilization exceeds cap"
+                );
+                return Err(RiskRejection::MarginUtilization { projected, max });
+            }
         }
         if state.ewma_pnl < Decimal::ZERO {
             warn!(
@@ -49,27 +459,120 @@ impl RiskManager {
                 ewma = state.ewma_pnl.to_string(),
                 "recent PnL negative, pausing deployment"
             );
-            return false;
+            return Err(RiskRejection::NegativePnl {
+                ewma: state.ewma_pnl,
+            });
         }
-        state.live_combos += 1;
+        if let Err(exhausted) = state.budget.check_combo(
+            config.max_combos_per_hour,
+            config.max_daily_notional_usd,
+            opp.notional_usd,
+            now,
+        ) {
+            return Err(defer(exhausted));
+        }
+        state.budget.record_combo(opp.notional_usd, now);
+        state.open.push(OpenCombo {
+            key: opp.combo_key(),
+            instruments: opp
+                .legs
+                .iter()
+                .map(|leg| leg.instrument_name.clone())
+                .collect(),
+            currency: opp.currency,
+            notional_usd: opp.notional_usd,
+            greeks,
+            margin_usd,
+            expires: opp.expiry.iter().max().copied().unwrap_or(now),
+            holds_slot: true,
+            pnl_usd: Decimal::ZERO,
+        });
         info!(
             target: "risk.approved",
-            combos = state.live_combos,
+            combos = state.live_combos(),
             "combo approved"
         );
-        true
+        self.persist(&state);
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> RiskSnapshot {
+        let state = self.state.lock();
+        RiskSnapshot {
+            live_combos: state.live_combos(),
+            ewma_pnl: state.ewma_pnl,
+            daily_pnl: state.daily_pnl,
+        }
+    }
+
+    /// Counts `count` orders against `--max-orders-per-minute`, or refuses
+    /// them all until the window has room. Approved combos keep their
+    /// budget even when released, so a loop that keeps failing still runs
+    /// out of it.
+    pub fn take_orders(
+        &self,
+        config: &AppConfig,
+        count: usize,
+        now: DateTime<Utc>,
+    ) -> Result<(), RiskRejection> {
+        self.state
+            .lock()
+            .budget
+            .take_orders(config.max_orders_per_minute, count, now)
+            .map_err(defer)
     }
 
-    pub fn release(&self) {
+    /// Frees the slot and exposure of an approved combo that did not trade.
+    /// `key` is [`StrategyOpportunity::combo_key`].
+    pub fn release(&self, key: &str) {
         let mut state = self.state.lock();
-        if state.live_combos > 0 {
-            state.live_combos -= 1;
+        if let Some(index) = state
+            .open
+            .iter()
+            .position(|combo| combo.holds_slot && combo.key == key)
+        {
+            state.open.remove(index);
+            self.persist(&state);
         }
     }
 
-    pub fn record_pnl(&self, pnl_usd: Decimal) {
+    /// Frees the slot of a combo that traded; its notional and greeks count
+    /// against the caps until its last expiry, when `pnl_usd` is booked as
+    /// realized.
+    pub fn record_fill(&self, key: &str, pnl_usd: Decimal) {
         let mut state = self.state.lock();
-        let alpha = Decimal::new(2, 1); // 0.2 smoothing
-        state.ewma_pnl = (Decimal::ONE - alpha) * state.ewma_pnl + alpha * pnl_usd;
+        if let Some(combo) = state
+            .open
+            .iter_mut()
+            .find(|combo| combo.holds_slot && combo.key == key)
+        {
+            combo.holds_slot = false;
+            combo.pnl_usd = pnl_usd;
+            self.persist(&state);
+        }
+    }
+
+    pub fn record_pnl(&self, pnl_usd: Decimal, now: DateTime<Utc>) {
+        let mut state = self.state.lock();
+        state.roll(now);
+        state.book(pnl_usd);
+        self.persist(&state);
+    }
+
+    /// Write failures are logged rather than propagated, like the audit log:
+    /// a full disk should not stop risk checks.
+    fn persist(&self, state: &RiskState) {
+        let Some(path) = &self.store else {
+            return;
+        };
+        // Write-then-rename so a crash mid-write never leaves a torn file.
+        let tmp = path.with_extension("tmp");
+        let result = serde_json::to_vec_pretty(state)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(std::fs::write(&tmp, bytes)?))
+            .and_then(|_| Ok(std::fs::rename(&tmp, path)?));
+        if let Err(err) = result {
+            warn!(target: "risk.state", path = %path.display(), error = %err, "failed to persist risk state");
+        }
     }
 }
@@ -0,0 +1,252 @@
+use crate::client::{DeribitHttpClient, DeribitWsClient, WsEvent};
+use crate::model::{
+    ComboLeg, ComboSide, Currency, Instrument, OptionKind, OrderRequest, OrderTimeInForce,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::future::Future;
+use std::time::Instant;
+
+/// How long the WebSocket step waits for the first ticker notification.
+const WS_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    /// Not run because a step it depends on failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepResult {
+    pub name: &'static str,
+    pub status: StepStatus,
+    pub detail: String,
+    pub elapsed_ms: u64,
+}
+
+/// Pass/fail log of a `selftest` run, in step order.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SelfTest {
+    pub steps: Vec<StepResult>,
+}
+
+impl SelfTest {
+    /// Runs one step, recording its outcome. The step yields a value for
+    /// later steps plus a one-line detail; `None` when it failed.
+    pub async fn step<T, F>(&mut self, name: &'static str, step: F) -> Option<T>
+    where
+        F: Future<Output = Result<(T, String)>>,
+    {
+        let started = Instant::now();
+        let outcome = step.await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let (value, status, detail) = match outcome {
+            Ok((value, detail)) => (Some(value), StepStatus::Passed, detail),
+            Err(err) => (None, StepStatus::Failed, format!("{err:#}")),
+        };
+        self.steps.push(StepResult {
+            name,
+            status,
+            detail,
+            elapsed_ms,
+        });
+        value
+    }
+
+    pub fn skip(&mut self, names: &[&'static str], reason: &str) {
+        self.steps.extend(names.iter().map(|name| StepResult {
+            name,
+            status: StepStatus::Skipped,
+            detail: reason.to_string(),
+            elapsed_ms: 0,
+        }));
+    }
+
+    /// Whether no step failed; skipped steps do not count against the run.
+    pub fn passed(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|step| step.status != StepStatus::Failed)
+    }
+}
+
+/// Exercises the client end to end against one currency: auth, instrument
+/// load, ticker, WebSocket subscribe, combo create and leg-price preview on
+/// a near-the-money call spread. With `orders`, also sends a one-tick IOC
+/// bid (which cannot fill) and rests and cancels a one-tick post-only bid,
+/// each for the minimum amount.
+pub async fn run(
+    client: &DeribitHttpClient,
+    ws: &DeribitWsClient,
+    currency: Currency,
+    orders: bool,
+) -> SelfTest {
+    let mut test = SelfTest::default();
+    let order_steps: &[&'static str] = if orders {
+        &["ioc order", "order cancel"]
+    } else {
+        &[]
+    };
+    let authed = test
+        .step("auth", async {
+            client.authenticate().await?;
+            Ok(((), "access token issued".to_string()))
+        })
+        .await
+        .is_some();
+
+    let spread = test
+        .step("instruments", async {
+            let instruments = client
+                .get_instruments(currency.instruments_query_currency())
+                .await?;
+            let count = instruments.len();
+            let index = client.get_index_price(currency).await?;
+            let legs = pick_call_spread(instruments, currency, index)
+                .ok_or_else(|| anyhow!("no two listed calls on one expiry for {currency}"))?;
+            let detail = format!(
+                "{count} instruments, spread {} / {}",
+                legs.0.instrument_name, legs.1.instrument_name
+            );
+            Ok((legs, detail))
+        })
+        .await;
+    let Some((low, high)) = spread else {
+        let steps = ["ticker", "ws subscribe", "combo create", "leg preview"];
+        test.skip(&steps, "no instruments");
+        test.skip(order_steps, "no instruments");
+        return test;
+    };
+
+    test.step("ticker", async {
+        let quote = client.get_ticker(&low.instrument_name).await?;
+        let detail = format!(
+            "bid {:?} ask {:?} index {}",
+            quote.best_bid.map(|level| level.price),
+            quote.best_ask.map(|level| level.price),
+            quote.index_price
+        );
+        Ok(((), detail))
+    })
+    .await;
+
+    test.step("ws subscribe", async {
+        let channel = format!("ticker.{}.100ms", low.instrument_name);
+        let mut rx = ws.subscribe(&[channel]).await?;
+        let first = tokio::time::timeout(std::time::Duration::from_secs(WS_TIMEOUT_SECS), async {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    WsEvent::TickerUpdate { .. } => return Ok(()),
+                    WsEvent::Disconnected { reason } => return Err(anyhow!(reason)),
+                    _ => {}
+                }
+            }
+            Err(anyhow!("stream closed"))
+        })
+        .await
+        .context("no ticker notification")?;
+        first.map(|_| ((), "ticker notification received".to_string()))
+    })
+    .await;
+
+    if !authed {
+        test.skip(&["combo create", "leg preview"], "auth failed");
+        test.skip(order_steps, "auth failed");
+        return test;
+    }
+
+    let legs = vec![
+        ComboLeg {
+            instrument_name: low.instrument_name.clone(),
+            ratio: 1,
+            side: ComboSide::Buy,
+        },
+        ComboLeg {
+            instrument_name: high.instrument_name.clone(),
+            ratio: 1,
+            side: ComboSide::Sell,
+        },
+    ];
+    let combo_id = test
+        .step("combo create", async {
+            let combo_id = client
+                .create_combo("selftest", &legs, low.settlement_currency)
+                .await?;
+            Ok((combo_id.clone(), combo_id))
+        })
+        .await;
+    match &combo_id {
+        Some(combo_id) => {
+            test.step("leg preview", async {
+                let preview = client
+                    .get_leg_prices(combo_id, low.min_trade_amount)
+                    .await?;
+                let priced = preview
+                    .get("legs")
+                    .and_then(|legs| legs.as_array())
+                    .map_or(0, |legs| legs.len());
+                Ok(((), format!("{priced} legs priced")))
+            })
+            .await;
+        }
+        None => test.skip(&["leg preview"], "combo create failed"),
+    }
+
+    if orders {
+        let bid = |tif, post_only| OrderRequest {
+            instrument_name: low.instrument_name.clone(),
+            side: ComboSide::Buy,
+            amount: low.min_trade_amount,
+            price: low.tick_size,
+            tif,
+            post_only,
+            label: Some("selftest".to_string()),
+        };
+        test.step("ioc order", async {
+            let order_id = client
+                .place_order(&bid(OrderTimeInForce::IOC, false))
+                .await?;
+            Ok(((), format!("order {order_id}")))
+        })
+        .await;
+        test.step("order cancel", async {
+            let order_id = client
+                .place_order(&bid(OrderTimeInForce::GTC, true))
+                .await?;
+            client.cancel_order(&order_id).await?;
+            Ok(((), format!("order {order_id} placed and cancelled")))
+        })
+        .await;
+    }
+    test
+}
+
+/// The two calls struck nearest at or above the index on the nearest expiry
+/// at least a day out, so the combo is listed long enough to preview.
+fn pick_call_spread(
+    instruments: Vec<Instrument>,
+    currency: Currency,
+    index: Decimal,
+) -> Option<(Instrument, Instrument)> {
+    let cutoff = Utc::now() + Duration::days(1);
+    let mut calls: Vec<Instrument> = instruments
+        .into_iter()
+        .filter(|inst| {
+            inst.currency == currency
+                && inst.option_kind == OptionKind::Call
+                && inst.expiry > cutoff
+        })
+        .collect();
+    let expiry = calls.iter().map(|inst| inst.expiry).min()?;
+    calls.retain(|inst| inst.expiry == expiry && inst.strike >= index);
+    calls.sort_by_key(|inst| inst.strike);
+    let settlement = calls.first()?.settlement_currency;
+    calls.retain(|inst| inst.settlement_currency == settlement);
+    let mut calls = calls.into_iter();
+    Some((calls.next()?, calls.next()?))
+}
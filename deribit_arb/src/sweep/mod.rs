@@ -0,0 +1,70 @@
+use crate::model::BookSummary;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    /// Best bid above best ask.
+    Crossed,
+    /// Best bid equal to best ask.
+    Locked,
+    /// Someone bids above Deribit's mark.
+    BidAboveMark,
+    /// Someone offers below Deribit's mark.
+    AskBelowMark,
+}
+
+impl Display for AnomalyKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnomalyKind::Crossed => write!(f, "crossed"),
+            AnomalyKind::Locked => write!(f, "locked"),
+            AnomalyKind::BidAboveMark => write!(f, "bid above mark"),
+            AnomalyKind::AskBelowMark => write!(f, "ask below mark"),
+        }
+    }
+}
+
+/// An option whose summary row looks mispriced, worth a full ticker read.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookAnomaly {
+    pub instrument_name: String,
+    pub kind: AnomalyKind,
+    pub bid_price: Option<Decimal>,
+    pub ask_price: Option<Decimal>,
+    pub mark_price: Option<Decimal>,
+}
+
+/// Flags crossed or locked books and touches through the mark across a
+/// `get_book_summary_by_currency` response. Summary rows lag the ticker
+/// feed, so these are candidates for the detectors rather than trades.
+/// A crossed or locked book is reported once, ahead of any mark check.
+pub fn find_anomalies(summaries: &[BookSummary]) -> Vec<BookAnomaly> {
+    summaries
+        .iter()
+        .filter_map(|summary| {
+            let bid = summary
+                .bid_price
+                .filter(|price| price.is_sign_positive() && !price.is_zero());
+            let ask = summary
+                .ask_price
+                .filter(|price| price.is_sign_positive() && !price.is_zero());
+            let kind = match (bid, ask, summary.mark_price) {
+                (Some(bid), Some(ask), _) if bid > ask => AnomalyKind::Crossed,
+                (Some(bid), Some(ask), _) if bid == ask => AnomalyKind::Locked,
+                (Some(bid), _, Some(mark)) if bid > mark => AnomalyKind::BidAboveMark,
+                (_, Some(ask), Some(mark)) if ask < mark => AnomalyKind::AskBelowMark,
+                _ => return None,
+            };
+            Some(BookAnomaly {
+                instrument_name: summary.instrument_name.clone(),
+                kind,
+                bid_price: summary.bid_price,
+                ask_price: summary.ask_price,
+                mark_price: summary.mark_price,
+            })
+        })
+        .collect()
+}
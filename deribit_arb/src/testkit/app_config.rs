@@ -0,0 +1,121 @@
+use crate::config::{AppConfig, Environment, ExecutionMode, OutputFormat, ReportFormat};
+use crate::model::{Currency, SettlementCurrency, StrategyFilter, StrategyKind};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// A dry-run testnet config scanning BTC USDC-settled listings for
+/// `strategies`: $50 minimum edge, no risk caps, no outputs beyond the
+/// table. Tests adjust the rest on the built [`AppConfig`].
+pub fn config(strategies: Vec<StrategyKind>) -> ConfigBuilder {
+    ConfigBuilder {
+        strategies,
+        min_edge_usd: dec!(50),
+        webhook_min_edge_usd: dec!(50),
+    }
+}
+
+/// See [`config`].
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    strategies: Vec<StrategyKind>,
+    min_edge_usd: Decimal,
+    webhook_min_edge_usd: Decimal,
+}
+
+impl ConfigBuilder {
+    pub fn min_edge_usd(mut self, min_edge_usd: Decimal) -> Self {
+        self.min_edge_usd = min_edge_usd;
+        self
+    }
+
+    pub fn webhook_min_edge_usd(mut self, webhook_min_edge_usd: Decimal) -> Self {
+        self.webhook_min_edge_usd = webhook_min_edge_usd;
+        self
+    }
+
+    pub fn build(self) -> AppConfig {
+        AppConfig {
+            environment: Environment::Testnet,
+            api_key: None,
+            api_secret: None,
+            currencies: vec![Currency::BTC],
+            settlements: vec![SettlementCurrency::Usdc],
+            dry_run: true,
+            max_ticket_usd: dec!(20000),
+            min_edge_usd: self.min_edge_usd,
+            min_edge_ratio: 1.5,
+            hold_to_expiry: false,
+            strategy_filter: StrategyFilter {
+                include: self.strategies,
+            },
+            max_concurrent_combos: 3,
+            min_depth_contracts: 1,
+            delta_hedge: true,
+            strategy_thresholds: Default::default(),
+            instrument_filter: Default::default(),
+            audit_log: None,
+            loop_interval_secs: None,
+            dedup_cooldown_secs: 300,
+            tui: false,
+            output: OutputFormat::Table,
+            webhook_url: None,
+            webhook_min_edge_usd: self.webhook_min_edge_usd,
+            report_dir: None,
+            report_format: ReportFormat::Html,
+            metrics_addr: None,
+            execution_mode: ExecutionMode::Taker,
+            maker_improve_ticks: 1,
+            maker_stale_secs: 10,
+            rfq_contracts: None,
+            rfq_wait_secs: 5,
+            rfq_execute: false,
+            max_currency_notional_usd: None,
+            max_net_delta_usd: None,
+            max_net_gamma_usd: None,
+            max_net_vega_usd: None,
+            daily_loss_limit_usd: None,
+            risk_state: None,
+            breaker_error_rate: 0.5,
+            breaker_stale_secs: 30,
+            breaker_index_bps: 50.0,
+            breaker_cooldown_secs: 300,
+            fee_tier: "default".into(),
+            fee_schedule: Default::default(),
+            chain_snapshot: None,
+            warm_start: false,
+            incremental_ms: None,
+            cancel_on_disconnect: true,
+            recheck_edge_fraction: None,
+            latency_budget_ms: 1500,
+            accounts: Vec::new(),
+            notifiers: Vec::new(),
+            diagnose_rejections: false,
+            view: Default::default(),
+            execute_top: 3,
+            record_dir: None,
+            record_layout: Default::default(),
+            record_snapshot_interval: None,
+            high_vol_threshold: None,
+            high_vol_edge_multiplier: 2.0,
+            adverse_window_secs: 10,
+            daemon_addr: None,
+            max_margin_utilization: None,
+            jelly_carry: true,
+            max_leg_skew_ms: None,
+            staleness_penalty_bps: 0.0,
+            max_index_divergence_bps: None,
+            sim_capital: None,
+            fee_report: None,
+            diff_min_change_usd: None,
+            min_annualized_return: None,
+            conflate_quotes: false,
+            combo_cache: None,
+            max_orders_per_minute: None,
+            max_combos_per_hour: None,
+            max_daily_notional_usd: None,
+            record: None,
+            replay: None,
+            post_trade_delay_secs: None,
+        }
+    }
+}
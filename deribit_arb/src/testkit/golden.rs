@@ -0,0 +1,89 @@
+use crate::model::{StrategyKind, StrategyOpportunity};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::path::Path;
+
+/// Set to rewrite golden files from the current output instead of
+/// comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+
+/// The reviewable part of a detected opportunity: what would trade, at what
+/// prices, and what it earns. IDs, timestamps and estimates are left out so
+/// a golden diff shows only detector behaviour.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GoldenOpportunity {
+    pub strategy: StrategyKind,
+    /// `"BUY 1 BTC_USDC-27DEC99-40000-C"`, in combo order.
+    pub legs: Vec<String>,
+    /// `"BTC_USDC-27DEC99-40000-C @ 1200 x 2"`, in combo order.
+    pub touches: Vec<String>,
+    pub size_contracts: Decimal,
+    pub total_cost: Decimal,
+    pub max_payout: Decimal,
+    pub fees_usd: Decimal,
+    pub net_edge_usd: Decimal,
+    pub notional_usd: Decimal,
+}
+
+impl From<&StrategyOpportunity> for GoldenOpportunity {
+    fn from(opportunity: &StrategyOpportunity) -> Self {
+        Self {
+            strategy: opportunity.strategy,
+            legs: opportunity
+                .legs
+                .iter()
+                .map(|leg| format!("{} {} {}", leg.side, leg.ratio, leg.instrument_name))
+                .collect(),
+            touches: opportunity
+                .touches
+                .iter()
+                .map(|touch| {
+                    format!(
+                        "{} @ {} x {}",
+                        touch.instrument_name,
+                        touch.price.normalize(),
+                        touch.size_contracts.normalize()
+                    )
+                })
+                .collect(),
+            size_contracts: opportunity.size_contracts.normalize(),
+            total_cost: opportunity.total_cost.normalize(),
+            max_payout: opportunity.max_payout.normalize(),
+            fees_usd: opportunity.fee_breakdown.total_usd.normalize(),
+            net_edge_usd: opportunity.net_edge_usd.normalize(),
+            notional_usd: opportunity.notional_usd.normalize(),
+        }
+    }
+}
+
+/// Compares `opportunities` with the golden JSON file at `path`, ignoring
+/// their order. With `UPDATE_GOLDEN` set, writes the file instead; review
+/// the diff before committing it.
+pub fn assert_golden(path: impl AsRef<Path>, opportunities: &[StrategyOpportunity]) {
+    let path = path.as_ref();
+    let mut golden: Vec<GoldenOpportunity> = opportunities.iter().map(Into::into).collect();
+    golden
+        .sort_by(|a, b| (a.strategy.to_string(), &a.legs).cmp(&(b.strategy.to_string(), &b.legs)));
+    let actual =
+        serde_json::to_string_pretty(&golden).expect("golden opportunities serialize") + "\n";
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).expect("creating golden directory");
+        }
+        std::fs::write(path, &actual)
+            .unwrap_or_else(|err| panic!("writing golden file {}: {err}", path.display()));
+        return;
+    }
+    let expected = std::fs::read_to_string(path).unwrap_or_else(|err| {
+        panic!(
+            "reading golden file {}: {err}; rerun with {UPDATE_GOLDEN_ENV}=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "opportunities differ from golden file {}; rerun with {UPDATE_GOLDEN_ENV}=1 to accept\n\
+         --- expected\n{expected}\n+++ actual\n{actual}",
+        path.display()
+    );
+}
@@ -0,0 +1,232 @@
+use crate::backtest::instrument_from_name;
+use crate::chain::OptionChain;
+use crate::model::{
+    format_strike, Currency, Instrument, InstrumentSnapshot, OptionKind, ParsedInstrumentName,
+    Quote, QuoteLevel, SettlementCurrency,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::str::FromStr;
+
+mod app_config;
+mod golden;
+
+pub use app_config::{config, ConfigBuilder};
+pub use golden::{assert_golden, GoldenOpportunity, UPDATE_GOLDEN_ENV};
+
+/// A touch: price and size in contracts.
+pub type Level = (Decimal, Decimal);
+
+/// Clock [`ChainBuilder`] stamps quotes with and golden tests scan at, so
+/// quote ages and days to expiry are the same on every run.
+pub fn fixed_now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2030, 1, 1, 8, 0, 0).unwrap()
+}
+
+/// A BTC, USDC-settled listing with unit contracts and a fresh two-sided
+/// quote at a $40,000 index. Expiry comes from `name` when it parses, else
+/// 30 days out.
+pub fn build_snapshot(
+    name: &str,
+    strike: Decimal,
+    option_kind: OptionKind,
+    best_bid: Level,
+    best_ask: Level,
+) -> InstrumentSnapshot {
+    let expiry = ParsedInstrumentName::from_str(name)
+        .ok()
+        .and_then(|parsed| parsed.expiry_date().ok())
+        .unwrap_or_else(|| Utc::now() + chrono::Duration::days(30));
+    InstrumentSnapshot {
+        instrument: Instrument {
+            instrument_name: name.to_string(),
+            currency: Currency::BTC,
+            is_usdc_settled: true,
+            is_combo: false,
+            option_kind,
+            strike,
+            expiry,
+            contract_size: Decimal::ONE,
+            settlement_currency: SettlementCurrency::Usdc,
+            tick_size: dec!(0.1),
+            min_trade_amount: Decimal::ONE,
+        },
+        quote: quote(Some(best_bid), Some(best_ask), dec!(40000), Utc::now()),
+        order_book: None,
+    }
+}
+
+fn quote(
+    bid: Option<Level>,
+    ask: Option<Level>,
+    index_price: Decimal,
+    timestamp: DateTime<Utc>,
+) -> Quote {
+    let level = |(price, amount): Level| QuoteLevel { price, amount };
+    Quote {
+        best_bid: bid.map(level),
+        best_ask: ask.map(level),
+        mark_iv: None,
+        bid_iv: None,
+        ask_iv: None,
+        interest_rate: None,
+        timestamp,
+        index_price,
+    }
+}
+
+/// Builds chains of one underlying and book for detector tests. Listings
+/// get the exchange metadata of
+/// [`instrument_from_name`](crate::backtest::instrument_from_name), quotes
+/// are stamped at [`fixed_now`] unless [`Self::quoted_at`] says otherwise,
+/// and expiries are Deribit date codes such as `"27DEC99"`.
+#[derive(Debug, Clone)]
+pub struct ChainBuilder {
+    currency: Currency,
+    settlement: SettlementCurrency,
+    index_price: Decimal,
+    quoted_at: DateTime<Utc>,
+    mark_iv: Option<f64>,
+    snapshots: Vec<InstrumentSnapshot>,
+}
+
+impl ChainBuilder {
+    pub fn new(currency: Currency, settlement: SettlementCurrency) -> Self {
+        Self {
+            currency,
+            settlement,
+            index_price: dec!(40000),
+            quoted_at: fixed_now(),
+            mark_iv: None,
+            snapshots: Vec::new(),
+        }
+    }
+
+    /// Index carried by quotes added from here on; $40,000 by default.
+    pub fn index_price(mut self, index_price: Decimal) -> Self {
+        self.index_price = index_price;
+        self
+    }
+
+    /// Timestamp of quotes added from here on.
+    pub fn quoted_at(mut self, quoted_at: DateTime<Utc>) -> Self {
+        self.quoted_at = quoted_at;
+        self
+    }
+
+    /// Mark IV, in percent, of quotes added from here on; needed for margin
+    /// and delta estimates.
+    pub fn mark_iv(mut self, mark_iv: f64) -> Self {
+        self.mark_iv = Some(mark_iv);
+        self
+    }
+
+    /// Deribit name of a listing in this builder's underlying and book.
+    pub fn name(&self, expiry: &str, strike: Decimal, option_kind: OptionKind) -> String {
+        let underlying = match self.settlement.stablecoin() {
+            Some(stablecoin) => format!("{}_{stablecoin}", self.currency),
+            None => self.currency.to_string(),
+        };
+        let kind = match option_kind {
+            OptionKind::Call => "C",
+            OptionKind::Put => "P",
+        };
+        format!("{underlying}-{expiry}-{}-{kind}", format_strike(strike))
+    }
+
+    /// One listing; either side of the quote may be missing.
+    pub fn option(
+        mut self,
+        expiry: &str,
+        strike: Decimal,
+        option_kind: OptionKind,
+        bid: Option<Level>,
+        ask: Option<Level>,
+    ) -> Self {
+        let name = self.name(expiry, strike, option_kind);
+        let instrument = instrument_from_name(&name)
+            .unwrap_or_else(|err| panic!("testkit listing {name}: {err}"));
+        let mut quote = quote(bid, ask, self.index_price, self.quoted_at);
+        quote.mark_iv = self.mark_iv;
+        self.snapshots.push(InstrumentSnapshot {
+            instrument,
+            quote,
+            order_book: None,
+        });
+        self
+    }
+
+    /// Listings of one expiry and option kind, one per `(strike, bid, ask)`:
+    /// the legs of verticals and butterflies.
+    pub fn ladder(
+        self,
+        expiry: &str,
+        option_kind: OptionKind,
+        rungs: &[(Decimal, Level, Level)],
+    ) -> Self {
+        rungs.iter().fold(self, |builder, &(strike, bid, ask)| {
+            builder.option(expiry, strike, option_kind, Some(bid), Some(ask))
+        })
+    }
+
+    /// The call and put at one strike and expiry, each as `(bid, ask)`: one
+    /// side of a box, or one expiry of a jelly roll.
+    pub fn strike_pair(
+        self,
+        expiry: &str,
+        strike: Decimal,
+        call: (Level, Level),
+        put: (Level, Level),
+    ) -> Self {
+        self.option(expiry, strike, OptionKind::Call, Some(call.0), Some(call.1))
+            .option(expiry, strike, OptionKind::Put, Some(put.0), Some(put.1))
+    }
+
+    /// Near and far listings of a calendar at one strike, each as
+    /// `(expiry, bid, ask)`.
+    pub fn calendar(
+        self,
+        strike: Decimal,
+        option_kind: OptionKind,
+        near: (&str, Level, Level),
+        far: (&str, Level, Level),
+    ) -> Self {
+        self.option(near.0, strike, option_kind, Some(near.1), Some(near.2))
+            .option(far.0, strike, option_kind, Some(far.1), Some(far.2))
+    }
+
+    /// A listing whose bid trades through its ask, as a stale or broken
+    /// book shows it. Panics unless the bid is above the ask.
+    pub fn crossed(
+        self,
+        expiry: &str,
+        strike: Decimal,
+        option_kind: OptionKind,
+        bid: Level,
+        ask: Level,
+    ) -> Self {
+        assert!(
+            bid.0 > ask.0,
+            "crossed book needs bid {} > ask {}",
+            bid.0,
+            ask.0
+        );
+        self.option(expiry, strike, option_kind, Some(bid), Some(ask))
+    }
+
+    pub fn build(self) -> Vec<InstrumentSnapshot> {
+        self.snapshots
+    }
+
+    /// The listings loaded into a live chain, quotes applied.
+    pub fn into_chain(self) -> OptionChain {
+        let chain = OptionChain::new();
+        for snapshot in self.snapshots {
+            let name = snapshot.instrument.instrument_name.clone();
+            chain.upsert_instrument(snapshot.instrument);
+            chain.update_quote(&name, snapshot.quote);
+        }
+        chain
+    }
+}
@@ -0,0 +1,282 @@
+use crate::chain::OptionChain;
+use crate::model::{Currency, StrategyKind, StrategyOpportunity};
+use crate::render::{format_decimal, format_strategy};
+use crate::risk::RiskSnapshot;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use parking_lot::Mutex;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table};
+use ratatui::Frame;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::error;
+
+const MAX_EXECUTIONS: usize = 50;
+const FRESH_QUOTES: usize = 12;
+const REDRAW_EVERY: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone)]
+pub struct ExecutionEntry {
+    pub at: DateTime<Utc>,
+    pub account: String,
+    pub strategy: StrategyKind,
+    pub currency: Currency,
+    pub outcome: String,
+}
+
+#[derive(Default)]
+struct DashboardState {
+    opportunities: Vec<StrategyOpportunity>,
+    executions: VecDeque<ExecutionEntry>,
+    risk: RiskSnapshot,
+    last_scan: Option<DateTime<Utc>>,
+    cycles: u64,
+}
+
+/// Interactive `--tui` view over the chain, the latest scan, risk state, and
+/// recent execution outcomes. Scan cycles push into it; a blocking thread
+/// owns the terminal and redraws on a fixed cadence.
+#[derive(Clone)]
+pub struct Dashboard {
+    chain: OptionChain,
+    state: Arc<Mutex<DashboardState>>,
+    closed: Arc<Notify>,
+}
+
+impl Dashboard {
+    pub fn new(chain: OptionChain) -> Self {
+        Self {
+            chain,
+            state: Arc::new(Mutex::new(DashboardState::default())),
+            closed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Replaces the live opportunity table with the latest scan.
+    pub fn update_scan(&self, mut opportunities: Vec<StrategyOpportunity>, risk: RiskSnapshot) {
+        opportunities.sort_by_key(|opp| std::cmp::Reverse(opp.net_edge_usd));
+        let mut state = self.state.lock();
+        state.opportunities = opportunities;
+        state.risk = risk;
+        state.last_scan = Some(Utc::now());
+        state.cycles += 1;
+    }
+
+    pub fn record_execution(&self, entry: ExecutionEntry) {
+        let mut state = self.state.lock();
+        state.executions.push_front(entry);
+        state.executions.truncate(MAX_EXECUTIONS);
+    }
+
+    /// Takes over the terminal on a blocking thread until the user quits.
+    pub fn spawn(&self) -> Result<()> {
+        let mut terminal = ratatui::try_init()?;
+        let dashboard = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let result = (|| -> Result<()> {
+                loop {
+                    terminal.draw(|frame| dashboard.draw(frame))?;
+                    if event::poll(REDRAW_EVERY)? {
+                        if let Event::Key(key) = event::read()? {
+                            let ctrl_c = key.modifiers.contains(KeyModifiers::CONTROL)
+                                && key.code == KeyCode::Char('c');
+                            if key.kind == KeyEventKind::Press
+                                && (ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc))
+                            {
+                                return Ok(());
+                            }
+                        }
+                    }
+                }
+            })();
+            ratatui::restore();
+            if let Err(err) = result {
+                error!(target: "tui", error = %err, "dashboard terminated");
+            }
+            dashboard.closed.notify_one();
+        });
+        Ok(())
+    }
+
+    /// Resolves once the user has closed the dashboard.
+    pub async fn closed(&self) {
+        self.closed.notified().await;
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let state = self.state.lock();
+        let stats = self.chain.stats();
+        let [header, body, footer] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Min(8),
+                Constraint::Length(FRESH_QUOTES as u16 + 3),
+            ])
+            .areas(frame.area());
+
+        let last_scan = state
+            .last_scan
+            .map(|ts| ts.format("%H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".into());
+        let summary = vec![
+            Line::from(format!(
+                "chain: {} instruments | {} quoted | {} fresh (10s) | bids {} asks {}",
+                stats.instrument_count,
+                stats.instruments_with_quotes,
+                stats.instruments_fresh_10s,
+                stats.bid_levels,
+                stats.ask_levels
+            )),
+            Line::from(format!(
+                "risk: {} live combos | EWMA PnL ${} | day PnL ${} | scans {} | last {} | q to quit",
+                state.risk.live_combos,
+                format_decimal(state.risk.ewma_pnl),
+                format_decimal(state.risk.daily_pnl),
+                state.cycles,
+                last_scan
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(summary)
+                .block(Block::default().borders(Borders::ALL).title("deribit_arb")),
+            header,
+        );
+
+        let rows = state.opportunities.iter().map(|opp| {
+            let expiries = opp
+                .expiry
+                .iter()
+                .map(|dt| dt.format("%d%b%y").to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            let strikes = opp
+                .strikes
+                .iter()
+                .map(|s| s.normalize().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            Row::new(vec![
+                format_strategy(opp.strategy).to_string(),
+                opp.currency.to_string(),
+                opp.settlement.to_string(),
+                expiries,
+                strikes,
+                format_decimal(opp.size_contracts),
+                format_decimal(opp.notional_usd),
+                format_decimal(opp.net_edge_usd),
+                format_decimal(opp.fee_breakdown.total_usd),
+                format!("{:.2}", opp.edge_bps),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(10),
+                Constraint::Length(5),
+                Constraint::Length(10),
+                Constraint::Length(16),
+                Constraint::Min(14),
+                Constraint::Length(8),
+                Constraint::Length(12),
+                Constraint::Length(12),
+                Constraint::Length(10),
+                Constraint::Length(9),
+            ],
+        )
+        .header(
+            Row::new(vec![
+                "Strategy",
+                "Ccy",
+                "Settle",
+                "Expiry",
+                "Strikes",
+                "Size",
+                "Notional $",
+                "Net Edge $",
+                "Fees $",
+                "Edge bps",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Opportunities ({})", state.opportunities.len())),
+        );
+        frame.render_widget(table, body);
+
+        let [quotes_area, executions_area] = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(footer);
+
+        let mut snapshot = self.chain.snapshot().instruments;
+        snapshot.retain(|inst| inst.quote.best_bid.is_some() || inst.quote.best_ask.is_some());
+        snapshot.sort_by_key(|inst| std::cmp::Reverse(inst.quote.timestamp));
+        let level = |level: Option<&crate::model::QuoteLevel>| {
+            level
+                .map(|l| format!("{}x{}", format_decimal(l.price), l.amount.normalize()))
+                .unwrap_or_else(|| "-".into())
+        };
+        let quote_rows = snapshot.iter().take(FRESH_QUOTES).map(|inst| {
+            Row::new(vec![
+                inst.instrument.instrument_name.clone(),
+                level(inst.quote.best_bid.as_ref()),
+                level(inst.quote.best_ask.as_ref()),
+                inst.quote.timestamp.format("%H:%M:%S").to_string(),
+            ])
+        });
+        let quotes = Table::new(
+            quote_rows,
+            [
+                Constraint::Min(22),
+                Constraint::Length(14),
+                Constraint::Length(14),
+                Constraint::Length(8),
+            ],
+        )
+        .header(Row::new(vec!["Instrument", "Bid", "Ask", "At"]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Freshest quotes"),
+        );
+        frame.render_widget(quotes, quotes_area);
+
+        let execution_rows = state.executions.iter().map(|entry| {
+            Row::new(vec![
+                entry.at.format("%H:%M:%S").to_string(),
+                entry.account.clone(),
+                format_strategy(entry.strategy).to_string(),
+                entry.currency.to_string(),
+                entry.outcome.clone(),
+            ])
+        });
+        let executions = Table::new(
+            execution_rows,
+            [
+                Constraint::Length(8),
+                Constraint::Length(12),
+                Constraint::Length(10),
+                Constraint::Length(5),
+                Constraint::Min(20),
+            ],
+        )
+        .header(Row::new(vec![
+            "At", "Account", "Strategy", "Ccy", "Outcome",
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Recent executions"),
+        );
+        frame.render_widget(executions, executions_area);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::model::StrategyOpportunity;
+use crate::render::format_decimal;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::{info, warn};
+use url::Url;
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    /// Slack-compatible summary line; other consumers can read `opportunity`.
+    text: String,
+    opportunity: &'a StrategyOpportunity,
+}
+
+/// POSTs opportunities at or above a net-edge threshold to a webhook URL.
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    client: Client,
+    url: Url,
+    min_edge_usd: Decimal,
+}
+
+impl WebhookSink {
+    pub fn new(url: Url, min_edge_usd: Decimal) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .context("building webhook client")?;
+        Ok(Self {
+            client,
+            url,
+            min_edge_usd,
+        })
+    }
+
+    pub fn summary(opportunity: &StrategyOpportunity) -> String {
+        let legs = opportunity
+            .legs
+            .iter()
+            .map(|leg| format!("{} {}", leg.side, leg.instrument_name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "{} {} {}: net edge ${} on ${} notional ({:.1} bps) – {}",
+            opportunity.strategy,
+            opportunity.currency,
+            opportunity.settlement,
+            format_decimal(opportunity.net_edge_usd),
+            format_decimal(opportunity.notional_usd),
+            opportunity.edge_bps,
+            legs
+        )
+    }
+
+    /// Sends every qualifying opportunity; returns how many were delivered.
+    /// Individual delivery failures are logged and skipped.
+    pub async fn publish(&self, opportunities: &[StrategyOpportunity]) -> usize {
+        let mut delivered = 0;
+        for opportunity in opportunities
+            .iter()
+            .filter(|opp| opp.net_edge_usd >= self.min_edge_usd)
+        {
+            let payload = WebhookPayload {
+                text: Self::summary(opportunity),
+                opportunity,
+            };
+            let result = self
+                .client
+                .post(self.url.clone())
+                .json(&payload)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => delivered += 1,
+                Err(err) => {
+                    warn!(target: "webhook", error = %err, "failed to deliver opportunity");
+                }
+            }
+        }
+        if delivered > 0 {
+            info!(target: "webhook", delivered, "posted opportunities");
+        }
+        delivered
+    }
+}
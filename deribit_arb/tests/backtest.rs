@@ -0,0 +1,448 @@
+use deribit_arb::backtest::{
+    self, instrument_from_name, BacktestWindow, ExpiryDelivery, ResearchData, SnapshotRecorder,
+};
+use deribit_arb::client::WsRecorder;
+use deribit_arb::config::AppConfig;
+use deribit_arb::detect::DetectorSuite;
+use deribit_arb::ledger::SimulatedLedger;
+use deribit_arb::model::{
+    ChainSnapshot, Currency, InstrumentSnapshot, Quote, QuoteLevel, SettlementCurrency,
+    StrategyKind,
+};
+use deribit_arb::testkit;
+use optstore::block::BlockLayout;
+use optstore::registry::InstrumentRegistry;
+use optstore::schema::event;
+use optstore::{InstrumentDictionary, Tick, TickReader, TickWriter};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Backtests book every capture, so any positive edge counts.
+fn base_config(strategies: Vec<StrategyKind>) -> AppConfig {
+    testkit::config(strategies)
+        .min_edge_usd(dec!(1))
+        .webhook_min_edge_usd(dec!(1))
+        .build()
+}
+
+fn book_tick(ts_ns: u64, instrument_id: u32, bid: i64, ask: i64) -> Tick {
+    const SCALE: i64 = 1_000_000;
+    Tick {
+        ts_ns,
+        instrument_id,
+        event: event::BOOK,
+        price_fp: 40_000 * SCALE,
+        size: 0,
+        bid_px_fp: [bid * SCALE, 0, 0, 0],
+        ask_px_fp: [ask * SCALE, 0, 0, 0],
+        bid_sz: [10_000, 0, 0, 0],
+        ask_sz: [10_000, 0, 0, 0],
+        flags: 0,
+    }
+}
+
+#[test]
+fn parses_instrument_metadata_from_names() {
+    let coin = instrument_from_name("BTC-29MAR24-40000-C").expect("coin option");
+    assert_eq!(coin.settlement_currency, SettlementCurrency::Coin);
+    assert_eq!(coin.contract_size, Decimal::ONE);
+
+    let linear = instrument_from_name("BTC_USDC-29MAR24-45000-P").expect("usdc option");
+    assert_eq!(linear.settlement_currency, SettlementCurrency::Usdc);
+    assert_eq!(linear.currency, Currency::BTC);
+    assert_eq!(linear.strike, dec!(45000));
+    assert_eq!(linear.instrument_name, "BTC_USDC-29MAR24-45000-P");
+
+    let eurr = instrument_from_name("ETH_EURR-29MAR24-3000-C").expect("eurr option");
+    assert_eq!(eurr.settlement_currency, SettlementCurrency::Eurr);
+    assert_eq!(eurr.contract_size, dec!(0.1));
+    assert!(instrument_from_name("BTC_DAI-29MAR24-40000-C").is_err());
+
+    assert!(instrument_from_name("BTC-PERPETUAL").is_err());
+}
+
+#[test]
+fn backtest_replays_book_ticks_and_dedups_captures() {
+    let data = std::env::temp_dir().join(format!("deribit_arb_backtest_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&data);
+    let path = optstore::util::day_to_path(&data, "2024-03-01");
+    let start_ns = chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+        .unwrap()
+        .timestamp_nanos_opt()
+        .unwrap() as u64;
+
+    let mut dictionary = InstrumentDictionary::default();
+    let mut writer = TickWriter::append(&path).expect("open writer");
+    for (name, bid, ask) in [
+        ("BTC_USDC-29MAR24-40000-C", 1800, 2000),
+        ("BTC_USDC-29MAR24-45000-C", 1500, 1700),
+        ("BTC_USDC-29MAR24-40000-P", 1500, 1700),
+        ("BTC_USDC-29MAR24-45000-P", 1800, 2000),
+        // Coin-settled books are outside the configured settlements.
+        ("BTC-29MAR24-40000-C", 1, 2),
+    ] {
+        let id = dictionary.insert(name);
+        writer
+            .write(&book_tick(start_ns + 10_000_000_000, id, bid, ask))
+            .expect("write tick");
+    }
+    writer.finish().expect("flush");
+    dictionary.store_for(&path).expect("store dictionary");
+
+    let mut config = base_config(vec![StrategyKind::Box]);
+    config.sim_capital = Some(dec!(1000000));
+    let window =
+        BacktestWindow::parse("2024-03-01T00:00:00Z", "2024-03-01T00:05:00Z", 60).expect("window");
+    let report = backtest::run(&config, &data, window).expect("backtest");
+    std::fs::remove_dir_all(&data).ok();
+
+    assert_eq!(report.ticks_replayed, 4);
+    assert_eq!(report.points.len(), 5);
+    assert!(report.points.iter().all(|point| point.instruments == 4));
+    assert!(report.points.iter().all(|point| point.opportunities > 0));
+    // The same box persists through every scan but is captured once per cooldown.
+    assert_eq!(report.total_captures(), 1);
+    assert_eq!(report.by_strategy[0].strategy, StrategyKind::Box);
+    assert!(report.total_edge_usd() > Decimal::ZERO);
+    assert_eq!(report.points[0].fresh_edge_usd, report.total_edge_usd());
+    // The box expires after the window, so its edge is still pending.
+    let ledger = report.ledger.as_ref().expect("sim capital set");
+    assert_eq!((ledger.fills, ledger.open_combos), (1, 1));
+    assert_eq!(ledger.unrealized_pnl_usd, report.total_edge_usd());
+    assert_eq!(ledger.realized_pnl_usd, Decimal::ZERO);
+    assert!(ledger.locked_usd > Decimal::ZERO && ledger.utilization > 0.0);
+}
+
+fn ticker_message(name: &str, bid: f64, ask: f64, at: chrono::DateTime<chrono::Utc>) -> String {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "subscription",
+        "params": {
+            "channel": format!("ticker.{name}.100ms"),
+            "data": {
+                "instrument_name": name,
+                "best_bid_price": bid,
+                "best_bid_amount": 10.0,
+                "best_ask_price": ask,
+                "best_ask_amount": 10.0,
+                "index_price": 40000.0,
+                "timestamp": at.timestamp_millis(),
+            }
+        }
+    })
+    .to_string()
+}
+
+#[test]
+fn ws_capture_replays_deterministically() {
+    let path = std::env::temp_dir().join(format!(
+        "deribit_arb_capture_{}.jsonl.zst",
+        std::process::id()
+    ));
+    let start = chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+        .unwrap()
+        .to_utc();
+    let recorder = WsRecorder::create(&path).expect("create capture");
+    let first = start + chrono::Duration::seconds(10);
+    recorder.record(first, r#"{"jsonrpc":"2.0","id":7,"result":["ticker"]}"#);
+    for (name, bid, ask) in [
+        ("BTC_USDC-29MAR24-40000-C", 1800.0, 2000.0),
+        ("BTC_USDC-29MAR24-45000-C", 1500.0, 1700.0),
+        ("BTC_USDC-29MAR24-40000-P", 1500.0, 1700.0),
+        ("BTC_USDC-29MAR24-45000-P", 1800.0, 2000.0),
+        // Coin-settled books are outside the configured settlements.
+        ("BTC-29MAR24-40000-C", 0.01, 0.02),
+    ] {
+        recorder.record(first, &ticker_message(name, bid, ask, first));
+    }
+    recorder.record(
+        start + chrono::Duration::seconds(100),
+        r#"{"jsonrpc":"2.0","method":"heartbeat","params":{"type":"heartbeat"}}"#,
+    );
+    let last = start + chrono::Duration::seconds(200);
+    recorder.record(
+        last,
+        &ticker_message("BTC_USDC-29MAR24-45000-P", 1850.0, 2000.0, last),
+    );
+    assert_eq!(recorder.messages(), 8);
+    recorder.finish().expect("finish capture");
+
+    let config = base_config(vec![StrategyKind::Box]);
+    let step = chrono::Duration::seconds(60);
+    let report = backtest::replay_capture(&config, &path, step).expect("replay");
+    let again = backtest::replay_capture(&config, &path, step).expect("replay again");
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(report, again);
+    assert_eq!((report.window.from, report.window.to), (first, last));
+    assert_eq!(report.ticks_replayed, 5);
+    // Scans run on recorded time: 70s, 130s and 190s after the hour.
+    let scanned: Vec<_> = report.points.iter().map(|point| point.at).collect();
+    assert_eq!(
+        scanned,
+        [70, 130, 190].map(|secs| start + chrono::Duration::seconds(secs))
+    );
+    assert!(report.points.iter().all(|point| point.instruments == 4));
+    assert_eq!(report.total_captures(), 1);
+    assert_eq!(report.by_strategy[0].strategy, StrategyKind::Box);
+}
+
+#[test]
+fn simulated_ledger_locks_capital_until_expiry() {
+    let at = chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:10Z")
+        .unwrap()
+        .to_utc();
+    let legs: Vec<InstrumentSnapshot> = [
+        ("BTC_USDC-29MAR24-40000-C", dec!(1800), dec!(2000)),
+        ("BTC_USDC-29MAR24-45000-C", dec!(1500), dec!(1700)),
+        ("BTC_USDC-29MAR24-40000-P", dec!(1500), dec!(1700)),
+        ("BTC_USDC-29MAR24-45000-P", dec!(1800), dec!(2000)),
+    ]
+    .into_iter()
+    .map(|(name, bid, ask)| recorded_leg(name, bid, ask, at))
+    .collect();
+    let config = base_config(vec![StrategyKind::Box]);
+    let opportunity = DetectorSuite::new(&config).scan_at(&legs, at)[0].clone();
+    let capital = opportunity.notional_usd;
+
+    let mut ledger = SimulatedLedger::new(capital);
+    assert!(ledger.fill(&opportunity, dec!(25), at));
+    // Every dollar is locked, so a second fill must wait for expiry.
+    assert!(!ledger.fill(&opportunity, dec!(25), at));
+    let open = ledger.summary();
+    assert_eq!((open.fills, open.skipped_for_capital), (1, 1));
+    assert_eq!(open.cash_usd, capital - open.locked_usd);
+    assert_eq!(open.turnover_usd, open.locked_usd);
+
+    let expiry = opportunity.expiry[0];
+    // Fills never settle on their own: the expired combo still holds its
+    // capital until `settle` releases it.
+    assert!(!ledger.fill(&opportunity, dec!(-5), expiry));
+    assert_eq!(ledger.settle(expiry), vec![dec!(25)]);
+    assert!(ledger.fill(&opportunity, dec!(-5), expiry));
+    let settled = ledger.summary();
+    assert_eq!((settled.settled, settled.open_combos), (1, 1));
+    assert_eq!(settled.skipped_for_capital, 2);
+    assert_eq!(settled.realized_pnl_usd, dec!(25));
+    assert_eq!(settled.unrealized_pnl_usd, dec!(-5));
+    assert_eq!(settled.turnover_usd, open.locked_usd * dec!(2));
+}
+
+fn recorded_leg(
+    name: &str,
+    bid: Decimal,
+    ask: Decimal,
+    at: chrono::DateTime<chrono::Utc>,
+) -> InstrumentSnapshot {
+    let level = |price| {
+        Some(QuoteLevel {
+            price,
+            amount: dec!(10),
+        })
+    };
+    InstrumentSnapshot {
+        instrument: instrument_from_name(name).expect("instrument"),
+        quote: Quote {
+            best_bid: level(bid),
+            best_ask: level(ask),
+            mark_iv: None,
+            bid_iv: None,
+            ask_iv: None,
+            interest_rate: None,
+            timestamp: at,
+            index_price: dec!(40000),
+        },
+        order_book: None,
+    }
+}
+
+#[test]
+fn recorded_scan_snapshots_replay_in_backtest() {
+    let data = std::env::temp_dir().join(format!("deribit_arb_record_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&data);
+    let at = chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:10Z")
+        .unwrap()
+        .to_utc();
+    let mut instruments: Vec<InstrumentSnapshot> = [
+        ("BTC_USDC-29MAR24-40000-C", dec!(1800), dec!(2000)),
+        ("BTC_USDC-29MAR24-45000-C", dec!(1500), dec!(1700)),
+        ("BTC_USDC-29MAR24-40000-P", dec!(1500), dec!(1700)),
+        ("BTC_USDC-29MAR24-45000-P", dec!(1800), dec!(2000)),
+    ]
+    .into_iter()
+    .map(|(name, bid, ask)| recorded_leg(name, bid, ask, at))
+    .collect();
+
+    let layout = BlockLayout::new(2, 1 << 20).expect("layout");
+    let mut recorder = SnapshotRecorder::new(data.clone(), layout);
+    let snapshot = |instruments: &[InstrumentSnapshot]| ChainSnapshot {
+        timestamp: at,
+        instruments: instruments.to_vec(),
+    };
+    assert_eq!(recorder.record(&snapshot(&instruments)).expect("record"), 4);
+    // Unchanged quotes are not written twice.
+    assert_eq!(recorder.record(&snapshot(&instruments)).expect("record"), 0);
+    instruments[0].quote.best_ask = Some(QuoteLevel {
+        price: dec!(1950),
+        amount: dec!(10),
+    });
+    instruments[0].quote.timestamp = at + chrono::Duration::seconds(90);
+    assert_eq!(recorder.record(&snapshot(&instruments)).expect("record"), 1);
+    let day = optstore::util::day_to_path(&data, &at.format("%Y-%m-%d").to_string());
+    let dictionary = InstrumentDictionary::load_for(&day).expect("dictionary");
+    assert_eq!(TickReader::open(&day).expect("reader").layout(), layout);
+    let leg = &instruments[0].instrument;
+    let id = dictionary.id(&leg.instrument_name).expect("recorded leg");
+    let registry = InstrumentRegistry::load(&data).expect("registry");
+    assert_eq!(registry.get(&leg.instrument_name), Some(id));
+    assert_eq!(
+        dictionary.contract_size(id),
+        leg.contract_size.to_f64().unwrap()
+    );
+
+    let config = base_config(vec![StrategyKind::Box]);
+    let window =
+        BacktestWindow::parse("2024-03-01T00:00:00Z", "2024-03-01T00:05:00Z", 60).expect("window");
+    let report = backtest::run(&config, &data, window).expect("backtest");
+    std::fs::remove_dir_all(&data).ok();
+
+    assert_eq!(report.ticks_replayed, 5);
+    assert!(report.points.iter().all(|point| point.instruments == 4));
+    assert!(report.points.iter().all(|point| point.opportunities > 0));
+    assert!(report.total_edge_usd() > Decimal::ZERO);
+}
+
+#[test]
+fn recorded_book_stream_replays_in_backtest() {
+    let data = std::env::temp_dir().join(format!("deribit_arb_book_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&data);
+    let at = chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:10Z")
+        .unwrap()
+        .to_utc();
+    let mut instruments: Vec<InstrumentSnapshot> = [
+        ("BTC_USDC-29MAR24-40000-C", dec!(1800), dec!(2000)),
+        ("BTC_USDC-29MAR24-45000-C", dec!(1500), dec!(1700)),
+        ("BTC_USDC-29MAR24-40000-P", dec!(1500), dec!(1700)),
+        ("BTC_USDC-29MAR24-45000-P", dec!(1800), dec!(2000)),
+    ]
+    .into_iter()
+    .map(|(name, bid, ask)| recorded_leg(name, bid, ask, at))
+    .collect();
+
+    let mut recorder =
+        SnapshotRecorder::new(data.clone(), BlockLayout::default()).with_book_stream(2);
+    let snapshot = |instruments: &[InstrumentSnapshot]| ChainSnapshot {
+        timestamp: at,
+        instruments: instruments.to_vec(),
+    };
+    assert_eq!(recorder.record(&snapshot(&instruments)).expect("record"), 4);
+    for (step, ask) in [dec!(1950), dec!(1900), dec!(1850)].into_iter().enumerate() {
+        instruments[0].quote.best_ask = Some(QuoteLevel {
+            price: ask,
+            amount: dec!(10),
+        });
+        instruments[0].quote.timestamp = at + chrono::Duration::seconds(30 * (step as i64 + 1));
+        assert_eq!(recorder.record(&snapshot(&instruments)).expect("record"), 1);
+    }
+    drop(recorder);
+    let day = optstore::util::day_to_path(&data, &at.format("%Y-%m-%d").to_string());
+    assert!(!day.exists());
+    let book = optstore::book::BookReader::open(&optstore::book::book_path(&day)).expect("book");
+    assert_eq!(book.snapshot_interval(), 2);
+    let last = book.last().expect("ticks").expect("tick");
+    assert_eq!(
+        last.ask_px_fp[0],
+        1850 * 10i64.pow(optstore::schema::PRICE_DECIMALS)
+    );
+
+    let config = base_config(vec![StrategyKind::Box]);
+    let window =
+        BacktestWindow::parse("2024-03-01T00:00:00Z", "2024-03-01T00:05:00Z", 60).expect("window");
+    let report = backtest::run(&config, &data, window).expect("backtest");
+    std::fs::remove_dir_all(&data).ok();
+
+    assert_eq!(report.ticks_replayed, 7);
+    assert!(report.points.iter().all(|point| point.instruments == 4));
+}
+
+#[test]
+fn backtest_window_includes_whole_end_day() {
+    let window = BacktestWindow::parse("2024-03-01", "2024-03-02", 60).expect("window");
+    assert_eq!(window.to - window.from, chrono::Duration::days(2));
+    assert!(BacktestWindow::parse("2024-03-02", "2024-03-01", 60).is_err());
+}
+
+#[test]
+fn research_history_overrides_listing_metadata_and_reports_deliveries() {
+    let data = std::env::temp_dir().join(format!("deribit_arb_research_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&data);
+    let path = optstore::util::day_to_path(&data, "2024-03-29");
+    let start_ns = chrono::DateTime::parse_from_rfc3339("2024-03-29T00:00:00Z")
+        .unwrap()
+        .timestamp_nanos_opt()
+        .unwrap() as u64;
+    let legs = [
+        ("BTC_USDC-29MAR24-40000-C", 1800, 2000),
+        ("BTC_USDC-29MAR24-45000-C", 1500, 1700),
+        ("BTC_USDC-29MAR24-40000-P", 1500, 1700),
+        ("BTC_USDC-29MAR24-45000-P", 1800, 2000),
+    ];
+    let mut dictionary = InstrumentDictionary::default();
+    let mut writer = TickWriter::append(&path).expect("open writer");
+    for (name, bid, ask) in legs {
+        let id = dictionary.insert(name);
+        writer
+            .write(&book_tick(start_ns + 10_000_000_000, id, bid, ask))
+            .expect("write tick");
+    }
+    writer.finish().expect("flush");
+    dictionary.store_for(&path).expect("store dictionary");
+
+    // The exchange listed these with twice the default contract size.
+    let mut research = ResearchData::default();
+    research.insert_instruments(legs.iter().map(|(name, _, _)| {
+        let mut instrument = instrument_from_name(name).expect("instrument");
+        instrument.contract_size = dec!(0.02);
+        instrument
+    }));
+    research.insert_deliveries(
+        Currency::BTC,
+        [
+            (
+                chrono::NaiveDate::from_ymd_opt(2024, 3, 28).unwrap(),
+                dec!(69000),
+            ),
+            (
+                chrono::NaiveDate::from_ymd_opt(2024, 3, 29).unwrap(),
+                dec!(70000),
+            ),
+        ],
+    );
+
+    let mut config = base_config(vec![StrategyKind::Box]);
+    config.dedup_cooldown_secs = 86_400;
+    let window =
+        BacktestWindow::parse("2024-03-29T00:00:00Z", "2024-03-29T09:00:00Z", 600).expect("window");
+    let plain = backtest::run(&config, &data, window).expect("backtest");
+    let report = backtest::run_with(&config, &data, window, Some(&research)).expect("backtest");
+    std::fs::remove_dir_all(&data).ok();
+
+    assert!(plain.deliveries.is_empty());
+    assert_eq!(report.total_captures(), 1);
+    assert_eq!(report.points[0].instruments, 4);
+    assert_ne!(report.total_edge_usd(), plain.total_edge_usd());
+    // The box expires at 08:00 and settles at that day's delivery price;
+    // the scan after expiry no longer sees it.
+    assert_eq!(
+        report.deliveries,
+        vec![ExpiryDelivery {
+            currency: Currency::BTC,
+            expiry: chrono::DateTime::parse_from_rfc3339("2024-03-29T08:00:00Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            delivery_price: dec!(70000),
+        }]
+    );
+    assert_eq!(report.points.last().unwrap().instruments, 0);
+}
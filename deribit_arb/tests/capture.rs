@@ -0,0 +1,124 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use deribit_arb::capture::{self, CaptureHealth, ListingChange};
+use deribit_arb::client::{WsCapture, WsRecorder};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::time::SystemTime;
+
+fn at(hour: u32, minute: u32, day: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 6, day, hour, minute, 0).unwrap()
+}
+
+fn scratch(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("deribit_arb_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn names(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn daily_capture_rotates_at_midnight_utc() {
+    let dir = scratch("capture_rotate");
+    let recorder = WsRecorder::daily(&dir, at(23, 58, 1)).unwrap();
+    recorder.record(at(23, 59, 1), "{\"first\":1}");
+    recorder.record(at(0, 1, 2), "{\"second\":1}");
+    recorder.record(at(0, 2, 2), "{\"second\":2}");
+    recorder.finish().unwrap();
+
+    let first = dir.join("2025-06-01.jsonl.zst");
+    let second = dir.join("2025-06-02.jsonl.zst");
+    assert_eq!(recorder.path(), second);
+    assert_eq!(recorder.messages(), 2);
+    let raw = |path| -> Vec<String> {
+        WsCapture::open(path)
+            .unwrap()
+            .map(|message| message.unwrap().raw)
+            .collect()
+    };
+    assert_eq!(raw(&first), vec!["{\"first\":1}"]);
+    assert_eq!(raw(&second), vec!["{\"second\":1}", "{\"second\":2}"]);
+
+    // A restart on a captured day starts a new file instead of appending.
+    let restarted = WsRecorder::daily(&dir, at(8, 0, 2)).unwrap();
+    assert_eq!(restarted.path(), dir.join("2025-06-02.1.jsonl.zst"));
+    restarted.finish().unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn prune_removes_only_expired_captures() {
+    let dir = scratch("capture_prune");
+    let now = SystemTime::now();
+    let day = std::time::Duration::from_secs(86_400);
+    for (name, age_days) in [
+        ("2025-06-01.jsonl.zst", 10),
+        ("2025-06-08.jsonl.zst", 3),
+        ("2025-06-09.jsonl.zst", 10),
+        ("notes.txt", 10),
+    ] {
+        let file = File::create(dir.join(name)).unwrap();
+        file.set_modified(now - day * age_days).unwrap();
+    }
+
+    // The file being written stays, however old.
+    let current = dir.join("2025-06-09.jsonl.zst");
+    let removed = capture::prune(&dir, day * 7, now, &current).unwrap();
+    assert_eq!(removed, vec![dir.join("2025-06-01.jsonl.zst")]);
+    assert!(current.exists());
+    assert!(dir.join("2025-06-08.jsonl.zst").exists());
+    assert!(dir.join("notes.txt").exists());
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[tokio::test]
+async fn health_reports_last_tick_age_per_instrument() {
+    let dir = scratch("capture_health");
+    let now = Utc::now();
+    let recorder = WsRecorder::daily(&dir, now).unwrap();
+    let health = CaptureHealth::new(&names(&["BTC-27JUN25-60000-C", "BTC-27JUN25-60000-P"]));
+    health.tick("BTC-27JUN25-60000-C", now - Duration::seconds(3));
+    health.tick("ETH-27JUN25-3000-C", now);
+
+    let report = health.report(&recorder, now);
+    assert_eq!(report.subscribed, 2);
+    assert_eq!(report.last_tick_age_ms, Some(3_000));
+    assert_eq!(report.instruments[0].instrument, "BTC-27JUN25-60000-P");
+    assert_eq!(report.instruments[0].last_tick_age_ms, None);
+    assert_eq!(report.instruments[1].last_tick_age_ms, Some(3_000));
+
+    // A new expiry is listed and the old put expires.
+    let before = names(&["BTC-27JUN25-60000-C", "BTC-27JUN25-60000-P"]);
+    let after = names(&["BTC-27JUN25-60000-C", "BTC-4JUL25-60000-C"]);
+    let change = ListingChange::between(&before, &after);
+    assert_eq!(change.added, vec!["BTC-4JUL25-60000-C"]);
+    assert_eq!(change.removed, vec!["BTC-27JUN25-60000-P"]);
+    health.resubscribe(&after);
+
+    let addr = capture::serve("127.0.0.1:0".parse().unwrap(), health, recorder.clone())
+        .await
+        .expect("serve");
+    let body: serde_json::Value = reqwest::get(format!("http://{addr}/health"))
+        .await
+        .expect("request")
+        .json()
+        .await
+        .expect("json");
+    assert_eq!(body["restarts"], 1);
+    assert_eq!(body["subscribed"], 2);
+    assert_eq!(body["instruments"][0]["instrument"], "BTC-4JUL25-60000-C");
+    assert!(body["instruments"][0]["last_tick_age_ms"].is_null());
+    assert_eq!(body["instruments"][1]["instrument"], "BTC-27JUN25-60000-C");
+    assert!(body["instruments"][1]["last_tick_age_ms"].as_i64().unwrap() >= 3_000);
+    assert_eq!(body["file"], recorder.path().display().to_string());
+
+    let missing = reqwest::get(format!("http://{addr}/other"))
+        .await
+        .expect("request");
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    recorder.finish().unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+}
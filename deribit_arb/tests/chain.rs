@@ -0,0 +1,202 @@
+use chrono::{Duration, Utc};
+use deribit_arb::backtest::instrument_from_name;
+use deribit_arb::chain::{OptionChain, QuoteConflator};
+use deribit_arb::model::{Currency, Quote, QuoteLevel};
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+
+#[test]
+fn chain_snapshot_round_trips_and_drops_expired() {
+    let path = std::env::temp_dir().join(format!("deribit_arb_chain_{}.json", std::process::id()));
+    let chain = OptionChain::new();
+    let live = instrument_from_name("BTC_USDC-25DEC99-40000-C").expect("instrument");
+    let expired = instrument_from_name("BTC_USDC-29MAR24-40000-C").expect("instrument");
+    let quoted_at = Utc::now() - Duration::minutes(5);
+    chain.upsert_instrument(live.clone());
+    chain.upsert_instrument(expired);
+    chain.update_quote(
+        &live.instrument_name,
+        Quote {
+            best_bid: Some(QuoteLevel {
+                price: dec!(1800),
+                amount: dec!(2),
+            }),
+            best_ask: None,
+            mark_iv: Some(55.0),
+            bid_iv: None,
+            ask_iv: None,
+            interest_rate: None,
+            timestamp: quoted_at,
+            index_price: dec!(40000),
+        },
+    );
+
+    chain.save(&path).expect("save");
+    let restored = OptionChain::load(&path);
+    std::fs::remove_file(&path).ok();
+    let snapshot = restored.expect("load").snapshot();
+
+    assert_eq!(snapshot.instruments.len(), 1);
+    let inst = &snapshot.instruments[0];
+    assert_eq!(inst.instrument, live);
+    assert_eq!(
+        inst.quote.best_bid.as_ref().map(|l| l.price),
+        Some(dec!(1800))
+    );
+    // Restored quotes keep their age rather than looking fresh.
+    assert_eq!(inst.quote.timestamp, quoted_at);
+}
+
+#[test]
+fn chain_evicts_expired_and_indexes_slices() {
+    let chain = OptionChain::new();
+    let names = [
+        "BTC_USDC-25DEC98-40000-C",
+        "BTC_USDC-25DEC98-45000-C",
+        "BTC_USDC-25DEC99-40000-P",
+        "ETH_USDC-25DEC98-2000-C",
+    ];
+    for name in names {
+        chain.upsert_instrument(instrument_from_name(name).expect("instrument"));
+    }
+    let near = instrument_from_name(names[0]).expect("instrument");
+    let far = instrument_from_name(names[2]).expect("instrument");
+
+    let mut expiry: Vec<_> = chain
+        .expiry_slice(Currency::BTC, near.expiry)
+        .into_iter()
+        .map(|inst| inst.instrument.instrument_name)
+        .collect();
+    expiry.sort();
+    assert_eq!(expiry, names[..2]);
+    assert_eq!(chain.strike_slice(Currency::BTC, dec!(40000)).len(), 2);
+    assert_eq!(chain.strike_slice(Currency::ETH, dec!(2000)).len(), 1);
+
+    // A quote stamped after the near expiry evicts it before applying.
+    let after_near = near.expiry + Duration::hours(1);
+    chain.update_quote(
+        &far.instrument_name,
+        Quote {
+            best_bid: None,
+            best_ask: None,
+            mark_iv: None,
+            bid_iv: None,
+            ask_iv: None,
+            interest_rate: None,
+            timestamp: after_near,
+            index_price: dec!(40000),
+        },
+    );
+    assert_eq!(chain.len(), 1);
+    assert!(chain.expiry_slice(Currency::BTC, near.expiry).is_empty());
+    let strike = chain.strike_slice(Currency::BTC, dec!(40000));
+    assert_eq!(strike.len(), 1);
+    assert_eq!(strike[0].quote.timestamp, after_near);
+    assert_eq!(chain.strike_keys(), vec![(Currency::BTC, dec!(40000))]);
+
+    assert_eq!(chain.evict_expired(far.expiry), 1);
+    assert!(chain.is_empty());
+    assert!(chain.expiry_keys().is_empty());
+}
+
+#[test]
+fn conflator_keeps_latest_quote_until_flushed() {
+    let chain = OptionChain::new();
+    let live = instrument_from_name("BTC_USDC-25DEC99-40000-C").expect("instrument");
+    chain.upsert_instrument(live.clone());
+    chain.take_dirty();
+    let conflator = QuoteConflator::new();
+    let quoted_at = Utc::now();
+    for (offset, bid) in [dec!(1700), dec!(1750), dec!(1800)].into_iter().enumerate() {
+        conflator.push_quote(
+            live.instrument_name.clone(),
+            Quote {
+                best_bid: Some(QuoteLevel {
+                    price: bid,
+                    amount: dec!(1),
+                }),
+                best_ask: None,
+                mark_iv: None,
+                bid_iv: None,
+                ask_iv: None,
+                interest_rate: None,
+                timestamp: quoted_at + Duration::milliseconds(100 * offset as i64),
+                index_price: dec!(40000),
+            },
+        );
+    }
+
+    // Nothing reaches the chain before the flush.
+    assert!(chain.take_dirty().is_empty());
+    let stats = conflator.flush(&chain);
+    assert_eq!(stats.quotes, 1);
+    assert_eq!(stats.books, 0);
+    assert_eq!(stats.coalesced, 2);
+    assert_eq!(chain.take_dirty(), vec![live.instrument_name.clone()]);
+    let quote = chain.get(&live.instrument_name).expect("quoted").quote;
+    assert_eq!(quote.best_bid.map(|level| level.price), Some(dec!(1800)));
+
+    assert_eq!(conflator.flush(&chain).quotes, 0);
+    assert!(chain.take_dirty().is_empty());
+}
+
+#[test]
+fn expiry_groups_are_shared_until_written_and_shards_take_concurrent_writes() {
+    let chain = OptionChain::new();
+    let names: Vec<String> = (0..64)
+        .map(|at| format!("BTC_USDC-25DEC99-{}-C", 40000 + 1000 * at))
+        .collect();
+    for name in &names {
+        chain.upsert_instrument(instrument_from_name(name).expect("instrument"));
+    }
+    let expiry = instrument_from_name(&names[0]).expect("instrument").expiry;
+
+    let first = chain.expiry_group(Currency::BTC, expiry);
+    assert_eq!(first.len(), names.len());
+    assert!(Arc::ptr_eq(
+        &first,
+        &chain.expiry_group(Currency::BTC, expiry)
+    ));
+
+    let quoted_at = Utc::now();
+    std::thread::scope(|scope| {
+        for writer in names.chunks(16) {
+            let chain = &chain;
+            scope.spawn(move || {
+                for name in writer {
+                    chain.update_quote(
+                        name,
+                        Quote {
+                            best_bid: Some(QuoteLevel {
+                                price: dec!(100),
+                                amount: dec!(1),
+                            }),
+                            best_ask: None,
+                            mark_iv: None,
+                            bid_iv: None,
+                            ask_iv: None,
+                            interest_rate: None,
+                            timestamp: quoted_at,
+                            index_price: dec!(40000),
+                        },
+                    );
+                }
+            });
+        }
+        scope.spawn(|| {
+            for _ in 0..32 {
+                assert_eq!(chain.snapshot().instruments.len(), names.len());
+            }
+        });
+    });
+
+    let written = chain.expiry_group(Currency::BTC, expiry);
+    assert!(!Arc::ptr_eq(&first, &written));
+    assert!(written.iter().all(|inst| inst.quote.best_bid.is_some()));
+    let mut dirty = chain.take_dirty();
+    dirty.sort();
+    let mut expected = names.clone();
+    expected.sort();
+    assert_eq!(dirty, expected);
+    assert_eq!(chain.stats().bid_levels, names.len());
+}
@@ -0,0 +1,192 @@
+use deribit_arb::client::{
+    parse_book_summaries, parse_carry_curve, parse_historical_volatility, parse_order_book,
+    parse_total_equity_usd, parse_volatility_index, parse_ws_event, ClientTelemetry, RpcErrorClass,
+    WsEvent,
+};
+use deribit_arb::model::ComboSide;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn parses_order_book_levels() {
+    let result = json!({
+        "instrument_name": "BTC-27DEC24-60000-C",
+        "timestamp": 1_700_000_000_123i64,
+        "bids": [[0.0515, 12.0], [0.051, 30.5]],
+        "asks": [[0.0525, 4.0]],
+    });
+    let book = parse_order_book(&result).expect("book");
+    assert_eq!(book.bids.len(), 2);
+    assert_eq!(book.bids[1].price, dec!(0.051));
+    assert_eq!(book.bids[1].amount, dec!(30.5));
+    assert_eq!(book.asks[0].price, dec!(0.0525));
+    assert_eq!(book.timestamp.timestamp_millis(), 1_700_000_000_123);
+
+    let malformed = json!({ "bids": [[0.05]], "asks": [] });
+    assert!(parse_order_book(&malformed).is_none());
+}
+
+#[test]
+fn parses_volatility_results() {
+    let realized = json!([[1_700_000_000_000i64, 41.5], [1_700_003_600_000i64, 44.25]]);
+    let points = parse_historical_volatility(&realized).expect("points");
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].0.timestamp_millis(), 1_700_003_600_000);
+    assert_eq!(points[1].1, 44.25);
+    assert!(parse_historical_volatility(&json!([[1_700_000_000_000i64]])).is_none());
+
+    let dvol = json!({
+        "data": [
+            [1_700_000_060_000i64, 55.0, 56.0, 54.5, 55.5],
+            [1_700_000_000_000i64, 54.0, 55.0, 53.5, 54.2],
+        ],
+        "continuation": null,
+    });
+    assert_eq!(parse_volatility_index(&dvol), Some(55.5));
+    assert_eq!(parse_volatility_index(&json!({ "data": [] })), None);
+}
+
+#[test]
+fn parses_total_equity_from_account_summaries() {
+    let summaries = json!({
+        "summaries": [{ "currency": "BTC", "equity": 1.5, "total_equity_usd": 152_000.25 }],
+        "total_equity_usd": 152_000.25,
+    });
+    assert_eq!(parse_total_equity_usd(&summaries), Some(dec!(152000.25)));
+    let nested = json!({ "summaries": [{ "currency": "ETH", "total_equity_usd": 9_000.0 }] });
+    assert_eq!(parse_total_equity_usd(&nested), Some(dec!(9000)));
+    assert_eq!(parse_total_equity_usd(&json!({ "summaries": [] })), None);
+}
+
+#[test]
+fn parses_futures_summary_into_carry_curve() {
+    let summary = json!([
+        { "instrument_name": "BTC-PERPETUAL", "mark_price": 40010.0, "estimated_delivery_price": 40000.0, "funding_8h": 0.0001 },
+        { "instrument_name": "BTC-27DEC24", "mark_price": 40400.0, "estimated_delivery_price": 40000.0 },
+        { "instrument_name": "BTC-28MAR25", "mark_price": 41000.0, "estimated_delivery_price": 40000.0 },
+    ]);
+    let curve = parse_carry_curve(&summary).expect("curve");
+    let funding = curve.funding_rate.expect("funding");
+    assert!((funding - 0.1095).abs() < 1e-9);
+    assert_eq!(curve.futures.len(), 2);
+    assert_eq!(curve.futures[0].0.to_rfc3339(), "2024-12-27T08:00:00+00:00");
+    assert!((curve.futures[1].1 - 1.025).abs() < 1e-12);
+
+    // Halfway to the December future, the forward sits halfway up its basis.
+    let now = curve.futures[0].0 - chrono::Duration::days(20);
+    let forward = curve
+        .forward(now + chrono::Duration::days(10), dec!(40000), now)
+        .expect("forward");
+    assert_eq!(forward.round_dp(6), dec!(40200));
+}
+
+#[test]
+fn parses_recorded_ws_payloads() {
+    let ticker = parse_ws_event(include_str!("fixtures/ws/ticker.json")).expect("ticker");
+    let WsEvent::TickerUpdate {
+        instrument_name,
+        quote,
+    } = ticker
+    else {
+        panic!("expected ticker update, got {ticker:?}");
+    };
+    assert_eq!(instrument_name, "BTC-27DEC24-60000-C");
+    assert_eq!(quote.best_bid.map(|level| level.amount), Some(dec!(12)));
+    assert_eq!(quote.index_price, dec!(42310.55));
+    assert_eq!(quote.mark_iv, Some(52.13));
+
+    let book = parse_ws_event(include_str!("fixtures/ws/book.json")).expect("book");
+    let WsEvent::BookUpdate {
+        instrument_name,
+        book,
+    } = book
+    else {
+        panic!("expected book update, got {book:?}");
+    };
+    assert_eq!(instrument_name, "BTC-27DEC24-60000-C");
+    assert_eq!(book.asks.len(), 3);
+    assert_eq!(book.asks[2].amount, dec!(2.5));
+
+    let trades = parse_ws_event(include_str!("fixtures/ws/trades.json")).expect("trades");
+    let WsEvent::TradeUpdate { trades } = trades else {
+        panic!("expected trade update, got {trades:?}");
+    };
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[1].trade_id, "BTC-294613");
+    assert_eq!(trades[1].price, dec!(0.053));
+    assert_eq!(trades[1].direction, ComboSide::Buy);
+
+    let orders = parse_ws_event(include_str!("fixtures/ws/orders.json")).expect("orders");
+    let WsEvent::OrderUpdate { orders } = orders else {
+        panic!("expected order update, got {orders:?}");
+    };
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].state, "open");
+    assert_eq!(orders[0].filled_amount, dec!(1));
+    assert_eq!(orders[0].label.as_deref(), Some("arb-vertical"));
+
+    assert_eq!(
+        parse_ws_event(include_str!("fixtures/ws/heartbeat.json")),
+        Some(WsEvent::Heartbeat)
+    );
+    assert_eq!(
+        parse_ws_event(include_str!("fixtures/ws/subscribe_ack.json")),
+        None
+    );
+}
+
+#[test]
+fn parses_option_book_summaries_with_empty_sides() {
+    let summary = json!([
+        { "instrument_name": "BTC-27DEC24-60000-C", "bid_price": 0.051, "ask_price": 0.0525, "mark_price": 0.0518 },
+        { "instrument_name": "BTC-27DEC24-90000-C", "bid_price": null, "ask_price": 0.0005, "mark_price": 0.0003 },
+    ]);
+    let rows = parse_book_summaries(&summary).expect("summaries");
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].bid_price, Some(dec!(0.051)));
+    assert_eq!(rows[0].mark_price, Some(dec!(0.0518)));
+    assert_eq!(rows[1].bid_price, None);
+    assert_eq!(rows[1].ask_price, Some(dec!(0.0005)));
+
+    assert!(parse_book_summaries(&json!([{ "bid_price": 0.1 }])).is_none());
+}
+
+#[test]
+fn telemetry_reports_per_method_percentiles_and_waits() {
+    let telemetry = ClientTelemetry::default();
+    for ms in 1..=100 {
+        telemetry.record_call("public/ticker", Duration::from_millis(ms), ms != 100);
+    }
+    telemetry.record_call("private/buy", Duration::from_millis(400), true);
+    telemetry.record_rate_limit_wait(Duration::from_millis(250));
+    telemetry.record_rate_limit_wait(Duration::from_millis(500));
+    telemetry.record_credits(42);
+
+    let stats = telemetry.stats();
+    let ticker = &stats.methods["public/ticker"];
+    assert_eq!((ticker.calls, ticker.errors), (100, 1));
+    assert_eq!(ticker.p50_ms, 50.0);
+    assert_eq!(ticker.p90_ms, 90.0);
+    assert_eq!(ticker.p99_ms, 99.0);
+    assert_eq!(stats.totals(), (101, 1));
+    assert_eq!(
+        stats.slowest().map(|(method, _)| method),
+        Some("private/buy")
+    );
+    assert_eq!((stats.rate_limit_waits, stats.rate_limit_wait_ms), (2, 750));
+    assert_eq!(stats.credits_remaining, Some(42));
+}
+
+#[test]
+fn classifies_deribit_error_codes() {
+    assert_eq!(RpcErrorClass::from_code(13009), RpcErrorClass::Auth);
+    assert_eq!(RpcErrorClass::from_code(10028), RpcErrorClass::RateLimit);
+    assert_eq!(RpcErrorClass::from_code(-32602), RpcErrorClass::Validation);
+    assert_eq!(RpcErrorClass::from_code(10009), RpcErrorClass::Validation);
+    assert_eq!(RpcErrorClass::from_code(10047), RpcErrorClass::Engine);
+    assert_eq!(RpcErrorClass::from_code(13888), RpcErrorClass::Engine);
+    assert_eq!(RpcErrorClass::from_code(42), RpcErrorClass::Other);
+    assert!(RpcErrorClass::Engine.is_transient());
+    assert!(!RpcErrorClass::Auth.is_transient());
+}
@@ -0,0 +1,462 @@
+use chrono::NaiveDate;
+use clap::Parser;
+use deribit_arb::config::{AppConfig, Cli, ConfigFile, Environment, NotifyTarget, DEFAULT_ACCOUNT};
+use deribit_arb::model::{Currency, SortKey, StrategyKind};
+use deribit_arb::notify::{AlertKind, NotifyFilter};
+use rust_decimal_macros::dec;
+
+#[test]
+fn per_strategy_thresholds_override_globals() {
+    let cli = Cli::try_parse_from([
+        "deribit_arb",
+        "--min-edge-usd",
+        "50",
+        "--strategy-min-edge-usd",
+        "box=200,vertical=30",
+        "--strategy-min-edge-ratio",
+        "calendar=3.5",
+        "--strategy-max-contracts",
+        "butterfly=5",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert_eq!(
+        config.requirements(StrategyKind::Box).min_edge_usd,
+        dec!(200)
+    );
+    assert_eq!(
+        config.requirements(StrategyKind::Vertical).min_edge_usd,
+        dec!(30)
+    );
+    assert_eq!(
+        config.requirements(StrategyKind::JellyRoll).min_edge_usd,
+        dec!(50)
+    );
+    assert_eq!(
+        config.requirements(StrategyKind::Calendar).min_edge_ratio,
+        3.5
+    );
+    assert_eq!(
+        config
+            .requirements(StrategyKind::Butterfly)
+            .max_size_contracts,
+        Some(dec!(5))
+    );
+    assert_eq!(
+        config.requirements(StrategyKind::Box).max_size_contracts,
+        None
+    );
+}
+
+#[test]
+fn malformed_strategy_threshold_is_rejected() {
+    let cli = Cli::try_parse_from(["deribit_arb", "--strategy-min-edge-usd", "box"]).expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+}
+
+#[test]
+fn expiry_and_moneyness_filters_parse() {
+    let cli = Cli::try_parse_from([
+        "deribit_arb",
+        "--min-dte",
+        "2",
+        "--max-dte",
+        "90",
+        "--moneyness-band",
+        "0.7..1.3",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    let filter = &config.instrument_filter;
+    assert_eq!(filter.min_dte, Some(2.0));
+    assert_eq!(filter.max_dte, Some(90.0));
+    assert_eq!(filter.moneyness_band, Some((dec!(0.7), dec!(1.3))));
+    assert!(filter.allows_strike(dec!(50000), dec!(50000)));
+    assert!(!filter.allows_strike(dec!(70000), dec!(50000)));
+}
+
+#[test]
+fn expiry_shortlist_limits_discovery_and_scans() {
+    let cli = Cli::try_parse_from([
+        "deribit_arb",
+        "--expiries",
+        "2024-12-27,28MAR25",
+        "--nearest-expiries",
+        "1",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    let filter = &config.instrument_filter;
+    let at = |day: NaiveDate| day.and_hms_opt(8, 0, 0).unwrap().and_utc();
+    let (dec, jan, mar) = (
+        at(NaiveDate::from_ymd_opt(2024, 12, 27).unwrap()),
+        at(NaiveDate::from_ymd_opt(2025, 1, 31).unwrap()),
+        at(NaiveDate::from_ymd_opt(2025, 3, 28).unwrap()),
+    );
+    let now = dec - chrono::Duration::days(30);
+    assert!(filter.is_active());
+    assert!(filter.allows_expiry(dec, now) && filter.allows_expiry(mar, now));
+    assert!(!filter.allows_expiry(jan, now));
+    // Nearest-N picks among the listed expiries the shortlist allows.
+    assert_eq!(
+        filter.nearest_expiries([mar, jan, dec, mar], now),
+        Some(vec![dec])
+    );
+    assert_eq!(
+        filter.nearest_expiries([mar, jan, dec], dec + chrono::Duration::hours(1)),
+        Some(vec![mar])
+    );
+
+    let cli = Cli::try_parse_from(["deribit_arb"]).expect("cli");
+    let filter = AppConfig::from_cli(cli).expect("config").instrument_filter;
+    assert_eq!(filter.nearest_expiries([dec], now), None);
+    let cli = Cli::try_parse_from(["deribit_arb", "--nearest-expiries", "0"]).expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+}
+
+#[test]
+fn inverted_moneyness_band_is_rejected() {
+    let cli = Cli::try_parse_from(["deribit_arb", "--moneyness-band", "1.3..0.7"]).expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+}
+
+#[test]
+fn output_view_flags_parse() {
+    let cli = Cli::try_parse_from([
+        "deribit_arb",
+        "--sort-by",
+        "notional",
+        "--filter-strategy",
+        "box,jelly",
+        "--filter-currency",
+        "eth",
+        "--filter-expiry",
+        "27DEC24,2025-03-28",
+        "--top",
+        "5",
+        "--execute-top",
+        "1",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert_eq!(config.view.sort_by, SortKey::Notional);
+    assert_eq!(
+        config.view.strategies,
+        [StrategyKind::Box, StrategyKind::JellyRoll]
+    );
+    assert_eq!(config.view.currencies, [Currency::ETH]);
+    assert_eq!(
+        config.view.expiries,
+        [
+            NaiveDate::from_ymd_opt(2024, 12, 27).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 28).unwrap()
+        ]
+    );
+    assert_eq!(config.view.top, Some(5));
+    assert_eq!(config.execute_top, 1);
+
+    let cli = Cli::try_parse_from(["deribit_arb", "--sort-by", "gamma"]).expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+}
+
+fn example_config() -> String {
+    format!("{}/arb.example.toml", env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn example_config_profiles_parse() {
+    let raw = std::fs::read_to_string(example_config()).expect("example config");
+    let file = ConfigFile::from_toml(&raw).expect("toml");
+    for name in ["testnet-paper", "prod-small", "prod-aggressive"] {
+        assert!(file.profile(Some(name)).is_ok(), "missing profile {name}");
+    }
+    assert!(file.profile(Some("nope")).is_err());
+}
+
+#[test]
+fn profile_values_apply_under_explicit_flags() {
+    let cli = Cli::try_parse_layered_from([
+        "deribit_arb",
+        "--config",
+        &example_config(),
+        "--profile",
+        "prod-small",
+        "--min-edge-usd",
+        "90",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert_eq!(config.environment, Environment::Production);
+    assert_eq!(config.max_ticket_usd, dec!(5000));
+    assert_eq!(config.min_edge_usd, dec!(90));
+    assert_eq!(config.min_edge_ratio, 3.0);
+    assert_eq!(
+        config.requirements(StrategyKind::Box).min_edge_usd,
+        dec!(150)
+    );
+    assert_eq!(
+        config.instrument_filter.moneyness_band,
+        Some((dec!(0.8), dec!(1.2)))
+    );
+}
+
+#[test]
+fn default_profile_used_when_none_selected() {
+    let cli =
+        Cli::try_parse_layered_from(["deribit_arb", "--config", &example_config()]).expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert_eq!(config.environment, Environment::Testnet);
+    assert_eq!(config.min_edge_usd, dec!(25));
+}
+
+#[test]
+fn unknown_profile_key_is_rejected() {
+    let raw = "[profiles.bad]\nmin-edge = 5\n";
+    assert!(ConfigFile::from_toml(raw).is_err());
+}
+
+#[test]
+fn warm_start_requires_chain_snapshot() {
+    let cli = Cli::try_parse_from(["deribit_arb", "--warm-start"]).expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+    let cli = Cli::try_parse_from([
+        "deribit_arb",
+        "--warm-start",
+        "--chain-snapshot",
+        "chain.json",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert!(config.warm_start);
+}
+
+#[test]
+fn incremental_detection_requires_loop_mode() {
+    let cli = Cli::try_parse_from(["deribit_arb", "--incremental-ms", "100"]).expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+    let cli = Cli::try_parse_from([
+        "deribit_arb",
+        "--incremental-ms",
+        "100",
+        "--loop-interval",
+        "30",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert_eq!(config.incremental_ms, Some(100));
+}
+
+#[test]
+fn ws_record_requires_loop_mode_and_excludes_replay() {
+    let cli = Cli::try_parse_from(["deribit_arb", "--record", "ws.zst"]).expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+    let cli = Cli::try_parse_from([
+        "deribit_arb",
+        "--record",
+        "ws.zst",
+        "--replay",
+        "old.zst",
+        "--loop-interval",
+        "30",
+    ])
+    .expect("cli");
+    assert!(AppConfig::from_cli(cli).is_err());
+    let cli = Cli::try_parse_from(["deribit_arb", "--replay", "ws.zst"]).expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert_eq!(config.replay, Some("ws.zst".into()));
+    assert_eq!(config.record, None);
+}
+
+#[test]
+fn cancel_on_disconnect_defaults_on() {
+    let cli = Cli::try_parse_from(["deribit_arb"]).expect("cli");
+    assert!(
+        AppConfig::from_cli(cli)
+            .expect("config")
+            .cancel_on_disconnect
+    );
+    let cli = Cli::try_parse_layered_from([
+        "deribit_arb",
+        "--config",
+        &example_config(),
+        "--profile",
+        "prod-aggressive",
+    ])
+    .expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    assert!(!config.dry_run);
+    assert!(config.cancel_on_disconnect);
+}
+
+fn accounts_config(name: &str, accounts: &str) -> String {
+    let path = std::env::temp_dir().join(format!(
+        "deribit_arb_accounts_{name}_{}.toml",
+        std::process::id()
+    ));
+    let raw =
+        format!("default-profile = \"paper\"\n\n[profiles.paper]\nenv = \"test\"\n\n{accounts}");
+    std::fs::write(&path, raw).expect("write config");
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn accounts_route_strategies_and_keep_secrets_out_of_logs() {
+    std::env::set_var("ARB_TEST_SUB_KEY", "sub-id");
+    std::env::set_var("ARB_TEST_SUB_SECRET", "sub-s3cret");
+    let path = accounts_config(
+        "route",
+        r#"[accounts.calendar-sub]
+key-env = "ARB_TEST_SUB_KEY"
+secret-env = "ARB_TEST_SUB_SECRET"
+strategies = ["calendar", "jelly"]
+
+[accounts.prod-readonly]
+env = "prod"
+key-env = "ARB_TEST_RO_KEY"
+secret-env = "ARB_TEST_RO_SECRET"
+read-only = true
+strategies = ["box"]
+"#,
+    );
+    let cli = Cli::try_parse_layered_from(["deribit_arb", "--config", &path]).expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(config.account_label(StrategyKind::Calendar), "calendar-sub");
+    assert_eq!(
+        config.account_label(StrategyKind::JellyRoll),
+        "calendar-sub"
+    );
+    assert_eq!(
+        config.account_label(StrategyKind::Vertical),
+        DEFAULT_ACCOUNT
+    );
+    let sub = config.account_for(StrategyKind::Calendar).expect("routed");
+    assert_eq!(sub.environment, Environment::Testnet);
+    assert_eq!(sub.api_key.as_deref(), Some("sub-id"));
+    let readonly = config.account_for(StrategyKind::Box).expect("routed");
+    assert_eq!(readonly.environment, Environment::Production);
+    assert!(readonly.read_only);
+    assert!(readonly.api_key.is_none());
+    let logged = serde_json::to_string(&config).expect("json");
+    assert!(logged.contains("calendar-sub"));
+    assert!(!logged.contains("sub-s3cret"));
+}
+
+#[test]
+fn conflicting_or_unusable_accounts_are_rejected() {
+    let path = accounts_config(
+        "conflict",
+        r#"[accounts.a]
+key-env = "ARB_TEST_A_KEY"
+secret-env = "ARB_TEST_A_SECRET"
+strategies = ["box"]
+
+[accounts.b]
+key-env = "ARB_TEST_B_KEY"
+secret-env = "ARB_TEST_B_SECRET"
+strategies = ["box"]
+"#,
+    );
+    let cli = Cli::try_parse_layered_from(["deribit_arb", "--config", &path]).expect("cli");
+    std::fs::remove_file(&path).ok();
+    let err = AppConfig::from_cli(cli).expect_err("box routed twice");
+    assert!(err.to_string().contains("routed to both"));
+
+    let path = accounts_config(
+        "live",
+        r#"[profiles.live]
+env = "test"
+dry-run = false
+
+[accounts.live]
+key-env = "ARB_TEST_UNSET_KEY"
+secret-env = "ARB_TEST_UNSET_SECRET"
+strategies = ["vertical"]
+"#,
+    );
+    let cli = Cli::try_parse_layered_from(["deribit_arb", "--config", &path, "--profile", "live"])
+        .expect("cli");
+    std::fs::remove_file(&path).ok();
+    let err = AppConfig::from_cli(cli).expect_err("live account without credentials");
+    assert!(err.to_string().contains("ARB_TEST_UNSET_KEY"));
+}
+
+#[test]
+fn notify_tables_resolve_routes_and_keep_secrets_out_of_logs() {
+    std::env::set_var("ARB_TEST_TG_TOKEN", "123:t0ken");
+    std::env::set_var("ARB_TEST_SLACK_URL", "https://hooks.slack.test/T000/s3cret");
+    let path = accounts_config(
+        "notify",
+        r#"[notify.desk]
+kind = "telegram"
+token-env = "ARB_TEST_TG_TOKEN"
+chat-id = "-100123"
+events = ["opportunity", "breaker"]
+min-edge-usd = 150
+top = 1
+
+[notify.ops]
+kind = "slack"
+url-env = "ARB_TEST_SLACK_URL"
+events = ["risk", "execution"]
+
+[notify.archive]
+kind = "webhook"
+url = "https://alerts.example.test/arb"
+"#,
+    );
+    let cli = Cli::try_parse_layered_from(["deribit_arb", "--config", &path]).expect("cli");
+    let config = AppConfig::from_cli(cli).expect("config");
+    std::fs::remove_file(&path).ok();
+
+    let labels: Vec<&str> = config.notifiers.iter().map(|n| n.label.as_str()).collect();
+    assert_eq!(labels, ["archive", "desk", "ops"]);
+    let desk = &config.notifiers[1];
+    assert!(matches!(
+        &desk.target,
+        NotifyTarget::Telegram { token, chat_id, .. } if token == "123:t0ken" && chat_id == "-100123"
+    ));
+    assert_eq!(
+        desk.filter,
+        NotifyFilter {
+            events: vec![AlertKind::Opportunity, AlertKind::Breaker],
+            min_edge_usd: dec!(150),
+            top: 1,
+        }
+    );
+    assert_eq!(config.notifiers[0].filter, NotifyFilter::default());
+    let logged = serde_json::to_string(&config).expect("json");
+    assert!(logged.contains("-100123"));
+    assert!(!logged.contains("t0ken"));
+    assert!(!logged.contains("s3cret"));
+
+    for (name, table, error) in [
+        (
+            "kind",
+            "[notify.x]\nkind = \"pager\"\n",
+            "unknown kind pager",
+        ),
+        (
+            "unset",
+            "[notify.x]\nkind = \"slack\"\nurl-env = \"ARB_TEST_UNSET_SLACK\"\n",
+            "set ARB_TEST_UNSET_SLACK",
+        ),
+        (
+            "chat",
+            "[notify.x]\nkind = \"telegram\"\ntoken-env = \"ARB_TEST_TG_TOKEN\"\n",
+            "chat-id is required",
+        ),
+        (
+            "event",
+            "[notify.x]\nkind = \"webhook\"\nurl = \"https://a.test\"\nevents = [\"fills\"]\n",
+            "unknown notify event: fills",
+        ),
+    ] {
+        let path = accounts_config(&format!("notify_{name}"), table);
+        let cli = Cli::try_parse_layered_from(["deribit_arb", "--config", &path]).expect("cli");
+        std::fs::remove_file(&path).ok();
+        let err = AppConfig::from_cli(cli).expect_err(name);
+        assert!(format!("{err:#}").contains(error), "{err:#}");
+    }
+}
@@ -0,0 +1,89 @@
+use deribit_arb::chain::OptionChain;
+use deribit_arb::control::{self, ControlHandle};
+use deribit_arb::model::RuntimeThresholds;
+use deribit_arb::risk::RiskManager;
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn control_api_pauses_and_adjusts_thresholds() {
+    let handle = ControlHandle::new(OptionChain::default(), RiskManager::new());
+    handle.publish(&[]);
+    let addr = control::serve("127.0.0.1:0".parse().unwrap(), handle.clone())
+        .await
+        .expect("serve");
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    let status: Value = reqwest::get(format!("{base}/status"))
+        .await
+        .expect("status")
+        .json()
+        .await
+        .expect("body");
+    assert_eq!(status["paused"], json!(false));
+    let opportunities: Value = reqwest::get(format!("{base}/opportunities"))
+        .await
+        .expect("opportunities")
+        .json()
+        .await
+        .expect("body");
+    assert_eq!(opportunities, json!([]));
+    let chain: Value = reqwest::get(format!("{base}/chain"))
+        .await
+        .expect("chain")
+        .json()
+        .await
+        .expect("body");
+    assert_eq!(chain["instrument_count"], json!(0));
+    let risk = reqwest::get(format!("{base}/risk")).await.expect("risk");
+    assert!(risk.status().is_success());
+
+    let paused = client
+        .post(format!("{base}/pause"))
+        .send()
+        .await
+        .expect("pause");
+    assert!(paused.status().is_success());
+    assert!(handle.is_paused());
+    client
+        .post(format!("{base}/resume"))
+        .send()
+        .await
+        .expect("resume");
+    assert!(!handle.is_paused());
+
+    let updated = client
+        .post(format!("{base}/thresholds"))
+        .json(&json!({ "min_edge_usd": "25", "min_edge_ratio": 0.002 }))
+        .send()
+        .await
+        .expect("thresholds");
+    assert!(updated.status().is_success());
+    assert_eq!(
+        handle.thresholds(),
+        RuntimeThresholds {
+            min_edge_usd: Some(dec!(25)),
+            min_edge_ratio: Some(0.002),
+        }
+    );
+
+    let negative = client
+        .post(format!("{base}/thresholds"))
+        .json(&json!({ "min_edge_ratio": -1.0 }))
+        .send()
+        .await
+        .expect("negative thresholds");
+    assert_eq!(negative.status().as_u16(), 400);
+    let unknown = client
+        .post(format!("{base}/thresholds"))
+        .json(&json!({ "min_edge": 1 }))
+        .send()
+        .await
+        .expect("unknown field");
+    assert_eq!(unknown.status().as_u16(), 400);
+    assert_eq!(handle.thresholds().min_edge_usd, Some(dec!(25)));
+
+    let missing = reqwest::get(format!("{base}/nope")).await.expect("404");
+    assert_eq!(missing.status().as_u16(), 404);
+}
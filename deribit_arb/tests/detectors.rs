@@ -1,81 +1,22 @@
-use deribit_arb::config::{AppConfig, Environment};
-use deribit_arb::detect::DetectorSuite;
+use deribit_arb::chain::OptionChain;
+use deribit_arb::detect::{
+    ChainView, Detector, DetectorSuite, IncrementalScanner, RejectionReason, SliceKind,
+};
 use deribit_arb::model::{
-    Currency, Instrument, InstrumentSnapshot, OptionKind, ParsedInstrumentName, Quote, QuoteLevel,
-    SettlementCurrency, StrategyFilter, StrategyKind,
+    CarryCurve, Currency, InstrumentFilter, InstrumentSnapshot, OpportunityView, OptionKind,
+    OrderBook, QuoteLevel, RuntimeThresholds, SettlementCurrency, SortKey, StrategyKind,
+    StrategyThresholds,
 };
-use rust_decimal::Decimal;
+use deribit_arb::registry::{DiffKind, OpportunityDiff, OpportunityRegistry};
+use deribit_arb::testkit::{self, assert_golden, build_snapshot, fixed_now, ChainBuilder};
+use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
-use std::str::FromStr;
-
-fn base_config(strategies: Vec<StrategyKind>) -> AppConfig {
-    AppConfig {
-        environment: Environment::Testnet,
-        api_key: None,
-        api_secret: None,
-        currencies: vec![Currency::BTC],
-        settlements: vec![SettlementCurrency::Usdc],
-        dry_run: true,
-        max_ticket_usd: dec!(20000),
-        min_edge_usd: dec!(50),
-        min_edge_ratio: 1.5,
-        hold_to_expiry: false,
-        strategy_filter: StrategyFilter {
-            include: strategies,
-        },
-        max_concurrent_combos: 3,
-        min_depth_contracts: 1,
-    }
-}
-
-fn build_snapshot(
-    name: &str,
-    strike: Decimal,
-    option_kind: OptionKind,
-    best_bid: (Decimal, Decimal),
-    best_ask: (Decimal, Decimal),
-) -> InstrumentSnapshot {
-    let expiry = ParsedInstrumentName::from_str(name)
-        .ok()
-        .and_then(|parsed| parsed.expiry_date().ok())
-        .unwrap_or_else(|| chrono::Utc::now() + chrono::Duration::days(30));
-    InstrumentSnapshot {
-        instrument: Instrument {
-            instrument_name: name.to_string(),
-            currency: Currency::BTC,
-            is_usdc_settled: true,
-            is_combo: false,
-            option_kind,
-            strike,
-            expiry,
-            contract_size: Decimal::ONE,
-            settlement_currency: SettlementCurrency::Usdc,
-            tick_size: dec!(0.1),
-            min_trade_amount: Decimal::ONE,
-        },
-        quote: Quote {
-            best_bid: Some(QuoteLevel {
-                price: best_bid.0,
-                amount: best_bid.1,
-            }),
-            best_ask: Some(QuoteLevel {
-                price: best_ask.0,
-                amount: best_ask.1,
-            }),
-            mark_iv: None,
-            bid_iv: None,
-            ask_iv: None,
-            interest_rate: None,
-            timestamp: chrono::Utc::now(),
-            index_price: dec!(40000),
-        },
-        order_book: None,
-    }
-}
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[test]
 fn detects_profitable_vertical() {
-    let config = base_config(vec![StrategyKind::Vertical]);
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
     let suite = DetectorSuite::new(&config);
     let low = build_snapshot(
         "BTC-25DEC24-40000-C",
@@ -100,7 +41,7 @@ fn detects_profitable_vertical() {
 
 #[test]
 fn detects_calendar_credit() {
-    let config = base_config(vec![StrategyKind::Calendar]);
+    let config = testkit::config(vec![StrategyKind::Calendar]).build();
     let suite = DetectorSuite::new(&config);
     let near = build_snapshot(
         "BTC-25DEC24-40000-C",
@@ -123,9 +64,125 @@ fn detects_calendar_credit() {
         .any(|opp| opp.strategy == StrategyKind::Calendar));
 }
 
+#[test]
+fn calendar_pairs_coin_and_usdc_books() {
+    let config = testkit::config(vec![StrategyKind::Calendar]).build();
+    let suite = DetectorSuite::new(&config);
+    let mut near = build_snapshot(
+        "BTC-25DEC24-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(0.04), dec!(10)),
+        (dec!(0.0425), dec!(10)),
+    );
+    near.instrument.is_usdc_settled = false;
+    near.instrument.settlement_currency = SettlementCurrency::Coin;
+    let mut far = build_snapshot(
+        "BTC_USDC-25JAN25-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(1100), dec!(1000)),
+        (dec!(1300), dec!(1000)),
+    );
+    far.instrument.contract_size = dec!(0.01);
+    let opportunities = suite.scan(&[near, far]);
+    let calendar = opportunities
+        .iter()
+        .find(|opp| opp.strategy == StrategyKind::Calendar)
+        .expect("cross-book calendar");
+    assert!(calendar.spans_settlements());
+    assert_eq!(calendar.settlement, SettlementCurrency::Coin);
+    // Both legs cover the same BTC amount despite the 1 vs 0.01 contract size.
+    assert_eq!(
+        calendar.touches[1].size_contracts,
+        calendar.touches[0].size_contracts * dec!(100)
+    );
+    assert_eq!(calendar.fee_breakdown.combo_discount_usd, Decimal::ZERO);
+    let payout_usd = (dec!(1600) - dec!(1300)) * calendar.touches[0].size_contracts;
+    assert_eq!(
+        calendar.net_edge_usd,
+        payout_usd - calendar.fee_breakdown.total_usd
+    );
+}
+
+#[test]
+fn scans_usdt_and_eurr_books() {
+    let config = testkit::config(vec![StrategyKind::Box]).build();
+    let suite = DetectorSuite::new(&config);
+    let booked = |settlement| {
+        let mut legs = box_legs();
+        for leg in &mut legs {
+            leg.instrument.is_usdc_settled = false;
+            leg.instrument.settlement_currency = settlement;
+        }
+        legs
+    };
+    let usdt = suite.scan(&booked(SettlementCurrency::Usdt));
+    assert!(!usdt.is_empty());
+    assert!(usdt
+        .iter()
+        .all(|opp| opp.settlement == SettlementCurrency::Usdt && opp.fx_usd == Decimal::ONE));
+    // EURR books quote in euros and wait for the eur_usd index.
+    assert!(suite.scan(&booked(SettlementCurrency::Eurr)).is_empty());
+    suite.set_eur_usd(dec!(1.1));
+    let eurr = suite.scan(&booked(SettlementCurrency::Eurr));
+    assert_eq!(eurr.len(), usdt.len());
+    for (eurr, usdt) in eurr.iter().zip(&usdt) {
+        assert_eq!(eurr.settlement, SettlementCurrency::Eurr);
+        assert_eq!(eurr.fx_usd, dec!(1.1));
+        assert_eq!(eurr.net_edge_usd, eurr.net_edge_native * dec!(1.1));
+        assert_eq!(
+            eurr.fee_breakdown.total_usd,
+            eurr.fee_breakdown.total_native * dec!(1.1)
+        );
+        // The $20k ticket buys fewer contracts priced in dearer euros.
+        assert_eq!(
+            eurr.size_contracts.round_dp(6),
+            (usdt.size_contracts / dec!(1.1)).round_dp(6)
+        );
+        assert_eq!(eurr.notional_usd.round_dp(6), usdt.notional_usd);
+    }
+
+    // The USD minimum edge applies after conversion: at 0.5 USD per euro a
+    // depth-bound box clearing it in euros falls short in dollars.
+    let mut config = testkit::config(vec![StrategyKind::Box]).build();
+    config.max_ticket_usd = dec!(1000000);
+    let best_usd =
+        DetectorSuite::new(&config).scan(&booked(SettlementCurrency::Usdt))[0].net_edge_usd;
+    config.min_edge_usd = best_usd * dec!(0.75);
+    let suite = DetectorSuite::new(&config);
+    assert!(!suite.scan(&booked(SettlementCurrency::Usdt)).is_empty());
+    suite.set_eur_usd(dec!(0.5));
+    assert!(suite.scan(&booked(SettlementCurrency::Eurr)).is_empty());
+
+    // EURR strikes are in euros, so a coin leg never pairs with an EURR one.
+    let config = testkit::config(vec![StrategyKind::Calendar]).build();
+    let suite = DetectorSuite::new(&config);
+    let mut near = build_snapshot(
+        "BTC-25DEC24-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(0.04), dec!(10)),
+        (dec!(0.0425), dec!(10)),
+    );
+    near.instrument.is_usdc_settled = false;
+    near.instrument.settlement_currency = SettlementCurrency::Coin;
+    let mut far = build_snapshot(
+        "BTC_EURR-25JAN25-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(1100), dec!(1000)),
+        (dec!(1300), dec!(1000)),
+    );
+    far.instrument.is_usdc_settled = false;
+    far.instrument.settlement_currency = SettlementCurrency::Eurr;
+    far.instrument.contract_size = dec!(0.01);
+    assert!(suite.scan(&[near, far]).is_empty());
+}
+
 #[test]
 fn calendar_same_expiry_mixed_types_rejected() {
-    let config = base_config(vec![StrategyKind::Calendar]);
+    let config = testkit::config(vec![StrategyKind::Calendar]).build();
     let suite = DetectorSuite::new(&config);
     let call_leg = build_snapshot(
         "ETH-27MAR26-12000-C",
@@ -149,7 +206,7 @@ fn calendar_same_expiry_mixed_types_rejected() {
 
 #[test]
 fn detects_free_butterfly() {
-    let config = base_config(vec![StrategyKind::Butterfly]);
+    let config = testkit::config(vec![StrategyKind::Butterfly]).build();
     let suite = DetectorSuite::new(&config);
     let low = build_snapshot(
         "BTC-25DEC24-38000-C",
@@ -181,7 +238,7 @@ fn detects_free_butterfly() {
 
 #[test]
 fn detects_box_parity_gap() {
-    let config = base_config(vec![StrategyKind::Box]);
+    let config = testkit::config(vec![StrategyKind::Box]).build();
     let suite = DetectorSuite::new(&config);
     let call_low = build_snapshot(
         "BTC-25DEC24-40000-C",
@@ -218,10 +275,7 @@ fn detects_box_parity_gap() {
         .any(|opp| opp.strategy == StrategyKind::Box));
 }
 
-#[test]
-fn detects_jelly_roll_credit() {
-    let config = base_config(vec![StrategyKind::JellyRoll]);
-    let suite = DetectorSuite::new(&config);
+fn jelly_roll_legs() -> Vec<InstrumentSnapshot> {
     let near_call = build_snapshot(
         "BTC-25DEC24-40000-C",
         dec!(40000),
@@ -250,9 +304,923 @@ fn detects_jelly_roll_credit() {
         (dec!(4.0), dec!(5)),
         (dec!(5.0), dec!(5)),
     );
-    let snapshot = vec![near_call, near_put, far_call, far_put];
+    vec![near_call, near_put, far_call, far_put]
+}
+
+#[test]
+fn detects_jelly_roll_credit() {
+    let config = testkit::config(vec![StrategyKind::JellyRoll]).build();
+    let suite = DetectorSuite::new(&config);
+    let snapshot = jelly_roll_legs();
     let opportunities = suite.scan(&snapshot);
     assert!(opportunities
         .iter()
         .any(|opp| opp.strategy == StrategyKind::JellyRoll));
 }
+
+#[test]
+fn jelly_roll_credit_is_valued_against_carry() {
+    let mut config = testkit::config(vec![StrategyKind::JellyRoll]).build();
+    config.diagnose_rejections = true;
+    let suite = DetectorSuite::new(&config);
+    let at = |text: &str| {
+        chrono::DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&chrono::Utc)
+    };
+    let now = at("2024-11-25T08:00:00Z");
+    let (near, far) = (at("2024-12-25T08:00:00Z"), at("2025-03-25T08:00:00Z"));
+    let edge = |suite: &DetectorSuite| {
+        suite
+            .scan_at(&jelly_roll_legs(), now)
+            .into_iter()
+            .find(|opp| opp.strategy == StrategyKind::JellyRoll)
+            .map(|opp| (opp.net_edge_usd, opp.size_contracts))
+    };
+    let (uncarried, size) = edge(&suite).expect("credit without a curve");
+
+    // Futures 0.01% apart: the roll keeps its credit less 4 USD per contract.
+    suite.set_carry(
+        Currency::BTC,
+        CarryCurve {
+            futures: vec![(near, 1.0001), (far, 1.0002)],
+            funding_rate: None,
+        },
+    );
+    let (carried, _) = edge(&suite).expect("credit beyond carry");
+    assert!(
+        (uncarried - dec!(4) * size - carried).abs() < dec!(0.001),
+        "{uncarried} vs {carried}"
+    );
+
+    // 20% annualized funding over three months covers the whole credit.
+    suite.take_rejections();
+    suite.set_carry(
+        Currency::BTC,
+        CarryCurve {
+            futures: Vec::new(),
+            funding_rate: Some(0.2),
+        },
+    );
+    assert_eq!(edge(&suite), None);
+    let rejections = suite.take_rejections().expect("diagnostics on");
+    assert_eq!(
+        rejections.get(StrategyKind::JellyRoll, RejectionReason::CarryExplained),
+        1
+    );
+}
+
+#[test]
+fn calendar_carries_delta_hedge_leg() {
+    let config = testkit::config(vec![StrategyKind::Calendar]).build();
+    let suite = DetectorSuite::new(&config);
+    let mut near = build_snapshot(
+        "BTC-25JUN27-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(1600), dec!(10)),
+        (dec!(1700), dec!(10)),
+    );
+    let mut far = build_snapshot(
+        "BTC-31DEC27-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(1100), dec!(10)),
+        (dec!(1300), dec!(10)),
+    );
+    near.quote.mark_iv = Some(40.0);
+    far.quote.mark_iv = Some(80.0);
+    let opportunities = suite.scan(&[near, far]);
+    let calendar = opportunities
+        .iter()
+        .find(|opp| opp.strategy == StrategyKind::Calendar)
+        .expect("calendar");
+    let hedge = calendar.execution_plan.hedge.as_ref().expect("hedge leg");
+    assert_eq!(hedge.instrument_name, "BTC_USDC-PERPETUAL");
+    assert!(hedge.combo_delta > Decimal::ZERO);
+    assert_eq!(hedge.side, deribit_arb::model::ComboSide::Sell);
+    assert!(calendar.fee_breakdown.hedge_fee_usd > Decimal::ZERO);
+}
+
+#[test]
+fn annualized_return_ranks_and_filters_on_locked_capital() {
+    let mut config = testkit::config(vec![StrategyKind::Box]).build();
+    let expiry = chrono::DateTime::parse_from_rfc3339("2024-12-25T08:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let now = expiry - chrono::Duration::days(73);
+    let found = DetectorSuite::new(&config).scan_at(&box_legs(), now);
+    let opportunity = found.first().expect("box");
+    let rate = opportunity.annualized_return.expect("positive capital");
+    let expected = (opportunity.net_edge_usd / opportunity.locked_capital_usd())
+        .to_f64()
+        .unwrap()
+        * 5.0;
+    assert!((rate - expected).abs() < 1e-9, "{rate} vs {expected}");
+    // A day before expiry the same edge annualizes 73 times higher; after
+    // expiry it is still counted over one day.
+    let late = opportunity.annualized_return_at(expiry - chrono::Duration::days(1));
+    assert!((late.unwrap() / rate - 73.0).abs() < 1e-9);
+    assert_eq!(
+        opportunity.annualized_return_at(expiry + chrono::Duration::days(3)),
+        late
+    );
+
+    config.min_annualized_return = Some(rate * 100.0 + 1.0);
+    config.diagnose_rejections = true;
+    let suite = DetectorSuite::new(&config);
+    assert!(suite.scan_at(&box_legs(), now).is_empty());
+    let rejections = suite.take_rejections().expect("diagnostics on");
+    assert_eq!(
+        rejections.get(StrategyKind::Box, RejectionReason::BelowMinReturn),
+        found.len() as u64
+    );
+    config.min_annualized_return = Some(rate * 100.0 - 1.0);
+    assert!(!DetectorSuite::new(&config)
+        .scan_at(&box_legs(), now)
+        .is_empty());
+
+    let mut short = opportunity.clone();
+    short.net_edge_usd = opportunity.net_edge_usd / dec!(2);
+    short.annualized_return = Some(rate * 10.0);
+    let view = OpportunityView {
+        sort_by: SortKey::AnnualizedReturn,
+        ..Default::default()
+    };
+    let shown = view.apply(&[opportunity.clone(), short], 10);
+    assert_eq!(shown[0].annualized_return, Some(rate * 10.0));
+}
+
+fn box_legs() -> Vec<InstrumentSnapshot> {
+    vec![
+        build_snapshot(
+            "BTC-25DEC24-40000-C",
+            dec!(40000),
+            OptionKind::Call,
+            (dec!(1800), dec!(10)),
+            (dec!(2000), dec!(10)),
+        ),
+        build_snapshot(
+            "BTC-25DEC24-45000-C",
+            dec!(45000),
+            OptionKind::Call,
+            (dec!(1500), dec!(10)),
+            (dec!(1700), dec!(10)),
+        ),
+        build_snapshot(
+            "BTC-25DEC24-40000-P",
+            dec!(40000),
+            OptionKind::Put,
+            (dec!(1500), dec!(10)),
+            (dec!(1700), dec!(10)),
+        ),
+        build_snapshot(
+            "BTC-25DEC24-45000-P",
+            dec!(45000),
+            OptionKind::Put,
+            (dec!(1800), dec!(10)),
+            (dec!(2000), dec!(10)),
+        ),
+    ]
+}
+
+#[test]
+fn chain_slice_scan_matches_full_scan() {
+    let config = testkit::config(vec![StrategyKind::Box, StrategyKind::JellyRoll]).build();
+    let suite = DetectorSuite::new(&config);
+    let now = chrono::Utc::now();
+    let near = (now + chrono::Duration::days(30))
+        .format("%-d%b%y")
+        .to_string()
+        .to_uppercase();
+    let far = (now + chrono::Duration::days(90))
+        .format("%-d%b%y")
+        .to_string()
+        .to_uppercase();
+    let leg = |expiry: &str, strike, kind, bid, ask, amount| {
+        let suffix = if kind == OptionKind::Call { "C" } else { "P" };
+        let name = format!("BTC-{expiry}-{strike}-{suffix}");
+        build_snapshot(&name, strike, kind, (bid, amount), (ask, amount))
+    };
+    let snapshot = vec![
+        // Jelly roll across the two expiries at 40000.
+        leg(
+            &near,
+            dec!(40000),
+            OptionKind::Call,
+            dec!(4.0),
+            dec!(5.0),
+            dec!(5),
+        ),
+        leg(
+            &near,
+            dec!(40000),
+            OptionKind::Put,
+            dec!(150.0),
+            dec!(151.0),
+            dec!(5),
+        ),
+        leg(
+            &far,
+            dec!(40000),
+            OptionKind::Call,
+            dec!(10.0),
+            dec!(11.0),
+            dec!(5),
+        ),
+        leg(
+            &far,
+            dec!(40000),
+            OptionKind::Put,
+            dec!(4.0),
+            dec!(5.0),
+            dec!(5),
+        ),
+        // Box on the far expiry against the 45000 strike.
+        leg(
+            &far,
+            dec!(45000),
+            OptionKind::Call,
+            dec!(1500),
+            dec!(1700),
+            dec!(10),
+        ),
+        leg(
+            &far,
+            dec!(45000),
+            OptionKind::Put,
+            dec!(1800),
+            dec!(2000),
+            dec!(10),
+        ),
+    ];
+    let chain = OptionChain::new();
+    for inst in &snapshot {
+        chain.upsert_instrument(inst.instrument.clone());
+        chain.update_quote(&inst.instrument.instrument_name, inst.quote.clone());
+    }
+
+    let keys = |opps: Vec<deribit_arb::model::StrategyOpportunity>| {
+        let mut keys: Vec<String> = opps.iter().map(|opp| opp.combo_key()).collect();
+        keys.sort();
+        keys
+    };
+    let full = keys(suite.scan_at(&snapshot, now));
+    assert!(!full.is_empty());
+    assert_eq!(keys(suite.scan_chain_at(&chain, now)), full);
+}
+
+#[test]
+fn incremental_scan_tracks_quote_updates() {
+    let config = testkit::config(vec![
+        StrategyKind::Vertical,
+        StrategyKind::Butterfly,
+        StrategyKind::Box,
+        StrategyKind::JellyRoll,
+    ])
+    .build();
+    let suite = DetectorSuite::new(&config);
+    let now = chrono::Utc::now();
+    let expiry = (now + chrono::Duration::days(30))
+        .format("%-d%b%y")
+        .to_string()
+        .to_uppercase();
+    let chain = OptionChain::new();
+    let quote = |strike: Decimal, kind: OptionKind, bid: Decimal, ask: Decimal| {
+        let suffix = if kind == OptionKind::Call { "C" } else { "P" };
+        let name = format!("BTC-{expiry}-{strike}-{suffix}");
+        let inst = build_snapshot(&name, strike, kind, (bid, dec!(10)), (ask, dec!(10)));
+        chain.upsert_instrument(inst.instrument.clone());
+        chain.update_quote(&name, inst.quote);
+    };
+    for (i, strike) in [
+        dec!(35000),
+        dec!(40000),
+        dec!(45000),
+        dec!(50000),
+        dec!(55000),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let call = dec!(6000) - Decimal::from(i as u32) * dec!(1200);
+        quote(strike, OptionKind::Call, call - dec!(50), call + dec!(50));
+        quote(
+            strike,
+            OptionKind::Put,
+            dec!(400) + Decimal::from(i as u32) * dec!(1000),
+            dec!(500) + Decimal::from(i as u32) * dec!(1000),
+        );
+    }
+    let keys = |opps: Vec<deribit_arb::model::StrategyOpportunity>| {
+        let mut keys: Vec<String> = opps.iter().map(|opp| opp.combo_key()).collect();
+        keys.sort();
+        keys
+    };
+
+    let mut incremental = IncrementalScanner::new();
+    chain.take_dirty();
+    incremental.reset(&suite.scan_chain_at(&chain, now));
+
+    // Crossing the 45000 call against its neighbours opens verticals and flies.
+    quote(dec!(45000), OptionKind::Call, dec!(5000), dec!(5100));
+    let dirty = chain.take_dirty();
+    assert_eq!(dirty.len(), 1);
+    let updated = keys(incremental.update(&suite, &chain, &dirty, now));
+    assert!(updated.iter().any(|key| key.contains("45000-C")));
+    assert_eq!(updated, keys(suite.scan_chain_at(&chain, now)));
+
+    // Restoring the quote withdraws them again.
+    quote(dec!(45000), OptionKind::Call, dec!(3550), dec!(3650));
+    let dirty = chain.take_dirty();
+    let restored = keys(incremental.update(&suite, &chain, &dirty, now));
+    assert_eq!(restored, keys(suite.scan_chain_at(&chain, now)));
+    assert_eq!(keys(incremental.update(&suite, &chain, &[], now)), restored);
+}
+
+#[test]
+fn strategy_min_edge_override_rejects_box() {
+    let mut config = testkit::config(vec![StrategyKind::Box]).build();
+    config.strategy_thresholds.insert(
+        StrategyKind::Box,
+        StrategyThresholds {
+            min_edge_usd: Some(dec!(1000000)),
+            ..Default::default()
+        },
+    );
+    let suite = DetectorSuite::new(&config);
+    assert!(suite.scan(&box_legs()).is_empty());
+}
+
+#[test]
+fn runtime_thresholds_override_configured_min_edge() {
+    let config = testkit::config(vec![StrategyKind::Box]).build();
+    let suite = DetectorSuite::new(&config);
+    assert!(!suite.scan(&box_legs()).is_empty());
+    suite.set_thresholds(RuntimeThresholds {
+        min_edge_usd: Some(dec!(1000000)),
+        min_edge_ratio: None,
+    });
+    assert!(suite.scan(&box_legs()).is_empty());
+    suite.set_thresholds(RuntimeThresholds::default());
+    assert!(!suite.scan(&box_legs()).is_empty());
+}
+
+#[test]
+fn diagnostics_count_rejections_per_reason() {
+    let snapshot = vec![
+        build_snapshot(
+            "BTC-25DEC24-40000-C",
+            dec!(40000),
+            OptionKind::Call,
+            (dec!(5800), dec!(10)),
+            (dec!(6000), dec!(10)),
+        ),
+        build_snapshot(
+            "BTC-25DEC24-45000-C",
+            dec!(45000),
+            OptionKind::Call,
+            (dec!(5400), dec!(10)),
+            (dec!(5600), dec!(10)),
+        ),
+        // Bid below min depth: the 45000/50000 call spread cannot be sold.
+        build_snapshot(
+            "BTC-25DEC24-50000-C",
+            dec!(50000),
+            OptionKind::Call,
+            (dec!(5000), dec!(0)),
+            (dec!(5200), dec!(10)),
+        ),
+        // Put spread debit exceeds the strike width.
+        build_snapshot(
+            "BTC-25DEC24-40000-P",
+            dec!(40000),
+            OptionKind::Put,
+            (dec!(500), dec!(10)),
+            (dec!(700), dec!(10)),
+        ),
+        build_snapshot(
+            "BTC-25DEC24-45000-P",
+            dec!(45000),
+            OptionKind::Put,
+            (dec!(5800), dec!(10)),
+            (dec!(6000), dec!(10)),
+        ),
+    ];
+
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    assert!(DetectorSuite::new(&config).take_rejections().is_none());
+    config.diagnose_rejections = true;
+    config.min_edge_usd = dec!(1000000);
+    let suite = DetectorSuite::new(&config);
+    assert!(suite.scan(&snapshot).is_empty());
+    let summary = suite.take_rejections().expect("diagnostics on");
+    let vertical = |reason| summary.get(StrategyKind::Vertical, reason);
+    assert_eq!(vertical(RejectionReason::NoDepth), 1);
+    assert_eq!(vertical(RejectionReason::NegativeEdge), 1);
+    assert_eq!(vertical(RejectionReason::BelowMinEdge), 1);
+    assert_eq!(vertical(RejectionReason::RatioTooLow), 0);
+    assert!(suite.take_rejections().expect("drained").is_empty());
+
+    config.min_edge_usd = Decimal::ZERO;
+    config.min_edge_ratio = 1_000_000.0;
+    let suite = DetectorSuite::new(&config);
+    assert!(suite.scan(&snapshot).is_empty());
+    let summary = suite.take_rejections().expect("diagnostics on");
+    let (strategy, counts) = summary.by_strategy()[0];
+    assert_eq!(strategy, StrategyKind::Vertical);
+    assert_eq!(counts.ratio_too_low, 1);
+    assert_eq!(counts.total(), 3);
+
+    // The scan summary counts the same verdicts without diagnostics on.
+    config.diagnose_rejections = false;
+    let suite = DetectorSuite::new(&config);
+    let found = suite.scan(&snapshot);
+    assert!(suite.take_rejections().is_none());
+    let summary =
+        suite.take_scan_summary(snapshot.len(), &found, std::time::Duration::from_millis(12));
+    assert_eq!(summary.instruments, snapshot.len());
+    assert_eq!((summary.opportunities, summary.best_edge_usd), (0, None));
+    assert_eq!(summary.candidates(), 3);
+    assert_eq!(summary.rejections.ratio_too_low, 1);
+    assert_eq!(summary.strategies[0].strategy, StrategyKind::Vertical);
+    assert_eq!(summary.duration_ms, 12);
+    let mut line = Vec::new();
+    deribit_arb::render::write_scan_summary(&summary, &mut line).expect("json line");
+    let json: serde_json::Value = serde_json::from_slice(&line).expect("json");
+    assert_eq!(json["type"], "scan_summary");
+    assert_eq!(json["rejections"]["negative_edge"], 1);
+    assert_eq!(json["strategies"][0]["candidates"], 3);
+    let drained = suite.take_scan_summary(0, &[], std::time::Duration::ZERO);
+    assert_eq!(drained.candidates(), 0);
+}
+
+#[test]
+fn strategy_size_cap_limits_vertical() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.strategy_thresholds.insert(
+        StrategyKind::Vertical,
+        StrategyThresholds {
+            max_size_contracts: Some(dec!(0.25)),
+            ..Default::default()
+        },
+    );
+    let suite = DetectorSuite::new(&config);
+    let low = build_snapshot(
+        "BTC-25DEC24-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(5800), dec!(10)),
+        (dec!(6000), dec!(10)),
+    );
+    let high = build_snapshot(
+        "BTC-25DEC24-45000-C",
+        dec!(45000),
+        OptionKind::Call,
+        (dec!(5400), dec!(10)),
+        (dec!(5600), dec!(10)),
+    );
+    let opportunities = suite.scan(&[low, high]);
+    assert!(!opportunities.is_empty());
+    assert!(opportunities
+        .iter()
+        .all(|opp| opp.size_contracts <= dec!(0.25)));
+}
+
+#[test]
+fn size_ladder_walks_the_l2_book() {
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let suite = DetectorSuite::new(&config);
+    let mut low = build_snapshot(
+        "BTC-25DEC24-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(5800), dec!(2)),
+        (dec!(6000), dec!(2)),
+    );
+    let mut high = build_snapshot(
+        "BTC-25DEC24-45000-C",
+        dec!(45000),
+        OptionKind::Call,
+        (dec!(5400), dec!(2)),
+        (dec!(5600), dec!(2)),
+    );
+    // Each side holds half a contract at the touch, the ticket size, and
+    // one more 10 worse.
+    for (inst, bid, ask) in [
+        (&mut low, dec!(5800), dec!(6000)),
+        (&mut high, dec!(5400), dec!(5600)),
+    ] {
+        let level = |price, amount| QuoteLevel { price, amount };
+        inst.order_book = Some(OrderBook {
+            bids: vec![level(bid, dec!(0.5)), level(bid - dec!(10), dec!(1))],
+            asks: vec![level(ask, dec!(0.5)), level(ask + dec!(10), dec!(1))],
+            timestamp: chrono::Utc::now(),
+        });
+    }
+    let opportunities = suite.scan(&[low, high]);
+    let opp = opportunities
+        .iter()
+        .find(|opp| opp.strategy == StrategyKind::Vertical)
+        .expect("vertical");
+    assert_eq!(opp.size_contracts, dec!(0.5));
+    let ladder: Vec<(u32, Decimal, Option<Decimal>)> = opp
+        .size_ladder
+        .iter()
+        .map(|rung| (rung.depth_multiple, rung.size_contracts, rung.net_edge_usd))
+        .collect();
+    let edge = opp.net_edge_usd;
+    assert_eq!(
+        ladder,
+        vec![
+            (1, dec!(0.5), Some(edge.round_dp(2))),
+            // Half of each leg fills one level deeper.
+            (2, dec!(1.0), Some((edge * dec!(2) - dec!(10)).round_dp(2))),
+            (5, dec!(2.5), None),
+        ]
+    );
+}
+
+#[test]
+fn moneyness_band_drops_wing_strikes() {
+    let mut config = testkit::config(vec![StrategyKind::Box]).build();
+    assert!(!DetectorSuite::new(&config).scan(&box_legs()).is_empty());
+    config.instrument_filter = InstrumentFilter {
+        moneyness_band: Some((dec!(0.9), dec!(1.1))),
+        ..Default::default()
+    };
+    assert!(DetectorSuite::new(&config).scan(&box_legs()).is_empty());
+}
+
+#[test]
+fn min_dte_drops_expired_legs() {
+    let mut config = testkit::config(vec![StrategyKind::Box]).build();
+    config.instrument_filter = InstrumentFilter {
+        min_dte: Some(1.0),
+        ..Default::default()
+    };
+    assert!(DetectorSuite::new(&config).scan(&box_legs()).is_empty());
+}
+
+#[test]
+fn registry_suppresses_repeats_within_cooldown() {
+    let config = testkit::config(vec![StrategyKind::Box]).build();
+    let suite = DetectorSuite::new(&config);
+    let mut registry = OpportunityRegistry::new(chrono::Duration::seconds(60));
+    let t0 = chrono::Utc::now();
+
+    let first = registry.observe(suite.scan(&box_legs()), t0);
+    assert_eq!(first.fresh.len(), 1);
+    let id = first.fresh[0].id.clone();
+    let repeat = registry.observe(suite.scan(&box_legs()), t0 + chrono::Duration::seconds(30));
+    assert!(repeat.fresh.is_empty());
+    assert_eq!(repeat.suppressed, 1);
+    let after_cooldown =
+        registry.observe(suite.scan(&box_legs()), t0 + chrono::Duration::seconds(90));
+    assert_eq!(after_cooldown.fresh.len(), 1);
+
+    let gone = registry.observe(Vec::new(), t0 + chrono::Duration::seconds(120));
+    assert!(registry.is_empty());
+    assert_eq!(gone.disappeared.len(), 1);
+    assert_eq!(gone.disappeared[0].strategy, StrategyKind::Box);
+    assert_eq!(gone.disappeared[0].persisted, chrono::Duration::seconds(90));
+    assert_eq!(gone.disappeared[0].id, id);
+}
+
+#[test]
+fn registry_treats_repriced_combo_as_new() {
+    let config = testkit::config(vec![StrategyKind::Box]).build();
+    let suite = DetectorSuite::new(&config);
+    let mut registry = OpportunityRegistry::new(chrono::Duration::seconds(60));
+    let t0 = chrono::Utc::now();
+    registry.observe(suite.scan(&box_legs()), t0);
+
+    let mut repriced = box_legs();
+    if let Some(ask) = repriced[0].quote.best_ask.as_mut() {
+        ask.price = dec!(1990);
+    }
+    let update = registry.observe(suite.scan(&repriced), t0 + chrono::Duration::seconds(1));
+    assert_eq!(update.fresh.len(), 1);
+    assert_eq!(update.disappeared.len(), 1);
+    assert_ne!(update.fresh[0].id, update.disappeared[0].id);
+}
+
+#[test]
+fn diff_reports_material_edge_changes_only() {
+    let config = testkit::config(vec![StrategyKind::Box]).build();
+    let suite = DetectorSuite::new(&config);
+    let mut diff = OpportunityDiff::new(dec!(5));
+    let repriced = |ask: Decimal| {
+        let mut legs = box_legs();
+        if let Some(level) = legs[0].quote.best_ask.as_mut() {
+            level.price = ask;
+        }
+        suite.scan(&legs)
+    };
+
+    let first = repriced(dec!(2000));
+    let events = diff.observe(&first);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, DiffKind::New);
+    assert!(events[0].alerts());
+    assert!(diff.observe(&first).is_empty());
+
+    // A $20 cheaper call over half a contract is $10 more edge, under a new ID.
+    let better = repriced(dec!(1980));
+    let events = diff.observe(&better);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, DiffKind::Improved);
+    assert_eq!(events[0].id, better[0].id);
+    assert_ne!(events[0].id, first[0].id);
+    assert_eq!(
+        events[0].net_edge_usd - events[0].previous_edge_usd.unwrap(),
+        better[0].net_edge_usd - first[0].net_edge_usd
+    );
+
+    // Sub-threshold moves are absorbed until they add up.
+    assert!(diff.observe(&repriced(dec!(1984))).is_empty());
+    let events = diff.observe(&repriced(dec!(2000)));
+    assert_eq!(events[0].kind, DiffKind::Degraded);
+    assert!(!events[0].alerts());
+
+    let events = diff.observe(&[]);
+    assert_eq!(events[0].kind, DiffKind::Gone);
+    assert_eq!(events[0].id, first[0].id);
+}
+
+#[test]
+fn opportunity_ids_are_content_hashes() {
+    let config = testkit::config(vec![StrategyKind::Box]).build();
+    let first = DetectorSuite::new(&config).scan(&box_legs());
+    let again = DetectorSuite::new(&config).scan(&box_legs());
+    assert_eq!(first[0].id.len(), 16);
+    assert_eq!(first[0].id, again[0].id);
+    assert_eq!(first[0].id, first[0].content_id());
+
+    // Fees are not part of the content, prices are.
+    let mut refeed = first[0].clone();
+    refeed.fee_breakdown.total_usd += dec!(1);
+    assert_eq!(refeed.content_id(), first[0].id);
+    refeed.touches[0].price += dec!(1);
+    assert_ne!(refeed.content_id(), first[0].id);
+}
+
+#[test]
+fn weighs_leg_quote_staleness() {
+    let now = chrono::Utc::now();
+    let mut low = build_snapshot(
+        "BTC-25DEC24-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(5800), dec!(10)),
+        (dec!(6000), dec!(10)),
+    );
+    low.quote.timestamp = now - chrono::Duration::seconds(10);
+    let mut high = build_snapshot(
+        "BTC-25DEC24-45000-C",
+        dec!(45000),
+        OptionKind::Call,
+        (dec!(5400), dec!(10)),
+        (dec!(5600), dec!(10)),
+    );
+    high.quote.timestamp = now;
+    let snapshot = vec![low, high];
+
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let fresh = DetectorSuite::new(&config).scan_at(&snapshot, now);
+    assert_eq!(fresh[0].leg_quote_ages_ms, vec![10_000, 0]);
+    assert_eq!(fresh[0].staleness_penalty_usd, Decimal::ZERO);
+
+    // 1 bp per second over 10s on the $20k max ticket.
+    config.staleness_penalty_bps = 1.0;
+    let penalized = DetectorSuite::new(&config).scan_at(&snapshot, now);
+    assert_eq!(penalized[0].staleness_penalty_usd, dec!(20));
+    assert_eq!(penalized[0].net_edge_usd, fresh[0].net_edge_usd - dec!(20));
+
+    config.max_leg_skew_ms = Some(5_000);
+    config.diagnose_rejections = true;
+    let suite = DetectorSuite::new(&config);
+    assert!(suite.scan_at(&snapshot, now).is_empty());
+    let summary = suite.take_rejections().expect("diagnostics on");
+    assert_eq!(
+        summary.get(StrategyKind::Vertical, RejectionReason::StaleQuotes),
+        1
+    );
+}
+
+#[test]
+fn values_legs_at_one_reference_index() {
+    let now = chrono::Utc::now();
+    let mut low = build_snapshot(
+        "BTC-25DEC24-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(5800), dec!(10)),
+        (dec!(6000), dec!(10)),
+    );
+    low.quote.timestamp = now - chrono::Duration::seconds(5);
+    low.quote.index_price = dec!(40400);
+    let high = build_snapshot(
+        "BTC-25DEC24-45000-C",
+        dec!(45000),
+        OptionKind::Call,
+        (dec!(5400), dec!(10)),
+        (dec!(5600), dec!(10)),
+    );
+    let snapshot = vec![low, high];
+
+    // The fresher leg's 40000 index is the reference for both legs.
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let opportunities = DetectorSuite::new(&config).scan_at(&snapshot, now);
+    assert_eq!(opportunities[0].reference_index, dec!(40000));
+
+    config.max_index_divergence_bps = Some(50.0);
+    config.diagnose_rejections = true;
+    let suite = DetectorSuite::new(&config);
+    assert!(suite.scan_at(&snapshot, now).is_empty());
+    let summary = suite.take_rejections().expect("diagnostics on");
+    assert_eq!(
+        summary.get(StrategyKind::Vertical, RejectionReason::IndexDivergence),
+        1
+    );
+}
+
+/// Counts the instruments it is shown and rejects every one of them.
+struct CountingDetector(Arc<AtomicUsize>);
+
+impl Detector for CountingDetector {
+    fn strategy(&self) -> StrategyKind {
+        StrategyKind::StaleQuote
+    }
+
+    fn slice(&self) -> SliceKind {
+        SliceKind::Strike
+    }
+
+    fn scan(&self, view: &ChainView<'_>) -> Vec<deribit_arb::model::StrategyOpportunity> {
+        for _ in view.snapshot() {
+            view.reject(self.strategy(), RejectionReason::BelowMinEdge);
+        }
+        self.0.fetch_add(view.snapshot().len(), Ordering::SeqCst);
+        Vec::new()
+    }
+}
+
+#[test]
+fn runs_registered_detectors_behind_the_strategy_filter() {
+    let legs = box_legs();
+    let seen = Arc::new(AtomicUsize::new(0));
+
+    let config = testkit::config(vec![StrategyKind::Box]).build();
+    let mut suite = DetectorSuite::new(&config);
+    suite.register(Box::new(CountingDetector(seen.clone())));
+    assert!(!suite.scan(&legs).is_empty());
+    assert_eq!(seen.load(Ordering::SeqCst), 0);
+
+    let mut config = testkit::config(vec![StrategyKind::Box, StrategyKind::StaleQuote]).build();
+    config.diagnose_rejections = true;
+    let mut suite = DetectorSuite::new(&config);
+    suite.register(Box::new(CountingDetector(seen.clone())));
+    assert!(suite
+        .scan(&legs)
+        .iter()
+        .all(|opp| opp.strategy == StrategyKind::Box));
+    assert_eq!(seen.load(Ordering::SeqCst), legs.len());
+    let summary = suite.take_rejections().expect("diagnostics on");
+    assert_eq!(
+        summary.get(StrategyKind::StaleQuote, RejectionReason::BelowMinEdge),
+        legs.len() as u64
+    );
+}
+
+/// Records the groupings its view hands it.
+struct GroupingProbe(Arc<std::sync::Mutex<Vec<usize>>>);
+
+impl Detector for GroupingProbe {
+    fn strategy(&self) -> StrategyKind {
+        StrategyKind::StaleQuote
+    }
+
+    fn slice(&self) -> SliceKind {
+        SliceKind::Expiry
+    }
+
+    fn scan(&self, view: &ChainView<'_>) -> Vec<deribit_arb::model::StrategyOpportunity> {
+        let ladders = view.expiry_ladders();
+        assert!(ladders.iter().all(|ladder| ladder
+            .legs
+            .windows(2)
+            .all(|pair| pair[0].instrument.strike < pair[1].instrument.strike)));
+        *self.0.lock().unwrap() = vec![
+            ladders.len(),
+            view.pairs_by_expiry().iter().map(|l| l.pairs.len()).sum(),
+            view.pairs_by_strike().len(),
+            view.calendar_pairs().len(),
+        ];
+        Vec::new()
+    }
+}
+
+#[test]
+fn chain_view_groups_the_slice_once() {
+    let mut legs = box_legs();
+    let mut far_call = build_snapshot(
+        "BTC-25JAN25-40000-C",
+        dec!(40000),
+        OptionKind::Call,
+        (dec!(1100), dec!(10)),
+        (dec!(1300), dec!(10)),
+    );
+    far_call.instrument.expiry = legs[0].instrument.expiry + chrono::Duration::days(31);
+    legs.push(far_call);
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let config = testkit::config(vec![StrategyKind::StaleQuote]).build();
+    let mut suite = DetectorSuite::new(&config);
+    suite.register(Box::new(GroupingProbe(seen.clone())));
+    suite.scan(&legs);
+    // Call and put ladders of the near expiry plus the lone far call; two
+    // strike pairs on the near expiry, one per strike; one calendar.
+    assert_eq!(*seen.lock().unwrap(), [3, 2, 2, 1]);
+}
+
+#[test]
+fn expiry_strategies_match_golden() {
+    let config = testkit::config(vec![
+        StrategyKind::Vertical,
+        StrategyKind::Butterfly,
+        StrategyKind::Box,
+    ])
+    .build();
+    let suite = DetectorSuite::new(&config);
+    let snapshot = ChainBuilder::new(Currency::BTC, SettlementCurrency::Usdc)
+        .ladder(
+            "27DEC30",
+            OptionKind::Call,
+            &[
+                (dec!(38000), (dec!(5800), dec!(20)), (dec!(6000), dec!(20))),
+                (dec!(40000), (dec!(5400), dec!(20)), (dec!(5500), dec!(20))),
+                (dec!(42000), (dec!(2000), dec!(20)), (dec!(2100), dec!(20))),
+            ],
+        )
+        .strike_pair(
+            "27DEC30",
+            dec!(45000),
+            ((dec!(1500), dec!(20)), (dec!(1600), dec!(20))),
+            ((dec!(9800), dec!(20)), (dec!(9900), dec!(20))),
+        )
+        .option(
+            "27DEC30",
+            dec!(38000),
+            OptionKind::Put,
+            Some((dec!(3900), dec!(20))),
+            Some((dec!(4000), dec!(20))),
+        )
+        .build();
+    let opportunities = suite.scan_at(&snapshot, fixed_now());
+    assert!(!opportunities.is_empty());
+    assert_golden(
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/golden/expiry_strategies.json"
+        ),
+        &opportunities,
+    );
+}
+
+#[test]
+fn strike_strategies_match_golden() {
+    let config = testkit::config(vec![StrategyKind::Calendar, StrategyKind::JellyRoll]).build();
+    let suite = DetectorSuite::new(&config);
+    let chain = ChainBuilder::new(Currency::BTC, SettlementCurrency::Usdc)
+        .mark_iv(50.0)
+        .calendar(
+            dec!(40000),
+            OptionKind::Call,
+            ("28JUN30", (dec!(4600), dec!(20)), (dec!(4700), dec!(20))),
+            ("27DEC30", (dec!(4000), dec!(20)), (dec!(4100), dec!(20))),
+        )
+        .crossed(
+            "28JUN30",
+            dec!(40000),
+            OptionKind::Put,
+            (dec!(3600), dec!(20)),
+            (dec!(3500), dec!(20)),
+        )
+        .option(
+            "27DEC30",
+            dec!(40000),
+            OptionKind::Put,
+            Some((dec!(2000), dec!(20))),
+            Some((dec!(2100), dec!(20))),
+        )
+        .into_chain();
+    let opportunities = suite.scan_chain_at(&chain, fixed_now());
+    assert!(!opportunities.is_empty());
+    assert_golden(
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/golden/strike_strategies.json"
+        ),
+        &opportunities,
+    );
+}
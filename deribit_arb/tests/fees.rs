@@ -1,5 +1,7 @@
-use deribit_arb::fees::{FeeComputationContext, FeeEngine, LegFeeInput};
-use deribit_arb::model::{ComboSide, FillRole, SettlementCurrency};
+use deribit_arb::fees::{
+    FeeComputationContext, FeeEngine, FeeRates, FeeSchedule, HedgeFeeInput, LegFeeInput,
+};
+use deribit_arb::model::{ComboSide, ExecutionVenue, FillRole, SettlementCurrency};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
@@ -12,6 +14,7 @@ fn coin_fee_matches_spec() {
             side: ComboSide::Buy,
             settlement: SettlementCurrency::Coin,
             role: FillRole::Taker,
+            venue: ExecutionVenue::Screen,
             option_price: dec!(0.015),
             index_price: dec!(40000),
             contracts: Decimal::from(10),
@@ -20,6 +23,7 @@ fn coin_fee_matches_spec() {
             is_daily: false,
         }],
         hold_to_expiry: false,
+        hedge: None,
     };
     let breakdown = engine.compute(ctx).expect("fees");
     let leg = &breakdown.legs[0];
@@ -36,6 +40,7 @@ fn usdc_linear_fee_matches_spec() {
             side: ComboSide::Buy,
             settlement: SettlementCurrency::Usdc,
             role: FillRole::Taker,
+            venue: ExecutionVenue::Screen,
             option_price: dec!(500),
             index_price: dec!(40000),
             contracts: Decimal::from(2),
@@ -44,6 +49,7 @@ fn usdc_linear_fee_matches_spec() {
             is_daily: false,
         }],
         hold_to_expiry: false,
+        hedge: None,
     };
     let breakdown = engine.compute(ctx).expect("fees");
     assert_eq!(breakdown.legs[0].trade_fee_native, dec!(24));
@@ -53,6 +59,7 @@ fn usdc_linear_fee_matches_spec() {
 #[test]
 fn combo_discount_waives_cheaper_side() {
     let engine = FeeEngine::new();
+    let expiry = chrono::Utc::now();
     let ctx = FeeComputationContext {
         legs: vec![
             LegFeeInput {
@@ -60,11 +67,12 @@ fn combo_discount_waives_cheaper_side() {
                 side: ComboSide::Buy,
                 settlement: SettlementCurrency::Usdc,
                 role: FillRole::Taker,
+                venue: ExecutionVenue::Screen,
                 option_price: dec!(100),
                 index_price: dec!(40000),
                 contracts: Decimal::ONE,
                 contract_size: Decimal::ONE,
-                expiry: chrono::Utc::now(),
+                expiry,
                 is_daily: false,
             },
             LegFeeInput {
@@ -72,21 +80,100 @@ fn combo_discount_waives_cheaper_side() {
                 side: ComboSide::Sell,
                 settlement: SettlementCurrency::Usdc,
                 role: FillRole::Taker,
+                venue: ExecutionVenue::Screen,
                 option_price: dec!(200),
                 index_price: dec!(40000),
                 contracts: Decimal::ONE,
                 contract_size: Decimal::ONE,
-                expiry: chrono::Utc::now(),
+                expiry,
                 is_daily: false,
             },
         ],
         hold_to_expiry: false,
+        hedge: None,
     };
     let breakdown = engine.compute(ctx).expect("fees");
     assert_eq!(breakdown.combo_discount_usd, dec!(12));
     assert_eq!(breakdown.legs[0].trade_fee_native, Decimal::ZERO);
 }
 
+#[test]
+fn combo_discount_eligibility_follows_schedule_rules() {
+    let expiry = chrono::Utc::now();
+    let leg = |name: &str, side: ComboSide, expiry: chrono::DateTime<chrono::Utc>| LegFeeInput {
+        instrument_name: name.into(),
+        side,
+        settlement: SettlementCurrency::Usdc,
+        role: FillRole::Taker,
+        venue: ExecutionVenue::Screen,
+        option_price: dec!(100),
+        index_price: dec!(40000),
+        contracts: Decimal::ONE,
+        contract_size: Decimal::ONE,
+        expiry,
+        is_daily: false,
+    };
+    let schedule = FeeSchedule::default();
+    let vertical = vec![
+        leg("BTC-A", ComboSide::Buy, expiry),
+        leg("BTC-B", ComboSide::Sell, expiry),
+    ];
+    assert!(schedule.combo_discount_applies(&vertical));
+
+    // Calendars span expiries and pay full fees on both sides by default.
+    let calendar = vec![
+        leg("BTC-NEAR", ComboSide::Sell, expiry),
+        leg(
+            "BTC-FAR",
+            ComboSide::Buy,
+            expiry + chrono::Duration::days(7),
+        ),
+    ];
+    assert!(!schedule.combo_discount_applies(&calendar));
+    let breakdown = FeeEngine::with_schedule(schedule.clone())
+        .compute(FeeComputationContext {
+            legs: calendar.clone(),
+            hold_to_expiry: false,
+            hedge: None,
+        })
+        .expect("fees");
+    assert_eq!(breakdown.combo_discount_usd, Decimal::ZERO);
+    assert_eq!(breakdown.total_usd, dec!(24));
+
+    let one_sided = vec![
+        leg("BTC-A", ComboSide::Buy, expiry),
+        leg("BTC-B", ComboSide::Buy, expiry),
+    ];
+    assert!(!schedule.combo_discount_applies(&one_sided));
+    let five_legs: Vec<LegFeeInput> = ["BTC-A", "BTC-B", "BTC-C", "BTC-D", "BTC-E"]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let side = if i % 2 == 0 {
+                ComboSide::Buy
+            } else {
+                ComboSide::Sell
+            };
+            leg(name, side, expiry)
+        })
+        .collect();
+    assert!(!schedule.combo_discount_applies(&five_legs));
+
+    let path = std::env::temp_dir().join(format!(
+        "deribit_arb_combo_rules_{}.toml",
+        std::process::id()
+    ));
+    std::fs::write(
+        &path,
+        "[tiers.loose]\ncombo-discount-max-legs = 6\ncombo-discount-same-expiry = false\n",
+    )
+    .expect("write schedule");
+    let loose = FeeSchedule::resolve(Some(&path), "loose").expect("tier");
+    let _ = std::fs::remove_file(&path);
+    assert!(loose.combo_discount_applies(&calendar));
+    assert!(loose.combo_discount_applies(&five_legs));
+}
+
 #[test]
 fn delivery_fee_cap_applies() {
     let engine = FeeEngine::new();
@@ -96,6 +183,7 @@ fn delivery_fee_cap_applies() {
             side: ComboSide::Buy,
             settlement: SettlementCurrency::Usdc,
             role: FillRole::Taker,
+            venue: ExecutionVenue::Screen,
             option_price: dec!(2000),
             index_price: dec!(50000),
             contracts: Decimal::from(3),
@@ -104,8 +192,162 @@ fn delivery_fee_cap_applies() {
             is_daily: false,
         }],
         hold_to_expiry: true,
+        hedge: None,
     };
     let breakdown = engine.compute(ctx).expect("fees");
     assert!(breakdown.delivery_fee_usd > Decimal::ZERO);
     assert!(breakdown.delivery_fee_usd <= dec!(750));
 }
+
+#[test]
+fn hedge_fee_included_in_totals() {
+    let engine = FeeEngine::new();
+    let ctx = FeeComputationContext {
+        legs: vec![LegFeeInput {
+            instrument_name: "BTC-HEDGED".into(),
+            side: ComboSide::Buy,
+            settlement: SettlementCurrency::Coin,
+            role: FillRole::Taker,
+            venue: ExecutionVenue::Screen,
+            option_price: dec!(0.015),
+            index_price: dec!(40000),
+            contracts: Decimal::from(10),
+            contract_size: Decimal::ONE,
+            expiry: chrono::Utc::now(),
+            is_daily: false,
+        }],
+        hold_to_expiry: false,
+        hedge: Some(HedgeFeeInput {
+            instrument_name: "BTC-PERPETUAL".into(),
+            settlement: SettlementCurrency::Coin,
+            venue: ExecutionVenue::Screen,
+            notional_usd: dec!(20000),
+            index_price: dec!(40000),
+            expiry: None,
+        }),
+    };
+    let breakdown = engine.compute(ctx).expect("fees");
+    assert_eq!(breakdown.hedge_fee_usd, dec!(10));
+    assert_eq!(breakdown.hedge_fee, dec!(0.00025));
+    assert_eq!(breakdown.total_usd, dec!(130));
+}
+
+fn usdc_leg(side: ComboSide, role: FillRole, option_price: Decimal) -> LegFeeInput {
+    LegFeeInput {
+        instrument_name: format!("BTC-{side}"),
+        side,
+        settlement: SettlementCurrency::Usdc,
+        role,
+        venue: ExecutionVenue::Screen,
+        option_price,
+        index_price: dec!(40000),
+        contracts: Decimal::ONE,
+        contract_size: Decimal::ONE,
+        expiry: chrono::Utc::now(),
+        is_daily: false,
+    }
+}
+
+#[test]
+fn mixed_settlement_legs_convert_through_index() {
+    let engine = FeeEngine::new();
+    let mut coin = usdc_leg(ComboSide::Sell, FillRole::Taker, dec!(0.04));
+    coin.settlement = SettlementCurrency::Coin;
+    let mut usdc = usdc_leg(ComboSide::Buy, FillRole::Taker, dec!(1300));
+    usdc.contracts = Decimal::from(100);
+    usdc.contract_size = dec!(0.01);
+    let ctx = FeeComputationContext {
+        legs: vec![coin, usdc],
+        hold_to_expiry: false,
+        hedge: None,
+    };
+    let breakdown = engine.compute(ctx).expect("fees");
+    // Each leg keeps its own settlement; no combo spans both books.
+    assert_eq!(breakdown.legs[0].trade_fee_native, dec!(0.0003));
+    assert_eq!(breakdown.legs[1].trade_fee_native, dec!(12));
+    assert_eq!(breakdown.combo_discount_usd, Decimal::ZERO);
+    assert_eq!(breakdown.total_usd, dec!(24));
+    // Totals are in the first leg's settlement: 24 USD at 40k is 0.0006 BTC.
+    assert_eq!(breakdown.total_native, dec!(0.0006));
+}
+
+#[test]
+fn fee_tier_applies_maker_rate_and_combo_rule() {
+    let path = std::env::temp_dir().join(format!("deribit_arb_fees_{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+[tiers.vip]
+combo-discount = false
+
+[tiers.vip.usdc]
+taker-rate = "0.0002"
+maker-rate = "0.0001"
+premium-cap = "0.125"
+delivery-rate = "0.00015"
+delivery-cap = "0.125"
+"#,
+    )
+    .expect("write schedule");
+    let schedule = FeeSchedule::resolve(Some(&path), "vip");
+    let missing = FeeSchedule::resolve(Some(&path), "vip2");
+    let default = FeeSchedule::resolve(Some(&path), "default");
+    std::fs::remove_file(&path).ok();
+    let schedule = schedule.expect("vip tier");
+    assert!(missing.is_err());
+    assert_eq!(default.expect("built-in tier"), FeeSchedule::default());
+    // Coin rates fall back to the base tier.
+    assert_eq!(schedule.coin, FeeSchedule::default().coin);
+
+    let engine = FeeEngine::with_schedule(schedule);
+    let ctx = FeeComputationContext {
+        legs: vec![
+            usdc_leg(ComboSide::Buy, FillRole::Maker, dec!(100)),
+            usdc_leg(ComboSide::Sell, FillRole::Taker, dec!(200)),
+        ],
+        hold_to_expiry: false,
+        hedge: None,
+    };
+    let breakdown = engine.compute(ctx).expect("fees");
+    assert_eq!(breakdown.legs[0].trade_fee_usd, dec!(4));
+    assert_eq!(breakdown.legs[1].trade_fee_usd, dec!(8));
+    assert_eq!(breakdown.combo_discount_usd, Decimal::ZERO);
+    assert_eq!(breakdown.total_usd, dec!(12));
+}
+
+#[test]
+fn block_trades_skip_combo_discount_and_futures_pay_settlement() {
+    let schedule = FeeSchedule {
+        usdc: FeeRates {
+            block_rate: dec!(0.0002),
+            ..FeeRates::default()
+        },
+        ..FeeSchedule::default()
+    };
+    let engine = FeeEngine::with_schedule(schedule);
+    let block = |side, price| LegFeeInput {
+        venue: ExecutionVenue::Block,
+        ..usdc_leg(side, FillRole::Taker, price)
+    };
+    let ctx = FeeComputationContext {
+        legs: vec![
+            block(ComboSide::Buy, dec!(100)),
+            block(ComboSide::Sell, dec!(200)),
+        ],
+        hold_to_expiry: true,
+        hedge: Some(HedgeFeeInput {
+            instrument_name: "BTC_USDC-27DEC24".into(),
+            settlement: SettlementCurrency::Usdc,
+            venue: ExecutionVenue::Block,
+            notional_usd: dec!(20000),
+            index_price: dec!(40000),
+            expiry: Some(chrono::Utc::now() + chrono::Duration::days(30)),
+        }),
+    };
+    let breakdown = engine.compute(ctx).expect("fees");
+    assert_eq!(breakdown.combo_discount_usd, Decimal::ZERO);
+    assert_eq!(breakdown.legs[0].trade_fee_usd, dec!(8));
+    assert_eq!(breakdown.legs[1].trade_fee_usd, dec!(8));
+    // 0.025% block rate plus 0.025% settlement on $20k.
+    assert_eq!(breakdown.hedge_fee_usd, dec!(10));
+}
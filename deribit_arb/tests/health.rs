@@ -0,0 +1,112 @@
+use chrono::{DateTime, Duration, Utc};
+use deribit_arb::health::{HealthMonitor, HealthTransition, TripReason};
+use deribit_arb::model::{
+    ChainSnapshot, Currency, Instrument, InstrumentSnapshot, OptionKind, Quote, SettlementCurrency,
+    StrategyKind,
+};
+use deribit_arb::testkit;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+fn snapshot(quotes: &[(&str, Decimal, DateTime<Utc>)], now: DateTime<Utc>) -> ChainSnapshot {
+    let instruments = quotes
+        .iter()
+        .map(|(name, index, quoted_at)| InstrumentSnapshot {
+            instrument: Instrument {
+                instrument_name: name.to_string(),
+                currency: Currency::BTC,
+                is_usdc_settled: true,
+                is_combo: false,
+                option_kind: OptionKind::Call,
+                strike: dec!(40000),
+                expiry: now + Duration::days(30),
+                contract_size: dec!(0.01),
+                settlement_currency: SettlementCurrency::Usdc,
+                tick_size: dec!(5),
+                min_trade_amount: dec!(0.01),
+            },
+            quote: Quote {
+                best_bid: None,
+                best_ask: None,
+                mark_iv: None,
+                bid_iv: None,
+                ask_iv: None,
+                interest_rate: None,
+                timestamp: *quoted_at,
+                index_price: *index,
+            },
+            order_book: None,
+        })
+        .collect();
+    ChainSnapshot {
+        timestamp: now,
+        instruments,
+    }
+}
+
+#[test]
+fn breaker_trips_on_stale_chain_and_resets_after_cooldown() {
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let health = HealthMonitor::new();
+    let now = Utc::now();
+    let healthy = snapshot(&[("BTC_USDC-A", dec!(40000), now)], now);
+    assert_eq!(health.check(&config, &healthy, (0, 0), now), None);
+
+    let later = now + Duration::seconds(60);
+    let transition = health.check(&config, &healthy, (0, 0), later);
+    assert_eq!(
+        transition,
+        Some(HealthTransition::Tripped(TripReason::StaleData {
+            age_secs: 60
+        }))
+    );
+    assert!(health.is_tripped());
+
+    // Fresh data alone does not reset the breaker before the cool-down.
+    let fresh = snapshot(&[("BTC_USDC-A", dec!(40000), later)], later);
+    assert_eq!(health.check(&config, &fresh, (0, 0), later), None);
+    assert!(health.is_tripped());
+    let reset_at = later + Duration::seconds(config.breaker_cooldown_secs as i64);
+    let fresh = snapshot(&[("BTC_USDC-A", dec!(40000), reset_at)], reset_at);
+    assert_eq!(
+        health.check(&config, &fresh, (0, 0), reset_at),
+        Some(HealthTransition::Reset)
+    );
+    assert!(!health.is_tripped());
+}
+
+#[test]
+fn breaker_trips_on_api_errors_and_index_divergence() {
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let now = Utc::now();
+    let healthy = snapshot(&[("BTC_USDC-A", dec!(40000), now)], now);
+
+    let health = HealthMonitor::new();
+    assert_eq!(health.check(&config, &healthy, (10, 1), now), None);
+    // Only the calls since the previous check count: 6 of 10 failed.
+    assert!(matches!(
+        health.check(&config, &healthy, (20, 7), now),
+        Some(HealthTransition::Tripped(TripReason::ApiErrors {
+            errors: 6,
+            calls: 10
+        }))
+    ));
+
+    let health = HealthMonitor::new();
+    let diverged = snapshot(
+        &[
+            ("BTC_USDC-A", dec!(40000), now),
+            ("BTC-A", dec!(40400), now),
+            // Stale quotes are not compared.
+            ("BTC-B", dec!(30000), now - Duration::seconds(120)),
+        ],
+        now,
+    );
+    match health.check(&config, &diverged, (0, 0), now) {
+        Some(HealthTransition::Tripped(TripReason::IndexDivergence { currency, bps })) => {
+            assert_eq!(currency, Currency::BTC);
+            assert!((bps - 100.0).abs() < 1e-6);
+        }
+        other => panic!("expected index divergence, got {other:?}"),
+    }
+}
@@ -0,0 +1,46 @@
+use deribit_arb::chain::ChainStats;
+use deribit_arb::metrics::{self, metrics};
+use std::time::Duration;
+
+#[tokio::test]
+async fn metrics_endpoint_serves_prometheus_text() {
+    let registry = metrics();
+    registry.record_chain(&ChainStats {
+        instrument_count: 42,
+        instruments_with_quotes: 40,
+        instruments_fresh_10s: 38,
+        bid_levels: 40,
+        ask_levels: 39,
+    });
+    registry.record_risk_rejection("ticket_cap");
+    registry.record_api_call("public/ticker", Duration::from_millis(30), true);
+    registry.record_api_call("public/ticker", Duration::from_millis(700), false);
+    registry.record_ws_reconnect();
+
+    let addr = metrics::serve("127.0.0.1:0".parse().unwrap())
+        .await
+        .expect("serve");
+    let body = reqwest::get(format!("http://{addr}/metrics"))
+        .await
+        .expect("scrape")
+        .text()
+        .await
+        .expect("body");
+
+    assert!(body.contains("# TYPE deribit_arb_chain_instruments gauge"));
+    assert!(body.contains("deribit_arb_chain_instruments 42"));
+    assert!(body.contains("deribit_arb_risk_rejections_total{reason=\"ticket_cap\"} 1"));
+    assert!(body.contains(
+        "deribit_arb_api_request_duration_seconds_bucket{method=\"public/ticker\",le=\"0.05\"} 1"
+    ));
+    assert!(
+        body.contains("deribit_arb_api_request_duration_seconds_count{method=\"public/ticker\"} 2")
+    );
+    assert!(body.contains("deribit_arb_api_errors_total{method=\"public/ticker\"} 1"));
+    assert!(body.contains("deribit_arb_ws_reconnects_total 1"));
+
+    let missing = reqwest::get(format!("http://{addr}/other"))
+        .await
+        .expect("request");
+    assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+}
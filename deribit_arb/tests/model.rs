@@ -0,0 +1,118 @@
+use deribit_arb::model::{
+    format_strike, Currency, ParseInstrumentError, ParsedInstrumentName, SettlementCurrency,
+};
+use rust_decimal_macros::dec;
+use std::str::FromStr;
+
+#[test]
+fn currency_accepts_listed_altcoins() {
+    assert_eq!(Currency::from_str("btc").unwrap(), Currency::BTC);
+    let sol = Currency::from_str("SOL").unwrap();
+    assert_eq!(sol, Currency::SOL);
+    assert_eq!(sol.to_string(), "SOL");
+    assert_eq!(sol.instruments_query_currency(), "USDC");
+    assert_eq!(Currency::ETH.instruments_query_currency(), "ETH");
+    assert_eq!(sol.index_name(), "sol_usdc");
+    assert_eq!(Currency::BTC.index_name(), "btc_usd");
+    assert_eq!(
+        sol.perpetual_instrument(SettlementCurrency::Usdc),
+        "SOL_USDC-PERPETUAL"
+    );
+}
+
+#[test]
+fn currency_rejects_malformed_codes() {
+    assert!(Currency::from_str("").is_err());
+    assert!(Currency::from_str("B").is_err());
+    assert!(Currency::from_str("SOL_USDC").is_err());
+    assert!(Currency::from_str("VERYLONGCODE1").is_err());
+}
+
+#[test]
+fn currency_serializes_as_code() {
+    let json = serde_json::to_string(&Currency::XRP).unwrap();
+    assert_eq!(json, "\"XRP\"");
+    let parsed: Currency = serde_json::from_str("\"avax\"").unwrap();
+    assert_eq!(parsed.as_str(), "AVAX");
+}
+
+#[test]
+fn parses_altcoin_instrument_name() {
+    let parsed = ParsedInstrumentName::from_str("XRP-27DEC24-2-C").unwrap();
+    assert_eq!(parsed.currency, Currency::XRP);
+}
+
+#[test]
+fn parses_usdc_linear_instrument_name() {
+    let parsed = ParsedInstrumentName::from_str("BTC_USDC-27DEC24-60000-P").unwrap();
+    assert_eq!(parsed.currency, Currency::BTC);
+    assert_eq!(parsed.strike.to_string(), "60000");
+}
+
+#[test]
+fn parses_daily_and_decimal_strike_names() {
+    let parsed = ParsedInstrumentName::from_str("XRP_USDC-7JUN25-0d625-C").unwrap();
+    assert_eq!(parsed.currency, Currency::XRP);
+    assert_eq!(parsed.settlement, SettlementCurrency::Usdc);
+    assert_eq!(parsed.day, 7);
+    assert_eq!(parsed.strike.to_string(), "0.625");
+    assert_eq!(parsed.to_string(), "XRP_USDC-7JUN25-0d625-C");
+
+    let sol = ParsedInstrumentName::from_str("SOL_USDC-30MAY25-150-P").unwrap();
+    assert_eq!(sol.currency, Currency::SOL);
+    assert_eq!(sol.to_string(), "SOL_USDC-30MAY25-150-P");
+    let coin = ParsedInstrumentName::from_str("BTC-7JUN24-64000-C").unwrap();
+    assert_eq!(coin.settlement, SettlementCurrency::Coin);
+    assert_eq!(format_strike(dec!(2.50)), "2d5");
+
+    for name in [
+        "XRP_USDC-7JUN25-0d6d2-C",
+        "XRP_USDC-7JUN25-0.625-C",
+        "XRP_USDC-7JUN25-d-C",
+    ] {
+        assert!(ParsedInstrumentName::from_str(name).is_err(), "{name}");
+    }
+    assert!(matches!(
+        ParsedInstrumentName::from_str("BTC_DAI-27DEC24-60000-P"),
+        Err(ParseInstrumentError::UnknownSettlement(code)) if code == "DAI"
+    ));
+}
+
+#[test]
+fn parses_usdt_and_eurr_settlements() {
+    for (name, settlement) in [
+        ("BTC_USDT-27DEC24-60000-P", SettlementCurrency::Usdt),
+        ("ETH_EURR-27DEC24-3000-C", SettlementCurrency::Eurr),
+    ] {
+        let stablecoin = settlement.stablecoin().unwrap();
+        assert!(name.contains(stablecoin));
+        assert!(ParsedInstrumentName::from_str(name).is_ok());
+        assert_eq!(
+            SettlementCurrency::from_stablecoin(&stablecoin.to_ascii_lowercase()),
+            Some(settlement)
+        );
+        assert_eq!(
+            SettlementCurrency::from_str(&settlement.to_string()).unwrap(),
+            settlement
+        );
+    }
+    assert!(ParsedInstrumentName::from_str("BTC_DAI-27DEC24-60000-P").is_err());
+    assert_eq!(
+        SettlementCurrency::from_str("coin").unwrap(),
+        SettlementCurrency::Coin
+    );
+    assert!(SettlementCurrency::from_str("dai").is_err());
+    assert!(SettlementCurrency::Usdt.pays_usd() && !SettlementCurrency::Eurr.pays_usd());
+    assert_eq!(
+        Currency::BTC.perpetual_instrument(SettlementCurrency::Usdt),
+        "BTC_USDT-PERPETUAL"
+    );
+    assert_eq!(
+        Currency::BTC.instrument_books(&[SettlementCurrency::Coin, SettlementCurrency::Eurr]),
+        ["BTC", "EURR"]
+    );
+    assert_eq!(
+        Currency::SOL.instrument_books(&[SettlementCurrency::Coin, SettlementCurrency::Usdc]),
+        ["USDC"]
+    );
+}
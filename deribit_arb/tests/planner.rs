@@ -1,34 +1,39 @@
-use deribit_arb::config::{AppConfig, Environment};
-use deribit_arb::exec::{ExecutionPlanner, MockComboApi};
+use chrono::Duration;
+use deribit_arb::audit::{AuditEvent, AuditLog};
+use deribit_arb::config::ExecutionMode;
+use deribit_arb::detect::DetectorSuite;
+use deribit_arb::exec::{
+    combo_signature, snap_to_ticks, AdverseSelectionTracker, CancelReason, ComboCache,
+    ExecutionPlanner, MakerAction, MakerQuoter, MockComboApi, RecheckRejection, TickRejection,
+};
+use deribit_arb::greeks::PositionGreeks;
+use deribit_arb::ledger::SimulatedLedger;
+use deribit_arb::margin::estimate_margin;
 use deribit_arb::model::{
-    ComboExecutionPlan, ComboLeg, ComboSide, Currency, FeeBreakdown, FillRole, LegFee,
-    OrderTimeInForce, SettlementCurrency, StrategyKind, StrategyOpportunity,
+    BlockRfqQuote, ComboExecutionPlan, ComboLeg, ComboSide, Currency, FeeBreakdown, FillRole,
+    GroupKey, Instrument, InstrumentSnapshot, LegFee, LegTouch, OpportunityView, OptionKind,
+    OrderTimeInForce, Position, Quote, QuoteLevel, SettlementCurrency, SortKey, StrategyKind,
+    StrategyOpportunity, Trade, OPPORTUNITY_SCHEMA_VERSION,
+};
+use deribit_arb::notify::{
+    Alert, AlertKind, Notifier, NotifyFilter, NotifySink, SlackSink, TelegramSink,
 };
+use deribit_arb::posttrade::{
+    validate, PendingCheck, PostTradeOutcome, PostTradeValidator, PostTradeVerdict, TradeHistory,
+};
+use deribit_arb::risk::{BudgetKind, RiskManager, RiskRejection};
+use deribit_arb::testkit::{self, fixed_now};
+use deribit_arb::webhook::WebhookSink;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-
-fn base_config() -> AppConfig {
-    AppConfig {
-        environment: Environment::Testnet,
-        api_key: None,
-        api_secret: None,
-        currencies: vec![Currency::BTC],
-        settlements: vec![SettlementCurrency::Usdc],
-        dry_run: true,
-        max_ticket_usd: dec!(20000),
-        min_edge_usd: dec!(50),
-        min_edge_ratio: 1.5,
-        hold_to_expiry: false,
-        strategy_filter: deribit_arb::model::StrategyFilter {
-            include: vec![StrategyKind::Vertical],
-        },
-        max_concurrent_combos: 3,
-        min_depth_contracts: 1,
-    }
-}
+use std::sync::Arc;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 fn sample_opportunity(size: Decimal) -> StrategyOpportunity {
     StrategyOpportunity {
+        id: String::new(),
+        schema_version: OPPORTUNITY_SCHEMA_VERSION,
         strategy: StrategyKind::Vertical,
         currency: Currency::BTC,
         settlement: SettlementCurrency::Usdc,
@@ -72,6 +77,8 @@ fn sample_opportunity(size: Decimal) -> StrategyOpportunity {
             combo_discount_usd: Decimal::ZERO,
             delivery_fee: Decimal::ZERO,
             delivery_fee_usd: Decimal::ZERO,
+            hedge_fee: Decimal::ZERO,
+            hedge_fee_usd: Decimal::ZERO,
             total_native: dec!(2),
             total_usd: dec!(2),
         },
@@ -86,13 +93,20 @@ fn sample_opportunity(size: Decimal) -> StrategyOpportunity {
             tif: OrderTimeInForce::IOC,
             price_limit: dec!(100),
             dry_run: true,
+            hedge: None,
         },
+        estimated_margin_usd: None,
+        size_ladder: Vec::new(),
+        leg_quote_ages_ms: Vec::new(),
+        staleness_penalty_usd: Decimal::ZERO,
+        annualized_return: None,
+        fx_usd: Decimal::ONE,
     }
 }
 
 #[tokio::test]
 async fn planner_generates_preview() {
-    let config = base_config();
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
     let mock = MockComboApi::new();
     let planner = ExecutionPlanner::new(&mock, &config);
     let report = planner
@@ -103,11 +117,1293 @@ async fn planner_generates_preview() {
     assert!(!report.submitted);
 }
 
+#[tokio::test]
+async fn planner_reports_leg_slippage_against_touch() {
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let mock = MockComboApi::new();
+    mock.leg_prices
+        .lock()
+        .insert("BTC-25DEC24-40000-C".into(), dec!(1210));
+    mock.leg_prices
+        .lock()
+        .insert("BTC-25DEC24-45000-C".into(), dec!(1090));
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.touches = vec![
+        LegTouch {
+            instrument_name: "BTC-25DEC24-40000-C".into(),
+            side: ComboSide::Buy,
+            price: dec!(1200),
+            size_contracts: dec!(2),
+        },
+        LegTouch {
+            instrument_name: "BTC-25DEC24-45000-C".into(),
+            side: ComboSide::Sell,
+            price: dec!(1100),
+            size_contracts: dec!(2),
+        },
+    ];
+    let report = ExecutionPlanner::new(&mock, &config)
+        .plan(&opportunity)
+        .await
+        .expect("plan success");
+
+    // Buying 10 above and selling 10 below the touch, two contracts each.
+    assert_eq!(report.legs.len(), 2);
+    assert_eq!(report.legs[0].preview_price, Some(dec!(1210)));
+    assert_eq!(report.legs[0].slippage_usd, Some(dec!(20)));
+    assert_eq!(report.legs[1].slippage_usd, Some(dec!(20)));
+    let bps = report.legs[1].slippage_bps.expect("bps");
+    assert!((bps - 90.909).abs() < 0.01, "{bps}");
+    assert_eq!(report.slippage_usd, dec!(40));
+    assert_eq!(report.expected_edge_usd, dec!(60));
+}
+
+#[tokio::test]
+async fn planner_reuses_combos_by_leg_signature() {
+    let path = std::env::temp_dir().join(format!("deribit_arb_combos_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let mock = MockComboApi::new();
+    let opportunity = sample_opportunity(Decimal::from(2));
+    let combos = ComboCache::open(&path).expect("fresh cache");
+    let first = ExecutionPlanner::new(&mock, &config)
+        .with_combos(combos.clone())
+        .plan(&opportunity)
+        .await
+        .expect("plan success");
+    assert_eq!(first.combo_id.as_deref(), Some("combo-1"));
+
+    // Leg order does not change the signature, so the cached id is reused.
+    let mut reordered = opportunity.clone();
+    reordered.legs.reverse();
+    let second = ExecutionPlanner::new(&mock, &config)
+        .with_combos(combos)
+        .plan(&reordered)
+        .await
+        .expect("plan success");
+    assert_eq!(second.combo_id, first.combo_id);
+    assert_eq!(mock.combos.lock().len(), 1);
+
+    // A restart reloads the cache instead of creating the combo again.
+    let restored = ComboCache::open(&path).expect("reload cache");
+    std::fs::remove_file(&path).ok();
+    assert_eq!(
+        restored.get(&combo_signature(opportunity.settlement, &opportunity.legs)),
+        first.combo_id
+    );
+    let fresh = MockComboApi::new();
+    let report = ExecutionPlanner::new(&fresh, &config)
+        .with_combos(restored)
+        .plan(&opportunity)
+        .await
+        .expect("plan success");
+    assert_eq!(report.combo_id, first.combo_id);
+    assert!(fresh.combos.lock().is_empty());
+
+    // Without a cache, the listed combo is found before creating another.
+    ExecutionPlanner::new(&mock, &config)
+        .plan(&opportunity)
+        .await
+        .expect("plan success");
+    assert_eq!(mock.combos.lock().len(), 1);
+
+    // A different side is a different combo.
+    let mut flipped = opportunity.clone();
+    flipped.legs[0].side = ComboSide::Sell;
+    flipped.legs[1].side = ComboSide::Buy;
+    let report = ExecutionPlanner::new(&mock, &config)
+        .plan(&flipped)
+        .await
+        .expect("plan success");
+    assert_eq!(report.combo_id.as_deref(), Some("combo-2"));
+}
+
 #[tokio::test]
 async fn planner_rejects_insufficient_depth() {
-    let config = base_config();
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
     let mock = MockComboApi::new();
     let planner = ExecutionPlanner::new(&mock, &config);
     let result = planner.plan(&sample_opportunity(Decimal::new(5, 1))).await;
     assert!(result.is_err());
 }
+
+#[test]
+fn planner_fits_size_to_leg_trade_amounts() {
+    let config = testkit::config(vec![StrategyKind::Vertical]).build();
+    let mock = MockComboApi::new();
+    let planner = ExecutionPlanner::new(&mock, &config);
+    let now = chrono::Utc::now();
+    let mut legs = vec![
+        leg_snapshot("BTC-25DEC24-40000-C", now),
+        leg_snapshot("BTC-25DEC24-45000-C", now),
+    ];
+    legs[0].instrument.min_trade_amount = dec!(0.1);
+    legs[1].instrument.min_trade_amount = dec!(0.25);
+
+    // 0.1 and 0.25 steps share a 0.5 combo step, so 2.7 trades as 2.5.
+    let mut opportunity = sample_opportunity(dec!(2.7));
+    opportunity.execution_plan.create_payload = serde_json::json!({ "amount": 2.7 });
+    let sized = planner
+        .fit_trade_amounts(&opportunity, &legs)
+        .expect("legal size");
+    assert_eq!(sized.size_contracts, dec!(2.5));
+    assert_eq!(sized.net_edge_usd.round_dp(6), dec!(92.592593));
+    assert_eq!(sized.execution_plan.create_payload["amount"], "2.5");
+
+    // Contract size multiplies into the traded amount: 0.01 BTC contracts
+    // with a 0.1 BTC minimum need combos in steps of 10.
+    legs[1].instrument.contract_size = dec!(0.01);
+    legs[1].instrument.min_trade_amount = dec!(0.1);
+    let err = planner
+        .fit_trade_amounts(&sample_opportunity(dec!(7)), &legs)
+        .expect_err("below legal size");
+    assert!(err.to_string().contains("smallest legal combo size 10"));
+}
+
+#[test]
+fn risk_caps_currency_notional_and_daily_loss() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.max_currency_notional_usd = Some(dec!(15000));
+    config.daily_loss_limit_usd = Some(dec!(500));
+    let risk = RiskManager::new();
+    let now = chrono::Utc::now();
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.expiry = vec![now + chrono::Duration::days(30)];
+
+    risk.evaluate(&config, &opportunity, None, now)
+        .expect("first combo fits");
+    risk.record_fill(&opportunity.combo_key(), Decimal::ZERO);
+    // The filled combo frees its slot but still counts towards BTC notional.
+    assert_eq!(risk.snapshot().live_combos, 0);
+    let rejection = risk
+        .evaluate(&config, &opportunity, None, now)
+        .expect_err("notional cap");
+    assert!(matches!(
+        rejection,
+        RiskRejection::CurrencyNotional { notional, .. } if notional == dec!(20000)
+    ));
+
+    let mut eth = opportunity.clone();
+    eth.currency = Currency::ETH;
+    risk.evaluate(&config, &eth, None, now)
+        .expect("separate cap");
+    risk.release(&eth.combo_key());
+
+    risk.record_pnl(dec!(-600), now);
+    let rejection = risk
+        .evaluate(&config, &eth, None, now)
+        .expect_err("daily loss hard stop");
+    assert_eq!(rejection.label(), "daily_loss_limit");
+    let tomorrow = now + chrono::Duration::days(1);
+    assert!(!matches!(
+        risk.evaluate(&config, &eth, None, tomorrow),
+        Err(RiskRejection::DailyLossLimit { .. })
+    ));
+}
+
+#[test]
+fn ledger_settlements_trip_daily_loss_limit() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.daily_loss_limit_usd = Some(dec!(500));
+    let risk = RiskManager::new();
+    let now = chrono::Utc::now();
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.expiry = vec![now + chrono::Duration::hours(1)];
+
+    let mut ledger = SimulatedLedger::new(dec!(1000000));
+    assert!(ledger.fill(&opportunity, dec!(-300), now));
+    assert!(ledger.fill(&opportunity, dec!(-300), now));
+    ledger.settle_into(now, &risk);
+    assert_eq!(risk.snapshot().daily_pnl, Decimal::ZERO);
+    risk.evaluate(&config, &opportunity, None, now)
+        .expect("open combos realize nothing");
+    risk.release(&opportunity.combo_key());
+
+    let settled_at = now + chrono::Duration::hours(2);
+    ledger.settle_into(settled_at, &risk);
+    assert_eq!(risk.snapshot().daily_pnl, dec!(-600));
+    let rejection = risk
+        .evaluate(&config, &opportunity, None, settled_at)
+        .expect_err("settled losses stop approvals");
+    assert_eq!(rejection.label(), "daily_loss_limit");
+}
+
+#[tokio::test]
+async fn execution_budget_defers_to_next_window() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.max_combos_per_hour = Some(2);
+    config.max_daily_notional_usd = Some(dec!(25000));
+    let now = fixed_now();
+    let opportunity = sample_opportunity(Decimal::from(2));
+    let risk = RiskManager::new();
+    for _ in 0..2 {
+        risk.evaluate(&config, &opportunity, None, now)
+            .expect("within budget");
+        risk.release(&opportunity.combo_key());
+    }
+    // Released combos keep their budget, so a failing loop still runs dry.
+    let deferred = risk.evaluate(&config, &opportunity, None, now + Duration::minutes(5));
+    assert_eq!(
+        deferred,
+        Err(RiskRejection::Budget {
+            budget: BudgetKind::CombosPerHour,
+            used: dec!(2),
+            max: dec!(2),
+            resumes_at: now + Duration::hours(1),
+        })
+    );
+    assert_eq!(deferred.unwrap_err().label(), "budget");
+    // An hour on the combo window has room, but the day's notional does not.
+    assert!(matches!(
+        risk.evaluate(&config, &opportunity, None, now + Duration::minutes(61)),
+        Err(RiskRejection::Budget {
+            budget: BudgetKind::NotionalPerDay,
+            ..
+        })
+    ));
+    risk.evaluate(&config, &opportunity, None, now + Duration::days(1))
+        .expect("new UTC day");
+
+    config.max_orders_per_minute = Some(1);
+    config.execution_mode = ExecutionMode::Maker;
+    let mock = MockComboApi::new();
+    let mut maker = MakerQuoter::new(&mock, &config).with_risk(RiskManager::new());
+    let legs = |at| {
+        vec![
+            leg_snapshot("BTC-25DEC24-40000-C", at),
+            leg_snapshot("BTC-25DEC24-45000-C", at),
+        ]
+    };
+    let mut other = opportunity.clone();
+    other.strategy = StrategyKind::Butterfly;
+    let targets = [opportunity, other];
+    assert_eq!(maker.sync(&targets, &legs(now), now).await.len(), 1);
+    let later = now + Duration::seconds(30);
+    assert!(maker.sync(&targets, &legs(later), later).await.is_empty());
+    let later = now + Duration::seconds(61);
+    let placed = maker.sync(&targets, &legs(later), later).await;
+    assert!(matches!(&placed[..], [MakerAction::Placed { .. }]));
+}
+
+#[test]
+fn risk_widens_min_edge_in_high_vol_regime() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.high_vol_threshold = Some(80.0);
+    let risk = RiskManager::new();
+    let now = chrono::Utc::now();
+    let opportunity = sample_opportunity(Decimal::from(2));
+
+    // Edge 100 clears the 60 minimum, but not the doubled 120 one.
+    config.min_edge_usd = dec!(60);
+    risk.set_volatility(Currency::BTC, 65.0);
+    risk.evaluate(&config, &opportunity, None, now)
+        .expect("calm regime");
+    risk.release(&opportunity.combo_key());
+
+    risk.set_volatility(Currency::BTC, 92.0);
+    let rejection = risk
+        .evaluate(&config, &opportunity, None, now)
+        .expect_err("high-vol regime");
+    assert!(matches!(
+        rejection,
+        RiskRejection::VolRegime { required, .. } if required == dec!(120)
+    ));
+    assert_eq!(rejection.label(), "vol_regime");
+
+    let mut eth = opportunity.clone();
+    eth.currency = Currency::ETH;
+    risk.evaluate(&config, &eth, None, now)
+        .expect("no reading for ETH");
+}
+
+#[test]
+fn margin_estimate_feeds_utilization_cap() {
+    let now = chrono::Utc::now();
+    let mut legs = vec![
+        leg_snapshot("BTC-25DEC24-40000-C", now),
+        leg_snapshot("BTC-25DEC24-45000-C", now),
+    ];
+    legs[1].instrument.strike = dec!(45000);
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    assert_eq!(estimate_margin(&opportunity, &legs, now), None);
+    for leg in &mut legs {
+        leg.quote.mark_iv = Some(60.0);
+    }
+
+    // The long 40000 call caps the short 45000 call's loss.
+    let spread = estimate_margin(&opportunity, &legs, now).expect("spread margin");
+    assert!(spread > Decimal::ZERO && spread < dec!(10000), "{spread}");
+    let mut naked = opportunity.clone();
+    naked.legs.remove(0);
+    let naked = estimate_margin(&naked, &legs, now).expect("naked margin");
+    assert!(naked > spread, "{naked} vs {spread}");
+
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.max_margin_utilization = Some(0.5);
+    let risk = RiskManager::new();
+    opportunity.estimated_margin_usd = Some(spread);
+    let rejection = risk
+        .evaluate(&config, &opportunity, None, now)
+        .expect_err("no equity yet");
+    assert_eq!(rejection.label(), "margin_unavailable");
+
+    risk.set_equity(spread * dec!(3));
+    risk.evaluate(&config, &opportunity, None, now)
+        .expect("a third of equity");
+    let rejection = risk
+        .evaluate(&config, &opportunity, None, now)
+        .expect_err("two thirds of equity");
+    assert!(matches!(
+        rejection,
+        RiskRejection::MarginUtilization { projected, max } if projected > 0.66 && max == 0.5
+    ));
+}
+
+#[test]
+fn risk_caps_net_greeks() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.max_net_vega_usd = Some(dec!(100));
+    let risk = RiskManager::new();
+    let now = chrono::Utc::now();
+    let opportunity = sample_opportunity(Decimal::from(2));
+    let long_vega = PositionGreeks {
+        delta_usd: 0.0,
+        gamma_usd: 0.0,
+        vega_usd: 80.0,
+    };
+
+    assert_eq!(
+        risk.evaluate(&config, &opportunity, None, now),
+        Err(RiskRejection::GreeksUnavailable)
+    );
+    risk.evaluate(&config, &opportunity, Some(long_vega), now)
+        .expect("within vega cap");
+    let rejection = risk
+        .evaluate(&config, &opportunity, Some(long_vega), now)
+        .expect_err("net vega above cap");
+    assert!(matches!(
+        rejection,
+        RiskRejection::NetGreek { greek: "vega", .. }
+    ));
+    // Offsetting exposure is still allowed.
+    let short_vega = PositionGreeks {
+        vega_usd: -80.0,
+        ..long_vega
+    };
+    risk.evaluate(&config, &opportunity, Some(short_vega), now)
+        .expect("reduces net vega");
+    risk.release(&opportunity.combo_key());
+    risk.release(&opportunity.combo_key());
+    assert_eq!(risk.snapshot().live_combos, 0);
+}
+
+#[test]
+fn live_fills_book_their_pnl_at_expiry_and_trip_daily_loss_limit() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.daily_loss_limit_usd = Some(dec!(500));
+    let risk = RiskManager::new();
+    let now = chrono::Utc::now();
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.expiry = vec![now + chrono::Duration::hours(1)];
+
+    risk.evaluate(&config, &opportunity, None, now)
+        .expect("first combo fits");
+    risk.record_fill(&opportunity.combo_key(), dec!(-600));
+    risk.evaluate(&config, &opportunity, None, now)
+        .expect("an open fill realizes nothing");
+    risk.release(&opportunity.combo_key());
+    assert_eq!(risk.snapshot().daily_pnl, Decimal::ZERO);
+
+    let settled_at = now + chrono::Duration::hours(2);
+    let rejection = risk
+        .evaluate(&config, &opportunity, None, settled_at)
+        .expect_err("settled loss trips the hard stop");
+    assert_eq!(rejection.label(), "daily_loss_limit");
+    assert_eq!(risk.snapshot().daily_pnl, dec!(-600));
+}
+
+#[test]
+fn in_memory_risk_state_is_never_written() {
+    let path =
+        std::env::temp_dir().join(format!("deribit_arb_risk_dry_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let risk = RiskManager::open(&path).expect("fresh state").in_memory();
+    risk.record_pnl(dec!(-600), chrono::Utc::now());
+    assert_eq!(risk.snapshot().daily_pnl, dec!(-600));
+    assert!(!path.exists());
+}
+
+#[test]
+fn risk_state_survives_restart_and_reconciles() {
+    let path = std::env::temp_dir().join(format!("deribit_arb_risk_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.max_currency_notional_usd = Some(dec!(15000));
+    let now = chrono::Utc::now();
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.expiry = vec![now + chrono::Duration::days(30)];
+
+    let risk = RiskManager::open(&path).expect("fresh state");
+    risk.evaluate(&config, &opportunity, None, now)
+        .expect("approved");
+    risk.record_pnl(dec!(25), now);
+    drop(risk);
+
+    let restored = RiskManager::open(&path).expect("reload state");
+    assert_eq!(restored.snapshot().live_combos, 1);
+    assert_eq!(restored.snapshot().daily_pnl, dec!(25));
+    assert!(matches!(
+        restored.evaluate(&config, &opportunity, None, now),
+        Err(RiskRejection::CurrencyNotional { .. })
+    ));
+
+    let held = |name: &str| Position {
+        instrument_name: name.into(),
+        size: Decimal::ONE,
+    };
+    // Both legs still held: the combo survives but no longer holds a slot.
+    assert_eq!(
+        restored.reconcile(
+            &[held("BTC-25DEC24-40000-C"), held("BTC-25DEC24-45000-C")],
+            now
+        ),
+        0
+    );
+    assert_eq!(restored.snapshot().live_combos, 0);
+    assert_eq!(restored.reconcile(&[held("BTC-25DEC24-40000-C")], now), 1);
+    let reloaded = RiskManager::open(&path).expect("reload reconciled state");
+    std::fs::remove_file(&path).ok();
+    reloaded
+        .evaluate(&config, &opportunity, None, now)
+        .expect("closed combo no longer counts");
+}
+
+#[tokio::test]
+async fn audit_log_records_decisions() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.max_ticket_usd = dec!(5000);
+    let path = std::env::temp_dir().join(format!("deribit_arb_audit_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let audit = AuditLog::open(&path).expect("open audit log");
+
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.id = opportunity.content_id();
+    audit.record(AuditEvent::Detected {
+        opportunity: &opportunity,
+    });
+    let rejection = RiskManager::new()
+        .evaluate(&config, &opportunity, None, chrono::Utc::now())
+        .expect_err("ticket above cap");
+    assert!(matches!(rejection, RiskRejection::TicketCap { .. }));
+    audit.record(AuditEvent::Rejected {
+        account: "default",
+        opportunity_id: &opportunity.id,
+        strategy: opportunity.strategy,
+        currency: opportunity.currency,
+        net_edge_usd: opportunity.net_edge_usd,
+        reason: rejection.to_string(),
+    });
+    let mock = MockComboApi::new();
+    let report = ExecutionPlanner::new(&mock, &config)
+        .plan(&opportunity)
+        .await
+        .expect("plan success");
+    audit.record(AuditEvent::Planned {
+        account: "default",
+        opportunity_id: &opportunity.id,
+        strategy: opportunity.strategy,
+        currency: opportunity.currency,
+        report: &report,
+    });
+
+    let records: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+        .expect("read audit log")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("json line"))
+        .collect();
+    std::fs::remove_file(&path).ok();
+    let events: Vec<&str> = records
+        .iter()
+        .map(|record| record["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(events, ["detected", "rejected", "planned"]);
+    assert!(records.iter().all(|record| record["ts"].is_string()));
+    assert!(records[1]["reason"]
+        .as_str()
+        .unwrap()
+        .contains("exceeds cap"));
+    assert_eq!(records[2]["report"]["combo_id"], "combo-1");
+    assert_eq!(records[2]["account"], "default");
+    assert_eq!(records[0]["opportunity"]["id"], opportunity.id.as_str());
+    assert_eq!(
+        records[0]["opportunity"]["schema_version"],
+        OPPORTUNITY_SCHEMA_VERSION
+    );
+    assert_eq!(records[2]["opportunity_id"], opportunity.id.as_str());
+}
+
+#[test]
+fn adverse_selection_tracks_prints_through_fill_levels() {
+    let mut tracker = AdverseSelectionTracker::new(chrono::Duration::seconds(10));
+    let filled_at = chrono::Utc::now();
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.touches = vec![
+        LegTouch {
+            instrument_name: "BTC-25DEC24-40000-C".into(),
+            side: ComboSide::Buy,
+            price: dec!(1200),
+            size_contracts: Decimal::from(2),
+        },
+        LegTouch {
+            instrument_name: "BTC-25DEC24-45000-C".into(),
+            side: ComboSide::Sell,
+            price: dec!(1100),
+            size_contracts: Decimal::from(2),
+        },
+    ];
+    let names = tracker.record_fill(&opportunity, filled_at);
+    assert_eq!(names.len(), 2);
+
+    let print = |name: &str, price: Decimal, after_secs: i64| Trade {
+        trade_id: format!("{name}-{after_secs}"),
+        instrument_name: name.into(),
+        price,
+        amount: Decimal::ONE,
+        direction: ComboSide::Sell,
+        timestamp: filled_at + chrono::Duration::seconds(after_secs),
+        index_price: dec!(40000),
+        iv: None,
+    };
+    tracker.on_trades(&[
+        // Our bought leg prints at our level, then trades below it.
+        print("BTC-25DEC24-40000-C", dec!(1200), 1),
+        print("BTC-25DEC24-40000-C", dec!(1190), 2),
+        // The sold leg only trades through after the window closed.
+        print("BTC-25DEC24-45000-C", dec!(1100), 3),
+        print("BTC-25DEC24-45000-C", dec!(1120), 30),
+    ]);
+    assert!(!tracker.settle(filled_at + chrono::Duration::seconds(5)));
+    assert!(tracker.settle(filled_at + chrono::Duration::seconds(11)));
+
+    let report = tracker.report();
+    assert_eq!(report.len(), 1);
+    assert_eq!(report[0].strategy, StrategyKind::Vertical);
+    assert_eq!(report[0].stats.legs, 2);
+    assert_eq!(report[0].stats.confirmed, 2);
+    assert_eq!(report[0].stats.traded_through, 1);
+    assert_eq!(report[0].traded_through_rate, 0.5);
+
+    let record = serde_json::to_value(AuditEvent::AdverseSelection { report: &report }).unwrap();
+    assert_eq!(record["event"], "adverse_selection");
+    assert_eq!(record["report"][0]["traded_through"], 1);
+}
+
+/// Prints served from memory, filtered to the requested window.
+struct RecordedPrints(Vec<Trade>);
+
+#[async_trait::async_trait]
+impl TradeHistory for RecordedPrints {
+    async fn trades_between(
+        &self,
+        instrument_name: &str,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<Vec<Trade>> {
+        Ok(self
+            .0
+            .iter()
+            .filter(|trade| trade.instrument_name == instrument_name)
+            .filter(|trade| trade.timestamp >= start && trade.timestamp <= end)
+            .cloned()
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn post_trade_checks_reprice_legs_at_their_prints() {
+    let validator = PostTradeValidator::new(Duration::seconds(60));
+    let at = fixed_now();
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.id = "vertical-1".into();
+    opportunity.touches = vec![
+        LegTouch {
+            instrument_name: "BTC-25DEC24-40000-C".into(),
+            side: ComboSide::Buy,
+            price: dec!(1200),
+            size_contracts: Decimal::from(2),
+        },
+        LegTouch {
+            instrument_name: "BTC-25DEC24-45000-C".into(),
+            side: ComboSide::Sell,
+            price: dec!(1100),
+            size_contracts: Decimal::from(2),
+        },
+    ];
+    validator.watch(&opportunity, PostTradeOutcome::Executed, at);
+    validator.watch(
+        &opportunity,
+        PostTradeOutcome::Missed,
+        at + Duration::seconds(120),
+    );
+    assert!(validator.due(at + Duration::seconds(59)).is_empty());
+    let due = validator.due(at + Duration::seconds(60));
+    assert_eq!(due.len(), 1);
+    assert_eq!(validator.pending(), 1);
+
+    let print = |name: &str, price: Decimal, amount: Decimal, after_secs: i64| Trade {
+        trade_id: format!("{name}-{after_secs}"),
+        instrument_name: name.into(),
+        price,
+        amount,
+        direction: ComboSide::Buy,
+        timestamp: at + Duration::seconds(after_secs),
+        index_price: dec!(40000),
+        iv: None,
+    };
+    let history = RecordedPrints(vec![
+        // Bought leg: VWAP 1215, 15 worse than the touch on 2 contracts.
+        print("BTC-25DEC24-40000-C", dec!(1200), Decimal::ONE, 5),
+        print("BTC-25DEC24-40000-C", dec!(1230), Decimal::ONE, 20),
+        // Sold leg: 10 worse on 2 contracts.
+        print("BTC-25DEC24-45000-C", dec!(1090), Decimal::from(2), 30),
+        // After the first window: the bought leg runs away.
+        print("BTC-25DEC24-40000-C", dec!(1260), Decimal::ONE, 150),
+        print("BTC-25DEC24-45000-C", dec!(1100), Decimal::ONE, 170),
+    ]);
+
+    let executed = validate(&history, &due[0]).await.expect("executed check");
+    assert_eq!(executed.window_secs, 60);
+    assert_eq!(executed.legs[0].vwap, Some(dec!(1215)));
+    assert_eq!(executed.legs[0].slippage_usd, Some(dec!(30)));
+    assert_eq!(executed.legs[1].slippage_usd, Some(dec!(20)));
+    assert_eq!(executed.edge_at_prints_usd, dec!(50));
+    assert_eq!(executed.verdict, PostTradeVerdict::Real);
+    validator.record(&executed);
+
+    let missed = validate(&history, &validator.due(at + Duration::seconds(180))[0])
+        .await
+        .expect("missed check");
+    assert_eq!(missed.edge_at_prints_usd, dec!(-20));
+    assert_eq!(missed.verdict, PostTradeVerdict::Vanished);
+    validator.record(&missed);
+
+    // A leg that never printed leaves the edge unverified.
+    let quiet = PendingCheck {
+        opportunity: opportunity.clone(),
+        outcome: PostTradeOutcome::Missed,
+        at: at + Duration::seconds(400),
+        due_at: at + Duration::seconds(460),
+    };
+    let unverified = validate(&history, &quiet).await.expect("quiet check");
+    assert_eq!(unverified.legs[0].prints, 0);
+    assert_eq!(unverified.verdict, PostTradeVerdict::Unverified);
+    validator.record(&unverified);
+
+    let rows = validator.report();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].outcome, PostTradeOutcome::Executed);
+    assert_eq!((rows[0].stats.checks, rows[0].stats.real), (1, 1));
+    assert_eq!(rows[1].stats.checks, 2);
+    assert_eq!((rows[1].stats.vanished, rows[1].stats.unverified), (1, 1));
+    assert_eq!(rows[1].stats.detected_edge_usd, dec!(200));
+    assert_eq!(rows[1].stats.edge_at_prints_usd, dec!(-20));
+
+    let record = serde_json::to_value(AuditEvent::PostTrade { report: &missed }).unwrap();
+    assert_eq!(record["event"], "post_trade");
+    assert_eq!(record["report"]["outcome"], "missed");
+    assert_eq!(record["report"]["verdict"], "vanished");
+}
+
+#[test]
+fn jsonl_output_round_trips() {
+    let opportunities = vec![
+        sample_opportunity(Decimal::from(2)),
+        sample_opportunity(Decimal::from(3)),
+    ];
+    let mut buffer = Vec::new();
+    deribit_arb::render::write_jsonl(&opportunities, &mut buffer).expect("write jsonl");
+    let parsed: Vec<StrategyOpportunity> = String::from_utf8(buffer)
+        .expect("utf8")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("opportunity line"))
+        .collect();
+    assert_eq!(parsed, opportunities);
+}
+
+#[test]
+fn fee_csv_has_one_row_per_leg() {
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.id = "abc".into();
+    opportunity.fee_breakdown.delivery_fee_usd = dec!(0.5);
+    let path = std::env::temp_dir().join(format!("deribit_arb_fees_{}.csv", std::process::id()));
+    deribit_arb::render::export_fee_csv(&[opportunity], &path).expect("export fees");
+    let contents = std::fs::read_to_string(&path).expect("read csv");
+    let _ = std::fs::remove_file(&path);
+
+    let lines = contents.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(
+        lines[0].starts_with("opportunity_id,strategy,currency,instrument,side,settlement,role,")
+    );
+    let first = lines[1].split(',').collect::<Vec<_>>();
+    assert_eq!(first[0], "abc");
+    assert_eq!(first[3], "BTC-25DEC24-40000-C");
+    assert_eq!(&first[4..7], ["BUY", "USDC", "taker"]);
+    assert_eq!(first[8], "1");
+    assert_eq!(first[12], "0.5");
+    assert_eq!(first[16], "2");
+    assert!(lines[2].contains("BTC-25DEC24-45000-C,SELL,USDC,taker"));
+}
+
+#[tokio::test]
+async fn webhook_posts_only_above_threshold() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind");
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        let mut request = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let read = socket.read(&mut chunk).await.expect("read");
+            request.extend_from_slice(&chunk[..read]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let length = head
+                    .lines()
+                    .find_map(|line| {
+                        line.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    break;
+                }
+            }
+        }
+        socket
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .await
+            .expect("respond");
+        String::from_utf8(request).unwrap()
+    });
+
+    let sink = WebhookSink::new(url.parse().unwrap(), dec!(80)).expect("sink");
+    let mut below = sample_opportunity(Decimal::from(2));
+    below.net_edge_usd = dec!(20);
+    let above = sample_opportunity(Decimal::from(2));
+    let delivered = sink.publish(&[below, above]).await;
+    assert_eq!(delivered, 1);
+
+    let request = server.await.expect("server");
+    assert!(request.starts_with("POST /hook"));
+    let body = request.split_once("\r\n\r\n").unwrap().1;
+    let payload: serde_json::Value = serde_json::from_str(body).expect("json body");
+    assert!(payload["text"]
+        .as_str()
+        .unwrap()
+        .contains("net edge $100.00"));
+    assert_eq!(payload["opportunity"]["net_edge_usd"], "100");
+}
+
+/// Sink that keeps what it was sent.
+#[derive(Clone, Default)]
+struct RecordingSink(Arc<parking_lot::Mutex<Vec<String>>>);
+
+#[async_trait::async_trait]
+impl NotifySink for RecordingSink {
+    async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        self.0.lock().push(alert.text());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn notifier_filters_routes_and_suppresses_repeats() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/botT0KEN/sendMessage"))
+        .and(body_partial_json(serde_json::json!({ "chat_id": "-100" })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(3)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/slack"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let recorder = RecordingSink::default();
+    let notifier = Notifier::new()
+        .route(
+            "desk",
+            NotifyFilter {
+                events: vec![AlertKind::Opportunity, AlertKind::Breaker],
+                min_edge_usd: dec!(50),
+                top: 1,
+            },
+            TelegramSink::new(server.uri().parse().unwrap(), "T0KEN".into(), "-100".into())
+                .expect("telegram"),
+        )
+        .route(
+            "ops",
+            NotifyFilter {
+                events: vec![AlertKind::Risk],
+                ..NotifyFilter::default()
+            },
+            SlackSink::new(format!("{}/slack", server.uri()).parse().unwrap()).expect("slack"),
+        )
+        .route("log", NotifyFilter::default(), recorder.clone());
+
+    let best = sample_opportunity(Decimal::from(2));
+    let mut runner_up = best.clone();
+    runner_up.strategy = StrategyKind::Butterfly;
+    runner_up.net_edge_usd = dec!(90);
+    let mut small = best.clone();
+    small.strategy = StrategyKind::Box;
+    small.net_edge_usd = dec!(20);
+    let budget = RiskRejection::Budget {
+        budget: BudgetKind::CombosPerHour,
+        used: dec!(2),
+        max: dec!(2),
+        resumes_at: fixed_now(),
+    };
+    assert!(Alert::risk_trip(&RiskRejection::GreeksUnavailable).is_none());
+    let mut alerts: Vec<Alert> = [&small, &runner_up, &best]
+        .into_iter()
+        .map(Alert::opportunity)
+        .collect();
+    alerts.push(Alert::risk_trip(&budget).expect("budget trips"));
+    alerts.push(Alert::Breaker {
+        tripped: true,
+        reason: Some("freshest quote is 30s old".into()),
+    });
+    alerts.push(Alert::Execution {
+        account: "default".into(),
+        strategy: StrategyKind::Vertical,
+        currency: Currency::BTC,
+        net_edge_usd: Some(dec!(100)),
+        outcome: "planned combo-1".into(),
+    });
+
+    // Desk: the best opportunity and the breaker; ops: the risk trip; log:
+    // all three opportunities plus the rest.
+    let now = fixed_now();
+    assert_eq!(notifier.deliver(&alerts, now).await, 2 + 1 + 6);
+    assert_eq!(
+        recorder.0.lock()[5],
+        "[default] vertical BTC (edge $100.00): planned combo-1"
+    );
+    // Opportunities and trips stay quiet until the repeat window passes.
+    let later = now + chrono::Duration::minutes(5);
+    assert_eq!(notifier.deliver(&alerts[2..4], later).await, 0);
+    let later = now + chrono::Duration::minutes(16);
+    assert_eq!(notifier.deliver(&alerts[2..3], later).await, 2);
+    server.verify().await;
+}
+
+#[test]
+fn scan_reports_include_legs_and_fees() {
+    let opportunities = vec![sample_opportunity(Decimal::from(2))];
+    let markdown = deribit_arb::render::render_markdown(&opportunities, 5);
+    assert!(markdown.contains("| Opportunities | Total edge ($)"));
+    assert!(markdown.contains("## 1. Vertical BTC USDC"));
+    assert!(markdown.contains("| Total | 2.00 | 2.00 |"));
+    assert!(markdown.contains("BTC-25DEC24-45000-C (Taker, USDC)"));
+
+    let html = deribit_arb::render::render_html(&opportunities, 5);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h2>1. Vertical BTC USDC"));
+    assert!(html.contains("<td>Total</td><td>2.00</td><td>2.00</td>"));
+    assert!(html.trim_end().ends_with("</html>"));
+}
+
+#[test]
+fn opportunity_view_filters_sorts_and_limits() {
+    let mut btc_box = sample_opportunity(Decimal::from(2));
+    btc_box.strategy = StrategyKind::Box;
+    btc_box.notional_usd = dec!(90000);
+    btc_box.edge_bps = 5.0;
+    let mut wide = sample_opportunity(Decimal::from(2));
+    wide.net_edge_usd = dec!(300);
+    wide.edge_bps = 1.0;
+    let mut eth = sample_opportunity(Decimal::from(2));
+    eth.currency = Currency::ETH;
+    eth.net_edge_usd = dec!(500);
+    let opportunities = vec![btc_box, wide, eth];
+
+    let view = OpportunityView {
+        sort_by: SortKey::Notional,
+        currencies: vec![Currency::BTC],
+        ..Default::default()
+    };
+    let shown = view.apply(&opportunities, 10);
+    assert_eq!(shown.len(), 2);
+    assert_eq!(shown[0].strategy, StrategyKind::Box);
+
+    let view = OpportunityView {
+        sort_by: SortKey::Bps,
+        strategies: vec![StrategyKind::Vertical],
+        top: Some(1),
+        ..Default::default()
+    };
+    let shown = view.apply(&opportunities, 10);
+    assert_eq!(shown.len(), 1);
+    assert_eq!(shown[0].strategy, StrategyKind::Vertical);
+    assert!(shown[0].edge_bps > 1.0);
+
+    let today = chrono::Utc::now().date_naive();
+    let view = OpportunityView {
+        expiries: vec![today.succ_opt().unwrap()],
+        ..Default::default()
+    };
+    assert!(view.apply(&opportunities, 10).is_empty());
+    let view = OpportunityView {
+        expiries: vec![today],
+        ..Default::default()
+    };
+    assert_eq!(view.apply(&opportunities, 2)[0].net_edge_usd, dec!(500));
+}
+
+#[test]
+fn groups_rows_with_subtotals_in_sorted_order() {
+    let near = chrono::Utc::now();
+    let far = near + chrono::Duration::days(7);
+    let mut best_box = sample_opportunity(Decimal::from(2));
+    best_box.strategy = StrategyKind::Box;
+    best_box.net_edge_usd = dec!(300);
+    best_box.expiry = vec![far];
+    let vertical = sample_opportunity(Decimal::from(2));
+    let mut calendar = sample_opportunity(Decimal::from(2));
+    calendar.strategy = StrategyKind::Calendar;
+    calendar.net_edge_usd = dec!(50);
+    calendar.expiry = vec![far, near];
+    let mut other_box = sample_opportunity(Decimal::from(2));
+    other_box.strategy = StrategyKind::Box;
+    other_box.net_edge_usd = dec!(25);
+    let rows = vec![best_box, vertical, calendar, other_box];
+
+    let groups = deribit_arb::render::group_opportunities(&rows, GroupKey::Strategy);
+    let labels: Vec<_> = groups.iter().map(|group| group.label.as_str()).collect();
+    assert_eq!(labels, ["Box", "Vertical", "Calendar"]);
+    assert_eq!(groups[0].rows.len(), 2);
+    assert_eq!(groups[0].edge_usd(), dec!(325));
+    assert_eq!(
+        groups[0].notional_usd(),
+        rows[0].notional_usd + rows[3].notional_usd
+    );
+    assert_eq!(
+        groups[0].fees_usd(),
+        rows[0].fee_breakdown.total_usd + rows[3].fee_breakdown.total_usd
+    );
+
+    // Calendars group under their near leg.
+    let groups = deribit_arb::render::group_opportunities(&rows, GroupKey::Expiry);
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].label, far.format("%Y-%m-%d").to_string());
+    assert_eq!(groups[0].rows.len(), 1);
+    assert_eq!(groups[1].rows.len(), 3);
+}
+
+fn leg_snapshot(name: &str, quoted_at: chrono::DateTime<chrono::Utc>) -> InstrumentSnapshot {
+    InstrumentSnapshot {
+        instrument: Instrument {
+            instrument_name: name.to_string(),
+            currency: Currency::BTC,
+            is_usdc_settled: true,
+            is_combo: false,
+            option_kind: OptionKind::Call,
+            strike: dec!(40000),
+            expiry: chrono::Utc::now() + chrono::Duration::days(30),
+            contract_size: Decimal::ONE,
+            settlement_currency: SettlementCurrency::Usdc,
+            tick_size: dec!(5),
+            min_trade_amount: Decimal::ONE,
+        },
+        quote: Quote {
+            best_bid: None,
+            best_ask: None,
+            mark_iv: None,
+            bid_iv: None,
+            ask_iv: None,
+            interest_rate: None,
+            timestamp: quoted_at,
+            index_price: dec!(40000),
+        },
+        order_book: None,
+    }
+}
+
+#[test]
+fn tick_rounding_snaps_limits_and_rejects_erased_edge() {
+    let now = chrono::Utc::now();
+    let legs = vec![
+        leg_snapshot("BTC-25DEC24-40000-C", now),
+        leg_snapshot("BTC-25DEC24-45000-C", now),
+    ];
+    let mut opportunity = sample_opportunity(Decimal::from(2));
+    opportunity.execution_plan.price_limit = dec!(102);
+    opportunity.touches = vec![
+        LegTouch {
+            instrument_name: "BTC-25DEC24-40000-C".into(),
+            side: ComboSide::Buy,
+            price: dec!(1201),
+            size_contracts: Decimal::from(2),
+        },
+        LegTouch {
+            instrument_name: "BTC-25DEC24-45000-C".into(),
+            side: ComboSide::Sell,
+            price: dec!(1149),
+            size_contracts: Decimal::from(2),
+        },
+    ];
+
+    // 51 per contract rounds up to 55 on the 5-wide grid, costing 8 USD.
+    let snapped = snap_to_ticks(&opportunity, &legs).expect("snapped");
+    assert_eq!(snapped.execution_plan.price_limit, dec!(110));
+    assert_eq!(snapped.touches[0].price, dec!(1205));
+    assert_eq!(snapped.touches[1].price, dec!(1145));
+    assert_eq!(snapped.net_edge_usd, dec!(92));
+    assert_eq!(snapped.net_edge_native, dec!(92));
+
+    opportunity.net_edge_usd = dec!(5);
+    assert!(matches!(
+        snap_to_ticks(&opportunity, &legs),
+        Err(TickRejection::EdgeErased { .. })
+    ));
+    assert_eq!(
+        snap_to_ticks(&opportunity, &legs[..1]),
+        Err(TickRejection::MissingLeg("BTC-25DEC24-45000-C".into()))
+    );
+}
+
+#[tokio::test]
+async fn maker_quoter_rests_reprices_and_cancels() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.dry_run = false;
+    config.execution_mode = ExecutionMode::Maker;
+    let mock = MockComboApi::new();
+    let mut maker = MakerQuoter::new(&mock, &config);
+    let now = chrono::Utc::now();
+    let legs = vec![
+        leg_snapshot("BTC-25DEC24-40000-C", now),
+        leg_snapshot("BTC-25DEC24-45000-C", now),
+    ];
+
+    // 100 debit over 2 contracts = 50 touch, rested one 5-wide tick inside.
+    let opportunity = sample_opportunity(Decimal::from(2));
+    let actions = maker
+        .sync(std::slice::from_ref(&opportunity), &legs, now)
+        .await;
+    assert!(matches!(&actions[..], [MakerAction::Placed { price, .. }] if *price == dec!(45)));
+    let placed = mock.orders.lock()[0].clone();
+    assert_eq!(placed.tif, OrderTimeInForce::GTC);
+    assert!(placed.post_only);
+    assert_eq!(placed.instrument_name, "combo-1");
+
+    // Unchanged touch: nothing to do.
+    assert!(maker
+        .sync(std::slice::from_ref(&opportunity), &legs, now)
+        .await
+        .is_empty());
+
+    let mut moved = opportunity;
+    moved.execution_plan.price_limit = dec!(121);
+    let actions = maker.sync(&[moved], &legs, now).await;
+    assert!(matches!(&actions[..], [MakerAction::Repriced { to, .. }] if *to == dec!(55)));
+    assert_eq!(
+        mock.edits.lock()[0],
+        ("order-1".to_string(), Decimal::from(2), dec!(55))
+    );
+
+    let actions = maker.sync(&[], &legs, now).await;
+    assert!(matches!(
+        &actions[..],
+        [MakerAction::Cancelled {
+            reason: CancelReason::EdgeGone,
+            ..
+        }]
+    ));
+    assert_eq!(mock.cancels.lock().as_slice(), ["order-1"]);
+    assert_eq!(maker.resting().count(), 0);
+}
+
+#[tokio::test]
+async fn maker_quoter_cancels_on_stale_quotes() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.dry_run = false;
+    let mock = MockComboApi::new();
+    let mut maker = MakerQuoter::new(&mock, &config);
+    let now = chrono::Utc::now();
+    let opportunity = sample_opportunity(Decimal::from(2));
+    let fresh = vec![
+        leg_snapshot("BTC-25DEC24-40000-C", now),
+        leg_snapshot("BTC-25DEC24-45000-C", now),
+    ];
+    maker
+        .sync(std::slice::from_ref(&opportunity), &fresh, now)
+        .await;
+
+    let later = now + chrono::Duration::seconds(config.maker_stale_secs as i64 + 1);
+    let actions = maker.sync(&[opportunity], &fresh, later).await;
+    assert!(matches!(
+        &actions[..],
+        [MakerAction::Cancelled {
+            reason: CancelReason::StaleQuotes,
+            ..
+        }]
+    ));
+    assert_eq!(mock.orders.lock().len(), 1);
+}
+
+#[tokio::test]
+async fn block_rfq_accepts_best_quote_beyond_screen_size() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.dry_run = false;
+    config.rfq_contracts = Some(dec!(10));
+    config.rfq_wait_secs = 0;
+    config.rfq_execute = true;
+    let mock = MockComboApi::new();
+    *mock.rfq_quotes.lock() = vec![
+        BlockRfqQuote {
+            quote_id: Some("q-1".into()),
+            price: dec!(60),
+            amount: dec!(10),
+        },
+        BlockRfqQuote {
+            quote_id: Some("q-2".into()),
+            price: dec!(48),
+            amount: dec!(10),
+        },
+    ];
+    let report = ExecutionPlanner::new(&mock, &config)
+        .plan(&sample_opportunity(Decimal::from(2)))
+        .await
+        .expect("plan success");
+
+    let rfq = report.block_rfq.expect("rfq requested");
+    assert_eq!(mock.rfqs.lock()[0].1, dec!(10));
+    // Touch is 50/contract with 51 gross edge; q-2 improves it by 2 and pays 1 in block fees.
+    let best = rfq.best_quote().expect("best quote");
+    assert_eq!(best.quote.quote_id.as_deref(), Some("q-2"));
+    assert_eq!(best.net_edge_usd, dec!(520));
+    assert_eq!(rfq.quotes[0].net_edge_usd, dec!(400));
+    assert!(rfq.accepted.is_some());
+    assert!(report.submitted);
+    assert_eq!(
+        mock.accepted_rfqs.lock().as_slice(),
+        [("rfq-1".to_string(), dec!(48), dec!(10))]
+    );
+    assert!(mock.cancelled_rfqs.lock().is_empty());
+}
+
+#[tokio::test]
+async fn block_rfq_is_cancelled_in_dry_run() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.rfq_contracts = Some(dec!(10));
+    config.rfq_wait_secs = 0;
+    config.rfq_execute = true;
+    let mock = MockComboApi::new();
+    *mock.rfq_quotes.lock() = vec![BlockRfqQuote {
+        quote_id: None,
+        price: dec!(48),
+        amount: dec!(4),
+    }];
+    let report = ExecutionPlanner::new(&mock, &config)
+        .plan(&sample_opportunity(Decimal::from(2)))
+        .await
+        .expect("plan success");
+
+    let rfq = report.block_rfq.expect("rfq requested");
+    assert_eq!(rfq.best_quote().unwrap().contracts, dec!(4));
+    assert!(rfq.accepted.is_none());
+    assert!(!report.submitted);
+    assert!(mock.accepted_rfqs.lock().is_empty());
+    assert_eq!(mock.cancelled_rfqs.lock().as_slice(), ["rfq-1"]);
+}
+
+fn quoted_leg(
+    name: &str,
+    strike: Decimal,
+    (bid, ask): (Decimal, Decimal),
+    quoted_at: chrono::DateTime<chrono::Utc>,
+) -> InstrumentSnapshot {
+    let mut leg = leg_snapshot(name, quoted_at);
+    leg.instrument.strike = strike;
+    leg.instrument.expiry = chrono::DateTime::from_timestamp(4_102_444_800, 0).expect("expiry");
+    leg.quote.best_bid = Some(QuoteLevel {
+        price: bid,
+        amount: dec!(10),
+    });
+    leg.quote.best_ask = Some(QuoteLevel {
+        price: ask,
+        amount: dec!(10),
+    });
+    leg
+}
+
+#[tokio::test]
+async fn recheck_reprices_and_aborts_on_degraded_or_stale_quotes() {
+    let mut config = testkit::config(vec![StrategyKind::Vertical]).build();
+    config.latency_budget_ms = 1_000;
+    let detector = DetectorSuite::new(&config);
+    let mock = MockComboApi::new();
+    let planner = ExecutionPlanner::new(&mock, &config);
+    let now = chrono::Utc::now();
+    let (low, high) = ("BTC-25DEC24-40000-C", "BTC-25DEC24-45000-C");
+    let detected_legs = vec![
+        quoted_leg(low, dec!(40000), (dec!(5800), dec!(6000)), now),
+        quoted_leg(high, dec!(45000), (dec!(5400), dec!(5600)), now),
+    ];
+    let opportunity = detector
+        .scan_at(&detected_legs, now)
+        .into_iter()
+        .find(|opp| opp.strategy == StrategyKind::Vertical)
+        .expect("vertical");
+    let serve = |legs: &[InstrumentSnapshot]| {
+        let mut tickers = mock.tickers.lock();
+        for leg in legs {
+            tickers.insert(leg.instrument.instrument_name.clone(), leg.quote.clone());
+        }
+    };
+
+    // Unchanged books: the refreshed combo keeps its full edge.
+    serve(&detected_legs);
+    let rechecked = planner
+        .recheck(&detector, &opportunity, &detected_legs, 0.8)
+        .await
+        .expect("recheck");
+    assert_eq!(rechecked.combo_key(), opportunity.combo_key());
+    assert_eq!(rechecked.net_edge_usd, opportunity.net_edge_usd);
+
+    // The short leg's bid dropping erodes most of the edge.
+    serve(&[quoted_leg(high, dec!(45000), (dec!(4000), dec!(4200)), now)]);
+    let rejection = planner
+        .recheck(&detector, &opportunity, &detected_legs, 0.8)
+        .await
+        .expect_err("degraded");
+    assert!(matches!(rejection, RecheckRejection::EdgeDegraded { .. }));
+
+    // Quotes older than the latency budget abort before re-detection.
+    let stale = now - chrono::Duration::seconds(5);
+    serve(&[
+        quoted_leg(low, dec!(40000), (dec!(5800), dec!(6000)), stale),
+        quoted_leg(high, dec!(45000), (dec!(5400), dec!(5600)), stale),
+    ]);
+    let rejection = planner
+        .recheck(&detector, &opportunity, &detected_legs, 0.8)
+        .await
+        .expect_err("stale");
+    assert!(matches!(
+        rejection,
+        RecheckRejection::LatencyBudget {
+            budget_ms: 1_000,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,188 @@
+use clap::Parser;
+use deribit_arb::config::{AppConfig, Cli};
+use deribit_arb::detect::DetectorSuite;
+use deribit_arb::model::{
+    format_strike, Currency, OptionKind, ParsedInstrumentName, SettlementCurrency,
+};
+use deribit_arb::testkit::{fixed_now, ChainBuilder, Level};
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// Components of a well-formed listing name: currency code, stablecoin
+/// suffix, day (unpadded, as daily options list it), month, two-digit year,
+/// strike and option kind.
+#[derive(Debug, Clone)]
+struct NameParts {
+    code: String,
+    stablecoin: Option<&'static str>,
+    day: u32,
+    month: usize,
+    year: u32,
+    strike: Decimal,
+    option_kind: OptionKind,
+}
+
+impl NameParts {
+    fn expiry(&self) -> String {
+        format!("{}{}{:02}", self.day, MONTHS[self.month], self.year)
+    }
+
+    fn name_with(&self, expiry: &str, strike: &str) -> String {
+        let underlying = match self.stablecoin {
+            Some(stablecoin) => format!("{}_{stablecoin}", self.code),
+            None => self.code.clone(),
+        };
+        let kind = match self.option_kind {
+            OptionKind::Call => "C",
+            OptionKind::Put => "P",
+        };
+        format!("{underlying}-{expiry}-{strike}-{kind}")
+    }
+
+    fn name(&self) -> String {
+        self.name_with(&self.expiry(), &format_strike(self.strike))
+    }
+}
+
+fn name_parts() -> impl Strategy<Value = NameParts> {
+    (
+        "[A-Z][A-Z0-9]{1,9}",
+        prop::option::of(prop::sample::select(vec!["USDC", "USDT", "EURR"])),
+        1u32..=28,
+        0usize..12,
+        24u32..=99,
+        // Whole strikes, and the fractional ones of low-priced underlyings.
+        prop_oneof![
+            (1u64..=1_000_000).prop_map(Decimal::from),
+            (1i64..=100_000, 1u32..=4).prop_map(|(units, scale)| Decimal::new(units, scale)),
+        ],
+        prop_oneof![Just(OptionKind::Call), Just(OptionKind::Put)],
+    )
+        .prop_map(
+            |(code, stablecoin, day, month, year, strike, option_kind)| NameParts {
+                code,
+                stablecoin,
+                day,
+                month,
+                year,
+                strike,
+                option_kind,
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn parses_every_well_formed_name(parts in name_parts()) {
+        let parsed = ParsedInstrumentName::from_str(&parts.name()).expect("well-formed name");
+        prop_assert_eq!(parsed.currency, Currency::from_str(&parts.code).unwrap());
+        prop_assert_eq!(parsed.day, parts.day);
+        prop_assert_eq!(parsed.month.as_str(), MONTHS[parts.month]);
+        prop_assert_eq!(parsed.year, 2000 + parts.year);
+        prop_assert_eq!(parsed.strike, parts.strike);
+        prop_assert_eq!(parsed.option_kind, parts.option_kind);
+        prop_assert_eq!(parsed.to_string(), parts.name());
+        prop_assert!(parsed.expiry_date().is_ok());
+    }
+
+    #[test]
+    fn never_panics_on_arbitrary_input(name in "\\PC{0,40}", dashed in "[A-Za-z0-9_é-]{0,30}") {
+        let _ = ParsedInstrumentName::from_str(&name);
+        let _ = ParsedInstrumentName::from_str(&dashed);
+    }
+
+    #[test]
+    fn rejects_invalid_expiries(
+        parts in name_parts(),
+        day in 32u32..=99,
+        month in "[A-Z]{3}",
+        garbage in "[^-]{1,8}",
+    ) {
+        let year = format!("{:02}", parts.year);
+        let mut expiries = vec![
+            format!("{day}{}{year}", MONTHS[parts.month]),
+            format!("+{}{}{year}", parts.day, MONTHS[parts.month]),
+            format!("{}{}ö{year}", parts.day, &MONTHS[parts.month][..2]),
+            garbage,
+        ];
+        if !MONTHS.contains(&month.as_str()) {
+            expiries.push(format!("{}{month}{year}", parts.day));
+        }
+        for expiry in expiries {
+            let name = parts.name_with(&expiry, &format_strike(parts.strike));
+            prop_assert!(ParsedInstrumentName::from_str(&name).is_err(), "accepted {}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_non_positive_strikes(parts in name_parts()) {
+        let strike = format_strike(parts.strike);
+        for strike in ["0".to_string(), format!("-{strike}"), "x".to_string(), format!("{strike}d1d")] {
+            let name = parts.name_with(&parts.expiry(), &strike);
+            prop_assert!(ParsedInstrumentName::from_str(&name).is_err(), "accepted {}", name);
+        }
+    }
+}
+
+/// A quote on each listing: bid, spread over it and sizes, all positive.
+fn quotes(count: usize) -> impl Strategy<Value = Vec<(Level, Level)>> {
+    prop::collection::vec(
+        (1u32..20_000, 0u32..2_000, 1u32..50, 1u32..50).prop_map(
+            |(bid, spread, bid_size, ask_size)| {
+                (
+                    (Decimal::from(bid), Decimal::from(bid_size)),
+                    (Decimal::from(bid + spread), Decimal::from(ask_size)),
+                )
+            },
+        ),
+        count,
+    )
+}
+
+const EXPIRIES: [&str; 2] = ["28JUN30", "27DEC30"];
+const STRIKES: [u32; 5] = [30_000, 35_000, 40_000, 45_000, 50_000];
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn detected_opportunities_are_sized_and_clear_min_edge(
+        quotes in quotes(EXPIRIES.len() * STRIKES.len() * 2),
+    ) {
+        let cli = Cli::try_parse_from(["deribit_arb"]).expect("cli");
+        let config = AppConfig::from_cli(cli).expect("config");
+        let suite = DetectorSuite::new(&config);
+        let mut builder = ChainBuilder::new(Currency::BTC, SettlementCurrency::Usdc).mark_iv(50.0);
+        let listings = EXPIRIES.iter().flat_map(|expiry| {
+            STRIKES.iter().flat_map(move |strike| {
+                [OptionKind::Call, OptionKind::Put].map(|kind| (*expiry, *strike, kind))
+            })
+        });
+        for ((expiry, strike, kind), (bid, ask)) in listings.zip(quotes) {
+            builder = builder.option(expiry, Decimal::from(strike), kind, Some(bid), Some(ask));
+        }
+
+        for opportunity in suite.scan_at(&builder.build(), fixed_now()) {
+            let requirements = config.requirements(opportunity.strategy);
+            prop_assert!(opportunity.size_contracts > Decimal::ZERO);
+            prop_assert!(!opportunity.legs.is_empty());
+            prop_assert!(opportunity
+                .touches
+                .iter()
+                .all(|touch| touch.size_contracts > Decimal::ZERO && touch.price >= Decimal::ZERO));
+            prop_assert!(opportunity.fee_breakdown.total_usd >= Decimal::ZERO);
+            prop_assert!(
+                opportunity.net_edge_usd >= requirements.min_edge_usd,
+                "{} edge {} under {}",
+                opportunity.strategy,
+                opportunity.net_edge_usd,
+                requirements.min_edge_usd
+            );
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use deribit_arb::client::{
+    rpc_error, DeribitCredentials, DeribitHttpClient, RpcErrorClass, MAX_BATCH_CALLS,
+};
+use deribit_arb::config::Environment;
+use deribit_arb::model::{ComboLeg, ComboSide, Currency, OptionKind, SettlementCurrency};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Answers JSON-RPC calls to `rpc_method` with a recorded response body.
+fn rpc(rpc_method: &str, body: &str) -> Mock {
+    let body: serde_json::Value = serde_json::from_str(body).expect("fixture json");
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({ "method": rpc_method })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+}
+
+fn client(server: &MockServer) -> DeribitHttpClient {
+    let credentials = DeribitCredentials {
+        client_id: "id".to_string(),
+        client_secret: "secret".to_string(),
+    };
+    DeribitHttpClient::new(Environment::Testnet, Some(credentials)).with_base_url(server.uri())
+}
+
+#[tokio::test]
+async fn loads_recorded_instruments_and_ticker() {
+    let server = MockServer::start().await;
+    rpc(
+        "public/get_instruments",
+        include_str!("fixtures/rpc/get_instruments.json"),
+    )
+    .mount(&server)
+    .await;
+    rpc("public/ticker", include_str!("fixtures/rpc/ticker.json"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    // The unparseable name is skipped rather than failing the load.
+    let instruments = client.get_instruments("BTC").await.expect("instruments");
+    assert_eq!(instruments.len(), 2);
+    assert_eq!(instruments[0].settlement_currency, SettlementCurrency::Coin);
+    assert_eq!(instruments[0].tick_size, dec!(0.0005));
+    assert_eq!(instruments[1].option_kind, OptionKind::Put);
+    assert_eq!(instruments[1].settlement_currency, SettlementCurrency::Usdc);
+    assert_eq!(instruments[1].contract_size, dec!(0.01));
+    assert_eq!(instruments[1].expiry.timestamp(), 1_735_286_400);
+
+    let quote = client
+        .get_ticker("BTC-27DEC24-60000-C")
+        .await
+        .expect("ticker");
+    let bid = quote.best_bid.expect("bid");
+    assert_eq!((bid.price, bid.amount), (dec!(0.051), dec!(12.5)));
+    assert_eq!(quote.best_ask.expect("ask").price, dec!(0.0525));
+    assert_eq!(quote.mark_iv, Some(54.2));
+    assert_eq!(quote.index_price, dec!(96512.4));
+}
+
+#[tokio::test]
+async fn loads_expired_instruments_and_delivery_prices() {
+    let server = MockServer::start().await;
+    let body: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/rpc/get_instruments.json")).unwrap();
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "public/get_instruments",
+            "params": { "expired": true },
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "public/get_delivery_prices",
+            "params": { "index_name": "btc_usd", "count": 2 },
+        })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(
+                serde_json::from_str::<serde_json::Value>(include_str!(
+                    "fixtures/rpc/get_delivery_prices.json"
+                ))
+                .unwrap(),
+            ),
+        )
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let expired = client
+        .get_expired_instruments("BTC")
+        .await
+        .expect("expired instruments");
+    assert_eq!(expired.len(), 2);
+    let deliveries = client
+        .get_delivery_prices(Currency::BTC, 2)
+        .await
+        .expect("delivery prices");
+    assert_eq!(
+        deliveries,
+        vec![
+            (
+                chrono::NaiveDate::from_ymd_opt(2024, 3, 29).unwrap(),
+                dec!(70123.45)
+            ),
+            (
+                chrono::NaiveDate::from_ymd_opt(2024, 3, 28).unwrap(),
+                dec!(69876.5)
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn loads_trade_prints_between_two_times() {
+    let server = MockServer::start().await;
+    let body: serde_json::Value = serde_json::from_str(include_str!(
+        "fixtures/rpc/get_last_trades_by_instrument_and_time.json"
+    ))
+    .unwrap();
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "public/get_last_trades_by_instrument_and_time",
+            "params": {
+                "instrument_name": "BTC-27DEC24-60000-C",
+                "start_timestamp": 1_733_900_000_000u64,
+                "end_timestamp": 1_733_900_060_000u64,
+                "count": 1000,
+                "include_oldest": true,
+            },
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let start = chrono::DateTime::from_timestamp_millis(1_733_900_000_000).unwrap();
+    // Counts above one page are capped rather than rejected by Deribit.
+    let trades = client
+        .get_last_trades_by_instrument_and_time(
+            "BTC-27DEC24-60000-C",
+            start,
+            start + chrono::Duration::seconds(60),
+            5000,
+        )
+        .await
+        .expect("trades");
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].trade_id, "BTC-1001");
+    assert_eq!(trades[0].direction, ComboSide::Buy);
+    assert_eq!(
+        (trades[0].price, trades[0].amount),
+        (dec!(0.0525), dec!(2.5))
+    );
+    assert_eq!(trades[0].iv, Some(54.8));
+    assert_eq!(trades[1].direction, ComboSide::Sell);
+    assert_eq!(trades[1].index_price, dec!(96480.1));
+    assert_eq!(trades[1].timestamp, start + chrono::Duration::seconds(30));
+}
+
+#[tokio::test]
+async fn authenticates_before_combo_calls() {
+    let server = MockServer::start().await;
+    rpc("public/auth", include_str!("fixtures/rpc/auth.json"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "method": "private/create_combo",
+            "params": { "access_token": "1733000000000.1abcdEFGH.access" },
+        })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(
+                serde_json::from_str::<serde_json::Value>(include_str!(
+                    "fixtures/rpc/create_combo.json"
+                ))
+                .expect("fixture json"),
+            ),
+        )
+        .mount(&server)
+        .await;
+    rpc(
+        "private/get_leg_prices",
+        include_str!("fixtures/rpc/get_leg_prices.json"),
+    )
+    .mount(&server)
+    .await;
+    let client = client(&server);
+
+    let legs = [
+        ComboLeg {
+            instrument_name: "BTC-27DEC24-60000-C".to_string(),
+            ratio: 1,
+            side: ComboSide::Buy,
+        },
+        ComboLeg {
+            instrument_name: "BTC-27DEC24-65000-C".to_string(),
+            ratio: 1,
+            side: ComboSide::Sell,
+        },
+    ];
+    let combo_id = client
+        .create_combo("spread", &legs, SettlementCurrency::Coin)
+        .await
+        .expect("combo");
+    assert_eq!(combo_id, "BTC-CS-27DEC24-60000_65000");
+    // The cached token serves the second private call without re-auth.
+    let preview = client
+        .get_leg_prices(&combo_id, dec!(1))
+        .await
+        .expect("preview");
+    assert_eq!(preview["legs"][1]["price"], json!(0.031));
+}
+
+#[tokio::test]
+async fn retries_rate_limited_calls_with_backoff() {
+    let server = MockServer::start().await;
+    rpc(
+        "public/ticker",
+        include_str!("fixtures/rpc/too_many_requests.json"),
+    )
+    .up_to_n_times(1)
+    .with_priority(1)
+    .mount(&server)
+    .await;
+    rpc("public/ticker", include_str!("fixtures/rpc/ticker.json"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let quote = client
+        .get_ticker("BTC-27DEC24-60000-C")
+        .await
+        .expect("ticker after retry");
+    assert_eq!(quote.index_price, dec!(96512.4));
+    let stats = client.stats();
+    let ticker = &stats.methods["public/ticker"];
+    assert_eq!((ticker.calls, ticker.errors), (2, 1));
+    assert_eq!((stats.rate_limit_waits, stats.rate_limit_wait_ms), (1, 250));
+}
+
+#[tokio::test]
+async fn surfaces_rpc_and_http_errors() {
+    let server = MockServer::start().await;
+    rpc(
+        "public/ticker",
+        include_str!("fixtures/rpc/invalid_params.json"),
+    )
+    .mount(&server)
+    .await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(
+            json!({ "method": "public/get_instruments" }),
+        ))
+        .respond_with(ResponseTemplate::new(502).set_body_string("bad gateway"))
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let err = client
+        .get_ticker("BTC-NOPE")
+        .await
+        .expect_err("invalid params");
+    assert!(err.to_string().contains("Invalid params (-32602)"), "{err}");
+    let rpc = rpc_error(&err).expect("typed rpc error");
+    assert_eq!((rpc.code, rpc.class), (-32602, RpcErrorClass::Validation));
+    let err = client.get_instruments("BTC").await.expect_err("http error");
+    assert!(err.to_string().starts_with("HTTP 502"), "{err}");
+    assert!(rpc_error(&err).is_none());
+    // Neither is a rate limit, so neither was retried.
+    assert_eq!(client.stats().rate_limit_waits, 0);
+    assert_eq!(client.stats().totals(), (2, 2));
+}
+
+#[tokio::test]
+async fn retries_public_engine_errors_but_not_private_ones() {
+    let server = MockServer::start().await;
+    let queue_full = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "error": { "code": 10047, "message": "matching_engine_queue_full" },
+    });
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({ "method": "public/ticker" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(queue_full.clone()))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    rpc("public/ticker", include_str!("fixtures/rpc/ticker.json"))
+        .mount(&server)
+        .await;
+    rpc("public/auth", include_str!("fixtures/rpc/auth.json"))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(
+            json!({ "method": "private/get_leg_prices" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(queue_full))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    client
+        .get_ticker("BTC-27DEC24-60000-C")
+        .await
+        .expect("ticker after retry");
+    let err = client
+        .get_leg_prices("BTC-CS-27DEC24-60000_65000", dec!(1))
+        .await
+        .expect_err("engine error");
+    assert_eq!(
+        rpc_error(&err).map(|rpc| rpc.class),
+        Some(RpcErrorClass::Engine)
+    );
+    // Engine backoffs are not rate-limit waits.
+    assert_eq!(client.stats().rate_limit_waits, 0);
+}
+
+/// Answers a JSON-RPC batch of tickers in reverse order, rejecting
+/// instruments named `UNKNOWN` the way Deribit does.
+struct TickerBatch;
+
+impl Respond for TickerBatch {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let ticker: serde_json::Value =
+            serde_json::from_str(include_str!("fixtures/rpc/ticker.json")).expect("fixture json");
+        let calls: Vec<serde_json::Value> = request.body_json().expect("batch body");
+        let responses: Vec<serde_json::Value> = calls
+            .iter()
+            .rev()
+            .map(|call| {
+                let name = call["params"]["instrument_name"]
+                    .as_str()
+                    .unwrap_or_default();
+                if name.starts_with("UNKNOWN") {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": call["id"],
+                        "error": { "code": 10029, "message": "not_found" },
+                    })
+                } else {
+                    let mut result = ticker["result"].clone();
+                    result["instrument_name"] = json!(name);
+                    json!({ "jsonrpc": "2.0", "id": call["id"], "result": result })
+                }
+            })
+            .collect();
+        ResponseTemplate::new(200).set_body_json(responses)
+    }
+}
+
+#[tokio::test]
+async fn batches_tickers_into_one_round_trip() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(TickerBatch)
+        .expect(3)
+        .mount(&server)
+        .await;
+    let client = client(&server);
+
+    let names = vec![
+        "BTC-27DEC24-60000-C".to_string(),
+        "UNKNOWN-27DEC24-1-C".to_string(),
+        "BTC-27DEC24-65000-C".to_string(),
+    ];
+    let quotes = client.get_tickers(&names).await.expect("batch");
+    assert_eq!(quotes.len(), 3);
+    assert_eq!(
+        quotes[0].as_ref().expect("first").index_price,
+        dec!(96512.4)
+    );
+    let missing = quotes[1].as_ref().expect_err("unknown instrument");
+    assert_eq!(rpc_error(missing).map(|err| err.code), Some(10029));
+    assert!(quotes[2].is_ok());
+
+    // Longer lists are split at MAX_BATCH_CALLS, one round trip per batch.
+    let many: Vec<String> = (0..MAX_BATCH_CALLS + 1)
+        .map(|at| format!("BTC-27DEC24-{}-C", 40000 + at * 1000))
+        .collect();
+    let quotes = client.get_tickers(&many).await.expect("batches");
+    assert_eq!(quotes.len(), many.len());
+    assert!(quotes.iter().all(Result::is_ok));
+}
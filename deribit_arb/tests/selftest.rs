@@ -0,0 +1,35 @@
+use anyhow::anyhow;
+use deribit_arb::selftest::{SelfTest, StepStatus};
+
+#[tokio::test]
+async fn selftest_records_passes_failures_and_skips() {
+    let mut test = SelfTest::default();
+    let value = test
+        .step("auth", async { Ok((7, "token".to_string())) })
+        .await;
+    assert_eq!(value, Some(7));
+    let failed: Option<()> = test
+        .step("combo create", async { Err(anyhow!("RPC error (11050)")) })
+        .await;
+    assert!(failed.is_none());
+    test.skip(&["leg preview"], "combo create failed");
+
+    let outcomes: Vec<(&str, StepStatus, &str)> = test
+        .steps
+        .iter()
+        .map(|step| (step.name, step.status, step.detail.as_str()))
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            ("auth", StepStatus::Passed, "token"),
+            ("combo create", StepStatus::Failed, "RPC error (11050)"),
+            ("leg preview", StepStatus::Skipped, "combo create failed"),
+        ]
+    );
+    assert!(!test.passed());
+
+    let mut skipped_only = SelfTest::default();
+    skipped_only.skip(&["ioc order"], "auth failed");
+    assert!(skipped_only.passed());
+}
@@ -0,0 +1,76 @@
+use deribit_arb::model::BookSummary;
+use deribit_arb::sweep::{find_anomalies, AnomalyKind};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+fn summary(
+    name: &str,
+    bid: Option<Decimal>,
+    ask: Option<Decimal>,
+    mark: Option<Decimal>,
+) -> BookSummary {
+    BookSummary {
+        instrument_name: name.to_string(),
+        bid_price: bid,
+        ask_price: ask,
+        mark_price: mark,
+    }
+}
+
+#[test]
+fn flags_crossed_locked_and_through_mark_books() {
+    let summaries = vec![
+        summary(
+            "BTC-27DEC24-60000-C",
+            Some(dec!(0.051)),
+            Some(dec!(0.0525)),
+            Some(dec!(0.0518)),
+        ),
+        summary(
+            "BTC-27DEC24-65000-C",
+            Some(dec!(0.04)),
+            Some(dec!(0.039)),
+            Some(dec!(0.0395)),
+        ),
+        summary(
+            "BTC-27DEC24-70000-C",
+            Some(dec!(0.03)),
+            Some(dec!(0.03)),
+            Some(dec!(0.0301)),
+        ),
+        summary(
+            "BTC-27DEC24-75000-C",
+            Some(dec!(0.021)),
+            Some(dec!(0.022)),
+            Some(dec!(0.0205)),
+        ),
+        summary(
+            "BTC-27DEC24-80000-C",
+            None,
+            Some(dec!(0.0140)),
+            Some(dec!(0.0145)),
+        ),
+        // A zero bid is an empty side, not a crossed book.
+        summary(
+            "BTC-27DEC24-90000-C",
+            Some(dec!(0)),
+            Some(dec!(0.0005)),
+            Some(dec!(0.0003)),
+        ),
+        summary("BTC-27DEC24-95000-C", Some(dec!(0.0002)), None, None),
+    ];
+    let anomalies = find_anomalies(&summaries);
+    let found: Vec<(&str, AnomalyKind)> = anomalies
+        .iter()
+        .map(|anomaly| (anomaly.instrument_name.as_str(), anomaly.kind))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("BTC-27DEC24-65000-C", AnomalyKind::Crossed),
+            ("BTC-27DEC24-70000-C", AnomalyKind::Locked),
+            ("BTC-27DEC24-75000-C", AnomalyKind::BidAboveMark),
+            ("BTC-27DEC24-80000-C", AnomalyKind::AskBelowMark),
+        ]
+    );
+}
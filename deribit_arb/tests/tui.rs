@@ -0,0 +1,49 @@
+use chrono::Utc;
+use deribit_arb::chain::OptionChain;
+use deribit_arb::model::{Currency, StrategyKind};
+use deribit_arb::risk::RiskSnapshot;
+use deribit_arb::tui::{Dashboard, ExecutionEntry};
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use rust_decimal_macros::dec;
+
+fn rendered(dashboard: &Dashboard) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(140, 40)).expect("terminal");
+    terminal
+        .draw(|frame| dashboard.draw(frame))
+        .expect("draw dashboard");
+    terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect()
+}
+
+#[test]
+fn dashboard_renders_risk_and_executions() {
+    let dashboard = Dashboard::new(OptionChain::new());
+    dashboard.update_scan(
+        Vec::new(),
+        RiskSnapshot {
+            live_combos: 2,
+            ewma_pnl: dec!(-12.5),
+            daily_pnl: dec!(-40),
+        },
+    );
+    dashboard.record_execution(ExecutionEntry {
+        at: Utc::now(),
+        account: "sub-calendar".into(),
+        strategy: StrategyKind::Calendar,
+        currency: Currency::ETH,
+        outcome: "planned combo-7".into(),
+    });
+    let screen = rendered(&dashboard);
+    assert!(screen.contains("Opportunities (0)"));
+    assert!(screen.contains("2 live combos"));
+    assert!(screen.contains("EWMA PnL $-12.50"));
+    assert!(screen.contains("planned combo-7"));
+    assert!(screen.contains("sub-calendar"));
+    assert!(screen.contains("scans 1"));
+}
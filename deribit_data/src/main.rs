@@ -0,0 +1,287 @@
+//! `deribit-data`: one entry point for the workspace's tools. Each subcommand
+//! parses the owning crate's own arguments and runs that crate's code, so
+//! `deribit-data retrieve ...` behaves exactly like `optstore retrieve ...`.
+
+use anyhow::{bail, Context, Result};
+use clap::builder::Resettable;
+use clap::{Args, CommandFactory, FromArgMatches, Id, Parser, Subcommand};
+use deribit_arb::config::{BacktestArgs, Cli as ScanCli, Command as ScanCommand};
+use optstore::exit::{self, Failure};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "deribit-data",
+    version,
+    about = "Deribit options data: retrieval, storage, scanning and research"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<DataCommand>,
+
+    /// Print the full command tree with flags and defaults as JSON and exit
+    #[arg(long = "help-json", exclusive = true)]
+    help_json: bool,
+
+    /// Machine-readable output on stdout; progress and logs go to stderr
+    #[arg(global = true, long)]
+    json: bool,
+
+    /// Suppress progress output and informational logs
+    #[arg(global = true, long)]
+    quiet: bool,
+
+    /// Scanner profile file (TOML) for `scan` and `backtest`
+    #[arg(global = true, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum DataCommand {
+    #[command(flatten)]
+    Store(optstore::cli::Commands),
+    /// Scan Deribit option chains for fee-adjusted arbitrage (the
+    /// `deribit_arb` scanner, including its `sweep`, `selftest` and
+    /// `capture` subcommands)
+    Scan(Box<ScanCli>),
+    /// Replay stored optstore book ticks through the scanner's detectors;
+    /// scanner flags such as --only apply
+    Backtest(Box<BacktestCommand>),
+    /// Find the oldest instrument of a market with recorded trades
+    Oldest(oldest_eth_options::Cli),
+}
+
+/// `deribit_arb backtest` with the scanner flags accepted after the
+/// subcommand name.
+#[derive(Args, Debug)]
+struct BacktestCommand {
+    #[command(flatten)]
+    backtest: BacktestArgs,
+
+    #[command(flatten)]
+    scan: ScanCli,
+}
+
+/// Exits with optstore's codes (`optstore::exit`): clap usage errors
+/// with 64, and failures with the code of the optstore failure behind them.
+fn main() -> ExitCode {
+    match command().try_get_matches() {
+        Ok(matches) => exit::report(run(&matches)),
+        Err(err) => exit::report_usage(err),
+    }
+}
+
+fn run(matches: &clap::ArgMatches) -> Result<()> {
+    let cli = Cli::from_arg_matches(matches)?;
+    if cli.help_json {
+        return optstore::cli::print_help_json(&command());
+    }
+    let Some(data_command) = cli.command else {
+        bail!(Failure::Usage(
+            "a subcommand is required; see --help".to_string()
+        ));
+    };
+    let (name, sub_matches) = matches.subcommand().context("missing subcommand")?;
+    match data_command {
+        DataCommand::Store(optstore::cli::Commands::Completions(command)) => {
+            command.print(&mut self::command());
+            Ok(())
+        }
+        DataCommand::Store(command) => {
+            reject_config(&cli.config, name)?;
+            tracing_subscriber::fmt()
+                .with_env_filter(EnvFilter::from_default_env())
+                .with_target(false)
+                .compact()
+                .init();
+            command.execute(cli.quiet, cli.json)
+        }
+        DataCommand::Scan(_) => {
+            // Re-read from the matches so the profile sits under explicit flags.
+            let scan = scanner_cli(sub_matches, cli.json)?;
+            runtime()?.block_on(deribit_arb::app::run_cli(scan))
+        }
+        DataCommand::Backtest(command) => {
+            if let Some(nested) = sub_matches.subcommand_name() {
+                bail!("backtest takes no subcommand, got {nested}");
+            }
+            let mut scan = scanner_cli(sub_matches, cli.json)?;
+            scan.command = Some(ScanCommand::Backtest(command.backtest));
+            runtime()?.block_on(deribit_arb::app::run_cli(scan))
+        }
+        DataCommand::Oldest(command) => {
+            reject_config(&cli.config, name)?;
+            runtime()?.block_on(oldest_eth_options::run(command))
+        }
+    }
+}
+
+/// The derived CLI with the scanner's own subcommands hidden under
+/// `backtest`, which flattens the scanner's arguments only for their flags.
+/// clap checks `--profile`'s `requires = "config"` before a global
+/// `--config` given ahead of the subcommand reaches it, so
+/// [`scanner_cli`] checks it instead.
+fn command() -> clap::Command {
+    Cli::command()
+        .mut_subcommand("scan", |scan| {
+            scan.mut_arg("profile", |arg| arg.requires(Resettable::<Id>::Reset))
+        })
+        .mut_subcommand("backtest", |backtest| {
+            ["backtest", "sweep", "selftest", "capture"]
+                .into_iter()
+                .fold(backtest, |backtest, name| {
+                    backtest.mut_subcommand(name, |nested| nested.hide(true))
+                })
+                .mut_arg("profile", |arg| arg.requires(Resettable::<Id>::Reset))
+                .disable_help_subcommand(true)
+                .override_usage(
+                    "deribit-data backtest [OPTIONS] --data <DATA> --from <FROM> --to <TO>",
+                )
+        })
+}
+
+/// The scanner's arguments with its `--config` profile layered underneath;
+/// `--json` selects JSON lines on stdout.
+fn scanner_cli(matches: &clap::ArgMatches, json: bool) -> Result<ScanCli> {
+    if matches.get_one::<String>("profile").is_some()
+        && matches.get_one::<PathBuf>("config").is_none()
+    {
+        bail!("--profile requires --config");
+    }
+    let mut scan = ScanCli::layered(matches)?;
+    if json {
+        scan.output = "jsonl".to_string();
+    }
+    Ok(scan)
+}
+
+fn reject_config(config: &Option<PathBuf>, command: &str) -> Result<()> {
+    if config.is_some() {
+        bail!("--config only applies to scan and backtest, not {command}");
+    }
+    Ok(())
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    Ok(tokio::runtime::Runtime::new()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> (Cli, clap::ArgMatches) {
+        let matches = command()
+            .try_get_matches_from(std::iter::once("deribit-data").chain(args.iter().copied()))
+            .expect("valid arguments");
+        (Cli::from_arg_matches(&matches).expect("cli"), matches)
+    }
+
+    #[test]
+    fn global_flags_reach_embedded_commands_from_either_side() {
+        for args in [
+            ["--json", "--quiet", "oldest"],
+            ["oldest", "--json", "--quiet"],
+        ] {
+            let (cli, matches) = parse(&args);
+            let (_, oldest) = matches.subcommand().expect("oldest");
+            assert!(cli.json && cli.quiet);
+            assert!(oldest.get_flag("json") && oldest.get_flag("quiet"));
+        }
+
+        let (cli, matches) = parse(&[
+            "--config",
+            "arb.toml",
+            "scan",
+            "--profile",
+            "prod",
+            "--quiet",
+        ]);
+        let (_, scan) = matches.subcommand().expect("scan");
+        let scan = ScanCli::from_arg_matches(scan).expect("scanner cli");
+        assert_eq!(cli.config, Some(PathBuf::from("arb.toml")));
+        assert_eq!(scan.config, Some(PathBuf::from("arb.toml")));
+        assert_eq!(scan.profile.as_deref(), Some("prod"));
+        assert!(scan.quiet);
+
+        let (_, matches) = parse(&["scan", "--profile", "prod"]);
+        let (_, scan) = matches.subcommand().expect("scan");
+        assert!(scanner_cli(scan, false).is_err());
+    }
+
+    #[test]
+    fn backtest_takes_scanner_flags_after_its_name() {
+        let (cli, matches) = parse(&[
+            "backtest",
+            "--data",
+            "ticks",
+            "--from",
+            "2024-01-01",
+            "--to",
+            "2024-01-02",
+            "--only",
+            "box",
+            "--json",
+        ]);
+        let Some(DataCommand::Backtest(command)) = cli.command else {
+            panic!("expected backtest");
+        };
+        assert_eq!(command.backtest.data, PathBuf::from("ticks"));
+        let (_, backtest) = matches.subcommand().expect("backtest");
+        let scan = scanner_cli(backtest, cli.json).expect("scanner cli");
+        assert_eq!(scan.only, ["box"]);
+        assert_eq!(scan.output, "jsonl");
+    }
+
+    #[test]
+    fn store_commands_delegate_to_optstore() {
+        let (cli, _) = parse(&["query", "--file", "day.opt", "--explain"]);
+        assert!(matches!(
+            cli.command,
+            Some(DataCommand::Store(optstore::cli::Commands::Query(ref query))) if query.explain
+        ));
+        assert!(reject_config(&Some(PathBuf::from("arb.toml")), "query").is_err());
+    }
+
+    #[test]
+    fn help_json_covers_every_command_and_completions_name_this_cli() {
+        let (cli, _) = parse(&["--help-json"]);
+        assert!(cli.help_json && cli.command.is_none());
+
+        let help = optstore::help::command_help(&command());
+        let names: Vec<&str> = help
+            .subcommands
+            .iter()
+            .map(|sub| sub.name.as_str())
+            .collect();
+        for name in [
+            "retrieve",
+            "stats",
+            "completions",
+            "scan",
+            "backtest",
+            "oldest",
+        ] {
+            assert!(names.contains(&name), "{name} missing from {names:?}");
+        }
+        let backtest = help
+            .subcommands
+            .iter()
+            .find(|sub| sub.name == "backtest")
+            .unwrap();
+        assert!(
+            backtest.subcommands.is_empty(),
+            "hidden scanner subcommands"
+        );
+        let data = backtest.args.iter().find(|arg| arg.id == "data").unwrap();
+        assert!(data.required);
+
+        let (cli, _) = parse(&["completions", "zsh"]);
+        assert!(matches!(
+            cli.command,
+            Some(DataCommand::Store(optstore::cli::Commands::Completions(_)))
+        ));
+    }
+}
@@ -0,0 +1,1696 @@
+//! Finds the oldest Deribit instrument of a market with recorded trades. The
+//! `oldest_eth_options` binary and `deribit-data oldest` both parse [`Cli`] and
+//! hand it to [`run`].
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{TimeZone, Utc};
+use clap::{Parser, ValueEnum};
+use deribit_api::{
+    ApiClient, Endpoint, GetInstruments, GetLastTradesByInstrumentAndTime, Host, InstrumentKind,
+    TradeRecord,
+};
+use futures::stream::{self, StreamExt};
+use optstore::retrieve::{self, RetrieveCommand, RetrieveKindArg};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_slice, to_vec_pretty};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+const API_HOSTS: [Host; 2] = [Host::History, Host::Production];
+const CACHE_DIR: &str = ".deribit_cache";
+const INSTRUMENT_CACHE_PREFIX: &str = "instruments";
+const CHECKPOINT_PREFIX: &str = "probes";
+
+/// Set by `--json`, which reserves stdout for the report.
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+/// Set by `--quiet`, which drops progress lines altogether.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Print a progress line to stdout, or to stderr in `--json` mode.
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if QUIET.load(Ordering::Relaxed) {
+        } else if JSON_OUTPUT.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Find the oldest Deribit instrument of a market with recorded trades.
+#[derive(Parser, Debug)]
+#[command(name = "oldest_eth_options", version, about)]
+pub struct Cli {
+    /// Base currency of the market (BTC, ETH, SOL, ...).
+    #[arg(long, default_value = "ETH")]
+    currency: String,
+
+    /// Instrument kind to search.
+    #[arg(long, value_enum, default_value_t = Kind::Option)]
+    kind: Kind,
+
+    /// Only consider this settlement period (day, week, month, quarter, perpetual).
+    #[arg(long)]
+    settlement: Option<String>,
+
+    /// Print the result as JSON on stdout; progress goes to stderr.
+    #[arg(long)]
+    json: bool,
+
+    /// Skip progress and request logs; warnings and the result are still printed.
+    #[arg(long)]
+    quiet: bool,
+
+    /// Instruments probed at once.
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u16).range(1..=32))]
+    concurrency: u16,
+
+    /// Requests per second, shared by both hosts.
+    #[arg(long, default_value_t = 10)]
+    rate: u32,
+
+    /// Order in which instruments are probed for trades.
+    #[arg(long, value_enum, default_value_t = Search::Bisect)]
+    search: Search,
+
+    /// Reuse the probe results checkpointed by earlier runs instead of starting over.
+    #[arg(long)]
+    resume: bool,
+
+    /// Backfill the trades of the oldest traded instrument and every later listing into an
+    /// optstore raw cache, one retrieve per instrument and day.
+    #[arg(long, requires = "out")]
+    download: bool,
+
+    /// Output directory for `--download`, as `optstore retrieve --out`.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Search {
+    /// Narrow down the creation time where recorded trades begin, then scan the
+    /// remaining gap oldest first.
+    Bisect,
+    /// Probe every instrument oldest first.
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Option,
+    Future,
+}
+
+impl Kind {
+    fn instrument_kind(self) -> InstrumentKind {
+        match self {
+            Kind::Option => InstrumentKind::Option,
+            Kind::Future => InstrumentKind::Future,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Option => "option",
+            Kind::Future => "future",
+        }
+    }
+}
+
+/// The market being searched, e.g. "ETH option" or "BTC future (week)".
+#[derive(Clone, Debug, Serialize)]
+struct Market {
+    currency: String,
+    kind: Kind,
+    settlement: Option<String>,
+}
+
+impl Market {
+    fn from_cli(cli: &Cli) -> Self {
+        Self {
+            currency: cli.currency.to_uppercase(),
+            kind: cli.kind,
+            settlement: cli.settlement.as_ref().map(|period| period.to_lowercase()),
+        }
+    }
+
+    fn matches(&self, instrument: &Instrument) -> bool {
+        match &self.settlement {
+            Some(period) => instrument.settlement_period.as_deref() == Some(period.as_str()),
+            None => true,
+        }
+    }
+
+    fn plural(&self) -> String {
+        let mut label = format!("{} {}s", self.currency, self.kind.as_str());
+        if let Some(period) = &self.settlement {
+            label.push_str(&format!(" ({period})"));
+        }
+        label
+    }
+}
+
+impl fmt::Display for Market {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.currency, self.kind.as_str())?;
+        if let Some(period) = &self.settlement {
+            write!(f, " ({period})")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Instrument {
+    name: String,
+    creation: u64,
+    expiration: Option<u64>,
+    strike: Option<f64>,
+    option_type: Option<String>,
+    settlement_period: Option<String>,
+    base_currency: Option<String>,
+    quote_currency: Option<String>,
+    underlying_index: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RequestStats {
+    total_elapsed: Duration,
+    bytes: usize,
+}
+
+struct FetchResult<T> {
+    data: T,
+    stats: RequestStats,
+}
+
+struct TradeFetch {
+    trades: Vec<TradeRecord>,
+    has_more: Option<bool>,
+    stats: RequestStats,
+    host: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TradeSample {
+    instrument: String,
+    host: String,
+    trades: usize,
+    has_more: Option<bool>,
+    stats: RequestStats,
+}
+
+/// One probed instrument as stored in the checkpoint file. `trades` is empty when no host
+/// has any on record.
+#[derive(Clone, Serialize, Deserialize)]
+struct ProbeRecord {
+    instrument: String,
+    trades: Vec<TradeRecord>,
+    samples: Vec<TradeSample>,
+    /// Milliseconds since the epoch; zero in checkpoints written before it was recorded.
+    #[serde(default)]
+    probed_at: u64,
+}
+
+impl ProbeRecord {
+    /// Whether the record still answers for `instrument`: not once the instrument has expired
+    /// since it was probed, as its trades may only have reached the history host since.
+    fn current_for(&self, instrument: &Instrument, now_ms: u64) -> bool {
+        !instrument
+            .expiration
+            .is_some_and(|expiration| self.probed_at < expiration && expiration <= now_ms)
+    }
+}
+
+/// The `--json` report.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    market: &'a Market,
+    total_instruments: usize,
+    /// The oldest instrument with recorded trades, if any was found.
+    instrument: Option<&'a Instrument>,
+    /// Its oldest trades, oldest first.
+    trades: &'a [TradeRecord],
+    estimation: Option<EstimationSummary>,
+    /// Present with `--download`.
+    download: Option<DownloadSummary>,
+}
+
+/// What `--download` fetched, counted in instrument-days.
+#[derive(Serialize)]
+struct DownloadSummary {
+    out: PathBuf,
+    instruments: usize,
+    days: usize,
+    failed: usize,
+}
+
+struct InstrumentFetchResult {
+    instruments: Vec<Instrument>,
+    cache_path: PathBuf,
+    from_cache: bool,
+}
+
+/// Find the oldest instrument of the requested market with recorded trades and print a summary.
+pub async fn run(cli: Cli) -> Result<()> {
+    JSON_OUTPUT.store(cli.json, Ordering::Relaxed);
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    let market = Market::from_cli(&cli);
+    let api = ApiClient::new(Host::Production).with_rate_limit(cli.rate);
+    let clients = API_HOSTS.map(|host| api.clone().with_host(host));
+
+    let instruments_map = fetch_all_instruments(&clients, &market).await?;
+    let mut instrument_list: Vec<_> = instruments_map
+        .into_values()
+        .filter(|inst| market.matches(inst))
+        .collect();
+    instrument_list.sort_by_key(|inst| inst.creation);
+
+    if instrument_list.is_empty() {
+        return Err(anyhow!(
+            "no {} instruments found from either Deribit host",
+            market
+        ));
+    }
+
+    let total_instruments = instrument_list.len();
+
+    progress!(
+        "{} {}",
+        format!("Total unique {} discovered:", market.plural())
+            .bold()
+            .bright_white(),
+        total_instruments.to_string().bold().cyan()
+    );
+
+    let checkpoint = Checkpoint::open(&market, cli.resume)?;
+    let mut prober = Prober::new(
+        clients.as_slice(),
+        &instrument_list,
+        usize::from(cli.concurrency),
+        checkpoint,
+    );
+    let oldest = match cli.search {
+        Search::Bisect => prober.bisect().await,
+        Search::Linear => prober.scan(0..total_instruments).await,
+    };
+    let trade_samples = prober.samples;
+    let oldest_instrument = oldest.as_ref().map(|(index, _)| &instrument_list[*index]);
+    let oldest_trades = oldest
+        .as_ref()
+        .map_or(&[][..], |(_, trades)| trades.as_slice());
+
+    if !cli.json {
+        match oldest_instrument {
+            Some(instrument) => print_instrument(&market, instrument, oldest_trades),
+            None => println!(
+                "{}",
+                format!("Unable to locate any {market} with recorded trades via the public API.")
+                    .red()
+                    .bold()
+            ),
+        }
+        print_estimation(total_instruments, &trade_samples);
+    }
+
+    let download = match (&oldest, &cli.out) {
+        (Some((index, trades)), Some(out)) if cli.download => {
+            let first_trade_ms = trades.first().map_or(0, |trade| trade.timestamp);
+            Some(download(&instrument_list[*index..], first_trade_ms, out, &cli).await)
+        }
+        _ => None,
+    };
+
+    if cli.json {
+        let report = JsonReport {
+            market: &market,
+            total_instruments,
+            instrument: oldest_instrument,
+            trades: oldest_trades,
+            estimation: estimate_download_requirements(total_instruments, &trade_samples),
+            download,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    }
+    Ok(())
+}
+
+/// Run optstore's retrieve pipeline for every day each instrument was listed, from its creation
+/// (or the first recorded trade, for the oldest) to its expiration or today. Failed days are
+/// reported and skipped; with `--resume` optstore continues partially cached days.
+async fn download(
+    instruments: &[Instrument],
+    first_trade_ms: u64,
+    out: &Path,
+    cli: &Cli,
+) -> DownloadSummary {
+    let now_ms = u64::try_from(Utc::now().timestamp_millis()).unwrap_or(0);
+    let mut summary = DownloadSummary {
+        out: out.to_path_buf(),
+        instruments: instruments.len(),
+        days: 0,
+        failed: 0,
+    };
+
+    for instrument in instruments {
+        let start_ms = instrument.creation.max(first_trade_ms);
+        let end_ms = instrument.expiration.unwrap_or(now_ms).min(now_ms);
+        let days = trading_days(start_ms, end_ms);
+        progress!(
+            "{} {} {} {}",
+            "Downloading".bold().blue(),
+            instrument.name.as_str().cyan(),
+            days.len().to_string().bold().cyan(),
+            "days".dimmed()
+        );
+
+        for day in days {
+            let command = RetrieveCommand {
+                source: "deribit".to_string(),
+                symbol: instrument.name.clone(),
+                day: day.clone(),
+                out: out.to_path_buf(),
+                resume: cli.resume,
+                max_pages: 0,
+                rate: cli.rate,
+                kind: RetrieveKindArg::Trades,
+                train_dict: false,
+                fail_on_empty: false,
+            };
+            // optstore drives its own runtime, so it runs off the async workers.
+            let quiet = cli.json || cli.quiet;
+            let result = tokio::task::spawn_blocking(move || retrieve::run(command, quiet, false))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            summary.days += 1;
+            if let Err(err) = result {
+                summary.failed += 1;
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Warning: failed to download {} on {day}: {err:#}",
+                        instrument.name
+                    )
+                    .bold()
+                    .red()
+                );
+            }
+        }
+    }
+
+    progress!(
+        "{} {} {} {} ({} {})",
+        "Downloaded".bold().green(),
+        (summary.days - summary.failed).to_string().bold().cyan(),
+        "instrument-days into".dimmed(),
+        out.display(),
+        summary.failed.to_string().red(),
+        "failed".dimmed()
+    );
+    summary
+}
+
+/// UTC days (`YYYY-MM-DD`) touched by `[start_ms, end_ms]`.
+fn trading_days(start_ms: u64, end_ms: u64) -> Vec<String> {
+    let day = |ms: u64| {
+        Utc.timestamp_millis_opt(ms as i64)
+            .single()
+            .map(|dt| dt.date_naive())
+    };
+    let (Some(mut current), Some(last)) = (day(start_ms), day(end_ms)) else {
+        return Vec::new();
+    };
+    let mut days = Vec::new();
+    while current <= last {
+        days.push(current.format("%Y-%m-%d").to_string());
+        match current.succ_opt() {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    days
+}
+
+/// Print the oldest instrument's metadata and its first trades.
+fn print_instrument(market: &Market, instrument: &Instrument, trades: &[TradeRecord]) {
+    let creation_iso = format_timestamp(instrument.creation);
+    let expiration_iso = instrument
+        .expiration
+        .map(|ts| format_timestamp(ts).bright_white().to_string())
+        .unwrap_or_else(|| "unknown".dimmed().to_string());
+    let strike_value = instrument
+        .strike
+        .map(|s| format!("{s:.2}").yellow().bold().to_string())
+        .unwrap_or_else(|| "N/A".dimmed().to_string());
+    let option_type = instrument
+        .option_type
+        .as_deref()
+        .map(|t| match t {
+            "call" | "C" => "CALL".green().bold().to_string(),
+            "put" | "P" => "PUT".red().bold().to_string(),
+            other => other.cyan().to_string(),
+        })
+        .unwrap_or_else(|| "unknown".dimmed().to_string());
+    let settlement = instrument
+        .settlement_period
+        .as_deref()
+        .map(|p| p.cyan().to_string())
+        .unwrap_or_else(|| "unknown".dimmed().to_string());
+    let underlying = instrument
+        .underlying_index
+        .as_deref()
+        .map(|u| u.bright_white().to_string())
+        .unwrap_or_else(|| "unknown".dimmed().to_string());
+    let base_currency = instrument.base_currency.as_deref().unwrap_or("?");
+    let quote_currency = instrument.quote_currency.as_deref().unwrap_or("?");
+
+    println!();
+    println!(
+        "{}",
+        format!("Earliest {market} with recorded trades:")
+            .bold()
+            .bright_green()
+    );
+    println!(
+        "{} {}",
+        "Instrument:".bold(),
+        instrument.name.as_str().bright_cyan().bold()
+    );
+    println!(
+        "{} {} ({} {})",
+        "Creation:".bold(),
+        creation_iso.bright_white(),
+        instrument.creation,
+        "ms since epoch".dimmed()
+    );
+    println!("{} {}", "Expiration:".bold(), expiration_iso);
+    println!("{} {}", "Strike:".bold(), strike_value);
+    println!("{} {}", "Option Type:".bold(), option_type);
+    println!("{} {}", "Settlement:".bold(), settlement);
+    println!(
+        "{} {}/{}",
+        "Quote/Base:".bold(),
+        quote_currency.yellow(),
+        base_currency.yellow()
+    );
+    println!("{} {}", "Underlying:".bold(), underlying);
+
+    println!();
+    println!("{}", "Oldest trades:".underline().bold());
+    for trade in trades.iter().take(10) {
+        print_trade(trade, instrument);
+    }
+}
+
+/// Retrieve expired instruments from both Deribit hosts plus the live listings from production
+/// (perpetuals and long-dated futures can predate every expired contract), deduplicating by name
+/// while preserving the earliest creation timestamp.
+async fn fetch_all_instruments(
+    clients: &[ApiClient],
+    market: &Market,
+) -> Result<HashMap<String, Instrument>> {
+    let mut instruments: HashMap<String, Instrument> = HashMap::new();
+    let listings = clients.iter().map(|client| (client, true)).chain(
+        clients
+            .iter()
+            .filter(|client| client.base_url() == Host::Production.base_url())
+            .map(|client| (client, false)),
+    );
+
+    for (client, expired) in listings {
+        let host = client.base_url();
+        let status = if expired { "expired" } else { "live" };
+        match fetch_instruments_from_host(client, market, expired).await {
+            Ok(InstrumentFetchResult {
+                instruments: host_instruments,
+                cache_path,
+                from_cache,
+            }) => {
+                let count = host_instruments.len();
+                if from_cache {
+                    progress!(
+                        "{} {} {} {} ({})",
+                        "Using cache".bold().blue(),
+                        count.to_string().bold().cyan(),
+                        format!("{status} {} metadata for", market.plural()).dimmed(),
+                        host.cyan(),
+                        cache_path.display()
+                    );
+                } else {
+                    progress!(
+                        "{} {} {} {} ({})",
+                        "Fetched".bold().blue(),
+                        count.to_string().bold().cyan(),
+                        format!("{status} {} metadata from", market.plural()).dimmed(),
+                        host.cyan(),
+                        cache_path.display()
+                    );
+                }
+                for inst in host_instruments {
+                    instruments
+                        .entry(inst.name.clone())
+                        .and_modify(|existing| {
+                            existing.creation = existing.creation.min(inst.creation)
+                        })
+                        .or_insert(inst);
+                }
+            }
+            Err(err) => eprintln!(
+                "{}",
+                format!("Warning: failed to fetch instruments from {host}: {err}")
+                    .bold()
+                    .red()
+            ),
+        }
+    }
+
+    Ok(instruments)
+}
+
+/// Fetch the expired or live instruments of a market's currency and kind from a specific host.
+/// The settlement filter is applied by the caller so one cache serves every period.
+async fn fetch_instruments_from_host(
+    client: &ApiClient,
+    market: &Market,
+    expired: bool,
+) -> Result<InstrumentFetchResult> {
+    let host = client.base_url();
+    let request = GetInstruments {
+        currency: market.currency.clone(),
+        kind: market.kind.instrument_kind(),
+        expired,
+    };
+    let context = format!("instrument request to {host}");
+    let cache_path = instrument_cache_path(host, market, expired);
+
+    if let Some(cached) = load_cached_instruments(&cache_path)? {
+        return Ok(InstrumentFetchResult {
+            instruments: cached,
+            cache_path,
+            from_cache: true,
+        });
+    }
+
+    let FetchResult { data: records, .. } = fetch(client, &request, context.as_str()).await?;
+
+    // Listings without a creation timestamp cannot be ordered by age.
+    let instruments: Vec<Instrument> = records
+        .into_iter()
+        .filter_map(|record| {
+            let creation = u64::try_from(record.creation_timestamp?).ok()?;
+            Some((record, creation))
+        })
+        .map(|(record, creation)| Instrument {
+            name: record.instrument_name,
+            creation,
+            expiration: record
+                .expiration_timestamp
+                .and_then(|ts| u64::try_from(ts).ok()),
+            strike: record.strike,
+            option_type: record.option_type,
+            settlement_period: record.settlement_period,
+            base_currency: record.base_currency,
+            quote_currency: record.quote_currency,
+            underlying_index: record.underlying_index,
+        })
+        .collect();
+
+    if let Err(err) = store_cached_instruments(&cache_path, &instruments) {
+        eprintln!(
+            "{}",
+            format!("Warning: failed to persist instrument cache for {host}: {err}")
+                .bold()
+                .red()
+        );
+    }
+
+    Ok(InstrumentFetchResult {
+        instruments,
+        cache_path,
+        from_cache: false,
+    })
+}
+
+fn instrument_cache_path(host: &str, market: &Market, expired: bool) -> PathBuf {
+    let trimmed = host
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let sanitized = trimmed.replace('/', "_");
+    let status = if expired { "expired" } else { "live" };
+    PathBuf::from(CACHE_DIR).join(format!(
+        "{INSTRUMENT_CACHE_PREFIX}_{}_{}_{status}_{sanitized}.json",
+        market.currency,
+        market.kind.as_str()
+    ))
+}
+
+fn load_cached_instruments(path: &Path) -> Result<Option<Vec<Instrument>>> {
+    match fs::read(path) {
+        Ok(bytes) => match from_slice::<Vec<Instrument>>(&bytes) {
+            Ok(data) => {
+                progress!(
+                    "{} {}",
+                    "Loaded instrument cache from".bold().blue(),
+                    path.display()
+                );
+                Ok(Some(data))
+            }
+            Err(err) => {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Warning: instrument cache at {} is invalid: {err}",
+                        path.display()
+                    )
+                    .bold()
+                    .red()
+                );
+                Ok(None)
+            }
+        },
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn store_cached_instruments(path: &Path, instruments: &[Instrument]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let data = to_vec_pretty(instruments)?;
+    fs::write(path, data)?;
+    progress!(
+        "{} {}",
+        "Stored instrument cache at".bold().blue(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Probe results of earlier runs, keyed by instrument name, and the file new results are
+/// appended to as they arrive, one JSON line each.
+struct Checkpoint {
+    known: HashMap<String, ProbeRecord>,
+    log: CheckpointLog,
+}
+
+struct CheckpointLog {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl Checkpoint {
+    /// Open the market's checkpoint, loading its results when resuming and truncating it otherwise.
+    fn open(market: &Market, resume: bool) -> Result<Self> {
+        let path = PathBuf::from(CACHE_DIR).join(format!(
+            "{CHECKPOINT_PREFIX}_{}_{}.jsonl",
+            market.currency,
+            market.kind.as_str()
+        ));
+        Self::open_at(path, resume)
+    }
+
+    fn open_at(path: PathBuf, resume: bool) -> Result<Self> {
+        let mut known = HashMap::new();
+        let mut needs_newline = false;
+
+        if resume {
+            match fs::read_to_string(&path) {
+                Ok(text) => {
+                    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                        match serde_json::from_str::<ProbeRecord>(line) {
+                            Ok(record) => {
+                                known.insert(record.instrument.clone(), record);
+                            }
+                            // A run interrupted mid-write leaves a partial last line.
+                            Err(err) => eprintln!(
+                                "{}",
+                                format!(
+                                    "Warning: skipping invalid checkpoint line in {}: {err}",
+                                    path.display()
+                                )
+                                .bold()
+                                .red()
+                            ),
+                        }
+                    }
+                    needs_newline = !text.is_empty() && !text.ends_with('\n');
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(err).with_context(|| format!("reading {}", path.display()));
+                }
+            }
+            progress!(
+                "{} {} {} ({})",
+                "Resuming with".bold().blue(),
+                known.len().to_string().bold().cyan(),
+                "instruments already probed".dimmed(),
+                path.display()
+            );
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut options = OpenOptions::new();
+        options.create(true);
+        if resume {
+            options.append(true);
+        } else {
+            options.write(true).truncate(true);
+        }
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("opening checkpoint {}", path.display()))?;
+        if needs_newline {
+            file.write_all(b"\n")?;
+        }
+
+        Ok(Self {
+            known,
+            log: CheckpointLog {
+                path,
+                file: Some(file),
+            },
+        })
+    }
+}
+
+impl CheckpointLog {
+    /// Append a fresh probe result; a failed write disables the checkpoint for the rest of the run.
+    fn append(&mut self, record: &ProbeRecord) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_vec(record)
+            .map_err(std::io::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                file.write_all(&line)
+            });
+        if let Err(err) = written {
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: failed to write checkpoint {}: {err}",
+                    self.path.display()
+                )
+                .bold()
+                .red()
+            );
+            self.file = None;
+        }
+    }
+}
+
+/// Where [`Prober`] gets an instrument's oldest trades: the Deribit hosts, or a stub in tests.
+trait TradeSource {
+    /// The instrument's oldest trades, if any host has some, with one sample per host asked.
+    async fn oldest_trades(
+        &self,
+        instrument_name: &str,
+    ) -> (Option<Vec<TradeRecord>>, Vec<TradeSample>);
+}
+
+impl TradeSource for [ApiClient] {
+    async fn oldest_trades(
+        &self,
+        instrument_name: &str,
+    ) -> (Option<Vec<TradeRecord>>, Vec<TradeSample>) {
+        fetch_oldest_trades(self, instrument_name).await
+    }
+}
+
+/// Probes instruments (sorted oldest first) for recorded trades, several at a time.
+struct Prober<'a, S: TradeSource + ?Sized> {
+    source: &'a S,
+    instruments: &'a [Instrument],
+    concurrency: usize,
+    logged: usize,
+    checkpoint: Checkpoint,
+    /// When the run started, in milliseconds since the epoch.
+    now_ms: u64,
+    samples: Vec<TradeSample>,
+}
+
+impl<'a, S: TradeSource + ?Sized> Prober<'a, S> {
+    fn new(
+        source: &'a S,
+        instruments: &'a [Instrument],
+        concurrency: usize,
+        checkpoint: Checkpoint,
+    ) -> Self {
+        Self {
+            source,
+            instruments,
+            concurrency: concurrency.max(1),
+            logged: 0,
+            checkpoint,
+            now_ms: u64::try_from(Utc::now().timestamp_millis()).unwrap_or(0),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Find the oldest instrument with trades assuming Deribit's trade history starts at some
+    /// point in time: everything listed well before it has none, nearly everything after it has
+    /// some. Each round probes `concurrency` evenly spaced instruments and keeps the gap between
+    /// the last empty probe and the first hit; the final gap is scanned in order, so sparse hits
+    /// near the boundary are still found.
+    async fn bisect(&mut self) -> Option<(usize, Vec<TradeRecord>)> {
+        let window = self.concurrency * 4;
+        let (mut lo, mut hi) = (0, self.instruments.len());
+        let mut best = None;
+
+        while hi - lo > window {
+            let step = (hi - lo) / (self.concurrency + 1);
+            let probes: Vec<usize> = (1..=self.concurrency).map(|k| lo + k * step).collect();
+            progress!(
+                "{} {} {} {} {} {}",
+                "Bisecting".bold().blue(),
+                (hi - lo).to_string().cyan(),
+                "instruments created".dimmed(),
+                format_timestamp(self.instruments[lo].creation).dimmed(),
+                "to".dimmed(),
+                format_timestamp(self.instruments[hi - 1].creation).dimmed()
+            );
+
+            let mut results = self.probe_all(&probes).await;
+            match results.iter().position(Option::is_some) {
+                Some(k) => {
+                    best = results.swap_remove(k).map(|trades| (probes[k], trades));
+                    hi = probes[k];
+                    if k > 0 {
+                        lo = probes[k - 1] + 1;
+                    }
+                }
+                None => lo = probes[self.concurrency - 1] + 1,
+            }
+        }
+
+        self.scan(lo..hi).await.or(best)
+    }
+
+    /// The first instrument in `range` with trades, probed in order with up to `concurrency`
+    /// requests in flight; probes queued behind a hit are dropped.
+    async fn scan(&mut self, range: Range<usize>) -> Option<(usize, Vec<TradeRecord>)> {
+        let (source, instruments, now_ms) = (self.source, self.instruments, self.now_ms);
+        let known = &self.checkpoint.known;
+        let logged = &mut self.logged;
+        let mut probes = stream::iter(range)
+            .map(|index| {
+                let instrument = &instruments[index];
+                // Avoid spamming output by only logging the first few probes.
+                if *logged < 3 {
+                    progress!(
+                        "{} {} {} {}",
+                        "Probing instrument".bold().blue(),
+                        instrument.name.as_str().cyan(),
+                        "created".dimmed(),
+                        format_timestamp(instrument.creation).dimmed()
+                    );
+                    *logged += 1;
+                    if *logged == 3 {
+                        progress!(
+                            "{}",
+                            "Further instrument probes suppressed until trades are found..."
+                                .italic()
+                                .dimmed()
+                        );
+                    }
+                }
+                async move { (index, probe(source, known, instrument, now_ms).await) }
+            })
+            .buffered(self.concurrency);
+
+        while let Some((index, (record, fresh))) = probes.next().await {
+            if fresh {
+                self.checkpoint.log.append(&record);
+            }
+            self.samples.extend(record.samples);
+            if !record.trades.is_empty() {
+                return Some((index, record.trades));
+            }
+        }
+        None
+    }
+
+    /// Probe `indices` concurrently; results come back in the same order.
+    async fn probe_all(&mut self, indices: &[usize]) -> Vec<Option<Vec<TradeRecord>>> {
+        let (source, instruments, now_ms) = (self.source, self.instruments, self.now_ms);
+        let known = &self.checkpoint.known;
+        let results: Vec<_> = stream::iter(indices)
+            .map(|&index| probe(source, known, &instruments[index], now_ms))
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+        results
+            .into_iter()
+            .map(|(record, fresh)| {
+                if fresh {
+                    self.checkpoint.log.append(&record);
+                }
+                self.samples.extend(record.samples);
+                Some(record.trades).filter(|trades| !trades.is_empty())
+            })
+            .collect()
+    }
+}
+
+/// Answer a probe from the checkpoint unless the instrument expired since, or ask the hosts;
+/// the flag is set for fresh results.
+async fn probe(
+    source: &(impl TradeSource + ?Sized),
+    known: &HashMap<String, ProbeRecord>,
+    instrument: &Instrument,
+    now_ms: u64,
+) -> (ProbeRecord, bool) {
+    if let Some(record) = known
+        .get(&instrument.name)
+        .filter(|record| record.current_for(instrument, now_ms))
+    {
+        return (record.clone(), false);
+    }
+    let (trades, samples) = source.oldest_trades(&instrument.name).await;
+    // Nothing to checkpoint when every host failed; the next run asks again.
+    let fresh = !samples.is_empty();
+    let record = ProbeRecord {
+        instrument: instrument.name.clone(),
+        trades: trades.unwrap_or_default(),
+        samples,
+        probed_at: now_ms,
+    };
+    (record, fresh)
+}
+
+/// Attempt to obtain the oldest recorded trades for an instrument across available hosts,
+/// returning them (if any) with one sample per host asked.
+async fn fetch_oldest_trades(
+    clients: &[ApiClient],
+    instrument_name: &str,
+) -> (Option<Vec<TradeRecord>>, Vec<TradeSample>) {
+    let mut samples = Vec::new();
+    for client in clients {
+        let host = client.base_url();
+        match fetch_trades_from_host(client, instrument_name).await {
+            Ok(fetch) => {
+                samples.push(TradeSample {
+                    instrument: instrument_name.to_string(),
+                    host: fetch.host.clone(),
+                    trades: fetch.trades.len(),
+                    has_more: fetch.has_more,
+                    stats: fetch.stats.clone(),
+                });
+
+                if !fetch.trades.is_empty() {
+                    return (Some(fetch.trades), samples);
+                }
+            }
+            Err(err) => eprintln!(
+                "{}",
+                format!("Warning: failed to fetch trades for {instrument_name} from {host}: {err}")
+                    .bold()
+                    .red()
+            ),
+        }
+    }
+
+    (None, samples)
+}
+
+/// Fetch trades for a specific instrument from a single host.
+async fn fetch_trades_from_host(client: &ApiClient, instrument_name: &str) -> Result<TradeFetch> {
+    let host = client.base_url();
+    let request = GetLastTradesByInstrumentAndTime {
+        instrument_name: instrument_name.to_string(),
+        start_timestamp: 0,
+        end_timestamp: None,
+        count: Some(100),
+        include_oldest: Some(true),
+    };
+    let context = format!("trades request for {instrument_name} via {host}");
+    let FetchResult { data: page, stats } = fetch(client, &request, context.as_str()).await?;
+
+    Ok(TradeFetch {
+        trades: page.trades,
+        has_more: Some(page.has_more),
+        stats,
+        host: host.to_string(),
+    })
+}
+
+/// Issue a request, log timing details, and decode the JSON-RPC result into the endpoint's response type.
+async fn fetch<E: Endpoint>(
+    client: &ApiClient,
+    request: &E,
+    context: &str,
+) -> Result<FetchResult<E::Response>> {
+    let url = format!("{}/{}", client.base_url(), E::METHOD);
+    let params_repr = serde_json::to_string(request).unwrap_or_default();
+
+    let start = Instant::now();
+    let raw_body = match client.request_raw(request).await {
+        Ok(body) => body,
+        Err(err) => {
+            let line = format!(
+                "{} {} params {} -> {} {}",
+                "HTTP POST".bold().red(),
+                url.cyan(),
+                params_repr.dimmed(),
+                format!("failed ({err})").bold().red(),
+                color_duration(start.elapsed())
+            );
+            progress!("{}", line);
+            return Err(err).with_context(|| format!("{context}: request failed"));
+        }
+    };
+    let total_elapsed = start.elapsed();
+
+    let parse_start = Instant::now();
+    let payload =
+        E::decode(&raw_body).with_context(|| format!("{context}: parsing response body"))?;
+    let parse_elapsed = parse_start.elapsed();
+
+    let bytes = raw_body.len();
+    let stats = RequestStats {
+        total_elapsed,
+        bytes,
+    };
+
+    let line = format!(
+        "{} {} params {} -> {} {} {}",
+        "HTTP POST".bold().blue(),
+        url.cyan(),
+        params_repr.dimmed(),
+        "ok".green().bold(),
+        color_duration(total_elapsed),
+        format!("{bytes} bytes").dimmed()
+    );
+    progress!("{}", line);
+
+    progress!(
+        "{} {} {} {}",
+        "Decoded JSON".dimmed(),
+        url.cyan(),
+        color_duration(parse_elapsed),
+        format!("parse {parse_elapsed:.2?}").dimmed()
+    );
+
+    Ok(FetchResult {
+        data: payload,
+        stats,
+    })
+}
+
+fn color_duration(duration: Duration) -> String {
+    let base = format!("in {:.2?}", duration);
+    let millis = duration.as_millis();
+    if millis >= 1_000 {
+        format!("{}", base.red().bold())
+    } else if millis >= 200 {
+        format!("{}", base.yellow().bold())
+    } else {
+        format!("{}", base.green())
+    }
+}
+
+/// Print a compact human-readable view of a trade entry, enriched with instrument metadata.
+fn print_trade(trade: &TradeRecord, instrument: &Instrument) {
+    let trade_id = trade.trade_id.as_str();
+    let price = trade.price;
+    let amount = trade.amount;
+    let timestamp = trade.timestamp;
+
+    let price_text = if price.is_nan() {
+        "NaN".to_string()
+    } else {
+        format!("{price:.8}")
+    };
+    let amount_text = if amount.is_nan() {
+        "NaN".to_string()
+    } else {
+        format!("{amount:.4}")
+    };
+
+    let price_display = if price.is_nan() {
+        format!("{}", price_text.as_str().yellow().italic())
+    } else {
+        format!("{}", price_text.as_str().green())
+    };
+
+    let amount_display = if amount.is_nan() {
+        format!("{}", amount_text.as_str().yellow().italic())
+    } else {
+        format!("{}", amount_text.as_str().blue())
+    };
+
+    let direction_display = match trade.direction.as_str() {
+        "buy" => format!("{}", "buy".green().bold()),
+        "sell" => format!("{}", "sell".red().bold()),
+        other => format!("{}", other.cyan()),
+    };
+
+    let strike_display = instrument
+        .strike
+        .map(|s| format!("{}", format!("{s:.2}").yellow()))
+        .unwrap_or_else(|| format!("{}", "N/A".dimmed()));
+    let expiration_display = instrument
+        .expiration
+        .map(|ts| format!("{}", format_timestamp(ts).bright_white()))
+        .unwrap_or_else(|| format!("{}", "unknown".dimmed()));
+    let option_type_display = instrument
+        .option_type
+        .as_deref()
+        .map(|t| match t {
+            "call" | "C" => format!("{}", "CALL".green().bold()),
+            "put" | "P" => format!("{}", "PUT".red().bold()),
+            other => format!("{}", other.cyan()),
+        })
+        .unwrap_or_else(|| format!("{}", "?".dimmed()));
+
+    let trade_id_display = format!("{}", format!("- {}", trade_id).bright_magenta());
+    let timestamp_display = format!("{}", format_timestamp(timestamp).dimmed());
+    let price_label = format!("{}", "price".dimmed());
+    let amount_label = format!("{}", "amount".dimmed());
+    let strike_label = format!("{}", "strike".dimmed());
+    let maturity_label = format!("{}", "maturity".dimmed());
+    let type_label = format!("{}", "type".dimmed());
+
+    let line = format!(
+        "{trade_id_display} | {timestamp_display} | {price_label} {price_display} | {amount_label} {amount_display} | {direction_display} | {strike_label} {strike_display} | {maturity_label} {expiration_display} | {type_label} {option_type_display}",
+    );
+
+    println!("{}", line);
+}
+
+/// Convert a Deribit millisecond timestamp into an ISO-8601 string for readability.
+fn format_timestamp(ms: u64) -> String {
+    let secs = (ms / 1000) as i64;
+    let nanos = ((ms % 1000) * 1_000_000) as u32;
+
+    Utc.timestamp_opt(secs, nanos)
+        .single()
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_else(|| ms.to_string())
+}
+
+fn print_estimation(total_instruments: usize, samples: &[TradeSample]) {
+    if total_instruments == 0 {
+        return;
+    }
+
+    match estimate_download_requirements(total_instruments, samples) {
+        Some(summary) => {
+            println!();
+            println!(
+                "{} {}",
+                "Estimated requests:".bold().bright_white(),
+                format!("{:.0}", summary.total_requests).bold().cyan()
+            );
+            println!(
+                "{} {} (~{:.1} min)",
+                "Estimated download time:".bold().bright_white(),
+                format_duration(summary.total_time_secs).bold().green(),
+                summary.total_time_secs / 60.0
+            );
+            println!(
+                "{} {} ({:.2} MB)",
+                "Estimated data volume:".bold().bright_white(),
+                human_bytes(summary.total_bytes).bold().yellow(),
+                summary.total_bytes / (1024.0 * 1024.0)
+            );
+            if let Some(host) = &summary.dominant_host {
+                println!(
+                    "{} {}",
+                    "Likely primary host:".bold().bright_white(),
+                    host.cyan()
+                );
+            }
+            println!(
+                "{} {}",
+                "Assumptions:".bold().dimmed(),
+                format!(
+                    "{} samples; {:.1}% instruments returned trades; {:.1}% required pagination (count=100)",
+                    summary.sample_size,
+                    summary.positive_ratio * 100.0,
+                    summary.has_more_ratio * 100.0
+                )
+                .dimmed()
+            );
+        }
+        None => {
+            if !samples.is_empty() {
+                println!(
+                    "{}",
+                    "Insufficient trade-bearing samples to estimate download duration.".dimmed()
+                );
+            } else {
+                println!(
+                    "{}",
+                    "No trade samples collected; cannot estimate download requirements.".dimmed()
+                );
+            }
+        }
+    }
+}
+
+fn estimate_download_requirements(
+    total_instruments: usize,
+    samples: &[TradeSample],
+) -> Option<EstimationSummary> {
+    if total_instruments == 0 || samples.is_empty() {
+        return None;
+    }
+
+    let mut per_instrument: HashMap<String, Vec<&TradeSample>> = HashMap::new();
+    for sample in samples {
+        per_instrument
+            .entry(sample.instrument.clone())
+            .or_default()
+            .push(sample);
+    }
+
+    let mut aggregates = Vec::new();
+    for (_instrument, entries) in per_instrument {
+        let chosen = entries
+            .iter()
+            .copied()
+            .find(|s| s.trades > 0)
+            .unwrap_or(entries[0]);
+        aggregates.push((
+            chosen.trades,
+            chosen.has_more.unwrap_or(false),
+            chosen.stats.clone(),
+            chosen.host.clone(),
+        ));
+    }
+
+    if aggregates.is_empty() {
+        return None;
+    }
+
+    let sample_size = aggregates.len();
+    let positive_count = aggregates
+        .iter()
+        .filter(|(trades, _, _, _)| *trades > 0)
+        .count();
+    if positive_count == 0 {
+        return None;
+    }
+
+    let zero_count = sample_size - positive_count;
+    let positive_duration_sum: f64 = aggregates
+        .iter()
+        .filter(|(trades, _, _, _)| *trades > 0)
+        .map(|(_, _, stats, _)| stats.total_elapsed.as_secs_f64())
+        .sum();
+    let positive_bytes_sum: f64 = aggregates
+        .iter()
+        .filter(|(trades, _, _, _)| *trades > 0)
+        .map(|(_, _, stats, _)| stats.bytes as f64)
+        .sum();
+    let avg_duration_positive = positive_duration_sum / positive_count as f64;
+    let avg_bytes_positive = positive_bytes_sum / positive_count as f64;
+
+    let (avg_duration_zero, avg_bytes_zero) = if zero_count > 0 {
+        let zero_duration_sum: f64 = aggregates
+            .iter()
+            .filter(|(trades, _, _, _)| *trades == 0)
+            .map(|(_, _, stats, _)| stats.total_elapsed.as_secs_f64())
+            .sum();
+        let zero_bytes_sum: f64 = aggregates
+            .iter()
+            .filter(|(trades, _, _, _)| *trades == 0)
+            .map(|(_, _, stats, _)| stats.bytes as f64)
+            .sum();
+        (
+            zero_duration_sum / zero_count as f64,
+            zero_bytes_sum / zero_count as f64,
+        )
+    } else {
+        (avg_duration_positive, avg_bytes_positive)
+    };
+
+    let has_more_ratio = if positive_count > 0 {
+        aggregates
+            .iter()
+            .filter(|(trades, _, _, _)| *trades > 0)
+            .filter(|(_, has_more, _, _)| *has_more)
+            .count() as f64
+            / positive_count as f64
+    } else {
+        0.0
+    };
+
+    let mut host_counts: HashMap<String, usize> = HashMap::new();
+    for (_, _, _, host) in &aggregates {
+        *host_counts.entry(host.clone()).or_default() += 1;
+    }
+    let dominant_host = host_counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(host, _)| host);
+
+    let total_instruments_f = total_instruments as f64;
+    let positive_ratio = positive_count as f64 / sample_size as f64;
+    let base_positive_requests = positive_ratio * total_instruments_f;
+    let additional_requests = base_positive_requests * has_more_ratio;
+    let total_requests = total_instruments_f + additional_requests;
+
+    let estimated_positive_time = base_positive_requests * avg_duration_positive;
+    let estimated_zero_time = (total_instruments_f - base_positive_requests) * avg_duration_zero;
+    let estimated_additional_time = additional_requests * avg_duration_positive;
+    let total_time_secs = estimated_positive_time + estimated_zero_time + estimated_additional_time;
+
+    let estimated_positive_bytes = base_positive_requests * avg_bytes_positive;
+    let estimated_zero_bytes = (total_instruments_f - base_positive_requests) * avg_bytes_zero;
+    let estimated_additional_bytes = additional_requests * avg_bytes_positive;
+    let total_bytes = estimated_positive_bytes + estimated_zero_bytes + estimated_additional_bytes;
+
+    Some(EstimationSummary {
+        total_requests,
+        total_time_secs,
+        total_bytes,
+        positive_ratio,
+        has_more_ratio,
+        sample_size,
+        dominant_host,
+    })
+}
+
+#[derive(Serialize)]
+struct EstimationSummary {
+    total_requests: f64,
+    total_time_secs: f64,
+    total_bytes: f64,
+    positive_ratio: f64,
+    has_more_ratio: f64,
+    sample_size: usize,
+    dominant_host: Option<String>,
+}
+
+fn human_bytes(bytes: f64) -> String {
+    if bytes.is_nan() || !bytes.is_finite() {
+        return "unknown".to_string();
+    }
+
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit_index = 0;
+    while value >= 1024.0 && unit_index < units.len() - 1 {
+        value /= 1024.0;
+        unit_index += 1;
+    }
+    format!("{value:.2} {}", units[unit_index])
+}
+
+fn format_duration(seconds: f64) -> String {
+    if seconds.is_nan() || !seconds.is_finite() {
+        return "unknown".to_string();
+    }
+
+    let seconds = seconds.max(0.0);
+    if seconds < 60.0 {
+        return format!("{seconds:.1}s");
+    }
+
+    let total_secs = seconds.round() as u64;
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    let secs = total_secs % 60;
+
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, secs)
+    } else {
+        format!("{}m {}s", minutes, secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Answers for instruments named `ETH-<index>`: every one from `first_traded` on has a trade.
+    struct StubSource {
+        first_traded: Option<usize>,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        asked: Mutex<Vec<usize>>,
+    }
+
+    impl StubSource {
+        fn new(first_traded: Option<usize>) -> Self {
+            Self {
+                first_traded,
+                in_flight: AtomicUsize::new(0),
+                max_in_flight: AtomicUsize::new(0),
+                asked: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn asked(&self) -> usize {
+            self.asked.lock().unwrap().len()
+        }
+    }
+
+    impl TradeSource for StubSource {
+        async fn oldest_trades(
+            &self,
+            instrument_name: &str,
+        ) -> (Option<Vec<TradeRecord>>, Vec<TradeSample>) {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            // Stay pending once so every buffered probe is started before any finishes.
+            tokio::task::yield_now().await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let index: usize = instrument_name["ETH-".len()..].parse().unwrap();
+            self.asked.lock().unwrap().push(index);
+            let trades = self
+                .first_traded
+                .filter(|first| index >= *first)
+                .map(|_| vec![trade(instrument_name, index)]);
+            let sample = TradeSample {
+                instrument: instrument_name.to_string(),
+                host: "stub".to_string(),
+                trades: trades.as_ref().map_or(0, Vec::len),
+                has_more: Some(false),
+                stats: RequestStats {
+                    total_elapsed: Duration::ZERO,
+                    bytes: 0,
+                },
+            };
+            (trades, vec![sample])
+        }
+    }
+
+    fn trade(instrument_name: &str, index: usize) -> TradeRecord {
+        TradeRecord {
+            trade_id: format!("T{index}"),
+            instrument_name: Some(instrument_name.to_string()),
+            timestamp: index as u64 * 1_000 + 1,
+            direction: "buy".to_string(),
+            price: 0.05,
+            amount: 1.0,
+            trade_seq: Some(1),
+            index_price: None,
+            mark_price: None,
+            iv: None,
+        }
+    }
+
+    fn instruments(count: usize) -> Vec<Instrument> {
+        (0..count)
+            .map(|index| Instrument {
+                name: format!("ETH-{index}"),
+                creation: index as u64 * 1_000,
+                expiration: None,
+                strike: None,
+                option_type: None,
+                settlement_period: None,
+                base_currency: None,
+                quote_currency: None,
+                underlying_index: None,
+            })
+            .collect()
+    }
+
+    fn no_checkpoint() -> Checkpoint {
+        Checkpoint {
+            known: HashMap::new(),
+            log: CheckpointLog {
+                path: PathBuf::new(),
+                file: None,
+            },
+        }
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("oldest_eth_options_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    async fn bisect(count: usize, first_traded: Option<usize>) -> (Option<usize>, StubSource) {
+        QUIET.store(true, Ordering::Relaxed);
+        let source = StubSource::new(first_traded);
+        let instruments = instruments(count);
+        let mut prober = Prober::new(&source, &instruments, 4, no_checkpoint());
+        let found = prober.bisect().await;
+        assert!(source.max_in_flight.load(Ordering::SeqCst) <= 4);
+        if let Some((index, trades)) = &found {
+            assert_eq!(trades[0].trade_id, format!("T{index}"));
+        }
+        (found.map(|(index, _)| index), source)
+    }
+
+    #[tokio::test]
+    async fn bisect_finds_the_first_instrument_with_trades() {
+        for first in [0, 1, 37, 62, 98, 99] {
+            let (found, source) = bisect(100, Some(first)).await;
+            assert_eq!(found, Some(first), "first traded at {first}");
+            assert!(
+                source.asked() < 100,
+                "{} probes for 100 instruments",
+                source.asked()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn bisect_reports_none_when_nothing_traded() {
+        let (found, source) = bisect(100, None).await;
+        assert_eq!(found, None);
+        assert!(source.asked() > 0);
+        let (found, _) = bisect(3, None).await;
+        assert_eq!(found, None);
+    }
+
+    #[tokio::test]
+    async fn probe_all_keeps_order_and_bounds_requests_in_flight() {
+        QUIET.store(true, Ordering::Relaxed);
+        let source = StubSource::new(Some(5));
+        let instruments = instruments(20);
+        let mut prober = Prober::new(&source, &instruments, 3, no_checkpoint());
+        let indices: Vec<usize> = (0..20).rev().collect();
+        let results = prober.probe_all(&indices).await;
+        let traded: Vec<bool> = results.iter().map(Option::is_some).collect();
+        let expected: Vec<bool> = indices.iter().map(|index| *index >= 5).collect();
+        assert_eq!(traded, expected);
+        assert_eq!(prober.samples.len(), 20);
+        assert_eq!(source.max_in_flight.load(Ordering::SeqCst), 3);
+
+        let source = StubSource::new(None);
+        let mut prober = Prober::new(&source, &instruments, 3, no_checkpoint());
+        assert!(prober.scan(0..20).await.is_none());
+        assert_eq!(source.asked(), 20);
+        assert_eq!(source.max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn resume_answers_probed_instruments_from_the_checkpoint() {
+        QUIET.store(true, Ordering::Relaxed);
+        let path = scratch("resume").join("probes.jsonl");
+        let instruments = instruments(10);
+
+        let source = StubSource::new(Some(6));
+        let checkpoint = Checkpoint::open_at(path.clone(), false).unwrap();
+        let mut prober = Prober::new(&source, &instruments, 2, checkpoint);
+        let first = prober.probe_all(&[2, 4, 6]).await;
+        drop(prober);
+
+        let source = StubSource::new(Some(6));
+        let checkpoint = Checkpoint::open_at(path.clone(), true).unwrap();
+        assert_eq!(checkpoint.known.len(), 3);
+        let mut prober = Prober::new(&source, &instruments, 2, checkpoint);
+        assert_eq!(prober.probe_all(&[2, 4, 6]).await, first);
+        assert_eq!(source.asked(), 0);
+        assert_eq!(prober.scan(3..8).await.map(|(index, _)| index), Some(6));
+        assert_eq!(*source.asked.lock().unwrap(), [3, 5]);
+        drop(prober);
+
+        // Without --resume the checkpoint starts over.
+        let checkpoint = Checkpoint::open_at(path.clone(), false).unwrap();
+        assert!(checkpoint.known.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[tokio::test]
+    async fn resume_tolerates_a_truncated_last_line() {
+        QUIET.store(true, Ordering::Relaxed);
+        let path = scratch("truncated").join("probes.jsonl");
+        let instruments = instruments(10);
+        let source = StubSource::new(Some(0));
+        let checkpoint = Checkpoint::open_at(path.clone(), false).unwrap();
+        let mut prober = Prober::new(&source, &instruments, 2, checkpoint);
+        prober.probe_all(&[1, 2]).await;
+        drop(prober);
+
+        // A run killed mid-write leaves half of its last record.
+        let text = fs::read_to_string(&path).unwrap();
+        let cut = text.len() - text.lines().last().unwrap().len() / 2 - 1;
+        fs::write(&path, &text[..cut]).unwrap();
+
+        let checkpoint = Checkpoint::open_at(path.clone(), true).unwrap();
+        assert_eq!(checkpoint.known.keys().collect::<Vec<_>>(), ["ETH-1"]);
+        let mut prober = Prober::new(&source, &instruments, 2, checkpoint);
+        prober.probe_all(&[2, 3]).await;
+        drop(prober);
+
+        // Appended records start on a line of their own.
+        let checkpoint = Checkpoint::open_at(path.clone(), true).unwrap();
+        let mut known: Vec<_> = checkpoint.known.keys().cloned().collect();
+        known.sort();
+        assert_eq!(known, ["ETH-1", "ETH-2", "ETH-3"]);
+    }
+
+    #[tokio::test]
+    async fn resume_probes_instruments_expired_since_again() {
+        QUIET.store(true, Ordering::Relaxed);
+        let path = scratch("expired").join("probes.jsonl");
+        let mut instruments = instruments(3);
+        // ETH-0 expired before the first run, ETH-1 between the runs, ETH-2 is still listed.
+        for (instrument, expiration) in instruments.iter_mut().zip([500, 1_500, 5_000]) {
+            instrument.expiration = Some(expiration);
+        }
+
+        let source = StubSource::new(None);
+        let checkpoint = Checkpoint::open_at(path.clone(), false).unwrap();
+        let mut prober = Prober::new(&source, &instruments, 2, checkpoint);
+        prober.now_ms = 1_000;
+        prober.probe_all(&[0, 1, 2]).await;
+        drop(prober);
+
+        let source = StubSource::new(Some(0));
+        let checkpoint = Checkpoint::open_at(path.clone(), true).unwrap();
+        let mut prober = Prober::new(&source, &instruments, 2, checkpoint);
+        prober.now_ms = 2_000;
+        let results = prober.probe_all(&[0, 1, 2]).await;
+        assert_eq!(*source.asked.lock().unwrap(), [1]);
+        assert_eq!(
+            results.iter().map(Option::is_some).collect::<Vec<_>>(),
+            [false, true, false]
+        );
+        drop(prober);
+
+        // The fresh record replaces the stale one on the next resume.
+        let checkpoint = Checkpoint::open_at(path, true).unwrap();
+        let record = &checkpoint.known["ETH-1"];
+        assert_eq!((record.probed_at, record.trades.len()), (2_000, 1));
+    }
+}
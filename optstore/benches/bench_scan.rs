@@ -0,0 +1,82 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use optstore::prefetch::PrefetchOptions;
+use optstore::retrieve::cache::CacheManager;
+use optstore::retrieve::{RawChunk, RetrieveKind, RetrieveSpec};
+
+const PARTS: u32 = 64;
+
+/// A page of 1000 trades, about the size `retrieve` fetches.
+fn trades_page(page: u32) -> bytes::Bytes {
+    let trades: Vec<_> = (0..1_000u32)
+        .map(|i| {
+            let seq = page * 1_000 + i;
+            serde_json::json!({
+                "trade_seq": seq,
+                "trade_id": format!("ETH-{seq}"),
+                "timestamp": 1_743_120_000_000u64 + seq as u64 * 997,
+                "price": 0.0125 + (seq % 17) as f64 * 0.0005,
+                "iv": 61.5 + (seq % 9) as f64 * 0.25,
+                "index_price": 1872.33 + (seq % 13) as f64,
+                "instrument_name": "ETH-28MAR25-2000-C",
+                "direction": if seq.is_multiple_of(3) { "sell" } else { "buy" },
+                "amount": (1 + seq % 25) as f64,
+            })
+        })
+        .collect();
+    let page = serde_json::json!({ "result": { "trades": trades, "has_more": true } });
+    serde_json::to_vec(&page).unwrap().into()
+}
+
+/// Stands in for the consumer's work per part, as `reconcile` does.
+fn process(raw: &[u8]) -> usize {
+    let page: serde_json::Value = serde_json::from_slice(raw).unwrap();
+    page["result"]["trades"].as_array().unwrap().len()
+}
+
+fn bench_cache_scan(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CacheManager::new(dir.path().to_path_buf());
+    let spec = RetrieveSpec {
+        symbol: "ETH-28MAR25-2000-C".to_string(),
+        day_ymd: 20250328,
+        kind: RetrieveKind::Trades,
+    };
+    for part in 0..PARTS {
+        let chunk = RawChunk {
+            data: trades_page(part),
+            start_ns: 0,
+            end_ns: 0,
+            resume: None,
+        };
+        cache.write_chunk(&spec, part, &chunk).unwrap();
+    }
+
+    let mut group = c.benchmark_group("cache_scan");
+    group.sample_size(20);
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            (0..PARTS)
+                .map(|part| process(&cache.read_part(&spec, part).unwrap()))
+                .sum::<usize>()
+        })
+    });
+    for workers in [1, 2, 4] {
+        let options = PrefetchOptions {
+            workers,
+            depth: workers * 2,
+        };
+        group.bench_function(format!("prefetch_{workers}"), |b| {
+            b.iter(|| {
+                cache
+                    .read_parts(&spec, 0..PARTS, options)
+                    .unwrap()
+                    .map(|raw| process(&raw.unwrap()))
+                    .sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_cache_scan);
+criterion_main!(benches);
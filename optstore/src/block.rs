@@ -1,6 +1,58 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::Tick;
+
 #[derive(Debug, Clone)]
 pub struct BlockMeta {
     pub rows: u32,
     pub raw_bytes: u64,
     pub compressed_bytes: u64,
 }
+
+/// Default `--block-rows`.
+pub const DEFAULT_BLOCK_ROWS: u32 = 4096;
+/// Default `--block-bytes`: 1 MiB, above the default rows at the fixed
+/// row width, so rows are the binding target.
+pub const DEFAULT_BLOCK_BYTES: u64 = 1 << 20;
+
+/// Row-group targets of a file. A block closes at `rows` rows or once
+/// another row would take it past `bytes`, whichever comes first; the zone
+/// map summarises one [`crate::index::Zone`] per block.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlockLayout {
+    pub rows: u32,
+    pub bytes: u64,
+}
+
+impl Default for BlockLayout {
+    fn default() -> Self {
+        Self {
+            rows: DEFAULT_BLOCK_ROWS,
+            bytes: DEFAULT_BLOCK_BYTES,
+        }
+    }
+}
+
+impl BlockLayout {
+    /// Checks that a block can hold at least one row.
+    pub fn new(rows: u32, bytes: u64) -> Result<Self> {
+        if rows == 0 {
+            bail!("block rows must be at least 1");
+        }
+        if bytes < Tick::ENCODED_LEN as u64 {
+            bail!(
+                "block bytes {bytes} is below one {}-byte row",
+                Tick::ENCODED_LEN
+            );
+        }
+        Ok(Self { rows, bytes })
+    }
+
+    /// Rows per block once both targets apply.
+    pub fn block_rows(&self) -> u64 {
+        (self.rows as u64)
+            .min(self.bytes / Tick::ENCODED_LEN as u64)
+            .max(1)
+    }
+}
@@ -0,0 +1,398 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use fxhash::FxHashMap;
+use tracing::warn;
+
+use crate::codec::{put_varint, take_varint, unzigzag, zigzag};
+use crate::exit::Failure;
+use crate::reader::TickReader;
+use crate::schema::{event, Tick};
+
+/// Leading bytes of every book stream file.
+pub const MAGIC: &[u8; 8] = b"OPTBOOK\0";
+pub const BOOK_FORMAT_VERSION: u32 = 1;
+/// Magic, version (u32 LE), snapshot interval (u32 LE).
+pub const HEADER_LEN: usize = 16;
+/// Default updates of an instrument between full snapshots.
+pub const DEFAULT_SNAPSHOT_INTERVAL: u32 = 64;
+
+const SNAPSHOT: u8 = 0;
+const DELTA: u8 = 1;
+/// `price_fp`, `size`, 4 + 4 prices, 4 + 4 sizes and `flags`.
+const FIELDS: usize = 19;
+
+/// The book stream stored next to a day's row file: `DD.opt` → `DD.book`.
+pub fn book_path(day_file: &Path) -> PathBuf {
+    day_file.with_extension("book")
+}
+
+/// The fields a record carries, in record order.
+fn fields(tick: &Tick) -> [i64; FIELDS] {
+    let mut out = [0i64; FIELDS];
+    out[0] = tick.price_fp;
+    out[1] = tick.size as i64;
+    out[2..6].copy_from_slice(&tick.bid_px_fp);
+    out[6..10].copy_from_slice(&tick.ask_px_fp);
+    for (slot, sz) in tick.bid_sz.iter().chain(&tick.ask_sz).enumerate() {
+        out[10 + slot] = *sz as i64;
+    }
+    out[18] = tick.flags as i64;
+    out
+}
+
+fn book_tick(ts_ns: u64, instrument_id: u32, fields: &[i64; FIELDS]) -> Tick {
+    let mut tick = Tick {
+        ts_ns,
+        instrument_id,
+        event: event::BOOK,
+        price_fp: fields[0],
+        size: fields[1] as u32,
+        bid_px_fp: [0; 4],
+        ask_px_fp: [0; 4],
+        bid_sz: [0; 4],
+        ask_sz: [0; 4],
+        flags: fields[18] as u16,
+    };
+    tick.bid_px_fp.copy_from_slice(&fields[2..6]);
+    tick.ask_px_fp.copy_from_slice(&fields[6..10]);
+    for slot in 0..4 {
+        tick.bid_sz[slot] = fields[10 + slot] as u32;
+        tick.ask_sz[slot] = fields[14 + slot] as u32;
+    }
+    tick
+}
+
+/// Replays records into full ticks; shared by the reader and by writers
+/// reopening a stream.
+#[derive(Default)]
+struct BookState {
+    last_ts_ns: u64,
+    books: FxHashMap<u32, [i64; FIELDS]>,
+}
+
+impl BookState {
+    /// Decodes the record at the front of `bytes`, advancing past it.
+    /// `None` when `bytes` ends inside it.
+    fn decode(&mut self, bytes: &mut &[u8]) -> Option<Result<Tick>> {
+        let (&tag, mut rest) = bytes.split_first()?;
+        let ts_ns = self
+            .last_ts_ns
+            .wrapping_add(unzigzag(take_varint(&mut rest)?) as u64);
+        let instrument_id = take_varint(&mut rest)? as u32;
+        let fields = match tag {
+            SNAPSHOT => {
+                let mut fields = [0i64; FIELDS];
+                for field in &mut fields {
+                    *field = unzigzag(take_varint(&mut rest)?);
+                }
+                fields
+            }
+            DELTA => {
+                let Some(mut fields) = self.books.get(&instrument_id).copied() else {
+                    return Some(Err(Failure::Corrupt(format!(
+                        "book delta for instrument {instrument_id:08x} before its snapshot"
+                    ))
+                    .into()));
+                };
+                let changed = take_varint(&mut rest)?;
+                for (index, field) in fields.iter_mut().enumerate() {
+                    if changed & (1 << index) != 0 {
+                        *field = field.wrapping_add(unzigzag(take_varint(&mut rest)?));
+                    }
+                }
+                fields
+            }
+            other => {
+                return Some(Err(Failure::Corrupt(format!(
+                    "unknown book record tag {other}"
+                ))
+                .into()))
+            }
+        };
+        *bytes = rest;
+        self.last_ts_ns = ts_ns;
+        self.books.insert(instrument_id, fields);
+        Some(Ok(book_tick(ts_ns, instrument_id, &fields)))
+    }
+}
+
+fn encode_header(snapshot_interval: u32) -> [u8; HEADER_LEN] {
+    let mut header = [0u8; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..12].copy_from_slice(&BOOK_FORMAT_VERSION.to_le_bytes());
+    header[12..16].copy_from_slice(&snapshot_interval.to_le_bytes());
+    header
+}
+
+/// Snapshot interval of a book stream header.
+fn decode_header(bytes: &[u8]) -> Result<u32> {
+    if bytes.len() < HEADER_LEN || &bytes[..8] != MAGIC {
+        bail!(Failure::Corrupt(
+            "not an optstore book stream (bad magic)".into()
+        ));
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version != BOOK_FORMAT_VERSION {
+        bail!(Failure::Corrupt(format!(
+            "unsupported book stream version {version}"
+        )));
+    }
+    Ok(u32::from_le_bytes(bytes[12..16].try_into().unwrap()))
+}
+
+/// Appends book ticks to a snapshot + delta stream. The first update of an
+/// instrument, and every `snapshot_interval`th after it, is stored whole;
+/// the rest store only the fields that changed, as varint differences.
+pub struct BookWriter {
+    inner: BufWriter<File>,
+    snapshot_interval: u32,
+    state: BookState,
+    /// Deltas written per instrument since its last snapshot.
+    since_snapshot: FxHashMap<u32, u32>,
+    record: Vec<u8>,
+    records: u64,
+}
+
+impl BookWriter {
+    /// Opens `path` for appending. A new file gets `snapshot_interval`; an
+    /// existing one keeps its own and is replayed so deltas continue from
+    /// its last books. A torn trailing record is cut off.
+    pub fn append(path: &Path, snapshot_interval: u32) -> Result<Self> {
+        if snapshot_interval == 0 {
+            bail!("snapshot interval must be at least 1");
+        }
+        crate::util::ensure_parent_dir(path)?;
+        let existing = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err).with_context(|| format!("read {}", path.display())),
+        };
+        let mut state = BookState::default();
+        let mut since_snapshot = FxHashMap::default();
+        let (interval, valid_len) = if existing.is_empty() {
+            (snapshot_interval, 0)
+        } else {
+            let interval = decode_header(&existing).with_context(|| path.display().to_string())?;
+            let mut rest = &existing[HEADER_LEN..];
+            while !rest.is_empty() {
+                let tag = rest[0];
+                match state.decode(&mut rest) {
+                    Some(tick) => {
+                        let id = tick?.instrument_id;
+                        let count = since_snapshot.entry(id).or_insert(0);
+                        *count = if tag == SNAPSHOT { 0 } else { *count + 1 };
+                    }
+                    None => break,
+                }
+            }
+            (interval, existing.len() - rest.len())
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open {}", path.display()))?;
+        if valid_len < existing.len() {
+            warn!(
+                target: "optstore::book",
+                file = %path.display(),
+                torn = existing.len() - valid_len,
+                "cutting torn book record"
+            );
+            file.set_len(valid_len as u64)?;
+        }
+        let mut inner = BufWriter::new(file);
+        if existing.is_empty() {
+            inner.write_all(&encode_header(interval))?;
+        }
+        Ok(Self {
+            inner,
+            snapshot_interval: interval,
+            state,
+            since_snapshot,
+            record: Vec::new(),
+            records: 0,
+        })
+    }
+
+    pub fn snapshot_interval(&self) -> u32 {
+        self.snapshot_interval
+    }
+
+    pub fn write(&mut self, tick: &Tick) -> Result<()> {
+        if tick.event != event::BOOK {
+            bail!(
+                "book streams hold book ticks only, got event {}",
+                tick.event
+            );
+        }
+        let fields = fields(tick);
+        let since = self.since_snapshot.entry(tick.instrument_id).or_insert(0);
+        let previous = self
+            .state
+            .books
+            .get(&tick.instrument_id)
+            .filter(|_| *since + 1 < self.snapshot_interval);
+
+        self.record.clear();
+        self.record
+            .push(if previous.is_some() { DELTA } else { SNAPSHOT });
+        put_varint(
+            &mut self.record,
+            zigzag(tick.ts_ns.wrapping_sub(self.state.last_ts_ns) as i64),
+        );
+        put_varint(&mut self.record, tick.instrument_id as u64);
+        match previous {
+            Some(previous) => {
+                let changed = (0..FIELDS)
+                    .filter(|&index| fields[index] != previous[index])
+                    .fold(0u64, |mask, index| mask | 1 << index);
+                put_varint(&mut self.record, changed);
+                for index in (0..FIELDS).filter(|index| changed & (1 << index) != 0) {
+                    put_varint(
+                        &mut self.record,
+                        zigzag(fields[index].wrapping_sub(previous[index])),
+                    );
+                }
+                *since += 1;
+            }
+            None => {
+                for field in fields {
+                    put_varint(&mut self.record, zigzag(field));
+                }
+                *since = 0;
+            }
+        }
+        self.inner.write_all(&self.record)?;
+        self.state.last_ts_ns = tick.ts_ns;
+        self.state.books.insert(tick.instrument_id, fields);
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Makes the records written so far visible to readers.
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    /// Flushes buffered records and returns how many were written.
+    pub fn finish(mut self) -> Result<u64> {
+        self.flush()?;
+        Ok(self.records)
+    }
+}
+
+/// Reads a book stream back as full book ticks, in stored order.
+pub struct BookReader {
+    bytes: Vec<u8>,
+    offset: usize,
+    state: BookState,
+    snapshot_interval: u32,
+}
+
+impl BookReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        let snapshot_interval =
+            decode_header(&bytes).with_context(|| path.display().to_string())?;
+        Ok(Self {
+            bytes,
+            offset: HEADER_LEN,
+            state: BookState::default(),
+            snapshot_interval,
+        })
+    }
+
+    pub fn snapshot_interval(&self) -> u32 {
+        self.snapshot_interval
+    }
+
+    /// Ticks with `from_ns <= ts_ns < to_ns`; earlier records are still
+    /// replayed, since later deltas build on them.
+    pub fn range(self, from_ns: u64, to_ns: u64) -> impl Iterator<Item = Result<Tick>> {
+        self.filter(move |tick| match tick {
+            Ok(tick) => tick.ts_ns >= from_ns && tick.ts_ns < to_ns,
+            Err(_) => true,
+        })
+    }
+}
+
+impl Iterator for BookReader {
+    type Item = Result<Tick>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut rest = &self.bytes[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        match self.state.decode(&mut rest) {
+            Some(tick) => {
+                self.offset = self.bytes.len() - rest.len();
+                if tick.is_err() {
+                    self.offset = self.bytes.len();
+                }
+                Some(tick)
+            }
+            None => {
+                self.offset = self.bytes.len();
+                Some(Err(Failure::Corrupt("truncated book record".into()).into()))
+            }
+        }
+    }
+}
+
+/// Every tick of a day's row file and its book stream, merged by time, or
+/// `None` when the day has neither. Either file may be missing.
+pub fn read_day(day_file: &Path) -> Result<Option<impl Iterator<Item = Result<Tick>>>> {
+    let rows = if day_file.exists() {
+        Some(TickReader::open(day_file)?)
+    } else {
+        None
+    };
+    let stream = book_path(day_file);
+    let books = if stream.exists() {
+        Some(BookReader::open(&stream)?)
+    } else {
+        None
+    };
+    if rows.is_none() && books.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(MergeByTime {
+        a: rows.into_iter().flatten().peekable(),
+        b: books.into_iter().flatten().peekable(),
+    }))
+}
+
+/// Merges two time-ordered tick streams; on equal times `a` goes first.
+struct MergeByTime<A: Iterator, B: Iterator> {
+    a: Peekable<A>,
+    b: Peekable<B>,
+}
+
+impl<A, B> Iterator for MergeByTime<A, B>
+where
+    A: Iterator<Item = Result<Tick>>,
+    B: Iterator<Item = Result<Tick>>,
+{
+    type Item = Result<Tick>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let take_a = match (self.a.peek(), self.b.peek()) {
+            (None, None) => return None,
+            (Some(_), None) | (Some(Err(_)), _) => true,
+            (None, Some(_)) | (_, Some(Err(_))) => false,
+            (Some(Ok(a)), Some(Ok(b))) => a.ts_ns <= b.ts_ns,
+        };
+        if take_a {
+            self.a.next()
+        } else {
+            self.b.next()
+        }
+    }
+}
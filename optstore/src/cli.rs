@@ -1,9 +1,30 @@
-use clap::{Parser, Subcommand};
-use tracing::info;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use tracing::{info, warn};
 
 use crate::{
-    progress::ProgressKind,
-    retrieve::{self, RetrieveCommand},
+    block::{self, BlockLayout},
+    codec::Compression,
+    dict::InstrumentDictionary,
+    exit::Failure,
+    export::{self, DuckDbExport, Output},
+    gc::{self, GcOptions, GcStatus},
+    progress::{Progress, ProgressHandle, ProgressKind, ProgressUpdate},
+    quality::{self, QualityRules},
+    query::{self, LargePrint},
+    reader::TickReader,
+    reconcile::{self, DayCompleteness},
+    registry::{self, InstrumentRegistry},
+    repair,
+    repro::{self, IngestParameters, ReproRecord},
+    retrieve::{self, CacheManager, DeribitSource, RetrieveCommand},
+    selection::{OptionKind, Selection},
+    stats::{self, ColumnStats},
     writer,
 };
 
@@ -11,7 +32,11 @@ use crate::{
 #[command(name = "optstore", version, about = "Options tick storage toolkit")]
 pub struct OptStoreCli {
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+
+    /// Print the full command tree with flags and defaults as JSON and exit
+    #[arg(long = "help-json", exclusive = true, default_value_t = false)]
+    pub help_json: bool,
 
     /// Suppress human-readable progress output
     #[arg(global = true, long = "quiet", default_value_t = false)]
@@ -28,13 +53,44 @@ pub enum Commands {
     Retrieve(RetrieveCommand),
     /// Ingest ticks from a cached/raw source into an optstore file
     Ingest(IngestCommand),
-    /// Execute a query against stored data (placeholder)
+    /// Count rows or rank the largest trade prints of a stored file
     Query(QueryCommand),
+    /// Check cached trade days against the exchange's trade numbering
+    Reconcile(ReconcileCommand),
+    /// Refetch only the trade numbers `reconcile` finds missing
+    Repair(RepairCommand),
+    /// Show where a stored file's bytes go under columnar encoding
+    Stats(StatsCommand),
+    /// Remove cached days whose trades are confirmed in optstore day files
+    Gc(GcCommand),
+    /// Move day files written with hashed instrument ids onto registry ids
+    MigrateIds(MigrateIdsCommand),
+    /// Check a stored file against its reproducibility record
+    Verify(VerifyCommand),
+    /// Print a shell completion script to stdout
+    Completions(CompletionsCommand),
+}
+
+#[derive(Parser, Debug)]
+pub struct CompletionsCommand {
+    /// Shell to complete for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+impl CompletionsCommand {
+    /// Writes the completion script for `command`, the CLI embedding these
+    /// commands, to stdout.
+    pub fn print(&self, command: &mut clap::Command) {
+        let name = command.get_name().to_string();
+        clap_complete::generate(self.shell, command, name, &mut std::io::stdout());
+    }
 }
 
 #[derive(Parser, Debug)]
 pub struct IngestCommand {
-    /// Input path containing JSONL ticks (possibly cached)
+    /// Input path containing JSONL ticks, or a retrieve cache day
+    /// directory (`<cache>/<symbol>/YYYY/MM/DD`) of parts and manifest
     #[arg(long)]
     pub input: String,
     /// Output optstore file path
@@ -43,19 +99,200 @@ pub struct IngestCommand {
     /// Day (YYYY-MM-DD)
     #[arg(long)]
     pub day: String,
+    /// Fail the ingest, removing the output, when any row breaks a
+    /// validation rule
+    #[arg(long, default_value_t = false)]
+    pub strict: bool,
+    /// Write the validation counts to `<out>.quality.json`
+    #[arg(long = "quality-report", default_value_t = false)]
+    pub quality_report: bool,
+    /// Largest plausible tick size, in contracts
+    #[arg(long = "max-size", default_value_t = quality::DEFAULT_MAX_SIZE)]
+    pub max_size: f64,
+    /// Rows per block, the unit the zone map summarises and queries prune
+    #[arg(long = "block-rows", default_value_t = block::DEFAULT_BLOCK_ROWS)]
+    pub block_rows: u32,
+    /// Byte ceiling per block; closes blocks early when below
+    /// `--block-rows` rows
+    #[arg(long = "block-bytes", default_value_t = block::DEFAULT_BLOCK_BYTES)]
+    pub block_bytes: u64,
+    /// Data directory whose instrument registry assigns ids to rows naming
+    /// their `instrument`; without it such rows get hashed ids
+    #[arg(long)]
+    pub registry: Option<PathBuf>,
 }
 
 #[derive(Parser, Debug)]
-pub struct QueryCommand {
-    /// Path to optstore file
+pub struct StatsCommand {
+    /// Optstore file to analyse
     #[arg(long)]
     pub file: String,
+    /// Compression applied after each column's encoding
+    #[arg(long, value_enum, default_value_t = Compression::Lz4)]
+    pub compression: Compression,
+    /// Also report every column of every block
+    #[arg(long, default_value_t = false)]
+    pub verbose: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct ReconcileCommand {
+    /// Retrieve cache directory (the `--out` of `retrieve`)
+    #[arg(long)]
+    pub cache: PathBuf,
+    /// Symbols to check (repeatable); defaults to every cached symbol
+    #[arg(long = "symbol")]
+    pub symbols: Vec<String>,
+    /// Only list days flagged as likely incomplete
+    #[arg(long = "flagged-only", default_value_t = false)]
+    pub flagged_only: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct GcCommand {
+    /// Retrieve cache directory (the `--out` of `retrieve`)
+    #[arg(long)]
+    pub cache: PathBuf,
+    /// Optstore data directory (`<dir>/YYYY/MM/DD.opt`) the cache was
+    /// ingested into
+    #[arg(long)]
+    pub data: PathBuf,
+    /// Symbols to collect (repeatable); defaults to every cached symbol
+    #[arg(long = "symbol")]
+    pub symbols: Vec<String>,
+    /// Keep the cached days of the last N days (UTC) without checking them
+    #[arg(long = "keep-days", default_value_t = 7u32)]
+    pub keep_days: u32,
+    /// Report what would be removed without deleting anything
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct MigrateIdsCommand {
+    /// Optstore data directory (`<dir>/YYYY/MM/DD.opt`) holding the registry
+    #[arg(long)]
+    pub data: PathBuf,
+    /// Report the days that would be rewritten without changing anything
+    #[arg(long = "dry-run", default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct VerifyCommand {
+    /// Optstore file whose `<file>.repro.json` to check
+    #[arg(long)]
+    pub file: PathBuf,
+    /// Also hash the recorded source files
+    #[arg(long, default_value_t = false)]
+    pub sources: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct RepairCommand {
+    /// Retrieve cache directory (the `--out` of `retrieve`)
+    #[arg(long)]
+    pub cache: PathBuf,
+    /// Symbols to repair (repeatable); defaults to every cached symbol
+    #[arg(long = "symbol")]
+    pub symbols: Vec<String>,
+    /// Rate limit (requests per second)
+    #[arg(long = "rate", default_value_t = 4u32)]
+    pub rate: u32,
+}
+
+#[derive(Parser, Debug)]
+pub struct QueryCommand {
+    /// Path to optstore file
+    #[arg(long, required_unless_present = "data", conflicts_with = "data")]
+    pub file: Option<String>,
+    /// Optstore data directory (`<dir>/YYYY/MM/DD.opt`); reads the day
+    /// files between `--from` and `--to`
+    #[arg(long, requires_all = ["daily_stats", "from", "to"])]
+    pub data: Option<PathBuf>,
     /// Optional explain without executing
     #[arg(long = "explain", default_value_t = false)]
     pub explain: bool,
     /// Instrument filter
     #[arg(long)]
     pub instrument: Option<String>,
+    /// Base currency of the option (BTC, ETH, ...)
+    #[arg(long)]
+    pub currency: Option<String>,
+    /// Option expiry date (YYYY-MM-DD)
+    #[arg(long)]
+    pub expiry: Option<NaiveDate>,
+    /// Option strike
+    #[arg(long)]
+    pub strike: Option<f64>,
+    /// Option kind
+    #[arg(long, value_enum)]
+    pub kind: Option<OptionKind>,
+    /// Return the N largest trade prints by notional instead of counting rows
+    #[arg(long, value_name = "N")]
+    pub top: Option<usize>,
+    /// Report VWAP, TWAP, notional, trade count and price range per
+    /// instrument and UTC day instead of counting rows
+    #[arg(long = "daily-stats", default_value_t = false, conflicts_with = "top")]
+    pub daily_stats: bool,
+    /// Only rows at or after this time (RFC 3339)
+    #[arg(long)]
+    pub from: Option<DateTime<Utc>>,
+    /// Only rows before this time (RFC 3339)
+    #[arg(long)]
+    pub to: Option<DateTime<Utc>>,
+    /// Exit with the no-data code (2) when no rows match
+    #[arg(long = "fail-on-empty", default_value_t = false)]
+    pub fail_on_empty: bool,
+    /// Also write the result rows (matching ticks, top prints or daily
+    /// stats) to a table, created if missing: `duckdb://<path>/<table>`
+    #[arg(long, value_name = "URI", conflicts_with = "explain")]
+    pub output: Option<Output>,
+}
+
+impl QueryCommand {
+    pub fn selection(&self) -> Selection {
+        Selection {
+            instrument: self.instrument.clone(),
+            currency: self.currency.clone(),
+            expiry: self.expiry,
+            strike: self.strike,
+            kind: self.kind,
+        }
+    }
+
+    /// `--from`/`--to` as a half-open nanosecond range.
+    pub fn range_ns(&self) -> (u64, u64) {
+        let ns = |at: &DateTime<Utc>| at.timestamp_nanos_opt().unwrap_or(i64::MAX).max(0) as u64;
+        (
+            self.from.as_ref().map_or(0, ns),
+            self.to.as_ref().map_or(u64::MAX, ns),
+        )
+    }
+
+    /// `--file`, or the day files under `--data` that exist for the UTC
+    /// days `--from..--to` touches, in day order.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let (Some(data), Some(from), Some(to)) = (&self.data, self.from, self.to) else {
+            return self.file.iter().map(PathBuf::from).collect();
+        };
+        let last = (to - chrono::Duration::nanoseconds(1)).date_naive();
+        from.date_naive()
+            .iter_days()
+            .take_while(|day| *day <= last)
+            .map(|day| crate::util::day_to_path(data, &day.format("%Y-%m-%d").to_string()))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// What the query reads, for messages.
+    fn source(&self) -> String {
+        match (&self.file, &self.data) {
+            (Some(file), _) => file.clone(),
+            (None, Some(data)) => data.display().to_string(),
+            (None, None) => String::new(),
+        }
+    }
 }
 
 impl OptStoreCli {
@@ -64,50 +301,668 @@ impl OptStoreCli {
     }
 
     pub fn execute(self) -> anyhow::Result<()> {
+        if self.help_json {
+            return print_help_json(&Self::command());
+        }
         match self.command {
-            Commands::Retrieve(cmd) => retrieve::run(cmd, self.quiet, self.json),
-            Commands::Ingest(cmd) => run_ingest(cmd, self.quiet, self.json),
-            Commands::Query(cmd) => run_query(cmd, self.quiet, self.json),
+            Some(Commands::Completions(cmd)) => {
+                cmd.print(&mut Self::command());
+                Ok(())
+            }
+            Some(command) => command.execute(self.quiet, self.json),
+            None => bail!(Failure::Usage(
+                "a subcommand is required; see --help".to_string()
+            )),
+        }
+    }
+}
+
+impl Commands {
+    /// Runs one subcommand; also the entry point for CLIs embedding these commands.
+    pub fn execute(self, quiet: bool, json: bool) -> anyhow::Result<()> {
+        match self {
+            Commands::Retrieve(cmd) => retrieve::run(cmd, quiet, json),
+            Commands::Ingest(cmd) => run_ingest(cmd, quiet, json),
+            Commands::Query(cmd) => run_query(cmd, quiet, json),
+            Commands::Reconcile(cmd) => run_reconcile(cmd, quiet, json),
+            Commands::Repair(cmd) => run_repair(cmd, quiet, json),
+            Commands::Stats(cmd) => run_stats(cmd, quiet, json),
+            Commands::Gc(cmd) => run_gc(cmd, quiet, json),
+            Commands::MigrateIds(cmd) => run_migrate_ids(cmd, quiet, json),
+            Commands::Verify(cmd) => run_verify(cmd, quiet, json),
+            // Without the embedding CLI's tree, complete these commands alone.
+            Commands::Completions(cmd) => {
+                cmd.print(&mut Commands::augment_subcommands(clap::Command::new(
+                    "optstore",
+                )));
+                Ok(())
+            }
         }
     }
 }
 
+/// Prints [`help::command_help`](crate::help::command_help) of `command`
+/// for `--help-json`.
+pub fn print_help_json(command: &clap::Command) -> anyhow::Result<()> {
+    let help = crate::help::command_help(command);
+    println!("{}", serde_json::to_string_pretty(&help)?);
+    Ok(())
+}
+
 fn run_ingest(cmd: IngestCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+    let day = NaiveDate::parse_from_str(&cmd.day, "%Y-%m-%d")
+        .with_context(|| Failure::Usage(format!("--day {} is not YYYY-MM-DD", cmd.day)))?;
+    let layout = BlockLayout::new(cmd.block_rows, cmd.block_bytes)
+        .map_err(|err| Failure::Usage(err.to_string()))?;
     let mut progress = crate::progress::Progress::new(quiet, json);
     let token = progress.start(ProgressKind::Ingest {
         symbol: "local".to_string(),
         day: cmd.day.clone(),
     });
-    info!(target: "optstore::ingest", input = %cmd.input, out = %cmd.out, "starting ingest placeholder");
+    info!(target: "optstore::ingest", input = %cmd.input, out = %cmd.out, "starting ingest");
 
-    writer::ingest_jsonl(&cmd.input, &cmd.out, &mut progress, token.clone())?;
+    let rules = QualityRules::for_day(day, cmd.max_size);
+    let mut registry = match &cmd.registry {
+        Some(dir) => Some(InstrumentRegistry::load(dir)?),
+        None => None,
+    };
+    let input = Path::new(&cmd.input);
+    let (report, sources) = if input.is_dir() {
+        let cached = CacheManager::open_day(input)?;
+        if format!("{:08}", cached.spec.day_ymd) != day.format("%Y%m%d").to_string() {
+            bail!(Failure::Usage(format!(
+                "{} caches {:08}, not --day {}",
+                cmd.input, cached.spec.day_ymd, cmd.day
+            )));
+        }
+        let report = writer::ingest_cache(
+            &cached,
+            &cmd.out,
+            rules,
+            layout,
+            registry.as_mut(),
+            &mut progress,
+            token.clone(),
+        )?;
+        (report, cached.files())
+    } else {
+        let report = writer::ingest_jsonl(
+            &cmd.input,
+            &cmd.out,
+            rules,
+            layout,
+            registry.as_mut(),
+            &mut progress,
+            token.clone(),
+        )?;
+        (report, vec![input.to_path_buf()])
+    };
+    if let (Some(dir), Some(registry)) = (&cmd.registry, &registry) {
+        registry.store(dir)?;
+    }
+    let out = Path::new(&cmd.out);
+    if cmd.quality_report {
+        report.store_for(out)?;
+    }
+    if report.is_clean() {
+        info!(target: "optstore::ingest", %report, "validation passed");
+    } else {
+        warn!(target: "optstore::ingest", %report, "validation flagged rows");
+    }
+    if !json && !quiet {
+        println!("quality: {report}");
+    }
 
-    progress.finish(token, None);
+    progress.finish(token, Some(ProgressUpdate::Quality(report.clone())));
+    if cmd.strict && !report.is_clean() {
+        std::fs::remove_file(out).with_context(|| format!("removing {}", out.display()))?;
+        bail!(Failure::Validation(format!(
+            "strict ingest failed validation: {report}"
+        )));
+    }
+    let parameters = IngestParameters {
+        day: cmd.day.clone(),
+        strict: cmd.strict,
+        quality_report: cmd.quality_report,
+        max_size: cmd.max_size,
+        block_rows: cmd.block_rows,
+        block_bytes: cmd.block_bytes,
+        registry: cmd.registry.as_ref().map(|dir| dir.display().to_string()),
+        key_id: repro::key_id(out)?,
+    };
+    let sources: Vec<&Path> = sources.iter().map(PathBuf::as_path).collect();
+    ReproRecord::ingest(parameters, &sources, out)?.store_for(out)?;
     Ok(())
 }
 
-fn run_query(cmd: QueryCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+fn run_verify(cmd: VerifyCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
     let mut progress = crate::progress::Progress::new(quiet, json);
-    let token = progress.start(ProgressKind::Query {
-        description: format!(
-            "file={} instrument={}",
-            cmd.file,
-            cmd.instrument.as_deref().unwrap_or("*")
-        ),
+    let token = progress.start(ProgressKind::Verify {
+        file: cmd.file.display().to_string(),
     });
-    if cmd.explain {
-        info!(target: "optstore::query", "explain not yet implemented");
+    let record = ReproRecord::load_for(&cmd.file)?;
+    let verification = record.verify(&cmd.file, cmd.sources)?;
+    info!(target: "optstore::repro", file = %cmd.file.display(), mismatches = verification.mismatches.len(), "verify complete");
+    if !json {
+        for mismatch in &verification.mismatches {
+            println!(
+                "{} expected={} found={}",
+                mismatch.path,
+                mismatch.expected,
+                mismatch.found.as_deref().unwrap_or("missing"),
+            );
+        }
+        println!(
+            "verify: outputs={} sources={} mismatches={}",
+            verification.outputs_checked,
+            verification.sources_checked,
+            verification.mismatches.len(),
+        );
+    }
+    let matched = verification.matched();
+    progress.finish(token, Some(ProgressUpdate::Verify(verification)));
+    if !matched {
+        bail!(Failure::Corrupt(format!(
+            "{} does not match its reproducibility record",
+            cmd.file.display()
+        )));
+    }
+    Ok(())
+}
+
+fn run_reconcile(cmd: ReconcileCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+    let mut progress = crate::progress::Progress::new(quiet, json);
+    let token = progress.start(ProgressKind::Reconcile {
+        cache: cmd.cache.display().to_string(),
+    });
+    let cache = CacheManager::new(cmd.cache.clone());
+    let symbols = if cmd.symbols.is_empty() {
+        cache.symbols()?
     } else {
-        info!(target: "optstore::query", "query execution placeholder");
+        cmd.symbols
+    };
+    let mut days = reconcile::reconcile(&cache, &symbols)?;
+    let flagged = days.iter().filter(|day| day.flagged()).count() as u64;
+    info!(target: "optstore::reconcile", symbols = symbols.len(), days = days.len(), flagged, "reconcile complete");
+    if cmd.flagged_only {
+        days.retain(DayCompleteness::flagged);
     }
+    if !json {
+        for day in &days {
+            println!(
+                "{} {} pages={} stored={} expected={} missing={} gaps={} missing_before={}{}{}",
+                day.symbol,
+                day.day,
+                day.pages,
+                day.stored,
+                day.expected,
+                day.missing,
+                day.gaps,
+                day.missing_before,
+                if day.truncated { " truncated" } else { "" },
+                if day.flagged() { " FLAGGED" } else { "" },
+            );
+        }
+    }
+    progress.finish(token, Some(ProgressUpdate::Completeness { days, flagged }));
+    Ok(())
+}
+
+fn run_gc(cmd: GcCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+    let mut progress = crate::progress::Progress::new(quiet, json);
+    let token = progress.start(ProgressKind::Gc {
+        cache: cmd.cache.display().to_string(),
+    });
+    let cache = CacheManager::new(cmd.cache.clone());
+    let symbols = if cmd.symbols.is_empty() {
+        cache.symbols()?
+    } else {
+        cmd.symbols
+    };
+    let options = GcOptions {
+        keep_days: cmd.keep_days,
+        today: Utc::now().date_naive(),
+        dry_run: cmd.dry_run,
+    };
+    let summary = gc::collect(&cache, &cmd.data, &symbols, options)?;
+    info!(target: "optstore::gc", removed = summary.days_removed, bytes = summary.bytes_freed, "gc complete");
+    if !json {
+        for day in summary
+            .days
+            .iter()
+            .filter(|day| day.status != GcStatus::Recent)
+        {
+            println!(
+                "{} {} {} parts={} bytes={} cached={} missing={}",
+                day.symbol,
+                day.day,
+                day.status.name(),
+                day.parts,
+                day.bytes,
+                day.cached,
+                day.missing,
+            );
+        }
+        println!(
+            "gc{}: checked={} removed={} parts={} bytes_freed={} unconfirmed={}",
+            if summary.dry_run { " (dry run)" } else { "" },
+            summary.days_checked,
+            summary.days_removed,
+            summary.parts_removed,
+            summary.bytes_freed,
+            summary.days_unconfirmed,
+        );
+    }
+    progress.finish(token, Some(ProgressUpdate::Gc(summary)));
+    Ok(())
+}
+
+fn run_migrate_ids(cmd: MigrateIdsCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+    let mut progress = crate::progress::Progress::new(quiet, json);
+    let token = progress.start(ProgressKind::MigrateIds {
+        data: cmd.data.display().to_string(),
+    });
+    let summary = registry::migrate(&cmd.data, cmd.dry_run)?;
+    info!(target: "optstore::registry", rewritten = summary.days_rewritten, registered = summary.instruments_registered, "migration complete");
+    if !json {
+        for day in &summary.days {
+            println!(
+                "{} ids_changed={} rows={} book_records={}",
+                day.file, day.ids_changed, day.rows, day.book_records,
+            );
+        }
+        for file in &summary.unmapped {
+            println!("{file} no dictionary; left alone");
+        }
+        println!(
+            "migrate-ids{}: checked={} rewritten={} registered={} unmapped={}",
+            if summary.dry_run { " (dry run)" } else { "" },
+            summary.days_checked,
+            summary.days_rewritten,
+            summary.instruments_registered,
+            summary.unmapped.len(),
+        );
+    }
+    progress.finish(token, Some(ProgressUpdate::MigrateIds(summary)));
+    Ok(())
+}
+
+fn run_repair(cmd: RepairCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+    let mut progress = crate::progress::Progress::new(quiet, json);
+    let token = progress.start(ProgressKind::Repair {
+        cache: cmd.cache.display().to_string(),
+    });
+    let cache = CacheManager::new(cmd.cache.clone());
+    let symbols = if cmd.symbols.is_empty() {
+        cache.symbols()?
+    } else {
+        cmd.symbols
+    };
+    let source = DeribitSource::new(cmd.rate);
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let summary = rt.block_on(repair::repair(&cache, &source, &symbols))?;
+    info!(target: "optstore::repair", ?summary, "repair complete");
+    if !json {
+        println!(
+            "repair: ranges={} requested={} pages={} parts_written={} still_missing={}",
+            summary.ranges,
+            summary.requested,
+            summary.pages,
+            summary.parts_written,
+            summary.still_missing,
+        );
+    }
+    progress.finish(token, Some(ProgressUpdate::Repair(summary)));
+    Ok(())
+}
+
+fn run_query(cmd: QueryCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+    let mut progress = crate::progress::Progress::new(quiet, json);
+    let selection = cmd.selection();
+    let mut description = match &cmd.data {
+        Some(data) => format!("data={} {selection}", data.display()),
+        None => format!("file={} {selection}", cmd.source()),
+    };
+    if let Some(n) = cmd.top {
+        description.push_str(&format!(" top={n}"));
+    }
+    if cmd.daily_stats {
+        description.push_str(" daily-stats");
+    }
+    let token = progress.start(ProgressKind::Query { description });
+    if cmd.daily_stats {
+        return run_daily_stats(cmd, progress, token, json);
+    }
+    // `--data` requires `--daily-stats`, so there is one file.
+    let source = cmd.source();
+    let path = Path::new(&source);
+
+    // Option terms live in the dictionary sidecar, so predicates become an
+    // instrument id set before any rows are read.
+    let resolved = if selection.is_empty() {
+        None
+    } else {
+        let dictionary = InstrumentDictionary::load_for(path)?;
+        let ids = selection.resolve(&dictionary);
+        let names: Vec<String> = ids
+            .iter()
+            .filter_map(|id| dictionary.name(*id).map(str::to_string))
+            .collect();
+        info!(target: "optstore::query", %selection, instruments = ids.len(), "resolved selection");
+        if ids.is_empty() {
+            warn!(target: "optstore::query", %selection, file = %source, "no instruments match");
+        }
+        Some((ids, names))
+    };
+    let instruments = resolved.as_ref().map(|(_, names)| names.clone());
+
+    if cmd.explain || resolved.as_ref().is_some_and(|(ids, _)| ids.is_empty()) {
+        progress.finish(
+            token,
+            Some(ProgressUpdate::QueryResult {
+                blocks_scanned: 0,
+                blocks_pruned: 0,
+                bytes_read: 0,
+                projected_columns: vec![],
+                instruments,
+                rows_scanned: 0,
+                rows_matched: 0,
+                prints: None,
+                daily: None,
+            }),
+        );
+        if !cmd.explain {
+            return no_match(&cmd, 0);
+        }
+        return Ok(());
+    }
+
+    let (from_ns, to_ns) = cmd.range_ns();
+    if let Some(n) = cmd.top {
+        let dictionary = InstrumentDictionary::load_for(path).unwrap_or_default();
+        let ids = resolved.as_ref().map(|(ids, _)| ids);
+        let top = query::top_prints(path, &dictionary, ids, from_ns, to_ns, n)?;
+        info!(
+            target: "optstore::query",
+            zones_scanned = top.zones_scanned,
+            zones_pruned = top.zones_pruned,
+            rows_scanned = top.rows_scanned,
+            "top prints complete"
+        );
+        if !json {
+            print_large_prints(&top.prints);
+        }
+        if let Some(output) = &cmd.output {
+            let mut export = DuckDbExport::create(output, export::PRINT_COLUMNS)?;
+            for print in &top.prints {
+                export.push(&export::print_cells(print))?;
+            }
+            exported(&mut progress, &token, output, export.finish()?, json);
+        }
+        let rows_matched = top.prints.len() as u64;
+        progress.finish(
+            token,
+            Some(ProgressUpdate::QueryResult {
+                blocks_scanned: top.zones_scanned,
+                blocks_pruned: top.zones_pruned,
+                bytes_read: top.rows_scanned * crate::schema::Tick::ENCODED_LEN as u64,
+                projected_columns: ["ts_ns", "instrument_id", "event", "price_fp", "size"]
+                    .map(str::to_string)
+                    .to_vec(),
+                instruments,
+                rows_scanned: top.rows_scanned,
+                rows_matched,
+                prints: Some(top.prints),
+                daily: None,
+            }),
+        );
+        return no_match(&cmd, rows_matched);
+    }
+
+    let mut reader = TickReader::open(path)?;
+    if let Some((ids, _)) = resolved {
+        reader = reader.instruments(ids);
+    }
+    let mut export = match &cmd.output {
+        Some(output) => Some((
+            DuckDbExport::create(output, export::TICK_COLUMNS)?,
+            InstrumentDictionary::load_for(path).unwrap_or_default(),
+        )),
+        None => None,
+    };
+    let mut rows_matched = 0_u64;
+    for tick in reader.by_ref() {
+        let tick = tick?;
+        if (from_ns..to_ns).contains(&tick.ts_ns) {
+            rows_matched += 1;
+            if let Some((export, dictionary)) = &mut export {
+                export.push(&export::tick_cells(&tick, dictionary))?;
+            }
+        }
+    }
+    if let (Some((export, _)), Some(output)) = (export, &cmd.output) {
+        exported(&mut progress, &token, output, export.finish()?, json);
+    }
+    let rows_scanned = reader.rows_read();
+    info!(target: "optstore::query", rows_scanned, rows_matched, "query complete");
     progress.finish(
         token,
-        Some(crate::progress::ProgressUpdate::QueryResult {
+        Some(ProgressUpdate::QueryResult {
             blocks_scanned: 0,
             blocks_pruned: 0,
-            bytes_read: 0,
+            bytes_read: std::fs::metadata(path)?.len(),
             projected_columns: vec![],
+            instruments,
+            rows_scanned,
+            rows_matched,
+            prints: None,
+            daily: None,
         }),
     );
+    no_match(&cmd, rows_matched)
+}
+
+fn run_daily_stats(
+    cmd: QueryCommand,
+    mut progress: Progress,
+    token: ProgressHandle,
+    json: bool,
+) -> anyhow::Result<()> {
+    let selection = cmd.selection();
+    let files = cmd.files();
+    let instruments = if selection.is_empty() {
+        None
+    } else {
+        let mut names = BTreeSet::new();
+        for file in &files {
+            let dictionary = InstrumentDictionary::load_for(file).unwrap_or_default();
+            names.extend(
+                selection
+                    .resolve(&dictionary)
+                    .iter()
+                    .filter_map(|id| dictionary.name(*id).map(str::to_string)),
+            );
+        }
+        Some(names.into_iter().collect::<Vec<_>>())
+    };
+    if cmd.explain {
+        progress.finish(
+            token,
+            Some(ProgressUpdate::QueryResult {
+                blocks_scanned: 0,
+                blocks_pruned: 0,
+                bytes_read: 0,
+                projected_columns: vec![],
+                instruments,
+                rows_scanned: 0,
+                rows_matched: 0,
+                prints: None,
+                daily: None,
+            }),
+        );
+        return Ok(());
+    }
+
+    let (from_ns, to_ns) = cmd.range_ns();
+    let stats = query::daily_stats(&files, &selection, from_ns, to_ns)?;
+    let rows_matched = stats.days.iter().map(|day| day.trades).sum();
+    info!(
+        target: "optstore::query",
+        files = stats.files,
+        zones_scanned = stats.zones_scanned,
+        zones_pruned = stats.zones_pruned,
+        rows_scanned = stats.rows_scanned,
+        "daily stats complete"
+    );
+    if let Some(output) = &cmd.output {
+        let mut export = DuckDbExport::create(output, export::DAILY_COLUMNS)?;
+        for day in &stats.days {
+            export.push(&export::daily_cells(day)?)?;
+        }
+        exported(&mut progress, &token, output, export.finish()?, json);
+    }
+    if !json {
+        for day in &stats.days {
+            println!(
+                "{} {} trades={} volume={} notional={:.6} vwap={:.6} twap={:.6} min={} max={}",
+                day.day,
+                day.instrument,
+                day.trades,
+                day.volume,
+                day.notional,
+                day.vwap,
+                day.twap,
+                day.min_price,
+                day.max_price,
+            );
+        }
+    }
+    progress.finish(
+        token,
+        Some(ProgressUpdate::QueryResult {
+            blocks_scanned: stats.zones_scanned,
+            blocks_pruned: stats.zones_pruned,
+            bytes_read: stats.rows_scanned * crate::schema::Tick::ENCODED_LEN as u64,
+            projected_columns: ["ts_ns", "instrument_id", "event", "price_fp", "size"]
+                .map(str::to_string)
+                .to_vec(),
+            instruments,
+            rows_scanned: stats.rows_scanned,
+            rows_matched,
+            prints: None,
+            daily: Some(stats.days),
+        }),
+    );
+    no_match(&cmd, rows_matched)
+}
+
+fn exported(
+    progress: &mut Progress,
+    token: &ProgressHandle,
+    output: &Output,
+    rows: u64,
+    json: bool,
+) {
+    let message = format!("exported {rows} rows to {output}");
+    if !json {
+        println!("{message}");
+    }
+    progress.update(token, ProgressUpdate::Message { message });
+}
+
+/// `--fail-on-empty`: no matching rows is [`Failure::NoData`].
+fn no_match(cmd: &QueryCommand, rows_matched: u64) -> anyhow::Result<()> {
+    if cmd.fail_on_empty && rows_matched == 0 {
+        bail!(Failure::NoData(format!(
+            "no rows in {} match",
+            cmd.source()
+        )));
+    }
+    Ok(())
+}
+
+fn run_stats(cmd: StatsCommand, quiet: bool, json: bool) -> anyhow::Result<()> {
+    let mut progress = crate::progress::Progress::new(quiet, json);
+    let token = progress.start(ProgressKind::Stats {
+        file: cmd.file.clone(),
+    });
+    let stats = stats::file_stats(Path::new(&cmd.file), cmd.compression, cmd.verbose)?;
+    info!(
+        target: "optstore::stats",
+        rows = stats.rows,
+        blocks = stats.blocks,
+        raw = stats.raw_bytes,
+        compressed = stats.compressed_bytes,
+        "stats complete"
+    );
+    if !json {
+        println!(
+            "rows={} blocks={} block_rows={} compression={} raw={} encoded={} compressed={}",
+            stats.rows,
+            stats.blocks,
+            stats.layout.block_rows(),
+            stats.compression.name(),
+            stats.raw_bytes,
+            stats.encoded_bytes,
+            stats.compressed_bytes,
+        );
+        for column in &stats.columns {
+            let encodings: Vec<String> = column
+                .encodings
+                .iter()
+                .map(|(encoding, blocks)| format!("{}×{blocks}", encoding.name()))
+                .collect();
+            println!(
+                "  {:<14} {:<28} raw={:<10} encoded={:<10} compressed={:<10} entropy={:.0}",
+                column.column,
+                encodings.join(","),
+                column.raw_bytes,
+                column.encoded_bytes,
+                column.compressed_bytes,
+                column.entropy_bytes,
+            );
+        }
+        for block in stats.per_block.iter().flatten() {
+            println!(
+                "block {} rows {}..{}",
+                block.block,
+                block.first_row,
+                block.first_row + block.rows
+            );
+            for column in &block.columns {
+                print_column_stats(column);
+            }
+        }
+    }
+    progress.finish(token, Some(ProgressUpdate::Stats(stats)));
     Ok(())
 }
+
+fn print_column_stats(column: &ColumnStats) {
+    println!(
+        "  {:<14} {:<10} raw={:<8} encoded={:<8} compressed={:<8} entropy={:.2} bits/byte",
+        column.column,
+        column.encoding.name(),
+        column.raw_bytes,
+        column.encoded_bytes,
+        column.compressed_bytes,
+        column.entropy_bits,
+    );
+}
+
+fn print_large_prints(prints: &[LargePrint]) {
+    for print in prints {
+        let at = DateTime::from_timestamp_nanos(print.ts_ns.min(i64::MAX as u64) as i64);
+        println!(
+            "{} {:<28} {:<12} price={} size={} notional={:.6}",
+            at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            print.instrument,
+            print.event,
+            print.price,
+            print.size,
+            print.notional,
+        );
+    }
+}
@@ -1,10 +1,166 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Compression applied to a block's encoded columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
 pub enum Compression {
+    #[default]
     Lz4,
     Zstd,
 }
 
-impl Default for Compression {
-    fn default() -> Self {
-        Compression::Lz4
+impl Compression {
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Lz4 => "lz4",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn compress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self {
+            Compression::Lz4 => lz4_flex::compress_prepend_size(bytes),
+            Compression::Zstd => zstd::bulk::compress(bytes, 3)?,
+        })
+    }
+}
+
+/// How one column of a block is laid out before compression. Values are
+/// handled as `i64`; unsigned columns are reinterpreted, which keeps
+/// their low `width` bytes intact.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    /// Little-endian values at the column's row-format width.
+    Plain,
+    /// Zigzag varints of the first value and each difference after it.
+    Delta,
+    /// Zigzag varint values, each followed by a varint repeat count.
+    RunLength,
+    /// Up to 256 distinct plain values, then one index byte per row.
+    Dictionary,
+}
+
+impl Encoding {
+    /// Candidates in order of preference when sizes tie.
+    pub const ALL: [Encoding; 4] = [
+        Encoding::Plain,
+        Encoding::Delta,
+        Encoding::RunLength,
+        Encoding::Dictionary,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Plain => "plain",
+            Encoding::Delta => "delta",
+            Encoding::RunLength => "run_length",
+            Encoding::Dictionary => "dictionary",
+        }
+    }
+
+    /// Encodes `values` of a `width`-byte column; `None` when the encoding
+    /// does not apply (a dictionary of more than 256 values).
+    pub fn encode(self, values: &[i64], width: usize) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        match self {
+            Encoding::Plain => {
+                for value in values {
+                    out.extend_from_slice(&value.to_le_bytes()[..width]);
+                }
+            }
+            Encoding::Delta => {
+                let mut previous = 0i64;
+                for value in values {
+                    put_varint(&mut out, zigzag(value.wrapping_sub(previous)));
+                    previous = *value;
+                }
+            }
+            Encoding::RunLength => {
+                for run in values.chunk_by(|a, b| a == b) {
+                    put_varint(&mut out, zigzag(run[0]));
+                    put_varint(&mut out, run.len() as u64);
+                }
+            }
+            Encoding::Dictionary => {
+                let distinct: BTreeSet<i64> = values.iter().copied().collect();
+                if distinct.len() > 256 {
+                    return None;
+                }
+                let dictionary: Vec<i64> = distinct.into_iter().collect();
+                put_varint(&mut out, dictionary.len() as u64);
+                for value in &dictionary {
+                    out.extend_from_slice(&value.to_le_bytes()[..width]);
+                }
+                for value in values {
+                    out.push(dictionary.binary_search(value).unwrap_or(0) as u8);
+                }
+            }
+        }
+        Some(out)
+    }
+
+    /// The smallest applicable encoding of `values` and its bytes.
+    pub fn choose(values: &[i64], width: usize) -> (Encoding, Vec<u8>) {
+        Self::ALL
+            .into_iter()
+            .filter_map(|encoding| Some((encoding, encoding.encode(values, width)?)))
+            .min_by_key(|(_, bytes)| bytes.len())
+            .expect("plain always applies")
+    }
+}
+
+/// Order-0 Shannon entropy of `bytes`, in bits per byte.
+pub fn entropy_bits(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+    let total = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+pub(crate) fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+pub(crate) fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// LEB128: seven bits per byte, low bits first.
+pub(crate) fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Decodes a varint from the front of `bytes`, advancing past it; `None`
+/// when `bytes` ends inside it or it overflows 64 bits.
+pub(crate) fn take_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().enumerate().take(10) {
+        value |= ((byte & 0x7f) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[index + 1..];
+            return Some(value);
+        }
     }
+    None
 }
@@ -0,0 +1,377 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{bail, Context, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::exit::Failure;
+use crate::schema::Tick;
+
+/// Environment variable holding the storage key as 64 hex digits.
+pub const KEY_ENV: &str = "OPTSTORE_KEY";
+/// Environment variable naming a file with the storage key, as 32 raw
+/// bytes or 64 hex digits.
+pub const KEY_FILE_ENV: &str = "OPTSTORE_KEY_FILE";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+/// Bytes a sealed block adds to its rows: the frame length (u32 LE), the
+/// nonce and the GCM tag.
+pub const FRAME_OVERHEAD: u64 = (4 + NONCE_LEN + TAG_LEN) as u64;
+
+/// An AES-256-GCM key for encrypted row files. Its [`id`](Self::id) is
+/// stored in the file header, so a missing or wrong key is reported as
+/// such rather than as a damaged file.
+#[derive(Clone)]
+pub struct Key {
+    cipher: Aes256Gcm,
+    id: u64,
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Key")
+            .field("id", &format_args!("{:016x}", self.id))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Key {
+    pub fn new(bytes: [u8; KEY_LEN]) -> Self {
+        let mut tagged = b"optstore key id ".to_vec();
+        tagged.extend_from_slice(&bytes);
+        Self {
+            cipher: Aes256Gcm::new(&bytes.into()),
+            id: xxhash_rust::xxh3::xxh3_64(&tagged),
+        }
+    }
+
+    /// A key written as 64 hex digits.
+    pub fn parse_hex(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let usage = || Failure::Usage("storage key must be 64 hex digits (32 bytes)".into());
+        if text.len() != KEY_LEN * 2 {
+            bail!(usage());
+        }
+        let mut bytes = [0u8; KEY_LEN];
+        for (byte, pair) in bytes.iter_mut().zip(text.as_bytes().chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| usage())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| usage())?;
+        }
+        Ok(Self::new(bytes))
+    }
+
+    /// A key file of 32 raw bytes or 64 hex digits.
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read(path)
+            .with_context(|| Failure::Usage(format!("reading key file {}", path.display())))?;
+        match <[u8; KEY_LEN]>::try_from(raw.as_slice()) {
+            Ok(bytes) => Ok(Self::new(bytes)),
+            Err(_) => Self::parse_hex(&String::from_utf8_lossy(&raw))
+                .with_context(|| format!("key file {}", path.display())),
+        }
+    }
+
+    /// The key configured through [`KEY_ENV`] or [`KEY_FILE_ENV`], if any.
+    pub fn from_env() -> Result<Option<Self>> {
+        let hex = std::env::var(KEY_ENV)
+            .ok()
+            .filter(|value| !value.is_empty());
+        let file = std::env::var_os(KEY_FILE_ENV).filter(|value| !value.is_empty());
+        match (hex, file) {
+            (Some(_), Some(_)) => bail!(Failure::Usage(format!(
+                "set only one of {KEY_ENV} and {KEY_FILE_ENV}"
+            ))),
+            (Some(hex), None) => Self::parse_hex(&hex).map(Some),
+            (None, Some(file)) => Self::load(Path::new(&file)).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Encrypts `rows` as block `block` of the file with `header` and
+    /// returns the whole frame.
+    pub fn seal(&self, header: &[u8], block: u64, rows: &[u8]) -> Result<Vec<u8>> {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let sealed = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: rows,
+                    aad: &aad(header, block),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("encrypting block {block}"))?;
+        let len = (NONCE_LEN + sealed.len()) as u32;
+        let mut frame = Vec::with_capacity(4 + len as usize);
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(&nonce);
+        frame.extend_from_slice(&sealed);
+        Ok(frame)
+    }
+
+    /// Decrypts the body of a frame (nonce and ciphertext) read by
+    /// [`read_frame`]. Blocks are bound to their file header and position,
+    /// so one moved or copied from elsewhere fails like a damaged one.
+    pub fn open(&self, header: &[u8], block: u64, body: &[u8]) -> Result<Vec<u8>> {
+        if body.len() < NONCE_LEN + TAG_LEN {
+            bail!(Failure::Corrupt(format!(
+                "encrypted block {block} is truncated"
+            )));
+        }
+        let (nonce, sealed) = body.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: sealed,
+                    aad: &aad(header, block),
+                },
+            )
+            .map_err(|_| {
+                Failure::Corrupt(format!("encrypted block {block} fails authentication")).into()
+            })
+    }
+}
+
+fn aad(header: &[u8], block: u64) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.extend_from_slice(&block.to_le_bytes());
+    aad
+}
+
+/// The key for a file encrypted under `key_id`: `key` if given, the
+/// environment's otherwise.
+pub fn file_key(key: Option<Key>, key_id: u64, path: &Path) -> Result<Key> {
+    let key = match key {
+        Some(key) => key,
+        None => Key::from_env()?.with_context(|| {
+            Failure::Usage(format!(
+                "{} is encrypted; set {KEY_ENV} or {KEY_FILE_ENV}",
+                path.display()
+            ))
+        })?,
+    };
+    if key.id() != key_id {
+        bail!(Failure::Usage(format!(
+            "{} is encrypted with another key (id {key_id:016x}, given {:016x})",
+            path.display(),
+            key.id()
+        )));
+    }
+    Ok(key)
+}
+
+/// Bytes of a sealed block of `rows` rows.
+pub fn sealed_len(rows: u64) -> u64 {
+    FRAME_OVERHEAD + rows * Tick::ENCODED_LEN as u64
+}
+
+/// Rows in an encrypted body of `body_len` bytes: whole blocks of
+/// `block_rows` rows and, last, at most one partial block.
+pub fn sealed_rows(body_len: u64, block_rows: u64) -> u64 {
+    let full = sealed_len(block_rows);
+    (body_len / full) * block_rows
+        + (body_len % full).saturating_sub(FRAME_OVERHEAD) / Tick::ENCODED_LEN as u64
+}
+
+/// The body of the frame at `offset` in a file of `block_rows` blocks;
+/// `None` when the file ends there.
+pub fn read_frame(
+    reader: &mut (impl Read + Seek),
+    offset: u64,
+    block_rows: u64,
+) -> Result<Option<Vec<u8>>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let mut body = vec![0u8; frame_body_len(len, block_rows)?];
+    reader.read_exact(&mut body).map_err(torn)?;
+    Ok(Some(body))
+}
+
+/// [`read_frame`] for async readers.
+pub async fn read_frame_async(
+    reader: &mut (impl AsyncRead + AsyncSeek + Unpin),
+    offset: u64,
+    block_rows: u64,
+) -> Result<Option<Vec<u8>>> {
+    reader.seek(SeekFrom::Start(offset)).await?;
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let mut body = vec![0u8; frame_body_len(len, block_rows)?];
+    reader.read_exact(&mut body).await.map_err(torn)?;
+    Ok(Some(body))
+}
+
+/// The body length in a frame's length prefix, checked before anything
+/// is allocated for it: no body is longer than a full block's.
+fn frame_body_len(len: [u8; 4], block_rows: u64) -> Result<usize> {
+    let len = u64::from(u32::from_le_bytes(len));
+    if len > sealed_len(block_rows) - 4 {
+        bail!(Failure::Corrupt(format!(
+            "encrypted block length {len} exceeds a {block_rows}-row block"
+        )));
+    }
+    Ok(len as usize)
+}
+
+fn torn(err: std::io::Error) -> anyhow::Error {
+    if err.kind() == ErrorKind::UnexpectedEof {
+        Failure::Corrupt("truncated encrypted block".into()).into()
+    } else {
+        err.into()
+    }
+}
+
+/// The decrypted block a reader is positioned in.
+pub(crate) struct OpenBlocks {
+    key: Key,
+    header: Vec<u8>,
+    header_len: u64,
+    block_rows: u64,
+    loaded: Option<(u64, Vec<u8>)>,
+}
+
+impl OpenBlocks {
+    pub fn new(key: Key, header: &[u8], block_rows: u64) -> Self {
+        Self {
+            key,
+            header: header.to_vec(),
+            header_len: header.len() as u64,
+            block_rows,
+            loaded: None,
+        }
+    }
+
+    pub fn key(&self) -> &Key {
+        &self.key
+    }
+
+    pub fn block_rows(&self) -> u64 {
+        self.block_rows
+    }
+
+    /// The block holding row `position`, and where its frame starts when
+    /// it still has to be loaded.
+    pub fn needs(&self, position: u64) -> Option<(u64, u64)> {
+        let block = position / self.block_rows;
+        match &self.loaded {
+            Some((loaded, _)) if *loaded == block => None,
+            _ => Some((block, self.header_len + block * sealed_len(self.block_rows))),
+        }
+    }
+
+    pub fn load(&mut self, block: u64, body: &[u8]) -> Result<()> {
+        let rows = self.key.open(&self.header, block, body)?;
+        self.loaded = Some((block, rows));
+        Ok(())
+    }
+
+    /// Copies row `position` of the loaded block into `row`; false past
+    /// the rows of a final partial block.
+    pub fn copy_row(&self, position: u64, row: &mut [u8]) -> bool {
+        let Some((_, rows)) = &self.loaded else {
+            return false;
+        };
+        let at = (position % self.block_rows) as usize * Tick::ENCODED_LEN;
+        match rows.get(at..at + Tick::ENCODED_LEN) {
+            Some(bytes) => {
+                row.copy_from_slice(bytes);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Buffers a writer's rows into blocks and seals each full one. A partial
+/// last block is sealed on [`finish`](Self::finish) and, when the file is
+/// appended to again, read back and rewritten with the new rows.
+pub(crate) struct BlockSealer {
+    key: Key,
+    header: Vec<u8>,
+    block_rows: u64,
+    block: u64,
+    pending: Vec<u8>,
+    /// Where the partial block read back at open starts; the file is cut
+    /// there just before the block is rewritten.
+    resume_at: Option<u64>,
+    dirty: bool,
+}
+
+impl BlockSealer {
+    pub fn new(key: Key, header: &[u8], block_rows: u64) -> Self {
+        Self {
+            key,
+            header: header.to_vec(),
+            block_rows,
+            block: 0,
+            pending: Vec::new(),
+            resume_at: None,
+            dirty: false,
+        }
+    }
+
+    /// Continues an existing encrypted file, reading back its partial
+    /// last block if it has one.
+    pub fn resume(key: Key, header: &[u8], block_rows: u64, file: &mut File) -> Result<Self> {
+        let body_len = file.metadata()?.len().saturating_sub(header.len() as u64);
+        let block = body_len / sealed_len(block_rows);
+        let offset = header.len() as u64 + block * sealed_len(block_rows);
+        let mut sealer = Self::new(key, header, block_rows);
+        sealer.block = block;
+        if let Some(body) = read_frame(file, offset, block_rows)? {
+            sealer.pending = sealer.key.open(header, block, &body)?;
+            sealer.resume_at = Some(offset);
+        }
+        Ok(sealer)
+    }
+
+    pub fn push(&mut self, out: &mut BufWriter<File>, row: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(row);
+        self.dirty = true;
+        if self.pending.len() as u64 == self.block_rows * Tick::ENCODED_LEN as u64 {
+            self.seal(out)?;
+            self.block += 1;
+            self.pending.clear();
+        }
+        Ok(())
+    }
+
+    /// Seals the rows of a partial block written since the last seal.
+    pub fn finish(&mut self, out: &mut BufWriter<File>) -> Result<()> {
+        if self.dirty && !self.pending.is_empty() {
+            self.seal(out)?;
+        }
+        Ok(())
+    }
+
+    fn seal(&mut self, out: &mut BufWriter<File>) -> Result<()> {
+        if let Some(offset) = self.resume_at.take() {
+            out.flush()?;
+            out.get_ref().set_len(offset)?;
+        }
+        let frame = self.key.seal(&self.header, self.block, &self.pending)?;
+        out.write_all(&frame)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
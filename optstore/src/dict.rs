@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::exit::Failure;
+
+/// Maps `Tick::instrument_id` back to instrument names. Stored as a JSON
+/// sidecar next to each optstore file (`<file>.instruments.json`).
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct InstrumentDictionary {
+    pub instruments: BTreeMap<u32, String>,
+    /// Underlying units per contract, where known; absent entries count as 1
+    /// (Deribit's coin-settled options).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contract_sizes: BTreeMap<u32, f64>,
+}
+
+impl InstrumentDictionary {
+    /// The low 32 bits of the name's xxh3, the id of files written before
+    /// the [`InstrumentRegistry`](crate::registry::InstrumentRegistry).
+    /// Distinct names can share one.
+    pub fn hash_id(name: &str) -> u32 {
+        (xxh3_64(name.as_bytes()) & 0xFFFF_FFFF) as u32
+    }
+
+    /// Registers `name` under its [`hash_id`](Self::hash_id) and returns
+    /// the id; a name whose hash is taken keeps the earlier name.
+    pub fn insert(&mut self, name: &str) -> u32 {
+        let id = Self::hash_id(name);
+        self.instruments
+            .entry(id)
+            .or_insert_with(|| name.to_string());
+        id
+    }
+
+    pub fn name(&self, id: u32) -> Option<&str> {
+        self.instruments.get(&id).map(String::as_str)
+    }
+
+    /// The id `name` is stored under.
+    pub fn id(&self, name: &str) -> Option<u32> {
+        self.instruments
+            .iter()
+            .find(|(_, stored)| *stored == name)
+            .map(|(id, _)| *id)
+    }
+
+    /// Registers `name` under `id`, as assigned by an
+    /// [`InstrumentRegistry`](crate::registry::InstrumentRegistry). Fails
+    /// when `id` already names another instrument or `name` has another id,
+    /// since rows of the two could no longer be told apart.
+    pub fn insert_as(&mut self, id: u32, name: &str) -> Result<()> {
+        match (self.name(id), self.id(name)) {
+            (Some(stored), _) if stored != name => bail!(Failure::Validation(format!(
+                "instrument id {id} is both {stored} and {name}"
+            ))),
+            (None, Some(other)) => bail!(Failure::Validation(format!(
+                "{name} is stored as instrument id {other}, not {id}; \
+                 run `optstore migrate-ids` on files written before the registry"
+            ))),
+            (Some(_), _) => Ok(()),
+            (None, None) => {
+                self.instruments.insert(id, name.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// Registers `name` together with its contract size and returns its id.
+    pub fn insert_with_contract_size(&mut self, name: &str, contract_size: f64) -> u32 {
+        let id = self.insert(name);
+        self.contract_sizes.insert(id, contract_size);
+        id
+    }
+
+    pub fn contract_size(&self, id: u32) -> f64 {
+        self.contract_sizes.get(&id).copied().unwrap_or(1.0)
+    }
+
+    pub fn sidecar_path(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_owned();
+        name.push(".instruments.json");
+        PathBuf::from(name)
+    }
+
+    pub fn load_for(file: &Path) -> Result<Self> {
+        let path = Self::sidecar_path(file);
+        let raw = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_slice(&raw).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Merges with any dictionary already on disk, then writes it back.
+    /// Fails, leaving the sidecar alone, when an id or name of `self` is
+    /// stored with another name or id (see [`Self::insert_as`]).
+    pub fn store_for(&self, file: &Path) -> Result<()> {
+        let path = Self::sidecar_path(file);
+        let mut merged = if path.exists() {
+            Self::load_for(file)?
+        } else {
+            Self::default()
+        };
+        for (id, name) in &self.instruments {
+            merged
+                .insert_as(*id, name)
+                .with_context(|| format!("merging into {}", path.display()))?;
+        }
+        for (id, size) in &self.contract_sizes {
+            merged.contract_sizes.entry(*id).or_insert(*size);
+        }
+        merged.replace_for(file)
+    }
+
+    /// Writes the sidecar, dropping whatever it held.
+    pub fn replace_for(&self, file: &Path) -> Result<()> {
+        let path = Self::sidecar_path(file);
+        crate::util::ensure_parent_dir(&path)?;
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
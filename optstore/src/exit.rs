@@ -0,0 +1,78 @@
+use std::process::ExitCode;
+
+/// Failures the CLI reports with their own exit code, so scripts can tell
+/// them apart without parsing messages. Raise one with `bail!` or attach
+/// it with `.context(..)`; [`code`] finds it through any later context.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Failure {
+    /// The file or day asked for does not exist, or `--fail-on-empty`
+    /// found nothing.
+    #[error("{0}")]
+    NoData(String),
+    /// A stored file or cached part is damaged or not in a known format.
+    #[error("{0}")]
+    Corrupt(String),
+    /// The upstream source failed or rejected the request.
+    #[error("{0}")]
+    Source(String),
+    /// `ingest --strict` found rows breaking a validation rule, or two
+    /// instruments collide on one id.
+    #[error("{0}")]
+    Validation(String),
+    /// Arguments clap could not reject on its own.
+    #[error("{0}")]
+    Usage(String),
+}
+
+/// Success.
+pub const OK: u8 = 0;
+/// Any failure without a more specific code.
+pub const ERROR: u8 = 1;
+pub const NO_DATA: u8 = 2;
+pub const CORRUPT: u8 = 3;
+pub const SOURCE: u8 = 4;
+pub const VALIDATION: u8 = 5;
+/// Bad arguments (`EX_USAGE`); clap's own usage errors exit with it too.
+pub const USAGE: u8 = 64;
+
+impl Failure {
+    pub fn code(&self) -> u8 {
+        match self {
+            Failure::NoData(_) => NO_DATA,
+            Failure::Corrupt(_) => CORRUPT,
+            Failure::Source(_) => SOURCE,
+            Failure::Validation(_) => VALIDATION,
+            Failure::Usage(_) => USAGE,
+        }
+    }
+}
+
+/// The exit code for `err`: that of the outermost [`Failure`] in it, or
+/// [`ERROR`].
+pub fn code(err: &anyhow::Error) -> u8 {
+    err.downcast_ref::<Failure>().map_or(ERROR, Failure::code)
+}
+
+/// Prints `result`'s error the way `main` returning it would and maps it
+/// to its exit code.
+pub fn report(result: anyhow::Result<()>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::from(OK),
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(code(&err))
+        }
+    }
+}
+
+/// Prints a clap parse error; `--help` and `--version` exit with [`OK`]
+/// and usage errors with [`USAGE`] rather than clap's 2, which means no
+/// data here.
+pub fn report_usage(err: clap::Error) -> ExitCode {
+    let _ = err.print();
+    if err.use_stderr() {
+        ExitCode::from(USAGE)
+    } else {
+        ExitCode::from(OK)
+    }
+}
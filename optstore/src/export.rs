@@ -0,0 +1,341 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate};
+use tracing::info;
+
+use crate::dict::InstrumentDictionary;
+use crate::exit::Failure;
+use crate::query::{DailyStats, LargePrint};
+use crate::schema::{event, Tick, PRICE_SCALE, SIZE_SCALE};
+
+/// Environment variable naming the `duckdb` binary; `duckdb` on `PATH`
+/// otherwise.
+pub const DUCKDB_ENV: &str = "OPTSTORE_DUCKDB";
+
+/// Where `query --output` writes its result rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// `duckdb://<database path>/<table>`: a table created on first use and
+    /// appended to afterwards.
+    DuckDb { database: PathBuf, table: String },
+}
+
+impl FromStr for Output {
+    type Err = Failure;
+
+    fn from_str(value: &str) -> Result<Self, Failure> {
+        let Some(rest) = value.strip_prefix("duckdb://") else {
+            return Err(Failure::Usage(format!(
+                "unsupported output {value}; expected duckdb://<path>/<table>"
+            )));
+        };
+        let (database, table) = rest
+            .rsplit_once('/')
+            .filter(|(database, _)| !database.is_empty())
+            .ok_or_else(|| {
+                Failure::Usage(format!(
+                    "{value} names no table; expected duckdb://<path>/<table>"
+                ))
+            })?;
+        let mut chars = table.chars();
+        let identifier = chars
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !identifier {
+            return Err(Failure::Usage(format!(
+                "table {table:?} must be letters, digits and underscores"
+            )));
+        }
+        Ok(Output::DuckDb {
+            database: PathBuf::from(database),
+            table: table.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::DuckDb { database, table } => {
+                write!(f, "duckdb://{}/{table}", database.display())
+            }
+        }
+    }
+}
+
+/// DuckDB type of an exported column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    UBigInt,
+    UInteger,
+    Double,
+    Varchar,
+    /// Microseconds; the exact time goes in a `UBIGINT` `*_ns` column next
+    /// to it.
+    Timestamp,
+    Date,
+}
+
+impl ColumnType {
+    pub fn sql(self) -> &'static str {
+        match self {
+            ColumnType::UBigInt => "UBIGINT",
+            ColumnType::UInteger => "UINTEGER",
+            ColumnType::Double => "DOUBLE",
+            ColumnType::Varchar => "VARCHAR",
+            ColumnType::Timestamp => "TIMESTAMP",
+            ColumnType::Date => "DATE",
+        }
+    }
+}
+
+pub type Column = (&'static str, ColumnType);
+
+/// Rows of a plain query: every matching tick.
+pub const TICK_COLUMNS: &[Column] = &[
+    ("ts", ColumnType::Timestamp),
+    ("ts_ns", ColumnType::UBigInt),
+    ("instrument", ColumnType::Varchar),
+    ("instrument_id", ColumnType::UInteger),
+    ("event", ColumnType::Varchar),
+    ("price", ColumnType::Double),
+    ("size", ColumnType::Double),
+    ("trade_seq", ColumnType::UBigInt),
+    ("bid_price", ColumnType::Double),
+    ("bid_size", ColumnType::Double),
+    ("ask_price", ColumnType::Double),
+    ("ask_size", ColumnType::Double),
+];
+
+/// Rows of a `--top` query.
+pub const PRINT_COLUMNS: &[Column] = &[
+    ("ts", ColumnType::Timestamp),
+    ("ts_ns", ColumnType::UBigInt),
+    ("instrument", ColumnType::Varchar),
+    ("event", ColumnType::Varchar),
+    ("price", ColumnType::Double),
+    ("size", ColumnType::Double),
+    ("contract_size", ColumnType::Double),
+    ("notional", ColumnType::Double),
+];
+
+/// Rows of a `--daily-stats` query.
+pub const DAILY_COLUMNS: &[Column] = &[
+    ("day", ColumnType::Date),
+    ("instrument", ColumnType::Varchar),
+    ("trades", ColumnType::UBigInt),
+    ("volume", ColumnType::Double),
+    ("notional", ColumnType::Double),
+    ("vwap", ColumnType::Double),
+    ("twap", ColumnType::Double),
+    ("min_price", ColumnType::Double),
+    ("max_price", ColumnType::Double),
+    ("first_ts", ColumnType::Timestamp),
+    ("last_ts", ColumnType::Timestamp),
+];
+
+/// One exported value; `Null` for columns a row does not have.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+    Null,
+    UInt(u64),
+    Float(f64),
+    Text(String),
+    /// Nanoseconds since the epoch.
+    Time(u64),
+    Day(NaiveDate),
+}
+
+impl Cell {
+    fn csv(&self) -> String {
+        match self {
+            Cell::Null => String::new(),
+            Cell::UInt(value) => value.to_string(),
+            Cell::Float(value) => value.to_string(),
+            Cell::Text(value) => value.clone(),
+            Cell::Time(ns) => DateTime::from_timestamp_nanos(*ns as i64)
+                .format("%Y-%m-%d %H:%M:%S%.6f")
+                .to_string(),
+            Cell::Day(day) => day.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+pub fn tick_cells(tick: &Tick, dictionary: &InstrumentDictionary) -> Vec<Cell> {
+    let price = |fp: i64| Cell::Float(fp as f64 / PRICE_SCALE);
+    let size = |fp: u32| Cell::Float(fp as f64 / SIZE_SCALE);
+    let book = tick.event == event::BOOK;
+    let level = |cell: Cell| if book { cell } else { Cell::Null };
+    vec![
+        Cell::Time(tick.ts_ns),
+        Cell::UInt(tick.ts_ns),
+        dictionary
+            .name(tick.instrument_id)
+            .map_or(Cell::Null, |name| Cell::Text(name.to_string())),
+        Cell::UInt(tick.instrument_id as u64),
+        Cell::Text(event::name(tick.event).to_string()),
+        price(tick.price_fp),
+        size(tick.size),
+        tick.trade_seq().map_or(Cell::Null, Cell::UInt),
+        level(price(tick.bid_px_fp[0])),
+        level(size(tick.bid_sz[0])),
+        level(price(tick.ask_px_fp[0])),
+        level(size(tick.ask_sz[0])),
+    ]
+}
+
+pub fn print_cells(print: &LargePrint) -> Vec<Cell> {
+    vec![
+        Cell::Time(print.ts_ns),
+        Cell::UInt(print.ts_ns),
+        Cell::Text(print.instrument.clone()),
+        Cell::Text(print.event.to_string()),
+        Cell::Float(print.price),
+        Cell::Float(print.size),
+        Cell::Float(print.contract_size),
+        Cell::Float(print.notional),
+    ]
+}
+
+pub fn daily_cells(stats: &DailyStats) -> Result<Vec<Cell>> {
+    let day = NaiveDate::parse_from_str(&stats.day, "%Y-%m-%d")
+        .with_context(|| format!("day {}", stats.day))?;
+    Ok(vec![
+        Cell::Day(day),
+        Cell::Text(stats.instrument.clone()),
+        Cell::UInt(stats.trades),
+        Cell::Float(stats.volume),
+        Cell::Float(stats.notional),
+        Cell::Float(stats.vwap),
+        Cell::Float(stats.twap),
+        Cell::Float(stats.min_price),
+        Cell::Float(stats.max_price),
+        Cell::Time(stats.first_ts_ns),
+        Cell::Time(stats.last_ts_ns),
+    ])
+}
+
+/// Stages rows in a CSV file next to the database, then has the `duckdb`
+/// CLI create the table if needed and append the file in one
+/// transaction, typed by the column list rather than sniffed.
+pub struct DuckDbExport {
+    database: PathBuf,
+    table: String,
+    columns: &'static [Column],
+    staging: PathBuf,
+    writer: csv::Writer<fs::File>,
+    rows: u64,
+}
+
+impl DuckDbExport {
+    pub fn create(output: &Output, columns: &'static [Column]) -> Result<Self> {
+        let Output::DuckDb { database, table } = output;
+        crate::util::ensure_parent_dir(database)?;
+        let staging = staging_path(output);
+        let mut writer = csv::Writer::from_path(&staging)
+            .with_context(|| format!("creating {}", staging.display()))?;
+        writer.write_record(columns.iter().map(|(name, _)| *name))?;
+        Ok(Self {
+            database: database.clone(),
+            table: table.clone(),
+            columns,
+            staging,
+            writer,
+            rows: 0,
+        })
+    }
+
+    pub fn push(&mut self, cells: &[Cell]) -> Result<()> {
+        debug_assert_eq!(cells.len(), self.columns.len());
+        self.writer.write_record(cells.iter().map(Cell::csv))?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// The statements `finish` runs.
+    pub fn sql(&self) -> String {
+        let definition = self
+            .columns
+            .iter()
+            .map(|(name, kind)| format!("{name} {}", kind.sql()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let types = self
+            .columns
+            .iter()
+            .map(|(name, kind)| format!("'{name}': '{}'", kind.sql()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        // The table may be a reserved word such as `order` and the staging
+        // path may hold a quote, so both are quoted for DuckDB.
+        let table = format!("\"{}\"", self.table.replace('"', "\"\""));
+        let staging = self.staging.display().to_string().replace('\'', "''");
+        format!(
+            "BEGIN TRANSACTION;\n\
+             CREATE TABLE IF NOT EXISTS {table} ({definition});\n\
+             INSERT INTO {table} BY NAME SELECT * FROM read_csv('{staging}', header = true, \
+             columns = {{{types}}});\n\
+             COMMIT;\n",
+        )
+    }
+
+    /// Loads the staged rows and returns how many there were. The staging
+    /// file is removed either way.
+    pub fn finish(mut self) -> Result<u64> {
+        self.writer.flush()?;
+        let result = self.load();
+        let _ = fs::remove_file(&self.staging);
+        result?;
+        info!(
+            target: "optstore::export",
+            database = %self.database.display(),
+            table = %self.table,
+            rows = self.rows,
+            "exported rows to duckdb"
+        );
+        Ok(self.rows)
+    }
+
+    fn load(&self) -> Result<()> {
+        let binary = std::env::var(DUCKDB_ENV).unwrap_or_else(|_| "duckdb".to_string());
+        let mut child = Command::new(&binary)
+            .arg("-bail")
+            .arg(&self.database)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("running {binary}; set {DUCKDB_ENV} to the duckdb CLI"))?;
+        child
+            .stdin
+            .take()
+            .expect("piped stdin")
+            .write_all(self.sql().as_bytes())?;
+        // `-bail` makes the first failing statement exit non-zero.
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            bail!(
+                "duckdb could not load {} into {}: {}",
+                self.staging.display(),
+                self.table,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The staging file [`DuckDbExport`] writes for `output`.
+pub fn staging_path(output: &Output) -> PathBuf {
+    let Output::DuckDb { database, table } = output;
+    let mut staging = database.as_os_str().to_owned();
+    staging.push(format!(".{table}.csv"));
+    PathBuf::from(staging)
+}
@@ -1,4 +1,132 @@
+use std::io::Read;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::block::BlockLayout;
+use crate::exit::Failure;
+
 pub struct OptFileMeta {
     pub version: u32,
     pub blocks: u32,
+    pub layout: BlockLayout,
+    /// Bytes before the first row.
+    pub header_len: usize,
+    /// Id of the key an encrypted file's blocks are sealed with.
+    pub key_id: Option<u64>,
+}
+
+/// Leading bytes of every optstore row file.
+pub const MAGIC: &[u8; 8] = b"OPTSTORE";
+/// Uncompressed fixed-width rows with the block layout in the header;
+/// compressed block files will bump this.
+pub const ROW_FORMAT_VERSION: u32 = 2;
+/// The version 2 rows sealed block by block with AES-256-GCM (see
+/// [`crate::crypt`]); the header stays readable without the key.
+pub const ENCRYPTED_FORMAT_VERSION: u32 = 3;
+/// Magic, version (u32 LE), block rows (u32 LE), block bytes (u64 LE),
+/// reserved (8 bytes), which version 3 uses for the key id (u64 LE).
+pub const HEADER_LEN: usize = 32;
+/// Version 1 files end the header after magic, version and 4 reserved
+/// bytes, and use the default layout.
+pub const HEADER_LEN_V1: usize = 16;
+
+pub fn encode_header(layout: BlockLayout) -> [u8; HEADER_LEN] {
+    let mut header = [0u8; HEADER_LEN];
+    header[..8].copy_from_slice(MAGIC);
+    header[8..12].copy_from_slice(&ROW_FORMAT_VERSION.to_le_bytes());
+    header[12..16].copy_from_slice(&layout.rows.to_le_bytes());
+    header[16..24].copy_from_slice(&layout.bytes.to_le_bytes());
+    header
+}
+
+/// The header of an encrypted file whose blocks are sealed with the key
+/// `key_id` names.
+pub fn encode_encrypted_header(layout: BlockLayout, key_id: u64) -> [u8; HEADER_LEN] {
+    let mut header = encode_header(layout);
+    header[8..12].copy_from_slice(&ENCRYPTED_FORMAT_VERSION.to_le_bytes());
+    header[24..32].copy_from_slice(&key_id.to_le_bytes());
+    header
+}
+
+/// Decodes a header from at least its first [`HEADER_LEN_V1`] bytes; a
+/// version 2 header needs all [`HEADER_LEN`].
+pub fn decode_header(bytes: &[u8]) -> anyhow::Result<OptFileMeta> {
+    if bytes.len() < HEADER_LEN_V1 || &bytes[..8] != MAGIC {
+        anyhow::bail!(Failure::Corrupt("not an optstore file (bad magic)".into()));
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    let (layout, header_len) = match version {
+        1 => (BlockLayout::default(), HEADER_LEN_V1),
+        ROW_FORMAT_VERSION | ENCRYPTED_FORMAT_VERSION => {
+            if bytes.len() < HEADER_LEN {
+                anyhow::bail!(Failure::Corrupt("truncated optstore header".into()));
+            }
+            let layout = BlockLayout::new(
+                u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+                u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            )?;
+            (layout, HEADER_LEN)
+        }
+        _ => anyhow::bail!(Failure::Corrupt(format!(
+            "unsupported optstore format version {version}"
+        ))),
+    };
+    Ok(OptFileMeta {
+        version,
+        blocks: 0,
+        layout,
+        header_len,
+        key_id: (version == ENCRYPTED_FORMAT_VERSION)
+            .then(|| u64::from_le_bytes(bytes[24..32].try_into().unwrap())),
+    })
+}
+
+/// Reads and decodes the header at the start of `reader`, leaving it at
+/// the first row.
+pub fn read_header(reader: &mut impl Read) -> anyhow::Result<OptFileMeta> {
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header[..HEADER_LEN_V1])
+        .map_err(truncated)?;
+    let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    if &header[..8] == MAGIC && full_header(version) {
+        reader
+            .read_exact(&mut header[HEADER_LEN_V1..])
+            .map_err(truncated)?;
+        return decode_header(&header);
+    }
+    decode_header(&header[..HEADER_LEN_V1])
+}
+
+/// [`read_header`] for async readers.
+pub async fn read_header_async(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> anyhow::Result<OptFileMeta> {
+    let mut header = [0u8; HEADER_LEN];
+    reader
+        .read_exact(&mut header[..HEADER_LEN_V1])
+        .await
+        .map_err(truncated)?;
+    let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    if &header[..8] == MAGIC && full_header(version) {
+        reader
+            .read_exact(&mut header[HEADER_LEN_V1..])
+            .await
+            .map_err(truncated)?;
+        return decode_header(&header);
+    }
+    decode_header(&header[..HEADER_LEN_V1])
+}
+
+fn full_header(version: u32) -> bool {
+    matches!(version, ROW_FORMAT_VERSION | ENCRYPTED_FORMAT_VERSION)
+}
+
+/// A file ending inside its header is corrupt; other IO errors are not.
+fn truncated(err: std::io::Error) -> anyhow::Error {
+    if err.kind() == std::io::ErrorKind::UnexpectedEof {
+        Failure::Corrupt("truncated optstore header".into()).into()
+    } else {
+        err.into()
+    }
 }
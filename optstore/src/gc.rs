@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use fxhash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+use tracing::info;
+
+use crate::dict::InstrumentDictionary;
+use crate::prefetch::PrefetchOptions;
+use crate::reader::TickReader;
+use crate::retrieve::{
+    day_bounds_ms, CacheManager, DeribitNormalizer, Normalizer, RawChunk, RetrieveKind,
+    RetrieveSpec,
+};
+use crate::util::day_to_path;
+
+type TickKey = (u32, u64, i64, u32, u8);
+
+/// What `gc` did with one cached instrument-day.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GcStatus {
+    /// Every cached trade is in the day file; the cached day was removed
+    /// (or would be, with `--dry-run`).
+    Removed,
+    /// Within `--keep-days` of today.
+    Recent,
+    /// The data directory has no file for the day.
+    NoFile,
+    /// Some cached trades are not in the day file.
+    Unconfirmed,
+}
+
+impl GcStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            GcStatus::Removed => "removed",
+            GcStatus::Recent => "recent",
+            GcStatus::NoFile => "no_file",
+            GcStatus::Unconfirmed => "unconfirmed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct GcDay {
+    pub symbol: String,
+    /// `YYYY-MM-DD`.
+    pub day: String,
+    pub status: GcStatus,
+    pub parts: u64,
+    /// Compressed bytes of the day's cached parts.
+    pub bytes: u64,
+    /// Distinct trades cached for the day; 0 when not compared.
+    pub cached: u64,
+    /// Cached trades without a matching row in the day file.
+    pub missing: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub struct GcSummary {
+    pub dry_run: bool,
+    pub days_checked: u64,
+    pub days_removed: u64,
+    pub parts_removed: u64,
+    pub bytes_freed: u64,
+    /// Old enough days kept because their data is not confirmed stored.
+    pub days_unconfirmed: u64,
+    pub days: Vec<GcDay>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GcOptions {
+    /// Days on or after `today - keep_days` are kept without comparing.
+    pub keep_days: u32,
+    pub today: NaiveDate,
+    pub dry_run: bool,
+}
+
+/// Removes cached trades days of `symbols` older than the `keep_days`
+/// window once every trade in them is confirmed present in the day's
+/// optstore file under `data` (`<data>/YYYY/MM/DD.opt`).
+///
+/// A cached day's parts are normalized as `retrieve` does, restricted to
+/// the UTC day, and each distinct trade must match a row of the day file
+/// on [`Tick::key`](crate::schema::Tick::key) (instrument, time, price,
+/// size, event), the instrument's id taken from the file's dictionary.
+/// Each day file is read once for all symbols cached on that day. Only
+/// whole days are removed, manifest included, so the cache never holds a
+/// manifest listing deleted parts; days in the `block_trades/` and
+/// `liquidations/` partitions are left alone.
+pub fn collect(
+    cache: &CacheManager,
+    data: &Path,
+    symbols: &[String],
+    options: GcOptions,
+) -> Result<GcSummary> {
+    let keep_from = options.today - chrono::Duration::days(options.keep_days as i64);
+    let mut by_day: BTreeMap<u32, Vec<&str>> = BTreeMap::new();
+    for symbol in symbols {
+        for day_ymd in cache.cached_days(symbol)? {
+            by_day.entry(day_ymd).or_default().push(symbol);
+        }
+    }
+
+    let mut summary = GcSummary {
+        dry_run: options.dry_run,
+        ..GcSummary::default()
+    };
+    for (day_ymd, symbols) in by_day {
+        let date = NaiveDate::parse_from_str(&format!("{day_ymd:08}"), "%Y%m%d")
+            .with_context(|| format!("cached day {day_ymd}"))?;
+        let day = date.format("%Y-%m-%d").to_string();
+        let path = day_to_path(data, &day);
+        let stored = if date >= keep_from || !path.exists() {
+            None
+        } else {
+            // Files written before the registry carry hashed ids and may
+            // lack a dictionary.
+            let dictionary = InstrumentDictionary::load_for(&path).unwrap_or_default();
+            let ids: FxHashMap<&str, u32> = symbols
+                .iter()
+                .map(|symbol| {
+                    let id = dictionary
+                        .id(symbol)
+                        .unwrap_or_else(|| InstrumentDictionary::hash_id(symbol));
+                    (*symbol, id)
+                })
+                .collect();
+            let mut keys = FxHashSet::default();
+            for tick in TickReader::open(&path)?.instruments(ids.values().copied().collect()) {
+                keys.insert(tick?.key());
+            }
+            Some((keys, ids))
+        };
+
+        for symbol in symbols {
+            let spec = RetrieveSpec {
+                symbol: symbol.to_string(),
+                day_ymd,
+                kind: RetrieveKind::Trades,
+            };
+            let Some(manifest) = cache.load_manifest(&spec)? else {
+                continue;
+            };
+            let mut entry = GcDay {
+                symbol: symbol.to_string(),
+                day: day.clone(),
+                status: GcStatus::Recent,
+                parts: manifest.parts.len() as u64,
+                bytes: manifest.parts.iter().map(|part| part.bytes).sum(),
+                cached: 0,
+                missing: 0,
+            };
+            summary.days_checked += 1;
+            match &stored {
+                None if date >= keep_from => {}
+                None => entry.status = GcStatus::NoFile,
+                Some((stored, ids)) => {
+                    let cached = cached_keys(cache, &spec, ids[symbol], &manifest)?;
+                    entry.cached = cached.len() as u64;
+                    entry.missing =
+                        cached.iter().filter(|key| !stored.contains(key)).count() as u64;
+                    if entry.missing > 0 {
+                        entry.status = GcStatus::Unconfirmed;
+                        summary.days_unconfirmed += 1;
+                    } else {
+                        if !options.dry_run {
+                            cache.remove_day(&spec)?;
+                        }
+                        entry.status = GcStatus::Removed;
+                        summary.days_removed += 1;
+                        summary.parts_removed += entry.parts;
+                        summary.bytes_freed += entry.bytes;
+                        info!(
+                            target: "optstore::gc",
+                            symbol = %entry.symbol,
+                            day = %entry.day,
+                            parts = entry.parts,
+                            bytes = entry.bytes,
+                            dry_run = options.dry_run,
+                            "removed cached day"
+                        );
+                    }
+                }
+            }
+            summary.days.push(entry);
+        }
+    }
+    Ok(summary)
+}
+
+/// Distinct keys of the trades cached for `spec`'s UTC day.
+fn cached_keys(
+    cache: &CacheManager,
+    spec: &RetrieveSpec,
+    instrument_id: u32,
+    manifest: &crate::retrieve::CacheManifest,
+) -> Result<FxHashSet<TickKey>> {
+    let (start_ms, end_ms) = day_bounds_ms(spec.day_ymd)?;
+    let (start_ns, end_ns) = (start_ms * 1_000_000, end_ms * 1_000_000);
+    let normalizer = DeribitNormalizer {
+        instrument_id,
+        kind: RetrieveKind::Trades,
+    };
+    let parts = manifest.parts.iter().map(|part| part.part);
+    let mut keys = FxHashSet::default();
+    for (part, raw) in
+        parts
+            .clone()
+            .zip(cache.read_parts(spec, parts, PrefetchOptions::default())?)
+    {
+        let chunk = RawChunk {
+            data: raw?.into(),
+            start_ns: 0,
+            end_ns: 0,
+            resume: None,
+        };
+        let ticks = normalizer
+            .to_ticks(chunk)
+            .with_context(|| format!("parsing {} part {part}", spec.symbol))?;
+        keys.extend(
+            ticks
+                .iter()
+                .filter(|tick| (start_ns..=end_ns).contains(&tick.ts_ns))
+                .map(|tick| tick.key()),
+        );
+    }
+    Ok(keys)
+}
@@ -0,0 +1,116 @@
+use clap::{ArgAction, Command};
+use serde::Serialize;
+
+/// One command of a CLI with its arguments and subcommands, as dumped by
+/// `--help-json` for wrapper UIs and scripts.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct CommandHelp {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub args: Vec<ArgHelp>,
+    pub subcommands: Vec<CommandHelp>,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct ArgHelp {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub long: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short: Option<char>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    /// `flag` for switches, otherwise `value`, `values` (repeatable) or
+    /// `positional`.
+    pub kind: &'static str,
+    pub required: bool,
+    /// Accepted before or after any subcommand.
+    pub global: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub value_names: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub default: Vec<String>,
+    /// The only values accepted, for enum arguments.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub possible_values: Vec<String>,
+    /// Environment variable the value falls back to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+}
+
+/// The tree under `command`, hidden commands and arguments left out;
+/// clap's generated `help` subcommand and `--help`/`--version` are too.
+pub fn command_help(command: &Command) -> CommandHelp {
+    let mut command = command.clone();
+    command.build();
+    describe(&command)
+}
+
+fn describe(command: &Command) -> CommandHelp {
+    CommandHelp {
+        name: command.get_name().to_string(),
+        about: command.get_about().map(ToString::to_string),
+        version: command.get_version().map(str::to_string),
+        args: command
+            .get_arguments()
+            .filter(|arg| !arg.is_hide_set())
+            .filter(|arg| !matches!(arg.get_action(), ArgAction::Help | ArgAction::Version))
+            .map(|arg| {
+                let flag = !arg.get_action().takes_values();
+                ArgHelp {
+                    id: arg.get_id().to_string(),
+                    long: arg.get_long().map(str::to_string),
+                    short: arg.get_short(),
+                    help: arg.get_help().map(ToString::to_string),
+                    kind: if flag {
+                        "flag"
+                    } else if arg.is_positional() {
+                        "positional"
+                    } else if matches!(arg.get_action(), ArgAction::Append) {
+                        "values"
+                    } else {
+                        "value"
+                    },
+                    required: arg.is_required_set(),
+                    global: arg.is_global_set(),
+                    // Flags take no value and default to off.
+                    value_names: if flag {
+                        Vec::new()
+                    } else {
+                        arg.get_value_names()
+                            .unwrap_or_default()
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect()
+                    },
+                    default: if flag {
+                        Vec::new()
+                    } else {
+                        arg.get_default_values()
+                            .iter()
+                            .map(|value| value.to_string_lossy().into_owned())
+                            .collect()
+                    },
+                    possible_values: if flag {
+                        Vec::new()
+                    } else {
+                        arg.get_possible_values()
+                            .iter()
+                            .filter(|value| !value.is_hide_set())
+                            .map(|value| value.get_name().to_string())
+                            .collect()
+                    },
+                    env: arg.get_env().map(|env| env.to_string_lossy().into_owned()),
+                }
+            })
+            .collect(),
+        subcommands: command
+            .get_subcommands()
+            .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+            .map(describe)
+            .collect(),
+    }
+}
@@ -1,4 +1,191 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::block::DEFAULT_BLOCK_ROWS;
+use crate::file::{read_header, OptFileMeta};
+use crate::reader::TickReader;
+use crate::schema::{event, Tick};
+
 pub struct IndexEntry {
     pub block: u32,
     pub offset: u64,
 }
+
+/// Rows summarised by one [`Zone`] in a file with the default block
+/// layout; other files get one zone per block of their header's layout.
+pub const ZONE_ROWS: u64 = DEFAULT_BLOCK_ROWS as u64;
+
+/// Min/max statistics of a run of consecutive rows.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Zone {
+    pub first_row: u64,
+    pub rows: u64,
+    pub min_ts_ns: u64,
+    pub max_ts_ns: u64,
+    /// Trade prints of every kind in the zone.
+    pub trades: u64,
+    /// Largest `price_fp × size` among the zone's trade prints, before
+    /// the contract size is applied.
+    pub max_trade_value_fp: u128,
+}
+
+impl Zone {
+    fn add(&mut self, tick: &Tick) {
+        if self.rows == 0 {
+            self.min_ts_ns = tick.ts_ns;
+            self.max_ts_ns = tick.ts_ns;
+        }
+        self.rows += 1;
+        self.min_ts_ns = self.min_ts_ns.min(tick.ts_ns);
+        self.max_ts_ns = self.max_ts_ns.max(tick.ts_ns);
+        if event::is_trade(tick.event) {
+            self.trades += 1;
+            self.max_trade_value_fp = self.max_trade_value_fp.max(trade_value_fp(tick));
+        }
+    }
+
+    /// Whether any row may fall in `from_ns..to_ns`.
+    pub fn overlaps(&self, from_ns: u64, to_ns: u64) -> bool {
+        self.rows > 0 && self.max_ts_ns >= from_ns && self.min_ts_ns < to_ns
+    }
+}
+
+/// `price_fp × size` of a trade print; negative prices count as zero.
+pub fn trade_value_fp(tick: &Tick) -> u128 {
+    tick.price_fp.max(0) as u128 * tick.size as u128
+}
+
+/// Zone map of a row file, stored as a JSON sidecar
+/// (`<file>.zones.json`). Row files are append-only, so
+/// [`ZoneMap::refresh`] only summarises rows written since the last refresh.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ZoneMap {
+    pub zone_rows: u64,
+    pub zones: Vec<Zone>,
+}
+
+impl Default for ZoneMap {
+    fn default() -> Self {
+        Self {
+            zone_rows: ZONE_ROWS,
+            zones: Vec::new(),
+        }
+    }
+}
+
+impl ZoneMap {
+    pub fn sidecar_path(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_owned();
+        name.push(".zones.json");
+        PathBuf::from(name)
+    }
+
+    pub fn load_for(file: &Path) -> Result<Self> {
+        let path = Self::sidecar_path(file);
+        let raw = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_slice(&raw).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn store_for(&self, file: &Path) -> Result<()> {
+        let path = Self::sidecar_path(file);
+        fs::write(&path, serde_json::to_vec(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Rows covered by the zones.
+    pub fn rows(&self) -> u64 {
+        self.zones.iter().map(|zone| zone.rows).sum()
+    }
+
+    /// Summarises every row of `file` from scratch, one zone per block.
+    pub fn build(file: &Path) -> Result<Self> {
+        let mut map = Self {
+            zone_rows: file_meta(file)?.layout.block_rows(),
+            zones: Vec::new(),
+        };
+        map.extend(file)?;
+        Ok(map)
+    }
+
+    /// Loads the sidecar, extends it over rows appended since it was
+    /// written and stores it again when anything changed. A missing,
+    /// unreadable or stale sidecar (more rows than the file) is rebuilt.
+    /// A sidecar built for another block layout is rebuilt too.
+    pub fn refresh(file: &Path) -> Result<Self> {
+        let rows = file_rows(file)?;
+        let zone_rows = file_meta(file)?.layout.block_rows();
+        let loaded = Self::load_for(file)
+            .ok()
+            .filter(|map| map.zone_rows == zone_rows && map.rows() <= rows);
+        match loaded {
+            Some(map) if map.rows() == rows => Ok(map),
+            stale => {
+                let mut map = stale.unwrap_or(Self {
+                    zone_rows,
+                    zones: Vec::new(),
+                });
+                map.extend(file)?;
+                map.store_for(file)?;
+                Ok(map)
+            }
+        }
+    }
+
+    /// Re-reads a trailing partial zone and summarises the rows after it.
+    fn extend(&mut self, file: &Path) -> Result<()> {
+        if self
+            .zones
+            .last()
+            .is_some_and(|zone| zone.rows < self.zone_rows)
+        {
+            self.zones.pop();
+        }
+        let start = self.rows();
+        let end = file_rows(file)?;
+        let mut reader = TickReader::open(file)?;
+        reader.seek_rows(start..end)?;
+        let mut zone = Zone {
+            first_row: start,
+            ..Zone::default()
+        };
+        for tick in reader {
+            zone.add(&tick?);
+            if zone.rows == self.zone_rows {
+                let first_row = zone.first_row + zone.rows;
+                self.zones.push(std::mem::replace(
+                    &mut zone,
+                    Zone {
+                        first_row,
+                        ..Zone::default()
+                    },
+                ));
+            }
+        }
+        if zone.rows > 0 {
+            self.zones.push(zone);
+        }
+        Ok(())
+    }
+}
+
+/// Complete rows in a row file; a torn trailing row is not counted.
+/// Encrypted files are counted from their length too, without the key.
+pub fn file_rows(file: &Path) -> Result<u64> {
+    let len = fs::metadata(file)
+        .with_context(|| format!("stat {}", file.display()))?
+        .len();
+    let meta = file_meta(file)?;
+    let body_len = len.saturating_sub(meta.header_len as u64);
+    Ok(match meta.key_id {
+        Some(_) => crate::crypt::sealed_rows(body_len, meta.layout.block_rows()),
+        None => body_len / Tick::ENCODED_LEN as u64,
+    })
+}
+
+fn file_meta(file: &Path) -> Result<OptFileMeta> {
+    let mut reader = fs::File::open(file).with_context(|| format!("open {}", file.display()))?;
+    read_header(&mut reader).with_context(|| format!("reading header of {}", file.display()))
+}
@@ -1,14 +1,35 @@
 pub mod block;
+pub mod book;
 pub mod cli;
 pub mod codec;
+pub mod crypt;
+pub mod dict;
+pub mod exit;
+pub mod export;
 pub mod file;
+pub mod gc;
+pub mod help;
 pub mod index;
+pub mod prefetch;
 pub mod progress;
+pub mod quality;
+pub mod query;
 pub mod reader;
+pub mod reconcile;
+pub mod registry;
+pub mod repair;
+pub mod repro;
 pub mod retrieve;
 pub mod schema;
+pub mod selection;
+pub mod seq;
+pub mod stats;
 pub mod util;
 pub mod wal;
 pub mod writer;
 
+pub use dict::InstrumentDictionary;
+pub use reader::{AsyncTickReader, TickReader};
 pub use schema::Tick;
+pub use selection::Selection;
+pub use writer::TickWriter;
@@ -1,13 +1,19 @@
+use std::process::ExitCode;
+
+use clap::Parser;
 use optstore::cli::OptStoreCli;
+use optstore::exit;
 use tracing_subscriber::EnvFilter;
 
-fn main() -> anyhow::Result<()> {
+fn main() -> ExitCode {
     tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .with_target(false)
         .compact()
         .init();
 
-    let cli = OptStoreCli::parse();
-    cli.execute()
+    match OptStoreCli::try_parse() {
+        Ok(cli) => exit::report(cli.execute()),
+        Err(err) => exit::report_usage(err),
+    }
 }
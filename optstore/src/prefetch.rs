@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{sync_channel, Receiver};
+
+use anyhow::Result;
+
+/// How far ahead of the consumer a [`Prefetch`] runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrefetchOptions {
+    /// Threads loading ahead; 0 loads each item when it is asked for.
+    pub workers: usize,
+    /// Items loaded or loading ahead of the consumer at most.
+    pub depth: usize,
+}
+
+impl PrefetchOptions {
+    pub const SEQUENTIAL: PrefetchOptions = PrefetchOptions {
+        workers: 0,
+        depth: 0,
+    };
+}
+
+impl Default for PrefetchOptions {
+    /// A worker per core but the consumer's, up to four, with two items in
+    /// flight each; sequential on a single core, where nothing overlaps
+    /// but IO and the handoff only costs.
+    fn default() -> Self {
+        let workers = std::thread::available_parallelism()
+            .map_or(1, |threads| threads.get())
+            .saturating_sub(1)
+            .min(4);
+        Self {
+            workers,
+            depth: workers * 2,
+        }
+    }
+}
+
+/// Runs loading jobs (read a block, decompress it) on a small thread pool
+/// ahead of the consuming iterator, so IO, decompression and whatever the
+/// consumer does with each result overlap. Results come back in job
+/// order; at most `depth` of them are held at once.
+pub struct Prefetch<J, T> {
+    jobs: J,
+    pool: Option<rayon::ThreadPool>,
+    depth: usize,
+    pending: VecDeque<Receiver<T>>,
+}
+
+impl<J, F, T> Prefetch<J, T>
+where
+    J: Iterator<Item = F>,
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    pub fn new(jobs: impl IntoIterator<IntoIter = J>, options: PrefetchOptions) -> Result<Self> {
+        let pool = match options.workers.min(options.depth) {
+            0 => None,
+            workers => Some(
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(workers)
+                    .thread_name(|index| format!("optstore-prefetch-{index}"))
+                    .build()?,
+            ),
+        };
+        let mut prefetch = Self {
+            jobs: jobs.into_iter(),
+            pool,
+            depth: options.depth,
+            pending: VecDeque::new(),
+        };
+        prefetch.fill();
+        Ok(prefetch)
+    }
+
+    fn fill(&mut self) {
+        let Some(pool) = &self.pool else {
+            return;
+        };
+        while self.pending.len() < self.depth {
+            let Some(job) = self.jobs.next() else {
+                return;
+            };
+            let (sender, receiver) = sync_channel(1);
+            pool.spawn(move || {
+                // The consumer may have stopped early and dropped its end.
+                let _ = sender.send(job());
+            });
+            self.pending.push_back(receiver);
+        }
+    }
+}
+
+impl<J, F, T> Iterator for Prefetch<J, T>
+where
+    J: Iterator<Item = F>,
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.pool.is_none() {
+            return self.jobs.next().map(|job| job());
+        }
+        let receiver = self.pending.pop_front()?;
+        self.fill();
+        Some(receiver.recv().expect("prefetch job panicked"))
+    }
+}
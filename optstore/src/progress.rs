@@ -6,6 +6,15 @@ use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use serde::Serialize;
 use tracing::info;
 
+use crate::gc::GcSummary;
+use crate::quality::QualityReport;
+use crate::query::{DailyStats, LargePrint};
+use crate::reconcile::DayCompleteness;
+use crate::registry::MigrateSummary;
+use crate::repair::RepairSummary;
+use crate::repro::Verification;
+use crate::stats::FileStats;
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum ProgressKind {
@@ -29,9 +38,24 @@ pub enum ProgressKind {
     Verify {
         file: String,
     },
+    Reconcile {
+        cache: String,
+    },
+    Repair {
+        cache: String,
+    },
+    Gc {
+        cache: String,
+    },
+    MigrateIds {
+        data: String,
+    },
     Query {
         description: String,
     },
+    Stats {
+        file: String,
+    },
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -49,7 +73,33 @@ pub enum ProgressUpdate {
         blocks_pruned: u64,
         bytes_read: u64,
         projected_columns: Vec<String>,
+        /// Instruments the selection resolved to; `None` reads them all.
+        instruments: Option<Vec<String>>,
+        rows_scanned: u64,
+        rows_matched: u64,
+        /// Largest prints of a `--top` query, largest first.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        prints: Option<Vec<LargePrint>>,
+        /// Per instrument-day statistics of a `--daily-stats` query.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        daily: Option<Vec<DailyStats>>,
+    },
+    /// Validation counts of a finished ingest.
+    Quality(QualityReport),
+    /// Per instrument-day completeness of a retrieve cache.
+    Completeness {
+        days: Vec<DayCompleteness>,
+        flagged: u64,
     },
+    /// Trade ranges refetched into a retrieve cache.
+    Repair(RepairSummary),
+    /// Cached days removed once confirmed stored.
+    Gc(GcSummary),
+    MigrateIds(MigrateSummary),
+    /// Per-column encoding statistics of a stored file.
+    Stats(FileStats),
+    /// A stored file checked against its reproducibility record.
+    Verify(Verification),
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -107,7 +157,12 @@ impl Progress {
                     format!("Write block #{id} ({bytes} bytes)")
                 }
                 ProgressKind::Verify { file } => format!("Verify {file}"),
+                ProgressKind::Reconcile { cache } => format!("Reconcile {cache}"),
+                ProgressKind::Repair { cache } => format!("Repair {cache}"),
+                ProgressKind::Gc { cache } => format!("Collect {cache}"),
+                ProgressKind::MigrateIds { data } => format!("Migrate ids in {data}"),
                 ProgressKind::Query { description } => description.clone(),
+                ProgressKind::Stats { file } => format!("Stats {file}"),
             });
             pb.set_style(
                 ProgressStyle::with_template("{spinner} {msg}")
@@ -131,7 +186,14 @@ impl Progress {
                 ProgressUpdate::Rows { rows, bytes } => {
                     bar.set_message(format!("{} rows={} bytes={}", bar.message(), rows, bytes));
                 }
-                ProgressUpdate::QueryResult { .. } => {}
+                ProgressUpdate::QueryResult { .. }
+                | ProgressUpdate::Quality(_)
+                | ProgressUpdate::Completeness { .. }
+                | ProgressUpdate::Repair(_)
+                | ProgressUpdate::Gc(_)
+                | ProgressUpdate::MigrateIds(_)
+                | ProgressUpdate::Stats(_)
+                | ProgressUpdate::Verify(_) => {}
             }
         }
         if self.json {
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use fxhash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{event, Tick, SIZE_SCALE};
+use crate::seq::SeqRange;
+
+/// Default `--max-size`, in contracts.
+pub const DEFAULT_MAX_SIZE: f64 = 100_000.0;
+
+/// Limits a tick has to stay within to pass [`QualityCheck`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QualityRules {
+    /// Ticks outside `day_from_ns..day_to_ns` are out of day.
+    pub day_from_ns: u64,
+    pub day_to_ns: u64,
+    /// Largest plausible size, fixed-point like `Tick::size`.
+    pub max_size: u32,
+}
+
+impl QualityRules {
+    /// Rules for the UTC day of an ingest, with `max_size` in contracts.
+    pub fn for_day(day: NaiveDate, max_size: f64) -> Self {
+        let start = |day: NaiveDate| {
+            day.and_hms_opt(0, 0, 0)
+                .and_then(|at| at.and_utc().timestamp_nanos_opt())
+                .unwrap_or(0)
+                .max(0) as u64
+        };
+        Self {
+            day_from_ns: start(day),
+            day_to_ns: day.succ_opt().map_or(u64::MAX, start),
+            max_size: (max_size * SIZE_SCALE).clamp(0.0, u32::MAX as f64) as u32,
+        }
+    }
+}
+
+/// Per-rule counts of an ingest. A row can break several rules, so the
+/// rule counts may add up to more than `flagged_rows`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QualityReport {
+    pub rows: u64,
+    /// Rows that broke at least one rule.
+    pub flagged_rows: u64,
+    /// Lines that did not parse as a tick; not counted in `rows`.
+    pub malformed: u64,
+    /// Earlier than the row before.
+    pub non_monotonic: u64,
+    /// Zero or negative trade price, or a negative index price on a book row.
+    pub bad_price: u64,
+    /// Zero-size trade, or a size above the configured maximum.
+    pub bad_size: u64,
+    /// Same `Tick::key` as an earlier row.
+    pub duplicates: u64,
+    pub out_of_day: u64,
+    /// Trade numbers skipped between consecutive prints of an instrument.
+    pub seq_missing: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seq_gaps: Vec<SeqGap>,
+}
+
+/// Trade numbers of one instrument that never reached the ingest.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SeqGap {
+    pub instrument_id: u32,
+    #[serde(flatten)]
+    pub missing: SeqRange,
+}
+
+impl QualityReport {
+    pub fn is_clean(&self) -> bool {
+        self.flagged_rows == 0 && self.malformed == 0 && self.seq_missing == 0
+    }
+
+    pub fn sidecar_path(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_owned();
+        name.push(".quality.json");
+        PathBuf::from(name)
+    }
+
+    pub fn load_for(file: &Path) -> Result<Self> {
+        let path = Self::sidecar_path(file);
+        let raw = fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_slice(&raw).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn store_for(&self, file: &Path) -> Result<()> {
+        let path = Self::sidecar_path(file);
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+}
+
+impl fmt::Display for QualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rows={} flagged={} malformed={} non_monotonic={} bad_price={} bad_size={} duplicates={} out_of_day={} seq_missing={}",
+            self.rows,
+            self.flagged_rows,
+            self.malformed,
+            self.non_monotonic,
+            self.bad_price,
+            self.bad_size,
+            self.duplicates,
+            self.out_of_day,
+            self.seq_missing,
+        )
+    }
+}
+
+/// Validates ticks in ingest order and counts what each rule flags.
+pub struct QualityCheck {
+    rules: QualityRules,
+    report: QualityReport,
+    last_ts_ns: Option<u64>,
+    seen: FxHashSet<(u32, u64, i64, u32, u8)>,
+    /// Highest `trade_seq` seen per instrument.
+    last_seq: FxHashMap<u32, u64>,
+}
+
+impl QualityCheck {
+    pub fn new(rules: QualityRules) -> Self {
+        Self {
+            rules,
+            report: QualityReport::default(),
+            last_ts_ns: None,
+            seen: FxHashSet::default(),
+            last_seq: FxHashMap::default(),
+        }
+    }
+
+    /// Checks the next tick; `false` when it broke any rule.
+    pub fn check(&mut self, tick: &Tick) -> bool {
+        let rules = &self.rules;
+        let report = &mut self.report;
+        report.rows += 1;
+        let trade = event::is_trade(tick.event);
+        let flags = [
+            (
+                self.last_ts_ns.is_some_and(|last| tick.ts_ns < last),
+                &mut report.non_monotonic,
+            ),
+            (
+                tick.price_fp < 0 || (trade && tick.price_fp == 0),
+                &mut report.bad_price,
+            ),
+            (
+                tick.size > rules.max_size || (trade && tick.size == 0),
+                &mut report.bad_size,
+            ),
+            (!self.seen.insert(tick.key()), &mut report.duplicates),
+            (
+                tick.ts_ns < rules.day_from_ns || tick.ts_ns >= rules.day_to_ns,
+                &mut report.out_of_day,
+            ),
+        ];
+        let mut clean = true;
+        for (broken, count) in flags {
+            if broken {
+                *count += 1;
+                clean = false;
+            }
+        }
+        self.last_ts_ns = Some(tick.ts_ns);
+        // A skipped number is missing data, not a bad row; repeats and
+        // late prints only move the high-water mark forward.
+        if let Some(seq) = tick.trade_seq() {
+            match self.last_seq.insert(tick.instrument_id, seq) {
+                Some(previous) if previous >= seq => {
+                    self.last_seq.insert(tick.instrument_id, previous);
+                }
+                Some(previous) => {
+                    if let Some(missing) = SeqRange::between(previous, seq) {
+                        report.seq_missing += missing.len();
+                        report.seq_gaps.push(SeqGap {
+                            instrument_id: tick.instrument_id,
+                            missing,
+                        });
+                    }
+                }
+                None => {}
+            }
+        }
+        if !clean {
+            report.flagged_rows += 1;
+        }
+        clean
+    }
+
+    /// Counts a line that could not be parsed.
+    pub fn malformed(&mut self) {
+        self.report.malformed += 1;
+    }
+
+    pub fn finish(self) -> QualityReport {
+        self.report
+    }
+}
@@ -0,0 +1,333 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate};
+use serde::Serialize;
+
+use crate::dict::InstrumentDictionary;
+use crate::index::{trade_value_fp, Zone, ZoneMap};
+use crate::reader::TickReader;
+use crate::schema::{event, Tick, PRICE_SCALE, SIZE_SCALE};
+use crate::selection::Selection;
+
+/// One trade print ranked by [`top_prints`].
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct LargePrint {
+    /// Dictionary name, or the id in hex when the dictionary lacks it.
+    pub instrument: String,
+    pub ts_ns: u64,
+    pub event: &'static str,
+    pub price: f64,
+    /// Contracts.
+    pub size: f64,
+    pub contract_size: f64,
+    /// `price × size × contract_size`: the premium in the price's currency.
+    pub notional: f64,
+}
+
+/// The largest prints and how much of the file was read to find them.
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub struct TopPrints {
+    /// Largest notional first.
+    pub prints: Vec<LargePrint>,
+    pub zones_scanned: u64,
+    pub zones_pruned: u64,
+    pub rows_scanned: u64,
+}
+
+/// Orders prints by notional, then earlier prints first.
+struct Ranked(LargePrint);
+
+impl Ranked {
+    fn key(&self) -> (f64, Reverse<u64>) {
+        (self.0.notional, Reverse(self.0.ts_ns))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ((a, a_ts), (b, b_ts)) = (self.key(), other.key());
+        a.total_cmp(&b).then(a_ts.cmp(&b_ts))
+    }
+}
+
+/// The `n` trade prints (of every kind) with the largest notional among
+/// `instruments` (all when `None`) in `from_ns..to_ns`.
+///
+/// Zones outside the range or without trades are skipped; the rest are read
+/// largest possible notional first, and reading stops once no remaining
+/// zone can beat the `n`th print found so far.
+pub fn top_prints(
+    path: &Path,
+    dictionary: &InstrumentDictionary,
+    instruments: Option<&BTreeSet<u32>>,
+    from_ns: u64,
+    to_ns: u64,
+    n: usize,
+) -> Result<TopPrints> {
+    let zones = ZoneMap::refresh(path)?.zones;
+    let mut result = TopPrints::default();
+    if n == 0 {
+        result.zones_pruned = zones.len() as u64;
+        return Ok(result);
+    }
+
+    // Unknown ids count as 1 contract, so the bound never drops below that.
+    let max_contract_size = match instruments {
+        Some(ids) => ids
+            .iter()
+            .map(|id| dictionary.contract_size(*id))
+            .fold(1.0, f64::max),
+        None => dictionary
+            .contract_sizes
+            .values()
+            .copied()
+            .fold(1.0, f64::max),
+    };
+    let bound = |zone: &Zone| {
+        zone.max_trade_value_fp as f64 / (PRICE_SCALE * SIZE_SCALE) * max_contract_size
+    };
+    let mut candidates: Vec<&Zone> = zones
+        .iter()
+        .filter(|zone| zone.trades > 0 && zone.overlaps(from_ns, to_ns))
+        .collect();
+    result.zones_pruned = (zones.len() - candidates.len()) as u64;
+    candidates.sort_by(|a, b| bound(b).total_cmp(&bound(a)));
+
+    let mut reader = TickReader::open(path)?;
+    // Min-heap of the best `n` so far.
+    let mut best: BinaryHeap<Reverse<Ranked>> = BinaryHeap::with_capacity(n + 1);
+    for (visited, zone) in candidates.iter().enumerate() {
+        if best.len() == n
+            && best
+                .peek()
+                .is_some_and(|Reverse(nth)| bound(zone) <= nth.0.notional)
+        {
+            result.zones_pruned += (candidates.len() - visited) as u64;
+            break;
+        }
+        result.zones_scanned += 1;
+        reader.seek_rows(zone.first_row..zone.first_row + zone.rows)?;
+        for tick in reader.by_ref() {
+            let tick = tick?;
+            if !event::is_trade(tick.event)
+                || tick.ts_ns < from_ns
+                || tick.ts_ns >= to_ns
+                || instruments.is_some_and(|ids| !ids.contains(&tick.instrument_id))
+            {
+                continue;
+            }
+            best.push(Reverse(Ranked(large_print(&tick, dictionary))));
+            if best.len() > n {
+                best.pop();
+            }
+        }
+    }
+    result.rows_scanned = reader.rows_read();
+    result.prints = best
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(Ranked(print))| print)
+        .collect();
+    Ok(result)
+}
+
+fn large_print(tick: &Tick, dictionary: &InstrumentDictionary) -> LargePrint {
+    let contract_size = dictionary.contract_size(tick.instrument_id);
+    let price = tick.price_fp as f64 / PRICE_SCALE;
+    let size = tick.size as f64 / SIZE_SCALE;
+    LargePrint {
+        instrument: instrument_name(dictionary, tick.instrument_id),
+        ts_ns: tick.ts_ns,
+        event: event::name(tick.event),
+        price,
+        size,
+        contract_size,
+        notional: trade_value_fp(tick) as f64 / (PRICE_SCALE * SIZE_SCALE) * contract_size,
+    }
+}
+
+/// Dictionary name, or the id in hex when the dictionary lacks it.
+fn instrument_name(dictionary: &InstrumentDictionary, id: u32) -> String {
+    dictionary
+        .name(id)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{id:08x}"))
+}
+
+/// Trade statistics of one instrument over one UTC day, computed by
+/// [`daily_stats`] from the trade prints of every kind.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct DailyStats {
+    /// Dictionary name, or the id in hex when the dictionary lacks it.
+    pub instrument: String,
+    /// `YYYY-MM-DD`.
+    pub day: String,
+    pub trades: u64,
+    /// Contracts.
+    pub volume: f64,
+    /// `Σ price × size × contract_size`: the premium traded in the price's
+    /// currency.
+    pub notional: f64,
+    pub vwap: f64,
+    /// Each print's price weighted by the time until the next print, from
+    /// the first print to the last; a lone print's price.
+    pub twap: f64,
+    pub min_price: f64,
+    pub max_price: f64,
+    pub first_ts_ns: u64,
+    pub last_ts_ns: u64,
+}
+
+/// Per instrument-day statistics and how much of the files was read.
+#[derive(Clone, Debug, Default, Serialize, PartialEq)]
+pub struct DailyStatsResult {
+    /// By day, then instrument.
+    pub days: Vec<DailyStats>,
+    pub files: u64,
+    pub zones_scanned: u64,
+    pub zones_pruned: u64,
+    pub rows_scanned: u64,
+}
+
+/// Running sums of one instrument-day, kept in fixed point.
+#[derive(Default)]
+struct DayTotals {
+    trades: u64,
+    size: u64,
+    value_fp: i128,
+    notional: f64,
+    /// `Σ price_fp × ns` the price held.
+    time_value: i128,
+    held_ns: u64,
+    min_price_fp: i64,
+    max_price_fp: i64,
+    first_ts_ns: u64,
+    last_ts_ns: u64,
+    last_price_fp: i64,
+}
+
+impl DayTotals {
+    fn add(&mut self, tick: &Tick, contract_size: f64) {
+        if self.trades == 0 {
+            self.first_ts_ns = tick.ts_ns;
+            self.last_ts_ns = tick.ts_ns;
+            self.min_price_fp = tick.price_fp;
+            self.max_price_fp = tick.price_fp;
+        }
+        // Rows out of time order hold no time.
+        let held = tick.ts_ns.saturating_sub(self.last_ts_ns);
+        self.time_value += self.last_price_fp as i128 * held as i128;
+        self.held_ns += held;
+        self.last_ts_ns = self.last_ts_ns.max(tick.ts_ns);
+        self.last_price_fp = tick.price_fp;
+        self.trades += 1;
+        self.size += tick.size as u64;
+        self.value_fp += tick.price_fp as i128 * tick.size as i128;
+        self.notional += trade_value_fp(tick) as f64 / (PRICE_SCALE * SIZE_SCALE) * contract_size;
+        self.min_price_fp = self.min_price_fp.min(tick.price_fp);
+        self.max_price_fp = self.max_price_fp.max(tick.price_fp);
+    }
+
+    fn finish(self, instrument: String, day: NaiveDate) -> DailyStats {
+        let vwap_fp = if self.size == 0 {
+            self.last_price_fp as f64
+        } else {
+            self.value_fp as f64 / self.size as f64
+        };
+        let twap_fp = if self.held_ns == 0 {
+            self.last_price_fp as f64
+        } else {
+            self.time_value as f64 / self.held_ns as f64
+        };
+        DailyStats {
+            instrument,
+            day: day.format("%Y-%m-%d").to_string(),
+            trades: self.trades,
+            volume: self.size as f64 / SIZE_SCALE,
+            notional: self.notional,
+            vwap: vwap_fp / PRICE_SCALE,
+            twap: twap_fp / PRICE_SCALE,
+            min_price: self.min_price_fp as f64 / PRICE_SCALE,
+            max_price: self.max_price_fp as f64 / PRICE_SCALE,
+            first_ts_ns: self.first_ts_ns,
+            last_ts_ns: self.last_ts_ns,
+        }
+    }
+}
+
+/// Per instrument and UTC day statistics of the trade prints in
+/// `from_ns..to_ns` across `files`, read in the order given; days should
+/// come in time order for the time weights to carry across files.
+/// Instruments are matched by name, each file's `selection` resolved
+/// through its own dictionary; an empty selection takes every instrument.
+///
+/// Zones outside the range or without trades are skipped, as are files
+/// where the selection matches nothing.
+pub fn daily_stats(
+    files: &[PathBuf],
+    selection: &Selection,
+    from_ns: u64,
+    to_ns: u64,
+) -> Result<DailyStatsResult> {
+    let mut totals: BTreeMap<(NaiveDate, String), DayTotals> = BTreeMap::new();
+    let mut result = DailyStatsResult::default();
+    for path in files {
+        result.files += 1;
+        let zones = ZoneMap::refresh(path)?.zones;
+        // Files without a dictionary can only be read whole.
+        let dictionary = InstrumentDictionary::load_for(path).unwrap_or_default();
+        let ids = (!selection.is_empty()).then(|| selection.resolve(&dictionary));
+        if ids.as_ref().is_some_and(BTreeSet::is_empty) {
+            result.zones_pruned += zones.len() as u64;
+            continue;
+        }
+        let candidates: Vec<&Zone> = zones
+            .iter()
+            .filter(|zone| zone.trades > 0 && zone.overlaps(from_ns, to_ns))
+            .collect();
+        result.zones_pruned += (zones.len() - candidates.len()) as u64;
+        result.zones_scanned += candidates.len() as u64;
+
+        let mut reader = TickReader::open(path)?;
+        if let Some(ids) = ids {
+            reader = reader.instruments(ids);
+        }
+        for zone in candidates {
+            reader.seek_rows(zone.first_row..zone.first_row + zone.rows)?;
+            for tick in reader.by_ref() {
+                let tick = tick?;
+                if !event::is_trade(tick.event) || tick.ts_ns < from_ns || tick.ts_ns >= to_ns {
+                    continue;
+                }
+                let day = DateTime::from_timestamp_nanos(tick.ts_ns as i64).date_naive();
+                totals
+                    .entry((day, instrument_name(&dictionary, tick.instrument_id)))
+                    .or_default()
+                    .add(&tick, dictionary.contract_size(tick.instrument_id));
+            }
+        }
+        result.rows_scanned += reader.rows_read();
+    }
+    result.days = totals
+        .into_iter()
+        .map(|((day, instrument), totals)| totals.finish(instrument, day))
+        .collect();
+    Ok(result)
+}
@@ -1,5 +1,320 @@
-use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use futures_util::Stream;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::block::BlockLayout;
+use crate::crypt::{self, Key, OpenBlocks};
+use crate::exit::Failure;
+use crate::file::{encode_encrypted_header, read_header, read_header_async, OptFileMeta};
+use crate::schema::Tick;
 
 pub fn query(_file: &str) -> Result<()> {
     Ok(())
 }
+
+/// Sequential reader over a row-format optstore file.
+pub struct TickReader {
+    inner: BufReader<File>,
+    row: Vec<u8>,
+    instruments: Option<BTreeSet<u32>>,
+    rows_read: u64,
+    /// Index of the next row, counted from the first row after the header.
+    position: u64,
+    end: Option<u64>,
+    header_len: u64,
+    layout: BlockLayout,
+    /// Set for encrypted files.
+    blocks: Option<OpenBlocks>,
+}
+
+/// Byte range of `Tick::instrument_id` within an encoded row.
+const INSTRUMENT_ID: std::ops::Range<usize> = 8..12;
+
+impl TickReader {
+    /// Opens a row file; an encrypted one needs the key configured in the
+    /// environment (see [`Key::from_env`]).
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with_key(path, None)
+    }
+
+    /// Like [`Self::open`], with `key` for an encrypted file instead of the
+    /// environment's. Plaintext files ignore it.
+    pub fn open_with_key(path: &Path, key: Option<Key>) -> Result<Self> {
+        let file = File::open(path).map_err(|err| not_found(err, path))?;
+        let mut inner = BufReader::new(file);
+        let meta = read_header(&mut inner)
+            .with_context(|| format!("reading header of {}", path.display()))?;
+        let blocks = open_blocks(&meta, key, path)?;
+        Ok(Self {
+            inner,
+            row: vec![0u8; Tick::ENCODED_LEN],
+            instruments: None,
+            rows_read: 0,
+            position: 0,
+            end: None,
+            header_len: meta.header_len as u64,
+            layout: meta.layout,
+            blocks,
+        })
+    }
+
+    /// Only yields ticks of these instruments; other rows are skipped
+    /// without being decoded.
+    pub fn instruments(mut self, ids: BTreeSet<u32>) -> Self {
+        self.instruments = Some(ids);
+        self
+    }
+
+    /// Block targets recorded in the file header.
+    pub fn layout(&self) -> BlockLayout {
+        self.layout
+    }
+
+    /// The key an encrypted file is read with; `None` for plaintext.
+    pub fn key(&self) -> Option<&Key> {
+        self.blocks.as_ref().map(OpenBlocks::key)
+    }
+
+    /// Rows read so far, including those skipped by [`Self::instruments`].
+    pub fn rows_read(&self) -> u64 {
+        self.rows_read
+    }
+
+    /// Repositions the reader so it yields only rows `rows.start..rows.end`;
+    /// rows are counted from the first row after the header.
+    pub fn seek_rows(&mut self, rows: Range<u64>) -> Result<()> {
+        // Encrypted files seek to a block when loading it.
+        if self.blocks.is_none() {
+            let offset = self.header_len + rows.start * Tick::ENCODED_LEN as u64;
+            self.inner.seek(SeekFrom::Start(offset))?;
+        }
+        self.position = rows.start;
+        self.end = Some(rows.end);
+        Ok(())
+    }
+
+    /// Ticks with `from_ns <= ts_ns < to_ns`.
+    pub fn range(self, from_ns: u64, to_ns: u64) -> impl Iterator<Item = Result<Tick>> {
+        self.filter(move |tick| match tick {
+            Ok(tick) => tick.ts_ns >= from_ns && tick.ts_ns < to_ns,
+            Err(_) => true,
+        })
+    }
+}
+
+impl Iterator for TickReader {
+    type Item = Result<Tick>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.end.is_some_and(|end| self.position >= end) {
+                return None;
+            }
+            if let Some(blocks) = &mut self.blocks {
+                if let Some((block, offset)) = blocks.needs(self.position) {
+                    let body = match crypt::read_frame(&mut self.inner, offset, blocks.block_rows())
+                    {
+                        Ok(Some(body)) => body,
+                        Ok(None) => return None,
+                        Err(err) => return Some(Err(err)),
+                    };
+                    if let Err(err) = blocks.load(block, &body) {
+                        return Some(Err(err));
+                    }
+                }
+                if !blocks.copy_row(self.position, &mut self.row) {
+                    return None;
+                }
+            } else {
+                let mut filled = 0;
+                while filled < self.row.len() {
+                    match self.inner.read(&mut self.row[filled..]) {
+                        Ok(0) if filled == 0 => return None,
+                        Ok(0) => {
+                            return Some(Err(Failure::Corrupt("truncated tick row".into()).into()))
+                        }
+                        Ok(read) => filled += read,
+                        Err(err) => return Some(Err(err.into())),
+                    }
+                }
+            }
+            self.rows_read += 1;
+            self.position += 1;
+            if let Some(ids) = &self.instruments {
+                let id = u32::from_le_bytes(self.row[INSTRUMENT_ID].try_into().unwrap());
+                if !ids.contains(&id) {
+                    continue;
+                }
+            }
+            return Some(Ok(Tick::decode(&self.row)));
+        }
+    }
+}
+
+/// The block state of an encrypted file; `None` for plaintext.
+fn open_blocks(meta: &OptFileMeta, key: Option<Key>, path: &Path) -> Result<Option<OpenBlocks>> {
+    let Some(key_id) = meta.key_id else {
+        return Ok(None);
+    };
+    let key = crypt::file_key(key, key_id, path)?;
+    Ok(Some(OpenBlocks::new(
+        key,
+        &encode_encrypted_header(meta.layout, key_id),
+        meta.layout.block_rows(),
+    )))
+}
+
+/// A missing file is [`Failure::NoData`].
+fn not_found(err: std::io::Error, path: &Path) -> anyhow::Error {
+    let message = format!("open {}", path.display());
+    if err.kind() == std::io::ErrorKind::NotFound {
+        anyhow::Error::new(err).context(Failure::NoData(message))
+    } else {
+        anyhow::Error::new(err).context(message)
+    }
+}
+
+/// [`TickReader`] on tokio file IO, for serving many concurrent scans
+/// without a blocking thread each. Rows come from [`Self::next_tick`] or,
+/// as a stream, from [`Self::into_stream`].
+pub struct AsyncTickReader {
+    inner: tokio::io::BufReader<tokio::fs::File>,
+    row: Vec<u8>,
+    instruments: Option<BTreeSet<u32>>,
+    rows_read: u64,
+    position: u64,
+    end: Option<u64>,
+    time: Option<Range<u64>>,
+    header_len: u64,
+    layout: BlockLayout,
+    blocks: Option<OpenBlocks>,
+}
+
+impl AsyncTickReader {
+    /// See [`TickReader::open`].
+    pub async fn open(path: &Path) -> Result<Self> {
+        Self::open_with_key(path, None).await
+    }
+
+    /// See [`TickReader::open_with_key`].
+    pub async fn open_with_key(path: &Path, key: Option<Key>) -> Result<Self> {
+        let file = tokio::fs::File::open(path)
+            .await
+            .map_err(|err| not_found(err, path))?;
+        let mut inner = tokio::io::BufReader::new(file);
+        let meta = read_header_async(&mut inner)
+            .await
+            .with_context(|| format!("reading header of {}", path.display()))?;
+        let blocks = open_blocks(&meta, key, path)?;
+        Ok(Self {
+            inner,
+            row: vec![0u8; Tick::ENCODED_LEN],
+            instruments: None,
+            rows_read: 0,
+            position: 0,
+            end: None,
+            time: None,
+            header_len: meta.header_len as u64,
+            layout: meta.layout,
+            blocks,
+        })
+    }
+
+    /// See [`TickReader::instruments`].
+    pub fn instruments(mut self, ids: BTreeSet<u32>) -> Self {
+        self.instruments = Some(ids);
+        self
+    }
+
+    /// Only yields ticks with `from_ns <= ts_ns < to_ns`.
+    pub fn range(mut self, from_ns: u64, to_ns: u64) -> Self {
+        self.time = Some(from_ns..to_ns);
+        self
+    }
+
+    pub fn layout(&self) -> BlockLayout {
+        self.layout
+    }
+
+    pub fn rows_read(&self) -> u64 {
+        self.rows_read
+    }
+
+    /// See [`TickReader::seek_rows`].
+    pub async fn seek_rows(&mut self, rows: Range<u64>) -> Result<()> {
+        if self.blocks.is_none() {
+            let offset = self.header_len + rows.start * Tick::ENCODED_LEN as u64;
+            self.inner.seek(SeekFrom::Start(offset)).await?;
+        }
+        self.position = rows.start;
+        self.end = Some(rows.end);
+        Ok(())
+    }
+
+    /// The next matching tick; `None` at the end of the file or of the
+    /// rows given to [`Self::seek_rows`].
+    pub async fn next_tick(&mut self) -> Result<Option<Tick>> {
+        loop {
+            if self.end.is_some_and(|end| self.position >= end) {
+                return Ok(None);
+            }
+            if let Some(blocks) = &mut self.blocks {
+                if let Some((block, offset)) = blocks.needs(self.position) {
+                    match crypt::read_frame_async(&mut self.inner, offset, blocks.block_rows())
+                        .await?
+                    {
+                        Some(body) => blocks.load(block, &body)?,
+                        None => return Ok(None),
+                    }
+                }
+                if !blocks.copy_row(self.position, &mut self.row) {
+                    return Ok(None);
+                }
+            } else {
+                let mut filled = 0;
+                while filled < self.row.len() {
+                    match self.inner.read(&mut self.row[filled..]).await? {
+                        0 if filled == 0 => return Ok(None),
+                        0 => anyhow::bail!(Failure::Corrupt("truncated tick row".into())),
+                        read => filled += read,
+                    }
+                }
+            }
+            self.rows_read += 1;
+            self.position += 1;
+            if let Some(ids) = &self.instruments {
+                let id = u32::from_le_bytes(self.row[INSTRUMENT_ID].try_into().unwrap());
+                if !ids.contains(&id) {
+                    continue;
+                }
+            }
+            let tick = Tick::decode(&self.row);
+            if self
+                .time
+                .as_ref()
+                .is_some_and(|time| !time.contains(&tick.ts_ns))
+            {
+                continue;
+            }
+            return Ok(Some(tick));
+        }
+    }
+
+    /// The remaining ticks as a stream; it ends after the first error.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Tick>> {
+        futures_util::stream::unfold(Some(self), |reader| async move {
+            let mut reader = reader?;
+            match reader.next_tick().await {
+                Ok(Some(tick)) => Some((Ok(tick), Some(reader))),
+                Ok(None) => None,
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+}
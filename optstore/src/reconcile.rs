@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::prefetch::PrefetchOptions;
+use crate::retrieve::{day_bounds_ms, CacheManager, RetrieveKind, RetrieveSpec};
+use crate::seq::{self, SeqRange};
+
+/// How one cached instrument-day compares with the exchange's numbering.
+/// Deribit gives every trade of an instrument the next `trade_seq`, so a
+/// skipped number is a trade the cache does not hold.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct DayCompleteness {
+    pub symbol: String,
+    /// `YYYY-MM-DD`.
+    pub day: String,
+    pub pages: u64,
+    /// Distinct trades cached for the day.
+    pub stored: u64,
+    /// Trades the exchange numbered between the day's first and last print,
+    /// plus any without a `trade_seq`.
+    pub expected: u64,
+    /// `expected - stored`: numbers skipped inside the day.
+    pub missing: u64,
+    /// Runs of consecutive skipped numbers.
+    pub gaps: u64,
+    /// Numbers skipped between the previous cached day's last print and
+    /// this day's first; days that were never cached show up here.
+    pub missing_before: u64,
+    /// Trades without a `trade_seq`, which continuity cannot check.
+    pub unsequenced: u64,
+    /// The page reaching furthest into the day still reported `has_more`
+    /// before the day ended.
+    pub truncated: bool,
+    /// The skipped numbers behind `missing_before` and `missing`, oldest
+    /// first; what `repair` refetches.
+    pub missing_ranges: Vec<SeqRange>,
+}
+
+impl DayCompleteness {
+    /// Likely missing data.
+    pub fn flagged(&self) -> bool {
+        self.missing > 0 || self.missing_before > 0 || self.truncated
+    }
+}
+
+/// The parts of a cached trades page that reconciliation reads.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Page {
+    pub result: PageResult,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct PageResult {
+    #[serde(default)]
+    pub trades: Vec<SequencedTrade>,
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SequencedTrade {
+    pub timestamp: u64,
+    pub trade_seq: Option<u64>,
+    pub trade_id: Option<String>,
+}
+
+/// Reconciles every cached trades day of `symbols`, oldest day first per
+/// symbol.
+pub fn reconcile(cache: &CacheManager, symbols: &[String]) -> Result<Vec<DayCompleteness>> {
+    let mut days = Vec::new();
+    for symbol in symbols {
+        days.extend(reconcile_symbol(cache, symbol)?);
+    }
+    Ok(days)
+}
+
+pub fn reconcile_symbol(cache: &CacheManager, symbol: &str) -> Result<Vec<DayCompleteness>> {
+    let mut days = Vec::new();
+    let mut last_seq: Option<u64> = None;
+    for day_ymd in cache.cached_days(symbol)? {
+        let spec = RetrieveSpec {
+            symbol: symbol.to_string(),
+            day_ymd,
+            kind: RetrieveKind::Trades,
+        };
+        let Some(manifest) = cache.load_manifest(&spec)? else {
+            continue;
+        };
+        let (start_ms, end_ms) = day_bounds_ms(day_ymd)?;
+        let mut seqs = BTreeSet::new();
+        let mut unsequenced = BTreeSet::new();
+        // (last print in ms, has_more) of the page reaching furthest.
+        let mut furthest: Option<(u64, bool)> = None;
+        let parts = manifest.parts.iter().map(|part| part.part);
+        let pages = cache.read_parts(&spec, parts, PrefetchOptions::default())?;
+        for (part, raw) in manifest.parts.iter().zip(pages) {
+            let raw = raw?;
+            let page: Page = serde_json::from_slice(&raw)
+                .with_context(|| format!("parsing {symbol} {day_ymd} part {}", part.part))?;
+            let last_ms = page.result.trades.last().map_or(0, |trade| trade.timestamp);
+            if furthest.is_none_or(|(furthest_ms, _)| last_ms >= furthest_ms) {
+                furthest = Some((last_ms, page.result.has_more));
+            }
+            for trade in page.result.trades {
+                if trade.timestamp < start_ms || trade.timestamp > end_ms {
+                    continue;
+                }
+                match trade.trade_seq {
+                    Some(seq) => {
+                        seqs.insert(seq);
+                    }
+                    None => {
+                        unsequenced.insert(trade.trade_id.unwrap_or_default());
+                    }
+                }
+            }
+        }
+        let truncated = furthest.is_some_and(|(last_ms, has_more)| has_more && last_ms < end_ms);
+
+        let stored = (seqs.len() + unsequenced.len()) as u64;
+        let inside = seq::gaps(&seqs);
+        let span = match (seqs.first(), seqs.last()) {
+            (Some(first), Some(last)) => last - first + 1,
+            _ => 0,
+        };
+        let before = match (last_seq, seqs.first()) {
+            (Some(previous), Some(first)) => SeqRange::between(previous, *first),
+            _ => None,
+        };
+        if let Some(last) = seqs.last() {
+            last_seq = Some(*last);
+        }
+        let date = format!("{day_ymd:08}");
+        days.push(DayCompleteness {
+            symbol: symbol.to_string(),
+            day: format!("{}-{}-{}", &date[0..4], &date[4..6], &date[6..8]),
+            pages: manifest.parts.len() as u64,
+            stored,
+            expected: span + unsequenced.len() as u64,
+            missing: span - seqs.len() as u64,
+            gaps: inside.len() as u64,
+            missing_before: before.map_or(0, |range| range.len()),
+            unsequenced: unsequenced.len() as u64,
+            truncated,
+            missing_ranges: before.into_iter().chain(inside).collect(),
+        });
+    }
+    Ok(days)
+}
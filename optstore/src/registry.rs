@@ -0,0 +1,264 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::book::{book_path, BookReader, BookWriter};
+use crate::dict::InstrumentDictionary;
+use crate::exit::Failure;
+use crate::quality::QualityReport;
+use crate::reader::TickReader;
+use crate::writer::TickWriter;
+
+/// Instrument ids of one data directory, kept in
+/// `<dir>/instruments.registry.json` and handed out in order of first use,
+/// so no two names ever share one. Files written before the registry use
+/// [`InstrumentDictionary::hash_id`]; the hash is now only a hint, used to
+/// spot names whose old ids collide.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InstrumentRegistry {
+    pub ids: BTreeMap<String, u32>,
+}
+
+impl InstrumentRegistry {
+    pub const FILE_NAME: &'static str = "instruments.registry.json";
+
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(Self::FILE_NAME)
+    }
+
+    /// The registry of `dir`; empty when it has none yet.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = Self::path(dir);
+        match fs::read(&path) {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .with_context(|| Failure::Corrupt(format!("parsing {}", path.display()))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("reading {}", path.display())),
+        }
+    }
+
+    /// Writes the registry through a temporary file, so a crash leaves the
+    /// previous one whole.
+    pub fn store(&self, dir: &Path) -> Result<()> {
+        let path = Self::path(dir);
+        crate::util::ensure_parent_dir(&path)?;
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("replacing {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.ids.get(name).copied()
+    }
+
+    /// The id of `name`, registering it with the next free id if new.
+    pub fn id(&mut self, name: &str) -> u32 {
+        if let Some(id) = self.get(name) {
+            return id;
+        }
+        let id = self.ids.values().max().map_or(1, |max| max + 1);
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    /// Other registered names with the same [`InstrumentDictionary::hash_id`]
+    /// as `name`: rows of these in files written before the registry may be
+    /// mixed up.
+    pub fn hash_collisions(&self, name: &str) -> Vec<&str> {
+        let hash = InstrumentDictionary::hash_id(name);
+        self.ids
+            .keys()
+            .filter(|other| *other != name && InstrumentDictionary::hash_id(other) == hash)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// One day rewritten, or to be rewritten, by [`migrate`].
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct MigratedDay {
+    /// The day's row file path, whether or not only its book stream exists.
+    pub file: String,
+    /// Instruments whose id changed.
+    pub ids_changed: u64,
+    pub rows: u64,
+    pub book_records: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub struct MigrateSummary {
+    pub dry_run: bool,
+    pub days_checked: u64,
+    pub days_rewritten: u64,
+    pub instruments_registered: u64,
+    /// Day files without a dictionary sidecar, whose ids cannot be mapped
+    /// and are left alone.
+    pub unmapped: Vec<String>,
+    pub days: Vec<MigratedDay>,
+}
+
+/// Moves every day under `data` (`YYYY/MM/DD.opt`, its `DD.book` stream
+/// and sidecars) onto the ids of `data`'s registry, registering names new
+/// to it day by day. Days already on registry ids are untouched. A
+/// rewritten file replaces the old one only once it is complete; the
+/// registry is stored before any file is.
+pub fn migrate(data: &Path, dry_run: bool) -> Result<MigrateSummary> {
+    let mut registry = InstrumentRegistry::load(data)?;
+    let registered = registry.ids.len();
+    let mut days = BTreeSet::new();
+    collect_days(data, &mut days)?;
+
+    let mut summary = MigrateSummary {
+        dry_run,
+        ..MigrateSummary::default()
+    };
+    let mut plans = Vec::new();
+    for file in days {
+        summary.days_checked += 1;
+        let dictionary_path = InstrumentDictionary::sidecar_path(&file);
+        if !dictionary_path.exists() {
+            summary.unmapped.push(file.display().to_string());
+            continue;
+        }
+        let dictionary = InstrumentDictionary::load_for(&file)?;
+        let remap: BTreeMap<u32, u32> = dictionary
+            .instruments
+            .iter()
+            .map(|(id, name)| (*id, registry.id(name)))
+            .collect();
+        if remap.iter().any(|(old, new)| old != new) {
+            plans.push((file, dictionary, remap));
+        }
+    }
+    summary.instruments_registered = (registry.ids.len() - registered) as u64;
+    if !dry_run && summary.instruments_registered > 0 {
+        registry.store(data)?;
+    }
+
+    for (file, dictionary, remap) in plans {
+        let mut day = MigratedDay {
+            file: file.display().to_string(),
+            ids_changed: remap.iter().filter(|(old, new)| old != new).count() as u64,
+            rows: 0,
+            book_records: 0,
+        };
+        if !dry_run {
+            day.rows = rewrite_rows(&file, &remap)?;
+            day.book_records = rewrite_book(&book_path(&file), &remap)?;
+            let mut moved = InstrumentDictionary::default();
+            for (id, name) in &dictionary.instruments {
+                moved.instruments.insert(remap[id], name.clone());
+            }
+            for (id, size) in &dictionary.contract_sizes {
+                moved
+                    .contract_sizes
+                    .insert(remap.get(id).copied().unwrap_or(*id), *size);
+            }
+            moved.replace_for(&file)?;
+            if let Ok(mut report) = QualityReport::load_for(&file) {
+                for gap in &mut report.seq_gaps {
+                    gap.instrument_id = remap
+                        .get(&gap.instrument_id)
+                        .copied()
+                        .unwrap_or(gap.instrument_id);
+                }
+                report.store_for(&file)?;
+            }
+            info!(
+                target: "optstore::registry",
+                file = %day.file,
+                ids_changed = day.ids_changed,
+                rows = day.rows,
+                book_records = day.book_records,
+                "migrated day to registry ids"
+            );
+        }
+        summary.days_rewritten += 1;
+        summary.days.push(day);
+    }
+    Ok(summary)
+}
+
+/// Day row file paths under `dir` with a row file, book stream or
+/// dictionary sidecar.
+fn collect_days(dir: &Path, days: &mut BTreeSet<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("list {dir:?}"))? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_days(&path, days)?;
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let day = name
+            .strip_suffix(".opt.instruments.json")
+            .or_else(|| name.strip_suffix(".opt"))
+            .or_else(|| name.strip_suffix(".book"));
+        if let Some(day) = day {
+            days.insert(path.with_file_name(format!("{day}.opt")));
+        }
+    }
+    Ok(())
+}
+
+fn remapped(file: &Path, remap: &BTreeMap<u32, u32>, id: u32) -> Result<u32> {
+    remap.get(&id).copied().with_context(|| {
+        Failure::Corrupt(format!(
+            "{}: instrument id {id} is missing from the dictionary",
+            file.display()
+        ))
+    })
+}
+
+fn temporary(path: &Path) -> PathBuf {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".migrate");
+    PathBuf::from(tmp)
+}
+
+/// Rewrites the row file with the same block layout; 0 rows when absent.
+fn rewrite_rows(file: &Path, remap: &BTreeMap<u32, u32>) -> Result<u64> {
+    if !file.exists() {
+        return Ok(0);
+    }
+    let reader = TickReader::open(file)?;
+    let tmp = temporary(file);
+    let _ = fs::remove_file(&tmp);
+    let mut writer = TickWriter::append_with_key(&tmp, reader.layout(), reader.key().cloned())?;
+    for tick in reader {
+        let mut tick = tick?;
+        tick.instrument_id = remapped(file, remap, tick.instrument_id)?;
+        writer.write(&tick)?;
+    }
+    let rows = writer.finish()?;
+    fs::rename(&tmp, file).with_context(|| format!("replacing {}", file.display()))?;
+    Ok(rows)
+}
+
+/// Rewrites the book stream with the same snapshot interval; 0 records
+/// when absent.
+fn rewrite_book(path: &Path, remap: &BTreeMap<u32, u32>) -> Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let reader = BookReader::open(path)?;
+    let tmp = temporary(path);
+    let _ = fs::remove_file(&tmp);
+    let mut writer = BookWriter::append(&tmp, reader.snapshot_interval())?;
+    for tick in reader {
+        let mut tick = tick?;
+        tick.instrument_id = remapped(path, remap, tick.instrument_id)?;
+        writer.write(&tick)?;
+    }
+    let records = writer.finish()?;
+    fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(records)
+}
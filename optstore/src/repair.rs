@@ -0,0 +1,119 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::DateTime;
+use serde::Serialize;
+use tracing::info;
+
+use crate::exit::Failure;
+use crate::reconcile::{self, Page};
+use crate::retrieve::{
+    CacheManager, CacheManifest, CacheManifestPart, RawChunk, RetrieveKind, RetrieveSpec,
+};
+use crate::seq::SeqRange;
+
+/// Where trades are refetched by their exchange numbers.
+#[async_trait]
+pub trait SeqSource {
+    /// Raw pages holding the trades numbered `range.first..=range.last`.
+    async fn fetch_seq_range(&self, symbol: &str, range: SeqRange) -> Result<Vec<RawChunk>>;
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Clone, Debug, Default, Serialize, PartialEq, Eq)]
+pub struct RepairSummary {
+    pub ranges: u64,
+    /// Trade numbers the ranges cover.
+    pub requested: u64,
+    pub pages: u64,
+    /// One per cached day a fetched page touched.
+    pub parts_written: u64,
+    /// Numbers still missing when the cache is reconciled again.
+    pub still_missing: u64,
+}
+
+/// Refetches the trade numbers [`reconcile`] finds missing for `symbols`
+/// and adds each page as a new part of every day its trades fall on, so
+/// the next reconcile sees them. Only the gaps are downloaded; truncated
+/// tails with no later cached day to bound them need `retrieve --resume`.
+pub async fn repair<S: SeqSource + Sync + ?Sized>(
+    cache: &CacheManager,
+    source: &S,
+    symbols: &[String],
+) -> Result<RepairSummary> {
+    let mut summary = RepairSummary::default();
+    for symbol in symbols {
+        let ranges: Vec<SeqRange> = reconcile::reconcile_symbol(cache, symbol)?
+            .into_iter()
+            .flat_map(|day| day.missing_ranges)
+            .collect();
+        for range in ranges {
+            summary.ranges += 1;
+            summary.requested += range.len();
+            let chunks = source
+                .fetch_seq_range(symbol, range)
+                .await
+                .with_context(|| {
+                    Failure::Source(format!(
+                        "refetching {symbol} trades {}..={}",
+                        range.first, range.last
+                    ))
+                })?;
+            info!(
+                target: "optstore::repair",
+                symbol = %symbol,
+                first = range.first,
+                last = range.last,
+                pages = chunks.len(),
+                "refetched trade range"
+            );
+            for chunk in chunks {
+                summary.pages += 1;
+                summary.parts_written += store_page(cache, source.name(), symbol, &chunk)?;
+            }
+        }
+        summary.still_missing += reconcile::reconcile_symbol(cache, symbol)?
+            .iter()
+            .map(|day| day.missing + day.missing_before)
+            .sum::<u64>();
+    }
+    Ok(summary)
+}
+
+/// Appends `chunk` as the next part of each UTC day its trades fall on,
+/// leaving the manifests' resume tokens alone. Returns the parts written.
+fn store_page(cache: &CacheManager, source: &str, symbol: &str, chunk: &RawChunk) -> Result<u64> {
+    let page: Page = serde_json::from_slice(&chunk.data)
+        .with_context(|| format!("parsing refetched {symbol} page"))?;
+    let days: BTreeSet<u32> = page
+        .result
+        .trades
+        .iter()
+        .filter_map(|trade| DateTime::from_timestamp_millis(trade.timestamp as i64))
+        .filter_map(|at| at.format("%Y%m%d").to_string().parse().ok())
+        .collect();
+    for &day_ymd in &days {
+        let spec = RetrieveSpec {
+            symbol: symbol.to_string(),
+            day_ymd,
+            kind: RetrieveKind::Trades,
+        };
+        let mut manifest = cache
+            .load_manifest(&spec)?
+            .unwrap_or_else(|| CacheManifest::new(source, &spec));
+        let part = manifest.parts.len() as u32;
+        let written = cache.write_chunk(&spec, part, chunk)?;
+        manifest.parts.push(CacheManifestPart {
+            part,
+            start_ns: chunk.start_ns,
+            end_ns: chunk.end_ns,
+            bytes: written.bytes_written,
+            rows: written.rows,
+            resume_token: None,
+            dictionary: written.dictionary,
+        });
+        cache.store_manifest(&spec, &manifest)?;
+    }
+    Ok(days.len() as u64)
+}
@@ -0,0 +1,257 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::dict::InstrumentDictionary;
+use crate::exit::Failure;
+use crate::file::read_header;
+use crate::quality::QualityReport;
+use crate::reader::TickReader;
+
+/// Layout version of [`ReproRecord`].
+pub const REPRO_VERSION: u32 = 1;
+
+/// Size and xxh3-128 of one file named in a [`ReproRecord`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileDigest {
+    /// Sources as given to the command; outputs by file name, next to the
+    /// row file.
+    pub path: String,
+    pub bytes: u64,
+    pub xxh3_128: String,
+}
+
+impl FileDigest {
+    /// Hashes `file`, recorded as `path`.
+    pub fn of(file: &Path, path: String) -> Result<Self> {
+        let mut input = File::open(file).with_context(|| format!("open {}", file.display()))?;
+        let mut hasher = Xxh3::new();
+        let mut buf = vec![0u8; 1 << 16];
+        let mut bytes = 0_u64;
+        loop {
+            let read = input.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            bytes += read as u64;
+        }
+        Ok(Self {
+            path,
+            bytes,
+            xxh3_128: hex(hasher.digest128()),
+        })
+    }
+}
+
+fn hex(hash: u128) -> String {
+    format!("{hash:032x}")
+}
+
+/// The xxh3-128 of a row file's rows, decoded in order: equal for two
+/// files holding the same ticks whatever their encryption.
+pub fn rows_hash(file: &Path) -> Result<(u64, String)> {
+    let mut hasher = Xxh3::new();
+    let mut row = Vec::new();
+    let mut rows = 0_u64;
+    for tick in TickReader::open(file)? {
+        row.clear();
+        tick?.encode(&mut row);
+        hasher.update(&row);
+        rows += 1;
+    }
+    Ok((rows, hex(hasher.digest128())))
+}
+
+/// The `ingest` arguments a row file was written with.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct IngestParameters {
+    pub day: String,
+    pub strict: bool,
+    pub quality_report: bool,
+    pub max_size: f64,
+    pub block_rows: u32,
+    pub block_bytes: u64,
+    /// Registry directory; its ids never change once handed out, so the
+    /// same registry gives the same rows again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    /// Id of the key an encrypted file was written with, in hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
+/// How a row file was made, stored next to it as `<file>.repro.json`:
+/// the hashed sources, the normalizer and arguments that turned them into
+/// rows, and hashes of everything written. Running the same version on the
+/// same sources with the same arguments gives the same outputs, byte for
+/// byte for plaintext files; encrypted ones get fresh nonces, so there only
+/// `rows_xxh3_128` repeats.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReproRecord {
+    pub version: u32,
+    /// `optstore <crate version>`.
+    pub tool: String,
+    /// [`crate::writer::NORMALIZER_VERSION`] of the writing build.
+    pub normalizer_version: u32,
+    pub command: String,
+    pub parameters: IngestParameters,
+    pub sources: Vec<FileDigest>,
+    /// The row file first, then its sidecars.
+    pub outputs: Vec<FileDigest>,
+    pub rows: u64,
+    pub rows_xxh3_128: String,
+}
+
+impl ReproRecord {
+    /// The record of an `ingest` of `sources` that just wrote `file` and
+    /// its sidecars.
+    pub fn ingest(parameters: IngestParameters, sources: &[&Path], file: &Path) -> Result<Self> {
+        let sources = sources
+            .iter()
+            .map(|source| FileDigest::of(source, source.display().to_string()))
+            .collect::<Result<_>>()?;
+        let (rows, rows_xxh3_128) = rows_hash(file)?;
+        Ok(Self {
+            version: REPRO_VERSION,
+            tool: format!("optstore {}", env!("CARGO_PKG_VERSION")),
+            normalizer_version: crate::writer::NORMALIZER_VERSION,
+            command: "ingest".to_string(),
+            outputs: outputs(file, parameters.quality_report)?,
+            parameters,
+            sources,
+            rows,
+            rows_xxh3_128,
+        })
+    }
+
+    pub fn sidecar_path(file: &Path) -> PathBuf {
+        let mut name = file.as_os_str().to_owned();
+        name.push(".repro.json");
+        PathBuf::from(name)
+    }
+
+    /// The record of `file`; [`Failure::NoData`] when it has none.
+    pub fn load_for(file: &Path) -> Result<Self> {
+        let path = Self::sidecar_path(file);
+        let raw = match fs::read(&path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => bail!(Failure::NoData(
+                format!("{} has no reproducibility record", file.display())
+            )),
+            Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+        };
+        serde_json::from_slice(&raw)
+            .with_context(|| Failure::Corrupt(format!("parsing {}", path.display())))
+    }
+
+    pub fn store_for(&self, file: &Path) -> Result<()> {
+        let path = Self::sidecar_path(file);
+        fs::write(&path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Checks `file` and its sidecars, and with `sources` the recorded
+    /// sources too, against the record. A row file whose bytes differ
+    /// still matches when its rows do, as for an encrypted file written
+    /// again from the same sources.
+    pub fn verify(&self, file: &Path, sources: bool) -> Result<Verification> {
+        let mut mismatches = Vec::new();
+        let dir = file.parent().unwrap_or(Path::new(""));
+        for expected in &self.outputs {
+            check(&dir.join(&expected.path), expected, &mut mismatches)?;
+        }
+        let name = file.file_name().map(|name| name.to_string_lossy());
+        if let Some(at) = mismatches
+            .iter()
+            .position(|m| m.found.is_some() && Some(m.path.as_str()) == name.as_deref())
+        {
+            if rows_hash(file)? == (self.rows, self.rows_xxh3_128.clone()) {
+                mismatches.remove(at);
+            }
+        }
+        if sources {
+            for expected in &self.sources {
+                check(Path::new(&expected.path), expected, &mut mismatches)?;
+            }
+        }
+        Ok(Verification {
+            file: file.display().to_string(),
+            outputs_checked: self.outputs.len(),
+            sources_checked: if sources { self.sources.len() } else { 0 },
+            mismatches,
+        })
+    }
+}
+
+/// The row file and the sidecars `ingest` wrote next to it.
+fn outputs(file: &Path, quality_report: bool) -> Result<Vec<FileDigest>> {
+    let mut outputs = Vec::new();
+    let dictionary = InstrumentDictionary::sidecar_path(file);
+    let quality = QualityReport::sidecar_path(file);
+    for (path, written) in [
+        (file, true),
+        (dictionary.as_path(), dictionary.exists()),
+        (quality.as_path(), quality_report),
+    ] {
+        if written {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            outputs.push(FileDigest::of(path, name)?);
+        }
+    }
+    Ok(outputs)
+}
+
+fn check(path: &Path, expected: &FileDigest, mismatches: &mut Vec<Mismatch>) -> Result<()> {
+    let found = match FileDigest::of(path, expected.path.clone()) {
+        Ok(found) => Some(found),
+        Err(_) if !path.exists() => None,
+        Err(err) => return Err(err),
+    };
+    if found.as_ref() != Some(expected) {
+        mismatches.push(Mismatch {
+            path: expected.path.clone(),
+            expected: expected.xxh3_128.clone(),
+            found: found.map(|found| found.xxh3_128),
+        });
+    }
+    Ok(())
+}
+
+/// Result of `verify` on one row file.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Verification {
+    pub file: String,
+    pub outputs_checked: usize,
+    pub sources_checked: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Verification {
+    pub fn matched(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// A recorded file that is gone (`found` absent) or hashes differently.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct Mismatch {
+    pub path: String,
+    pub expected: String,
+    pub found: Option<String>,
+}
+
+/// The key id of `file` in hex, for [`IngestParameters::key_id`].
+pub fn key_id(file: &Path) -> Result<Option<String>> {
+    let mut input = File::open(file).with_context(|| format!("open {}", file.display()))?;
+    Ok(read_header(&mut input)?
+        .key_id
+        .map(|id| format!("{id:016x}")))
+}
@@ -1,14 +1,18 @@
 use std::fs::{self, File};
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use zstd::stream::read::Decoder as ZstdDecoder;
 use zstd::stream::write::Encoder as ZstdEncoder;
 
-use super::{RawChunk, RetrieveSpec};
+use super::{RawChunk, RetrieveKind, RetrieveSpec};
+use crate::exit::Failure;
+use crate::prefetch::{Prefetch, PrefetchOptions};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CacheManager {
     root: PathBuf,
 }
@@ -18,8 +22,21 @@ pub struct CacheWriteResult {
     pub path: PathBuf,
     pub bytes_written: u64,
     pub rows: u64,
+    /// Id of the dictionary the part was compressed with.
+    pub dictionary: Option<u32>,
 }
 
+/// Fewest cached parts a dictionary is trained on.
+pub const DICTIONARY_MIN_PARTS: usize = 4;
+/// Most cached parts sampled for a dictionary, newest first.
+pub const DICTIONARY_MAX_PARTS: usize = 64;
+/// Upper bound on a trained dictionary, zstd's usual default. Smaller
+/// samplings get a dictionary of a tenth of their size.
+const DICTIONARY_MAX_BYTES: usize = 112_640;
+/// Pages are cut into samples of this size; the trainer wants many small
+/// samples rather than a few large ones.
+const DICTIONARY_SAMPLE_BYTES: usize = 4096;
+
 impl CacheManager {
     pub fn new(root: PathBuf) -> Self {
         Self { root }
@@ -33,11 +50,14 @@ impl CacheManager {
     ) -> Result<CacheWriteResult> {
         let dir = self.partition_dir(spec);
         fs::create_dir_all(&dir).with_context(|| format!("create cache dir {dir:?}"))?;
-        let filename = format!("part-{part:04}.jsonl.zst");
-        let path = dir.join(filename);
+        let path = self.part_path(spec, part);
 
+        let dictionary = self.active_dictionary(&spec.symbol)?;
         let file = File::create(&path).with_context(|| format!("create cache file {path:?}"))?;
-        let mut encoder = ZstdEncoder::new(BufWriter::new(file), 3)?;
+        let mut encoder = match &dictionary {
+            Some((_, dict)) => ZstdEncoder::with_dictionary(BufWriter::new(file), 3, dict)?,
+            None => ZstdEncoder::new(BufWriter::new(file), 3)?,
+        };
         encoder.write_all(&chunk.data)?;
         let mut writer = encoder.finish()?;
         writer.flush()?;
@@ -49,6 +69,7 @@ impl CacheManager {
             path,
             bytes_written,
             rows,
+            dictionary: dictionary.map(|(id, _)| id),
         })
     }
 
@@ -78,14 +99,313 @@ impl CacheManager {
         Ok(())
     }
 
+    pub fn part_path(&self, spec: &RetrieveSpec, part: u32) -> PathBuf {
+        self.partition_dir(spec)
+            .join(format!("part-{part:04}.jsonl.zst"))
+    }
+
+    /// Decompressed contents of one cached part: the raw page as fetched.
+    /// Parts compressed with a dictionary name it in their frame header.
+    pub fn read_part(&self, spec: &RetrieveSpec, part: u32) -> Result<Vec<u8>> {
+        let path = self.part_path(spec, part);
+        let compressed = fs::read(&path).with_context(|| format!("open cache file {path:?}"))?;
+        let dictionary = self.frame_dictionary(&spec.symbol, &compressed)?;
+        let mut decoder = ZstdDecoder::with_dictionary(&compressed[..], &dictionary)?;
+        let mut raw = Vec::new();
+        decoder
+            .read_to_end(&mut raw)
+            .with_context(|| Failure::Corrupt(format!("decompress {path:?}")))?;
+        Ok(raw)
+    }
+
+    /// Like [`Self::read_part`], but decompresses as the page is read
+    /// instead of holding the compressed part in memory.
+    pub fn open_part(&self, spec: &RetrieveSpec, part: u32) -> Result<impl Read> {
+        let path = self.part_path(spec, part);
+        let mut file = File::open(&path).with_context(|| format!("open cache file {path:?}"))?;
+        // The dictionary id sits in the frame header, at most 18 bytes.
+        let mut header = Vec::with_capacity(18);
+        (&mut file).take(18).read_to_end(&mut header)?;
+        let dictionary = self.frame_dictionary(&spec.symbol, &header)?;
+        let input = std::io::BufReader::new(std::io::Cursor::new(header).chain(file));
+        Ok(ZstdDecoder::with_dictionary(input, &dictionary)?)
+    }
+
+    /// The dictionary a frame starting with `frame` was compressed with;
+    /// empty for plain parts.
+    fn frame_dictionary(&self, symbol: &str, frame: &[u8]) -> Result<Vec<u8>> {
+        match zstd::zstd_safe::get_dict_id_from_frame(frame) {
+            Some(id) => self.load_dictionary(symbol, id.get()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// [`Self::read_part`] of each of `parts` in order, read and
+    /// decompressed on a prefetch pool ahead of the caller.
+    pub fn read_parts(
+        &self,
+        spec: &RetrieveSpec,
+        parts: impl IntoIterator<Item = u32>,
+        options: PrefetchOptions,
+    ) -> Result<impl Iterator<Item = Result<Vec<u8>>>> {
+        let jobs: Vec<_> = parts
+            .into_iter()
+            .map(|part| {
+                let (cache, spec) = (self.clone(), spec.clone());
+                move || cache.read_part(&spec, part)
+            })
+            .collect();
+        Prefetch::new(jobs, options)
+    }
+
+    /// Trains a zstd dictionary on `symbol`'s cached parts and makes it the
+    /// one later parts are compressed with. Returns its id, or `None` when
+    /// fewer than [`DICTIONARY_MIN_PARTS`] parts are cached or training
+    /// fails; parts already written keep the dictionary they were written
+    /// with.
+    pub fn train_dictionary(&self, symbol: &str) -> Result<Option<u32>> {
+        let mut pages = Vec::new();
+        'days: for day_ymd in self.cached_days(symbol)?.into_iter().rev() {
+            let spec = RetrieveSpec {
+                symbol: symbol.to_string(),
+                day_ymd,
+                kind: RetrieveKind::Trades,
+            };
+            let Some(manifest) = self.load_manifest(&spec)? else {
+                continue;
+            };
+            let wanted = DICTIONARY_MAX_PARTS - pages.len();
+            let parts = manifest
+                .parts
+                .iter()
+                .rev()
+                .take(wanted)
+                .map(|part| part.part);
+            for page in self.read_parts(&spec, parts, PrefetchOptions::default())? {
+                pages.push(page?);
+            }
+            if pages.len() == DICTIONARY_MAX_PARTS {
+                break 'days;
+            }
+        }
+        if pages.len() < DICTIONARY_MIN_PARTS {
+            return Ok(None);
+        }
+        let samples: Vec<&[u8]> = pages
+            .iter()
+            .flat_map(|page| page.chunks(DICTIONARY_SAMPLE_BYTES))
+            .collect();
+        let sampled: usize = pages.iter().map(Vec::len).sum();
+        let max_bytes = (sampled / 10).clamp(1024, DICTIONARY_MAX_BYTES);
+        let dict = match zstd::dict::from_samples(&samples, max_bytes) {
+            Ok(dict) => dict,
+            Err(err) => {
+                warn!(target: "optstore::retrieve", symbol, %err, "dictionary training failed");
+                return Ok(None);
+            }
+        };
+        let Some(id) = zstd::zstd_safe::get_dict_id_from_dict(&dict).map(|id| id.get()) else {
+            warn!(target: "optstore::retrieve", symbol, "trained dictionary has no id");
+            return Ok(None);
+        };
+        let dir = self.dictionary_dir(symbol);
+        fs::create_dir_all(&dir).with_context(|| format!("create dictionary dir {dir:?}"))?;
+        let path = dir.join(format!("{id:08x}.dict"));
+        fs::write(&path, &dict).with_context(|| format!("write dictionary {path:?}"))?;
+        fs::write(dir.join("active"), format!("{id:08x}\n"))?;
+        info!(
+            target: "optstore::retrieve",
+            symbol,
+            id = format!("{id:08x}"),
+            parts = pages.len(),
+            bytes = dict.len(),
+            "trained cache dictionary"
+        );
+        Ok(Some(id))
+    }
+
+    /// Id and bytes of the dictionary new parts of `symbol` are compressed
+    /// with, if one was trained.
+    pub fn active_dictionary(&self, symbol: &str) -> Result<Option<(u32, Vec<u8>)>> {
+        let path = self.dictionary_dir(symbol).join("active");
+        let active = match fs::read_to_string(&path) {
+            Ok(active) => active,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let id = u32::from_str_radix(active.trim(), 16)
+            .with_context(|| format!("parsing dictionary id in {path:?}"))?;
+        Ok(Some((id, self.load_dictionary(symbol, id)?)))
+    }
+
+    fn load_dictionary(&self, symbol: &str, id: u32) -> Result<Vec<u8>> {
+        let path = self.dictionary_dir(symbol).join(format!("{id:08x}.dict"));
+        fs::read(&path).with_context(|| format!("read dictionary {path:?}"))
+    }
+
+    /// Dictionaries are shared by all days and partitions of a symbol.
+    fn dictionary_dir(&self, symbol: &str) -> PathBuf {
+        self.root.join(symbol).join("dictionaries")
+    }
+
+    /// Deletes a cached day: its parts and manifest, then its month and
+    /// year directories if that leaves them empty.
+    pub fn remove_day(&self, spec: &RetrieveSpec) -> Result<()> {
+        let dir = self.partition_dir(spec);
+        fs::remove_dir_all(&dir).with_context(|| format!("remove cache dir {dir:?}"))?;
+        for parent in dir.ancestors().skip(1).take(2) {
+            if fs::remove_dir(parent).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The cached day in `dir`, `<root>/<symbol>[/<partition>]/YYYY/MM/DD`:
+    /// the day and symbol come from its manifest, the partition from the
+    /// path.
+    pub fn open_day(dir: &Path) -> Result<CachedDay> {
+        let path = dir.join("manifest.json");
+        let raw = match fs::read(&path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                bail!(Failure::NoData(format!(
+                    "{} has no manifest.json; expected a retrieve cache day directory",
+                    dir.display()
+                )))
+            }
+            Err(err) => return Err(err).with_context(|| format!("reading {path:?}")),
+        };
+        let manifest: CacheManifest = serde_json::from_slice(&raw)
+            .with_context(|| Failure::Corrupt(format!("parsing {path:?}")))?;
+        let name = |dir: Option<&Path>| {
+            dir.and_then(Path::file_name)
+                .and_then(|name| name.to_str())
+                .map(str::to_string)
+        };
+        let above = dir.ancestors().nth(3);
+        let (kind, symbol_dir) = match name(above)
+            .as_deref()
+            .and_then(RetrieveKind::from_partition)
+        {
+            Some(kind) => (kind, above.and_then(Path::parent)),
+            None => (RetrieveKind::Trades, above),
+        };
+        let spec = RetrieveSpec {
+            symbol: manifest.symbol.clone(),
+            day_ymd: manifest.day_ymd,
+            kind,
+        };
+        let cache = Self::new(
+            symbol_dir
+                .and_then(Path::parent)
+                .unwrap_or(Path::new(""))
+                .to_path_buf(),
+        );
+        if name(symbol_dir).as_deref() != Some(spec.symbol.as_str())
+            || cache.partition_dir(&spec) != dir
+        {
+            bail!(Failure::Usage(format!(
+                "{} holds {} {:08} but is not <cache>/{}/YYYY/MM/DD",
+                dir.display(),
+                spec.symbol,
+                spec.day_ymd,
+                spec.symbol
+            )));
+        }
+        Ok(CachedDay {
+            cache,
+            spec,
+            manifest,
+        })
+    }
+
+    /// Symbols with a directory under the cache root, sorted.
+    pub fn symbols(&self) -> Result<Vec<String>> {
+        let mut symbols = Vec::new();
+        for entry in fs::read_dir(&self.root).with_context(|| format!("list {:?}", self.root))? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                symbols.extend(entry.file_name().to_str().map(str::to_string));
+            }
+        }
+        symbols.sort();
+        Ok(symbols)
+    }
+
+    /// `YYYYMMDD` codes of the days cached for `symbol` in the plain
+    /// trades/quotes layout, sorted; partitions such as `block_trades/` are
+    /// not descended into.
+    pub fn cached_days(&self, symbol: &str) -> Result<Vec<u32>> {
+        let mut days = Vec::new();
+        let dir = self.root.join(symbol);
+        for year in numbered_dirs(&dir, 4)? {
+            for month in numbered_dirs(&dir.join(&year), 2)? {
+                for day in numbered_dirs(&dir.join(&year).join(&month), 2)? {
+                    if dir
+                        .join(&year)
+                        .join(&month)
+                        .join(&day)
+                        .join("manifest.json")
+                        .exists()
+                    {
+                        days.push(format!("{year}{month}{day}").parse()?);
+                    }
+                }
+            }
+        }
+        days.sort_unstable();
+        Ok(days)
+    }
+
     fn partition_dir(&self, spec: &RetrieveSpec) -> PathBuf {
         let date = format!("{:08}", spec.day_ymd);
         let (year, month, day) = (&date[0..4], &date[4..6], &date[6..8]);
-        self.root
-            .join(&spec.symbol)
-            .join(year)
-            .join(month)
-            .join(day)
+        let mut dir = self.root.join(&spec.symbol);
+        if let Some(partition) = spec.kind.partition() {
+            dir.push(partition);
+        }
+        dir.join(year).join(month).join(day)
+    }
+}
+
+/// Subdirectories of `dir` named by exactly `digits` ASCII digits.
+fn numbered_dirs(dir: &Path, digits: usize) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("list {dir:?}"))? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if entry.file_type()?.is_dir()
+            && name.len() == digits
+            && name.bytes().all(|b| b.is_ascii_digit())
+        {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// One cached day opened by [`CacheManager::open_day`].
+#[derive(Clone, Debug)]
+pub struct CachedDay {
+    pub cache: CacheManager,
+    pub spec: RetrieveSpec,
+    pub manifest: CacheManifest,
+}
+
+impl CachedDay {
+    /// The manifest, then the parts it lists in order.
+    pub fn files(&self) -> Vec<PathBuf> {
+        std::iter::once(self.cache.manifest_path(&self.spec))
+            .chain(
+                self.manifest
+                    .parts
+                    .iter()
+                    .map(|part| self.cache.part_path(&self.spec, part.part)),
+            )
+            .collect()
     }
 }
 
@@ -107,6 +427,9 @@ pub struct CacheManifestPart {
     pub bytes: u64,
     pub rows: u64,
     pub resume_token: Option<String>,
+    /// Dictionary id the part was compressed with; absent for plain parts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary: Option<u32>,
 }
 
 impl CacheManifest {
@@ -1,14 +1,14 @@
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
-use governor::{clock::DefaultClock, state::InMemoryState, state::NotKeyed, Quota, RateLimiter};
-use reqwest::Client;
-use reqwest::StatusCode;
-use serde_json::Value;
-use std::num::NonZeroU32;
-use std::sync::Arc;
+use deribit_api::{
+    ApiClient, ApiError, Endpoint, GetInstrument, GetLastTradesByInstrument,
+    GetLastTradesByInstrumentAndTime, Host, RpcErrorClass,
+};
 use tracing::info;
 
-use super::{RawChunk, RetrieveKind, RetrieveOptions, RetrieveSpec, Source};
+use super::{RawChunk, RetrieveOptions, RetrieveSpec, Source};
+use crate::repair::SeqSource;
+use crate::seq::SeqRange;
 
 #[derive(Clone, Debug)]
 pub enum DeribitKind {
@@ -17,64 +17,57 @@ pub enum DeribitKind {
     Both,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct DeribitSource {
-    client: Client,
-    limiter: Arc<RateLimiter<NotKeyed, InMemoryState, DefaultClock>>,
+    client: ApiClient,
+}
+
+fn is_rejected(err: &ApiError) -> bool {
+    err.rpc()
+        .is_some_and(|rpc| rpc.class == RpcErrorClass::Validation)
 }
 
 impl DeribitSource {
     pub fn new(rate: u32) -> Self {
-        let client = Client::builder()
-            .user_agent("optstore/0.1")
-            .build()
-            .expect("reqwest client");
-        let per_sec = NonZeroU32::new(rate.max(1)).unwrap();
-        let limiter = RateLimiter::direct(Quota::per_second(per_sec));
-        Self {
-            client,
-            limiter: Arc::new(limiter),
-        }
+        let client = ApiClient::new(Host::Production)
+            .with_user_agent("optstore/0.1")
+            .with_rate_limit(rate);
+        Self { client }
     }
 
-    async fn ensure_instrument(&self, symbol: &str) -> Result<()> {
-        self.limiter.until_ready().await;
-        let response = self
-            .client
-            .get("https://www.deribit.com/api/v2/public/get_instrument")
-            .query(&[("instrument_name", symbol)])
-            .send()
-            .await
-            .context("verify instrument")?;
-
-        let status = response.status();
-        let body = response.bytes().await?;
-
-        if !status.is_success() {
-            if let Ok(err) = serde_json::from_slice::<Value>(&body) {
-                if let Some(message) = err
-                    .get("error")
-                    .and_then(|e| e.get("message"))
-                    .and_then(|m| m.as_str())
-                {
-                    bail!("Deribit unknown instrument '{}': {}", symbol, message);
-                }
-            }
-            bail!("Deribit unknown instrument '{}': status {}", symbol, status);
+    /// Finds the host that lists `symbol`: production for live instruments,
+    /// the history host for expired ones. Both share one rate limit.
+    async fn ensure_instrument(&self, symbol: &str) -> Result<ApiClient> {
+        let request = GetInstrument {
+            instrument_name: symbol.to_string(),
+        };
+        match self.client.request(&request).await {
+            Ok(_) => return Ok(self.client.clone()),
+            Err(err) if !is_rejected(&err) => return Err(err).context("verify instrument"),
+            Err(_) => {}
         }
 
-        Ok(())
+        let history = self.client.clone().with_host(Host::History);
+        match history.request(&request).await {
+            Ok(_) => Ok(history),
+            Err(err) => match err.rpc() {
+                Some(rpc) => bail!("Deribit unknown instrument '{}': {}", symbol, rpc.message),
+                None => Err(err).context("verify instrument"),
+            },
+        }
     }
 }
 
 #[async_trait]
 impl Source for DeribitSource {
     async fn fetch(&self, spec: &RetrieveSpec, options: &RetrieveOptions) -> Result<Vec<RawChunk>> {
-        if !matches!(spec.kind, RetrieveKind::Trades | RetrieveKind::Both) {
+        // Block trades and liquidations are flagged prints of the trades
+        // feed; the normalizer keeps the requested ones.
+        if !spec.kind.reads_trades() {
             bail!("Deribit quotes retrieval is not implemented yet");
         }
 
-        self.ensure_instrument(&spec.symbol).await?;
+        let client = self.ensure_instrument(&spec.symbol).await?;
 
         let mut resume_token = options.resume_from.clone();
         let mut chunks = Vec::new();
@@ -87,8 +80,6 @@ impl Source for DeribitSource {
                 }
             }
 
-            self.limiter.until_ready().await;
-
             let start_ms = resume_token
                 .as_ref()
                 .and_then(|s| s.parse::<u64>().ok())
@@ -98,71 +89,32 @@ impl Source for DeribitSource {
                 break;
             }
 
-            let start_str = start_ms.to_string();
-            let end_str = options.end_ms.to_string();
-            let response = self
-                .client
-                .get("https://www.deribit.com/api/v2/public/get_last_trades_by_instrument_and_time")
-                .query(&[
-                    ("instrument_name", spec.symbol.as_str()),
-                    ("count", "1000"),
-                    ("include_oldest", "true"),
-                    ("start_timestamp", start_str.as_str()),
-                    ("end_timestamp", end_str.as_str()),
-                ])
-                .send()
-                .await
-                .context("deribit request")?;
-
-            let status = response.status();
-            let body_bytes = response.bytes().await?;
-            if !status.is_success() {
-                if status == StatusCode::BAD_REQUEST {
-                    if let Ok(err) = serde_json::from_slice::<Value>(&body_bytes) {
-                        if let Some(message) = err
-                            .get("error")
-                            .and_then(|e| e.get("message"))
-                            .and_then(|m| m.as_str())
-                        {
-                            bail!("Deribit rejected '{}': {}", spec.symbol, message);
-                        }
+            let request = GetLastTradesByInstrumentAndTime {
+                instrument_name: spec.symbol.clone(),
+                start_timestamp: start_ms,
+                end_timestamp: Some(options.end_ms),
+                count: Some(1000),
+                include_oldest: Some(true),
+            };
+            // The raw body is stored as sent; the normalizer parses it later.
+            let body_bytes = match client.request_raw(&request).await {
+                Ok(body) => body,
+                Err(err) => match err.rpc() {
+                    Some(rpc) if is_rejected(&err) => {
+                        bail!("Deribit rejected '{}': {}", spec.symbol, rpc.message)
                     }
-                    bail!(
-                        "Deribit rejected instrument_name={}; ensure the instrument exists and is not delisted",
-                        spec.symbol
-                    );
-                }
-                bail!("deribit status {}", status);
-            }
+                    _ => return Err(err).context("deribit request"),
+                },
+            };
+            let trades_page = GetLastTradesByInstrumentAndTime::decode(&body_bytes)?;
+            let trades = &trades_page.trades;
 
-            let json: Value = serde_json::from_slice(&body_bytes)?;
-
-            let trades = json
-                .get("result")
-                .and_then(|r| r.get("trades"))
-                .and_then(|t| t.as_array())
-                .cloned()
-                .unwrap_or_default();
-
-            let mut first_ts: Option<u64> = None;
-            let mut last_ts: Option<u64> = None;
-            for trade in &trades {
-                if let Some(ts) = trade.get("timestamp").and_then(|v| v.as_u64()) {
-                    if first_ts.is_none() {
-                        first_ts = Some(ts);
-                    }
-                    last_ts = Some(ts);
-                }
-            }
+            let first_ts = trades.first().map(|trade| trade.timestamp);
+            let last_ts = trades.last().map(|trade| trade.timestamp);
 
             let start_ns = first_ts.map(|ts| ts * 1_000_000).unwrap_or(0);
             let end_ns = last_ts.map(|ts| ts * 1_000_000).unwrap_or(start_ns);
-            let next_resume = json
-                .get("result")
-                .and_then(|r| r.get("continuation"))
-                .and_then(|c| c.as_str())
-                .map(|s| s.to_string())
-                .or_else(|| last_ts.map(|ts| (ts + 1).to_string()));
+            let next_resume = last_ts.map(|ts| (ts + 1).to_string());
 
             info!(
                 target: "optstore::retrieve",
@@ -174,7 +126,7 @@ impl Source for DeribitSource {
             );
 
             let chunk = RawChunk {
-                data: body_bytes.clone(),
+                data: body_bytes,
                 start_ns,
                 end_ns,
                 resume: next_resume.clone(),
@@ -191,13 +143,7 @@ impl Source for DeribitSource {
                 }
             }
 
-            let has_more = json
-                .get("result")
-                .and_then(|r| r.get("has_more"))
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-
-            if !has_more {
+            if !trades_page.has_more {
                 break;
             }
 
@@ -217,3 +163,51 @@ impl Source for DeribitSource {
         "deribit"
     }
 }
+
+#[async_trait]
+impl SeqSource for DeribitSource {
+    async fn fetch_seq_range(&self, symbol: &str, range: SeqRange) -> Result<Vec<RawChunk>> {
+        let client = self.ensure_instrument(symbol).await?;
+        let mut chunks = Vec::new();
+        let mut start_seq = range.first;
+        while start_seq <= range.last {
+            let request = GetLastTradesByInstrument {
+                instrument_name: symbol.to_string(),
+                start_seq: Some(start_seq),
+                end_seq: Some(range.last),
+                count: Some(1000),
+                sorting: Some("asc".to_string()),
+            };
+            let body_bytes = client
+                .request_raw(&request)
+                .await
+                .context("deribit request")?;
+            let trades = GetLastTradesByInstrument::decode(&body_bytes)?.trades;
+            let last_seq = trades.iter().filter_map(|trade| trade.trade_seq).max();
+            info!(
+                target: "optstore::repair",
+                symbol,
+                start_seq,
+                trades = trades.len(),
+                "fetched deribit seq page"
+            );
+            chunks.push(RawChunk {
+                data: body_bytes,
+                start_ns: trades
+                    .first()
+                    .map_or(0, |trade| trade.timestamp * 1_000_000),
+                end_ns: trades.last().map_or(0, |trade| trade.timestamp * 1_000_000),
+                resume: None,
+            });
+            match last_seq {
+                Some(last) if last >= start_seq => start_seq = last + 1,
+                _ => break,
+            }
+        }
+        Ok(chunks)
+    }
+
+    fn name(&self) -> &'static str {
+        "deribit"
+    }
+}
@@ -1,10 +1,14 @@
+use crate::dict::InstrumentDictionary;
+use crate::exit::Failure;
 use crate::progress::{Progress, ProgressKind, ProgressUpdate};
+use crate::registry::InstrumentRegistry;
+use crate::schema::event;
+use anyhow::{bail, Context};
 use clap::{Parser, ValueEnum};
 use fxhash::FxHashSet;
 use std::path::PathBuf;
 use std::time::Instant;
 use tracing::warn;
-use xxhash_rust::xxh3::xxh3_64;
 
 pub mod cache;
 pub mod deribit;
@@ -12,7 +16,10 @@ pub mod normalize;
 
 use async_trait::async_trait;
 use bytes::Bytes;
-pub use cache::{CacheManager, CacheManifest, CacheManifestPart, CacheWriteResult};
+pub use cache::{
+    CacheManager, CacheManifest, CacheManifestPart, CacheWriteResult, CachedDay,
+    DICTIONARY_MIN_PARTS,
+};
 pub use deribit::{DeribitKind, DeribitSource};
 pub use normalize::{DeribitNormalizer, Normalizer};
 
@@ -29,6 +36,46 @@ pub enum RetrieveKind {
     Trades,
     Quotes,
     Both,
+    /// Only the block-trade prints of the trades feed.
+    BlockTrades,
+    /// Only the liquidation prints of the trades feed.
+    Liquidations,
+}
+
+impl RetrieveKind {
+    /// Whether a normalized tick with this `Tick::event` code belongs to the kind.
+    pub fn keeps(&self, code: u8) -> bool {
+        match self {
+            RetrieveKind::Trades | RetrieveKind::Both => event::is_trade(code),
+            RetrieveKind::Quotes => code == event::BOOK,
+            RetrieveKind::BlockTrades => code == event::BLOCK_TRADE,
+            RetrieveKind::Liquidations => code == event::LIQUIDATION,
+        }
+    }
+
+    /// Cache subdirectory under the symbol; trades and quotes keep the
+    /// original `<symbol>/YYYY/MM/DD` layout.
+    pub fn partition(&self) -> Option<&'static str> {
+        match self {
+            RetrieveKind::Trades | RetrieveKind::Quotes | RetrieveKind::Both => None,
+            RetrieveKind::BlockTrades => Some("block_trades"),
+            RetrieveKind::Liquidations => Some("liquidations"),
+        }
+    }
+
+    /// The kind cached under partition `name`; see [`Self::partition`].
+    pub fn from_partition(name: &str) -> Option<Self> {
+        match name {
+            "block_trades" => Some(RetrieveKind::BlockTrades),
+            "liquidations" => Some(RetrieveKind::Liquidations),
+            _ => None,
+        }
+    }
+
+    /// Served from the trades endpoint.
+    pub fn reads_trades(&self) -> bool {
+        !matches!(self, RetrieveKind::Quotes)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -79,9 +126,16 @@ pub struct RetrieveCommand {
     /// Rate limit (requests per second)
     #[arg(long = "rate", default_value_t = 4u32)]
     pub rate: u32,
-    /// Fetch trades, quotes or both
+    /// Fetch trades, quotes or both, or only the block-trade or liquidation prints
     #[arg(long = "kind", default_value = "trades")]
     pub kind: RetrieveKindArg,
+    /// Train a zstd dictionary on the symbol's cached parts, if it has none
+    /// yet, and compress the new parts with it
+    #[arg(long = "train-dict", default_value_t = false)]
+    pub train_dict: bool,
+    /// Exit with the no-data code (2) when no rows were retrieved
+    #[arg(long = "fail-on-empty", default_value_t = false)]
+    pub fail_on_empty: bool,
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq, ValueEnum)]
@@ -89,6 +143,8 @@ pub enum RetrieveKindArg {
     Trades,
     Quotes,
     Both,
+    BlockTrades,
+    Liquidations,
 }
 
 impl From<RetrieveKindArg> for RetrieveKind {
@@ -97,6 +153,8 @@ impl From<RetrieveKindArg> for RetrieveKind {
             RetrieveKindArg::Trades => RetrieveKind::Trades,
             RetrieveKindArg::Quotes => RetrieveKind::Quotes,
             RetrieveKindArg::Both => RetrieveKind::Both,
+            RetrieveKindArg::BlockTrades => RetrieveKind::BlockTrades,
+            RetrieveKindArg::Liquidations => RetrieveKind::Liquidations,
         }
     }
 }
@@ -116,6 +174,36 @@ pub fn run(cmd: RetrieveCommand, quiet: bool, json: bool) -> anyhow::Result<()>
     });
 
     let cache = CacheManager::new(cmd.out.clone());
+    let mut registry = InstrumentRegistry::load(&cmd.out)?;
+    let instrument_id = registry.id(&spec.symbol);
+    registry.store(&cmd.out)?;
+    for other in registry.hash_collisions(&spec.symbol) {
+        warn!(
+            target: "optstore::retrieve",
+            symbol = %spec.symbol,
+            %other,
+            "instruments share a hashed id; migrate files written before the registry"
+        );
+        progress.update(
+            &token,
+            ProgressUpdate::Message {
+                message: format!(
+                    "{} and {other} share hashed id {:08x}; run `optstore migrate-ids` on older day files",
+                    spec.symbol,
+                    InstrumentDictionary::hash_id(other)
+                ),
+            },
+        );
+    }
+    if cmd.train_dict && cache.active_dictionary(&spec.symbol)?.is_none() {
+        let message = match cache.train_dictionary(&spec.symbol)? {
+            Some(id) => format!("trained dictionary {id:08x}"),
+            None => format!(
+                "dictionary needs at least {DICTIONARY_MIN_PARTS} cached parts; compressing without one"
+            ),
+        };
+        progress.update(&token, ProgressUpdate::Message { message });
+    }
 
     let mut manifest = if cmd.resume {
         cache
@@ -149,7 +237,10 @@ pub fn run(cmd: RetrieveCommand, quiet: bool, json: bool) -> anyhow::Result<()>
             let rt = tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build()?;
-            rt.block_on(source.fetch(&spec, &options))?
+            rt.block_on(source.fetch(&spec, &options))
+                .with_context(|| {
+                    Failure::Source(format!("retrieving {} from deribit", spec.symbol))
+                })?
         }
         other => {
             warn!(target: "optstore::retrieve", ?other, "unknown source");
@@ -158,12 +249,14 @@ pub fn run(cmd: RetrieveCommand, quiet: bool, json: bool) -> anyhow::Result<()>
     };
 
     let normalizer = DeribitNormalizer {
-        instrument_id: instrument_id_from_symbol(&spec.symbol),
+        instrument_id,
+        kind: spec.kind.clone(),
     };
     let mut dedup = FxHashSet::default();
 
-    let mut part_index = manifest.parts.len() as u32;
-    for chunk in chunks.into_iter() {
+    let first_part = manifest.parts.len() as u32;
+    let mut rows_retrieved = 0;
+    for (part_index, chunk) in (first_part..).zip(chunks) {
         let result = cache.write_chunk(&spec, part_index, &chunk)?;
 
         let unique_summary = normalize_and_dedup(
@@ -175,6 +268,7 @@ pub fn run(cmd: RetrieveCommand, quiet: bool, json: bool) -> anyhow::Result<()>
         )?;
 
         total_rows += result.rows;
+        rows_retrieved += result.rows;
         total_bytes += result.bytes_written;
 
         let manifest_part = CacheManifestPart {
@@ -184,6 +278,7 @@ pub fn run(cmd: RetrieveCommand, quiet: bool, json: bool) -> anyhow::Result<()>
             bytes: result.bytes_written,
             rows: result.rows,
             resume_token: chunk.resume.clone(),
+            dictionary: result.dictionary,
         };
         manifest.append_part(manifest_part);
 
@@ -224,8 +319,6 @@ pub fn run(cmd: RetrieveCommand, quiet: bool, json: bool) -> anyhow::Result<()>
                 bytes: total_bytes,
             },
         );
-
-        part_index += 1;
     }
 
     cache.store_manifest(&spec, &manifest)?;
@@ -236,14 +329,20 @@ pub fn run(cmd: RetrieveCommand, quiet: bool, json: bool) -> anyhow::Result<()>
             message: "retrieve complete".to_string(),
         }),
     );
+    if cmd.fail_on_empty && rows_retrieved == 0 {
+        bail!(Failure::NoData(format!(
+            "no rows retrieved for {} on {}",
+            spec.symbol, cmd.day
+        )));
+    }
     Ok(())
 }
 
-fn day_bounds_ms(day: u32) -> anyhow::Result<(u64, u64)> {
+pub(crate) fn day_bounds_ms(day: u32) -> anyhow::Result<(u64, u64)> {
     use chrono::{Duration, NaiveDate};
     let year = (day / 10_000) as i32;
-    let month = ((day / 100) % 100) as u32;
-    let day_of_month = (day % 100) as u32;
+    let month = (day / 100) % 100;
+    let day_of_month = day % 100;
     let date = NaiveDate::from_ymd_opt(year, month, day_of_month)
         .ok_or_else(|| anyhow::anyhow!("invalid day code {day}"))?;
     let start = date.and_hms_opt(0, 0, 0).unwrap();
@@ -260,10 +359,6 @@ fn parse_day(day: &str) -> anyhow::Result<u32> {
         .map_err(|err| anyhow::anyhow!("invalid day {day}: {err}"))
 }
 
-fn instrument_id_from_symbol(symbol: &str) -> u32 {
-    (xxh3_64(symbol.as_bytes()) & 0xFFFF_FFFF) as u32
-}
-
 fn normalize_and_dedup(
     normalizer: &DeribitNormalizer,
     chunk: &RawChunk,
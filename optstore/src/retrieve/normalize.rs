@@ -1,13 +1,17 @@
+use std::io::Read;
+
 use anyhow::Result;
 use bytes::Bytes;
 use serde::Deserialize;
 
-use super::RawChunk;
-use crate::schema::Tick;
+use super::{RawChunk, RetrieveKind};
+use crate::schema::{event, Tick};
 
 #[derive(Clone)]
 pub struct DeribitNormalizer {
     pub instrument_id: u32,
+    /// Prints of other kinds are dropped; see [`RetrieveKind::keeps`].
+    pub kind: RetrieveKind,
 }
 
 #[derive(Debug, Deserialize)]
@@ -15,6 +19,23 @@ struct TradeRecord {
     price: Option<f64>,
     amount: Option<f64>,
     timestamp: Option<u64>,
+    trade_seq: Option<u64>,
+    block_trade_id: Option<String>,
+    /// `M`, `T` or `MT`: which side of the print was liquidated.
+    liquidation: Option<String>,
+}
+
+impl TradeRecord {
+    /// A liquidation inside a block trade still counts as a liquidation.
+    fn event(&self) -> u8 {
+        if self.liquidation.is_some() {
+            event::LIQUIDATION
+        } else if self.block_trade_id.is_some() {
+            event::BLOCK_TRADE
+        } else {
+            event::TRADE
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,17 +55,32 @@ pub trait Normalizer {
 impl Normalizer for DeribitNormalizer {
     fn to_ticks(&self, raw: RawChunk) -> Result<Vec<Tick>> {
         let bytes: Bytes = raw.data;
-        let response: TradeResponse = serde_json::from_slice(&bytes)?;
+        Ok(self.page_ticks(serde_json::from_slice(&bytes)?))
+    }
+}
+
+impl DeribitNormalizer {
+    /// [`Normalizer::to_ticks`] of a page parsed as it is read, such as a
+    /// cached part being decompressed.
+    pub fn read_ticks(&self, page: impl Read) -> Result<Vec<Tick>> {
+        Ok(self.page_ticks(serde_json::from_reader(page)?))
+    }
+
+    fn page_ticks(&self, response: TradeResponse) -> Vec<Tick> {
         let mut ticks = Vec::new();
         if let Some(trades) = response.result.trades {
             for trade in trades {
+                let event = trade.event();
+                if !self.kind.keeps(event) {
+                    continue;
+                }
                 if let (Some(price), Some(amount), Some(ts)) =
                     (trade.price, trade.amount, trade.timestamp)
                 {
-                    let tick = Tick {
+                    let mut tick = Tick {
                         ts_ns: ts * 1_000_000,
                         instrument_id: self.instrument_id,
-                        event: 1,
+                        event,
                         price_fp: (price * 1_000_000.0).round() as i64,
                         size: (amount.abs() * 1_000.0) as u32,
                         bid_px_fp: [0; 4],
@@ -53,10 +89,13 @@ impl Normalizer for DeribitNormalizer {
                         ask_sz: [0; 4],
                         flags: 0,
                     };
+                    if let Some(seq) = trade.trade_seq {
+                        tick.set_trade_seq(seq);
+                    }
                     ticks.push(tick);
                 }
             }
         }
-        Ok(ticks)
+        ticks
     }
 }
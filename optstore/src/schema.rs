@@ -1,5 +1,47 @@
 use serde::{Deserialize, Serialize};
 
+/// `Tick::event` codes.
+pub mod event {
+    /// Trade print: `price_fp`/`size` are the fill and `bid_px_fp[0]`, unused
+    /// by trades, holds the exchange's `trade_seq` (0 when unknown; see
+    /// [`super::Tick::trade_seq`]).
+    pub const TRADE: u8 = 1;
+    /// Top-of-book snapshot: up to four bid/ask levels, `price_fp` carries
+    /// the underlying index price at the time of the snapshot.
+    pub const BOOK: u8 = 2;
+    /// Trade print of a privately negotiated block trade (Deribit sets
+    /// `block_trade_id`); fields as for [`TRADE`].
+    pub const BLOCK_TRADE: u8 = 3;
+    /// Trade print in which a position was liquidated (Deribit sets
+    /// `liquidation`); fields as for [`TRADE`].
+    pub const LIQUIDATION: u8 = 4;
+
+    /// Trade prints of every kind, as opposed to book snapshots.
+    pub fn is_trade(event: u8) -> bool {
+        matches!(event, TRADE | BLOCK_TRADE | LIQUIDATION)
+    }
+
+    /// Lower-case name of an event code, as printed by queries.
+    pub fn name(event: u8) -> &'static str {
+        match event {
+            TRADE => "trade",
+            BOOK => "book",
+            BLOCK_TRADE => "block_trade",
+            LIQUIDATION => "liquidation",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Fixed-point scale for `*_px_fp` and `price_fp`.
+pub const PRICE_SCALE: f64 = 1_000_000.0;
+/// Decimal places implied by [`PRICE_SCALE`].
+pub const PRICE_DECIMALS: u32 = 6;
+/// Fixed-point scale for sizes (contracts × 1000).
+pub const SIZE_SCALE: f64 = 1_000.0;
+/// Decimal places implied by [`SIZE_SCALE`].
+pub const SIZE_DECIMALS: u32 = 3;
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Tick {
     pub ts_ns: u64,
@@ -15,6 +57,21 @@ pub struct Tick {
 }
 
 impl Tick {
+    /// Bytes per row in the fixed-width row format.
+    pub const ENCODED_LEN: usize = 8 + 4 + 1 + 8 + 4 + 32 + 32 + 16 + 16 + 2;
+
+    /// Deribit's per-instrument trade number of a trade print of any kind;
+    /// `None` for book rows and prints stored without one.
+    pub fn trade_seq(&self) -> Option<u64> {
+        let seq = self.bid_px_fp[0];
+        (event::is_trade(self.event) && seq > 0).then_some(seq as u64)
+    }
+
+    /// Stores `seq` in the trade row's `bid_px_fp[0]`.
+    pub fn set_trade_seq(&mut self, seq: u64) {
+        self.bid_px_fp[0] = seq.min(i64::MAX as u64) as i64;
+    }
+
     pub fn key(&self) -> (u32, u64, i64, u32, u8) {
         (
             self.instrument_id,
@@ -24,4 +81,57 @@ impl Tick {
             self.event,
         )
     }
+
+    /// Appends the little-endian row encoding to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.ts_ns.to_le_bytes());
+        out.extend_from_slice(&self.instrument_id.to_le_bytes());
+        out.push(self.event);
+        out.extend_from_slice(&self.price_fp.to_le_bytes());
+        out.extend_from_slice(&self.size.to_le_bytes());
+        for px in self.bid_px_fp.iter().chain(&self.ask_px_fp) {
+            out.extend_from_slice(&px.to_le_bytes());
+        }
+        for sz in self.bid_sz.iter().chain(&self.ask_sz) {
+            out.extend_from_slice(&sz.to_le_bytes());
+        }
+        out.extend_from_slice(&self.flags.to_le_bytes());
+    }
+
+    /// Decodes one row; `bytes` must be exactly [`Tick::ENCODED_LEN`] long.
+    pub fn decode(bytes: &[u8]) -> Tick {
+        assert_eq!(bytes.len(), Self::ENCODED_LEN, "tick row length");
+        let mut pos = 0;
+        let mut take = |len: usize| {
+            let slice = &bytes[pos..pos + len];
+            pos += len;
+            slice
+        };
+        let ts_ns = u64::from_le_bytes(take(8).try_into().unwrap());
+        let instrument_id = u32::from_le_bytes(take(4).try_into().unwrap());
+        let event = take(1)[0];
+        let price_fp = i64::from_le_bytes(take(8).try_into().unwrap());
+        let size = u32::from_le_bytes(take(4).try_into().unwrap());
+        let mut levels_px = [0i64; 8];
+        for px in levels_px.iter_mut() {
+            *px = i64::from_le_bytes(take(8).try_into().unwrap());
+        }
+        let mut levels_sz = [0u32; 8];
+        for sz in levels_sz.iter_mut() {
+            *sz = u32::from_le_bytes(take(4).try_into().unwrap());
+        }
+        let flags = u16::from_le_bytes(take(2).try_into().unwrap());
+        Tick {
+            ts_ns,
+            instrument_id,
+            event,
+            price_fp,
+            size,
+            bid_px_fp: levels_px[..4].try_into().unwrap(),
+            ask_px_fp: levels_px[4..].try_into().unwrap(),
+            bid_sz: levels_sz[..4].try_into().unwrap(),
+            ask_sz: levels_sz[4..].try_into().unwrap(),
+            flags,
+        }
+    }
 }
@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::dict::InstrumentDictionary;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+impl fmt::Display for OptionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OptionKind::Call => "call",
+            OptionKind::Put => "put",
+        })
+    }
+}
+
+/// Option terms decoded from a Deribit instrument name such as
+/// `BTC-28MAR25-60000-C`, `SOL_USDC-7JUN25-150-P` or `XRP_USDC-7JUN25-0d625-C`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OptionTerms {
+    /// Base currency; a linear book's `_USDC` suffix is dropped.
+    pub currency: String,
+    pub expiry: NaiveDate,
+    pub strike: f64,
+    pub kind: OptionKind,
+}
+
+impl OptionTerms {
+    /// `None` for futures, perpetuals, combos and anything unparseable.
+    pub fn parse(name: &str) -> Option<Self> {
+        let mut parts = name.split('-');
+        let underlying = parts.next()?;
+        let expiry = parts.next()?;
+        let strike = parts.next()?;
+        let kind = match parts.next()? {
+            "C" => OptionKind::Call,
+            "P" => OptionKind::Put,
+            _ => return None,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        let currency = underlying.split('_').next()?;
+        if currency.is_empty() {
+            return None;
+        }
+        Some(Self {
+            currency: currency.to_string(),
+            expiry: NaiveDate::parse_from_str(expiry, "%d%b%y").ok()?,
+            strike: strike.replace('d', ".").parse().ok()?,
+            kind,
+        })
+    }
+}
+
+/// Query predicates on instrument names and option terms. Resolved against
+/// a file's [`InstrumentDictionary`] into the instrument ids to read, so
+/// rows of other instruments are skipped before they are decoded.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Selection {
+    pub instrument: Option<String>,
+    pub currency: Option<String>,
+    pub expiry: Option<NaiveDate>,
+    pub strike: Option<f64>,
+    pub kind: Option<OptionKind>,
+}
+
+impl Selection {
+    /// No predicates: every instrument matches.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn filters_terms(&self) -> bool {
+        self.currency.is_some()
+            || self.expiry.is_some()
+            || self.strike.is_some()
+            || self.kind.is_some()
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        if self
+            .instrument
+            .as_deref()
+            .is_some_and(|wanted| wanted != name)
+        {
+            return false;
+        }
+        if !self.filters_terms() {
+            return true;
+        }
+        let Some(terms) = OptionTerms::parse(name) else {
+            return false;
+        };
+        self.currency
+            .as_deref()
+            .is_none_or(|currency| currency.eq_ignore_ascii_case(&terms.currency))
+            && self.expiry.is_none_or(|expiry| expiry == terms.expiry)
+            && self
+                .strike
+                .is_none_or(|strike| (strike - terms.strike).abs() < 1e-9)
+            && self.kind.is_none_or(|kind| kind == terms.kind)
+    }
+
+    /// Ids of the dictionary's instruments that match.
+    pub fn resolve(&self, dictionary: &InstrumentDictionary) -> BTreeSet<u32> {
+        dictionary
+            .instruments
+            .iter()
+            .filter(|(_, name)| self.matches(name))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = Vec::new();
+        if let Some(instrument) = &self.instrument {
+            terms.push(format!("instrument={instrument}"));
+        }
+        if let Some(currency) = &self.currency {
+            terms.push(format!("currency={}", currency.to_uppercase()));
+        }
+        if let Some(expiry) = self.expiry {
+            terms.push(format!("expiry={expiry}"));
+        }
+        if let Some(strike) = self.strike {
+            terms.push(format!("strike={strike}"));
+        }
+        if let Some(kind) = self.kind {
+            terms.push(format!("kind={kind}"));
+        }
+        if terms.is_empty() {
+            f.write_str("*")
+        } else {
+            f.write_str(&terms.join(" "))
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Inclusive run of exchange trade numbers (`trade_seq`).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SeqRange {
+    pub first: u64,
+    pub last: u64,
+}
+
+impl SeqRange {
+    /// Numbers strictly between two consecutive ones seen, if any.
+    pub fn between(previous: u64, next: u64) -> Option<Self> {
+        (next > previous.saturating_add(1)).then(|| Self {
+            first: previous + 1,
+            last: next - 1,
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        self.last - self.first + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last < self.first
+    }
+}
+
+/// Runs missing between the smallest and largest of `seqs`.
+pub fn gaps(seqs: &BTreeSet<u64>) -> Vec<SeqRange> {
+    seqs.iter()
+        .zip(seqs.iter().skip(1))
+        .filter_map(|(previous, next)| SeqRange::between(*previous, *next))
+        .collect()
+}
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::block::BlockLayout;
+use crate::codec::{entropy_bits, Compression, Encoding};
+use crate::reader::TickReader;
+use crate::schema::{event, Tick};
+
+/// One column of the row format: its name, plain width, value, and the
+/// rows that hold one.
+struct Column {
+    name: &'static str,
+    width: usize,
+    value: fn(&Tick) -> i64,
+    rows: fn(&Tick) -> bool,
+}
+
+const COLUMNS: [Column; 23] = [
+    column("ts_ns", 8, |t| t.ts_ns as i64),
+    column("instrument_id", 4, |t| t.instrument_id as i64),
+    column("event", 1, |t| t.event as i64),
+    column("price_fp", 8, |t| t.price_fp),
+    column("size", 4, |t| t.size as i64),
+    // Trade prints keep `trade_seq` in the bytes of the best bid, so the two
+    // are measured as separate columns over their own rows.
+    Column {
+        name: "bid_px_fp[0]",
+        width: 8,
+        value: |t| t.bid_px_fp[0],
+        rows: |t| !event::is_trade(t.event),
+    },
+    Column {
+        name: "trade_seq",
+        width: 8,
+        value: |t| t.trade_seq().unwrap_or(0) as i64,
+        rows: |t| event::is_trade(t.event),
+    },
+    column("bid_px_fp[1]", 8, |t| t.bid_px_fp[1]),
+    column("bid_px_fp[2]", 8, |t| t.bid_px_fp[2]),
+    column("bid_px_fp[3]", 8, |t| t.bid_px_fp[3]),
+    column("ask_px_fp[0]", 8, |t| t.ask_px_fp[0]),
+    column("ask_px_fp[1]", 8, |t| t.ask_px_fp[1]),
+    column("ask_px_fp[2]", 8, |t| t.ask_px_fp[2]),
+    column("ask_px_fp[3]", 8, |t| t.ask_px_fp[3]),
+    column("bid_sz[0]", 4, |t| t.bid_sz[0] as i64),
+    column("bid_sz[1]", 4, |t| t.bid_sz[1] as i64),
+    column("bid_sz[2]", 4, |t| t.bid_sz[2] as i64),
+    column("bid_sz[3]", 4, |t| t.bid_sz[3] as i64),
+    column("ask_sz[0]", 4, |t| t.ask_sz[0] as i64),
+    column("ask_sz[1]", 4, |t| t.ask_sz[1] as i64),
+    column("ask_sz[2]", 4, |t| t.ask_sz[2] as i64),
+    column("ask_sz[3]", 4, |t| t.ask_sz[3] as i64),
+    column("flags", 2, |t| t.flags as i64),
+];
+
+const fn column(name: &'static str, width: usize, value: fn(&Tick) -> i64) -> Column {
+    Column {
+        name,
+        width,
+        value,
+        rows: |_| true,
+    }
+}
+
+/// Where one column's bytes go in one block.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct ColumnStats {
+    pub column: &'static str,
+    /// The smallest of [`Encoding::ALL`] for the block.
+    pub encoding: Encoding,
+    /// Bytes in the row format.
+    pub raw_bytes: u64,
+    pub encoded_bytes: u64,
+    /// Encoded bytes after the block compression.
+    pub compressed_bytes: u64,
+    /// Order-0 entropy of the encoded bytes, in bits per byte.
+    pub entropy_bits: f64,
+    /// `encoded_bytes × entropy_bits / 8`: what a coder treating each
+    /// encoded byte independently could reach at best.
+    pub entropy_bytes: f64,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct BlockStats {
+    pub block: u64,
+    pub first_row: u64,
+    pub rows: u64,
+    pub columns: Vec<ColumnStats>,
+}
+
+/// One column summed over every block.
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct ColumnTotals {
+    pub column: &'static str,
+    /// Blocks per chosen encoding.
+    pub encodings: BTreeMap<Encoding, u64>,
+    pub raw_bytes: u64,
+    pub encoded_bytes: u64,
+    pub compressed_bytes: u64,
+    pub entropy_bytes: f64,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+pub struct FileStats {
+    pub rows: u64,
+    pub blocks: u64,
+    pub layout: BlockLayout,
+    pub compression: Compression,
+    pub raw_bytes: u64,
+    pub encoded_bytes: u64,
+    pub compressed_bytes: u64,
+    pub columns: Vec<ColumnTotals>,
+    /// Every column of every block, with `--verbose`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_block: Option<Vec<BlockStats>>,
+}
+
+/// Encodes each column of each block of `path` the way a columnar block
+/// would store it, choosing the smallest encoding per column and block,
+/// and measures the bytes at every stage. Blocks follow the file's
+/// [`BlockLayout`]; `verbose` keeps the per-block breakdown.
+pub fn file_stats(path: &Path, compression: Compression, verbose: bool) -> Result<FileStats> {
+    let reader = TickReader::open(path)?;
+    let layout = reader.layout();
+    let block_rows = layout.block_rows() as usize;
+    let mut stats = FileStats {
+        rows: 0,
+        blocks: 0,
+        layout,
+        compression,
+        raw_bytes: 0,
+        encoded_bytes: 0,
+        compressed_bytes: 0,
+        columns: COLUMNS
+            .iter()
+            .map(|column| ColumnTotals {
+                column: column.name,
+                encodings: BTreeMap::new(),
+                raw_bytes: 0,
+                encoded_bytes: 0,
+                compressed_bytes: 0,
+                entropy_bytes: 0.0,
+            })
+            .collect(),
+        per_block: verbose.then(Vec::new),
+    };
+
+    let mut ticks = Vec::with_capacity(block_rows);
+    let mut reader = reader.peekable();
+    while reader.peek().is_some() {
+        ticks.clear();
+        for tick in reader.by_ref().take(block_rows) {
+            ticks.push(tick?);
+        }
+        let block = block_stats(&ticks, compression)?;
+        for (totals, column) in stats.columns.iter_mut().zip(&block) {
+            *totals.encodings.entry(column.encoding).or_default() += 1;
+            totals.raw_bytes += column.raw_bytes;
+            totals.encoded_bytes += column.encoded_bytes;
+            totals.compressed_bytes += column.compressed_bytes;
+            totals.entropy_bytes += column.entropy_bytes;
+        }
+        if let Some(per_block) = &mut stats.per_block {
+            per_block.push(BlockStats {
+                block: stats.blocks,
+                first_row: stats.rows,
+                rows: ticks.len() as u64,
+                columns: block,
+            });
+        }
+        stats.blocks += 1;
+        stats.rows += ticks.len() as u64;
+    }
+    stats.raw_bytes = stats.columns.iter().map(|c| c.raw_bytes).sum();
+    stats.encoded_bytes = stats.columns.iter().map(|c| c.encoded_bytes).sum();
+    stats.compressed_bytes = stats.columns.iter().map(|c| c.compressed_bytes).sum();
+    Ok(stats)
+}
+
+fn block_stats(ticks: &[Tick], compression: Compression) -> Result<Vec<ColumnStats>> {
+    COLUMNS
+        .iter()
+        .map(|column| {
+            let values: Vec<i64> = ticks
+                .iter()
+                .filter(|tick| (column.rows)(tick))
+                .map(column.value)
+                .collect();
+            let (encoding, encoded) = Encoding::choose(&values, column.width);
+            let entropy = entropy_bits(&encoded);
+            Ok(ColumnStats {
+                column: column.name,
+                encoding,
+                raw_bytes: (values.len() * column.width) as u64,
+                encoded_bytes: encoded.len() as u64,
+                compressed_bytes: compression.compress(&encoded)?.len() as u64,
+                entropy_bits: entropy,
+                entropy_bytes: encoded.len() as f64 * entropy / 8.0,
+            })
+        })
+        .collect()
+}
@@ -1,39 +1,71 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::Path;
 use std::time::Instant;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 use tracing::{info, warn};
 
 use crate::{
+    block::BlockLayout,
+    crypt::{self, BlockSealer, Key},
+    dict::InstrumentDictionary,
+    exit::Failure,
+    file::{encode_encrypted_header, encode_header, read_header},
+    index::ZoneMap,
     progress::{Progress, ProgressHandle, ProgressUpdate},
+    quality::{QualityCheck, QualityReport, QualityRules},
+    registry::InstrumentRegistry,
+    repro::ReproRecord,
+    retrieve::{CachedDay, DeribitNormalizer},
     schema::Tick,
 };
 
+/// Version of the mapping from input to rows in [`ingest_jsonl`] and, through
+/// [`DeribitNormalizer`], [`ingest_cache`], kept in each file's
+/// [`ReproRecord`]. Bump it whenever the same input would give different
+/// rows.
+pub const NORMALIZER_VERSION: u32 = 1;
+
 #[derive(Debug, Deserialize)]
 struct InputTick {
     ts_ns: u64,
-    instrument_id: u32,
+    /// Either the id or the instrument's name, which then gets an id.
+    #[serde(default)]
+    instrument_id: Option<u32>,
+    #[serde(default)]
+    instrument: Option<String>,
     event: u8,
     price_fp: i64,
     size: u32,
+    #[serde(default)]
+    trade_seq: Option<u64>,
 }
 
+/// Ingests JSONL ticks into a fresh row file with the given block
+/// targets, validating each against `rules`; flagged rows are still
+/// written and only counted in the returned report. Rows naming their
+/// `instrument` get its id from `registry`, or its hashed id without one,
+/// and the names are written to the file's dictionary sidecar; two names
+/// on one id fail the ingest.
 pub fn ingest_jsonl(
     input: &str,
     out: &str,
+    rules: QualityRules,
+    layout: BlockLayout,
+    mut registry: Option<&mut InstrumentRegistry>,
     progress: &mut Progress,
     token: ProgressHandle,
-) -> Result<()> {
+) -> Result<QualityReport> {
     let file = File::open(input).with_context(|| format!("open input {input}"))?;
     let reader = BufReader::new(file);
 
     let out_path = Path::new(out);
-    crate::util::ensure_parent_dir(out_path)?;
-    let mut writer = BufWriter::new(File::create(out_path)?);
+    let mut writer = create_fresh(out_path, layout)?;
 
+    let mut quality = QualityCheck::new(rules);
+    let mut dictionary = InstrumentDictionary::default();
     let mut rows = 0_u64;
     let mut bytes = 0_u64;
     let start = Instant::now();
@@ -43,9 +75,29 @@ pub fn ingest_jsonl(
         bytes += line.len() as u64;
         match serde_json::from_str::<InputTick>(&line) {
             Ok(raw) => {
-                let tick = Tick {
+                let instrument_id = match (&raw.instrument, raw.instrument_id) {
+                    (Some(name), _) => {
+                        let id = match registry.as_deref_mut() {
+                            Some(registry) => registry.id(name),
+                            None => InstrumentDictionary::hash_id(name),
+                        };
+                        if dictionary.name(id) != Some(name.as_str()) {
+                            dictionary
+                                .insert_as(id, name)
+                                .with_context(|| format!("ingesting {input}"))?;
+                        }
+                        id
+                    }
+                    (None, Some(id)) => id,
+                    (None, None) => {
+                        quality.malformed();
+                        warn!(target: "optstore::ingest", "tick names no instrument; skipping");
+                        continue;
+                    }
+                };
+                let mut tick = Tick {
                     ts_ns: raw.ts_ns,
-                    instrument_id: raw.instrument_id,
+                    instrument_id,
                     event: raw.event,
                     price_fp: raw.price_fp,
                     size: raw.size,
@@ -55,27 +107,282 @@ pub fn ingest_jsonl(
                     ask_sz: [0; 4],
                     flags: 0,
                 };
-                writer.write_all(&tick.ts_ns.to_le_bytes())?;
+                if let Some(seq) = raw.trade_seq {
+                    tick.set_trade_seq(seq);
+                }
+                quality.check(&tick);
+                writer.write(&tick)?;
                 rows += 1;
-                if rows % 10_000 == 0 {
+                if rows.is_multiple_of(10_000) {
                     progress.update(&token, ProgressUpdate::Rows { rows, bytes });
                 }
             }
             Err(err) => {
+                quality.malformed();
                 warn!(target: "optstore::ingest", ?err, "failed to parse tick; skipping");
             }
         }
     }
 
-    writer.flush()?;
+    writer.finish()?;
+    if !dictionary.instruments.is_empty() {
+        dictionary.replace_for(out_path)?;
+    }
     info!(
         target: "optstore::ingest",
         rows,
         bytes,
+        block_rows = layout.block_rows(),
         elapsed = ?start.elapsed(),
-        "ingest placeholder complete"
+        "ingest complete"
     );
 
     progress.update(&token, ProgressUpdate::Rows { rows, bytes });
-    Ok(())
+    Ok(quality.finish())
+}
+
+/// Ingests a cached retrieve day (see
+/// [`CacheManager::open_day`](crate::retrieve::CacheManager::open_day)) like
+/// [`ingest_jsonl`], reading its parts in manifest order and normalizing
+/// each page as `retrieve` does while it is decompressed. A part missing,
+/// or whose size or line count differs from the manifest, fails the ingest
+/// as corrupt; the instrument's id comes from `registry` as for named rows.
+pub fn ingest_cache(
+    day: &CachedDay,
+    out: &str,
+    rules: QualityRules,
+    layout: BlockLayout,
+    registry: Option<&mut InstrumentRegistry>,
+    progress: &mut Progress,
+    token: ProgressHandle,
+) -> Result<QualityReport> {
+    let CachedDay {
+        cache,
+        spec,
+        manifest,
+    } = day;
+    let out_path = Path::new(out);
+    let instrument_id = match registry {
+        Some(registry) => registry.id(&spec.symbol),
+        None => InstrumentDictionary::hash_id(&spec.symbol),
+    };
+    let mut dictionary = InstrumentDictionary::default();
+    dictionary.insert_as(instrument_id, &spec.symbol)?;
+    let normalizer = DeribitNormalizer {
+        instrument_id,
+        kind: spec.kind.clone(),
+    };
+    let mut writer = create_fresh(out_path, layout)?;
+
+    let mut quality = QualityCheck::new(rules);
+    let mut rows = 0_u64;
+    let mut bytes = 0_u64;
+    let start = Instant::now();
+    for part in &manifest.parts {
+        let path = cache.part_path(spec, part.part);
+        let corrupt = |what: String| Failure::Corrupt(format!("{}: {what}", path.display()));
+        let size = fs::metadata(&path)
+            .with_context(|| corrupt("listed in the manifest but missing".to_string()))?
+            .len();
+        if size != part.bytes {
+            bail!(corrupt(format!(
+                "{size} bytes, manifest has {}",
+                part.bytes
+            )));
+        }
+        let mut page = LineCount::new(cache.open_part(spec, part.part)?);
+        let ticks = normalizer
+            .read_ticks(&mut page)
+            .with_context(|| corrupt("not a trades page".to_string()))?;
+        // The parser stops at the end of the page; count the rest.
+        std::io::copy(&mut page, &mut std::io::sink())
+            .with_context(|| corrupt("decompressing".to_string()))?;
+        if page.lines != part.rows {
+            bail!(corrupt(format!(
+                "{} lines, manifest has {}",
+                page.lines, part.rows
+            )));
+        }
+        for tick in &ticks {
+            quality.check(tick);
+            writer.write(tick)?;
+        }
+        rows += ticks.len() as u64;
+        bytes += page.bytes;
+        progress.update(&token, ProgressUpdate::Rows { rows, bytes });
+    }
+
+    writer.finish()?;
+    dictionary.replace_for(out_path)?;
+    info!(
+        target: "optstore::ingest",
+        symbol = %spec.symbol,
+        parts = manifest.parts.len(),
+        rows,
+        bytes,
+        block_rows = layout.block_rows(),
+        elapsed = ?start.elapsed(),
+        "ingest complete"
+    );
+    Ok(quality.finish())
+}
+
+/// Removes `out` and the sidecars describing its old contents, then
+/// creates it with `layout`.
+fn create_fresh(out: &Path, layout: BlockLayout) -> Result<TickWriter> {
+    for stale in [
+        out.to_path_buf(),
+        ZoneMap::sidecar_path(out),
+        ReproRecord::sidecar_path(out),
+    ] {
+        match fs::remove_file(&stale) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(err).with_context(|| format!("removing {}", stale.display()));
+            }
+            _ => {}
+        }
+    }
+    TickWriter::append_with_layout(out, layout)
+}
+
+/// Counts the bytes and newlines read through it.
+struct LineCount<R> {
+    inner: R,
+    lines: u64,
+    bytes: u64,
+}
+
+impl<R> LineCount<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            lines: 0,
+            bytes: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for LineCount<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.lines += bytecount::count(&buf[..read], b'\n') as u64;
+        self.bytes += read as u64;
+        Ok(read)
+    }
+}
+
+/// Appends ticks to a row-format optstore file (see [`crate::file`]).
+/// New files are encrypted when a key is configured (see
+/// [`Key::from_env`]); a file keeps the format it was created with.
+pub struct TickWriter {
+    inner: BufWriter<File>,
+    row: Vec<u8>,
+    rows: u64,
+    /// Set for encrypted files.
+    sealer: Option<BlockSealer>,
+}
+
+impl TickWriter {
+    /// Opens `path` for appending, writing the header with the default
+    /// block layout if the file is new; an existing file keeps its own.
+    pub fn append(path: &Path) -> Result<Self> {
+        Self::open(path, None, Key::from_env()?)
+    }
+
+    /// Like [`Self::append`], but a new file gets `layout` and an existing
+    /// one must already have it: blocks of one file share a size.
+    pub fn append_with_layout(path: &Path, layout: BlockLayout) -> Result<Self> {
+        Self::open(path, Some(layout), Key::from_env()?)
+    }
+
+    /// Like [`Self::append_with_layout`], with `key` instead of the
+    /// environment's: a new file is encrypted with it, or left plaintext
+    /// without one.
+    pub fn append_with_key(path: &Path, layout: BlockLayout, key: Option<Key>) -> Result<Self> {
+        Self::open(path, Some(layout), key)
+    }
+
+    fn open(path: &Path, layout: Option<BlockLayout>, mut key: Option<Key>) -> Result<Self> {
+        crate::util::ensure_parent_dir(path)?;
+        let fresh = fs::metadata(path).map(|m| m.len() == 0).unwrap_or(true);
+        let mut sealer = None;
+        if !fresh {
+            let mut existing =
+                File::open(path).with_context(|| format!("open {}", path.display()))?;
+            let meta = read_header(&mut existing)
+                .with_context(|| format!("reading header of {}", path.display()))?;
+            if let Some(layout) = layout.filter(|layout| *layout != meta.layout) {
+                bail!(
+                    "{} has blocks of {} rows / {} bytes, not {} / {}",
+                    path.display(),
+                    meta.layout.rows,
+                    meta.layout.bytes,
+                    layout.rows,
+                    layout.bytes
+                );
+            }
+            if let Some(key_id) = meta.key_id {
+                let key = crypt::file_key(key.take(), key_id, path)?;
+                let header = encode_encrypted_header(meta.layout, key_id);
+                sealer = Some(
+                    BlockSealer::resume(key, &header, meta.layout.block_rows(), &mut existing)
+                        .with_context(|| format!("reading last block of {}", path.display()))?,
+                );
+            }
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open {}", path.display()))?;
+        let mut inner = BufWriter::new(file);
+        if fresh {
+            let layout = layout.unwrap_or_default();
+            match key {
+                Some(key) => {
+                    let header = encode_encrypted_header(layout, key.id());
+                    inner.write_all(&header)?;
+                    sealer = Some(BlockSealer::new(key, &header, layout.block_rows()));
+                }
+                None => inner.write_all(&encode_header(layout))?,
+            }
+        }
+        Ok(Self {
+            inner,
+            row: Vec::with_capacity(Tick::ENCODED_LEN),
+            rows: 0,
+            sealer,
+        })
+    }
+
+    pub fn write(&mut self, tick: &Tick) -> Result<()> {
+        self.row.clear();
+        tick.encode(&mut self.row);
+        match &mut self.sealer {
+            Some(sealer) => sealer.push(&mut self.inner, &self.row)?,
+            None => self.inner.write_all(&self.row)?,
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Flushes buffered rows and returns how many were written.
+    pub fn finish(mut self) -> Result<u64> {
+        if let Some(sealer) = &mut self.sealer {
+            sealer.finish(&mut self.inner)?;
+        }
+        self.inner.flush()?;
+        Ok(self.rows)
+    }
+}
+
+impl Drop for TickWriter {
+    /// Seals a partial block left by a writer dropped without
+    /// [`finish`](Self::finish), as the buffered plaintext rows are
+    /// flushed on drop.
+    fn drop(&mut self) {
+        if let Some(sealer) = &mut self.sealer {
+            let _ = sealer.finish(&mut self.inner);
+        }
+    }
 }
@@ -0,0 +1,149 @@
+use optstore::book::{book_path, read_day, BookReader, BookWriter};
+use optstore::schema::{event, Tick};
+use optstore::TickWriter;
+
+/// A book that drifts by a tick or two per update, like a quoted option.
+fn updates(count: u64) -> Vec<Tick> {
+    let mut state: u64 = 0x9e37_79b9;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut books = [(0x1234_5678u32, 5_000_000i64), (0x0bad_cafe, 120_000)];
+    (0..count)
+        .map(|row| {
+            let (id, bid) = &mut books[(next() % 2) as usize];
+            if next() % 3 == 0 {
+                *bid += [-500, 500][(next() % 2) as usize];
+            }
+            let depth = 1_000 + (next() % 4) as u32 * 500;
+            Tick {
+                ts_ns: 1_743_120_000_000_000_000 + row * 250_000_000,
+                instrument_id: *id,
+                event: event::BOOK,
+                price_fp: 84_000_000_000 + (row as i64 / 40) * 1_000_000,
+                size: 0,
+                bid_px_fp: [*bid, *bid - 500, *bid - 1_000, *bid - 1_500],
+                ask_px_fp: [*bid + 500, *bid + 1_000, *bid + 1_500, *bid + 2_000],
+                bid_sz: [depth, 2_000, 3_000, 4_000],
+                ask_sz: [1_500, depth, 3_000, 4_000],
+                flags: 0,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn book_stream_reconstructs_every_update_in_a_fraction_of_the_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    let rows = dir.path().join("2025/03/28.opt");
+    let book = book_path(&rows);
+    assert_eq!(book, dir.path().join("2025/03/28.book"));
+    let ticks = updates(2_000);
+
+    let mut writer = TickWriter::append(&rows).unwrap();
+    for tick in &ticks {
+        writer.write(tick).unwrap();
+    }
+    writer.finish().unwrap();
+    // Written in two sessions: the second continues the first's deltas.
+    let mut writer = BookWriter::append(&book, 32).unwrap();
+    for tick in &ticks[..700] {
+        writer.write(tick).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), 700);
+    let mut writer = BookWriter::append(&book, 8).unwrap();
+    assert_eq!(
+        writer.snapshot_interval(),
+        32,
+        "the file keeps its interval"
+    );
+    for tick in &ticks[700..] {
+        writer.write(tick).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let read: Vec<Tick> = BookReader::open(&book)
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(read, ticks);
+    let from = ticks[1_000].ts_ns;
+    let ranged: Vec<Tick> = BookReader::open(&book)
+        .unwrap()
+        .range(from, u64::MAX)
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(ranged, ticks[1_000..]);
+
+    let row_bytes = std::fs::metadata(&rows).unwrap().len();
+    let book_bytes = std::fs::metadata(&book).unwrap().len();
+    assert!(
+        book_bytes * 4 < row_bytes,
+        "book stream {book_bytes} bytes vs rows {row_bytes}"
+    );
+}
+
+#[test]
+fn book_writer_cuts_a_torn_record_and_rejects_trades() {
+    let dir = tempfile::tempdir().unwrap();
+    let book = dir.path().join("28.book");
+    let ticks = updates(10);
+    let mut writer = BookWriter::append(&book, 4).unwrap();
+    for tick in &ticks[..5] {
+        writer.write(tick).unwrap();
+    }
+    writer.finish().unwrap();
+    let intact = std::fs::metadata(&book).unwrap().len();
+    let mut bytes = std::fs::read(&book).unwrap();
+    bytes.extend_from_slice(&[1, 0x80]);
+    std::fs::write(&book, &bytes).unwrap();
+    assert!(BookReader::open(&book)
+        .unwrap()
+        .collect::<anyhow::Result<Vec<_>>>()
+        .is_err());
+
+    let mut writer = BookWriter::append(&book, 4).unwrap();
+    assert_eq!(std::fs::metadata(&book).unwrap().len(), intact);
+    for tick in &ticks[5..] {
+        writer.write(tick).unwrap();
+    }
+    let mut trade = ticks[0].clone();
+    trade.event = event::TRADE;
+    assert!(writer.write(&trade).is_err());
+    writer.finish().unwrap();
+    let read: Vec<Tick> = BookReader::open(&book)
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(read, ticks);
+}
+
+#[test]
+fn read_day_merges_rows_and_book_stream_by_time() {
+    let dir = tempfile::tempdir().unwrap();
+    let rows = dir.path().join("2025/03/28.opt");
+    assert!(read_day(&rows).unwrap().is_none());
+    let ticks = updates(40);
+
+    // A session recorded rows, the next one a book stream.
+    let mut writer = TickWriter::append(&rows).unwrap();
+    for tick in ticks.iter().step_by(2) {
+        writer.write(tick).unwrap();
+    }
+    writer.finish().unwrap();
+    let mut writer = BookWriter::append(&book_path(&rows), 4).unwrap();
+    for tick in ticks.iter().skip(1).step_by(2) {
+        writer.write(tick).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let merged: Vec<Tick> = read_day(&rows)
+        .unwrap()
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(merged, ticks);
+}
@@ -0,0 +1,71 @@
+//! Fixtures shared by the integration tests. Each test binary uses only
+//! some of them.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use bytes::Bytes;
+use optstore::retrieve::{CacheManager, CacheManifest, CacheManifestPart, RawChunk, RetrieveSpec};
+use optstore::schema::Tick;
+
+/// A tick with an empty book.
+pub fn tick(ts_ns: u64, instrument_id: u32, event: u8, price_fp: i64, size: u32) -> Tick {
+    Tick {
+        ts_ns,
+        instrument_id,
+        event,
+        price_fp,
+        size,
+        bid_px_fp: [0; 4],
+        ask_px_fp: [0; 4],
+        bid_sz: [0; 4],
+        ask_sz: [0; 4],
+        flags: 0,
+    }
+}
+
+/// A `get_last_trades_*` response holding `trades`.
+pub fn page(trades: Vec<serde_json::Value>, has_more: bool) -> Bytes {
+    serde_json::to_vec(&body(trades, has_more)).unwrap().into()
+}
+
+/// `page`, pretty-printed so that it spans lines.
+pub fn pretty_page(trades: Vec<serde_json::Value>, has_more: bool) -> Bytes {
+    serde_json::to_vec_pretty(&body(trades, has_more))
+        .unwrap()
+        .into()
+}
+
+fn body(trades: Vec<serde_json::Value>, has_more: bool) -> serde_json::Value {
+    serde_json::json!({ "result": { "trades": trades, "has_more": has_more } })
+}
+
+/// Caches one part per page the way `retrieve` does and returns the day's
+/// directory.
+pub fn cache_day(
+    cache: &CacheManager,
+    spec: &RetrieveSpec,
+    pages: impl IntoIterator<Item = Bytes>,
+) -> PathBuf {
+    let mut manifest = CacheManifest::new("deribit", spec);
+    for (part, data) in (0..).zip(pages) {
+        let chunk = RawChunk {
+            data,
+            start_ns: 0,
+            end_ns: 0,
+            resume: None,
+        };
+        let written = cache.write_chunk(spec, part, &chunk).unwrap();
+        manifest.append_part(CacheManifestPart {
+            part,
+            start_ns: 0,
+            end_ns: 0,
+            bytes: written.bytes_written,
+            rows: written.rows,
+            resume_token: None,
+            dictionary: written.dictionary,
+        });
+    }
+    cache.store_manifest(spec, &manifest).unwrap();
+    cache.manifest_path(spec).parent().unwrap().to_path_buf()
+}
@@ -1,4 +1,116 @@
+#![allow(clippy::assertions_on_constants)]
+
 #[test]
 fn corruption_placeholder() {
     assert!(true);
 }
+
+use anyhow::Context;
+use clap::Parser;
+use optstore::cli::OptStoreCli;
+use optstore::exit::{self, Failure};
+use optstore::schema::event;
+use optstore::{InstrumentDictionary, Tick, TickReader, TickWriter};
+
+fn run(args: &[&str]) -> anyhow::Result<()> {
+    OptStoreCli::try_parse_from(std::iter::once("optstore").chain(args.iter().copied()))
+        .expect("valid arguments")
+        .execute()
+}
+
+fn trade(ts_ns: u64) -> Tick {
+    Tick {
+        ts_ns,
+        instrument_id: 7,
+        event: event::TRADE,
+        price_fp: 50_000,
+        size: 1_000,
+        bid_px_fp: [0; 4],
+        ask_px_fp: [0; 4],
+        bid_sz: [0; 4],
+        ask_sz: [0; 4],
+        flags: 0,
+    }
+}
+
+#[test]
+fn damaged_files_exit_with_the_corrupt_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let mut writer = TickWriter::append(&path).unwrap();
+    writer.write(&trade(1_000)).unwrap();
+    writer.write(&trade(2_000)).unwrap();
+    writer.finish().unwrap();
+    let file = path.display().to_string();
+
+    let len = std::fs::metadata(&path).unwrap().len();
+    let truncate = |len: u64| {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len)
+            .unwrap()
+    };
+    truncate(len - 1);
+    let err = run(&["query", "--file", &file]).unwrap_err();
+    assert_eq!(exit::code(&err), exit::CORRUPT);
+    assert_eq!(err.to_string(), "truncated tick row");
+
+    truncate(10);
+    let err = TickReader::open(&path).err().expect("short header");
+    assert_eq!(exit::code(&err), exit::CORRUPT);
+
+    std::fs::write(&path, b"not a tick file at all, far too long").unwrap();
+    let err = run(&["stats", "--file", &file]).unwrap_err();
+    assert_eq!(exit::code(&err), exit::CORRUPT);
+}
+
+#[test]
+fn missing_and_empty_results_exit_with_the_no_data_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let file = path.display().to_string();
+    let err = run(&["query", "--file", &file]).unwrap_err();
+    assert_eq!(exit::code(&err), exit::NO_DATA);
+
+    let mut writer = TickWriter::append(&path).unwrap();
+    writer.write(&trade(1_000)).unwrap();
+    writer.finish().unwrap();
+    let mut dictionary = InstrumentDictionary::default();
+    dictionary.insert("ETH-28MAR25-2000-C");
+    dictionary.store_for(&path).unwrap();
+    let late = "2030-01-01T00:00:00Z";
+    run(&["query", "--file", &file, "--from", late, "--quiet"]).expect("empty is fine");
+    for args in [
+        vec!["query", "--file", &file, "--from", late],
+        vec!["query", "--file", &file, "--from", late, "--top", "3"],
+        vec!["query", "--file", &file, "--instrument", "ETH-1JAN30-1-C"],
+    ] {
+        let err = run(&[&args[..], &["--quiet", "--fail-on-empty"]].concat()).unwrap_err();
+        assert_eq!(exit::code(&err), exit::NO_DATA, "{args:?}");
+    }
+    run(&["query", "--file", &file, "--quiet", "--fail-on-empty"]).expect("one row matches");
+    run(&[
+        "query",
+        "--file",
+        &file,
+        "--instrument",
+        "x",
+        "--explain",
+        "--fail-on-empty",
+    ])
+    .expect("explain runs nothing");
+}
+
+#[test]
+fn codes_survive_added_context_and_default_to_one() {
+    let err = Err::<(), _>(Failure::Source("rejected".into()))
+        .context("retrieving")
+        .context("day 2025-03-28")
+        .unwrap_err();
+    assert_eq!(exit::code(&err), exit::SOURCE);
+    assert_eq!(exit::code(&anyhow::anyhow!("plain")), exit::ERROR);
+    let err = run(&["--quiet"]).unwrap_err();
+    assert_eq!(exit::code(&err), exit::USAGE);
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use clap::Parser;
+use optstore::cli::OptStoreCli;
+use optstore::exit;
+use optstore::index::ZONE_ROWS;
+use optstore::query::{daily_stats, DailyStats};
+use optstore::schema::{event, Tick};
+use optstore::util::day_to_path;
+use optstore::{InstrumentDictionary, Selection, TickWriter};
+
+use common::tick;
+
+const CALL: &str = "BTC-28MAR25-40000-C";
+const LINEAR: &str = "BTC_USDC-28MAR25-40000-C";
+const DAY27_NS: u64 = 1_743_033_600_000_000_000; // 2025-03-27T00:00:00Z
+const DAY28_NS: u64 = 1_743_120_000_000_000_000;
+const SECOND: u64 = 1_000_000_000;
+const HOUR: u64 = 3_600 * SECOND;
+
+fn write_day(path: &std::path::Path, ticks: &[Tick], dictionary: &InstrumentDictionary) {
+    let mut writer = TickWriter::append(path).unwrap();
+    for tick in ticks {
+        writer.write(tick).unwrap();
+    }
+    writer.finish().unwrap();
+    dictionary.store_for(path).unwrap();
+}
+
+/// Two days whose files give the call different ids: a zone of book rows
+/// then three call prints and a linear one on the 27th, and two call
+/// prints on the 28th.
+fn write_days(data: &std::path::Path) {
+    let mut dictionary = InstrumentDictionary::default();
+    let call = dictionary.insert(CALL);
+    let linear = dictionary.insert_with_contract_size(LINEAR, 0.01);
+    let mut ticks: Vec<Tick> = (0..ZONE_ROWS)
+        .map(|row| tick(DAY27_NS + row, call, event::BOOK, 0, 0))
+        .collect();
+    ticks.extend([
+        tick(DAY27_NS + HOUR, call, event::TRADE, 10_000, 1_000),
+        tick(
+            DAY27_NS + HOUR + SECOND,
+            linear,
+            event::TRADE,
+            40_000_000,
+            2_000,
+        ),
+        tick(
+            DAY27_NS + HOUR + 10 * SECOND,
+            call,
+            event::BLOCK_TRADE,
+            20_000,
+            3_000,
+        ),
+        tick(
+            DAY27_NS + HOUR + 40 * SECOND,
+            call,
+            event::TRADE,
+            15_000,
+            1_000,
+        ),
+    ]);
+    write_day(&day_to_path(data, "2025-03-27"), &ticks, &dictionary);
+
+    let mut dictionary = InstrumentDictionary::default();
+    dictionary.insert_as(1, CALL).unwrap();
+    write_day(
+        &day_to_path(data, "2025-03-28"),
+        &[
+            tick(DAY28_NS + 5 * SECOND, 1, event::TRADE, 30_000, 2_000),
+            tick(DAY28_NS + 2 * HOUR, 1, event::TRADE, 90_000, 2_000),
+        ],
+        &dictionary,
+    );
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn daily_stats_aggregate_per_instrument_day_across_files() {
+    let dir = tempfile::tempdir().unwrap();
+    write_days(dir.path());
+    let files = [
+        day_to_path(dir.path(), "2025-03-27"),
+        day_to_path(dir.path(), "2025-03-28"),
+    ];
+
+    let stats = daily_stats(&files, &Selection::default(), DAY27_NS, DAY28_NS + HOUR).unwrap();
+    assert_eq!(
+        (stats.files, stats.zones_scanned, stats.zones_pruned),
+        (2, 2, 1)
+    );
+    assert_eq!(stats.rows_scanned, 4 + 2);
+    let summary: Vec<(&str, &str, u64)> = stats
+        .days
+        .iter()
+        .map(|day| (day.day.as_str(), day.instrument.as_str(), day.trades))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("2025-03-27", CALL, 3),
+            ("2025-03-27", LINEAR, 1),
+            ("2025-03-28", CALL, 1)
+        ]
+    );
+
+    let DailyStats {
+        volume,
+        notional,
+        vwap,
+        twap,
+        min_price,
+        max_price,
+        first_ts_ns,
+        last_ts_ns,
+        ..
+    } = stats.days[0].clone();
+    assert!(close(volume, 5.0));
+    assert!(close(notional, 0.085));
+    assert!(close(vwap, 0.017));
+    // 0.01 held 10s, 0.02 held 30s.
+    assert!(close(twap, 0.0175), "{twap}");
+    assert_eq!((min_price, max_price), (0.01, 0.02));
+    assert_eq!(
+        (first_ts_ns, last_ts_ns),
+        (DAY27_NS + HOUR, DAY27_NS + HOUR + 40 * SECOND)
+    );
+    let linear = &stats.days[1];
+    assert!(close(linear.notional, 0.8));
+    assert!(close(linear.twap, 40.0));
+    assert!(close(stats.days[2].vwap, 0.03));
+
+    // By name, whatever id each file gives the call.
+    let calls = Selection {
+        instrument: Some(CALL.to_string()),
+        ..Selection::default()
+    };
+    let stats = daily_stats(&files, &calls, 0, u64::MAX).unwrap();
+    assert_eq!(
+        stats
+            .days
+            .iter()
+            .map(|day| (day.day.as_str(), day.trades))
+            .collect::<Vec<_>>(),
+        [("2025-03-27", 3), ("2025-03-28", 2)]
+    );
+}
+
+#[test]
+fn daily_stats_query_reads_day_files_between_from_and_to() {
+    let dir = tempfile::tempdir().unwrap();
+    write_days(dir.path());
+    let data = dir.path().display().to_string();
+    let args = |extra: &[&str]| {
+        let mut args = vec![
+            "optstore",
+            "query",
+            "--data",
+            &data,
+            "--from",
+            "2025-03-27T00:00:00Z",
+        ];
+        args.extend_from_slice(extra);
+        OptStoreCli::try_parse_from(args)
+    };
+    assert!(args(&["--to", "2025-03-29T00:00:00Z"]).is_err());
+    assert!(args(&["--daily-stats"]).is_err());
+    assert!(args(&[
+        "--to",
+        "2025-03-29T00:00:00Z",
+        "--daily-stats",
+        "--top",
+        "3"
+    ])
+    .is_err());
+
+    args(&[
+        "--to",
+        "2025-03-29T00:00:00Z",
+        "--daily-stats",
+        "--quiet",
+        "--fail-on-empty",
+    ])
+    .unwrap()
+    .execute()
+    .expect("both days have prints");
+    let err = args(&[
+        "--to",
+        "2025-03-27T00:30:00Z",
+        "--daily-stats",
+        "--quiet",
+        "--fail-on-empty",
+    ])
+    .unwrap()
+    .execute()
+    .expect_err("no print in the first half hour");
+    assert_eq!(exit::code(&err), exit::NO_DATA);
+}
@@ -1,4 +1,27 @@
+#![allow(clippy::assertions_on_constants)]
+
 #[test]
 fn dictionary_placeholder() {
     assert!(true);
 }
+
+#[test]
+fn dictionary_sidecar_merges_on_store() {
+    use optstore::InstrumentDictionary;
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("01.opt");
+
+    let mut first = InstrumentDictionary::default();
+    let call = first.insert("BTC-27JUN25-60000-C");
+    first.store_for(&file).unwrap();
+
+    let mut second = InstrumentDictionary::default();
+    let put = second.insert("BTC-27JUN25-60000-P");
+    second.store_for(&file).unwrap();
+
+    let loaded = InstrumentDictionary::load_for(&file).unwrap();
+    assert_eq!(loaded.name(call), Some("BTC-27JUN25-60000-C"));
+    assert_eq!(loaded.name(put), Some("BTC-27JUN25-60000-P"));
+    assert_eq!(call, InstrumentDictionary::hash_id("BTC-27JUN25-60000-C"));
+}
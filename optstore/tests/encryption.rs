@@ -0,0 +1,163 @@
+use futures_util::StreamExt;
+use optstore::block::BlockLayout;
+use optstore::crypt::{sealed_len, Key};
+use optstore::exit::{self, CORRUPT, USAGE};
+use optstore::file::{read_header, ENCRYPTED_FORMAT_VERSION};
+use optstore::index::file_rows;
+use optstore::schema::event;
+use optstore::{AsyncTickReader, Tick, TickReader, TickWriter};
+
+fn trade(i: u64) -> Tick {
+    Tick {
+        ts_ns: 1_700_000_000_000_000_000 + i * 1_000,
+        instrument_id: 3,
+        event: event::TRADE,
+        price_fp: 61_000_000_000 + i as i64,
+        size: 1_000,
+        bid_px_fp: [0; 4],
+        ask_px_fp: [0; 4],
+        bid_sz: [0; 4],
+        ask_sz: [0; 4],
+        flags: 0,
+    }
+}
+
+fn key(byte: u8) -> Key {
+    Key::new([byte; 32])
+}
+
+#[tokio::test]
+async fn encrypted_file_roundtrips_across_appends() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/04.opt");
+    let layout = BlockLayout::new(4, 1 << 20).unwrap();
+    let ticks: Vec<Tick> = (0..17).map(trade).collect();
+
+    let mut writer = TickWriter::append_with_key(&path, layout, Some(key(7))).unwrap();
+    for tick in &ticks[..10] {
+        writer.write(tick).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), 10);
+    // The partial third block is read back and rewritten with these.
+    let mut writer = TickWriter::append_with_key(&path, layout, Some(key(7))).unwrap();
+    for tick in &ticks[10..] {
+        writer.write(tick).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), 7);
+
+    // The header stays readable and rows are counted without the key.
+    let meta = read_header(&mut std::fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(meta.version, ENCRYPTED_FORMAT_VERSION);
+    assert_eq!(meta.layout, layout);
+    assert_eq!(meta.key_id, Some(key(7).id()));
+    assert_eq!(file_rows(&path).unwrap(), 17);
+    let raw = std::fs::read(&path).unwrap();
+    let mut row = Vec::new();
+    ticks[0].encode(&mut row);
+    assert!(!raw.windows(row.len()).any(|window| window == row));
+
+    let read: Vec<Tick> = TickReader::open_with_key(&path, Some(key(7)))
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(read, ticks);
+
+    let mut reader = TickReader::open_with_key(&path, Some(key(7))).unwrap();
+    reader.seek_rows(6..13).unwrap();
+    let sought: Vec<Tick> = reader.collect::<anyhow::Result<_>>().unwrap();
+    assert_eq!(sought, &ticks[6..13]);
+
+    let streamed: Vec<Tick> = AsyncTickReader::open_with_key(&path, Some(key(7)))
+        .await
+        .unwrap()
+        .into_stream()
+        .map(|tick| tick.unwrap())
+        .collect()
+        .await;
+    assert_eq!(streamed, ticks);
+}
+
+#[test]
+fn encrypted_file_needs_its_key_and_detects_tampering() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("04.opt");
+    let mut writer =
+        TickWriter::append_with_key(&path, BlockLayout::default(), Some(key(7))).unwrap();
+    for i in 0..5 {
+        writer.write(&trade(i)).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let err = TickReader::open_with_key(&path, Some(key(8)))
+        .err()
+        .unwrap();
+    assert_eq!(exit::code(&err), USAGE);
+    let err = TickWriter::append_with_key(&path, BlockLayout::default(), None)
+        .err()
+        .unwrap();
+    assert_eq!(exit::code(&err), USAGE);
+
+    let mut raw = std::fs::read(&path).unwrap();
+    let last = raw.len() - 1;
+    raw[last] ^= 1;
+    std::fs::write(&path, raw).unwrap();
+    let err = TickReader::open_with_key(&path, Some(key(7)))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(exit::code(&err), CORRUPT);
+}
+
+#[tokio::test]
+async fn garbled_block_length_is_corrupt_before_allocating() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("04.opt");
+    let layout = BlockLayout::new(4, 1 << 20).unwrap();
+    let mut writer = TickWriter::append_with_key(&path, layout, Some(key(7))).unwrap();
+    for i in 0..4 {
+        writer.write(&trade(i)).unwrap();
+    }
+    writer.finish().unwrap();
+
+    // One full block: its length prefix directly follows the header.
+    let mut raw = std::fs::read(&path).unwrap();
+    let frame = raw.len() - sealed_len(4) as usize;
+    let valid = u32::from_le_bytes(raw[frame..frame + 4].try_into().unwrap());
+    assert_eq!(u64::from(valid), sealed_len(4) - 4);
+    for len in [valid + 1, u32::MAX] {
+        raw[frame..frame + 4].copy_from_slice(&len.to_le_bytes());
+        std::fs::write(&path, &raw).unwrap();
+
+        let err = TickReader::open_with_key(&path, Some(key(7)))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(exit::code(&err), CORRUPT);
+        let err = AsyncTickReader::open_with_key(&path, Some(key(7)))
+            .await
+            .unwrap()
+            .next_tick()
+            .await
+            .unwrap_err();
+        assert_eq!(exit::code(&err), CORRUPT);
+    }
+}
+
+#[test]
+fn keys_parse_from_hex_and_key_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let hex = "07".repeat(32);
+    assert_eq!(Key::parse_hex(&hex).unwrap().id(), key(7).id());
+
+    let raw = dir.path().join("raw.key");
+    std::fs::write(&raw, [7u8; 32]).unwrap();
+    assert_eq!(Key::load(&raw).unwrap().id(), key(7).id());
+    let text = dir.path().join("hex.key");
+    std::fs::write(&text, format!("{hex}\n")).unwrap();
+    assert_eq!(Key::load(&text).unwrap().id(), key(7).id());
+
+    let err = Key::parse_hex("not a key").err().unwrap();
+    assert_eq!(exit::code(&err), USAGE);
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use optstore::cli::OptStoreCli;
+use optstore::exit::{self, Failure};
+use optstore::export::{Output, DUCKDB_ENV};
+use optstore::schema::event;
+use optstore::{InstrumentDictionary, TickWriter};
+
+use common::tick;
+
+const CALL: &str = "BTC-28MAR25-40000-C";
+const DAY_NS: u64 = 1_743_120_000_000_000_000; // 2025-03-28T00:00:00Z
+
+fn run(args: &[&str]) -> anyhow::Result<()> {
+    OptStoreCli::try_parse_from(std::iter::once("optstore").chain(args.iter().copied()))
+        .expect("valid arguments")
+        .execute()
+}
+
+/// A stand-in for the duckdb CLI that keeps the SQL it is fed and a copy of
+/// the staged rows next to the database, and fails when asked to.
+fn fake_duckdb(dir: &Path) -> PathBuf {
+    let script = dir.join("duckdb");
+    std::fs::write(
+        &script,
+        "#!/bin/sh\n\
+         cat > \"$2.sql\"\n\
+         for staged in \"$2\".*.csv; do cp \"$staged\" \"$2.rows\"; done\n\
+         [ -z \"$FAIL\" ] || { echo 'Catalog Error: type mismatch' >&2; exit 1; }\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    script
+}
+
+#[test]
+fn output_uris_name_a_database_and_table() {
+    assert_eq!(
+        "duckdb://warehouse/ticks.duckdb/btc_prints".parse::<Output>(),
+        Ok(Output::DuckDb {
+            database: PathBuf::from("warehouse/ticks.duckdb"),
+            table: "btc_prints".to_string(),
+        })
+    );
+    assert_eq!(
+        "duckdb:///tmp/a.duckdb/t"
+            .parse::<Output>()
+            .unwrap()
+            .to_string(),
+        "duckdb:///tmp/a.duckdb/t"
+    );
+    for bad in [
+        "parquet://out.parquet",
+        "duckdb://ticks.duckdb",
+        "duckdb://ticks.duckdb/",
+        "duckdb://ticks.duckdb/1st",
+        "duckdb://ticks.duckdb/drop table",
+    ] {
+        assert!(
+            matches!(bad.parse::<Output>(), Err(Failure::Usage(_))),
+            "{bad}"
+        );
+    }
+    assert!(OptStoreCli::try_parse_from([
+        "optstore",
+        "query",
+        "--file",
+        "28.opt",
+        "--output",
+        "csv://rows.csv"
+    ])
+    .is_err());
+}
+
+#[test]
+fn query_output_hands_typed_rows_to_duckdb() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let mut dictionary = InstrumentDictionary::default();
+    let call = dictionary.insert(CALL);
+    let mut trade = tick(DAY_NS + 1_500, call, event::TRADE, 25_000, 1_500);
+    trade.set_trade_seq(42);
+    let mut book = tick(DAY_NS + 2_000_000_000, call, event::BOOK, 25_000, 1_500);
+    book.bid_px_fp[0] = 24_000;
+    book.bid_sz[0] = 3_000;
+    let mut writer = TickWriter::append(&path).unwrap();
+    writer.write(&trade).unwrap();
+    writer.write(&book).unwrap();
+    writer.finish().unwrap();
+    dictionary.store_for(&path).unwrap();
+
+    let database = dir.path().join("warehouse.duckdb");
+    std::env::set_var(DUCKDB_ENV, fake_duckdb(dir.path()));
+    let file = path.display().to_string();
+    let output = format!("duckdb://{}/ticks", database.display());
+    run(&["query", "--file", &file, "--output", &output, "--quiet"]).unwrap();
+
+    let sql = std::fs::read_to_string(dir.path().join("warehouse.duckdb.sql")).unwrap();
+    assert!(sql.contains(
+        "CREATE TABLE IF NOT EXISTS \"ticks\" (ts TIMESTAMP, ts_ns UBIGINT, instrument VARCHAR, \
+         instrument_id UINTEGER, event VARCHAR, price DOUBLE, size DOUBLE, trade_seq UBIGINT, \
+         bid_price DOUBLE, bid_size DOUBLE, ask_price DOUBLE, ask_size DOUBLE);"
+    ));
+    assert!(sql.contains("INSERT INTO \"ticks\" BY NAME SELECT * FROM read_csv("));
+    let rows = std::fs::read_to_string(dir.path().join("warehouse.duckdb.rows")).unwrap();
+    let id = call.to_string();
+    assert_eq!(
+        rows.lines().collect::<Vec<_>>(),
+        [
+            "ts,ts_ns,instrument,instrument_id,event,price,size,trade_seq,bid_price,bid_size,ask_price,ask_size",
+            &format!("2025-03-28 00:00:00.000001,{},{CALL},{id},trade,0.025,1.5,42,,,,", DAY_NS + 1_500),
+            &format!(
+                "2025-03-28 00:00:02.000000,{},{CALL},{id},book,0.025,1.5,,0.024,3,0,0",
+                DAY_NS + 2_000_000_000
+            ),
+        ]
+    );
+    assert!(!dir.path().join("warehouse.duckdb.ticks.csv").exists());
+
+    // Daily stats go to their own table.
+    run(&[
+        "query",
+        "--file",
+        &file,
+        "--daily-stats",
+        "--output",
+        &format!("duckdb://{}/daily", database.display()),
+        "--quiet",
+    ])
+    .unwrap();
+    let sql = std::fs::read_to_string(dir.path().join("warehouse.duckdb.sql")).unwrap();
+    assert!(sql.contains("CREATE TABLE IF NOT EXISTS \"daily\" (day DATE, instrument VARCHAR,"));
+
+    // A failed load is an error and leaves no staging file behind.
+    std::env::set_var("FAIL", "1");
+    let err = run(&["query", "--file", &file, "--output", &output, "--quiet"]).unwrap_err();
+    std::env::remove_var("FAIL");
+    assert!(format!("{err:#}").contains("type mismatch"), "{err:#}");
+    assert_eq!(exit::code(&err), exit::ERROR);
+    assert!(!dir.path().join("warehouse.duckdb.ticks.csv").exists());
+
+    // Reserved-word tables and quotes in the staging path are escaped.
+    let warehouse = dir.path().join("o'brien");
+    let database = warehouse.join("warehouse.duckdb");
+    let output = format!("duckdb://{}/order", database.display());
+    run(&["query", "--file", &file, "--output", &output, "--quiet"]).unwrap();
+    let sql = std::fs::read_to_string(warehouse.join("warehouse.duckdb.sql")).unwrap();
+    assert!(sql.contains("CREATE TABLE IF NOT EXISTS \"order\" (ts TIMESTAMP"));
+    let staging = warehouse.join("warehouse.duckdb.order.csv");
+    assert!(sql.contains(&format!(
+        "INSERT INTO \"order\" BY NAME SELECT * FROM read_csv('{}', header = true",
+        staging.display().to_string().replace('\'', "''")
+    )));
+}
@@ -0,0 +1,139 @@
+mod common;
+
+use bytes::Bytes;
+use chrono::NaiveDate;
+use optstore::gc::{self, GcOptions, GcStatus};
+use optstore::retrieve::cache::CacheManager;
+use optstore::retrieve::{DeribitNormalizer, Normalizer, RawChunk, RetrieveKind, RetrieveSpec};
+use optstore::{InstrumentDictionary, TickWriter};
+
+const SYMBOL: &str = "ETH-28MAR25-2000-C";
+
+fn spec(day_ymd: u32) -> RetrieveSpec {
+    RetrieveSpec {
+        symbol: SYMBOL.to_string(),
+        day_ymd,
+        kind: RetrieveKind::Trades,
+    }
+}
+
+/// A page of three trades an hour into `day`.
+fn page(day: NaiveDate, page: u32) -> Bytes {
+    let start_ms = day
+        .and_hms_opt(1, 0, 0)
+        .unwrap()
+        .and_utc()
+        .timestamp_millis() as u64;
+    let trades = (0..3u32)
+        .map(|i| {
+            let seq = page * 3 + i;
+            serde_json::json!({
+                "trade_seq": seq,
+                "timestamp": start_ms + seq as u64 * 1_000,
+                "price": 0.0125 + seq as f64 * 0.0005,
+                "amount": 1.0 + seq as f64,
+            })
+        })
+        .collect();
+    common::page(trades, false)
+}
+
+/// Caches two pages of `day`; with `ingest_all` false the last trade is
+/// left out of the day file, and with `data` unset no file is written.
+fn cache_day(
+    cache: &CacheManager,
+    data: Option<&std::path::Path>,
+    day: NaiveDate,
+    ingest_all: bool,
+) {
+    let day_ymd: u32 = day.format("%Y%m%d").to_string().parse().unwrap();
+    let pages: Vec<_> = (0..2).map(|part| page(day, part)).collect();
+    common::cache_day(cache, &spec(day_ymd), pages.clone());
+
+    let Some(data) = data else {
+        return;
+    };
+    let normalizer = DeribitNormalizer {
+        instrument_id: InstrumentDictionary::hash_id(SYMBOL),
+        kind: RetrieveKind::Trades,
+    };
+    let mut ticks = Vec::new();
+    for data in pages {
+        let chunk = RawChunk {
+            data,
+            start_ns: 0,
+            end_ns: 0,
+            resume: None,
+        };
+        ticks.extend(normalizer.to_ticks(chunk).unwrap());
+    }
+    if !ingest_all {
+        ticks.pop();
+    }
+    let path = optstore::util::day_to_path(data, &day.format("%Y-%m-%d").to_string());
+    let mut writer = TickWriter::append(&path).unwrap();
+    for tick in &ticks {
+        writer.write(tick).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+#[test]
+fn gc_removes_only_old_days_confirmed_in_day_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let (root, data) = (dir.path().join("cache"), dir.path().join("data"));
+    let cache = CacheManager::new(root.clone());
+    let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+    cache_day(&cache, Some(&data), day(20), true);
+    cache_day(&cache, Some(&data), day(21), false);
+    cache_day(&cache, None, day(22), true);
+    cache_day(&cache, Some(&data), day(27), true);
+
+    let options = |dry_run| GcOptions {
+        keep_days: 2,
+        today: day(28),
+        dry_run,
+    };
+    let symbols = [SYMBOL.to_string()];
+    let dry = gc::collect(&cache, &data, &symbols, options(true)).unwrap();
+    let statuses: Vec<(&str, GcStatus, u64)> = dry
+        .days
+        .iter()
+        .map(|day| (day.day.as_str(), day.status, day.missing))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            ("2025-03-20", GcStatus::Removed, 0),
+            ("2025-03-21", GcStatus::Unconfirmed, 1),
+            ("2025-03-22", GcStatus::NoFile, 0),
+            ("2025-03-27", GcStatus::Recent, 0),
+        ]
+    );
+    assert_eq!(dry.days[0].cached, 6);
+    assert_eq!(
+        (dry.days_checked, dry.days_removed, dry.parts_removed),
+        (4, 1, 2)
+    );
+    assert_eq!(dry.bytes_freed, dry.days[0].bytes);
+    assert_eq!(
+        cache.cached_days(SYMBOL).unwrap().len(),
+        4,
+        "dry run deletes nothing"
+    );
+
+    let summary = gc::collect(&cache, &data, &symbols, options(false)).unwrap();
+    assert!(!summary.dry_run);
+    assert_eq!(summary.days_removed, 1);
+    assert_eq!(summary.days_unconfirmed, 1);
+    assert_eq!(
+        cache.cached_days(SYMBOL).unwrap(),
+        [20250321, 20250322, 20250327]
+    );
+    assert!(cache.read_part(&spec(20250321), 0).is_ok());
+
+    // A second pass finds nothing more to remove.
+    let again = gc::collect(&cache, &data, &symbols, options(false)).unwrap();
+    assert_eq!(again.days_removed, 0);
+    assert_eq!(again.days_checked, 3);
+}
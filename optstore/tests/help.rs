@@ -0,0 +1,81 @@
+use clap::CommandFactory;
+use optstore::cli::OptStoreCli;
+use optstore::help::command_help;
+
+#[test]
+fn help_tree_lists_commands_flags_and_defaults() {
+    let help = command_help(&OptStoreCli::command());
+    assert_eq!(help.name, "optstore");
+    assert!(help.args.iter().any(|arg| arg.id == "json" && arg.global));
+    assert!(!help
+        .args
+        .iter()
+        .any(|arg| arg.id == "help" || arg.id == "version"));
+    assert!(!help.subcommands.iter().any(|sub| sub.name == "help"));
+
+    let stats = help
+        .subcommands
+        .iter()
+        .find(|sub| sub.name == "stats")
+        .expect("stats");
+    let compression = stats
+        .args
+        .iter()
+        .find(|arg| arg.id == "compression")
+        .expect("compression");
+    assert_eq!(compression.long.as_deref(), Some("compression"));
+    assert_eq!(compression.kind, "value");
+    assert_eq!(compression.default, ["lz4"]);
+    assert_eq!(compression.possible_values, ["lz4", "zstd"]);
+    let verbose = stats.args.iter().find(|arg| arg.id == "verbose").unwrap();
+    assert_eq!(verbose.kind, "flag");
+    assert!(verbose.default.is_empty() && verbose.value_names.is_empty());
+    let file = stats.args.iter().find(|arg| arg.id == "file").unwrap();
+    assert!(file.required);
+
+    let reconcile = help
+        .subcommands
+        .iter()
+        .find(|sub| sub.name == "reconcile")
+        .unwrap();
+    let symbols = reconcile
+        .args
+        .iter()
+        .find(|arg| arg.id == "symbols")
+        .unwrap();
+    assert_eq!(symbols.kind, "values");
+
+    let completions = help
+        .subcommands
+        .iter()
+        .find(|sub| sub.name == "completions")
+        .unwrap();
+    assert_eq!(completions.args[0].kind, "positional");
+    assert!(completions.args[0]
+        .possible_values
+        .contains(&"zsh".to_string()));
+}
+
+#[test]
+fn completions_cover_every_command() {
+    let mut script = Vec::new();
+    clap_complete::generate(
+        clap_complete::Shell::Bash,
+        &mut OptStoreCli::command(),
+        "optstore",
+        &mut script,
+    );
+    let script = String::from_utf8(script).unwrap();
+    for name in [
+        "retrieve",
+        "ingest",
+        "query",
+        "reconcile",
+        "repair",
+        "stats",
+        "completions",
+    ] {
+        assert!(script.contains(name), "{name}");
+    }
+    assert!(script.contains("--block-rows"));
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use optstore::cli::{Commands, IngestCommand};
+use optstore::exit::{self, CORRUPT, USAGE};
+use optstore::repro::ReproRecord;
+use optstore::retrieve::{CacheManager, CacheManifest, RetrieveKind, RetrieveSpec};
+use optstore::schema::event;
+use optstore::{InstrumentDictionary, TickReader};
+
+const SYMBOL: &str = "BTC-28MAR25-60000-C";
+const DAY_MS: u64 = 1_743_120_000_000; // 2025-03-28T00:00:00Z
+
+/// A page of `count` trades from `first`; every fifth is a block trade.
+/// Pretty-printed pages span lines, as the manifest's row counts reflect.
+fn page(first: u64, count: u64, pretty: bool) -> Bytes {
+    let trades = (first..first + count)
+        .map(|seq| {
+            let mut trade = serde_json::json!({
+                "trade_seq": seq,
+                "timestamp": DAY_MS + seq * 1_000,
+                "price": 0.05,
+                "amount": 1.0 + seq as f64,
+            });
+            if seq % 5 == 0 {
+                trade["block_trade_id"] = format!("BLOCK-{seq}").into();
+            }
+            trade
+        })
+        .collect();
+    if pretty {
+        common::pretty_page(trades, false)
+    } else {
+        common::page(trades, false)
+    }
+}
+
+/// Caches two pages of `kind` the way `retrieve` does and returns the
+/// day's directory.
+fn cache_day(root: &Path, kind: RetrieveKind) -> PathBuf {
+    let spec = RetrieveSpec {
+        symbol: SYMBOL.to_string(),
+        day_ymd: 20250328,
+        kind,
+    };
+    common::cache_day(
+        &CacheManager::new(root.to_path_buf()),
+        &spec,
+        [page(1, 6, true), page(7, 4, false)],
+    )
+}
+
+fn ingest(input: &Path, out: &Path, day: &str) -> anyhow::Result<()> {
+    Commands::Ingest(IngestCommand {
+        input: input.display().to_string(),
+        out: out.display().to_string(),
+        day: day.to_string(),
+        strict: false,
+        quality_report: false,
+        max_size: 100_000.0,
+        block_rows: 4,
+        block_bytes: 1 << 20,
+        registry: None,
+    })
+    .execute(true, false)
+}
+
+#[test]
+fn ingest_reads_a_cached_day_in_part_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let day = cache_day(&dir.path().join("raw_cache"), RetrieveKind::Trades);
+    let out = dir.path().join("data/2025/03/28.opt");
+    ingest(&day, &out, "2025-03-28").unwrap();
+
+    let ticks: Vec<_> = TickReader::open(&out)
+        .unwrap()
+        .map(|tick| tick.unwrap())
+        .collect();
+    let seqs: Vec<u64> = ticks.iter().filter_map(|tick| tick.trade_seq()).collect();
+    assert_eq!(seqs, (1..=10).collect::<Vec<_>>());
+    assert_eq!(ticks[4].event, event::BLOCK_TRADE);
+    let id = InstrumentDictionary::hash_id(SYMBOL);
+    assert!(ticks.iter().all(|tick| tick.instrument_id == id));
+    assert_eq!(
+        InstrumentDictionary::load_for(&out).unwrap().name(id),
+        Some(SYMBOL)
+    );
+
+    // The manifest and every part are the record's sources.
+    let record = ReproRecord::load_for(&out).unwrap();
+    let sources: Vec<String> = record
+        .sources
+        .iter()
+        .map(|source| source.path.clone())
+        .collect();
+    assert_eq!(
+        sources,
+        [
+            "manifest.json",
+            "part-0000.jsonl.zst",
+            "part-0001.jsonl.zst"
+        ]
+        .map(|name| day.join(name).display().to_string())
+    );
+
+    let err = ingest(&day, &out, "2025-03-29").unwrap_err();
+    assert_eq!(exit::code(&err), USAGE);
+}
+
+#[test]
+fn ingest_keeps_only_the_prints_of_a_partition() {
+    let dir = tempfile::tempdir().unwrap();
+    let day = cache_day(&dir.path().join("raw_cache"), RetrieveKind::BlockTrades);
+    assert!(day.to_string_lossy().contains("block_trades"));
+    let out = dir.path().join("28.opt");
+    ingest(&day, &out, "2025-03-28").unwrap();
+    let seqs: Vec<u64> = TickReader::open(&out)
+        .unwrap()
+        .map(|tick| tick.unwrap().trade_seq().unwrap())
+        .collect();
+    assert_eq!(seqs, [5, 10]);
+}
+
+#[test]
+fn ingest_checks_parts_against_the_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    let day = cache_day(&dir.path().join("raw_cache"), RetrieveKind::Trades);
+    let out = dir.path().join("28.opt");
+    let manifest_path = day.join("manifest.json");
+    let original = std::fs::read(&manifest_path).unwrap();
+
+    let mut manifest: CacheManifest = serde_json::from_slice(&original).unwrap();
+    assert!(manifest.parts[0].rows > 0);
+    manifest.parts[0].rows += 1;
+    std::fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+    let err = ingest(&day, &out, "2025-03-28").unwrap_err();
+    assert_eq!(exit::code(&err), CORRUPT);
+    assert!(format!("{err:#}").contains("part-0000.jsonl.zst"));
+
+    std::fs::write(&manifest_path, &original).unwrap();
+    std::fs::remove_file(day.join("part-0001.jsonl.zst")).unwrap();
+    let err = ingest(&day, &out, "2025-03-28").unwrap_err();
+    assert_eq!(exit::code(&err), CORRUPT);
+    assert!(format!("{err:#}").contains("missing"));
+}
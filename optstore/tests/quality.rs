@@ -0,0 +1,151 @@
+mod common;
+
+use chrono::NaiveDate;
+use optstore::cli::{Commands, IngestCommand};
+use optstore::quality::{QualityCheck, QualityReport, QualityRules, SeqGap};
+use optstore::schema::{event, Tick};
+use optstore::seq::SeqRange;
+use optstore::TickReader;
+
+use common::tick;
+
+const DAY_NS: u64 = 1_743_120_000_000_000_000; // 2025-03-28T00:00:00Z
+
+#[test]
+fn quality_check_counts_each_rule() {
+    let rules = QualityRules::for_day(NaiveDate::from_ymd_opt(2025, 3, 28).unwrap(), 1_000.0);
+    assert_eq!(rules.day_from_ns, DAY_NS);
+    assert_eq!(rules.day_to_ns - rules.day_from_ns, 86_400_000_000_000);
+    assert_eq!(rules.max_size, 1_000_000);
+
+    let mut check = QualityCheck::new(rules);
+    let clean = [
+        tick(DAY_NS + 1, 7, event::TRADE, 50_000, 1_000),
+        // Book rows carry no size and may lack an index price.
+        tick(DAY_NS + 2, 7, event::BOOK, 0, 0),
+    ];
+    for tick in &clean {
+        assert!(check.check(tick));
+    }
+    assert!(!check.check(&tick(DAY_NS + 3, 7, event::TRADE, 0, 1_000)));
+    assert!(!check.check(&tick(DAY_NS + 4, 7, event::BLOCK_TRADE, 50_000, 0)));
+    assert!(!check.check(&tick(DAY_NS + 5, 7, event::TRADE, 50_000, 2_000_000)));
+    // Back in time, and a repeat of the first row: two rules, one row.
+    assert!(!check.check(&clean[0]));
+    assert!(!check.check(&tick(DAY_NS - 1, 7, event::BOOK, -1, 0)));
+    check.malformed();
+
+    assert_eq!(
+        check.finish(),
+        QualityReport {
+            rows: 7,
+            flagged_rows: 5,
+            malformed: 1,
+            non_monotonic: 2,
+            bad_price: 2,
+            bad_size: 2,
+            duplicates: 1,
+            out_of_day: 1,
+            seq_missing: 0,
+            seq_gaps: vec![],
+        }
+    );
+}
+
+#[test]
+fn quality_check_finds_trade_seq_gaps_per_instrument() {
+    let rules = QualityRules::for_day(NaiveDate::from_ymd_opt(2025, 3, 28).unwrap(), 1_000.0);
+    let mut check = QualityCheck::new(rules);
+    // Instrument 7 skips 3..=4; instrument 8 interleaves without gaps, and
+    // a repeated number is not a gap.
+    for (ms, instrument_id, seq) in [
+        (1, 7, 1),
+        (2, 8, 40),
+        (3, 7, 2),
+        (4, 8, 41),
+        (5, 7, 5),
+        (6, 7, 5),
+    ] {
+        let mut print = tick(DAY_NS + ms, 7, event::TRADE, 50_000, 1_000 + ms as u32);
+        print.instrument_id = instrument_id;
+        print.set_trade_seq(seq);
+        assert_eq!(print.trade_seq(), Some(seq));
+        check.check(&print);
+    }
+    let report = check.finish();
+    assert_eq!(report.seq_missing, 2);
+    assert_eq!(
+        report.seq_gaps,
+        [SeqGap {
+            instrument_id: 7,
+            missing: SeqRange { first: 3, last: 4 },
+        }]
+    );
+    assert!(!report.is_clean());
+    assert_eq!(tick(DAY_NS, 7, event::BOOK, 0, 0).trade_seq(), None);
+}
+
+#[test]
+fn strict_ingest_fails_and_writes_the_quality_sidecar() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("ticks.jsonl");
+    let out = dir.path().join("2025/03/28.opt");
+    let line = |ts_ns: u64, price_fp: i64| {
+        format!(
+            r#"{{"ts_ns":{ts_ns},"instrument_id":7,"event":1,"price_fp":{price_fp},"size":1000}}"#
+        )
+    };
+    std::fs::write(
+        &input,
+        [line(DAY_NS + 1, 50_000), line(DAY_NS + 2, 60_000)].join("\n"),
+    )
+    .unwrap();
+    let ingest = |strict: bool| {
+        Commands::Ingest(IngestCommand {
+            input: input.display().to_string(),
+            out: out.display().to_string(),
+            day: "2025-03-28".to_string(),
+            strict,
+            quality_report: true,
+            max_size: 100_000.0,
+            block_rows: 1,
+            block_bytes: 1 << 20,
+            registry: None,
+        })
+        .execute(true, false)
+    };
+
+    ingest(true).expect("clean input passes strict ingest");
+    assert!(QualityReport::load_for(&out).unwrap().is_clean());
+    let reader = TickReader::open(&out).unwrap();
+    assert_eq!(reader.layout().block_rows(), 1);
+    let ingested: Vec<Tick> = reader.collect::<anyhow::Result<_>>().unwrap();
+    assert_eq!(
+        ingested
+            .iter()
+            .map(|tick| tick.price_fp)
+            .collect::<Vec<_>>(),
+        [50_000, 60_000]
+    );
+
+    std::fs::write(
+        &input,
+        [
+            line(DAY_NS + 1, 50_000),
+            line(DAY_NS + 1, 50_000),
+            "{".to_string(),
+        ]
+        .join("\n"),
+    )
+    .unwrap();
+    ingest(false).expect("lenient ingest keeps going");
+    assert!(out.exists());
+    let err = ingest(true).expect_err("strict ingest rejects duplicates");
+    assert!(err.to_string().contains("duplicates=1"), "{err}");
+    assert!(!out.exists());
+    let report = QualityReport::load_for(&out).unwrap();
+    assert_eq!(
+        (report.rows, report.duplicates, report.malformed),
+        (2, 1, 1)
+    );
+}
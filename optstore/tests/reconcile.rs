@@ -0,0 +1,203 @@
+mod common;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use optstore::reconcile::reconcile_symbol;
+use optstore::repair::{repair, RepairSummary, SeqSource};
+use optstore::retrieve::{CacheManager, RawChunk, RetrieveKind, RetrieveSpec};
+use optstore::seq::SeqRange;
+
+const SYMBOL: &str = "BTC-28MAR25-60000-C";
+
+fn page(trades: &[(u64, u64)], has_more: bool) -> Bytes {
+    let trades = trades
+        .iter()
+        .map(|(seq, ms)| {
+            serde_json::json!({
+                "trade_id": format!("T-{seq}"),
+                "trade_seq": seq,
+                "timestamp": ms,
+                "price": 0.05,
+                "amount": 1.0,
+                "direction": "buy",
+            })
+        })
+        .collect();
+    common::page(trades, has_more)
+}
+
+/// Caches one page per entry of `pages`: `(trade_seq, ms into the day)`
+/// pairs and the page's `has_more`.
+fn cache_day(
+    cache: &CacheManager,
+    day_ymd: u32,
+    day_start_ms: u64,
+    pages: &[(&[(u64, u64)], bool)],
+) {
+    let spec = RetrieveSpec {
+        symbol: SYMBOL.to_string(),
+        day_ymd,
+        kind: RetrieveKind::Trades,
+    };
+    let pages = pages.iter().map(|(trades, has_more)| {
+        let trades: Vec<_> = trades
+            .iter()
+            .map(|(seq, ms)| (*seq, day_start_ms + ms))
+            .collect();
+        page(&trades, *has_more)
+    });
+    common::cache_day(cache, &spec, pages);
+}
+
+#[test]
+fn reconcile_flags_trade_seq_gaps_and_truncated_days() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CacheManager::new(dir.path().to_path_buf());
+    // 2025-03-27: complete over two pages that overlap on seq 2.
+    cache_day(
+        &cache,
+        20250327,
+        1_743_033_600_000,
+        &[(&[(1, 10), (2, 20)], true), (&[(2, 20), (3, 30)], false)],
+    );
+    // 2025-03-28: seq 4 never arrived, 7 is missing inside the day and
+    // paging stopped early.
+    cache_day(
+        &cache,
+        20250328,
+        1_743_120_000_000,
+        &[(&[(5, 10), (6, 20), (8, 30)], true)],
+    );
+    // 2025-03-30: continues at 9 after an empty, uncached 29th.
+    cache_day(&cache, 20250330, 1_743_292_800_000, &[(&[(9, 10)], false)]);
+    // A block-trade partition is not a separate day.
+    std::fs::create_dir_all(dir.path().join(SYMBOL).join("block_trades/2025/03/27")).unwrap();
+
+    assert_eq!(cache.symbols().unwrap(), [SYMBOL]);
+    let days = reconcile_symbol(&cache, SYMBOL).unwrap();
+    let summary: Vec<_> = days
+        .iter()
+        .map(|day| {
+            (
+                day.day.as_str(),
+                day.stored,
+                day.expected,
+                day.missing,
+                day.missing_before,
+                day.truncated,
+                day.flagged(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("2025-03-27", 3, 3, 0, 0, false, false),
+            ("2025-03-28", 3, 4, 1, 1, true, true),
+            ("2025-03-30", 1, 1, 0, 0, false, false),
+        ]
+    );
+}
+
+/// Every trade the exchange numbered, as `(trade_seq, timestamp ms)`.
+struct Exchange {
+    trades: Vec<(u64, u64)>,
+    requested: parking_lot::Mutex<Vec<SeqRange>>,
+}
+
+#[async_trait]
+impl SeqSource for Exchange {
+    async fn fetch_seq_range(
+        &self,
+        _symbol: &str,
+        range: SeqRange,
+    ) -> anyhow::Result<Vec<RawChunk>> {
+        self.requested.lock().push(range);
+        let trades: Vec<_> = self
+            .trades
+            .iter()
+            .copied()
+            .filter(|(seq, _)| (range.first..=range.last).contains(seq))
+            .collect();
+        Ok(vec![RawChunk {
+            data: page(&trades, false),
+            start_ns: 0,
+            end_ns: 0,
+            resume: None,
+        }])
+    }
+
+    fn name(&self) -> &'static str {
+        "test"
+    }
+}
+
+#[tokio::test]
+async fn repair_refetches_only_the_missing_numbers() {
+    const MAR28: u64 = 1_743_120_000_000;
+    const MAR29: u64 = 1_743_206_400_000;
+    let dir = tempfile::tempdir().unwrap();
+    let cache = CacheManager::new(dir.path().to_path_buf());
+    cache_day(
+        &cache,
+        20250328,
+        MAR28,
+        &[(&[(5, 10), (6, 20), (8, 30)], false)],
+    );
+    cache_day(&cache, 20250330, 1_743_292_800_000, &[(&[(10, 10)], false)]);
+    cache_day(
+        &cache,
+        20250327,
+        1_743_033_600_000,
+        &[(&[(1, 10), (2, 20), (3, 30)], false)],
+    );
+    // Seq 9 printed on the never-cached 29th.
+    let exchange = Exchange {
+        trades: vec![(4, MAR28 + 5), (7, MAR28 + 25), (9, MAR29 + 500)],
+        requested: Default::default(),
+    };
+
+    let summary = repair(&cache, &exchange, &[SYMBOL.to_string()])
+        .await
+        .unwrap();
+    assert_eq!(
+        *exchange.requested.lock(),
+        [
+            SeqRange { first: 4, last: 4 },
+            SeqRange { first: 7, last: 7 },
+            SeqRange { first: 9, last: 9 },
+        ]
+    );
+    assert_eq!(
+        summary,
+        RepairSummary {
+            ranges: 3,
+            requested: 3,
+            pages: 3,
+            parts_written: 3,
+            still_missing: 0,
+        }
+    );
+
+    let days = reconcile_symbol(&cache, SYMBOL).unwrap();
+    assert_eq!(
+        days.iter()
+            .map(|day| (day.day.as_str(), day.stored, day.pages, day.flagged()))
+            .collect::<Vec<_>>(),
+        [
+            ("2025-03-27", 3, 1, false),
+            ("2025-03-28", 5, 3, false),
+            ("2025-03-29", 1, 1, false),
+            ("2025-03-30", 1, 1, false),
+        ]
+    );
+    let manifest = cache
+        .load_manifest(&RetrieveSpec {
+            symbol: SYMBOL.to_string(),
+            day_ymd: 20250329,
+            kind: RetrieveKind::Trades,
+        })
+        .unwrap()
+        .unwrap();
+    assert_eq!(manifest.source, "test");
+}
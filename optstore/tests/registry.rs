@@ -0,0 +1,183 @@
+mod common;
+
+use optstore::book::{book_path, BookReader, BookWriter};
+use optstore::cli::{Commands, IngestCommand, MigrateIdsCommand};
+use optstore::registry::{self, InstrumentRegistry};
+use optstore::schema::{event, Tick};
+use optstore::{InstrumentDictionary, TickReader, TickWriter};
+
+use common::tick;
+
+/// Two names sharing one hashed id.
+const COLLIDING: [&str; 2] = ["BTC-27JUN25-4609-P", "BTC-27JUN25-37063-P"];
+const CALL: &str = "BTC-27JUN25-60000-C";
+
+const DAY_NS: u64 = 1_743_120_000_000_000_000; // 2025-03-28T00:00:00Z
+
+#[test]
+fn registry_assigns_ids_in_order_and_spots_hash_collisions() {
+    let dir = tempfile::tempdir().unwrap();
+    assert_eq!(
+        InstrumentDictionary::hash_id(COLLIDING[0]),
+        InstrumentDictionary::hash_id(COLLIDING[1])
+    );
+
+    let mut registry = InstrumentRegistry::load(dir.path()).unwrap();
+    assert_eq!(registry.id(COLLIDING[0]), 1);
+    assert_eq!(registry.id(CALL), 2);
+    assert_eq!(registry.id(COLLIDING[0]), 1);
+    assert!(registry.hash_collisions(CALL).is_empty());
+    assert_eq!(registry.id(COLLIDING[1]), 3);
+    assert_eq!(registry.hash_collisions(COLLIDING[1]), [COLLIDING[0]]);
+    registry.store(dir.path()).unwrap();
+    assert_eq!(InstrumentRegistry::load(dir.path()).unwrap(), registry);
+}
+
+#[test]
+fn dictionary_refuses_a_second_name_on_one_id() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("28.opt");
+    let mut first = InstrumentDictionary::default();
+    first.insert(COLLIDING[0]);
+    first.store_for(&file).unwrap();
+
+    let mut second = InstrumentDictionary::default();
+    second.insert(COLLIDING[1]);
+    let err = second.store_for(&file).expect_err("hashed ids collide");
+    assert_eq!(optstore::exit::code(&err), optstore::exit::VALIDATION);
+    assert_eq!(InstrumentDictionary::load_for(&file).unwrap(), first);
+
+    // The same name under another id is refused as well.
+    let mut moved = InstrumentDictionary::default();
+    moved.insert_as(1, COLLIDING[0]).unwrap();
+    assert!(moved.store_for(&file).is_err());
+}
+
+#[test]
+fn ingest_gives_named_rows_registry_ids() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("ticks.jsonl");
+    let out = dir.path().join("2025/03/28.opt");
+    let rows: Vec<String> = [COLLIDING[0], COLLIDING[1], COLLIDING[0]]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            format!(
+                r#"{{"ts_ns":{},"instrument":"{name}","event":1,"price_fp":50000,"size":1000}}"#,
+                DAY_NS + i as u64
+            )
+        })
+        .collect();
+    std::fs::write(&input, rows.join("\n")).unwrap();
+    let ingest = |registry: Option<std::path::PathBuf>| {
+        Commands::Ingest(IngestCommand {
+            input: input.display().to_string(),
+            out: out.display().to_string(),
+            day: "2025-03-28".to_string(),
+            strict: false,
+            quality_report: false,
+            max_size: 100_000.0,
+            block_rows: 1024,
+            block_bytes: 1 << 20,
+            registry,
+        })
+        .execute(true, false)
+    };
+
+    let err = ingest(None).expect_err("hashed ids collide");
+    assert_eq!(optstore::exit::code(&err), optstore::exit::VALIDATION);
+
+    ingest(Some(dir.path().to_path_buf())).unwrap();
+    let ids: Vec<u32> = TickReader::open(&out)
+        .unwrap()
+        .map(|tick| tick.unwrap().instrument_id)
+        .collect();
+    assert_eq!(ids, [1, 2, 1]);
+    let dictionary = InstrumentDictionary::load_for(&out).unwrap();
+    assert_eq!(
+        (dictionary.name(1), dictionary.name(2)),
+        (Some(COLLIDING[0]), Some(COLLIDING[1]))
+    );
+    assert_eq!(
+        InstrumentRegistry::load(dir.path())
+            .unwrap()
+            .get(COLLIDING[1]),
+        Some(2)
+    );
+}
+
+#[test]
+fn migrate_moves_hashed_days_onto_registry_ids() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = dir.path();
+    let hashed = InstrumentDictionary::hash_id(CALL);
+    let put = "BTC-27JUN25-60000-P";
+
+    // A day from before the registry: rows, a book stream and a dictionary.
+    let day = optstore::util::day_to_path(data, "2025-03-28");
+    let mut writer = TickWriter::append(&day).unwrap();
+    writer
+        .write(&tick(DAY_NS, hashed, event::TRADE, 50_000, 1_000))
+        .unwrap();
+    writer
+        .write(&tick(
+            DAY_NS + 1,
+            InstrumentDictionary::hash_id(put),
+            event::TRADE,
+            50_000,
+            1_000,
+        ))
+        .unwrap();
+    writer.finish().unwrap();
+    let mut book = BookWriter::append(&book_path(&day), 2).unwrap();
+    book.write(&tick(DAY_NS + 2, hashed, event::BOOK, 50_000, 0))
+        .unwrap();
+    book.finish().unwrap();
+    let mut dictionary = InstrumentDictionary::default();
+    dictionary.insert(put);
+    dictionary.insert_with_contract_size(CALL, 10.0);
+    dictionary.store_for(&day).unwrap();
+    // A day without a dictionary cannot be mapped.
+    let orphan = optstore::util::day_to_path(data, "2025-03-27");
+    let mut writer = TickWriter::append(&orphan).unwrap();
+    writer
+        .write(&tick(DAY_NS - 1, hashed, event::TRADE, 50_000, 1_000))
+        .unwrap();
+    writer.finish().unwrap();
+    let mut registry = InstrumentRegistry::default();
+    registry.id(CALL);
+    registry.store(data).unwrap();
+
+    let dry = registry::migrate(data, true).unwrap();
+    assert_eq!((dry.days_checked, dry.days_rewritten), (2, 1));
+    assert_eq!(dry.instruments_registered, 1);
+    assert_eq!(dry.unmapped, [orphan.display().to_string()]);
+    assert_eq!(InstrumentRegistry::load(data).unwrap(), registry);
+    assert_eq!(InstrumentDictionary::load_for(&day).unwrap(), dictionary);
+
+    Commands::MigrateIds(MigrateIdsCommand {
+        data: data.to_path_buf(),
+        dry_run: false,
+    })
+    .execute(true, false)
+    .unwrap();
+    let registry = InstrumentRegistry::load(data).unwrap();
+    assert_eq!((registry.get(CALL), registry.get(put)), (Some(1), Some(2)));
+    let ids: Vec<u32> = TickReader::open(&day)
+        .unwrap()
+        .map(|tick| tick.unwrap().instrument_id)
+        .collect();
+    assert_eq!(ids, [1, 2]);
+    let books: Vec<Tick> = BookReader::open(&book_path(&day))
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(books, [tick(DAY_NS + 2, 1, event::BOOK, 50_000, 0)]);
+    let moved = InstrumentDictionary::load_for(&day).unwrap();
+    assert_eq!((moved.name(1), moved.name(2)), (Some(CALL), Some(put)));
+    assert_eq!(moved.contract_size(1), 10.0);
+
+    let again = registry::migrate(data, false).unwrap();
+    assert_eq!(again.days_rewritten, 0);
+    assert_eq!(again.instruments_registered, 0);
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use optstore::cli::{Commands, IngestCommand, VerifyCommand};
+use optstore::exit::{self, CORRUPT, NO_DATA};
+use optstore::repro::{self, ReproRecord};
+use optstore::writer::NORMALIZER_VERSION;
+
+const DAY_NS: u64 = 1_743_120_000_000_000_000; // 2025-03-28T00:00:00Z
+
+fn ingest(input: &Path, out: &Path) -> anyhow::Result<()> {
+    Commands::Ingest(IngestCommand {
+        input: input.display().to_string(),
+        out: out.display().to_string(),
+        day: "2025-03-28".to_string(),
+        strict: false,
+        quality_report: true,
+        max_size: 100_000.0,
+        block_rows: 4,
+        block_bytes: 1 << 20,
+        registry: None,
+    })
+    .execute(true, false)
+}
+
+fn verify(file: &Path, sources: bool) -> anyhow::Result<()> {
+    Commands::Verify(VerifyCommand {
+        file: file.to_path_buf(),
+        sources,
+    })
+    .execute(true, false)
+}
+
+#[test]
+fn ingest_records_sources_parameters_and_output_hashes() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("ticks.jsonl");
+    let rows: Vec<String> = (0..10)
+        .map(|i| {
+            format!(
+                r#"{{"ts_ns":{},"instrument":"BTC-28MAR25-60000-C","event":1,"price_fp":{},"size":1000,"trade_seq":{}}}"#,
+                DAY_NS + i * 1_000,
+                50_000 + i,
+                100 + i
+            )
+        })
+        .collect();
+    std::fs::write(&input, rows.join("\n")).unwrap();
+
+    let out = dir.path().join("a/2025/03/28.opt");
+    ingest(&input, &out).unwrap();
+    let record = ReproRecord::load_for(&out).unwrap();
+    assert_eq!(record.command, "ingest");
+    assert_eq!(record.normalizer_version, NORMALIZER_VERSION);
+    assert_eq!(record.parameters.day, "2025-03-28");
+    assert_eq!(record.parameters.block_rows, 4);
+    assert_eq!(record.parameters.key_id, None);
+    assert_eq!(record.rows, 10);
+    assert_eq!(record.sources.len(), 1);
+    assert_eq!(record.sources[0].path, input.display().to_string());
+    assert_eq!(
+        record.sources[0].bytes,
+        std::fs::metadata(&input).unwrap().len()
+    );
+    let names: Vec<&str> = record.outputs.iter().map(|o| o.path.as_str()).collect();
+    assert_eq!(
+        names,
+        ["28.opt", "28.opt.instruments.json", "28.opt.quality.json"]
+    );
+    assert_eq!(
+        record.outputs[0].bytes,
+        std::fs::metadata(&out).unwrap().len()
+    );
+    verify(&out, true).unwrap();
+
+    // The same input and arguments give the same bytes again.
+    let again = dir.path().join("b/2025/03/28.opt");
+    ingest(&input, &again).unwrap();
+    let second = ReproRecord::load_for(&again).unwrap();
+    assert_eq!(second.outputs, record.outputs);
+    assert_eq!(second.rows_xxh3_128, record.rows_xxh3_128);
+    assert_eq!(
+        repro::rows_hash(&again).unwrap(),
+        (10, record.rows_xxh3_128.clone())
+    );
+
+    // Changed sidecars and sources are reported.
+    std::fs::write(
+        optstore::InstrumentDictionary::sidecar_path(&out),
+        r#"{"instruments":{}}"#,
+    )
+    .unwrap();
+    let verification = record.verify(&out, false).unwrap();
+    assert_eq!(verification.mismatches.len(), 1);
+    assert_eq!(verification.mismatches[0].path, "28.opt.instruments.json");
+    assert_eq!(exit::code(&verify(&out, false).unwrap_err()), CORRUPT);
+
+    std::fs::remove_file(&input).unwrap();
+    verify(&again, false).unwrap();
+    let verification = second.verify(&again, true).unwrap();
+    assert_eq!(verification.sources_checked, 1);
+    assert_eq!(verification.mismatches[0].found, None);
+
+    let bare = dir.path().join("bare.opt");
+    std::fs::write(&bare, b"").unwrap();
+    assert_eq!(exit::code(&verify(&bare, false).unwrap_err()), NO_DATA);
+}
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use optstore::prefetch::PrefetchOptions;
 use optstore::retrieve::cache::{CacheManager, CacheManifest, CacheManifestPart};
 use tempfile::TempDir;
 
@@ -26,6 +27,7 @@ fn manifest_roundtrip_eth() {
         bytes: 128,
         rows: 64,
         resume_token: Some("42".to_string()),
+        dictionary: None,
     });
     manager.store_manifest(&spec, &manifest).unwrap();
     let loaded = manager.load_manifest(&spec).unwrap().unwrap();
@@ -50,9 +52,165 @@ fn manifest_roundtrip_btc() {
         bytes: 256,
         rows: 90,
         resume_token: Some("88".to_string()),
+        dictionary: None,
     });
     manager.store_manifest(&spec, &manifest).unwrap();
     let loaded = manager.load_manifest(&spec).unwrap().unwrap();
     assert_eq!(loaded.parts[0].bytes, 256);
     assert_eq!(loaded.parts[0].rows, 90);
 }
+
+fn trades_page(day: u32, page: u32) -> bytes::Bytes {
+    let trades: Vec<_> = (0..5u32)
+        .map(|i| {
+            let seq = day * 10_000 + page * 5 + i;
+            serde_json::json!({
+                "trade_seq": seq,
+                "trade_id": format!("ETH-{seq}"),
+                "timestamp": 1_743_120_000_000u64 + seq as u64 * 997,
+                "tick_direction": seq % 4,
+                "price": 0.0125 + (seq % 17) as f64 * 0.0005,
+                "mark_price": 0.01263,
+                "iv": 61.5 + (seq % 9) as f64 * 0.25,
+                "index_price": 1872.33 + (seq % 13) as f64,
+                "instrument_name": "ETH-28MAR25-2000-C",
+                "direction": if seq.is_multiple_of(3) { "sell" } else { "buy" },
+                "amount": (1 + seq % 25) as f64,
+                "contracts": (1 + seq % 25) as f64,
+            })
+        })
+        .collect();
+    let page = serde_json::json!({
+        "jsonrpc": "2.0",
+        "result": { "trades": trades, "has_more": true },
+    });
+    serde_json::to_vec(&page).unwrap().into()
+}
+
+#[test]
+fn trained_dictionary_shrinks_new_parts_and_reads_back() {
+    let (_guard, root) = temp_cache();
+    let manager = CacheManager::new(root);
+    let spec = |day_ymd| optstore::retrieve::RetrieveSpec {
+        symbol: "ETH-28MAR25-2000-C".to_string(),
+        day_ymd,
+        kind: optstore::retrieve::RetrieveKind::Trades,
+    };
+    let write = |day_ymd: u32, part: u32| {
+        let chunk = optstore::retrieve::RawChunk {
+            data: trades_page(day_ymd % 100, part),
+            start_ns: 0,
+            end_ns: 0,
+            resume: None,
+        };
+        let written = manager.write_chunk(&spec(day_ymd), part, &chunk).unwrap();
+        let mut manifest = manager
+            .load_manifest(&spec(day_ymd))
+            .unwrap()
+            .unwrap_or_else(|| CacheManifest::new("deribit", &spec(day_ymd)));
+        manifest.append_part(CacheManifestPart {
+            part,
+            start_ns: 0,
+            end_ns: 0,
+            bytes: written.bytes_written,
+            rows: written.rows,
+            resume_token: None,
+            dictionary: written.dictionary,
+        });
+        manager.store_manifest(&spec(day_ymd), &manifest).unwrap();
+        written
+    };
+
+    write(20250326, 0);
+    assert_eq!(
+        manager.train_dictionary("ETH-28MAR25-2000-C").unwrap(),
+        None,
+        "one part is too few to train on"
+    );
+    for part in 1..16 {
+        write(20250326, part);
+    }
+    let plain = write(20250327, 0);
+    assert_eq!(plain.dictionary, None);
+
+    let id = manager
+        .train_dictionary("ETH-28MAR25-2000-C")
+        .unwrap()
+        .expect("trained");
+    assert_eq!(
+        manager
+            .active_dictionary("ETH-28MAR25-2000-C")
+            .unwrap()
+            .map(|(active, _)| active),
+        Some(id)
+    );
+    // The cache's day listing is not confused by the dictionary directory.
+    assert_eq!(
+        manager.cached_days("ETH-28MAR25-2000-C").unwrap(),
+        [20250326, 20250327]
+    );
+
+    let trained = write(20250327, 1);
+    assert_eq!(trained.dictionary, Some(id));
+    assert!(
+        trained.bytes_written * 2 < plain.bytes_written,
+        "{} bytes with the dictionary vs {} without",
+        trained.bytes_written,
+        plain.bytes_written
+    );
+    assert_eq!(
+        manager.read_part(&spec(20250327), 1).unwrap(),
+        trades_page(27, 1)
+    );
+    assert_eq!(
+        manager.read_part(&spec(20250327), 0).unwrap(),
+        trades_page(27, 0)
+    );
+}
+
+#[test]
+fn prefetched_parts_match_sequential_reads_in_order() {
+    let (_guard, root) = temp_cache();
+    let manager = CacheManager::new(root);
+    let spec = optstore::retrieve::RetrieveSpec {
+        symbol: "ETH-28MAR25-2000-C".to_string(),
+        day_ymd: 20250326,
+        kind: optstore::retrieve::RetrieveKind::Trades,
+    };
+    for part in 0..24 {
+        let chunk = optstore::retrieve::RawChunk {
+            data: trades_page(26, part),
+            start_ns: 0,
+            end_ns: 0,
+            resume: None,
+        };
+        manager.write_chunk(&spec, part, &chunk).unwrap();
+    }
+    let sequential: Vec<Vec<u8>> = (0..24)
+        .map(|part| manager.read_part(&spec, part).unwrap())
+        .collect();
+
+    for options in [
+        PrefetchOptions::SEQUENTIAL,
+        PrefetchOptions {
+            workers: 3,
+            depth: 5,
+        },
+    ] {
+        let prefetched: Vec<Vec<u8>> = manager
+            .read_parts(&spec, 0..24, options)
+            .unwrap()
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        assert_eq!(prefetched, sequential);
+    }
+
+    // A missing part surfaces as an error at its position, and stopping
+    // early leaves the pool to wind down on its own.
+    let mut parts = manager
+        .read_parts(&spec, [0, 1, 99, 2], PrefetchOptions::default())
+        .unwrap();
+    assert!(parts.next().unwrap().is_ok());
+    assert!(parts.next().unwrap().is_ok());
+    assert!(parts.next().unwrap().is_err());
+}
@@ -1,4 +1,101 @@
+#![allow(clippy::assertions_on_constants)]
+
 #[test]
 fn retrieve_roundtrip_placeholder() {
     assert!(true);
 }
+
+use bytes::Bytes;
+use optstore::retrieve::{
+    CacheManager, DeribitNormalizer, Normalizer, RawChunk, RetrieveKind, RetrieveSpec,
+};
+use optstore::schema::event;
+
+fn trades_page() -> RawChunk {
+    let body = serde_json::json!({
+        "jsonrpc": "2.0",
+        "result": {
+            "trades": [
+                { "trade_id": "1", "trade_seq": 11, "timestamp": 1_000, "price": 0.05, "amount": 10.0, "direction": "buy" },
+                { "trade_id": "2", "timestamp": 2_000, "price": 0.051, "amount": 250.0, "direction": "sell",
+                  "block_trade_id": "BLOCK-77", "block_trade_leg_count": 2 },
+                { "trade_id": "3", "timestamp": 3_000, "price": 0.049, "amount": 3.0, "direction": "sell",
+                  "liquidation": "T" },
+                { "trade_id": "4", "timestamp": 4_000, "price": 0.052, "amount": 1.0, "direction": "buy" }
+            ],
+            "has_more": false
+        }
+    });
+    RawChunk {
+        data: Bytes::from(serde_json::to_vec(&body).unwrap()),
+        start_ns: 1_000_000_000,
+        end_ns: 4_000_000_000,
+        resume: None,
+    }
+}
+
+fn normalize(kind: RetrieveKind) -> Vec<(u64, u8)> {
+    DeribitNormalizer {
+        instrument_id: 7,
+        kind,
+    }
+    .to_ticks(trades_page())
+    .unwrap()
+    .into_iter()
+    .map(|tick| (tick.ts_ns / 1_000_000, tick.event))
+    .collect()
+}
+
+#[test]
+fn normalizer_tags_block_trades_and_liquidations() {
+    assert_eq!(
+        normalize(RetrieveKind::Trades),
+        [
+            (1_000, event::TRADE),
+            (2_000, event::BLOCK_TRADE),
+            (3_000, event::LIQUIDATION),
+            (4_000, event::TRADE),
+        ]
+    );
+    assert_eq!(
+        normalize(RetrieveKind::BlockTrades),
+        [(2_000, event::BLOCK_TRADE)]
+    );
+    assert_eq!(
+        normalize(RetrieveKind::Liquidations),
+        [(3_000, event::LIQUIDATION)]
+    );
+
+    let seqs: Vec<_> = DeribitNormalizer {
+        instrument_id: 7,
+        kind: RetrieveKind::Trades,
+    }
+    .to_ticks(trades_page())
+    .unwrap()
+    .iter()
+    .map(|tick| tick.trade_seq())
+    .collect();
+    assert_eq!(seqs, [Some(11), None, None, None]);
+}
+
+#[test]
+fn flagged_prints_get_their_own_cache_partition() {
+    let manager = CacheManager::new("raw".into());
+    let spec = |kind| RetrieveSpec {
+        symbol: "BTC-28MAR25-60000-C".to_string(),
+        day_ymd: 20250328,
+        kind,
+    };
+    assert_eq!(
+        manager.manifest_path(&spec(RetrieveKind::Trades)),
+        std::path::Path::new("raw/BTC-28MAR25-60000-C/2025/03/28/manifest.json")
+    );
+    assert_eq!(
+        manager.manifest_path(&spec(RetrieveKind::BlockTrades)),
+        std::path::Path::new("raw/BTC-28MAR25-60000-C/block_trades/2025/03/28/manifest.json")
+    );
+    assert_eq!(
+        manager.manifest_path(&spec(RetrieveKind::Liquidations)),
+        std::path::Path::new("raw/BTC-28MAR25-60000-C/liquidations/2025/03/28/manifest.json")
+    );
+}
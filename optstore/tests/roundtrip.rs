@@ -2,3 +2,199 @@
 fn roundtrip_placeholder() {
     assert_eq!(2 + 2, 4);
 }
+
+use futures_util::StreamExt;
+use optstore::block::BlockLayout;
+use optstore::file::{HEADER_LEN_V1, MAGIC};
+use optstore::index::ZoneMap;
+use optstore::schema::event;
+use optstore::{AsyncTickReader, Tick, TickReader, TickWriter};
+
+fn book_tick(ts_ns: u64, instrument_id: u32, bid: i64) -> Tick {
+    Tick {
+        ts_ns,
+        instrument_id,
+        event: event::BOOK,
+        price_fp: 40_000_000_000,
+        size: 0,
+        bid_px_fp: [bid, bid - 5, 0, 0],
+        ask_px_fp: [bid + 10, bid + 15, 0, 0],
+        bid_sz: [1_000, 2_500, 0, 0],
+        ask_sz: [1_500, 500, 0, 0],
+        flags: 7,
+    }
+}
+
+#[test]
+fn row_file_roundtrips_and_appends() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/01/02.opt");
+    let first: Vec<Tick> = (0..3)
+        .map(|i| book_tick(i * 1_000, 11, 100 + i as i64))
+        .collect();
+
+    let mut writer = TickWriter::append(&path).unwrap();
+    for tick in &first {
+        writer.write(tick).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), 3);
+
+    let mut writer = TickWriter::append(&path).unwrap();
+    writer.write(&book_tick(5_000, 12, 900)).unwrap();
+    writer.finish().unwrap();
+
+    let all: Vec<Tick> = TickReader::open(&path)
+        .unwrap()
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(all.len(), 4);
+    assert_eq!(&all[..3], first.as_slice());
+    assert_eq!(all[3].instrument_id, 12);
+
+    let ranged: Vec<Tick> = TickReader::open(&path)
+        .unwrap()
+        .range(1_000, 5_000)
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(ranged.len(), 2);
+}
+
+#[test]
+fn reader_rejects_foreign_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bogus.opt");
+    std::fs::write(&path, b"definitely not optstore").unwrap();
+    assert!(TickReader::open(&path).is_err());
+}
+
+#[test]
+fn block_layout_is_kept_in_the_header_and_sizes_zones() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/01/03.opt");
+    // 1 000 bytes hold 8 rows, so the byte target binds before 100 rows.
+    let layout = BlockLayout::new(100, 1_000).unwrap();
+    assert_eq!(layout.block_rows(), 8);
+    assert!(BlockLayout::new(0, 1_000).is_err());
+    assert!(BlockLayout::new(10, 100).is_err());
+
+    let mut writer = TickWriter::append_with_layout(&path, layout).unwrap();
+    for i in 0..20 {
+        writer.write(&book_tick(i, 11, 100)).unwrap();
+    }
+    writer.finish().unwrap();
+    assert_eq!(TickReader::open(&path).unwrap().layout(), layout);
+    // Plain appends keep the file's layout; a different one is refused.
+    TickWriter::append(&path).unwrap().finish().unwrap();
+    let err = TickWriter::append_with_layout(&path, BlockLayout::default())
+        .err()
+        .expect("layout mismatch");
+    assert!(err.to_string().contains("100 rows / 1000 bytes"), "{err}");
+
+    let zones = ZoneMap::refresh(&path).unwrap();
+    assert_eq!(zones.zone_rows, 8);
+    assert_eq!(
+        zones.zones.iter().map(|zone| zone.rows).collect::<Vec<_>>(),
+        [8, 8, 4]
+    );
+}
+
+#[test]
+fn version_1_files_read_with_the_default_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("v1.opt");
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.resize(HEADER_LEN_V1, 0);
+    let tick = book_tick(42, 11, 100);
+    tick.encode(&mut bytes);
+    std::fs::write(&path, &bytes).unwrap();
+
+    let reader = TickReader::open(&path).unwrap();
+    assert_eq!(reader.layout(), BlockLayout::default());
+    assert_eq!(reader.collect::<anyhow::Result<Vec<_>>>().unwrap(), [tick]);
+    assert_eq!(optstore::index::file_rows(&path).unwrap(), 1);
+    let mut writer = TickWriter::append(&path).unwrap();
+    writer.write(&book_tick(43, 11, 100)).unwrap();
+    writer.finish().unwrap();
+    assert_eq!(optstore::index::file_rows(&path).unwrap(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn async_reader_serves_concurrent_range_scans() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/01/03.opt");
+    let mut writer = TickWriter::append(&path).unwrap();
+    for i in 0..1_000u64 {
+        writer
+            .write(&book_tick(i * 1_000, 10 + (i % 3) as u32, i as i64))
+            .unwrap();
+    }
+    writer.finish().unwrap();
+
+    let scans = (0..16u64).map(|scan| {
+        let path = path.clone();
+        tokio::spawn(async move {
+            let (from, to) = (scan * 50_000, scan * 50_000 + 100_000);
+            let reader = AsyncTickReader::open(&path).await.unwrap().range(from, to);
+            let ticks: Vec<Tick> = reader
+                .into_stream()
+                .map(|tick| tick.unwrap())
+                .collect()
+                .await;
+            let expected: Vec<Tick> = TickReader::open(&path)
+                .unwrap()
+                .range(from, to)
+                .collect::<anyhow::Result<_>>()
+                .unwrap();
+            (ticks, expected)
+        })
+    });
+    for scan in scans.collect::<Vec<_>>() {
+        let (ticks, expected) = scan.await.unwrap();
+        assert!(!ticks.is_empty());
+        assert_eq!(ticks, expected);
+    }
+
+    let mut reader = AsyncTickReader::open(&path)
+        .await
+        .unwrap()
+        .instruments([11].into());
+    reader.seek_rows(10..20).await.unwrap();
+    let mut ids = Vec::new();
+    while let Some(tick) = reader.next_tick().await.unwrap() {
+        ids.push(tick.ts_ns / 1_000);
+    }
+    assert_eq!(ids, [10, 13, 16, 19]);
+    assert_eq!(reader.rows_read(), 10);
+}
+
+#[tokio::test]
+async fn async_reader_reports_truncated_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/01/04.opt");
+    let mut writer = TickWriter::append(&path).unwrap();
+    writer.write(&book_tick(1_000, 11, 100)).unwrap();
+    writer.write(&book_tick(2_000, 11, 101)).unwrap();
+    writer.finish().unwrap();
+    let len = std::fs::metadata(&path).unwrap().len();
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_len(len - 10)
+        .unwrap();
+
+    let results: Vec<anyhow::Result<Tick>> = AsyncTickReader::open(&path)
+        .await
+        .unwrap()
+        .into_stream()
+        .collect()
+        .await;
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(results[1]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("truncated"));
+}
@@ -1,4 +1,118 @@
+#![allow(clippy::assertions_on_constants)]
+
 #[test]
 fn selections_placeholder() {
     assert!(true);
 }
+
+use chrono::NaiveDate;
+use optstore::schema::event;
+use optstore::selection::{OptionKind, OptionTerms};
+use optstore::{InstrumentDictionary, Selection, Tick, TickReader, TickWriter};
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn option_terms_parse_deribit_names() {
+    let terms = OptionTerms::parse("BTC-28MAR25-60000-C").unwrap();
+    assert_eq!(terms.currency, "BTC");
+    assert_eq!(terms.expiry, date(2025, 3, 28));
+    assert_eq!(terms.strike, 60000.0);
+    assert_eq!(terms.kind, OptionKind::Call);
+
+    // Linear books, dailies without a leading zero and `d` decimal strikes.
+    let terms = OptionTerms::parse("XRP_USDC-7JUN25-0d625-P").unwrap();
+    assert_eq!(terms.currency, "XRP");
+    assert_eq!(terms.expiry, date(2025, 6, 7));
+    assert_eq!(terms.strike, 0.625);
+    assert_eq!(terms.kind, OptionKind::Put);
+
+    for name in [
+        "BTC-PERPETUAL",
+        "BTC-28MAR25",
+        "BTC-28MAR25-60000-X",
+        "BTC-FS-28MAR25_PERP",
+    ] {
+        assert!(OptionTerms::parse(name).is_none(), "{name}");
+    }
+}
+
+#[test]
+fn selection_resolves_option_terms_to_instrument_ids() {
+    let mut dictionary = InstrumentDictionary::default();
+    let call = dictionary.insert("BTC-28MAR25-40000-C");
+    let put = dictionary.insert("BTC-28MAR25-40000-P");
+    let linear = dictionary.insert("BTC_USDC-28MAR25-40000-C");
+    dictionary.insert("BTC-27JUN25-40000-C");
+    dictionary.insert("BTC-28MAR25-45000-C");
+    dictionary.insert("ETH-28MAR25-4000-C");
+    dictionary.insert("BTC-PERPETUAL");
+
+    let selection = Selection {
+        currency: Some("btc".to_string()),
+        expiry: Some(date(2025, 3, 28)),
+        strike: Some(40000.0),
+        ..Selection::default()
+    };
+    assert_eq!(
+        selection.resolve(&dictionary),
+        [call, put, linear].into_iter().collect()
+    );
+
+    let calls = Selection {
+        kind: Some(OptionKind::Call),
+        ..selection.clone()
+    };
+    assert_eq!(
+        calls.resolve(&dictionary),
+        [call, linear].into_iter().collect()
+    );
+    assert_eq!(
+        calls.to_string(),
+        "currency=BTC expiry=2025-03-28 strike=40000 kind=call"
+    );
+
+    let exact = Selection {
+        instrument: Some("BTC-28MAR25-40000-P".to_string()),
+        ..Selection::default()
+    };
+    assert_eq!(exact.resolve(&dictionary), [put].into_iter().collect());
+    assert!(Selection::default().is_empty());
+    assert_eq!(Selection::default().resolve(&dictionary).len(), 7);
+}
+
+#[test]
+fn reader_skips_rows_outside_the_instrument_set() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let mut writer = TickWriter::append(&path).unwrap();
+    for (ts_ns, instrument_id) in [(1, 11), (2, 12), (3, 11), (4, 13)] {
+        writer
+            .write(&Tick {
+                ts_ns,
+                instrument_id,
+                event: event::TRADE,
+                price_fp: 1_000_000,
+                size: 1_000,
+                bid_px_fp: [0; 4],
+                ask_px_fp: [0; 4],
+                bid_sz: [0; 4],
+                ask_sz: [0; 4],
+                flags: 0,
+            })
+            .unwrap();
+    }
+    writer.finish().unwrap();
+
+    let mut reader = TickReader::open(&path)
+        .unwrap()
+        .instruments([11, 13].into_iter().collect());
+    let ticks: Vec<Tick> = reader.by_ref().collect::<anyhow::Result<_>>().unwrap();
+    assert_eq!(
+        ticks.iter().map(|tick| tick.ts_ns).collect::<Vec<_>>(),
+        [1, 3, 4]
+    );
+    assert_eq!(reader.rows_read(), 4);
+}
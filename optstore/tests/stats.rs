@@ -0,0 +1,129 @@
+use optstore::block::BlockLayout;
+use optstore::codec::{entropy_bits, Compression, Encoding};
+use optstore::schema::{event, Tick};
+use optstore::stats::file_stats;
+use optstore::TickWriter;
+
+#[test]
+fn encodings_pick_the_smallest_layout() {
+    let steady: Vec<i64> = (0..100).map(|i| 1_000_000 + i * 250).collect();
+    assert_eq!(Encoding::choose(&steady, 8).0, Encoding::Delta);
+    let constant = vec![7i64; 100];
+    let (encoding, bytes) = Encoding::choose(&constant, 1);
+    assert_eq!((encoding, bytes.len()), (Encoding::RunLength, 2));
+    let two_ids: Vec<i64> = (0..100)
+        .map(|i| [0x1234_5678, 0x0bad_cafe][i % 2])
+        .collect();
+    assert_eq!(Encoding::choose(&two_ids, 4).0, Encoding::Dictionary);
+    let wide: Vec<i64> = (0..300).collect();
+    assert_eq!(Encoding::Dictionary.encode(&wide, 4), None);
+    assert_eq!(Encoding::Plain.encode(&wide, 2).unwrap().len(), 600);
+
+    assert_eq!(entropy_bits(&[9; 64]), 0.0);
+    let all: Vec<u8> = (0..=255).collect();
+    assert!((entropy_bits(&all) - 8.0).abs() < 1e-9);
+}
+
+#[test]
+fn stats_break_down_every_column_of_every_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let layout = BlockLayout::new(64, 1 << 20).unwrap();
+    let mut writer = TickWriter::append_with_layout(&path, layout).unwrap();
+    for row in 0..150u64 {
+        writer
+            .write(&Tick {
+                ts_ns: 1_743_120_000_000_000_000 + row * 1_000_000,
+                instrument_id: [0x1234_5678, 0x0bad_cafe][row as usize % 2],
+                event: event::BOOK,
+                price_fp: 84_000_000_000 + (row as i64 * 7919) % 1_000_000,
+                size: 0,
+                bid_px_fp: [5_000_000, 4_900_000, 0, 0],
+                ask_px_fp: [5_100_000, 5_200_000, 0, 0],
+                bid_sz: [1_000, 2_000, 0, 0],
+                ask_sz: [1_500, 2_500, 0, 0],
+                flags: 0,
+            })
+            .unwrap();
+    }
+    writer.finish().unwrap();
+
+    let stats = file_stats(&path, Compression::Zstd, false).unwrap();
+    assert_eq!((stats.rows, stats.blocks), (150, 3));
+    assert_eq!(stats.raw_bytes, 150 * Tick::ENCODED_LEN as u64);
+    assert!(stats.encoded_bytes < stats.raw_bytes / 4);
+    assert!(stats.per_block.is_none());
+    let column = |name: &str| {
+        stats
+            .columns
+            .iter()
+            .find(|column| column.column == name)
+            .unwrap()
+    };
+    assert_eq!(column("ts_ns").encodings[&Encoding::Delta], 3);
+    assert_eq!(column("instrument_id").encodings[&Encoding::Dictionary], 3);
+    assert_eq!(column("event").encodings[&Encoding::RunLength], 3);
+    assert_eq!(column("flags").raw_bytes, 300);
+    assert_eq!(stats.columns.len(), 23);
+
+    let verbose = file_stats(&path, Compression::Lz4, true).unwrap();
+    let blocks = verbose.per_block.as_ref().unwrap();
+    assert_eq!(
+        blocks
+            .iter()
+            .map(|block| (block.first_row, block.rows))
+            .collect::<Vec<_>>(),
+        [(0, 64), (64, 64), (128, 22)]
+    );
+    assert!(blocks[2]
+        .columns
+        .iter()
+        .all(|column| column.entropy_bits <= 8.0));
+    let json = serde_json::to_value(&verbose).unwrap();
+    assert_eq!(json["columns"][0]["encodings"]["delta"], 3);
+    assert_eq!(json["per_block"][0]["columns"][2]["encoding"], "run_length");
+}
+
+#[test]
+fn stats_measure_trade_seq_apart_from_the_best_bid() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let mut writer = TickWriter::append(&path).unwrap();
+    for row in 0..10u64 {
+        let trade = row % 2 == 0;
+        let mut tick = Tick {
+            ts_ns: 1_743_120_000_000_000_000 + row * 1_000_000,
+            instrument_id: 7,
+            event: if trade { event::TRADE } else { event::BOOK },
+            price_fp: 5_000_000,
+            size: 1_000,
+            bid_px_fp: [0; 4],
+            ask_px_fp: [0; 4],
+            bid_sz: [0; 4],
+            ask_sz: [0; 4],
+            flags: 0,
+        };
+        if trade {
+            tick.set_trade_seq(1_000 + row);
+        } else {
+            tick.bid_px_fp[0] = 4_900_000;
+        }
+        writer.write(&tick).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let stats = file_stats(&path, Compression::Zstd, true).unwrap();
+    assert_eq!(stats.raw_bytes, 10 * Tick::ENCODED_LEN as u64);
+    let column = |name: &str| {
+        stats
+            .columns
+            .iter()
+            .find(|column| column.column == name)
+            .unwrap()
+    };
+    assert_eq!(column("trade_seq").raw_bytes, 5 * 8);
+    assert_eq!(column("bid_px_fp[0]").raw_bytes, 5 * 8);
+    // Only book rows reach the bid column: one price, run-length encoded.
+    assert_eq!(column("bid_px_fp[0]").encodings[&Encoding::RunLength], 1);
+    assert_eq!(column("trade_seq").encodings[&Encoding::Delta], 1);
+}
@@ -0,0 +1,109 @@
+use optstore::index::{ZoneMap, ZONE_ROWS};
+use optstore::query::top_prints;
+use optstore::schema::{event, Tick};
+use optstore::{InstrumentDictionary, TickWriter};
+
+fn print(ts_ns: u64, instrument_id: u32, event: u8, price_fp: i64, size: u32) -> Tick {
+    Tick {
+        ts_ns,
+        instrument_id,
+        event,
+        price_fp,
+        size,
+        bid_px_fp: [0; 4],
+        ask_px_fp: [0; 4],
+        bid_sz: [0; 4],
+        ask_sz: [0; 4],
+        flags: 0,
+    }
+}
+
+#[test]
+fn top_prints_rank_by_notional_and_prune_zones() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let mut dictionary = InstrumentDictionary::default();
+    let btc = dictionary.insert("BTC-28MAR25-40000-C");
+    let linear = dictionary.insert_with_contract_size("BTC_USDC-28MAR25-40000-C", 0.01);
+
+    // Three full zones and a partial one of 0.01 × 1 prints and book rows,
+    // with a few large prints planted in the first and third zones.
+    let rows = ZONE_ROWS * 3 + 10;
+    let mut writer = TickWriter::append(&path).unwrap();
+    for row in 0..rows {
+        let tick = match row {
+            5 => print(row, btc, event::BLOCK_TRADE, 2_000_000, 50_000),
+            7 => print(row, linear, event::TRADE, 1_500_000_000, 1_000),
+            r if r == ZONE_ROWS * 2 + 3 => print(row, btc, event::LIQUIDATION, 500_000, 40_000),
+            r if r % 2 == 0 => print(row, btc, event::TRADE, 10_000, 1_000),
+            _ => print(row, btc, event::BOOK, 0, 0),
+        };
+        writer.write(&tick).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let top = top_prints(&path, &dictionary, None, 0, u64::MAX, 2).unwrap();
+    assert_eq!(
+        top.prints
+            .iter()
+            .map(|print| (print.ts_ns, print.event, print.notional))
+            .collect::<Vec<_>>(),
+        [
+            (5, "block_trade", 100.0),
+            (ZONE_ROWS * 2 + 3, "liquidation", 20.0)
+        ]
+    );
+    assert_eq!((top.zones_scanned, top.zones_pruned), (2, 2));
+    assert_eq!(top.rows_scanned, ZONE_ROWS * 2);
+
+    // The 0.01 contract size ranks the linear print's 1500 × 1 below both.
+    let third = top_prints(&path, &dictionary, None, 0, u64::MAX, 3).unwrap();
+    assert_eq!(third.prints[2].instrument, "BTC_USDC-28MAR25-40000-C");
+    assert!((third.prints[2].notional - 15.0).abs() < 1e-9);
+
+    // Zones entirely before the range are pruned without being read.
+    let later = top_prints(&path, &dictionary, None, ZONE_ROWS, u64::MAX, 1).unwrap();
+    assert_eq!(later.prints[0].ts_ns, ZONE_ROWS * 2 + 3);
+    assert_eq!(later.zones_scanned, 1);
+    let linear_only = [linear].into_iter().collect();
+    let only = top_prints(&path, &dictionary, Some(&linear_only), 0, u64::MAX, 5).unwrap();
+    assert_eq!(only.prints.len(), 1);
+}
+
+#[test]
+fn zone_map_extends_over_appended_rows() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("2025/03/28.opt");
+    let append = |from: u64, to: u64| {
+        let mut writer = TickWriter::append(&path).unwrap();
+        for row in from..to {
+            writer
+                .write(&print(row, 1, event::TRADE, 1_000_000, row as u32))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+    };
+
+    append(0, ZONE_ROWS + 5);
+    let first = ZoneMap::refresh(&path).unwrap();
+    assert_eq!(first.zones.len(), 2);
+    assert_eq!(ZoneMap::load_for(&path).unwrap(), first);
+
+    append(ZONE_ROWS + 5, ZONE_ROWS * 2 + 1);
+    let extended = ZoneMap::refresh(&path).unwrap();
+    assert_eq!(extended, ZoneMap::build(&path).unwrap());
+    assert_eq!(extended.zones[0], first.zones[0]);
+    assert_eq!(
+        extended
+            .zones
+            .iter()
+            .map(|zone| (zone.first_row, zone.rows))
+            .collect::<Vec<_>>(),
+        [(0, ZONE_ROWS), (ZONE_ROWS, ZONE_ROWS), (ZONE_ROWS * 2, 1)]
+    );
+    assert_eq!(extended.zones[1].max_ts_ns, ZONE_ROWS * 2 - 1);
+    assert_eq!(
+        extended.zones[1].max_trade_value_fp,
+        1_000_000 * (ZONE_ROWS as u128 * 2 - 1)
+    );
+}